@@ -0,0 +1,84 @@
+// 截图热路径基准：全屏截图（合成画面）、BGRA -> RGBA、overlay 遮罩缓存、PNG 编码
+//
+// 运行：cargo bench --features bench
+// feature bench 让全屏截图返回合成画面，不依赖真实屏幕，结果可在 CI / 不同机器间比较。
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use snip_rust::capture::{self, bench};
+use snip_rust::overlay::state::build_dim_cache;
+
+const SIZES: [(u32, u32); 3] = [(1920, 1080), (2560, 1440), (3840, 2160)];
+
+fn label((w, h): (u32, u32)) -> String {
+    format!("{w}x{h}")
+}
+
+fn bytes((w, h): (u32, u32)) -> u64 {
+    w as u64 * h as u64 * 4
+}
+
+fn capture_fullscreen(c: &mut Criterion) {
+    let mut group = c.benchmark_group("capture_fullscreen");
+    group.sample_size(10);
+    for size in SIZES {
+        bench::set_size(size.0, size.1);
+        group.throughput(Throughput::Bytes(bytes(size)));
+        group.bench_function(BenchmarkId::from_parameter(label(size)), |b| {
+            b.iter(|| capture::capture_fullscreen().unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bgra_to_rgba(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bgra_to_rgba");
+    for size in SIZES {
+        let bgra = bench::synthetic_frame(size.0, size.1);
+        group.throughput(Throughput::Bytes(bytes(size)));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(label(size)),
+            &bgra,
+            |b, bgra| b.iter(|| capture::bgra_to_rgba(black_box(bgra), size.0, size.1)),
+        );
+    }
+    group.finish();
+}
+
+fn build_caches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build_caches");
+    for size in SIZES {
+        let rgba = bench::synthetic_frame(size.0, size.1);
+        // 与 overlay 相同复用缓冲，只测像素变换本身
+        let mut dim = Vec::new();
+        group.throughput(Throughput::Bytes(bytes(size)));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(label(size)),
+            &rgba,
+            |b, rgba| b.iter(|| build_dim_cache(black_box(rgba), &mut dim)),
+        );
+    }
+    group.finish();
+}
+
+fn encode_png(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode_png");
+    group.sample_size(10);
+    for size in SIZES {
+        let rgba = bench::synthetic_frame(size.0, size.1);
+        group.throughput(Throughput::Bytes(bytes(size)));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(label(size)),
+            &rgba,
+            |b, rgba| b.iter(|| capture::encode_png(black_box(rgba), size.0, size.1).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    capture_fullscreen,
+    bgra_to_rgba,
+    build_caches,
+    encode_png
+);
+criterion_main!(benches);
@@ -0,0 +1,53 @@
+// 在自己的 winit 程序中嵌入 overlay 选区：启动即截图，导出的图像写入 selection_<n>.png
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use snip_rust::engine::{SnipEngine, SnipEvent};
+use winit::application::ApplicationHandler;
+use winit::event::WindowEvent;
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::window::WindowId;
+
+struct Demo {
+    engine: SnipEngine,
+    done: Rc<RefCell<bool>>,
+}
+
+impl ApplicationHandler for Demo {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if let Err(e) = self.engine.start_capture(event_loop) {
+            eprintln!("start capture: {e}");
+            event_loop.exit();
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, id: WindowId, event: WindowEvent) {
+        self.engine.handle_window_event(id, &event);
+        if *self.done.borrow() {
+            event_loop.exit();
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let done = Rc::new(RefCell::new(false));
+    let flag = done.clone();
+    let mut saved = 0;
+    let engine = SnipEngine::new(move |event| match event {
+        SnipEvent::SelectionConfirmed(rect) => println!("selection {rect:?}"),
+        SnipEvent::ImageReady(png) => {
+            saved += 1;
+            let name = format!("selection_{saved}.png");
+            match std::fs::write(&name, &png) {
+                Ok(()) => println!("saved {name} ({} bytes)", png.len()),
+                Err(e) => eprintln!("write {name}: {e}"),
+            }
+            *flag.borrow_mut() = true;
+        }
+        SnipEvent::Canceled => *flag.borrow_mut() = true,
+        SnipEvent::CaptureStarted => println!("drag to select, Esc to cancel"),
+    });
+    let event_loop = EventLoop::new()?;
+    event_loop.run_app(&mut Demo { engine, done })?;
+    Ok(())
+}
@@ -0,0 +1,2855 @@
+// app: 应用状态（overlay / 贴图 / 托盘 / 配置 / 编码队列）+ winit ApplicationHandler
+//
+// main.rs 只负责启动（参数、单实例、托盘与回调注册），事件分发全部在这里。
+// 菜单路由拆成纯函数 resolve_menu，便于单元测试。
+use image::ImageReader;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tray_icon::{
+    menu::{
+        CheckMenuItem, Icon as MenuIcon, IconMenuItem, Menu, MenuId, MenuItem, PredefinedMenuItem,
+        Submenu,
+    },
+    Icon, TrayIcon, TrayIconBuilder,
+};
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoopProxy},
+    window::{CursorIcon, WindowId},
+};
+
+use snip_rust::autostart;
+use snip_rust::burst::{self, BurstFrame};
+use snip_rust::capture::{
+    capture_area_rgba, capture_fullscreen_raw_into, capture_monitor, capture_monitor_number,
+    capture_virtual_desktop, list_monitors, monitor_at, Monitor, Rect,
+};
+use snip_rust::clipboard::{copy_rgba, copy_text, read_rgba, read_text};
+use snip_rust::config::{CaptureAction, Config};
+use snip_rust::crash::{self, RecoveredPin};
+use snip_rust::decode::{decode_qr, is_url};
+use snip_rust::diff::diff_heatmap;
+use snip_rust::encode::{png_data_uri, EncodeDone, EncodePurpose, EncodeQueue, JobId};
+use snip_rust::flyout::{Flyout, FlyoutAction};
+use snip_rust::fullscreen::FullscreenConfig;
+use snip_rust::hooks::{
+    has_hooks, run_args, run_post_capture, uploads_in_progress, HookContext, HookEvent,
+};
+use snip_rust::hotkey::{BindOutcome, Hotkeys};
+use snip_rust::image_info::{analyze, ImageInfo};
+use snip_rust::ipc::{IpcCommand, Responder};
+use snip_rust::logging;
+use snip_rust::ocr::recognize;
+use snip_rust::open_with::{self, TempFiles};
+use snip_rust::overlay::annotate::AnnotationLayers;
+use snip_rust::overlay::handles::MIN_RESIZE;
+use snip_rust::overlay::toolbar::ToolButton;
+use snip_rust::overlay::{OverlayAction, OverlayMenuIds, OverlayState};
+use snip_rust::paste_window::{parse_position, rect_text, PasteWindow, PinMenuIds, PinTag};
+use snip_rust::paths;
+use snip_rust::project::{CaptureSource, Project};
+use snip_rust::qr::render_qr;
+use snip_rust::redact::find_sensitive;
+use snip_rust::renderer::{stitch, StitchLayout};
+use snip_rust::share::{self, ShareMethod};
+use snip_rust::sound::{self, Sound};
+use snip_rust::stats::{self, StatEvent, Stats};
+use snip_rust::timelapse::{Progress, StopReason, Timelapse};
+use snip_rust::toast::ProgressToast;
+use snip_rust::tray_badge::{compose, downscale, Badges, ICON_SIZE, UPLOAD_FRAME};
+use snip_rust::uploads;
+use snip_rust::webdav;
+use snip_rust::windows_util::{self, TopWindow, WINDOW_ICON_SIZE};
+use snip_rust::workspace;
+
+// 后台线程 / 回调推送给事件循环的消息（ControlFlow::Wait 下空闲时不再轮询）
+#[derive(Debug)]
+pub enum UserEvent {
+    Hotkey,
+    HotkeyWindow,     // Alt+PrintScreen：截图并预选前台窗口
+    HotkeyFullscreen, // 双击截图热键：截取鼠标所在的整个屏幕
+    HotkeyHold(bool), // hotkey_mode = "hold"：截图热键按下（true）/ 松开
+    Menu(MenuId),
+    TrayHover,                  // 鼠标移到托盘图标上：刷新“截取窗口”/“截取整个屏幕”列表
+    WindowList(Vec<TopWindow>), // 后台线程列出的可截取窗口（重建“截取窗口”子菜单）
+    TrayClick((i32, i32, u32, u32)), // 左键单击托盘图标（图标矩形）：切换快捷面板
+    Ipc(IpcCommand, Option<Responder>),
+    Encoded,                                // 后台编码完成，结果在 encoder 队列中
+    EncodeProgress(JobId, f32),             // png_optimize 编码进度（0..=1）
+    TimelapseStopped(StopReason, Progress), // 定时截图线程已结束
+    WindowChanged(isize),                   // 窗口触发器：需要截取的前台窗口
+    // 后台 OCR 敏感信息检测结果：发起检测时的 overlay 截图编号，overlay 坐标 x, y, w, h
+    Sensitive(u64, Result<Vec<(u32, u32, u32, u32)>, String>),
+    // 连拍完成：各帧与最清晰的一帧
+    Burst(Result<(Vec<BurstFrame>, usize), String>),
+    Fullscreen(bool),   // 全屏程序 / 演示进入（true）或退出前台
+    ElevatedForeground, // 管理员权限窗口首次出现在前台
+    RelaunchElevated,   // 用户在提示中选择以管理员重启
+    UploadTick(bool),   // 上传动画换帧；true 表示计时线程已结束（上传全部完成）
+}
+
+// 托盘图标与菜单项（菜单项需存活以保持 id 有效）
+pub struct Tray {
+    icon: Option<TrayIcon>,
+    base_icon: Vec<u8>, // 缩小到 ICON_SIZE 的应用图标，角标画在其上
+    badges: Badges,
+    tooltip: &'static str,
+    window_menu: Submenu,
+    window_targets: Vec<(MenuId, isize)>, // “截取窗口”子菜单项 -> HWND
+    monitor_menu: Submenu,
+    monitor_targets: Vec<(MenuId, u32)>, // “截取整个屏幕”子菜单项 -> 显示器序号
+    desktop_item: MenuItem,
+    burst_item: MenuItem,
+    timelapse_item: CheckMenuItem,
+    timelapse_pause_item: CheckMenuItem, // 仅在定时截图运行时可用
+    text_qr_item: MenuItem,
+    pause_hotkeys_item: CheckMenuItem,
+    autostart_item: CheckMenuItem,
+    open_log_item: MenuItem,
+    open_data_item: MenuItem,
+    stats_item: MenuItem,
+    upload_menu: Submenu,
+    upload_targets: Vec<(MenuId, u64, bool)>, // “上传记录”菜单项 -> (记录 id, 是否为删除)
+    uploads_generation: Option<u64>,          // 上传记录菜单对应的 uploads::generation
+    group_menu: Submenu,
+    group_targets: Vec<(MenuId, Option<usize>)>, // “贴图分组”菜单项 -> 分组序号（None 为全部显示）
+    workspace_menu: Submenu,
+    save_workspace_item: MenuItem,
+    workspace_targets: Vec<(MenuId, String)>, // “工作区”菜单项 -> 工作区名称
+    restore_pin_item: MenuItem,
+    relaunch_item: MenuItem, // 已提升权限时不可用
+    quit_item: MenuItem,
+}
+
+impl Tray {
+    // 退出时先移除图标，避免通知区域残留失效图标
+    fn remove_icon(&mut self) {
+        self.icon = None;
+    }
+
+    // 托盘菜单（截取窗口 / 截取整个屏幕 / 截取所有屏幕 / 连拍截图 / 定时截图 / 暂停定时截图 / 文本转二维码 / 暂停热键 / 开机启动 / 打开日志 / 打开数据目录 / 统计 / 上传记录 / 贴图分组 / 工作区 / 恢复关闭的贴图 / 以管理员重启 / 退出）
+    pub fn new() -> Self {
+        let menu = Menu::new();
+        let window_menu = Submenu::new("截取窗口(&W)", true);
+        let monitor_menu = Submenu::new("截取整个屏幕(&F)", true);
+        let desktop_item = MenuItem::new("截取所有屏幕(&V)", true, None);
+        let burst_item = MenuItem::new("连拍截图(&B)", true, None);
+        let timelapse_item = CheckMenuItem::new(TIMELAPSE_LABEL, true, false, None);
+        let timelapse_pause_item = CheckMenuItem::new("暂停定时截图(&E)", false, false, None);
+        let text_qr_item = MenuItem::new("文本转二维码(&R)", true, None);
+        let pause_hotkeys_item = CheckMenuItem::new("暂停热键(&P)", true, false, None);
+        let autostart_item =
+            CheckMenuItem::new("开机启动(&A)", true, autostart::is_enabled(), None);
+        let open_log_item = MenuItem::new("打开日志(&L)", true, None);
+        let open_data_item = MenuItem::new("打开数据目录(&D)", true, None);
+        let stats_item = MenuItem::new("统计(&S)", true, None);
+        let upload_menu = Submenu::new("上传记录(&U)", true);
+        let group_menu = Submenu::new("贴图分组(&G)", true);
+        let workspace_menu = Submenu::new("工作区(&K)", true);
+        let save_workspace_item = MenuItem::new("保存当前贴图为工作区", true, None);
+        let restore_pin_item = MenuItem::new("恢复关闭的贴图(&Z)", true, None);
+        let relaunch_item = MenuItem::new("以管理员重启(&M)", !windows_util::is_elevated(), None);
+        let quit_item = MenuItem::new("退出(&Q)", true, None);
+        menu.append(&window_menu).ok();
+        menu.append(&monitor_menu).ok();
+        menu.append(&desktop_item).ok();
+        menu.append(&burst_item).ok();
+        menu.append(&timelapse_item).ok();
+        menu.append(&timelapse_pause_item).ok();
+        menu.append(&text_qr_item).ok();
+        menu.append(&pause_hotkeys_item).ok();
+        menu.append(&autostart_item).ok();
+        menu.append(&open_log_item).ok();
+        menu.append(&open_data_item).ok();
+        menu.append(&stats_item).ok();
+        menu.append(&upload_menu).ok();
+        menu.append(&group_menu).ok();
+        menu.append(&workspace_menu).ok();
+        menu.append(&restore_pin_item).ok();
+        menu.append(&relaunch_item).ok();
+        menu.append(&quit_item).ok();
+        let tooltip = match paths::get().map(|p| p.mode) {
+            Some(paths::Mode::Portable) => "Snip Rust（便携模式）",
+            _ => "Snip Rust",
+        };
+        let base_icon = tray_base_icon();
+        let icon = TrayIconBuilder::new()
+            .with_tooltip(tooltip)
+            .with_icon(Icon::from_rgba(base_icon.clone(), ICON_SIZE, ICON_SIZE).expect("icon rgba"))
+            .with_menu(Box::new(menu))
+            .with_menu_on_left_click(false)
+            .build()
+            .ok();
+        let mut tray = Self {
+            icon,
+            base_icon,
+            badges: Badges::default(),
+            tooltip,
+            window_menu,
+            window_targets: Vec::new(),
+            monitor_menu,
+            monitor_targets: Vec::new(),
+            desktop_item,
+            burst_item,
+            timelapse_item,
+            timelapse_pause_item,
+            text_qr_item,
+            pause_hotkeys_item,
+            autostart_item,
+            open_log_item,
+            open_data_item,
+            stats_item,
+            upload_menu,
+            upload_targets: Vec::new(),
+            uploads_generation: None,
+            group_menu,
+            group_targets: Vec::new(),
+            workspace_menu,
+            save_workspace_item,
+            workspace_targets: Vec::new(),
+            restore_pin_item,
+            relaunch_item,
+            quit_item,
+        };
+        tray.set_windows(windows_util::list_top_windows());
+        tray.refresh_monitors();
+        tray.refresh_uploads();
+        tray.refresh_workspaces();
+        tray
+    }
+
+    // 重建可截取的窗口列表。鼠标进入托盘图标时在后台线程枚举（逐个窗口 SendMessageTimeout 取图标，
+    // 无响应的窗口会拖慢枚举），结果经 UserEvent::WindowList 回到这里；右键菜单弹出前通常已是最新
+    pub fn set_windows(&mut self, windows: Vec<TopWindow>) {
+        while self.window_menu.remove_at(0).is_some() {}
+        self.window_targets.clear();
+        if windows.is_empty() {
+            let empty = MenuItem::new("（没有可截取的窗口）", false, None);
+            self.window_menu.append(&empty).ok();
+        }
+        for win in windows {
+            let icon = win.icon.and_then(|rgba| {
+                MenuIcon::from_rgba(rgba, WINDOW_ICON_SIZE, WINDOW_ICON_SIZE).ok()
+            });
+            let item = IconMenuItem::new(window_menu_label(&win.title), true, icon, None);
+            self.window_menu.append(&item).ok();
+            self.window_targets.push((item.id().clone(), win.handle));
+        }
+    }
+
+    // 重新枚举显示器（插拔 / 调整排列后编号可能变化），与窗口列表同时刷新
+    pub fn refresh_monitors(&mut self) {
+        while self.monitor_menu.remove_at(0).is_some() {}
+        self.monitor_targets.clear();
+        let monitors = list_monitors().unwrap_or_else(|e| {
+            log::warn!("list monitors: {e}");
+            Vec::new()
+        });
+        if monitors.is_empty() {
+            let empty = MenuItem::new("（未检测到显示器）", false, None);
+            self.monitor_menu.append(&empty).ok();
+        }
+        for monitor in &monitors {
+            let item = MenuItem::new(monitor_menu_label(monitor), true, None);
+            self.monitor_menu.append(&item).ok();
+            self.monitor_targets
+                .push((item.id().clone(), monitor.number));
+        }
+    }
+
+    // 最近的上传记录（hook 配置 history = true 时写入），每条一个子菜单：复制链接（缩略图图标）/ 删除远程图片。
+    // 每次鼠标移入托盘都会调用：记录没有追加 / 删除（uploads::generation 未变）时沿用已建的菜单
+    pub fn refresh_uploads(&mut self) {
+        let generation = uploads::generation();
+        if self.uploads_generation == Some(generation) {
+            return;
+        }
+        self.uploads_generation = Some(generation);
+        while self.upload_menu.remove_at(0).is_some() {}
+        self.upload_targets.clear();
+        let history = uploads::history_path();
+        let records = history
+            .as_deref()
+            .map(|path| {
+                uploads::load(path).unwrap_or_else(|e| {
+                    log::warn!("load upload history: {e}");
+                    Vec::new()
+                })
+            })
+            .unwrap_or_default();
+        if records.is_empty() {
+            let empty = MenuItem::new("（没有上传记录）", false, None);
+            self.upload_menu.append(&empty).ok();
+        }
+        for record in records.iter().take(UPLOAD_MENU_MAX) {
+            let label = format!("{}  {}", record.destination, record.url);
+            let entry = Submenu::new(window_menu_label(&label), true);
+            let icon = history
+                .as_deref()
+                .and_then(|path| uploads::thumbnail_icon(path, record, WINDOW_ICON_SIZE))
+                .and_then(|rgba| {
+                    MenuIcon::from_rgba(rgba, WINDOW_ICON_SIZE, WINDOW_ICON_SIZE).ok()
+                });
+            let copy = IconMenuItem::new("复制链接", true, icon, None);
+            let delete = MenuItem::new("删除远程图片", record.delete_argv().is_some(), None);
+            entry.append(&copy).ok();
+            entry.append(&delete).ok();
+            self.upload_menu.append(&entry).ok();
+            self.upload_targets
+                .push((copy.id().clone(), record.id, false));
+            self.upload_targets
+                .push((delete.id().clone(), record.id, true));
+        }
+    }
+
+    // 按配置的分组名称重建“贴图分组”：全部显示 + 每组一项，勾选当前显示的分组
+    pub fn refresh_groups(&mut self, names: &[String], shown: Option<&str>) {
+        while self.group_menu.remove_at(0).is_some() {}
+        self.group_targets.clear();
+        let all = CheckMenuItem::new("全部显示", true, shown.is_none(), None);
+        self.group_menu.append(&all).ok();
+        self.group_targets.push((all.id().clone(), None));
+        for (i, name) in names.iter().enumerate() {
+            let checked = shown == Some(name.as_str());
+            let item = CheckMenuItem::new(window_menu_label(name), true, checked, None);
+            self.group_menu.append(&item).ok();
+            self.group_targets.push((item.id().clone(), Some(i)));
+        }
+    }
+
+    // 保存的工作区（最新在前），与上传记录同时刷新
+    pub fn refresh_workspaces(&mut self) {
+        while self.workspace_menu.remove_at(0).is_some() {}
+        self.workspace_targets.clear();
+        self.workspace_menu.append(&self.save_workspace_item).ok();
+        self.workspace_menu
+            .append(&PredefinedMenuItem::separator())
+            .ok();
+        let names = workspace::root()
+            .map(|root| workspace::list(&root))
+            .unwrap_or_default();
+        if names.is_empty() {
+            let empty = MenuItem::new("（没有保存的工作区）", false, None);
+            self.workspace_menu.append(&empty).ok();
+        }
+        for name in names.into_iter().take(WORKSPACE_MENU_MAX) {
+            let item = MenuItem::new(window_menu_label(&name), true, None);
+            self.workspace_menu.append(&item).ok();
+            self.workspace_targets.push((item.id().clone(), name));
+        }
+    }
+
+    // 托盘图标角标（定时截图红点 / 热键暂停 / 上传动画）；不变时不重建图标
+    fn set_badges(&mut self, badges: Badges) {
+        if badges == self.badges {
+            return;
+        }
+        self.badges = badges;
+        let Some(icon) = &self.icon else {
+            return;
+        };
+        let rgba = compose(&self.base_icon, ICON_SIZE, badges);
+        match Icon::from_rgba(rgba, ICON_SIZE, ICON_SIZE) {
+            Ok(image) => {
+                if let Err(e) = icon.set_icon(Some(image)) {
+                    log::debug!("set tray icon: {e}");
+                }
+            }
+            Err(e) => log::debug!("tray icon rgba: {e}"),
+        }
+    }
+
+    pub fn set_hotkeys_paused(&mut self, paused: bool) {
+        self.set_badges(Badges {
+            paused,
+            ..self.badges
+        });
+    }
+
+    // 上传动画帧（None 为没有上传）
+    pub fn set_upload_frame(&mut self, frame: Option<u32>) {
+        self.set_badges(Badges {
+            uploading: frame,
+            ..self.badges
+        });
+    }
+
+    // 定时截图状态（None 为未运行）：写入菜单项文本与托盘提示，鼠标移到托盘图标上时刷新
+    pub fn refresh_timelapse(&mut self, status: Option<(Progress, bool)>) {
+        self.set_badges(Badges {
+            recording: status.is_some_and(|(_, paused)| !paused),
+            ..self.badges
+        });
+        self.timelapse_pause_item.set_enabled(status.is_some());
+        let Some((progress, paused)) = status else {
+            self.timelapse_pause_item.set_checked(false);
+            self.timelapse_item.set_text(TIMELAPSE_LABEL);
+            if let Some(icon) = &self.icon {
+                let _ = icon.set_tooltip(Some(self.tooltip));
+            }
+            return;
+        };
+        let text = progress.status_text(paused);
+        self.timelapse_pause_item.set_checked(paused);
+        self.timelapse_item
+            .set_text(format!("{TIMELAPSE_LABEL}（{text}）"));
+        if let Some(icon) = &self.icon {
+            let _ = icon.set_tooltip(Some(format!("{}\n定时截图：{text}", self.tooltip)));
+        }
+    }
+}
+
+const TIMELAPSE_LABEL: &str = "定时截图(&T)";
+
+// 管理员权限窗口在前台时的提示（每次运行一次）
+const ELEVATED_NOTICE: &str = "前台窗口以管理员身份运行，Snip Rust 在它获得焦点时收不到热键和鼠标键盘输入。\n\n是否以管理员身份重启 Snip Rust？当前贴图会保留。\n（托盘“以管理员重启”同样可用；配置 ignore_elevated_windows = true 不再提示）";
+
+// 显示器 -> 菜单文本：前 9 个以数字为助记键（菜单打开后按 1..9 直接截取）
+fn monitor_menu_label(monitor: &Monitor) -> String {
+    let n = monitor.number;
+    let name = if monitor.is_primary {
+        format!("屏幕 {n}（主屏）")
+    } else {
+        format!("屏幕 {n}")
+    };
+    let size = format!("{}×{}", monitor.bounds.width, monitor.bounds.height);
+    if (1..=9).contains(&n) {
+        format!("&{n}  {name}  {size}")
+    } else {
+        format!("{name}  {size}")
+    }
+}
+
+const WINDOW_TITLE_MAX: usize = 60; // 子菜单中窗口标题最多显示的字符数
+const UPLOAD_MENU_MAX: usize = 10; // “上传记录”子菜单最多列出的条数
+const WORKSPACE_MENU_MAX: usize = 20; // “工作区”子菜单最多列出的条数
+
+// 窗口标题 -> 菜单文本：过长截断；& 转义，避免被当作助记键
+fn window_menu_label(title: &str) -> String {
+    let mut label: String = title.chars().take(WINDOW_TITLE_MAX).collect();
+    if title.chars().count() > WINDOW_TITLE_MAX {
+        label.push('…');
+    }
+    label.replace('&', "&&")
+}
+
+// 运行上传记录中的删除命令，成功后删除记录（在后台线程调用）
+fn delete_upload(id: u64) {
+    let Some(history) = uploads::history_path() else {
+        return;
+    };
+    let Some(record) = uploads::find(&history, id) else {
+        return;
+    };
+    let Some(argv) = record.delete_argv() else {
+        return;
+    };
+    let title = "Snip Rust - 上传记录";
+    if !windows_util::confirm(title, &format!("删除远程图片？\n{}", record.url)) {
+        return;
+    }
+    match run_args(&argv) {
+        Ok(()) => {
+            if let Err(e) = uploads::remove(&history, id) {
+                log::warn!("remove upload record: {e}");
+            }
+        }
+        Err(e) => {
+            log::warn!("delete upload {argv:?}: {e}");
+            windows_util::show_message(title, &format!("删除失败：{e}"));
+        }
+    }
+}
+
+// 从嵌入的 PNG 构建托盘图标（assets/app_icon.png），缩小为 ICON_SIZE 见方的 RGBA
+fn tray_base_icon() -> Vec<u8> {
+    const BYTES: &[u8] = include_bytes!("../assets/app_icon.png");
+    let reader = ImageReader::new(std::io::Cursor::new(BYTES))
+        .with_guessed_format()
+        .unwrap();
+    let img = reader.decode().expect("decode icon").to_rgba8();
+    let (w, h) = img.dimensions();
+    downscale(img.as_raw(), w, h, ICON_SIZE)
+}
+
+// 菜单事件解析结果
+#[derive(Debug, PartialEq, Eq)]
+pub enum MenuCommand {
+    Quit,
+    ToggleAutostart,
+    TogglePauseHotkeys,
+    OpenLog,
+    OpenDataDir,
+    ShowStats,
+    TextToQr,
+    CaptureWindow(isize),
+    CaptureMonitor(u32),
+    CaptureDesktop,
+    BurstCapture,
+    ToggleTimelapse,
+    ToggleTimelapsePause,
+    CopyUploadUrl(u64),
+    DeleteUpload(u64),
+    ShowPinGroup(Option<usize>),
+    SaveWorkspace,
+    OpenWorkspace(String),
+    RestoreClosedPin,
+    RelaunchElevated,
+    CopyPin(usize),
+    CopyPinDataUri(usize),
+    CopyPinRect(usize),
+    PastePinRect(usize),
+    OpenPinWith(usize),
+    SharePin(usize),
+    RefreshPin(usize),
+    SetPinGroup(usize, Option<usize>), // 贴图序号，分组序号（None 为未分组）
+    SetPinTag(usize, Option<PinTag>),
+    SelectPin(usize),
+    ComparePins,
+    StitchPins { layout: StitchLayout, save: bool },
+    DestroyPin(usize),
+    // 工具栏“更多”菜单中的按钮
+    OverlayTool(ToolButton),
+    // 上传目标选择菜单中的第几个 WebDAV 目标
+    UploadSelection(usize),
+}
+
+// 固定菜单项 id：托盘 + overlay 溢出菜单（overlay 尚未创建时为 None）
+pub struct MenuIds<'a> {
+    pub quit: &'a MenuId,
+    pub autostart: &'a MenuId,
+    pub text_qr: &'a MenuId,
+    pub pause_hotkeys: &'a MenuId,
+    pub open_log: &'a MenuId,
+    pub open_data: &'a MenuId,
+    pub stats: &'a MenuId,
+    pub windows: &'a [(MenuId, isize)],
+    pub monitors: &'a [(MenuId, u32)],
+    pub desktop: &'a MenuId,
+    pub burst: &'a MenuId,
+    pub timelapse: &'a MenuId,
+    pub timelapse_pause: &'a MenuId,
+    pub uploads: &'a [(MenuId, u64, bool)],
+    pub pin_groups: &'a [(MenuId, Option<usize>)],
+    pub save_workspace: &'a MenuId,
+    pub workspaces: &'a [(MenuId, String)],
+    pub restore_pin: &'a MenuId,
+    pub relaunch: &'a MenuId,
+    pub overlay: Option<&'a OverlayMenuIds>,
+}
+
+/// 菜单 id -> 命令：先匹配固定菜单项，再线性扫描贴图右键菜单
+/// （窗口数量一般很少，O(n) 足够）
+pub fn resolve_menu<'a>(
+    id: &MenuId,
+    ids: &MenuIds,
+    pins: impl IntoIterator<Item = &'a PinMenuIds>,
+) -> Option<MenuCommand> {
+    if id == ids.quit {
+        return Some(MenuCommand::Quit);
+    }
+    if id == ids.autostart {
+        return Some(MenuCommand::ToggleAutostart);
+    }
+    if id == ids.text_qr {
+        return Some(MenuCommand::TextToQr);
+    }
+    if id == ids.pause_hotkeys {
+        return Some(MenuCommand::TogglePauseHotkeys);
+    }
+    if id == ids.open_log {
+        return Some(MenuCommand::OpenLog);
+    }
+    if id == ids.open_data {
+        return Some(MenuCommand::OpenDataDir);
+    }
+    if id == ids.stats {
+        return Some(MenuCommand::ShowStats);
+    }
+    if id == ids.desktop {
+        return Some(MenuCommand::CaptureDesktop);
+    }
+    if id == ids.burst {
+        return Some(MenuCommand::BurstCapture);
+    }
+    if id == ids.timelapse {
+        return Some(MenuCommand::ToggleTimelapse);
+    }
+    if id == ids.timelapse_pause {
+        return Some(MenuCommand::ToggleTimelapsePause);
+    }
+    if let Some(&(_, handle)) = ids.windows.iter().find(|(item, _)| item == id) {
+        return Some(MenuCommand::CaptureWindow(handle));
+    }
+    if let Some(&(_, number)) = ids.monitors.iter().find(|(item, _)| item == id) {
+        return Some(MenuCommand::CaptureMonitor(number));
+    }
+    if let Some(&(_, record, delete)) = ids.uploads.iter().find(|(item, ..)| item == id) {
+        return Some(if delete {
+            MenuCommand::DeleteUpload(record)
+        } else {
+            MenuCommand::CopyUploadUrl(record)
+        });
+    }
+    if let Some(&(_, group)) = ids.pin_groups.iter().find(|(item, _)| item == id) {
+        return Some(MenuCommand::ShowPinGroup(group));
+    }
+    if id == ids.save_workspace {
+        return Some(MenuCommand::SaveWorkspace);
+    }
+    if let Some((_, name)) = ids.workspaces.iter().find(|(item, _)| item == id) {
+        return Some(MenuCommand::OpenWorkspace(name.clone()));
+    }
+    if id == ids.restore_pin {
+        return Some(MenuCommand::RestoreClosedPin);
+    }
+    if id == ids.relaunch {
+        return Some(MenuCommand::RelaunchElevated);
+    }
+    if let Some(button) = ids.overlay.and_then(|overlay| overlay.resolve(id)) {
+        return Some(MenuCommand::OverlayTool(button));
+    }
+    if let Some(i) = ids.overlay.and_then(|overlay| overlay.resolve_upload(id)) {
+        return Some(MenuCommand::UploadSelection(i));
+    }
+    for (i, pin) in pins.into_iter().enumerate() {
+        if id == &pin.copy {
+            return Some(MenuCommand::CopyPin(i));
+        }
+        if id == &pin.data_uri {
+            return Some(MenuCommand::CopyPinDataUri(i));
+        }
+        if id == &pin.copy_rect {
+            return Some(MenuCommand::CopyPinRect(i));
+        }
+        if id == &pin.paste_rect {
+            return Some(MenuCommand::PastePinRect(i));
+        }
+        if id == &pin.open_with {
+            return Some(MenuCommand::OpenPinWith(i));
+        }
+        if id == &pin.share {
+            return Some(MenuCommand::SharePin(i));
+        }
+        if id == &pin.refresh {
+            return Some(MenuCommand::RefreshPin(i));
+        }
+        if let Some(g) = pin.groups.iter().position(|item| item == id) {
+            return Some(MenuCommand::SetPinGroup(i, g.checked_sub(1)));
+        }
+        if let Some(t) = pin.tags.iter().position(|item| item == id) {
+            let tag = t.checked_sub(1).map(|t| PinTag::ALL[t]);
+            return Some(MenuCommand::SetPinTag(i, tag));
+        }
+        if id == &pin.select {
+            return Some(MenuCommand::SelectPin(i));
+        }
+        if id == &pin.compare {
+            return Some(MenuCommand::ComparePins);
+        }
+        for (layout, (pin_id, save_id)) in StitchLayout::ALL
+            .into_iter()
+            .zip(pin.stitch_pin.iter().zip(&pin.stitch_save))
+        {
+            if id == pin_id || id == save_id {
+                let save = id == save_id;
+                return Some(MenuCommand::StitchPins { layout, save });
+            }
+        }
+        if id == &pin.destroy {
+            return Some(MenuCommand::DestroyPin(i));
+        }
+    }
+    None
+}
+
+// 截图时需要临时隐藏的贴图下标：无法设置显示亲和性且当前可见（已按分组隐藏的不计入）
+fn pins_to_hide(pins: &[PasteWindow], exclude: bool) -> Vec<usize> {
+    if !exclude {
+        return Vec::new();
+    }
+    pins.iter()
+        .enumerate()
+        .filter(|(_, p)| !p.is_capture_excluded() && p.window.is_visible() != Some(false))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+// 无法设置显示亲和性的贴图：截图期间临时隐藏，返回下标供截完恢复
+fn hide_pins_for_capture(pins: &[PasteWindow], exclude: bool) -> Vec<usize> {
+    let hidden = pins_to_hide(pins, exclude);
+    for &i in &hidden {
+        pins[i].window.set_visible(false);
+    }
+    if !hidden.is_empty() {
+        windows_util::flush_compositor();
+    }
+    hidden
+}
+
+// 贴图是否在当前显示的分组中（未选择分组时全部显示）
+fn pin_in_view(shown: Option<&str>, group: Option<&str>) -> bool {
+    shown.is_none() || shown == group
+}
+
+const QR_MODULE_PX: u32 = 8; // 二维码贴图每模块像素
+const CLOSED_PIN_CACHE: usize = 5; // “恢复关闭的贴图”最多保留的贴图数
+const EXIT_ENCODE_WAIT: std::time::Duration = std::time::Duration::from_secs(3); // 退出时等待保存完成
+const DIFF_THRESHOLD: u8 = 8; // 通道差值不超过此值视为相同（容忍缩放 / 编码噪声）
+const HOLD_TAP: std::time::Duration = std::time::Duration::from_millis(250); // hold 模式下短于此视为短按
+
+// 开启时读取已有统计；文件损坏时记录日志并重新开始
+// 用默认程序打开配置文件；不存在时先写入一行说明（改完后执行 --reload-config 生效）
+fn open_settings() {
+    let Some(path) = Config::path() else {
+        return;
+    };
+    if !path.exists() {
+        let created = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| {
+                std::fs::write(
+                    &path,
+                    "# Snip Rust 配置，保存后执行 snip_rust --reload-config\n",
+                )
+            });
+        if let Err(e) = created {
+            log::warn!("create config {}: {e}", path.display());
+            return;
+        }
+    }
+    if let Err(e) = windows_util::open_path(&path) {
+        log::warn!("open config {}: {e}", path.display());
+    }
+}
+
+fn load_stats(enabled: bool) -> Option<Stats> {
+    if !enabled {
+        return None;
+    }
+    let path = stats::stats_path()?;
+    Some(stats::load(&path).unwrap_or_else(|e| {
+        log::warn!("load stats {}: {e}", path.display());
+        Stats::new()
+    }))
+}
+
+// 图像信息：文本复制到剪贴板（便于粘贴色值）并弹窗显示
+fn report_image_info(info: &ImageInfo) {
+    let text = info.summary();
+    let copied = match copy_text(&text) {
+        Ok(()) => "\n\n（已复制到剪贴板）",
+        Err(e) => {
+            log::warn!("copy image info failed: {e}");
+            ""
+        }
+    };
+    windows_util::show_message("Snip Rust - 图像信息", &format!("{text}{copied}"));
+}
+
+// 识别结果：文本复制到剪贴板并弹窗显示；单个网址时询问是否打开
+fn report_qr(texts: &[String]) {
+    const TITLE: &str = "Snip Rust - 二维码";
+    if texts.is_empty() {
+        windows_util::show_message(TITLE, "未识别到二维码");
+        return;
+    }
+    let joined = texts.join("\n");
+    let copied = match copy_text(&joined) {
+        Ok(()) => "（已复制到剪贴板）",
+        Err(e) => {
+            log::warn!("copy qr text failed: {e}");
+            ""
+        }
+    };
+    match texts {
+        [url] if is_url(url) => {
+            if windows_util::confirm(TITLE, &format!("{url}\n{copied}\n\n在浏览器中打开？"))
+            {
+                if let Err(e) = windows_util::open_url(url.trim()) {
+                    log::warn!("open url failed: {e}");
+                }
+            }
+        }
+        _ => windows_util::show_message(TITLE, &format!("{joined}\n{copied}")),
+    }
+}
+
+// 热键未能按首选注册时提示用户（后台线程弹窗，不阻塞事件循环）
+pub fn report_hotkey_bind(outcome: &BindOutcome) {
+    let Some(text) = outcome.message() else {
+        return;
+    };
+    log::warn!("hotkey: {outcome:?}");
+    std::thread::spawn(move || windows_util::show_message("Snip Rust - 热键", &text));
+}
+
+// 接管 PrintScreen 时由本程序关闭了“PrintScreen 打开截图工具”：释放接管 / 退出时改回
+static SNIPPING_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// 接管 / 释放 PrintScreen。Windows 11 默认把 PrintScreen 交给截图工具（系统占用该键，
+/// RegisterHotKey 会失败），接管时先关闭该用户设置再注册，释放时恢复
+pub fn take_over_print_screen(hotkeys: &mut Hotkeys, enabled: bool) {
+    if enabled && windows_util::print_screen_opens_snipping() {
+        match windows_util::set_print_screen_opens_snipping(false) {
+            Ok(()) => {
+                SNIPPING_DISABLED.store(true, Ordering::SeqCst);
+                log::info!("disabled \"PrintScreen opens Snipping Tool\" setting");
+            }
+            Err(e) => log::warn!("disable snipping tool print screen: {e}"),
+        }
+    }
+    if !enabled {
+        restore_print_screen_snipping();
+    }
+    if let Err(e) = hotkeys.set_print_screen(enabled) {
+        log::warn!("print screen takeover: {e}");
+        let text = format!(
+            "PrintScreen 接管失败（{e}）。\n请在 设置 → 辅助功能 → 键盘 关闭“使用 Print Screen 键打开屏幕截图”，必要时注销后重新登录，再执行 snip_rust --reload-config。"
+        );
+        std::thread::spawn(move || windows_util::show_message("Snip Rust - 热键", &text));
+    }
+}
+
+/// 恢复接管时关闭的“PrintScreen 打开截图工具”设置（未改动过时不写注册表）
+pub fn restore_print_screen_snipping() {
+    if !SNIPPING_DISABLED.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    match windows_util::set_print_screen_opens_snipping(true) {
+        Ok(()) => log::info!("restored \"PrintScreen opens Snipping Tool\" setting"),
+        Err(e) => log::warn!("restore snipping tool print screen: {e}"),
+    }
+}
+
+pub struct App {
+    config: Config,
+    hotkeys: Option<Hotkeys>, // None：事件处理器安装失败，只能经 IPC / 托盘使用
+    proxy: EventLoopProxy<UserEvent>, // 后台任务（OCR）结果回到事件循环
+    tray: Tray,
+    encoder: EncodeQueue, // 后台 PNG 编码（保存 / copy hook）
+    overlay: Option<OverlayState>,
+    pins: Vec<PasteWindow>,                         // 多 PasteWindow
+    stats: Option<Stats>,                           // 本地使用统计（配置 stats = true 时）
+    pick_reply: Option<Responder>,                  // IPC pick_region 等待中的回复
+    timelapse: Option<Timelapse>,                   // 运行中的定时截图
+    burst_hidden: Option<Vec<usize>>,               // 连拍进行中：期间隐藏的贴图
+    pending_layers: Vec<(JobId, AnnotationLayers)>, // 保存完成后要写 sidecar 的标注图层
+    pending_shares: Vec<(JobId, Option<isize>)>,    // 分享任务及分享面板锚定的窗口
+    pending_uploads: Vec<(JobId, usize)>,           // 上传任务及所选 WebDAV 目标序号
+    capture_source: CaptureSource,                  // 当前 overlay 截图的来源（写入工程文件）
+    temp_files: TempFiles,                          // “用编辑器打开”写出的临时文件，退出时清理
+    shown_group: Option<String>,                    // 托盘“贴图分组”当前只显示的分组（None 为全部）
+    closed_pins: Vec<(RecoveredPin, Option<Rect>)>, // 最近关闭的贴图（快照 + 来源区域），最新在后
+    fullscreen: bool,                               // 全屏程序 / 演示在前台（配置 [fullscreen] 时）
+    upload_frame: Option<u32>,                      // 托盘上传动画的当前帧（计时线程运行中）
+    flyout: Option<Flyout>,                         // 托盘左键快捷面板（首次单击时创建）
+    toast: Option<ProgressToast>,                   // PNG 优化进度提示（首次需要时创建）
+    encode_progress: Vec<(JobId, f32)>,             // 进行中的优化编码任务与进度
+    hold_since: Option<std::time::Instant>,         // hold 模式：截图热键按下的时间（预览中）
+    listing_windows: bool,                          // 后台线程正在枚举托盘窗口列表
+}
+
+impl App {
+    pub fn new(
+        config: Config,
+        hotkeys: Option<Hotkeys>,
+        mut tray: Tray,
+        encoder: EncodeQueue,
+        proxy: EventLoopProxy<UserEvent>,
+    ) -> Self {
+        let stats = load_stats(config.stats);
+        tray.refresh_groups(&config.pin_group_names(), None);
+        let temp_files = TempFiles::default();
+        temp_files.sweep(open_with::MAX_AGE);
+        Self {
+            config,
+            hotkeys,
+            proxy,
+            tray,
+            encoder,
+            overlay: None,
+            pins: Vec::new(),
+            stats,
+            pick_reply: None,
+            timelapse: None,
+            burst_hidden: None,
+            pending_layers: Vec::new(),
+            pending_shares: Vec::new(),
+            pending_uploads: Vec::new(),
+            capture_source: CaptureSource::default(),
+            temp_files,
+            shown_group: None,
+            closed_pins: Vec::new(),
+            fullscreen: false,
+            upload_frame: None,
+            hold_since: None,
+            listing_windows: false,
+            flyout: None,
+            toast: None,
+            encode_progress: Vec::new(),
+        }
+    }
+
+    fn handle_menu(&mut self, event_loop: &ActiveEventLoop, id: MenuId) {
+        let pin_ids = self.pins.iter().map(|pw| &pw.ctx_ids);
+        let ids = MenuIds {
+            quit: self.tray.quit_item.id(),
+            autostart: self.tray.autostart_item.id(),
+            text_qr: self.tray.text_qr_item.id(),
+            pause_hotkeys: self.tray.pause_hotkeys_item.id(),
+            open_log: self.tray.open_log_item.id(),
+            open_data: self.tray.open_data_item.id(),
+            stats: self.tray.stats_item.id(),
+            windows: &self.tray.window_targets,
+            monitors: &self.tray.monitor_targets,
+            desktop: self.tray.desktop_item.id(),
+            burst: self.tray.burst_item.id(),
+            timelapse: self.tray.timelapse_item.id(),
+            timelapse_pause: self.tray.timelapse_pause_item.id(),
+            uploads: &self.tray.upload_targets,
+            pin_groups: &self.tray.group_targets,
+            save_workspace: self.tray.save_workspace_item.id(),
+            workspaces: &self.tray.workspace_targets,
+            restore_pin: self.tray.restore_pin_item.id(),
+            relaunch: self.tray.relaunch_item.id(),
+            overlay: self.overlay.as_ref().map(|ov| &ov.more_ids),
+        };
+        let command = resolve_menu(&id, &ids, pin_ids);
+        match command {
+            Some(MenuCommand::Quit) => {
+                log::debug!("quit menu selected");
+                event_loop.exit();
+            }
+            // 开机启动切换：菜单已自动翻转勾选状态，失败时还原
+            Some(MenuCommand::ToggleAutostart) => {
+                let want = self.tray.autostart_item.is_checked();
+                if let Err(e) = autostart::set(want) {
+                    log::warn!("toggle autostart: {e}");
+                    self.tray.autostart_item.set_checked(!want);
+                }
+            }
+            // 暂停 / 恢复全部热键（游戏、演示时避免误触）；菜单已自动翻转勾选状态
+            Some(MenuCommand::TogglePauseHotkeys) => {
+                let pause = self.tray.pause_hotkeys_item.is_checked();
+                let Some(hotkeys) = &mut self.hotkeys else {
+                    self.tray.pause_hotkeys_item.set_checked(false);
+                    return;
+                };
+                if pause {
+                    hotkeys.pause();
+                    self.tray.set_hotkeys_paused(true);
+                } else {
+                    let failed = hotkeys.resume();
+                    self.tray.set_hotkeys_paused(false);
+                    if !failed.is_empty() {
+                        let text = format!(
+                            "以下热键恢复失败（可能已被其它程序占用）：{}\n可修改配置后执行 snip_rust --reload-config。",
+                            failed.join("、")
+                        );
+                        std::thread::spawn(move || {
+                            windows_util::show_message("Snip Rust - 热键", &text)
+                        });
+                    }
+                }
+            }
+            // 用默认程序打开最新日志文件（发布版无控制台，排查问题用）
+            Some(MenuCommand::OpenLog) => {
+                let Some(path) = logging::log_path().filter(|p| p.exists()) else {
+                    std::thread::spawn(|| {
+                        windows_util::show_message("Snip Rust", "日志文件不存在")
+                    });
+                    return;
+                };
+                if let Err(e) = windows_util::open_path(&path) {
+                    log::warn!("open log {}: {e}", path.display());
+                }
+            }
+            // 数据目录（便携模式为可执行文件旁的 data/），不存在时先创建
+            Some(MenuCommand::OpenDataDir) => {
+                let Some(dir) = paths::data_dir() else {
+                    return;
+                };
+                let opened =
+                    std::fs::create_dir_all(&dir).and_then(|_| windows_util::open_path(&dir));
+                if let Err(e) = opened {
+                    log::warn!("open data dir {}: {e}", dir.display());
+                }
+            }
+            Some(MenuCommand::ShowStats) => {
+                let text = match &self.stats {
+                    Some(stats) => stats.summary(),
+                    None => {
+                        "统计未开启：在配置中设置 stats = true 后执行 snip_rust --reload-config"
+                            .to_string()
+                    }
+                };
+                std::thread::spawn(move || windows_util::show_message("Snip Rust - 统计", &text));
+            }
+            // 剪贴板文本 -> 二维码贴图（方便把链接发到手机）
+            Some(MenuCommand::TextToQr) => {
+                match read_text().and_then(|text| render_qr(&text, QR_MODULE_PX)) {
+                    Ok((w, h, rgba)) => {
+                        match PasteWindow::new_from_rgba(event_loop, w, h, &rgba, None) {
+                            Ok(pw) => self.add_pin(pw),
+                            Err(e) => log::warn!("pin qr code: {e}"),
+                        }
+                    }
+                    Err(e) => log::warn!("text to qr: {e}"),
+                }
+            }
+            Some(MenuCommand::CaptureWindow(handle)) => self.capture_window(event_loop, handle),
+            Some(MenuCommand::CaptureMonitor(number)) => {
+                self.capture_monitor(event_loop, number, self.config.capture_action)
+            }
+            Some(MenuCommand::CaptureDesktop) => self.capture_desktop(event_loop),
+            Some(MenuCommand::BurstCapture) => self.start_burst(),
+            // 菜单已自动翻转勾选状态：勾选启动，取消停止
+            Some(MenuCommand::CopyUploadUrl(record)) => {
+                match uploads::history_path().and_then(|path| uploads::find(&path, record)) {
+                    Some(record) => {
+                        if let Err(e) = copy_text(&record.url) {
+                            log::warn!("copy upload url: {e}");
+                        }
+                    }
+                    None => log::warn!("upload record {record} not found"),
+                }
+            }
+            // 确认、删除命令与弹窗都会阻塞，放到独立线程；记录在下次鼠标移到托盘时从菜单消失
+            Some(MenuCommand::DeleteUpload(record)) => {
+                std::thread::spawn(move || delete_upload(record));
+            }
+            // 只显示某一组贴图（None 为全部显示）；菜单勾选按结果重建
+            Some(MenuCommand::ShowPinGroup(group)) => {
+                // 同时重新显示 Esc 隐藏的贴图
+                for pw in &mut self.pins {
+                    pw.clear_user_hidden();
+                }
+                let names = self.config.pin_group_names();
+                self.shown_group = group.and_then(|g| names.get(g).cloned());
+                self.tray
+                    .refresh_groups(&names, self.shown_group.as_deref());
+                self.apply_pin_visibility();
+            }
+            Some(MenuCommand::SaveWorkspace) => {
+                let text = match workspace::save_current() {
+                    Ok(dir) => {
+                        self.tray.refresh_workspaces();
+                        format!("已保存工作区：{}", dir.display())
+                    }
+                    Err(e) => {
+                        log::warn!("save workspace: {e}");
+                        format!("保存工作区失败：{e}")
+                    }
+                };
+                std::thread::spawn(move || windows_util::show_message("Snip Rust - 工作区", &text));
+            }
+            // 工作区的贴图追加到当前贴图之后（不关闭已有贴图）
+            Some(MenuCommand::OpenWorkspace(name)) => {
+                let Some(root) = workspace::root() else {
+                    return;
+                };
+                match workspace::load(&root, &name) {
+                    Ok(pins) => {
+                        let total = pins.len();
+                        let opened = self.open_pins(event_loop, pins);
+                        log::info!("opened {opened}/{total} pins from workspace {name}");
+                    }
+                    Err(e) => log::warn!("open workspace {name}: {e}"),
+                }
+            }
+            Some(MenuCommand::RestoreClosedPin) => self.restore_closed_pin(event_loop),
+            Some(MenuCommand::RelaunchElevated) => self.relaunch_elevated(event_loop),
+            Some(MenuCommand::ToggleTimelapse) => {
+                if self.tray.timelapse_item.is_checked() {
+                    self.start_timelapse();
+                } else if let Some(mut timelapse) = self.timelapse.take() {
+                    timelapse.stop();
+                    self.tray.refresh_timelapse(None);
+                }
+            }
+            Some(MenuCommand::ToggleTimelapsePause) => match &self.timelapse {
+                Some(timelapse) => {
+                    let control = timelapse.control();
+                    control.set_paused(!control.is_paused());
+                    self.tray
+                        .refresh_timelapse(Some((control.progress(), control.is_paused())));
+                }
+                None => self.tray.refresh_timelapse(None),
+            },
+            Some(MenuCommand::CopyPin(i)) => {
+                log::debug!("context copy triggered id={:?}", id);
+                let pw = &self.pins[i];
+                let rgba = pw.image_rgba();
+                match copy_rgba(pw.width, pw.height, &rgba) {
+                    Ok(()) => self.after_copy(pw.width, pw.height, rgba),
+                    Err(e) => log::warn!("copy failed: {e}"),
+                }
+            }
+            Some(MenuCommand::CopyPinDataUri(i)) => {
+                let pw = &self.pins[i];
+                let rgba = pw.image_rgba();
+                self.encoder
+                    .submit(EncodePurpose::DataUri, pw.width, pw.height, rgba);
+            }
+            // 位置文本 `x y width height`，可贴进 bug 报告 / 文档，之后再“粘贴位置”还原
+            Some(MenuCommand::CopyPinRect(i)) => {
+                let Some(rect) = self.pins[i].screen_rect() else {
+                    return;
+                };
+                if let Err(e) = copy_text(&rect_text(rect)) {
+                    log::warn!("copy pin rect: {e}");
+                }
+            }
+            Some(MenuCommand::PastePinRect(i)) => {
+                match read_text().map(|text| parse_position(&text)) {
+                    Ok(Some((x, y))) => self.pins[i].move_to(x, y),
+                    Ok(None) => log::warn!("paste pin position: no coordinates in clipboard text"),
+                    Err(e) => log::warn!("paste pin position: {e}"),
+                }
+            }
+            Some(MenuCommand::OpenPinWith(i)) => {
+                let pw = &self.pins[i];
+                let rgba = pw.image_rgba();
+                self.encoder
+                    .submit(EncodePurpose::Edit, pw.width, pw.height, rgba);
+            }
+            Some(MenuCommand::SharePin(i)) => {
+                let pw = &self.pins[i];
+                let handle = windows_util::window_handle(&pw.window);
+                let rgba = pw.image_rgba();
+                let id = self
+                    .encoder
+                    .submit(EncodePurpose::Share, pw.width, pw.height, rgba);
+                self.pending_shares.push((id, handle));
+            }
+            Some(MenuCommand::RefreshPin(i)) => self.refresh_pin(i),
+            Some(MenuCommand::SetPinGroup(i, group)) => {
+                let group = group.and_then(|g| self.config.pin_group_names().get(g).cloned());
+                self.pins[i].set_group(group);
+                self.apply_pin_visibility();
+            }
+            Some(MenuCommand::SetPinTag(i, tag)) => self.pins[i].set_tag(tag),
+            // 勾选状态由菜单自动翻转，这里只需刷新边框
+            Some(MenuCommand::SelectPin(i)) => self.pins[i].window.request_redraw(),
+            Some(MenuCommand::ComparePins) => self.compare_selected_pins(event_loop),
+            Some(MenuCommand::StitchPins { layout, save }) => {
+                self.stitch_selected_pins(event_loop, layout, save)
+            }
+            Some(MenuCommand::OverlayTool(button)) => {
+                if let Some(ov) = &mut self.overlay {
+                    let action = ov.execute_tool(button);
+                    self.handle_overlay_action(event_loop, action);
+                }
+            }
+            Some(MenuCommand::UploadSelection(i)) => {
+                if let Some(ov) = &mut self.overlay {
+                    let action = ov.upload_selection(i);
+                    self.handle_overlay_action(event_loop, action);
+                }
+            }
+            Some(MenuCommand::DestroyPin(i)) => {
+                log::debug!(
+                    "context destroy triggered id={:?} removing window #{}",
+                    id,
+                    i
+                );
+                self.close_pin(i);
+            }
+            None => {}
+        }
+    }
+
+    fn handle_command(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        cmd: IpcCommand,
+        reply: Option<Responder>,
+    ) {
+        match cmd {
+            IpcCommand::CaptureRegion => self.start_capture(event_loop),
+            IpcCommand::PickRegion => self.start_pick(event_loop, reply),
+            IpcCommand::CaptureDesktop => self.capture_desktop(event_loop),
+            IpcCommand::Burst => self.start_burst(),
+            IpcCommand::EditAnnotations { path } => self.edit_annotations(event_loop, &path),
+            IpcCommand::OpenProject { path } => self.open_project(event_loop, &path),
+            IpcCommand::PinImage { path } => self.pin_image(event_loop, &path),
+            IpcCommand::PinClipboard => match read_rgba() {
+                Ok((w, h, rgba)) => match PasteWindow::new_from_rgba(event_loop, w, h, &rgba, None)
+                {
+                    Ok(pw) => self.add_pin(pw),
+                    Err(e) => log::warn!("pin clipboard: {e}"),
+                },
+                Err(e) => log::warn!("pin clipboard: {e}"),
+            },
+            IpcCommand::ReloadConfig => self.reload_config(),
+            IpcCommand::Quit => event_loop.exit(),
+        }
+    }
+
+    // 重新读取配置：热键变化时重新注册，overlay 相关设置立即生效；
+    // 编码相关（ICC / 注释 / 文件名模板）与热键后端仍需重启
+    fn reload_config(&mut self) {
+        let config = Config::load();
+        if let Some(hotkeys) = &mut self.hotkeys {
+            if config.hotkey_backend != hotkeys.backend() {
+                log::warn!("hotkey_backend change takes effect after restart");
+            }
+            let changed = config.hotkey != self.config.hotkey
+                || config.fallback_hotkeys != self.config.fallback_hotkeys
+                || hotkeys.current().is_none();
+            if changed {
+                let (wanted, fallbacks) = config.hotkey_bindings();
+                report_hotkey_bind(&hotkeys.bind(wanted, &fallbacks));
+            }
+            if config.print_screen != self.config.print_screen {
+                take_over_print_screen(hotkeys, config.print_screen);
+            }
+            hotkeys.set_double_press(config.double_press_window());
+            hotkeys.set_mode(config.hotkey_mode);
+        }
+        if let Some(ov) = &mut self.overlay {
+            ov.set_low_memory(config.low_memory);
+            ov.set_region_layout(config.multi_selection_layout);
+            ov.set_auto_trim(config.auto_trim);
+            ov.set_corner_radius(config.corner_radius);
+            ov.set_export_scale(config.export_scale);
+            ov.set_upload_destinations(config.webdav.iter().map(|d| d.name.as_str()));
+            ov.set_selection_guides(config.selection_guides);
+            ov.set_min_selection_size(config.min_selection_size.unwrap_or(MIN_RESIZE));
+            ov.set_selection_grid(config.selection_grid);
+            ov.set_annotation_sidecar(config.annotation_sidecar);
+            ov.set_toolbar(config.toolbar_spec());
+        }
+        logging::set_level(config.log_level);
+        if config.stats != self.stats.is_some() {
+            self.stats = load_stats(config.stats);
+        }
+        // 分组改名 / 删除后，正在显示的分组不存在时恢复全部显示
+        let names = config.pin_group_names();
+        if !names
+            .iter()
+            .any(|n| Some(n.as_str()) == self.shown_group.as_deref())
+        {
+            self.shown_group = None;
+        }
+        self.tray
+            .refresh_groups(&names, self.shown_group.as_deref());
+        for pw in &mut self.pins {
+            pw.set_groups(&names);
+            pw.set_shadow(config.pin_shadow);
+            pw.set_ui_scale(config.ui_scale());
+            pw.set_escape(config.pin_escape);
+        }
+        self.config = config;
+        self.apply_pin_visibility();
+        log::debug!("config reloaded");
+    }
+
+    fn handle_overlay_action(&mut self, event_loop: &ActiveEventLoop, action: OverlayAction) {
+        match action {
+            OverlayAction::Canceled => {
+                // overlay 已隐藏；拾取模式下回复“未选择”
+                if let Some(reply) = self.pick_reply.take() {
+                    reply.ok(serde_json::Value::Null);
+                }
+            }
+            OverlayAction::RegionPicked(rect) => {
+                if let Some(reply) = self.pick_reply.take() {
+                    reply.ok(serde_json::to_value(rect).unwrap_or_default());
+                }
+            }
+            OverlayAction::Batch(actions) => {
+                for a in actions {
+                    self.handle_overlay_action(event_loop, a);
+                }
+            }
+            OverlayAction::PasteSelection {
+                rgba,
+                width,
+                height,
+                screen_x,
+                screen_y,
+                refreshable,
+            } => {
+                self.record_stats(StatEvent::Pin, Some((width, height)));
+                if let Ok(mut pw) = PasteWindow::new_from_rgba(
+                    event_loop,
+                    width,
+                    height,
+                    &rgba,
+                    Some((screen_x, screen_y)),
+                ) {
+                    // 有编辑（打码 / 标注等）的贴图不能刷新：重新截取会丢掉编辑
+                    if refreshable {
+                        pw.set_source(Rect {
+                            x: screen_x,
+                            y: screen_y,
+                            width,
+                            height,
+                        });
+                    }
+                    self.add_pin(pw);
+                }
+            }
+            OverlayAction::SaveSelection {
+                rgba,
+                width,
+                height,
+                layers,
+            } => {
+                self.record_stats(StatEvent::Save, Some((width, height)));
+                // 确认导出时播放快门声，写完文件后 finish_encode 再播放保存音
+                self.play_sound(Sound::Capture);
+                let id = self
+                    .encoder
+                    .submit(EncodePurpose::Save, width, height, rgba);
+                if let Some(layers) = layers {
+                    self.pending_layers.push((id, layers));
+                }
+            }
+            OverlayAction::Copied {
+                rgba,
+                width,
+                height,
+            } => {
+                self.record_stats(StatEvent::Copy, Some((width, height)));
+                // 复制音排在快门声之后
+                self.play_sound(Sound::Capture);
+                self.after_copy(width, height, rgba)
+            }
+            OverlayAction::CopyDataUri {
+                rgba,
+                width,
+                height,
+            } => {
+                self.record_stats(StatEvent::Copy, Some((width, height)));
+                self.play_sound(Sound::Capture);
+                self.encoder
+                    .submit(EncodePurpose::DataUri, width, height, rgba);
+            }
+            OverlayAction::OpenWith {
+                rgba,
+                width,
+                height,
+            } => {
+                self.encoder
+                    .submit(EncodePurpose::Edit, width, height, rgba);
+            }
+            // overlay 已隐藏但窗口仍在，作为分享面板的锚点
+            OverlayAction::Share {
+                rgba,
+                width,
+                height,
+            } => {
+                let handle = self
+                    .overlay
+                    .as_ref()
+                    .and_then(|ov| windows_util::window_handle(&ov.window));
+                let id = self
+                    .encoder
+                    .submit(EncodePurpose::Share, width, height, rgba);
+                self.pending_shares.push((id, handle));
+            }
+            OverlayAction::Upload {
+                rgba,
+                width,
+                height,
+                destination,
+            } => {
+                let id = self
+                    .encoder
+                    .submit(EncodePurpose::Upload, width, height, rgba);
+                self.pending_uploads.push((id, destination));
+            }
+            // 识别 + 弹窗都会阻塞，放到独立线程（与 hook 相同）
+            OverlayAction::DecodeQr {
+                rgba,
+                width,
+                height,
+            } => {
+                std::thread::spawn(move || report_qr(&decode_qr(width, height, &rgba)));
+            }
+            OverlayAction::ImageInfo {
+                rgba,
+                width,
+                height,
+            } => {
+                std::thread::spawn(move || report_image_info(&analyze(width, height, &rgba)));
+            }
+            // OCR 较慢：后台识别，结果经 UserEvent::Sensitive 回填 overlay
+            OverlayAction::DetectSensitive {
+                rgba,
+                width,
+                height,
+                origin,
+                session,
+            } => {
+                let proxy = self.proxy.clone();
+                std::thread::spawn(move || {
+                    let result = recognize(width, height, &rgba)
+                        .map(|lines| {
+                            find_sensitive(&lines)
+                                .into_iter()
+                                .map(|m| {
+                                    let (x, y, w, h) = m.rect;
+                                    (x + origin.0, y + origin.1, w, h)
+                                })
+                                .collect()
+                        })
+                        .map_err(|e| e.to_string());
+                    let _ = proxy.send_event(UserEvent::Sensitive(session, result));
+                });
+            }
+            // 整屏 PNG 编码较慢：后台写文件，文件名沿用模板
+            OverlayAction::SaveProject(mut project) => {
+                project.manifest.source = self.capture_source.clone();
+                let (width, height) = project
+                    .manifest
+                    .selection
+                    .map_or((project.manifest.width, project.manifest.height), |r| {
+                        (r.2, r.3)
+                    });
+                let name = self.encoder.next_name(width, height);
+                std::thread::spawn(move || match project.save(&name) {
+                    Ok(path) => log::info!("project saved: {}", path.display()),
+                    Err(e) => {
+                        log::warn!("save project: {e}");
+                        windows_util::show_message("Snip Rust", &format!("保存工程失败：{e}"));
+                    }
+                });
+            }
+            OverlayAction::None => {}
+        }
+    }
+
+    // 检测结果：有命中则在 overlay 中标出，否则提示
+    fn finish_sensitive(
+        &mut self,
+        session: u64,
+        result: Result<Vec<(u32, u32, u32, u32)>, String>,
+    ) {
+        const TITLE: &str = "Snip Rust - 敏感信息";
+        // 识别期间 overlay 已关闭或换了截图：结果（及提示）不再适用
+        let Some(ov) = self
+            .overlay
+            .as_mut()
+            .filter(|o| o.session() == Some(session))
+        else {
+            log::debug!("dropping sensitive text result for a closed capture");
+            return;
+        };
+        let text = match result {
+            Ok(regions) if regions.is_empty() => "未发现邮箱 / API key / IP 地址".to_string(),
+            Ok(regions) => {
+                ov.set_redactions(session, regions);
+                return;
+            }
+            Err(e) => {
+                log::warn!("sensitive text detection failed: {e}");
+                format!("文字识别失败：{e}")
+            }
+        };
+        // 模态弹窗放到独立线程，避免阻塞仍在显示的 overlay
+        std::thread::spawn(move || windows_util::show_message(TITLE, &text));
+    }
+
+    // 对比两个选中的贴图：差异热力图钉为新贴图（位于第二个贴图右侧）
+    fn compare_selected_pins(&mut self, event_loop: &ActiveEventLoop) {
+        const TITLE: &str = "Snip Rust - 对比";
+        let selected: Vec<&PasteWindow> = self.pins.iter().filter(|p| p.is_selected()).collect();
+        let [a, b] = selected[..] else {
+            std::thread::spawn(|| {
+                windows_util::show_message(TITLE, "请先在贴图右键菜单中勾选两个“选中”贴图")
+            });
+            return;
+        };
+        let (rgba_a, rgba_b) = (a.image_rgba(), b.image_rgba());
+        let diff = diff_heatmap(
+            (a.width, a.height, &rgba_a),
+            (b.width, b.height, &rgba_b),
+            DIFF_THRESHOLD,
+        );
+        if diff.changed == 0 {
+            std::thread::spawn(|| windows_util::show_message(TITLE, "两个贴图没有差异"));
+            return;
+        }
+        log::info!(
+            "pin diff: {} pixels changed ({:.2}%)",
+            diff.changed,
+            diff.changed_ratio() * 100.0
+        );
+        let pos = b
+            .window
+            .outer_position()
+            .ok()
+            .map(|p| (p.x + b.window.outer_size().width as i32 + 8, p.y));
+        match PasteWindow::new_from_rgba(event_loop, diff.width, diff.height, &diff.rgba, pos) {
+            Ok(pw) => self.add_pin(pw),
+            Err(e) => log::warn!("pin diff: {e}"),
+        }
+    }
+
+    // 合并选中的贴图（按屏幕位置排序）：钉为新贴图（放在最左上的贴图处）或直接保存
+    fn stitch_selected_pins(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        layout: StitchLayout,
+        save: bool,
+    ) {
+        let mut selected: Vec<(i32, i32, &PasteWindow)> = self
+            .pins
+            .iter()
+            .filter(|p| p.is_selected())
+            .map(|p| {
+                let pos = p
+                    .window
+                    .outer_position()
+                    .map(|p| (p.x, p.y))
+                    .unwrap_or((0, 0));
+                (pos.0, pos.1, p)
+            })
+            .collect();
+        if selected.len() < 2 {
+            std::thread::spawn(|| {
+                windows_util::show_message(
+                    "Snip Rust - 合并",
+                    "请先在贴图右键菜单中勾选至少两个“选中”贴图",
+                )
+            });
+            return;
+        }
+        match layout {
+            StitchLayout::Horizontal => selected.sort_by_key(|&(x, y, _)| (x, y)),
+            // 纵向 / 网格按行优先：先上后下，同一行从左到右
+            StitchLayout::Vertical | StitchLayout::Grid => {
+                selected.sort_by_key(|&(x, y, _)| (y, x))
+            }
+        }
+        let pixels: Vec<Vec<u8>> = selected.iter().map(|(_, _, p)| p.image_rgba()).collect();
+        let images: Vec<(u32, u32, &[u8])> = selected
+            .iter()
+            .zip(&pixels)
+            .map(|((_, _, p), rgba)| (p.width, p.height, rgba.as_slice()))
+            .collect();
+        let pos = selected
+            .iter()
+            .map(|&(x, y, _)| (x, y))
+            .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1)));
+        let (width, height, rgba) = match stitch(layout, &images) {
+            Ok(out) => out,
+            Err(e) => {
+                log::warn!("stitch pins: {e}");
+                return;
+            }
+        };
+        if save {
+            self.encoder
+                .submit(EncodePurpose::Save, width, height, rgba);
+            return;
+        }
+        match PasteWindow::new_from_rgba(event_loop, width, height, &rgba, pos) {
+            Ok(pw) => self.add_pin(pw),
+            Err(e) => log::warn!("pin stitched image: {e}"),
+        }
+    }
+
+    // “刷新”：重新截取贴图的来源区域并原地替换图像。截图期间本贴图也要隐藏（它通常正盖在来源区域上）
+    fn refresh_pin(&mut self, i: usize) {
+        let Some(rect) = self.pins[i].source() else {
+            return;
+        };
+        let mut hidden = hide_pins_for_capture(&self.pins, self.config.exclude_pins_from_capture);
+        if !self.pins[i].is_capture_excluded() && !hidden.contains(&i) {
+            self.pins[i].window.set_visible(false);
+            windows_util::flush_compositor();
+            hidden.push(i);
+        }
+        let captured = capture_area_rgba(rect);
+        for &j in &hidden {
+            self.pins[j].window.set_visible(true);
+        }
+        let result =
+            captured.and_then(|rgba| self.pins[i].replace_image(rect.width, rect.height, &rgba));
+        if let Err(e) = result {
+            log::warn!("refresh pin: {e}");
+        }
+    }
+
+    // 计数并立即写盘；未开启统计时不做任何事
+    fn record_stats(&mut self, event: StatEvent, selection: Option<(u32, u32)>) {
+        let (Some(stats), Some(path)) = (&mut self.stats, stats::stats_path()) else {
+            return;
+        };
+        stats.record(event);
+        if let Some((w, h)) = selection {
+            stats.record_selection(w, h);
+        }
+        if let Err(e) = stats::save(&path, stats) {
+            log::warn!("write stats {}: {e}", path.display());
+        }
+    }
+
+    // 有上传 hook 运行时启动计时线程，每 UPLOAD_FRAME 推动一次托盘上传动画，全部完成后停止
+    fn watch_uploads(&mut self) {
+        if self.upload_frame.is_some() || uploads_in_progress() == 0 {
+            return;
+        }
+        self.upload_frame = Some(0);
+        self.tray.set_upload_frame(self.upload_frame);
+        let proxy = self.proxy.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(UPLOAD_FRAME);
+            let done = uploads_in_progress() == 0;
+            if proxy.send_event(UserEvent::UploadTick(done)).is_err() || done {
+                break;
+            }
+        });
+    }
+
+    // 截图完成：计数（快门声等到确认保存 / 复制时才播放，见 handle_overlay_action）
+    fn captured(&mut self) {
+        self.record_stats(StatEvent::Capture, None);
+    }
+
+    // 按 [sounds] 配置播放提示音
+    fn play_sound(&self, sound: Sound) {
+        if self
+            .config
+            .sounds
+            .as_ref()
+            .is_some_and(|s| s.enabled(sound))
+        {
+            sound::play(sound);
+        }
+    }
+
+    // 复制成功后：存在 copy hook 时才提交后台编码
+    fn after_copy(&mut self, width: u32, height: u32, rgba: Vec<u8>) {
+        self.play_sound(Sound::Copy);
+        if has_hooks(&self.config.post_capture, HookEvent::Copy) {
+            self.encoder
+                .submit(EncodePurpose::Copy, width, height, rgba);
+        }
+    }
+
+    // 托盘左键：显示 / 隐藏快捷面板（配色与缩放按当前配置）
+    fn toggle_flyout(&mut self, event_loop: &ActiveEventLoop, icon: (i32, i32, u32, u32)) {
+        if self.flyout.is_none() {
+            match Flyout::new(event_loop) {
+                Ok(fly) => self.flyout = Some(fly),
+                Err(e) => {
+                    log::warn!("create flyout: {e}");
+                    return;
+                }
+            }
+        }
+        if let Some(fly) = &mut self.flyout {
+            fly.toggle(icon, self.config.theme());
+        }
+    }
+
+    // 快捷面板按钮：面板已隐藏，等合成器移除后再截图，避免截到面板
+    fn run_flyout_action(&mut self, event_loop: &ActiveEventLoop, action: FlyoutAction) {
+        windows_util::flush_compositor();
+        match action {
+            FlyoutAction::CaptureRegion => self.start_capture(event_loop),
+            // 最上层的窗口（面板已隐藏，本程序窗口已排除）
+            FlyoutAction::CaptureWindow => match windows_util::list_top_windows().first() {
+                Some(top) => self.capture_window(event_loop, top.handle),
+                None => {
+                    std::thread::spawn(|| {
+                        windows_util::show_message("Snip Rust", "没有可截取的窗口")
+                    });
+                }
+            },
+            FlyoutAction::PinClipboard => {
+                self.handle_command(event_loop, IpcCommand::PinClipboard, None)
+            }
+            FlyoutAction::OpenSettings => open_settings(),
+        }
+    }
+
+    // Alt+PrintScreen：前台窗口矩形须在 overlay 显示（抢走前台）之前读取
+    fn start_window_capture(&mut self, event_loop: &ActiveEventLoop) {
+        if self.overlay.as_ref().is_some_and(|o| o.visible) {
+            return;
+        }
+        let rect = windows_util::foreground_window_rect();
+        self.start_capture(event_loop);
+        if let (Some(rect), Some(ov)) = (rect, &mut self.overlay) {
+            ov.preselect(rect);
+        }
+    }
+
+    // 托盘“截取窗口”：先把窗口提到前台（避免被遮挡），截取后执行 capture_action
+    fn capture_window(&mut self, event_loop: &ActiveEventLoop, handle: isize) {
+        if self.overlay.as_ref().is_some_and(|o| o.visible) {
+            return;
+        }
+        if !windows_util::bring_to_front(handle) {
+            log::debug!("window {handle:#x} not brought to front");
+        }
+        if !self.capture_window_rect(event_loop, handle, self.config.capture_action) {
+            std::thread::spawn(|| windows_util::show_message("Snip Rust", "窗口已关闭或最小化"));
+        }
+    }
+
+    // 窗口触发器：新的前台窗口本身在最上层，直接截取并执行 [window_trigger] 的 action
+    fn capture_triggered_window(&mut self, event_loop: &ActiveEventLoop, handle: isize) {
+        if self.overlay.as_ref().is_some_and(|o| o.visible) {
+            return;
+        }
+        let Some(action) = self.config.window_trigger.as_ref().map(|t| t.action) else {
+            return;
+        };
+        if !self.capture_window_rect(event_loop, handle, action) {
+            log::debug!("triggered window {handle:#x} is gone");
+        }
+    }
+
+    // 按 DWM 边框截取窗口并执行 action；窗口已关闭 / 最小化时返回 false
+    fn capture_window_rect(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        handle: isize,
+        action: CaptureAction,
+    ) -> bool {
+        let Some((x, y, width, height)) =
+            windows_util::window_rect(handle, self.config.window_shadow)
+        else {
+            return false;
+        };
+        let hidden = hide_pins_for_capture(&self.pins, self.config.exclude_pins_from_capture);
+        // 等 DWM 合成完前台切换，否则可能截到切换前的画面
+        windows_util::flush_compositor();
+        let rect = Rect {
+            x,
+            y,
+            width,
+            height,
+        };
+        let captured = capture_area_rgba(rect);
+        for &i in &hidden {
+            self.pins[i].window.set_visible(true);
+        }
+        let rgba = match captured {
+            Ok(rgba) => rgba,
+            Err(e) => {
+                log::warn!("capture window: {e}");
+                return true;
+            }
+        };
+        self.encoder.set_name_source(
+            windows_util::foreground_app_name(),
+            capture_monitor_number(),
+        );
+        self.captured();
+        self.run_capture_action(event_loop, action, rect, rgba);
+        true
+    }
+
+    // 托盘“截取整个屏幕” / 双击截图热键：截取指定显示器整屏，不进入选区模式
+    fn capture_monitor(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        number: u32,
+        action: CaptureAction,
+    ) {
+        if self.overlay.as_ref().is_some_and(|o| o.visible) {
+            return;
+        }
+        let hidden = hide_pins_for_capture(&self.pins, self.config.exclude_pins_from_capture);
+        let captured = capture_monitor(number);
+        for &i in &hidden {
+            self.pins[i].window.set_visible(true);
+        }
+        let (x, y, width, height, rgba) = match captured {
+            Ok(c) => c,
+            Err(e) => {
+                log::warn!("capture monitor: {e}");
+                return;
+            }
+        };
+        self.encoder
+            .set_name_source(windows_util::foreground_app_name(), number);
+        self.captured();
+        let rect = Rect {
+            x,
+            y,
+            width,
+            height,
+        };
+        self.run_capture_action(event_loop, action, rect, rgba);
+    }
+
+    // 按住截图热键：overlay 跟随鼠标预选窗口
+    fn start_hold_capture(&mut self, event_loop: &ActiveEventLoop) {
+        if self.overlay.as_ref().is_some_and(|o| o.visible) {
+            return;
+        }
+        self.start_capture(event_loop);
+        if let Some(ov) = self.overlay.as_mut().filter(|o| o.visible) {
+            ov.start_window_detect();
+            self.hold_since = Some(std::time::Instant::now());
+        }
+    }
+
+    // 松开截图热键：按住超过 HOLD_TAP 时按 capture_action 导出预选的窗口（同工具栏钉图 / 保存 / 复制）；
+    // 短按、期间用鼠标接管或鼠标不在任何窗口上时 overlay 保持打开，照常选区
+    fn finish_hold_capture(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(since) = self.hold_since.take() else {
+            return;
+        };
+        let Some(ov) = self.overlay.as_mut().filter(|o| o.visible) else {
+            return;
+        };
+        if !ov.stop_window_detect() || since.elapsed() < HOLD_TAP || ov.selection.is_none() {
+            return;
+        }
+        let button = match self.config.capture_action {
+            CaptureAction::Pin => ToolButton::Pin,
+            CaptureAction::Save => ToolButton::Save,
+            CaptureAction::Copy => ToolButton::Copy,
+        };
+        let action = ov.execute_tool(button);
+        self.handle_overlay_action(event_loop, action);
+    }
+
+    // 双击截图热键：鼠标所在显示器整屏，按 [double_press] action 处理
+    fn capture_cursor_monitor(&mut self, event_loop: &ActiveEventLoop) {
+        let action = self.config.double_press.unwrap_or_default().action;
+        let number = windows_util::cursor_position()
+            .and_then(|(x, y)| {
+                let monitors = list_monitors().ok()?;
+                let point = Rect {
+                    x,
+                    y,
+                    width: 1,
+                    height: 1,
+                };
+                monitor_at(&monitors, &point).map(|m| m.number)
+            })
+            .unwrap_or_else(capture_monitor_number);
+        self.capture_monitor(event_loop, number, action);
+    }
+
+    // 托盘“截取所有屏幕” / IPC capture_desktop：全部显示器按虚拟桌面布局拼成一张图
+    fn capture_desktop(&mut self, event_loop: &ActiveEventLoop) {
+        if self.overlay.as_ref().is_some_and(|o| o.visible) {
+            return;
+        }
+        let hidden = hide_pins_for_capture(&self.pins, self.config.exclude_pins_from_capture);
+        let captured = capture_virtual_desktop();
+        for &i in &hidden {
+            self.pins[i].window.set_visible(true);
+        }
+        let (rect, rgba) = match captured {
+            Ok(c) => c,
+            Err(e) => {
+                log::warn!("capture virtual desktop: {e}");
+                return;
+            }
+        };
+        self.encoder.set_name_source(
+            windows_util::foreground_app_name(),
+            capture_monitor_number(),
+        );
+        self.captured();
+        self.run_capture_action(event_loop, self.config.capture_action, rect, rgba);
+    }
+
+    // 托盘“连拍截图” / IPC burst：后台连拍（期间隐藏贴图），完成后经 UserEvent::Burst 打开 overlay
+    fn start_burst(&mut self) {
+        if self.burst_hidden.is_some() || self.overlay.as_ref().is_some_and(|o| o.visible) {
+            return;
+        }
+        self.burst_hidden = Some(hide_pins_for_capture(
+            &self.pins,
+            self.config.exclude_pins_from_capture,
+        ));
+        let config = self.config.burst.clone();
+        let proxy = self.proxy.clone();
+        std::thread::spawn(move || {
+            let result = burst::capture(&config).map(|frames| {
+                let best = burst::sharpest(&frames).unwrap_or(0);
+                (frames, best)
+            });
+            let _ = proxy.send_event(UserEvent::Burst(result.map_err(|e| e.to_string())));
+        });
+    }
+
+    // 连拍完成：恢复贴图，以最清晰的一帧打开 overlay（PageUp / PageDown 切换）
+    fn finish_burst(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        result: Result<(Vec<BurstFrame>, usize), String>,
+    ) {
+        for i in self.burst_hidden.take().unwrap_or_default() {
+            if let Some(pw) = self.pins.get(i) {
+                pw.window.set_visible(true);
+            }
+        }
+        let (frames, best) = match result {
+            Ok(r) => r,
+            Err(e) => {
+                log::warn!("burst capture: {e}");
+                return;
+            }
+        };
+        log::debug!("burst captured {} frames, sharpest {best}", frames.len());
+        if self.overlay.as_ref().is_some_and(|o| o.visible) || !self.ensure_overlay(event_loop) {
+            return;
+        }
+        self.capture_source = CaptureSource::now(
+            windows_util::foreground_app_name(),
+            capture_monitor_number(),
+        );
+        self.encoder
+            .set_name_source(self.capture_source.app.clone(), self.capture_source.monitor);
+        let Some(ov) = &mut self.overlay else {
+            return;
+        };
+        match ov.show_burst(frames, best) {
+            Ok(()) => {
+                ov.window.set_cursor(CursorIcon::Crosshair);
+                self.captured();
+            }
+            Err(e) => log::warn!("show burst: {e}"),
+        }
+    }
+
+    // 在后台线程重新枚举托盘“截取窗口”列表；上一次还没结束时不再启动
+    fn list_windows(&mut self) {
+        if self.listing_windows {
+            return;
+        }
+        self.listing_windows = true;
+        let proxy = self.proxy.clone();
+        std::thread::spawn(move || {
+            let _ = proxy.send_event(UserEvent::WindowList(windows_util::list_top_windows()));
+        });
+    }
+
+    // 按配置启动定时截图；结束（达到上限 / 停止）时经 UserEvent::TimelapseStopped 通知
+    fn start_timelapse(&mut self) {
+        let proxy = self.proxy.clone();
+        let started = Timelapse::start(self.config.timelapse.clone(), move |reason, progress| {
+            let _ = proxy.send_event(UserEvent::TimelapseStopped(reason, progress));
+        });
+        match started {
+            Ok(timelapse) => {
+                self.tray
+                    .refresh_timelapse(Some((Progress::default(), false)));
+                self.timelapse = Some(timelapse);
+            }
+            Err(e) => {
+                log::warn!("start timelapse: {e}");
+                self.tray.timelapse_item.set_checked(false);
+                let text = format!("定时截图启动失败：{e}");
+                std::thread::spawn(move || {
+                    windows_util::show_message("Snip Rust - 定时截图", &text)
+                });
+            }
+        }
+    }
+
+    // 定时截图线程结束：自动停止时取消勾选并提示原因
+    fn finish_timelapse(&mut self, reason: StopReason, progress: Progress) {
+        if reason == StopReason::Requested {
+            return;
+        }
+        self.timelapse = None;
+        self.tray.timelapse_item.set_checked(false);
+        self.tray.refresh_timelapse(None);
+        let limit = match reason {
+            StopReason::MaxCount => "已达到 max_count",
+            StopReason::Duration => "已达到 max_minutes",
+            _ => "已达到 max_mb",
+        };
+        let text = format!(
+            "定时截图已停止（{limit}）：共保存 {} 张，{:.1} MB",
+            progress.count,
+            progress.bytes as f64 / (1024.0 * 1024.0)
+        );
+        std::thread::spawn(move || windows_util::show_message("Snip Rust - 定时截图", &text));
+    }
+
+    // 不经 overlay 选区的截图：钉图 / 保存 / 复制
+    fn run_capture_action(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        action: CaptureAction,
+        rect: Rect,
+        rgba: Vec<u8>,
+    ) {
+        let (width, height) = (rect.width, rect.height);
+        let scale = self.config.export_scale;
+        let action = match action {
+            CaptureAction::Pin => OverlayAction::PasteSelection {
+                rgba,
+                width,
+                height,
+                screen_x: rect.x,
+                screen_y: rect.y,
+                refreshable: true,
+            },
+            CaptureAction::Save => {
+                let (width, height, rgba) = scale.apply(width, height, rgba);
+                OverlayAction::SaveSelection {
+                    rgba,
+                    width,
+                    height,
+                    layers: None,
+                }
+            }
+            CaptureAction::Copy => {
+                let (width, height, rgba) = scale.apply(width, height, rgba);
+                match copy_rgba(width, height, &rgba) {
+                    Ok(()) => OverlayAction::Copied {
+                        rgba,
+                        width,
+                        height,
+                    },
+                    Err(e) => {
+                        log::warn!("copy capture: {e}");
+                        return;
+                    }
+                }
+            }
+        };
+        self.handle_overlay_action(event_loop, action);
+    }
+
+    // IPC pick_region：overlay 以拾取模式显示，确认 / 取消后经 Responder 回复坐标
+    fn start_pick(&mut self, event_loop: &ActiveEventLoop, reply: Option<Responder>) {
+        if self.overlay.as_ref().is_some_and(|o| o.visible) {
+            if let Some(reply) = reply {
+                reply.err("overlay already open");
+            }
+            return;
+        }
+        self.start_capture(event_loop);
+        match self.overlay.as_mut().filter(|o| o.visible) {
+            Some(ov) => {
+                ov.set_pick_mode(true);
+                self.pick_reply = reply;
+            }
+            None => {
+                if let Some(reply) = reply {
+                    reply.err("capture failed");
+                }
+            }
+        }
+    }
+
+    // pin <file> / IPC pin_image：把 PNG 文件钉为粘贴窗口
+    fn pin_image(&mut self, event_loop: &ActiveEventLoop, path: &Path) {
+        let decoded = ImageReader::open(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|r| Ok(r.with_guessed_format()?.decode()?.to_rgba8()));
+        let img = match decoded {
+            Ok(img) => img,
+            Err(e) => {
+                log::warn!("pin image {}: {e}", path.display());
+                return;
+            }
+        };
+        let (w, h) = img.dimensions();
+        match PasteWindow::new_from_rgba(event_loop, w, h, img.as_raw(), None) {
+            Ok(pw) => self.add_pin(pw),
+            Err(e) => log::warn!("pin image: {e}"),
+        }
+    }
+
+    // --edit / IPC edit_annotations：以当前屏幕为背景打开 overlay，载入标注图层继续编辑
+    fn edit_annotations(&mut self, event_loop: &ActiveEventLoop, path: &Path) {
+        let layers = match AnnotationLayers::load(path) {
+            Ok(layers) => layers,
+            Err(e) => {
+                log::warn!("edit annotations: {e}");
+                let text = format!("无法载入标注：{e}");
+                std::thread::spawn(move || windows_util::show_message("Snip Rust", &text));
+                return;
+            }
+        };
+        if self.overlay.as_ref().is_some_and(|o| o.visible) {
+            return;
+        }
+        self.start_capture(event_loop);
+        if let Some(ov) = self.overlay.as_mut().filter(|o| o.visible) {
+            ov.load_layers(layers);
+        }
+    }
+
+    // --open / 双击 .snip / IPC open_project：overlay 显示工程中的原始截图并恢复编辑
+    fn open_project(&mut self, event_loop: &ActiveEventLoop, path: &Path) {
+        let project = match Project::load(path) {
+            Ok(project) => project,
+            Err(e) => {
+                log::warn!("open project: {e}");
+                let text = format!("无法打开工程：{e}");
+                std::thread::spawn(move || windows_util::show_message("Snip Rust", &text));
+                return;
+            }
+        };
+        if self.overlay.as_ref().is_some_and(|o| o.visible) {
+            return;
+        }
+        self.start_capture(event_loop);
+        if let Some(ov) = self.overlay.as_mut().filter(|o| o.visible) {
+            self.capture_source = project.manifest.source.clone();
+            ov.load_project(project);
+        }
+    }
+
+    // 进入 overlay 选区模式（F4 / IPC capture_region 共用）
+    fn start_capture(&mut self, event_loop: &ActiveEventLoop) {
+        // 若 overlay 已存在且当前可见，则忽略重复触发，避免多实例 / 叠加创建
+        let already_visible = self.overlay.as_ref().map(|o| o.visible).unwrap_or(false);
+        if already_visible {
+            return;
+        }
+        if !self.ensure_overlay(event_loop) {
+            return;
+        }
+        let Some(ov) = &mut self.overlay else {
+            return;
+        };
+        // overlay 尚未显示：前台仍是用户正在截取的程序
+        self.capture_source = CaptureSource::now(
+            windows_util::foreground_app_name(),
+            capture_monitor_number(),
+        );
+        self.encoder
+            .set_name_source(self.capture_source.app.clone(), self.capture_source.monitor);
+        // 实时选区：不预先截图，确认时 overlay 自行截取（选区内镂空，可操作下方程序）
+        if self.config.live_selection {
+            let target = event_loop
+                .primary_monitor()
+                .map(|m| (m.position(), m.size()));
+            if let Some((pos, size)) = target {
+                // 确认截图时由 overlay 隐藏、截完恢复；期间贴图可能被关闭，只交弱引用
+                let pins = pins_to_hide(&self.pins, self.config.exclude_pins_from_capture)
+                    .into_iter()
+                    .map(|i| Rc::downgrade(&self.pins[i].window))
+                    .collect();
+                match ov.show_live(size.width, size.height, (pos.x, pos.y), pins) {
+                    Ok(true) => {
+                        ov.window.set_cursor(CursorIcon::Crosshair);
+                        self.captured();
+                        return;
+                    }
+                    Ok(false) => log::debug!("live selection unsupported, freezing the screen"),
+                    Err(e) => log::warn!("live selection: {e}"),
+                }
+            }
+        }
+        let hidden = hide_pins_for_capture(&self.pins, self.config.exclude_pins_from_capture);
+        let mut buf = ov.take_capture_buffer();
+        let captured = capture_fullscreen_raw_into(&mut buf);
+        for &i in &hidden {
+            self.pins[i].window.set_visible(true);
+        }
+        match captured {
+            Ok((ox, oy, w, h)) => {
+                if ov.show_with_image(w, h, buf, (ox, oy)).is_ok() {
+                    ov.window.set_cursor(CursorIcon::Crosshair);
+                    self.captured();
+                }
+            }
+            Err(e) => {
+                log::warn!("capture screen: {e}");
+                if windows_util::secure_desktop_active() {
+                    std::thread::spawn(|| {
+                        windows_util::show_message(
+                            "Snip Rust",
+                            "安全桌面（UAC 提示 / 锁屏）显示期间无法截图",
+                        )
+                    });
+                }
+            }
+        }
+    }
+
+    // 首次使用时按配置创建 overlay 窗口（之后隐藏复用）；创建失败返回 false
+    fn ensure_overlay(&mut self, event_loop: &ActiveEventLoop) -> bool {
+        if self.overlay.is_none() {
+            if let Ok(mut ov) = OverlayState::new(event_loop) {
+                ov.set_low_memory(self.config.low_memory);
+                ov.set_region_layout(self.config.multi_selection_layout);
+                ov.set_auto_trim(self.config.auto_trim);
+                ov.set_corner_radius(self.config.corner_radius);
+                ov.set_export_scale(self.config.export_scale);
+                ov.set_upload_destinations(self.config.webdav.iter().map(|d| d.name.as_str()));
+                ov.set_selection_guides(self.config.selection_guides);
+                let min_size = self.config.min_selection_size.unwrap_or(MIN_RESIZE);
+                ov.set_min_selection_size(min_size);
+                ov.set_selection_grid(self.config.selection_grid);
+                ov.set_annotation_sidecar(self.config.annotation_sidecar);
+                ov.set_toolbar(self.config.toolbar_spec());
+                self.overlay = Some(ov);
+            }
+        }
+        // 高对比度 / 动画设置可能在两次截图之间改变
+        if let Some(ov) = &mut self.overlay {
+            ov.set_theme(self.config.theme());
+        }
+        self.overlay.is_some()
+    }
+
+    // 新贴图统一入口：按配置排除截图；只显示某一组时，未分组的新贴图加入该组
+    fn add_pin(&mut self, mut pw: PasteWindow) {
+        if self.config.exclude_pins_from_capture && !pw.exclude_from_capture() {
+            log::debug!("display affinity unsupported; pin will be hidden during capture");
+        }
+        pw.set_escape(self.config.pin_escape);
+        if self.config.pin_shadow && !pw.set_shadow(true) {
+            log::debug!("per-pixel alpha unsupported; pin keeps the plain border");
+        }
+        pw.set_ui_scale(self.config.ui_scale());
+        pw.set_groups(&self.config.pin_group_names());
+        if pw.group().is_none() && self.shown_group.is_some() {
+            pw.set_group(self.shown_group.clone());
+        }
+        pw.window.set_visible(
+            pin_in_view(self.shown_group.as_deref(), pw.group())
+                && !self.fullscreen_paused(|c| c.hide_pins),
+        );
+        self.pins.push(pw);
+    }
+
+    // 按托盘“贴图分组”的选择显示 / 隐藏贴图（Esc 隐藏的保持隐藏，全屏程序在前台时全部隐藏）
+    fn apply_pin_visibility(&self) {
+        let paused = self.fullscreen_paused(|c| c.hide_pins);
+        for pw in &self.pins {
+            let visible = pin_in_view(self.shown_group.as_deref(), pw.group());
+            pw.window
+                .set_visible(visible && !pw.is_user_hidden() && !paused);
+        }
+    }
+
+    // 全屏程序在前台且 [fullscreen] 开启了对应选项
+    fn fullscreen_paused(&self, option: impl Fn(&FullscreenConfig) -> bool) -> bool {
+        self.fullscreen && self.config.fullscreen.as_ref().is_some_and(option)
+    }
+
+    // 关闭贴图：快照放入“恢复关闭的贴图”缓存（最多 CLOSED_PIN_CACHE 个），drop 即释放窗口与 surface
+    fn close_pin(&mut self, i: usize) {
+        let pw = self.pins.remove(i);
+        if self.closed_pins.len() >= CLOSED_PIN_CACHE {
+            self.closed_pins.remove(0);
+        }
+        self.closed_pins.push((pw.snapshot(), pw.source()));
+    }
+
+    // Ctrl+Z / 托盘“恢复关闭的贴图”：按原位置重建最近关闭的贴图
+    fn restore_closed_pin(&mut self, event_loop: &ActiveEventLoop) {
+        let Some((pin, source)) = self.closed_pins.pop() else {
+            log::debug!("no closed pin to restore");
+            return;
+        };
+        if self.open_pins(event_loop, vec![pin]) == 1 {
+            if let (Some(rect), Some(pw)) = (source, self.pins.last_mut()) {
+                pw.set_source(rect);
+            }
+        }
+    }
+
+    // 崩溃恢复 / 工作区的贴图：按原位置与分组重新创建，返回成功数
+    fn open_pins(&mut self, event_loop: &ActiveEventLoop, pins: Vec<RecoveredPin>) -> usize {
+        let mut opened = 0;
+        for pin in pins {
+            match PasteWindow::new_from_rgba(
+                event_loop,
+                pin.width,
+                pin.height,
+                &pin.rgba,
+                Some(pin.pos),
+            ) {
+                Ok(mut pw) => {
+                    pw.set_group(pin.group);
+                    pw.set_tag(pin.tag);
+                    self.add_pin(pw);
+                    opened += 1;
+                }
+                Err(e) => log::warn!("restore pin: {e}"),
+            }
+        }
+        opened
+    }
+
+    // 托盘“以管理员重启”：贴图存入会话目录，启动提升权限的新进程后退出；
+    // 新进程等本进程释放单实例互斥体，启动后恢复贴图。UAC 提示被拒绝时继续运行
+    fn relaunch_elevated(&mut self, event_loop: &ActiveEventLoop) {
+        match crash::save_session() {
+            Ok(count) => log::info!("saved {count} pins for elevated relaunch"),
+            Err(e) => {
+                log::warn!("save session: {e}");
+                let text = format!("无法保存贴图，未重启：{e}");
+                std::thread::spawn(move || windows_util::show_message("Snip Rust", &text));
+                return;
+            }
+        }
+        match windows_util::relaunch_elevated(crate::cli::RELAUNCHED_FLAG) {
+            Ok(()) => event_loop.exit(),
+            Err(e) => {
+                log::warn!("relaunch elevated: {e}");
+                crash::discard_session();
+            }
+        }
+    }
+
+    // 以管理员重启前保存的贴图，静默恢复
+    fn restore_session_pins(&mut self, event_loop: &ActiveEventLoop) {
+        let saved = crash::take_session_pins();
+        if saved.is_empty() {
+            return;
+        }
+        let total = saved.len();
+        let restored = self.open_pins(event_loop, saved);
+        log::info!("restored {restored}/{total} pins after relaunch");
+    }
+
+    // 恢复目录读取后即删除，resumed 再次触发时为空
+    fn restore_crashed_pins(&mut self, event_loop: &ActiveEventLoop) {
+        let recovered = crash::take_recovered_pins();
+        if recovered.is_empty() {
+            return;
+        }
+        let total = recovered.len();
+        let restored = self.open_pins(event_loop, recovered);
+        log::info!("restored {restored}/{total} pins after crash");
+        let text = format!(
+            "上次异常退出，已恢复 {restored} 个贴图\n崩溃日志位于 {}",
+            paths::data_dir()
+                .map(|d| d.display().to_string())
+                .unwrap_or_default()
+        );
+        std::thread::spawn(move || windows_util::show_message("Snip Rust", &text));
+    }
+
+    // 优化编码进度：多个任务同时进行时显示平均进度
+    fn show_encode_progress(&mut self, event_loop: &ActiveEventLoop, id: JobId, progress: f32) {
+        match self.encode_progress.iter_mut().find(|(job, _)| *job == id) {
+            Some(entry) => entry.1 = progress,
+            None => self.encode_progress.push((id, progress)),
+        }
+        if self.toast.is_none() {
+            match ProgressToast::new(event_loop) {
+                Ok(toast) => self.toast = Some(toast),
+                Err(e) => {
+                    log::warn!("create progress toast: {e}");
+                    return;
+                }
+            }
+        }
+        let total = self.encode_progress.iter().map(|(_, f)| f).sum::<f32>()
+            / self.encode_progress.len() as f32;
+        if let Some(toast) = &mut self.toast {
+            toast.show("正在优化 PNG", total, self.config.theme());
+        }
+    }
+
+    // 后台编码结果：保存成功后触发 save hook，copy 编码直接交给 copy hook
+    fn finish_encode(&mut self, done: EncodeDone) {
+        self.encode_progress.retain(|(id, _)| *id != done.id);
+        if self.encode_progress.is_empty() {
+            if let Some(toast) = &mut self.toast {
+                toast.hide();
+            }
+        }
+        let layers = self
+            .pending_layers
+            .iter()
+            .position(|(id, _)| *id == done.id)
+            .map(|i| self.pending_layers.swap_remove(i).1);
+        let share_anchor = self
+            .pending_shares
+            .iter()
+            .position(|(id, _)| *id == done.id)
+            .and_then(|i| self.pending_shares.swap_remove(i).1);
+        let upload_to = self
+            .pending_uploads
+            .iter()
+            .position(|(id, _)| *id == done.id)
+            .map(|i| self.pending_uploads.swap_remove(i).1);
+        let out = match done.result {
+            Ok(out) => out,
+            Err(e) => {
+                log::warn!("{:?} job #{} failed: {e}", done.purpose, done.id);
+                return;
+            }
+        };
+        // 底图编码较慢，放到独立线程
+        if let (Some(layers), Some(path)) = (layers, out.path.clone()) {
+            std::thread::spawn(move || match layers.write(&path) {
+                Ok(sidecar) => log::debug!("annotation sidecar: {}", sidecar.display()),
+                Err(e) => log::warn!("annotation sidecar: {e}"),
+            });
+        }
+        let event = match done.purpose {
+            EncodePurpose::Save => {
+                if out.path.is_some() {
+                    self.play_sound(Sound::Save);
+                }
+                HookEvent::Save
+            }
+            EncodePurpose::Copy => HookEvent::Copy,
+            // 文本剪贴板，不触发图像 copy hook
+            EncodePurpose::DataUri => {
+                match copy_text(&png_data_uri(&out.png)) {
+                    Ok(()) => self.play_sound(Sound::Copy),
+                    Err(e) => log::warn!("copy data uri failed: {e}"),
+                }
+                return;
+            }
+            // 不属于保存 / 复制，不触发 hook
+            EncodePurpose::Edit => {
+                match self.temp_files.write(&out.png, &out.name) {
+                    Ok(path) => {
+                        let editor = self.config.image_editor.clone();
+                        std::thread::spawn(move || {
+                            if let Err(e) = open_with::launch(&path, editor.as_deref()) {
+                                log::warn!("open with editor: {e}");
+                            }
+                        });
+                    }
+                    Err(e) => log::warn!("write temp file: {e}"),
+                }
+                return;
+            }
+            EncodePurpose::Share => {
+                match self.temp_files.write(&out.png, &out.name) {
+                    Ok(path) => self.share_file(share_anchor, path),
+                    Err(e) => log::warn!("write temp file: {e}"),
+                }
+                return;
+            }
+            EncodePurpose::Upload => {
+                match upload_to.and_then(|i| self.config.webdav.get(i)) {
+                    Some(dest) => {
+                        webdav::spawn_upload(dest.clone(), out.name, out.png);
+                        self.watch_uploads();
+                    }
+                    None => log::warn!("upload destination removed from config"),
+                }
+                return;
+            }
+        };
+        run_post_capture(
+            &self.config.post_capture,
+            event,
+            &HookContext {
+                path: out.path.as_deref(),
+                width: done.width,
+                height: done.height,
+                png: &out.png,
+                name: &out.name,
+            },
+        );
+        self.watch_uploads();
+    }
+
+    // 分享面板需在事件循环线程打开；失败或配置为 mail 时在后台线程弹出邮件窗口
+    fn share_file(&self, anchor: Option<isize>, path: PathBuf) {
+        if self.config.share == ShareMethod::Sheet {
+            match anchor.map(|handle| share::share_sheet(handle, &path)) {
+                Some(Ok(())) => return,
+                Some(Err(e)) => log::warn!("share sheet: {e}; falling back to mail"),
+                None => {}
+            }
+        }
+        std::thread::spawn(move || {
+            if let Err(e) = share::send_mail(&path) {
+                log::warn!("share by mail: {e}");
+            }
+        });
+    }
+
+    fn is_overlay(&self, window_id: WindowId) -> bool {
+        self.overlay
+            .as_ref()
+            .map(|ov| ov.window.id() == window_id)
+            .unwrap_or(false)
+    }
+}
+
+impl ApplicationHandler<UserEvent> for App {
+    // 窗口按需创建（F4 / IPC）；启动时只恢复上次崩溃前抢救的贴图
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        self.restore_session_pins(event_loop);
+        self.restore_crashed_pins(event_loop);
+    }
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: UserEvent) {
+        match event {
+            UserEvent::Menu(id) => self.handle_menu(event_loop, id),
+            UserEvent::TrayHover => {
+                self.list_windows();
+                self.tray.refresh_monitors();
+                self.tray.refresh_uploads();
+                self.tray.refresh_workspaces();
+                let status = self.timelapse.as_ref().map(|t| {
+                    let control = t.control();
+                    (control.progress(), control.is_paused())
+                });
+                self.tray.refresh_timelapse(status);
+            }
+            UserEvent::WindowList(windows) => {
+                self.listing_windows = false;
+                self.tray.set_windows(windows);
+            }
+            UserEvent::TrayClick(rect) => self.toggle_flyout(event_loop, rect),
+            // 热键：进入 overlay 选区模式
+            UserEvent::Hotkey
+            | UserEvent::HotkeyWindow
+            | UserEvent::HotkeyFullscreen
+            | UserEvent::HotkeyHold(true)
+                if self.fullscreen_paused(|c| c.suppress_hotkey) =>
+            {
+                log::debug!("fullscreen app in foreground: hotkey ignored");
+            }
+            UserEvent::Hotkey => self.start_capture(event_loop),
+            UserEvent::HotkeyWindow => self.start_window_capture(event_loop),
+            UserEvent::HotkeyFullscreen => self.capture_cursor_monitor(event_loop),
+            UserEvent::HotkeyHold(true) => self.start_hold_capture(event_loop),
+            UserEvent::HotkeyHold(false) => self.finish_hold_capture(event_loop),
+            UserEvent::Ipc(cmd, reply) => self.handle_command(event_loop, cmd, reply),
+            // 后台编码完成：写文件结果 / copy hook 回到主线程处理
+            UserEvent::Encoded => {
+                while let Some(done) = self.encoder.try_recv() {
+                    self.finish_encode(done);
+                }
+            }
+            UserEvent::EncodeProgress(id, progress) => {
+                self.show_encode_progress(event_loop, id, progress)
+            }
+            UserEvent::Sensitive(session, result) => self.finish_sensitive(session, result),
+            UserEvent::ElevatedForeground => {
+                log::info!("elevated window in foreground");
+                let proxy = self.proxy.clone();
+                std::thread::spawn(move || {
+                    if windows_util::confirm("Snip Rust", ELEVATED_NOTICE) {
+                        let _ = proxy.send_event(UserEvent::RelaunchElevated);
+                    }
+                });
+            }
+            UserEvent::RelaunchElevated => self.relaunch_elevated(event_loop),
+            UserEvent::UploadTick(done) => {
+                self.upload_frame = match (done, self.upload_frame) {
+                    (true, _) => None,
+                    (false, frame) => Some(frame.map_or(0, |f| f + 1)),
+                };
+                self.tray.set_upload_frame(self.upload_frame);
+                // 计时线程结束前又开始了新的上传
+                self.watch_uploads();
+            }
+            UserEvent::Fullscreen(active) => {
+                log::info!(
+                    "fullscreen app {}",
+                    if active { "started" } else { "ended" }
+                );
+                self.fullscreen = active;
+                self.apply_pin_visibility();
+            }
+            UserEvent::Burst(result) => self.finish_burst(event_loop, result),
+            UserEvent::WindowChanged(handle) => self.capture_triggered_window(event_loop, handle),
+            UserEvent::TimelapseStopped(reason, progress) => {
+                self.finish_timelapse(reason, progress)
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        match event {
+            WindowEvent::RedrawRequested => {
+                if let Some(fly) = self.flyout.as_mut().filter(|f| f.window.id() == window_id) {
+                    fly.redraw();
+                }
+                if let Some(toast) = self.toast.as_mut().filter(|t| t.window.id() == window_id) {
+                    toast.redraw();
+                }
+                if let Some(ov) = &mut self.overlay {
+                    if window_id == ov.window.id() {
+                        ov.redraw();
+                    }
+                }
+                for pw in self.pins.iter_mut() {
+                    pw.redraw(window_id);
+                }
+            }
+            WindowEvent::CloseRequested => {
+                // 关闭 paste window（overlay 关闭请求忽略：由 ESC / 工具栏退出）
+                if let Some(i) = self.pins.iter().position(|pw| pw.window.id() == window_id) {
+                    self.close_pin(i);
+                }
+            }
+            WindowEvent::Resized(_) => {
+                if let Some(ov) = &self.overlay {
+                    if window_id == ov.window.id() {
+                        ov.window.request_redraw();
+                    }
+                }
+                for pw in &mut self.pins {
+                    if pw.window.id() == window_id {
+                        pw.redraw(window_id);
+                    }
+                }
+            }
+            event => {
+                let flyout = self.flyout.as_mut().filter(|f| f.window.id() == window_id);
+                if let Some(action) = flyout.and_then(|f| f.handle_event(&event)) {
+                    self.run_flyout_action(event_loop, action);
+                    return;
+                }
+                if self.is_overlay(window_id) {
+                    let action = match &mut self.overlay {
+                        Some(ov) => ov.handle_event(&event),
+                        None => OverlayAction::None,
+                    };
+                    self.handle_overlay_action(event_loop, action);
+                }
+                for pw in &mut self.pins {
+                    if pw.window.id() == window_id {
+                        pw.handle_event(&event);
+                    }
+                }
+            }
+        }
+    }
+
+    // 所有退出路径（托盘退出 / IPC quit）都经 event_loop.exit() 到这里：
+    // 注销热键（恢复 PrintScreen 设置） -> 等待进行中的保存 -> 清理未编辑的临时文件 -> 销毁 overlay 与贴图 -> 移除托盘图标
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(mut hotkeys) = self.hotkeys.take() {
+            hotkeys.unregister_all();
+        }
+        restore_print_screen_snipping();
+        if let Some(mut timelapse) = self.timelapse.take() {
+            timelapse.stop();
+        }
+        for done in self.encoder.drain(EXIT_ENCODE_WAIT) {
+            self.finish_encode(done);
+        }
+        self.temp_files.cleanup();
+        if let Some(mut ov) = self.overlay.take() {
+            ov.hide();
+        }
+        self.pins.clear();
+        self.tray.remove_icon();
+        log::debug!("shutdown complete");
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // 贴图只在 RedrawRequested 时重绘（状态变化处自行 request_redraw）
+        let mut restore = false;
+        for pw in self.pins.iter_mut() {
+            restore |= pw.take_restore_request();
+        }
+        // 回收 ESC 标记待销毁窗口（drop 即释放）
+        while let Some(i) = self.pins.iter().position(|pw| pw.is_pending_destroy()) {
+            self.close_pin(i);
+        }
+        if restore {
+            self.restore_closed_pin(event_loop);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_menu() {
+        let quit = MenuId::new("quit");
+        let auto = MenuId::new("autostart");
+        let text_qr = MenuId::new("text_qr");
+        let pause = MenuId::new("pause_hotkeys");
+        let open_log = MenuId::new("open_log");
+        let open_data = MenuId::new("open_data");
+        let stats = MenuId::new("stats");
+        let windows = [(MenuId::new("w0"), 0x1234), (MenuId::new("w1"), 0x5678)];
+        let monitors = [(MenuId::new("mon2"), 2)];
+        let desktop = MenuId::new("desktop");
+        let burst = MenuId::new("burst");
+        let timelapse = MenuId::new("timelapse");
+        let timelapse_pause = MenuId::new("timelapse_pause");
+        let uploads = [
+            (MenuId::new("up0"), 7, false),
+            (MenuId::new("up0_rm"), 7, true),
+        ];
+        let pin_groups = [(MenuId::new("g_all"), None), (MenuId::new("g1"), Some(1))];
+        let save_workspace = MenuId::new("save_workspace");
+        let workspaces = [(MenuId::new("ws0"), "2026-03-05 09-07-01".to_string())];
+        let restore_pin = MenuId::new("restore_pin");
+        let relaunch = MenuId::new("relaunch");
+        let overlay = OverlayMenuIds::new(vec![
+            (MenuId::new("overlay_uri"), ToolButton::DataUri),
+            (MenuId::new("overlay_qr"), ToolButton::DecodeQr),
+            (MenuId::new("overlay_trim"), ToolButton::AutoTrim),
+            (MenuId::new("overlay_redact"), ToolButton::Redact),
+            (MenuId::new("overlay_save"), ToolButton::Save),
+        ]);
+        let ids = MenuIds {
+            quit: &quit,
+            autostart: &auto,
+            text_qr: &text_qr,
+            pause_hotkeys: &pause,
+            open_log: &open_log,
+            open_data: &open_data,
+            stats: &stats,
+            windows: &windows,
+            monitors: &monitors,
+            desktop: &desktop,
+            burst: &burst,
+            timelapse: &timelapse,
+            timelapse_pause: &timelapse_pause,
+            uploads: &uploads,
+            pin_groups: &pin_groups,
+            save_workspace: &save_workspace,
+            workspaces: &workspaces,
+            restore_pin: &restore_pin,
+            relaunch: &relaunch,
+            overlay: Some(&overlay),
+        };
+        let pin = |i: usize| PinMenuIds {
+            copy: MenuId::new(format!("c{i}")),
+            data_uri: MenuId::new(format!("u{i}")),
+            copy_rect: MenuId::new(format!("cr{i}")),
+            paste_rect: MenuId::new(format!("pr{i}")),
+            open_with: MenuId::new(format!("e{i}")),
+            share: MenuId::new(format!("x{i}")),
+            refresh: MenuId::new(format!("r{i}")),
+            groups: (0..3).map(|g| MenuId::new(format!("g{g}_{i}"))).collect(),
+            tags: std::array::from_fn(|t| MenuId::new(format!("t{t}_{i}"))),
+            select: MenuId::new(format!("s{i}")),
+            compare: MenuId::new(format!("m{i}")),
+            stitch_pin: ["h", "v", "g"].map(|l| MenuId::new(format!("p{l}{i}"))),
+            stitch_save: ["h", "v", "g"].map(|l| MenuId::new(format!("s{l}{i}"))),
+            destroy: MenuId::new(format!("d{i}")),
+        };
+        let pins = [pin(0), pin(1)];
+        let resolve = |id: &str| resolve_menu(&MenuId::new(id), &ids, &pins);
+        assert_eq!(resolve("quit"), Some(MenuCommand::Quit));
+        assert_eq!(resolve("autostart"), Some(MenuCommand::ToggleAutostart));
+        assert_eq!(resolve("text_qr"), Some(MenuCommand::TextToQr));
+        assert_eq!(
+            resolve("pause_hotkeys"),
+            Some(MenuCommand::TogglePauseHotkeys)
+        );
+        assert_eq!(resolve("open_log"), Some(MenuCommand::OpenLog));
+        assert_eq!(resolve("open_data"), Some(MenuCommand::OpenDataDir));
+        assert_eq!(resolve("stats"), Some(MenuCommand::ShowStats));
+        assert_eq!(resolve("w1"), Some(MenuCommand::CaptureWindow(0x5678)));
+        assert_eq!(resolve("mon2"), Some(MenuCommand::CaptureMonitor(2)));
+        assert_eq!(resolve("desktop"), Some(MenuCommand::CaptureDesktop));
+        assert_eq!(resolve("burst"), Some(MenuCommand::BurstCapture));
+        assert_eq!(resolve("timelapse"), Some(MenuCommand::ToggleTimelapse));
+        assert_eq!(
+            resolve("timelapse_pause"),
+            Some(MenuCommand::ToggleTimelapsePause)
+        );
+        assert_eq!(resolve("up0"), Some(MenuCommand::CopyUploadUrl(7)));
+        assert_eq!(resolve("up0_rm"), Some(MenuCommand::DeleteUpload(7)));
+        assert_eq!(resolve("c1"), Some(MenuCommand::CopyPin(1)));
+        assert_eq!(resolve("d0"), Some(MenuCommand::DestroyPin(0)));
+        assert_eq!(resolve("u1"), Some(MenuCommand::CopyPinDataUri(1)));
+        assert_eq!(resolve("cr0"), Some(MenuCommand::CopyPinRect(0)));
+        assert_eq!(resolve("pr1"), Some(MenuCommand::PastePinRect(1)));
+        assert_eq!(resolve("e0"), Some(MenuCommand::OpenPinWith(0)));
+        assert_eq!(resolve("r1"), Some(MenuCommand::RefreshPin(1)));
+        assert_eq!(resolve("g0_1"), Some(MenuCommand::SetPinGroup(1, None)));
+        assert_eq!(resolve("g2_0"), Some(MenuCommand::SetPinGroup(0, Some(1))));
+        assert_eq!(resolve("t0_0"), Some(MenuCommand::SetPinTag(0, None)));
+        assert_eq!(
+            resolve("t3_1"),
+            Some(MenuCommand::SetPinTag(1, Some(PinTag::Green)))
+        );
+        assert_eq!(resolve("g_all"), Some(MenuCommand::ShowPinGroup(None)));
+        assert_eq!(resolve("g1"), Some(MenuCommand::ShowPinGroup(Some(1))));
+        assert_eq!(resolve("save_workspace"), Some(MenuCommand::SaveWorkspace));
+        assert_eq!(resolve("restore_pin"), Some(MenuCommand::RestoreClosedPin));
+        assert_eq!(resolve("relaunch"), Some(MenuCommand::RelaunchElevated));
+        assert_eq!(
+            resolve("ws0"),
+            Some(MenuCommand::OpenWorkspace("2026-03-05 09-07-01".into()))
+        );
+        assert!(pin_in_view(None, Some("分组 1")));
+        assert!(pin_in_view(Some("分组 1"), Some("分组 1")));
+        assert!(!pin_in_view(Some("分组 1"), None));
+        assert_eq!(resolve("x1"), Some(MenuCommand::SharePin(1)));
+        assert_eq!(resolve("s0"), Some(MenuCommand::SelectPin(0)));
+        assert_eq!(resolve("m1"), Some(MenuCommand::ComparePins));
+        assert_eq!(
+            resolve("pv0"),
+            Some(MenuCommand::StitchPins {
+                layout: StitchLayout::Vertical,
+                save: false
+            })
+        );
+        assert_eq!(
+            resolve("sg1"),
+            Some(MenuCommand::StitchPins {
+                layout: StitchLayout::Grid,
+                save: true
+            })
+        );
+        let tool = |b| Some(MenuCommand::OverlayTool(b));
+        assert_eq!(resolve("overlay_uri"), tool(ToolButton::DataUri));
+        assert_eq!(resolve("overlay_qr"), tool(ToolButton::DecodeQr));
+        assert_eq!(resolve("overlay_trim"), tool(ToolButton::AutoTrim));
+        assert_eq!(resolve("overlay_redact"), tool(ToolButton::Redact));
+        assert_eq!(resolve("overlay_save"), tool(ToolButton::Save));
+        assert_eq!(resolve("other"), None);
+    }
+
+    #[test]
+    fn test_window_menu_label() {
+        assert_eq!(
+            window_menu_label("README.md - Notepad"),
+            "README.md - Notepad"
+        );
+        assert_eq!(window_menu_label("Tom & Jerry"), "Tom && Jerry");
+        let long = "标".repeat(WINDOW_TITLE_MAX + 5);
+        let label = window_menu_label(&long);
+        assert_eq!(label.chars().count(), WINDOW_TITLE_MAX + 1);
+        assert!(label.ends_with('…'));
+    }
+
+    #[test]
+    fn test_monitor_menu_label() {
+        let monitor = |number, is_primary| Monitor {
+            number,
+            bounds: Rect {
+                x: -1920,
+                y: 0,
+                width: 1920,
+                height: 1080,
+            },
+            is_primary,
+            dpi: 96,
+        };
+        assert_eq!(
+            monitor_menu_label(&monitor(1, true)),
+            "&1  屏幕 1（主屏）  1920×1080"
+        );
+        assert_eq!(
+            monitor_menu_label(&monitor(2, false)),
+            "&2  屏幕 2  1920×1080"
+        );
+        assert_eq!(
+            monitor_menu_label(&monitor(10, false)),
+            "屏幕 10  1920×1080"
+        );
+    }
+}
@@ -0,0 +1,165 @@
+// autostart: 登录时自动启动
+//
+// Windows：HKCU\Software\Microsoft\Windows\CurrentVersion\Run 下的 SnipRust 值
+// Linux：$XDG_CONFIG_HOME/autostart/snip_rust.desktop
+// macOS：~/Library/LaunchAgents/com.snip_rust.plist
+
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+fn current_exe() -> Result<PathBuf> {
+    std::env::current_exe().map_err(|e| anyhow!("locate executable: {e}"))
+}
+
+/// 按布尔值启用 / 禁用
+pub fn set(enabled: bool) -> Result<()> {
+    if enabled {
+        enable()
+    } else {
+        disable()
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::*;
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::{ERROR_FILE_NOT_FOUND, ERROR_SUCCESS};
+    use windows::Win32::System::Registry::{
+        RegDeleteKeyValueW, RegGetValueW, RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ, RRF_RT_REG_SZ,
+    };
+
+    const RUN_KEY: PCWSTR = w!("Software\\Microsoft\\Windows\\CurrentVersion\\Run");
+    const VALUE_NAME: PCWSTR = w!("SnipRust");
+
+    pub fn enable() -> Result<()> {
+        let cmd = format!("\"{}\"", current_exe()?.display());
+        let wide: Vec<u16> = cmd.encode_utf16().chain(std::iter::once(0)).collect();
+        let err = unsafe {
+            RegSetKeyValueW(
+                HKEY_CURRENT_USER,
+                RUN_KEY,
+                VALUE_NAME,
+                REG_SZ.0,
+                Some(wide.as_ptr() as *const _),
+                (wide.len() * 2) as u32,
+            )
+        };
+        if err != ERROR_SUCCESS {
+            return Err(anyhow!("set run key failed: {err:?}"));
+        }
+        Ok(())
+    }
+
+    pub fn disable() -> Result<()> {
+        let err = unsafe { RegDeleteKeyValueW(HKEY_CURRENT_USER, RUN_KEY, VALUE_NAME) };
+        if err != ERROR_SUCCESS && err != ERROR_FILE_NOT_FOUND {
+            return Err(anyhow!("delete run key failed: {err:?}"));
+        }
+        Ok(())
+    }
+
+    pub fn is_enabled() -> bool {
+        let err = unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                RUN_KEY,
+                VALUE_NAME,
+                RRF_RT_REG_SZ,
+                None,
+                None,
+                None,
+            )
+        };
+        err == ERROR_SUCCESS
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+
+    fn entry_path() -> Result<PathBuf> {
+        dirs::config_dir()
+            .map(|d| d.join("autostart").join("snip_rust.desktop"))
+            .ok_or_else(|| anyhow!("no config dir"))
+    }
+
+    pub fn enable() -> Result<()> {
+        let path = entry_path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| anyhow!("create autostart dir: {e}"))?;
+        }
+        let entry = format!(
+            "[Desktop Entry]\nType=Application\nName=Snip Rust\nExec=\"{}\"\nX-GNOME-Autostart-enabled=true\n",
+            current_exe()?.display()
+        );
+        std::fs::write(&path, entry).map_err(|e| anyhow!("write {}: {e}", path.display()))
+    }
+
+    pub fn disable() -> Result<()> {
+        let path = entry_path()?;
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(anyhow!("remove {}: {e}", path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn is_enabled() -> bool {
+        entry_path().map(|p| p.exists()).unwrap_or(false)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::*;
+
+    fn agent_path() -> Result<PathBuf> {
+        dirs::home_dir()
+            .map(|d| {
+                d.join("Library")
+                    .join("LaunchAgents")
+                    .join("com.snip_rust.plist")
+            })
+            .ok_or_else(|| anyhow!("no home dir"))
+    }
+
+    pub fn enable() -> Result<()> {
+        let path = agent_path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| anyhow!("create launch agents dir: {e}"))?;
+        }
+        let plist = format!(
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" ",
+                "\"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n",
+                "<plist version=\"1.0\"><dict>\n",
+                "<key>Label</key><string>com.snip_rust</string>\n",
+                "<key>ProgramArguments</key><array><string>{}</string></array>\n",
+                "<key>RunAtLoad</key><true/>\n",
+                "</dict></plist>\n"
+            ),
+            current_exe()?.display()
+        );
+        std::fs::write(&path, plist).map_err(|e| anyhow!("write {}: {e}", path.display()))
+    }
+
+    pub fn disable() -> Result<()> {
+        let path = agent_path()?;
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(anyhow!("remove {}: {e}", path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn is_enabled() -> bool {
+        agent_path().map(|p| p.exists()).unwrap_or(false)
+    }
+}
+
+pub use platform::{disable, enable, is_enabled};
@@ -0,0 +1,191 @@
+// burst: 连拍——在 span_ms 内按固定节拍连续截取 frames 帧主屏，用于抓住一闪而过的提示框 / 动画
+//
+// 托盘“连拍截图”或 IPC burst 在后台线程调用 capture，用 sharpest 挑出最清晰的一帧，
+// 回到主循环后 overlay 以该帧打开，顶部缩略图条单击或 PageUp / PageDown 在各帧之间切换后再选区。
+// 每帧都是整屏 RGBA（4K 约 33 MB）：连拍过程中只保留最清晰的 KEEP_FRAMES 帧，
+// 拍满 MAX_FRAMES 帧时同时持有的也不超过 KEEP_FRAMES + 1 帧。
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+use crate::capture;
+use crate::filters::luma;
+
+/// 帧数上限
+pub const MAX_FRAMES: u32 = 20;
+/// 连拍结束后保留的整帧数（按清晰度挑选，保持拍摄顺序）
+pub const KEEP_FRAMES: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct BurstConfig {
+    /// 帧数（2..=MAX_FRAMES）
+    pub frames: u32,
+    /// 第一帧到最后一帧的时长（毫秒）
+    pub span_ms: u64,
+    /// 开始前的等待（毫秒），留时间让鼠标回到目标上触发提示框
+    pub delay_ms: u64,
+}
+
+impl Default for BurstConfig {
+    fn default() -> Self {
+        Self {
+            frames: 8,
+            span_ms: 1000,
+            delay_ms: 0,
+        }
+    }
+}
+
+impl BurstConfig {
+    pub fn frame_count(&self) -> u32 {
+        self.frames.clamp(2, MAX_FRAMES)
+    }
+
+    /// 相邻两帧的间隔
+    pub fn frame_interval(&self) -> Duration {
+        Duration::from_millis(self.span_ms) / (self.frame_count() - 1)
+    }
+}
+
+/// 一帧截图：显示器原点 + RGBA
+#[derive(Debug, Clone)]
+pub struct BurstFrame {
+    pub origin: (i32, i32),
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+/// 按配置连拍主屏（阻塞，请在后台线程调用）；单帧失败时跳过，全部失败时返回最后一个错误。
+/// 返回最清晰的至多 KEEP_FRAMES 帧（拍摄顺序）
+pub fn capture(config: &BurstConfig) -> Result<Vec<BurstFrame>> {
+    std::thread::sleep(Duration::from_millis(config.delay_ms));
+    let start = Instant::now();
+    let interval = config.frame_interval();
+    let mut frames = Vec::new();
+    let mut scores = Vec::new();
+    let mut last_err = None;
+    for i in 0..config.frame_count() {
+        // 固定节拍：截图耗时不累积
+        std::thread::sleep((interval * i).saturating_sub(start.elapsed()));
+        match capture::capture_fullscreen_raw_with_origin() {
+            Ok((x, y, width, height, rgba)) => {
+                scores.push(sharpness(width, height, &rgba));
+                frames.push(BurstFrame {
+                    origin: (x, y),
+                    width,
+                    height,
+                    rgba,
+                });
+            }
+            Err(e) => last_err = Some(e),
+        }
+        // 超出保留帧数时立即丢掉最模糊的一帧，避免 20 帧整屏同时驻留内存
+        if frames.len() > KEEP_FRAMES {
+            if let Some(worst) = blurriest(&scores) {
+                frames.remove(worst);
+                scores.remove(worst);
+            }
+        }
+    }
+    match (frames.is_empty(), last_err) {
+        (true, Some(e)) => Err(e),
+        (true, None) => Err(anyhow!("burst captured no frames")),
+        _ => Ok(frames),
+    }
+}
+
+/// 清晰度：相邻像素亮度差的平方均值（每隔 2 像素采样）；运动模糊 / 过渡帧数值更低
+pub fn sharpness(width: u32, height: u32, rgba: &[u8]) -> f64 {
+    let (w, h) = (width as usize, height as usize);
+    if w < 2 || h < 2 || rgba.len() < w * h * 4 {
+        return 0.0;
+    }
+    let luma_at = |x: usize, y: usize| {
+        let i = (y * w + x) * 4;
+        luma(rgba[i], rgba[i + 1], rgba[i + 2]) as i32
+    };
+    let (mut sum, mut n) = (0u64, 0u64);
+    for y in (0..h - 1).step_by(2) {
+        for x in (0..w - 1).step_by(2) {
+            let c = luma_at(x, y);
+            let dx = luma_at(x + 1, y) - c;
+            let dy = luma_at(x, y + 1) - c;
+            sum += (dx * dx + dy * dy) as u64;
+            n += 1;
+        }
+    }
+    sum as f64 / n as f64
+}
+
+/// 最模糊的一帧（相同时取靠后的，与 sharpest 相反）
+fn blurriest(scores: &[f64]) -> Option<usize> {
+    (0..scores.len()).reduce(|worst, i| if scores[i] <= scores[worst] { i } else { worst })
+}
+
+/// 最清晰的一帧（相同时取靠前的）
+pub fn sharpest(frames: &[BurstFrame]) -> Option<usize> {
+    let scores: Vec<f64> = frames
+        .iter()
+        .map(|f| sharpness(f.width, f.height, &f.rgba))
+        .collect();
+    (0..scores.len()).reduce(|best, i| if scores[i] > scores[best] { i } else { best })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(rgba: Vec<u8>) -> BurstFrame {
+        BurstFrame {
+            origin: (0, 0),
+            width: 4,
+            height: 4,
+            rgba,
+        }
+    }
+
+    #[test]
+    fn test_sharpest_prefers_edges() {
+        let flat = vec![128u8; 4 * 4 * 4];
+        // 黑白相间的竖条
+        let stripes: Vec<u8> = (0..16)
+            .flat_map(|i| {
+                let v = if i % 2 == 0 { 0 } else { 255 };
+                [v, v, v, 255]
+            })
+            .collect();
+        assert_eq!(sharpness(4, 4, &flat), 0.0);
+        assert_eq!(sharpness(4, 4, &stripes), 255.0 * 255.0);
+        assert_eq!(sharpness(4, 4, &[]), 0.0);
+        let frames = [
+            frame(flat.clone()),
+            frame(stripes.clone()),
+            frame(flat),
+            frame(stripes),
+        ];
+        assert_eq!(sharpest(&frames), Some(1));
+        assert_eq!(sharpest(&[]), None);
+    }
+
+    #[test]
+    fn test_blurriest_drops_later_ties() {
+        assert_eq!(blurriest(&[3.0, 1.0, 2.0, 1.0, 5.0]), Some(3));
+        assert_eq!(blurriest(&[2.0]), Some(0));
+        assert_eq!(blurriest(&[]), None);
+    }
+
+    #[test]
+    fn test_config_limits() {
+        let c: BurstConfig = toml::from_str("frames = 100\nspan_ms = 380").unwrap();
+        assert_eq!(c.frame_count(), MAX_FRAMES);
+        assert_eq!(c.frame_interval(), Duration::from_millis(20));
+        let one = BurstConfig {
+            frames: 1,
+            ..Default::default()
+        };
+        assert_eq!(one.frame_count(), 2);
+        assert_eq!(one.frame_interval(), Duration::from_millis(1000));
+    }
+}
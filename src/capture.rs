@@ -1,10 +1,15 @@
 use anyhow::{anyhow, Result};
 use image::{codecs::png::PngEncoder, ExtendedColorType, ImageEncoder};
+// 截图后端自带的 image 版本（与本 crate 的 image 不同），用于查询返回图像的像素类型
+use screenshots::image::{ExtendedColorType as BackendColor, ImageBuffer, PixelWithColorType};
 use screenshots::Screen;
-use std::env;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::io::Cursor;
+use std::sync::Arc;
+use std::time::SystemTime;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Rect {
     pub x: i32,
     pub y: i32,
@@ -12,74 +17,498 @@ pub struct Rect {
     pub height: u32,
 }
 
-/// 全屏截图，返回 PNG 字节
-pub fn capture_fullscreen() -> Result<Vec<u8>> {
-    let screen = Screen::from_point(0, 0).map_err(|e| anyhow!("detect screen failed: {e}"))?;
+/// 像素字节序（每像素 4 字节）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    Rgba8,
+    Bgra8,
+}
+
+/// 一帧原始截图：后端缓冲直接移入，不编码、不复制；通过 `Arc` 在 overlay / 录制 / OCR 间共享
+#[derive(Debug)]
+pub struct Frame {
+    pub origin: (i32, i32), // 显示器左上角（虚拟桌面坐标）
+    pub width: u32,
+    pub height: u32,
+    pub stride: usize, // 每行字节数
+    pub format: PixelFormat,
+    pub timestamp: SystemTime,
+    pub data: Vec<u8>,
+}
+
+impl Frame {
+    /// 第 y 行像素字节
+    pub fn row(&self, y: u32) -> &[u8] {
+        let start = y as usize * self.stride;
+        &self.data[start..start + self.width as usize * 4]
+    }
+
+    /// 转为 RGBA 缓冲：已是紧凑 RGBA 时直接移出，不复制
+    pub fn into_rgba(self) -> Vec<u8> {
+        match self.rgba() {
+            Cow::Borrowed(_) => self.data,
+            Cow::Owned(rgba) => rgba,
+        }
+    }
+
+    /// RGBA 写入 out（先清空，复用已有容量）
+    pub fn rgba_into(&self, out: &mut Vec<u8>) {
+        if self.stride == self.width as usize * 4 {
+            normalize_into(&self.data, self.format, out);
+        } else {
+            out.clear();
+            out.extend_from_slice(&self.rgba());
+        }
+    }
+
+    /// RGBA 视图：已是 RGBA 时借用，BGRA 时才转换
+    pub fn rgba(&self) -> Cow<'_, [u8]> {
+        match self.format {
+            PixelFormat::Rgba8 if self.stride == self.width as usize * 4 => {
+                Cow::Borrowed(&self.data)
+            }
+            _ => {
+                let mut out = Vec::with_capacity((self.width * self.height * 4) as usize);
+                for y in 0..self.height {
+                    let row = self.row(y);
+                    match self.format {
+                        PixelFormat::Rgba8 => out.extend_from_slice(row),
+                        PixelFormat::Bgra8 => {
+                            for px in row.chunks_exact(4) {
+                                out.extend_from_slice(&[px[2], px[1], px[0], px[3]]);
+                            }
+                        }
+                    }
+                }
+                Cow::Owned(out)
+            }
+        }
+    }
+}
+
+/// 全屏截图（原点所在屏幕），返回共享帧。格式取自后端实际返回的像素类型，不做转换。
+pub fn capture_fullscreen_frame() -> Result<Arc<Frame>> {
+    grab().map(Arc::new)
+}
+
+/// 被截取屏幕（原点所在屏幕）在显示器列表中的序号，从 1 开始；枚举失败时为 1
+pub fn capture_monitor_number() -> u32 {
+    let Ok(screen) = Screen::from_point(0, 0) else {
+        return 1;
+    };
+    Screen::all()
+        .ok()
+        .and_then(|all| {
+            all.iter()
+                .position(|s| s.display_info.id == screen.display_info.id)
+        })
+        .map_or(1, |i| i as u32 + 1)
+}
+
+/// 显示器（虚拟桌面坐标）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Monitor {
+    /// 在显示器列表中的序号，从 1 开始（与文件名模板 {monitor} 相同）
+    pub number: u32,
+    pub bounds: Rect,
+    pub is_primary: bool,
+    /// 系统缩放换算的 DPI（100% 为 96）
+    pub dpi: u32,
+}
+
+/// 与 rect 重叠面积最大的显示器（完全不重叠时为 None）
+pub fn monitor_at<'a>(monitors: &'a [Monitor], rect: &Rect) -> Option<&'a Monitor> {
+    monitors
+        .iter()
+        .filter_map(|m| {
+            let area = m.bounds.intersect(rect)?;
+            Some((area.width as u64 * area.height as u64, m))
+        })
+        .max_by_key(|(area, _)| *area)
+        .map(|(_, m)| m)
+}
+
+/// 枚举显示器，按系统返回的顺序编号
+pub fn list_monitors() -> Result<Vec<Monitor>> {
+    let screens = Screen::all().map_err(|e| anyhow!("list screens failed: {e}"))?;
+    Ok(screens
+        .iter()
+        .enumerate()
+        .map(|(i, screen)| {
+            let info = &screen.display_info;
+            Monitor {
+                number: i as u32 + 1,
+                bounds: Rect {
+                    x: info.x,
+                    y: info.y,
+                    width: info.width,
+                    height: info.height,
+                },
+                is_primary: info.is_primary,
+                dpi: (96.0 * info.scale_factor).round() as u32,
+            }
+        })
+        .collect())
+}
+
+/// 截取第 number 个显示器（见 list_monitors）的整个画面，返回 (x, y, width, height, RGBA)
+pub fn capture_monitor(number: u32) -> Result<(i32, i32, u32, u32, Vec<u8>)> {
+    let screens = Screen::all().map_err(|e| anyhow!("list screens failed: {e}"))?;
+    let screen = number
+        .checked_sub(1)
+        .and_then(|i| screens.get(i as usize))
+        .ok_or_else(|| anyhow!("monitor {number} not found ({} connected)", screens.len()))?;
+    let info = screen.display_info;
     let img = screen
         .capture()
-        .map_err(|e| anyhow!("capture failed: {e}"))?; // RgbaImage
-    let raw = img.as_raw();
-    // 经验：screenshots 0.8 在当前平台实际返回 RGBA，之前误当 BGRA 导致偏色。
-    // 如果用户设置 SNIP_FORCE_BGRA=1 则执行 BGRA->RGBA 转换。
-    let rgba = maybe_convert_bgra(raw, img.width(), img.height());
-    encode_png(&rgba, img.width(), img.height())
+        .map_err(|e| anyhow!("capture screen {} failed: {e}", info.id))?;
+    let (w, h, rgba) = into_rgba(img)?;
+    Ok((info.x, info.y, w, h, rgba))
+}
+
+/// 全屏截图，返回 PNG 字节
+pub fn capture_fullscreen() -> Result<Vec<u8>> {
+    let frame = grab()?;
+    let (w, h) = (frame.width, frame.height);
+    encode_png(&frame.into_rgba(), w, h)
 }
 
 /// 返回原始 RGBA 像素及尺寸 (width,height,Vec<u8>)，供后续 UI 直接使用。
 pub fn capture_fullscreen_raw() -> Result<(u32, u32, Vec<u8>)> {
-    let screen = Screen::from_point(0, 0).map_err(|e| anyhow!("detect screen failed: {e}"))?;
-    let img = screen
-        .capture()
-        .map_err(|e| anyhow!("capture failed: {e}"))?;
-    let rgba = maybe_convert_bgra(img.as_raw(), img.width(), img.height());
-    Ok((img.width(), img.height(), rgba))
+    let frame = grab()?;
+    Ok((frame.width, frame.height, frame.into_rgba()))
 }
 
 /// 返回包含显示器原点坐标的原始数据 (origin_x, origin_y, width, height, RGBA Vec)
 pub fn capture_fullscreen_raw_with_origin() -> Result<(i32, i32, u32, u32, Vec<u8>)> {
+    let frame = grab()?;
+    let ((x, y), w, h) = (frame.origin, frame.width, frame.height);
+    Ok((x, y, w, h, frame.into_rgba()))
+}
+
+/// 与 `capture_fullscreen_raw_with_origin` 相同，但 RGBA 写入调用方提供的缓冲
+/// （先清空再写入，复用已有容量）；返回 (x, y, width, height)
+pub fn capture_fullscreen_raw_into(buf: &mut Vec<u8>) -> Result<(i32, i32, u32, u32)> {
+    capture_raw_into_from(&ScreenBackend, buf)
+}
+
+/// 与 `capture_fullscreen_raw_into` 相同，但从指定后端截取（嵌入 / 无显示器测试用）
+pub fn capture_raw_into_from(
+    backend: &dyn CaptureBackend,
+    buf: &mut Vec<u8>,
+) -> Result<(i32, i32, u32, u32)> {
+    let frame = backend.capture()?;
+    frame.rgba_into(buf);
+    Ok((frame.origin.0, frame.origin.1, frame.width, frame.height))
+}
+
+/// 截图来源：真实屏幕（ScreenBackend）或测试用的确定性画面（MockBackend）
+pub trait CaptureBackend {
+    /// 截取主屏（原点所在屏幕）
+    fn capture(&self) -> Result<Frame>;
+}
+
+/// 真实屏幕（screenshots）；feature bench 下返回合成画面
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScreenBackend;
+
+impl CaptureBackend for ScreenBackend {
+    fn capture(&self) -> Result<Frame> {
+        grab()
+    }
+}
+
+/// 确定性渐变画面，不访问显示器：R 随 x、G 随 y 从 0 渐变到 255，B = (x + y) 低 8 位，alpha 255
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MockBackend {
+    pub origin: (i32, i32),
+    pub width: u32,
+    pub height: u32,
+    pub format: PixelFormat,
+}
+
+impl MockBackend {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            origin: (0, 0),
+            width,
+            height,
+            format: PixelFormat::Rgba8,
+        }
+    }
+
+    /// (x, y) 处应得的 RGBA 像素
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let r = x * 255 / self.width.saturating_sub(1).max(1);
+        let g = y * 255 / self.height.saturating_sub(1).max(1);
+        [r as u8, g as u8, ((x + y) & 0xFF) as u8, 255]
+    }
+}
+
+impl CaptureBackend for MockBackend {
+    fn capture(&self) -> Result<Frame> {
+        let mut data = Vec::with_capacity(self.width as usize * self.height as usize * 4);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let [r, g, b, a] = self.pixel(x, y);
+                match self.format {
+                    PixelFormat::Rgba8 => data.extend_from_slice(&[r, g, b, a]),
+                    PixelFormat::Bgra8 => data.extend_from_slice(&[b, g, r, a]),
+                }
+            }
+        }
+        Ok(Frame {
+            origin: self.origin,
+            width: self.width,
+            height: self.height,
+            stride: self.width as usize * 4,
+            format: self.format,
+            timestamp: SystemTime::UNIX_EPOCH,
+            data,
+        })
+    }
+}
+
+// 截取原点所在屏幕，后端缓冲直接移入 Frame
+#[cfg(not(feature = "bench"))]
+fn grab() -> Result<Frame> {
     let screen = Screen::from_point(0, 0).map_err(|e| anyhow!("detect screen failed: {e}"))?;
     let img = screen
         .capture()
         .map_err(|e| anyhow!("capture failed: {e}"))?;
-    let rgba = maybe_convert_bgra(img.as_raw(), img.width(), img.height());
-    Ok((
-        screen.display_info.x,
-        screen.display_info.y,
-        img.width(),
-        img.height(),
-        rgba,
-    ))
+    let format = backend_format(&img)?;
+    let (width, height) = img.dimensions();
+    Ok(Frame {
+        origin: (screen.display_info.x, screen.display_info.y),
+        width,
+        height,
+        stride: width as usize * 4,
+        format,
+        timestamp: SystemTime::now(),
+        data: img.into_raw(),
+    })
+}
+
+// 基准测试：不访问真实屏幕，返回 bench::set_size 设定尺寸的合成画面
+#[cfg(feature = "bench")]
+fn grab() -> Result<Frame> {
+    let (width, height) = bench::size();
+    Ok(Frame {
+        origin: (0, 0),
+        width,
+        height,
+        stride: width as usize * 4,
+        format: PixelFormat::Rgba8,
+        timestamp: SystemTime::now(),
+        data: bench::synthetic_frame(width, height),
+    })
 }
 
-/// 区域截图（跨屏时暂以包含左上角的屏幕为准）
+/// 基准测试用的合成截图（feature bench）：全屏截图函数返回此处生成的画面，区域截图不受影响
+#[cfg(feature = "bench")]
+pub mod bench {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    // 高 32 位宽、低 32 位高；默认 1920x1080
+    static SIZE: AtomicU64 = AtomicU64::new((1920 << 32) | 1080);
+
+    /// 设定合成截图尺寸
+    pub fn set_size(width: u32, height: u32) {
+        SIZE.store(((width as u64) << 32) | height as u64, Ordering::Relaxed);
+    }
+
+    pub(super) fn size() -> (u32, u32) {
+        let v = SIZE.load(Ordering::Relaxed);
+        ((v >> 32) as u32, v as u32)
+    }
+
+    /// 生成 4 字节像素画面：渐变叠加伪随机噪声，避免 PNG 压缩过于理想。
+    /// 通道顺序由调用方解释（RGBA 或 BGRA），alpha 恒为 255
+    pub fn synthetic_frame(width: u32, height: u32) -> Vec<u8> {
+        let mut out = Vec::with_capacity(width as usize * height as usize * 4);
+        let mut seed = 0x9E37_79B9u32;
+        for y in 0..height {
+            for x in 0..width {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                let noise = (seed & 0x0F) as u8;
+                out.extend_from_slice(&[
+                    (x * 255 / width.max(1)) as u8 ^ noise,
+                    (y * 255 / height.max(1)) as u8 ^ noise,
+                    ((x + y) & 0xFF) as u8,
+                    255,
+                ]);
+            }
+        }
+        out
+    }
+}
+
+impl Rect {
+    /// 两个矩形的交集（无交集返回 None）
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let x0 = self.x.max(other.x);
+        let y0 = self.y.max(other.y);
+        let x1 = (self.x as i64 + self.width as i64).min(other.x as i64 + other.width as i64);
+        let y1 = (self.y as i64 + self.height as i64).min(other.y as i64 + other.height as i64);
+        if x1 <= x0 as i64 || y1 <= y0 as i64 {
+            return None;
+        }
+        Some(Rect {
+            x: x0,
+            y: y0,
+            width: (x1 - x0 as i64) as u32,
+            height: (y1 - y0 as i64) as u32,
+        })
+    }
+
+    /// 解析 `x,y,w,h`（逗号或空白分隔，与 --pick-region 输出相同）；宽高为 0 时为 None
+    pub fn parse(text: &str) -> Option<Rect> {
+        let parts: Vec<&str> = text
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|t| !t.is_empty())
+            .collect();
+        let [x, y, w, h] = parts[..] else {
+            return None;
+        };
+        let rect = Rect {
+            x: x.parse().ok()?,
+            y: y.parse().ok()?,
+            width: w.parse().ok()?,
+            height: h.parse().ok()?,
+        };
+        (rect.width > 0 && rect.height > 0).then_some(rect)
+    }
+}
+
+/// 区域截图：遍历与矩形相交的所有屏幕并拼接；不被任何屏幕覆盖的部分为透明
 pub fn capture_area(rect: Rect) -> Result<Vec<u8>> {
-    let screen = Screen::from_point(rect.x, rect.y)
-        .map_err(|e| anyhow!("find screen for point ({}, {}) failed: {e}", rect.x, rect.y))?;
-    let img = screen
-        .capture()
-        .map_err(|e| anyhow!("capture failed: {e}"))?; // RgbaImage
+    let rgba = capture_area_rgba(rect)?;
+    encode_png(&rgba, rect.width, rect.height)
+}
+
+/// 同 capture_area，返回 rect 大小的 RGBA 像素（不编码）
+pub fn capture_area_rgba(rect: Rect) -> Result<Vec<u8>> {
+    let captured = capture_screens(Some(rect))?;
+    if captured.is_empty() {
+        return Err(anyhow!(
+            "rect ({}, {}, {}x{}) does not intersect any screen",
+            rect.x,
+            rect.y,
+            rect.width,
+            rect.height
+        ));
+    }
+    Ok(compose_captured(rect, &captured))
+}
 
-    // 屏幕坐标原点
-    let origin_x = screen.display_info.x;
-    let origin_y = screen.display_info.y;
-    let rel_x = (rect.x - origin_x).max(0) as u32;
-    let rel_y = (rect.y - origin_y).max(0) as u32;
-    let max_w = img.width().saturating_sub(rel_x);
-    let max_h = img.height().saturating_sub(rel_y);
-    let crop_w = rect.width.min(max_w);
-    let crop_h = rect.height.min(max_h);
+/// 截取全部显示器并按虚拟桌面布局拼成一张图（原点可为负，屏幕之间的空隙透明）；
+/// 返回拼接范围（虚拟桌面坐标）与 RGBA
+pub fn capture_virtual_desktop() -> Result<(Rect, Vec<u8>)> {
+    let captured = capture_screens(None)?;
+    let bounds: Vec<Rect> = captured.iter().map(|(rect, _)| *rect).collect();
+    let rect = virtual_desktop_bounds(&bounds).ok_or_else(|| anyhow!("no screen to capture"))?;
+    Ok((rect, compose_captured(rect, &captured)))
+}
 
-    let rgba_full = maybe_convert_bgra(img.as_raw(), img.width(), img.height());
-    let mut cropped: Vec<u8> = Vec::with_capacity((crop_w * crop_h * 4) as usize);
-    for row in 0..crop_h {
-        let start = (((rel_y + row) * img.width()) + rel_x) as usize * 4;
-        let end = start + crop_w as usize * 4;
-        cropped.extend_from_slice(&rgba_full[start..end]);
+/// 屏幕矩形的外接矩形（虚拟桌面范围）；列表为空时为 None
+pub fn virtual_desktop_bounds(screens: &[Rect]) -> Option<Rect> {
+    let x0 = screens.iter().map(|r| r.x as i64).min()?;
+    let y0 = screens.iter().map(|r| r.y as i64).min()?;
+    let x1 = screens.iter().map(|r| r.x as i64 + r.width as i64).max()?;
+    let y1 = screens.iter().map(|r| r.y as i64 + r.height as i64).max()?;
+    Some(Rect {
+        x: x0 as i32,
+        y: y0 as i32,
+        width: (x1 - x0) as u32,
+        height: (y1 - y0) as u32,
+    })
+}
+
+// 截取与 rect 相交的屏幕（None 为全部）；矩形的尺寸取实际截到的图像
+fn capture_screens(rect: Option<Rect>) -> Result<Vec<(Rect, Vec<u8>)>> {
+    let screens = Screen::all().map_err(|e| anyhow!("list screens failed: {e}"))?;
+    let mut captured = Vec::new();
+    for screen in screens {
+        let info = screen.display_info;
+        let bounds = Rect {
+            x: info.x,
+            y: info.y,
+            width: info.width,
+            height: info.height,
+        };
+        if rect.is_some_and(|r| r.intersect(&bounds).is_none()) {
+            continue;
+        }
+        let img = screen
+            .capture()
+            .map_err(|e| anyhow!("capture screen {} failed: {e}", info.id))?;
+        let (width, height, rgba) = into_rgba(img)?;
+        let bounds = Rect {
+            width,
+            height,
+            ..bounds
+        };
+        captured.push((bounds, rgba));
     }
-    encode_png(&cropped, crop_w, crop_h)
+    Ok(captured)
 }
 
-fn bgra_to_rgba(bgra: &[u8], w: u32, h: u32) -> Vec<u8> {
+fn compose_captured(rect: Rect, captured: &[(Rect, Vec<u8>)]) -> Vec<u8> {
+    let parts: Vec<ScreenPart> = captured
+        .iter()
+        .map(|(bounds, rgba)| ScreenPart {
+            x: bounds.x,
+            y: bounds.y,
+            width: bounds.width,
+            height: bounds.height,
+            rgba,
+        })
+        .collect();
+    compose_area(rect, &parts)
+}
+
+// 单个屏幕的截图数据（全局虚拟桌面坐标）
+struct ScreenPart<'a> {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    rgba: &'a [u8],
+}
+
+// 把各屏幕与 rect 相交的部分按行拷贝进 rect 大小的 RGBA 缓冲
+fn compose_area(rect: Rect, parts: &[ScreenPart]) -> Vec<u8> {
+    let out_stride = rect.width as usize * 4;
+    let mut out = vec![0u8; out_stride * rect.height as usize];
+    for part in parts {
+        let bounds = Rect {
+            x: part.x,
+            y: part.y,
+            width: part.width,
+            height: part.height,
+        };
+        let Some(isect) = rect.intersect(&bounds) else {
+            continue;
+        };
+        let src_stride = part.width as usize * 4;
+        let src_x = (isect.x - part.x) as usize;
+        let dst_x = (isect.x - rect.x) as usize;
+        let bytes = isect.width as usize * 4;
+        for row in 0..isect.height as usize {
+            let src_y = (isect.y - part.y) as usize + row;
+            let dst_y = (isect.y - rect.y) as usize + row;
+            let src = src_y * src_stride + src_x * 4;
+            let dst = dst_y * out_stride + dst_x * 4;
+            out[dst..dst + bytes].copy_from_slice(&part.rgba[src..src + bytes]);
+        }
+    }
+    out
+}
+
+/// BGRA -> RGBA 转换（不足 w*h 像素时以 0 补齐）
+pub fn bgra_to_rgba(bgra: &[u8], w: u32, h: u32) -> Vec<u8> {
     let mut out = Vec::with_capacity(bgra.len());
     for chunk in bgra.chunks_exact(4) {
         if chunk.len() == 4 {
@@ -95,23 +524,106 @@ fn bgra_to_rgba(bgra: &[u8], w: u32, h: u32) -> Vec<u8> {
     out
 }
 
-fn maybe_convert_bgra(raw: &[u8], w: u32, h: u32) -> Vec<u8> {
-    if env::var("SNIP_FORCE_BGRA").is_ok() {
-        bgra_to_rgba(raw, w, h)
-    } else {
-        // 直接克隆为 RGBA
-        raw.to_vec()
+// 查询截图后端返回的像素格式：由返回图像的像素类型决定，而不是靠环境变量猜测。
+// screenshots 0.8 在 Windows / macOS 内部已做 BGRA -> RGBA，此处得到 Rgba8；
+// 换用直接返回 BGRA 的后端时自动走转换分支。
+fn backend_format<P: PixelWithColorType<Subpixel = u8>>(
+    _img: &ImageBuffer<P, Vec<u8>>,
+) -> Result<PixelFormat> {
+    format_of(P::COLOR_TYPE.into())
+        .ok_or_else(|| anyhow!("unsupported capture pixel format: {:?}", P::COLOR_TYPE))
+}
+
+// 非 4 字节 RGBA / BGRA 布局返回 None
+fn format_of(color: BackendColor) -> Option<PixelFormat> {
+    match color {
+        BackendColor::Rgba8 => Some(PixelFormat::Rgba8),
+        BackendColor::Bgra8 => Some(PixelFormat::Bgra8),
+        _ => None,
     }
 }
 
-fn encode_png(rgba: &[u8], w: u32, h: u32) -> Result<Vec<u8>> {
+// 后端图像归一化为 RGBA：已是 RGBA 时直接移出缓冲，不复制
+fn into_rgba<P: PixelWithColorType<Subpixel = u8>>(
+    img: ImageBuffer<P, Vec<u8>>,
+) -> Result<(u32, u32, Vec<u8>)> {
+    let format = backend_format(&img)?;
+    let (w, h) = img.dimensions();
+    let raw = img.into_raw();
+    let rgba = match format {
+        PixelFormat::Rgba8 => raw,
+        PixelFormat::Bgra8 => bgra_to_rgba(&raw, w, h),
+    };
+    Ok((w, h, rgba))
+}
+
+// 按给定格式归一化为 RGBA 写入 out（先清空，复用已有容量）
+fn normalize_into(raw: &[u8], format: PixelFormat, out: &mut Vec<u8>) {
+    out.clear();
+    match format {
+        PixelFormat::Rgba8 => out.extend_from_slice(raw),
+        PixelFormat::Bgra8 => {
+            out.reserve(raw.len());
+            for px in raw.chunks_exact(4) {
+                out.extend_from_slice(&[px[2], px[1], px[0], px[3]]);
+            }
+        }
+    }
+}
+
+/// RGBA 编码为 PNG
+pub fn encode_png(rgba: &[u8], w: u32, h: u32) -> Result<Vec<u8>> {
+    encode_png_with_icc(rgba, w, h, None)
+}
+
+/// PNG 编码，可选嵌入 ICC 配置文件（iCCP 块），让浏览器按显示器色域解释像素
+pub(crate) fn encode_png_with_icc(
+    rgba: &[u8],
+    w: u32,
+    h: u32,
+    icc: Option<&[u8]>,
+) -> Result<Vec<u8>> {
     let mut data = Vec::new();
     let cursor = Cursor::new(&mut data);
-    let encoder = PngEncoder::new(cursor);
+    let mut encoder = PngEncoder::new(cursor);
+    if let Some(icc) = icc {
+        encoder
+            .set_icc_profile(icc.to_vec())
+            .map_err(|e| anyhow!("set icc profile: {e}"))?;
+    }
     encoder.write_image(rgba, w, h, ExtendedColorType::Rgba8)?;
     Ok(data)
 }
 
+/// 在 IEND 之前插入文本块：ASCII 用 tEXt，否则用未压缩的 iTXt（UTF-8）
+pub(crate) fn insert_png_text(png: &mut Vec<u8>, keyword: &str, text: &str) -> Result<()> {
+    const IEND_LEN: usize = 12; // length + type + crc，无数据
+    if keyword.is_empty() || keyword.len() > 79 || !keyword.is_ascii() {
+        return Err(anyhow!("invalid png text keyword {keyword:?}"));
+    }
+    if png.len() < 8 + IEND_LEN || &png[png.len() - 8..png.len() - 4] != b"IEND" {
+        return Err(anyhow!("png without trailing IEND"));
+    }
+    let (kind, mut body): (&[u8; 4], Vec<u8>) = if text.is_ascii() {
+        (b"tEXt", keyword.as_bytes().to_vec())
+    } else {
+        // keyword \0 压缩标志 压缩方法 语言 \0 译名 \0 文本
+        let mut body = keyword.as_bytes().to_vec();
+        body.extend_from_slice(&[0, 0, 0, 0]);
+        (b"iTXt", body)
+    };
+    body.push(0);
+    body.extend_from_slice(text.as_bytes());
+    let mut chunk = Vec::with_capacity(body.len() + 12);
+    chunk.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(kind);
+    chunk.extend_from_slice(&body);
+    chunk.extend_from_slice(&crc32fast::hash(&chunk[4..]).to_be_bytes());
+    let at = png.len() - IEND_LEN;
+    png.splice(at..at, chunk);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -124,6 +636,29 @@ mod tests {
         assert_eq!(rgba, vec![30, 20, 10, 255]);
     }
 
+    #[test]
+    fn test_normalize_into_reuses_buffer() {
+        let mut buf = Vec::with_capacity(64);
+        buf.extend_from_slice(&[9u8; 12]);
+        let ptr = buf.as_ptr();
+        normalize_into(&[1, 2, 3, 4], PixelFormat::Rgba8, &mut buf);
+        assert_eq!(buf, [1, 2, 3, 4]);
+        assert_eq!(buf.as_ptr(), ptr); // 未重新分配
+        normalize_into(&[1, 2, 3, 4], PixelFormat::Bgra8, &mut buf);
+        assert_eq!(buf, [3, 2, 1, 4]);
+    }
+
+    #[test]
+    fn test_backend_format_detection() {
+        use screenshots::image::{RgbImage, RgbaImage};
+        assert_eq!(
+            backend_format(&RgbaImage::new(1, 1)).unwrap(),
+            PixelFormat::Rgba8
+        );
+        assert!(backend_format(&RgbImage::new(1, 1)).is_err());
+        assert_eq!(format_of(BackendColor::Bgra8), Some(PixelFormat::Bgra8));
+    }
+
     #[test]
     fn test_encode_png_signature() {
         // 2x1 像素: 红, 绿
@@ -134,6 +669,219 @@ mod tests {
         assert!(png.starts_with(&[137, 80, 78, 71, 13, 10, 26, 10]));
     }
 
+    #[test]
+    fn test_encode_png_embeds_icc() {
+        use image::codecs::png::PngDecoder;
+        use image::ImageDecoder;
+        let icc = b"fake icc profile bytes".to_vec();
+        let png = encode_png_with_icc(&[1, 2, 3, 255], 1, 1, Some(&icc)).unwrap();
+        let mut decoder = PngDecoder::new(Cursor::new(&png)).unwrap();
+        assert_eq!(decoder.icc_profile().unwrap(), Some(icc));
+    }
+
+    #[test]
+    fn test_insert_png_text_chunks() {
+        assert_eq!(crc32fast::hash(b"IEND"), 0xAE42_6082);
+        let mut png = encode_png(&[1, 2, 3, 255], 1, 1).unwrap();
+        insert_png_text(&mut png, "Comment", "snip_rust").unwrap();
+        insert_png_text(&mut png, "Comment", "截图").unwrap();
+        let find = |needle: &[u8]| png.windows(needle.len()).any(|w| w == needle);
+        assert!(find(b"tEXtComment\0snip_rust"));
+        assert!(find("iTXtComment\0\0\0\0\0截图".as_bytes()));
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+        // 解码器校验块 CRC
+        assert!(image::load_from_memory(&png).is_ok());
+        assert!(insert_png_text(&mut png, "", "x").is_err());
+    }
+
+    fn solid(w: u32, h: u32, px: [u8; 4]) -> Vec<u8> {
+        px.repeat((w * h) as usize)
+    }
+
+    #[test]
+    fn test_compose_area_negative_origin() {
+        // 左屏位于 (-100, 0)，右屏位于 (0, 0)；选区横跨两屏
+        let left = solid(100, 50, [255, 0, 0, 255]);
+        let right = solid(100, 50, [0, 255, 0, 255]);
+        let parts = [
+            ScreenPart {
+                x: -100,
+                y: 0,
+                width: 100,
+                height: 50,
+                rgba: &left,
+            },
+            ScreenPart {
+                x: 0,
+                y: 0,
+                width: 100,
+                height: 50,
+                rgba: &right,
+            },
+        ];
+        let rect = Rect {
+            x: -10,
+            y: 10,
+            width: 20,
+            height: 5,
+        };
+        let out = compose_area(rect, &parts);
+        assert_eq!(out.len(), 20 * 5 * 4);
+        for row in 0..5 {
+            let base = row * 20 * 4;
+            assert_eq!(&out[base..base + 4], &[255, 0, 0, 255]);
+            assert_eq!(&out[base + 9 * 4..base + 10 * 4], &[255, 0, 0, 255]);
+            assert_eq!(&out[base + 10 * 4..base + 11 * 4], &[0, 255, 0, 255]);
+            assert_eq!(&out[base + 19 * 4..base + 20 * 4], &[0, 255, 0, 255]);
+        }
+    }
+
+    #[test]
+    fn test_virtual_desktop_bounds() {
+        assert_eq!(virtual_desktop_bounds(&[]), None);
+        // 左侧副屏原点为负、右侧竖屏下移：外接矩形覆盖全部，空隙计入
+        let screens = [
+            Rect {
+                x: -1920,
+                y: 200,
+                width: 1920,
+                height: 1080,
+            },
+            Rect {
+                x: 0,
+                y: 0,
+                width: 2560,
+                height: 1440,
+            },
+            Rect {
+                x: 2560,
+                y: -400,
+                width: 1080,
+                height: 1920,
+            },
+        ];
+        assert_eq!(
+            virtual_desktop_bounds(&screens),
+            Some(Rect {
+                x: -1920,
+                y: -400,
+                width: 1920 + 2560 + 1080,
+                height: 1920,
+            })
+        );
+    }
+
+    #[test]
+    fn test_compose_area_vertical_gap() {
+        // 上屏 (0,-40) 高 40，下屏 (0,10)：y ∈ [0,10) 无屏幕覆盖，应保持透明
+        let top = solid(30, 40, [1, 2, 3, 255]);
+        let bottom = solid(30, 40, [4, 5, 6, 255]);
+        let parts = [
+            ScreenPart {
+                x: 0,
+                y: -40,
+                width: 30,
+                height: 40,
+                rgba: &top,
+            },
+            ScreenPart {
+                x: 0,
+                y: 10,
+                width: 30,
+                height: 40,
+                rgba: &bottom,
+            },
+        ];
+        let rect = Rect {
+            x: 5,
+            y: -2,
+            width: 4,
+            height: 14,
+        };
+        let out = compose_area(rect, &parts);
+        let px = |row: usize| &out[row * 4 * 4..row * 4 * 4 + 4];
+        assert_eq!(px(0), &[1, 2, 3, 255]);
+        assert_eq!(px(1), &[1, 2, 3, 255]);
+        assert_eq!(px(2), &[0, 0, 0, 0]);
+        assert_eq!(px(11), &[0, 0, 0, 0]);
+        assert_eq!(px(12), &[4, 5, 6, 255]);
+    }
+
+    #[test]
+    fn test_frame_rgba_view() {
+        let frame = Frame {
+            origin: (0, 0),
+            width: 1,
+            height: 2,
+            stride: 8, // 每行带 4 字节填充
+            format: PixelFormat::Bgra8,
+            timestamp: SystemTime::now(),
+            data: vec![10, 20, 30, 255, 0, 0, 0, 0, 1, 2, 3, 4, 0, 0, 0, 0],
+        };
+        assert_eq!(frame.rgba().as_ref(), &[30, 20, 10, 255, 3, 2, 1, 4]);
+    }
+
+    #[test]
+    fn test_rect_parse() {
+        let r = Rect::parse("-1920,10,800,600").unwrap();
+        assert_eq!((r.x, r.y, r.width, r.height), (-1920, 10, 800, 600));
+        assert_eq!(Rect::parse("1 2 3 4"), Rect::parse(" 1, 2, 3, 4 "));
+        assert!(Rect::parse("1,2,3").is_none());
+        assert!(Rect::parse("1,2,0,4").is_none());
+        assert!(Rect::parse("1,2,-3,4").is_none());
+    }
+
+    #[test]
+    fn test_monitor_at_largest_overlap() {
+        let monitor = |number, x| Monitor {
+            number,
+            bounds: Rect {
+                x,
+                y: 0,
+                width: 1920,
+                height: 1080,
+            },
+            is_primary: number == 1,
+            dpi: 96,
+        };
+        let monitors = [monitor(1, 0), monitor(2, 1920)];
+        let rect = |x, width| Rect {
+            x,
+            y: 100,
+            width,
+            height: 100,
+        };
+        // 跨屏时取重叠多的一边
+        assert_eq!(monitor_at(&monitors, &rect(1850, 200)).unwrap().number, 2);
+        assert_eq!(monitor_at(&monitors, &rect(1700, 300)).unwrap().number, 1);
+        assert!(monitor_at(&monitors, &rect(-500, 100)).is_none());
+    }
+
+    #[test]
+    fn test_rect_intersect() {
+        let a = Rect {
+            x: -5,
+            y: -5,
+            width: 10,
+            height: 10,
+        };
+        let b = Rect {
+            x: 0,
+            y: 0,
+            width: 10,
+            height: 10,
+        };
+        let i = a.intersect(&b).unwrap();
+        assert_eq!((i.x, i.y, i.width, i.height), (0, 0, 5, 5));
+        let c = Rect {
+            x: 5,
+            y: 0,
+            width: 3,
+            height: 3,
+        };
+        assert!(a.intersect(&c).is_none());
+    }
+
     #[test]
     fn test_fullscreen_runtime_capture() {
         let png = capture_fullscreen().unwrap();
@@ -0,0 +1,177 @@
+// capture_async: 异步截图 API（不依赖具体运行时，tokio / async-std / 手写 executor 均可 await）
+//
+// 阻塞的截图 + PNG 编码在固定大小的后台线程池中执行，结果通过一次性 future 返回，
+// 避免 4K 编码阻塞 UI 线程。
+
+use anyhow::{anyhow, Result};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use crate::capture::{self, Frame, Rect};
+
+const WORKERS: usize = 2;
+
+pub(crate) type Job = Box<dyn FnOnce() + Send + 'static>;
+
+fn pool() -> &'static Mutex<Sender<Job>> {
+    static POOL: OnceLock<Mutex<Sender<Job>>> = OnceLock::new();
+    POOL.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<Job>();
+        let rx = Arc::new(Mutex::new(rx));
+        for i in 0..WORKERS {
+            let rx = rx.clone();
+            let _ = thread::Builder::new()
+                .name(format!("snip-capture-{i}"))
+                .spawn(move || loop {
+                    let job = match rx.lock() {
+                        Ok(guard) => guard.recv(),
+                        Err(_) => return,
+                    };
+                    match job {
+                        // 单个任务 panic 不应带走工作线程
+                        Ok(job) => {
+                            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(job));
+                        }
+                        Err(_) => return,
+                    }
+                });
+        }
+        Mutex::new(tx)
+    })
+}
+
+struct Slot<T> {
+    result: Option<Result<T>>,
+    waker: Option<Waker>,
+}
+
+/// 后台任务结果
+pub struct CaptureFuture<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+}
+
+// 完成端：任务未写入结果就被丢弃（例如 panic）时，以错误唤醒 future，避免永远挂起
+struct Completer<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+}
+
+impl<T> Completer<T> {
+    fn complete(&self, result: Result<T>) {
+        let waker = {
+            let mut slot = self.slot.lock().unwrap_or_else(|e| e.into_inner());
+            if slot.result.is_some() {
+                return;
+            }
+            slot.result = Some(result);
+            slot.waker.take()
+        };
+        if let Some(w) = waker {
+            w.wake();
+        }
+    }
+}
+
+impl<T> Drop for Completer<T> {
+    fn drop(&mut self) {
+        self.complete(Err(anyhow!("capture worker aborted")));
+    }
+}
+
+impl<T> Future for CaptureFuture<T> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.slot.lock().unwrap_or_else(|e| e.into_inner());
+        match slot.result.take() {
+            Some(r) => Poll::Ready(r),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+// 投递任务到线程池；失败时任务被直接丢弃
+pub(crate) fn execute(job: Job) -> bool {
+    pool()
+        .lock()
+        .map(|tx| tx.send(job).is_ok())
+        .unwrap_or(false)
+}
+
+/// 在截图线程池中执行阻塞任务
+pub fn spawn_blocking<T, F>(f: F) -> CaptureFuture<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let slot = Arc::new(Mutex::new(Slot {
+        result: None,
+        waker: None,
+    }));
+    let completer = Completer { slot: slot.clone() };
+    let job: Job = Box::new(move || completer.complete(f()));
+    if !execute(job) {
+        // 线程池不可用：job（连同 completer）已被丢弃，future 会得到错误
+        log::warn!("capture pool unavailable");
+    }
+    CaptureFuture { slot }
+}
+
+/// 全屏截图 PNG（异步）
+pub async fn capture_fullscreen_async() -> Result<Vec<u8>> {
+    spawn_blocking(capture::capture_fullscreen).await
+}
+
+/// 全屏共享帧（异步）
+pub async fn capture_fullscreen_frame_async() -> Result<Arc<Frame>> {
+    spawn_blocking(capture::capture_fullscreen_frame).await
+}
+
+/// 区域截图 PNG（异步）
+pub async fn capture_area_async(rect: Rect) -> Result<Vec<u8>> {
+    spawn_blocking(move || capture::capture_area(rect)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Wake;
+
+    struct ThreadWaker(thread::Thread);
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    // 最小 executor：验证不依赖任何运行时即可 await
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = std::pin::pin!(fut);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(v) = fut.as_mut().poll(&mut cx) {
+                return v;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn test_spawn_blocking_resolves() {
+        let v = block_on(spawn_blocking(|| Ok(21 * 2))).unwrap();
+        assert_eq!(v, 42);
+    }
+
+    #[test]
+    fn test_spawn_blocking_panic_becomes_error() {
+        let r: Result<u32> = block_on(spawn_blocking(|| panic!("boom")));
+        assert!(r.is_err());
+    }
+}
@@ -0,0 +1,310 @@
+// cli: 命令行参数 -> IPC 命令（首个实例直接执行；第二实例转发给正在运行的实例）
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use snip_rust::capture::{self, Rect};
+use snip_rust::diff::diff_heatmap;
+use snip_rust::engine;
+use snip_rust::ipc::IpcCommand;
+use snip_rust::open_with;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+pub const USAGE: &str = "usage: snip_rust [--capture] [--capture-desktop] [--burst] [--edit <file>] [--open <file.snip>] [--pin-clipboard] [pin <file.png|->] [--reload-config] [--quit] | -o <file.png|-> [--json] | --pick-region [--json] | verify ...";
+
+pub const VERIFY_USAGE: &str = "usage: snip_rust verify --baseline <base.png> [--region x,y,w,h] [--threshold 0.01] [--tolerance 8] [--diff <diff.png>] [--update]";
+
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Vec<IpcCommand>> {
+    let mut cmds = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let cmd = match arg.as_str() {
+            "--capture" => IpcCommand::CaptureRegion,
+            "--capture-desktop" => IpcCommand::CaptureDesktop,
+            "--burst" => IpcCommand::Burst,
+            "--edit" => IpcCommand::EditAnnotations {
+                path: file_arg(&mut args, "--edit")?,
+            },
+            "--open" => IpcCommand::OpenProject {
+                path: file_arg(&mut args, "--open")?,
+            },
+            "--pin-clipboard" => IpcCommand::PinClipboard,
+            "pin" => IpcCommand::PinImage {
+                path: pin_arg(&mut args)?,
+            },
+            "--reload-config" => IpcCommand::ReloadConfig,
+            "--pick-region" => IpcCommand::PickRegion,
+            "--quit" => IpcCommand::Quit,
+            other => return Err(anyhow!("unknown argument: {other}")),
+        };
+        cmds.push(cmd);
+    }
+    Ok(cmds)
+}
+
+// 选项后面的文件参数；转发给正在运行的实例时工作目录可能不同，转为绝对路径
+fn file_arg(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(args.next().ok_or_else(|| anyhow!("{flag} needs a file"))?);
+    Ok(std::path::absolute(&path).unwrap_or(path))
+}
+
+// pin 的参数：`-` 时从 stdin 读入 PNG，写入临时目录后把该文件交给运行中的实例钉图
+// （临时文件由 open_with::TempFiles 的过期清理删除）
+fn pin_arg(args: &mut impl Iterator<Item = String>) -> Result<PathBuf> {
+    let arg = args
+        .next()
+        .ok_or_else(|| anyhow!("pin needs a file or -"))?;
+    if arg != "-" {
+        let path = PathBuf::from(arg);
+        return Ok(std::path::absolute(&path).unwrap_or(path));
+    }
+    let mut png = Vec::new();
+    std::io::stdin()
+        .read_to_end(&mut png)
+        .map_err(|e| anyhow!("read stdin: {e}"))?;
+    image::load_from_memory(&png).map_err(|e| anyhow!("stdin is not a png image: {e}"))?;
+    open_with::TempFiles::default().write(&png, "stdin")
+}
+
+/// 以管理员身份重启时新进程的参数：等待旧实例退出后再启动
+pub const RELAUNCHED_FLAG: &str = "--relaunched";
+
+/// 取出不转为 IPC 命令的开关（`--json` / RELAUNCHED_FLAG），返回是否出现过
+pub fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|a| a != flag);
+    args.len() != before
+}
+
+// --json 输出的截图信息（一行 JSON）
+#[derive(Serialize)]
+struct CaptureInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<PathBuf>,
+    rect: Rect,
+    /// 与选区重叠最多的显示器序号
+    monitor: Option<u32>,
+    dpi: Option<u32>,
+    /// 从显示 overlay 到确认选区的耗时
+    duration_ms: u64,
+}
+
+impl CaptureInfo {
+    fn new(rect: Rect, path: Option<PathBuf>, started: Instant) -> Self {
+        let monitors = capture::list_monitors().unwrap_or_default();
+        let monitor = capture::monitor_at(&monitors, &rect);
+        Self {
+            path,
+            rect,
+            monitor: monitor.map(|m| m.number),
+            dpi: monitor.map(|m| m.dpi),
+            duration_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+
+    fn print(&self) {
+        match serde_json::to_string(self) {
+            Ok(line) => println!("{line}"),
+            Err(e) => eprintln!("snip_rust: serialize capture info: {e}"),
+        }
+    }
+}
+
+/// --pick-region：框选后向 stdout 打印 `x y width height`（屏幕物理像素），
+/// json 时打印 CaptureInfo；取消时不输出，退出码 1。不能与其它参数同时使用
+pub fn pick_region(cmds: &[IpcCommand], json: bool) -> Result<()> {
+    if cmds.len() > 1 {
+        eprintln!("snip_rust: --pick-region cannot be combined with other arguments\n{USAGE}");
+        std::process::exit(2);
+    }
+    let started = Instant::now();
+    match engine::pick_region()? {
+        Some(r) if json => {
+            CaptureInfo::new(r, None, started).print();
+            Ok(())
+        }
+        Some(r) => {
+            println!("{} {} {} {}", r.x, r.y, r.width, r.height);
+            Ok(())
+        }
+        None => std::process::exit(1),
+    }
+}
+
+/// -o <file|->：本进程截图一次，把导出的 PNG 写到文件，`-` 时写到 stdout，便于接入管道；
+/// json 时写出文件后再向 stdout 打印 CaptureInfo（不能与 `-o -` 同时使用）。
+/// 取消时不输出，退出码 1。只能与 --capture 同时使用
+pub fn capture_output(args: &[String], json: bool) -> ! {
+    let code = match run_capture_output(args, json) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("snip_rust: {e}\n{USAGE}");
+            2
+        }
+    };
+    std::process::exit(code)
+}
+
+fn run_capture_output(args: &[String], json: bool) -> Result<bool> {
+    let mut target = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => {
+                target = Some(
+                    args.next()
+                        .ok_or_else(|| anyhow!("{arg} needs a file or -"))?,
+                )
+            }
+            "--capture" => {}
+            other => return Err(anyhow!("-o cannot be combined with {other}")),
+        }
+    }
+    let target = target.ok_or_else(|| anyhow!("-o needs a file or -"))?;
+    if json && target == "-" {
+        return Err(anyhow!(
+            "--json cannot be used with -o - (stdout carries the png)"
+        ));
+    }
+    let started = Instant::now();
+    let Some((rect, png)) = engine::capture_png()? else {
+        return Ok(false);
+    };
+    if target == "-" {
+        let mut out = std::io::stdout().lock();
+        out.write_all(&png)
+            .and_then(|_| out.flush())
+            .map_err(|e| anyhow!("write stdout: {e}"))?;
+        return Ok(true);
+    }
+    std::fs::write(target, png).map_err(|e| anyhow!("write {target}: {e}"))?;
+    if json {
+        let path = PathBuf::from(target);
+        let path = std::path::absolute(&path).unwrap_or(path);
+        CaptureInfo::new(rect, Some(path), started).print();
+    }
+    Ok(true)
+}
+
+// verify 的参数
+struct VerifyArgs {
+    baseline: PathBuf,
+    region: Option<Rect>, // 未设置时截取主屏全屏
+    threshold: f64,       // 允许的差异像素占比
+    tolerance: u8,        // 通道差值不超过此值视为相同
+    diff: Option<PathBuf>,
+    update: bool, // 把本次截图写为基准图
+}
+
+fn parse_verify(args: impl IntoIterator<Item = String>) -> Result<VerifyArgs> {
+    let mut parsed = VerifyArgs {
+        baseline: PathBuf::new(),
+        region: None,
+        threshold: 0.0,
+        tolerance: 8,
+        diff: None,
+        update: false,
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| anyhow!("{arg} needs a value"));
+        match arg.as_str() {
+            "--baseline" => parsed.baseline = PathBuf::from(value()?),
+            "--region" => {
+                let text = value()?;
+                parsed.region =
+                    Some(Rect::parse(&text).ok_or_else(|| anyhow!("invalid region: {text}"))?);
+            }
+            "--threshold" => {
+                let text = value()?;
+                parsed.threshold = text
+                    .parse()
+                    .ok()
+                    .filter(|t: &f64| (0.0..=1.0).contains(t))
+                    .ok_or_else(|| anyhow!("threshold must be between 0 and 1: {text}"))?;
+            }
+            "--tolerance" => {
+                let text = value()?;
+                parsed.tolerance = text
+                    .parse()
+                    .map_err(|_| anyhow!("tolerance must be 0..=255: {text}"))?;
+            }
+            "--diff" => parsed.diff = Some(PathBuf::from(value()?)),
+            "--update" => parsed.update = true,
+            other => return Err(anyhow!("unknown argument: {other}")),
+        }
+    }
+    if parsed.baseline.as_os_str().is_empty() {
+        return Err(anyhow!("--baseline is required"));
+    }
+    Ok(parsed)
+}
+
+/// `snip_rust verify`：截图与基准图逐像素对比（视觉回归检查），不一致时写出差异热力图
+/// （默认 `<baseline>.diff.png`）。退出码：0 一致，1 不一致，2 参数 / 截图 / 读图错误
+pub fn verify(args: impl IntoIterator<Item = String>) -> ! {
+    let code = match run_verify(args) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("snip_rust verify: {e}\n{VERIFY_USAGE}");
+            2
+        }
+    };
+    std::process::exit(code)
+}
+
+fn run_verify(args: impl IntoIterator<Item = String>) -> Result<bool> {
+    let args = parse_verify(args)?;
+    let (width, height, rgba) = match args.region {
+        Some(rect) => (rect.width, rect.height, capture::capture_area_rgba(rect)?),
+        None => capture::capture_fullscreen_raw()?,
+    };
+    if args.update {
+        std::fs::write(&args.baseline, capture::encode_png(&rgba, width, height)?)
+            .map_err(|e| anyhow!("write {}: {e}", args.baseline.display()))?;
+        println!("baseline written to {}", args.baseline.display());
+        return Ok(true);
+    }
+    let baseline = image::open(&args.baseline)
+        .map_err(|e| anyhow!("open {}: {e}", args.baseline.display()))?
+        .to_rgba8();
+    let result = diff_heatmap(
+        (width, height, &rgba),
+        (baseline.width(), baseline.height(), baseline.as_raw()),
+        args.tolerance,
+    );
+    let same_size = (width, height) == baseline.dimensions();
+    let ratio = result.changed_ratio();
+    if same_size && ratio <= args.threshold {
+        println!(
+            "match: {} pixels differ ({:.3}%)",
+            result.changed,
+            ratio * 100.0
+        );
+        return Ok(true);
+    }
+    let diff = args
+        .diff
+        .unwrap_or_else(|| args.baseline.with_extension("diff.png"));
+    let png = capture::encode_png(&result.rgba, result.width, result.height)?;
+    std::fs::write(&diff, png).map_err(|e| anyhow!("write {}: {e}", diff.display()))?;
+    if same_size {
+        println!(
+            "mismatch: {} pixels differ ({:.3}% > {:.3}%), diff written to {}",
+            result.changed,
+            ratio * 100.0,
+            args.threshold * 100.0,
+            diff.display()
+        );
+    } else {
+        println!(
+            "mismatch: size {width}x{height} differs from baseline {}x{}, diff written to {}",
+            baseline.width(),
+            baseline.height(),
+            diff.display()
+        );
+    }
+    Ok(false)
+}
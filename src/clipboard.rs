@@ -0,0 +1,38 @@
+// clipboard: 系统剪贴板读写（arboard），像素统一使用 RGBA
+use anyhow::{anyhow, Result};
+use arboard::{Clipboard, ImageData};
+use std::borrow::Cow;
+
+/// 复制 RGBA 图像到剪贴板
+pub fn copy_rgba(width: u32, height: u32, rgba: &[u8]) -> Result<()> {
+    let mut cb = Clipboard::new().map_err(|e| anyhow!("open clipboard: {e}"))?;
+    cb.set_image(ImageData {
+        width: width as usize,
+        height: height as usize,
+        bytes: Cow::Borrowed(rgba),
+    })
+    .map_err(|e| anyhow!("set clipboard image: {e}"))
+}
+
+/// 复制文本到剪贴板
+pub fn copy_text(text: &str) -> Result<()> {
+    let mut cb = Clipboard::new().map_err(|e| anyhow!("open clipboard: {e}"))?;
+    cb.set_text(text)
+        .map_err(|e| anyhow!("set clipboard text: {e}"))
+}
+
+/// 读取剪贴板文本
+pub fn read_text() -> Result<String> {
+    let mut cb = Clipboard::new().map_err(|e| anyhow!("open clipboard: {e}"))?;
+    cb.get_text()
+        .map_err(|e| anyhow!("get clipboard text: {e}"))
+}
+
+/// 读取剪贴板图像，返回 (width, height, RGBA)
+pub fn read_rgba() -> Result<(u32, u32, Vec<u8>)> {
+    let mut cb = Clipboard::new().map_err(|e| anyhow!("open clipboard: {e}"))?;
+    let img = cb
+        .get_image()
+        .map_err(|e| anyhow!("get clipboard image: {e}"))?;
+    Ok((img.width as u32, img.height as u32, img.bytes.into_owned()))
+}
@@ -0,0 +1,249 @@
+// config: 用户配置（TOML），启动时加载一次；文件缺失或解析失败时回退默认值
+//
+// 位置：可执行文件旁的 snip_rust.toml（便携模式），否则 <config_dir>/snip_rust/snip_rust.toml
+// （Windows 为 %APPDATA%\snip_rust\snip_rust.toml），见 paths
+
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::burst::BurstConfig;
+use crate::formats::{AvifConfig, SaveFormat};
+use crate::fullscreen::FullscreenConfig;
+use crate::hooks::PostCaptureHook;
+use crate::hotkey::{
+    DoublePressConfig, HotkeyBackend, HotkeyMode, DEFAULT_FALLBACKS, DEFAULT_HOTKEY,
+};
+use crate::logging::LogLevel;
+use crate::optimize::PngOptimize;
+use crate::overlay::drawing::SelectionGuides;
+use crate::overlay::regions::RegionLayout;
+use crate::overlay::theme::{Theme, ThemeMode, UiScale};
+use crate::overlay::toolbar::{ToolButton, ToolbarSpec};
+use crate::postprocess::ExportScale;
+use crate::share::ShareMethod;
+use crate::sound::SoundConfig;
+use crate::timelapse::TimelapseConfig;
+use crate::webdav::WebDavDestination;
+use crate::window_trigger::WindowTriggerConfig;
+
+/// 未配置 pin_groups 时的贴图分组
+pub const DEFAULT_PIN_GROUPS: [&str; 3] = ["分组 1", "分组 2", "分组 3"];
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// 保存 / 复制后执行的外部命令
+    pub post_capture: Vec<PostCaptureHook>,
+    /// 工具栏“上传”的 WebDAV 目标（[[webdav]]）；多个时上传前弹出选择菜单
+    pub webdav: Vec<WebDavDestination>,
+    /// 登录自启：Some 时启动即同步到系统设置；None 保持托盘中的手动选择
+    pub autostart: Option<bool>,
+    /// 截图热键（global-hotkey 语法，例如 "F4"、"Ctrl+Shift+A"），默认 F4
+    pub hotkey: Option<String>,
+    /// 首选热键被占用时依次尝试的备用按键；未设置时使用 hotkey::DEFAULT_FALLBACKS
+    pub fallback_hotkeys: Option<Vec<String>>,
+    /// 接管 PrintScreen（截图）与 Alt+PrintScreen（预选前台窗口）；
+    /// 系统设置把 PrintScreen 交给截图工具时会关闭该设置
+    pub print_screen: bool,
+    /// 热键实现：global（默认，RegisterHotKey）/ low_level（WH_KEYBOARD_LL 钩子）
+    pub hotkey_backend: HotkeyBackend,
+    /// 双击截图热键截取整个屏幕（[double_press]）；未设置时不区分双击
+    pub double_press: Option<DoublePressConfig>,
+    /// 截图热键触发方式：press（默认）/ hold（按住预览窗口，松开截取）
+    pub hotkey_mode: HotkeyMode,
+    /// 截图时排除粘贴窗口（优先 WDA_EXCLUDEFROMCAPTURE，不支持时截图期间临时隐藏）
+    pub exclude_pins_from_capture: bool,
+    /// 贴图使用柔和阴影与圆角（逐像素透明的分层窗口）代替 2 像素边框
+    pub pin_shadow: bool,
+    /// 在贴图上按 Esc：close（默认，关闭，可 Ctrl+Z 恢复）/ hide（隐藏）/ ignore（不处理）
+    pub pin_escape: PinEscape,
+    /// 低内存模式：overlay 关闭后释放截图缓冲（默认保留容量供下次截图复用）
+    pub low_memory: bool,
+    /// 保存 / 复制的 PNG 嵌入显示器 ICC 配置文件（广色域显示器上浏览器不再显示过饱和）
+    pub embed_icc_profile: bool,
+    /// 输出 PNG 不写入任何元数据（忽略 embed_icc_profile 与 png_comment）
+    pub strip_metadata: bool,
+    /// 写入输出 PNG 的注释（tEXt / iTXt "Comment" 块）
+    pub png_comment: Option<String>,
+    /// PNG 体积优化：off（默认）/ lossless（不超过 256 色时写调色板 PNG）/ quantize（量化为 256 色）；
+    /// 均使用最高压缩等级，较慢，编码时在屏幕右下角显示进度
+    pub png_optimize: PngOptimize,
+    /// 保存文件的格式：png（默认）/ avif（需以 --features avif 构建，否则回退为 png）；
+    /// hook、剪贴板与上传记录仍使用 PNG。AVIF 同样按 export_scale 缩放，但不含 ICC 配置文件与
+    /// png_comment，也不经 png_optimize；不支持 jxl（没有 JPEG XL 编码器，配置报错）
+    pub save_format: SaveFormat,
+    /// AVIF 画质与编码速度（[avif]）
+    pub avif: AvifConfig,
+    /// Ctrl 多选区导出方式：separate（默认，逐个保存 / 钉图）/ original / horizontal / vertical
+    pub multi_selection_layout: RegionLayout,
+    /// 保存 / 钉图 / 复制前自动裁掉选区四周的纯色边（工具栏“更多”→“自动裁边”可手动裁剪）
+    pub auto_trim: bool,
+    /// 导出圆角：保存 / 钉图 / 复制的选区图像四角裁成该半径（像素）的抗锯齿透明圆角
+    pub corner_radius: Option<u32>,
+    /// 导出缩放（[export_scale]）：保存 / 复制 / 分享前按百分比或最大宽高缩放，贴图不缩放
+    pub export_scale: ExportScale,
+    /// 贴图分组名称（贴图右键“分组”、托盘“贴图分组”）；未设置时为 DEFAULT_PIN_GROUPS
+    pub pin_groups: Option<Vec<String>>,
+    /// 实时选区：overlay 为半透明层盖在实时桌面上（选区内可直接操作下方程序），确认时才截图；
+    /// 默认 false 为先冻结画面再选区
+    pub live_selection: bool,
+    /// 选区内的构图辅助线：off（默认）/ thirds（三分网格）/ center（中心十字）/ all；overlay 中按 G 切换
+    pub selection_guides: SelectionGuides,
+    /// 拖动手柄调整选区时的最小宽高（像素），默认 4
+    pub min_selection_size: Option<u32>,
+    /// 拖出 / 调整 / 移动选区时吸附到该像素的网格（例如 8），按住 Ctrl 时暂时不吸附；未设置时关闭
+    pub selection_grid: Option<u32>,
+    /// overlay 配色：auto（默认，跟随 Windows 高对比度设置）/ default / high_contrast；
+    /// 高对比度时工具栏与选区使用系统颜色并加粗边框
+    pub overlay_theme: ThemeMode,
+    /// 减少动效（不做过渡动画）；未设置时跟随 Windows“显示动画”设置
+    pub reduced_motion: Option<bool>,
+    /// UI 缩放倍数（1.0–2.0，与系统 DPI 无关）：放大工具栏按钮、手柄、边框与贴图边框；默认 1.0
+    pub ui_scale: Option<f32>,
+    /// 保存有标注的选区时，另写未标注的底图（<name>.base.png）与笔画（<name>.annotations.json），
+    /// 之后可用 --edit 重新载入继续编辑
+    pub annotation_sidecar: bool,
+    /// 启动时在 HKCU 注册 .snip 工程文件关联（双击打开；仅 Windows）
+    pub associate_project_files: bool,
+    /// “用编辑器打开”使用的程序（截图临时文件路径作为唯一参数）；未设置时用系统默认图像编辑器
+    pub image_editor: Option<PathBuf>,
+    /// “分享”方式：sheet（默认，系统分享面板，失败时回退邮件）/ mail（默认邮件客户端新建带附件的邮件）
+    pub share: ShareMethod,
+    /// 保存 / 上传 hook 临时文件的文件名模板（不含扩展名），默认 snip_{timestamp}
+    pub filename_template: Option<String>,
+    /// 工具栏按钮及顺序（exit / pin / save / copy / annotate / adjust / data_uri / decode_qr / auto_trim / redact / save_project / open_with / share / image_info / upload）；
+    /// 选区太窄或未列出的按钮进入“更多”菜单，未设置时为 exit、pin、save、copy、annotate
+    pub toolbar: Option<Vec<ToolButton>>,
+    /// 文件日志级别：off / error / warn / info（默认）/ debug / trace；设置 RUST_LOG 时以环境变量为准
+    pub log_level: LogLevel,
+    /// 本地使用统计（截图 / 保存 / 复制 / 钉图次数与平均选区尺寸，只写入数据目录，不联网）
+    pub stats: bool,
+    /// 直接截图（托盘“截取窗口”等不经选区的截图）的默认动作：pin（默认，钉在原位置）/ save / copy
+    pub capture_action: CaptureAction,
+    /// “截取窗口”包含窗口阴影与透明边框（默认只截 DWM 可见边框内）
+    pub window_shadow: bool,
+    /// 定时截图（[timelapse] 表：间隔 / 目录 / 区域 / 张数与大小上限），由托盘“定时截图”启动
+    pub timelapse: TimelapseConfig,
+    /// 连拍（[burst] 表：帧数 / 时长 / 开始前等待），由托盘“连拍截图”或 IPC burst 触发
+    pub burst: BurstConfig,
+    /// 前台窗口变化时自动截图（[window_trigger] 表；未设置时关闭，修改后需重启）
+    pub window_trigger: Option<WindowTriggerConfig>,
+    /// 管理员权限窗口在前台时不提示“以管理员重启”（默认提示一次；修改后需重启）
+    pub ignore_elevated_windows: bool,
+    /// 全屏程序 / 演示在前台时隐藏贴图、忽略截图热键（[fullscreen] 表；未设置时关闭，修改后需重启）
+    pub fullscreen: Option<FullscreenConfig>,
+    /// 截图 / 复制 / 保存完成时的提示音（[sounds] 表，可逐项关闭；未设置时静音）
+    pub sounds: Option<SoundConfig>,
+    /// 热区 / 中键手势触发截图（[hot_corner] 表；需 hot_corner feature）
+    #[cfg(feature = "hot_corner")]
+    pub hot_corner: Option<crate::trigger::TriggerConfig>,
+}
+
+/// 不经 overlay 选区的截图结果如何处理
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureAction {
+    /// 钉图，位置与截取范围重合
+    #[default]
+    Pin,
+    /// 保存 PNG（与工具栏“保存”相同的目录与文件名模板）
+    Save,
+    /// 复制到剪贴板
+    Copy,
+}
+
+/// 贴图上按 Esc 的行为
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PinEscape {
+    /// 关闭贴图（进入“恢复关闭的贴图”缓存）
+    #[default]
+    Close,
+    /// 隐藏贴图，托盘“贴图分组”→“全部显示”重新显示
+    Hide,
+    /// 不处理
+    Ignore,
+}
+
+impl Config {
+    /// 截图热键与备用按键
+    pub fn hotkey_bindings(&self) -> (&str, Vec<String>) {
+        let wanted = self.hotkey.as_deref().unwrap_or(DEFAULT_HOTKEY);
+        let fallbacks = match &self.fallback_hotkeys {
+            Some(list) => list.clone(),
+            None => DEFAULT_FALLBACKS.iter().map(|s| s.to_string()).collect(),
+        };
+        (wanted, fallbacks)
+    }
+
+    /// 截图热键的双击判定间隔（interval_ms = 0 视为关闭）
+    pub fn double_press_window(&self) -> Option<std::time::Duration> {
+        self.double_press
+            .filter(|d| d.interval_ms > 0)
+            .map(|d| std::time::Duration::from_millis(d.interval_ms))
+    }
+
+    /// 贴图分组名称（去掉空白与重复项）
+    pub fn pin_group_names(&self) -> Vec<String> {
+        let names = match &self.pin_groups {
+            Some(names) => names.clone(),
+            None => DEFAULT_PIN_GROUPS.iter().map(|n| n.to_string()).collect(),
+        };
+        let mut out: Vec<String> = Vec::with_capacity(names.len());
+        for name in names {
+            let name = name.trim();
+            if !name.is_empty() && !out.iter().any(|n| n == name) {
+                out.push(name.to_string());
+            }
+        }
+        out
+    }
+
+    /// 工具栏按钮集合
+    pub fn toolbar_spec(&self) -> ToolbarSpec {
+        match &self.toolbar {
+            Some(buttons) => ToolbarSpec::new(buttons),
+            None => ToolbarSpec::default(),
+        }
+    }
+
+    /// overlay 主题（按当前系统高对比度 / 动画设置解析，每次打开 overlay 前调用）
+    pub fn theme(&self) -> Theme {
+        Theme::detect(self.overlay_theme, self.reduced_motion).scaled(self.ui_scale())
+    }
+
+    pub fn ui_scale(&self) -> UiScale {
+        self.ui_scale.map_or_else(UiScale::default, UiScale::new)
+    }
+
+    /// 配置文件路径（平台无配置目录时返回 None）
+    pub fn path() -> Option<PathBuf> {
+        crate::paths::config_file()
+    }
+
+    /// 读取配置；不存在 / 格式错误时记录日志并返回默认值，不阻止启动
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(t) => t,
+            Err(_) => return Self::default(),
+        };
+        match Self::parse(&text) {
+            Ok(cfg) => {
+                log::debug!("config loaded from {}", path.display());
+                cfg
+            }
+            Err(e) => {
+                log::warn!("invalid config {}: {e}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        toml::from_str(text).map_err(|e| anyhow::anyhow!("parse config: {e}"))
+    }
+}
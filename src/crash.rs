@@ -0,0 +1,326 @@
+// crash: panic 钩子（崩溃日志 + 贴图抢救）与下次启动时的贴图恢复
+//
+// 发布版为 windows 子系统，panic 信息没有控制台可看：钩子把消息、位置与 backtrace 写入
+// <数据目录>/crash-<unix秒>.log（见 paths），并把当前贴图保存到 recovered/（PNG + pins.json），
+// 下次启动时恢复为贴图后删除。贴图通过 register_pin / update_pin / unregister_pin 登记，
+// 像素以 Arc 共享，登记不复制图像。
+// 以管理员身份重启时同样把贴图保存到 session/（save_session），新进程启动后恢复。
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::paste_window::PinTag;
+use crate::paths;
+
+const RECOVERED_DIR: &str = "recovered";
+const SESSION_DIR: &str = "session";
+const MANIFEST: &str = "pins.json";
+
+/// 登记的贴图快照（位置为图像左上角的屏幕物理像素坐标，像素为 BGRA u32）
+#[derive(Clone)]
+pub struct PinSnapshot {
+    pub id: u64,
+    pub pos: (i32, i32),
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Arc<[u32]>,
+    /// 贴图分组（见 update_pin_group）
+    pub group: Option<String>,
+    /// 颜色标记（见 update_pin_tag）
+    pub tag: Option<PinTag>,
+}
+
+/// 待恢复的贴图（RGBA）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveredPin {
+    pub pos: (i32, i32),
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+    pub group: Option<String>,
+    pub tag: Option<PinTag>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestEntry {
+    file: String,
+    x: i32,
+    y: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tag: Option<PinTag>,
+}
+
+static PINS: Mutex<Vec<PinSnapshot>> = Mutex::new(Vec::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 登记贴图，返回用于更新 / 注销的 id
+pub fn register_pin(pos: (i32, i32), width: u32, height: u32, pixels: Arc<[u32]>) -> u64 {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut pins) = PINS.lock() {
+        pins.push(PinSnapshot {
+            id,
+            pos,
+            width,
+            height,
+            pixels,
+            group: None,
+            tag: None,
+        });
+    }
+    id
+}
+
+/// 更新贴图位置（拖动结束后调用）
+pub fn update_pin(id: u64, pos: (i32, i32)) {
+    if let Ok(mut pins) = PINS.lock() {
+        if let Some(pin) = pins.iter_mut().find(|p| p.id == id) {
+            pin.pos = pos;
+        }
+    }
+}
+
+/// 更新贴图分组（右键“分组”后调用）
+pub fn update_pin_group(id: u64, group: Option<String>) {
+    if let Ok(mut pins) = PINS.lock() {
+        if let Some(pin) = pins.iter_mut().find(|p| p.id == id) {
+            pin.group = group;
+        }
+    }
+}
+
+/// 更新贴图颜色标记（右键“颜色标记”后调用）
+pub fn update_pin_tag(id: u64, tag: Option<PinTag>) {
+    if let Ok(mut pins) = PINS.lock() {
+        if let Some(pin) = pins.iter_mut().find(|p| p.id == id) {
+            pin.tag = tag;
+        }
+    }
+}
+
+/// 当前登记的全部贴图（像素共享，不复制图像），保存工作区用
+pub fn registered_pins() -> Vec<PinSnapshot> {
+    PINS.lock().map(|pins| pins.clone()).unwrap_or_default()
+}
+
+pub fn unregister_pin(id: u64) {
+    if let Ok(mut pins) = PINS.lock() {
+        pins.retain(|p| p.id != id);
+    }
+}
+
+/// 安装 panic 钩子；之前的钩子（默认打印到 stderr）仍会被调用
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Some(dir) = paths::data_dir() {
+            match write_crash_log(&dir, info) {
+                Ok(path) => log::error!("crash log written to {}", path.display()),
+                Err(e) => log::error!("write crash log: {e}"),
+            }
+            // panic 可能发生在持有锁时：拿不到锁就放弃抢救，不能在钩子里死锁
+            if let Ok(pins) = PINS.try_lock() {
+                if !pins.is_empty() {
+                    if let Err(e) = save_pins(&dir.join(RECOVERED_DIR), &pins) {
+                        log::error!("save pins for recovery: {e}");
+                    }
+                }
+            }
+        }
+        previous(info);
+    }));
+}
+
+fn write_crash_log(dir: &Path, info: &PanicHookInfo) -> Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let thread = std::thread::current();
+    let report = format!(
+        "snip_rust {} crashed at {timestamp}\nthread '{}' {info}\n\nbacktrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        thread.name().unwrap_or("<unnamed>"),
+        std::backtrace::Backtrace::force_capture(),
+    );
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("crash-{timestamp}.log"));
+    fs::write(&path, report)?;
+    Ok(path)
+}
+
+/// 把贴图写为 pin_<序号>.png 并生成清单；覆盖上一次未恢复的内容
+pub fn save_pins(dir: &Path, pins: &[PinSnapshot]) -> Result<()> {
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    fs::create_dir_all(dir)?;
+    let mut manifest = Vec::with_capacity(pins.len());
+    for (i, pin) in pins.iter().enumerate() {
+        let mut rgba = Vec::with_capacity(pin.pixels.len() * 4);
+        for px in pin.pixels.iter() {
+            let [b, g, r, a] = px.to_le_bytes();
+            rgba.extend_from_slice(&[r, g, b, a]);
+        }
+        let png = crate::capture::encode_png(&rgba, pin.width, pin.height)?;
+        let file = format!("pin_{i}.png");
+        fs::write(dir.join(&file), png)?;
+        manifest.push(ManifestEntry {
+            file,
+            x: pin.pos.0,
+            y: pin.pos.1,
+            group: pin.group.clone(),
+            tag: pin.tag,
+        });
+    }
+    fs::write(dir.join(MANIFEST), serde_json::to_string_pretty(&manifest)?)?;
+    Ok(())
+}
+
+/// 读取清单中的贴图；单个文件损坏时跳过
+pub fn load_pins(dir: &Path) -> Result<Vec<RecoveredPin>> {
+    let text = fs::read_to_string(dir.join(MANIFEST))?;
+    let manifest: Vec<ManifestEntry> =
+        serde_json::from_str(&text).map_err(|e| anyhow!("invalid recovery manifest: {e}"))?;
+    let mut out = Vec::with_capacity(manifest.len());
+    for entry in manifest {
+        let decoded = fs::read(dir.join(&entry.file))
+            .map_err(anyhow::Error::from)
+            .and_then(|png| Ok(image::load_from_memory(&png)?.to_rgba8()));
+        match decoded {
+            Ok(img) => out.push(RecoveredPin {
+                pos: (entry.x, entry.y),
+                width: img.width(),
+                height: img.height(),
+                rgba: img.into_raw(),
+                group: entry.group,
+                tag: entry.tag,
+            }),
+            Err(e) => log::warn!("skip recovered pin {}: {e}", entry.file),
+        }
+    }
+    Ok(out)
+}
+
+/// 取出上次崩溃时保存的贴图并删除恢复目录（没有时返回空）
+pub fn take_recovered_pins() -> Vec<RecoveredPin> {
+    take_pins(RECOVERED_DIR)
+}
+
+/// 重启前保存当前全部贴图（覆盖上一次的），返回贴图数
+pub fn save_session() -> Result<usize> {
+    let dir = paths::data_dir()
+        .ok_or_else(|| anyhow!("no data directory"))?
+        .join(SESSION_DIR);
+    let pins = registered_pins();
+    save_pins(&dir, &pins)?;
+    Ok(pins.len())
+}
+
+/// 重启没有发生（UAC 提示被拒绝）时删除 save_session 的内容
+pub fn discard_session() {
+    if let Some(dir) = paths::data_dir().map(|d| d.join(SESSION_DIR)) {
+        let _ = fs::remove_dir_all(dir);
+    }
+}
+
+/// 取出重启前保存的贴图并删除目录（没有时返回空）
+pub fn take_session_pins() -> Vec<RecoveredPin> {
+    take_pins(SESSION_DIR)
+}
+
+fn take_pins(name: &str) -> Vec<RecoveredPin> {
+    let Some(dir) = paths::data_dir().map(|d| d.join(name)) else {
+        return Vec::new();
+    };
+    if !dir.exists() {
+        return Vec::new();
+    }
+    let pins = load_pins(&dir).unwrap_or_else(|e| {
+        log::warn!("load recovered pins: {e}");
+        Vec::new()
+    });
+    if let Err(e) = fs::remove_dir_all(&dir) {
+        log::warn!("remove {}: {e}", dir.display());
+    }
+    pins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_save_and_load_pins_roundtrip() {
+        let dir = TempDir::new("crash_test");
+        // BGRA：蓝色不透明、半透明红色
+        let pixels: Arc<[u32]> = Arc::from(vec![
+            u32::from_le_bytes([255, 0, 0, 255]),
+            u32::from_le_bytes([0, 0, 255, 128]),
+        ]);
+        let pins = [
+            PinSnapshot {
+                id: 1,
+                pos: (-100, 40),
+                width: 2,
+                height: 1,
+                pixels: pixels.clone(),
+                group: Some("任务 A".into()),
+                tag: Some(PinTag::Blue),
+            },
+            PinSnapshot {
+                id: 2,
+                pos: (300, 200),
+                width: 1,
+                height: 2,
+                pixels,
+                group: None,
+                tag: None,
+            },
+        ];
+        save_pins(&dir, &pins).unwrap();
+        let loaded = load_pins(&dir).unwrap();
+        fs::remove_dir_all(&dir).ok();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].pos, (-100, 40));
+        assert_eq!((loaded[0].width, loaded[0].height), (2, 1));
+        assert_eq!(loaded[0].rgba, [0, 0, 255, 255, 255, 0, 0, 128]);
+        assert_eq!(loaded[0].group.as_deref(), Some("任务 A"));
+        assert_eq!(loaded[1].group, None);
+        assert_eq!(loaded[0].tag, Some(PinTag::Blue));
+        assert_eq!(loaded[1].tag, None);
+        assert_eq!(
+            (loaded[1].pos, loaded[1].width, loaded[1].height),
+            ((300, 200), 1, 2)
+        );
+    }
+
+    #[test]
+    fn test_registry_tracks_pins() {
+        let id = register_pin((1, 2), 1, 1, Arc::from(vec![0u32]));
+        update_pin(id, (5, 6));
+        let pos = |id| {
+            PINS.lock()
+                .unwrap()
+                .iter()
+                .find(|p| p.id == id)
+                .map(|p| p.pos)
+        };
+        assert_eq!(pos(id), Some((5, 6)));
+        update_pin_group(id, Some("A".into()));
+        let snapshot = registered_pins().into_iter().find(|p| p.id == id).unwrap();
+        assert_eq!(snapshot.group.as_deref(), Some("A"));
+        update_pin_tag(id, Some(PinTag::Red));
+        let snapshot = registered_pins().into_iter().find(|p| p.id == id).unwrap();
+        assert_eq!(snapshot.tag, Some(PinTag::Red));
+        unregister_pin(id);
+        assert_eq!(pos(id), None);
+    }
+}
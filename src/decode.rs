@@ -0,0 +1,55 @@
+// decode: 选区内二维码识别（rqrr，纯 CPU，无需 image 解码）
+//
+// 输入 RGBA，转灰度后检测全部 QR 网格并逐个解码；解码失败的网格跳过。
+// 目前只支持 QR 码，一维条码需要另外的识别库。
+
+use rqrr::PreparedImage;
+
+use crate::filters::luma;
+
+/// 识别 RGBA 图像中的全部二维码，按检测顺序返回文本
+pub fn decode_qr(width: u32, height: u32, rgba: &[u8]) -> Vec<String> {
+    let (w, h) = (width as usize, height as usize);
+    if w == 0 || h == 0 || rgba.len() < w * h * 4 {
+        return Vec::new();
+    }
+    let mut img = PreparedImage::prepare_from_greyscale(w, h, |x, y| {
+        let i = (y * w + x) * 4;
+        luma(rgba[i], rgba[i + 1], rgba[i + 2])
+    });
+    img.detect_grids()
+        .into_iter()
+        .filter_map(|grid| match grid.decode() {
+            Ok((_, text)) => Some(text),
+            Err(e) => {
+                log::debug!("qr grid decode failed: {e:?}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// 可直接交给浏览器打开的网址
+pub fn is_url(text: &str) -> bool {
+    let t = text.trim();
+    (t.starts_with("https://") || t.starts_with("http://")) && !t.contains(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_qr_blank_and_invalid() {
+        assert!(decode_qr(16, 16, &[255u8; 16 * 16 * 4]).is_empty());
+        assert!(decode_qr(16, 16, &[0u8; 8]).is_empty()); // 缓冲不足
+    }
+
+    #[test]
+    fn test_is_url() {
+        assert!(is_url("https://example.com/a?b=1"));
+        assert!(is_url(" http://x.y \n"));
+        assert!(!is_url("ftp://x"));
+        assert!(!is_url("https://a b"));
+    }
+}
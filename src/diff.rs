@@ -0,0 +1,106 @@
+// diff: 两张 RGBA 图像的逐像素对比（贴图对比 / UI 回归检查）
+//
+// 输出热力图：未变化像素为原图灰度并压暗，变化像素按差异幅度从黄到红着色；
+// 尺寸不同时按左上角对齐，只在一张图中存在的区域视为完全不同，两张都不覆盖的角落透明。
+use crate::filters::luma;
+
+/// 对比结果
+pub struct DiffResult {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>, // 热力图
+    pub changed: u64,  // 差异超过阈值的像素数
+}
+
+impl DiffResult {
+    /// 差异像素占比（0.0 ~ 1.0）
+    pub fn changed_ratio(&self) -> f64 {
+        let total = self.width as u64 * self.height as u64;
+        if total == 0 {
+            0.0
+        } else {
+            self.changed as f64 / total as f64
+        }
+    }
+}
+
+/// 一张待对比图像：(width, height, RGBA)
+pub type Image<'a> = (u32, u32, &'a [u8]);
+
+/// 逐像素对比 a / b；通道最大差值 <= threshold 视为相同（容忍编码 / 缩放噪声）
+pub fn diff_heatmap(a: Image, b: Image, threshold: u8) -> DiffResult {
+    let (aw, ah, ap) = a;
+    let (bw, bh, bp) = b;
+    let (width, height) = (aw.max(bw), ah.max(bh));
+    let mut rgba = vec![0u8; width as usize * height as usize * 4];
+    let mut changed = 0u64;
+    let pixel = |w: u32, h: u32, px: &[u8], x: u32, y: u32| -> Option<[u8; 4]> {
+        if x >= w || y >= h {
+            return None;
+        }
+        let i = (y as usize * w as usize + x as usize) * 4;
+        px.get(i..i + 4).map(|p| [p[0], p[1], p[2], p[3]])
+    };
+    for y in 0..height {
+        for x in 0..width {
+            let pa = pixel(aw, ah, ap, x, y);
+            let pb = pixel(bw, bh, bp, x, y);
+            let o = (y as usize * width as usize + x as usize) * 4;
+            let out = &mut rgba[o..o + 4];
+            let delta = match (pa, pb) {
+                (Some(pa), Some(pb)) => (0..4).map(|c| pa[c].abs_diff(pb[c])).max().unwrap_or(0),
+                (None, None) => continue, // 两张图都不覆盖的角落保持透明
+                _ => u8::MAX,
+            };
+            if delta > threshold {
+                changed += 1;
+                out.copy_from_slice(&heat(delta));
+            } else if let Some(base) = pa {
+                let l = luma(base[0], base[1], base[2]) / 3;
+                out.copy_from_slice(&[l, l, l, 255]);
+            }
+        }
+    }
+    DiffResult {
+        width,
+        height,
+        rgba,
+        changed,
+    }
+}
+
+// 差异幅度 -> 颜色：小差异偏黄，大差异纯红
+fn heat(delta: u8) -> [u8; 4] {
+    [255, 255 - delta / 2 - delta / 4, 0, 255]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_identical_and_threshold() {
+        let a = [10u8, 20, 30, 255].repeat(4);
+        let mut b = a.clone();
+        b[0] = 14; // 小于阈值的噪声
+        let r = diff_heatmap((2, 2, &a), (2, 2, &b), 8);
+        assert_eq!(r.changed, 0);
+        assert_eq!(&r.rgba[..4], &[6, 6, 6, 255]);
+        b[4] = 200;
+        let r = diff_heatmap((2, 2, &a), (2, 2, &b), 8);
+        assert_eq!(r.changed, 1);
+        assert_eq!(r.rgba[4..8], heat(190));
+        assert!((r.changed_ratio() - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_diff_size_mismatch() {
+        let a = vec![0u8; 2 * 4];
+        let b = vec![0u8; 2 * 4];
+        let r = diff_heatmap((2, 1, &a), (1, 2, &b), 0);
+        assert_eq!((r.width, r.height), (2, 2));
+        // (1,0) 只在 a 中，(0,1) 只在 b 中；(1,1) 都不覆盖，透明且不计入
+        assert_eq!(r.changed, 2);
+        assert_eq!(r.rgba[12..16], [0, 0, 0, 0]);
+    }
+}
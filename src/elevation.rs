@@ -0,0 +1,38 @@
+// elevation: 管理员权限窗口提示
+//
+// 未提升权限的进程收不到管理员窗口（以及 UAC 提示 / 锁屏等安全桌面）上的键鼠输入（UIPI），
+// 低级钩子热键与 overlay 在这些窗口前台时没有任何反应。订阅共用的前台窗口钩子
+// （window_trigger::subscribe），启动时与每次前台切换后在后台线程查询前台窗口的完整性级别，
+// 第一次发现比本进程高时调用 on_detect（每次运行只提示一次），由 app 询问是否以管理员身份重启
+// （贴图经 crash::save_session 保留）。读不到完整性级别（打不开进程或令牌）视为未知，不提示。
+use anyhow::{anyhow, Result};
+use std::sync::mpsc;
+
+use crate::window_trigger::{self, ForegroundEvent};
+use crate::windows_util;
+
+/// 启动检测线程；本进程已提升权限时不启动
+pub fn spawn(on_detect: impl FnOnce() + Send + 'static) -> Result<()> {
+    if windows_util::is_elevated() {
+        return Ok(());
+    }
+    let (tx, rx) = mpsc::channel();
+    window_trigger::subscribe(false, move |event| {
+        if let ForegroundEvent::Activated(_) = event {
+            let _ = tx.send(());
+        }
+    })?;
+    std::thread::Builder::new()
+        .name("snip-elevation".into())
+        .spawn(move || {
+            // 提示过后不再检查，后续事件留在通道里丢弃
+            while !windows_util::foreground_elevated() {
+                if rx.recv().is_err() {
+                    return;
+                }
+            }
+            on_detect();
+        })
+        .map(|_| ())
+        .map_err(|e| anyhow!("spawn elevation watcher: {e}"))
+}
@@ -0,0 +1,414 @@
+// encode: 后台 PNG 编码队列
+//
+// 保存 / 复制 hook 需要的 PNG 编码放到截图线程池执行，避免大选区在事件处理中卡顿。
+// submit 立即返回 JobId；任务完成后经 notify 唤醒事件循环（winit user event），
+// 主循环再 try_recv 取回结果在主线程处理。png_optimize 开启时改用 optimize::encode_png
+// （调色板 / 量化 + 最高压缩等级，慢得多），编码中经 progress 回调报告进度。save_format 不是
+// png 时，Save 另按该格式编码写入文件（见 formats），内存中的 PNG 仍交给 hook 使用。
+
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+
+use crate::capture::insert_png_text;
+use crate::capture_async;
+use crate::formats::{self, AvifConfig, SaveFormat};
+use crate::naming::Namer;
+use crate::optimize::{self, PngOptimize};
+
+pub type JobId = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodePurpose {
+    /// 编码后写入文件
+    Save,
+    /// 仅编码（供 copy hook 使用）
+    Copy,
+    /// 编码后以 data:image/png;base64 文本写入剪贴板
+    DataUri,
+    /// 编码后写入临时文件交给图像编辑器（open_with）
+    Edit,
+    /// 编码后写入临时文件交给分享面板 / 邮件（share）
+    Share,
+    /// 编码后上传到 WebDAV 目标（webdav）
+    Upload,
+}
+
+pub struct EncodeOutput {
+    pub png: Vec<u8>,
+    pub path: Option<PathBuf>, // Save 时写入的文件
+    pub name: String,          // 按文件名模板生成的名称（无扩展名；DataUri 为空）
+}
+
+pub struct EncodeDone {
+    pub id: JobId,
+    pub purpose: EncodePurpose,
+    pub width: u32,
+    pub height: u32,
+    pub result: Result<EncodeOutput>,
+}
+
+type Notify = Arc<dyn Fn() + Send + Sync>;
+type Progress = Arc<dyn Fn(JobId, f32) + Send + Sync>;
+
+pub struct EncodeQueue {
+    tx: Sender<EncodeDone>,
+    rx: Receiver<EncodeDone>,
+    next_id: JobId,
+    pending: usize, // 已提交但尚未被 try_recv / drain 取走的任务数
+    notify: Option<Notify>,
+    progress: Option<Progress>,
+    optimize: PngOptimize,
+    format: SaveFormat, // Save 写入文件的格式
+    avif: AvifConfig,
+    icc: Option<Arc<[u8]>>,    // 嵌入输出 PNG 的显示器 ICC 配置文件
+    comment: Option<Arc<str>>, // 写入 tEXt/iTXt "Comment" 块的文本
+    namer: Namer,
+}
+
+impl Default for EncodeQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EncodeQueue {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            tx,
+            rx,
+            next_id: 1,
+            pending: 0,
+            notify: None,
+            progress: None,
+            optimize: PngOptimize::Off,
+            format: SaveFormat::Png,
+            avif: AvifConfig::default(),
+            icc: None,
+            comment: None,
+            namer: Namer::default(),
+        }
+    }
+
+    /// 任务完成时在工作线程调用（通常为 EventLoopProxy::send_event）
+    pub fn with_notify(mut self, notify: impl Fn() + Send + Sync + 'static) -> Self {
+        self.notify = Some(Arc::new(notify));
+        self
+    }
+
+    /// 之后编码的 PNG 按该方式优化体积（见 optimize）
+    pub fn with_png_optimize(mut self, optimize: PngOptimize) -> Self {
+        self.optimize = optimize;
+        self
+    }
+
+    /// 优化编码的进度（0..=1）在工作线程回调；默认编码不报告进度
+    pub fn with_progress(mut self, progress: impl Fn(JobId, f32) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    /// Save 写入文件的格式（未编译进来的格式回退为 PNG）
+    pub fn with_save_format(mut self, format: SaveFormat, avif: AvifConfig) -> Self {
+        self.format = format.resolve();
+        self.avif = avif;
+        self
+    }
+
+    /// 之后编码的 PNG 都嵌入该 ICC 配置文件
+    pub fn with_icc_profile(mut self, icc: Vec<u8>) -> Self {
+        self.icc = Some(icc.into());
+        self
+    }
+
+    /// 之后编码的 PNG 都写入该注释（空串忽略）
+    pub fn with_comment(mut self, comment: String) -> Self {
+        self.comment = (!comment.is_empty()).then(|| comment.into());
+        self
+    }
+
+    /// 保存 / hook 临时文件的文件名模板（见 naming）
+    pub fn with_name_template(mut self, template: &str) -> Self {
+        self.namer = Namer::new(template);
+        self
+    }
+
+    /// 按文件名模板生成下一个名称（工程文件等不经编码队列保存的输出）
+    pub fn next_name(&self, width: u32, height: u32) -> String {
+        self.namer.next(width, height)
+    }
+
+    /// 截图开始时记录来源（前台程序、显示器序号），供 {app} / {monitor} 使用
+    pub fn set_name_source(&mut self, app: Option<String>, monitor: u32) {
+        self.namer.set_source(app, monitor);
+    }
+
+    /// 提交 RGBA 编码任务（像素所有权移入工作线程，不复制）
+    pub fn submit(
+        &mut self,
+        purpose: EncodePurpose,
+        width: u32,
+        height: u32,
+        rgba: Vec<u8>,
+    ) -> JobId {
+        let id = self.next_id;
+        self.next_id += 1;
+        let tx = self.tx.clone();
+        let notify = self.notify.clone();
+        let progress = self.progress.clone();
+        let format = self.format;
+        let avif = self.avif;
+        // 保存为其它格式时 PNG 只给 hook 使用，不必优化；复制 / Data URI 要立即可用，也不走慢速优化
+        let mode = match purpose {
+            EncodePurpose::Save if format != SaveFormat::Png => PngOptimize::Off,
+            EncodePurpose::Copy | EncodePurpose::DataUri => PngOptimize::Off,
+            _ => self.optimize,
+        };
+        let icc = self.icc.clone();
+        let comment = self.comment.clone();
+        // 在主线程生成名称：{counter} 按提交顺序递增
+        let name = match purpose {
+            EncodePurpose::Save
+            | EncodePurpose::Copy
+            | EncodePurpose::Edit
+            | EncodePurpose::Share
+            | EncodePurpose::Upload => self.namer.next(width, height),
+            EncodePurpose::DataUri => String::new(),
+        };
+        let job = Box::new(move || {
+            let encoded = optimize::encode_png(&rgba, width, height, mode, icc.as_deref(), |f| {
+                if let Some(progress) = &progress {
+                    progress(id, f);
+                }
+            });
+            let result = encoded.and_then(|mut png| {
+                if let Some(comment) = &comment {
+                    insert_png_text(&mut png, "Comment", comment)?;
+                }
+                let path = match purpose {
+                    EncodePurpose::Save => Some(match format {
+                        SaveFormat::Png => save_png_auto(&png, &name)?,
+                        other => {
+                            let data = formats::encode(other, &rgba, width, height, avif)?;
+                            save_file_auto(&data, &name, other.extension())?
+                        }
+                    }),
+                    EncodePurpose::Copy
+                    | EncodePurpose::DataUri
+                    | EncodePurpose::Edit
+                    | EncodePurpose::Share
+                    | EncodePurpose::Upload => None,
+                };
+                Ok(EncodeOutput { png, path, name })
+            });
+            let _ = tx.send(EncodeDone {
+                id,
+                purpose,
+                width,
+                height,
+                result,
+            });
+            if let Some(notify) = notify {
+                notify();
+            }
+        });
+        if capture_async::execute(job) {
+            self.pending += 1;
+        } else {
+            log::warn!("encode job #{id} dropped: worker pool unavailable");
+        }
+        log::debug!("encode job #{id} submitted ({purpose:?} {width}x{height})");
+        id
+    }
+
+    /// 非阻塞取出一个已完成任务
+    pub fn try_recv(&mut self) -> Option<EncodeDone> {
+        let done = self.rx.try_recv().ok()?;
+        self.pending = self.pending.saturating_sub(1);
+        Some(done)
+    }
+
+    /// 退出前等待未完成的任务（最多 timeout），返回期间完成的结果
+    pub fn drain(&mut self, timeout: std::time::Duration) -> Vec<EncodeDone> {
+        let deadline = std::time::Instant::now() + timeout;
+        let mut out = Vec::new();
+        while self.pending > 0 {
+            let left = deadline.saturating_duration_since(std::time::Instant::now());
+            match self.rx.recv_timeout(left) {
+                Ok(done) => {
+                    self.pending -= 1;
+                    out.push(done);
+                }
+                Err(_) => {
+                    log::warn!("{} encode job(s) unfinished at exit", self.pending);
+                    break;
+                }
+            }
+        }
+        out
+    }
+}
+
+/// 写入当前工作目录 <name>.png；同名文件已存在时依次尝试 <name>_2.png、<name>_3.png …
+pub fn save_png_auto(data: &[u8], name: &str) -> Result<PathBuf> {
+    save_file_auto(data, name, "png")
+}
+
+/// 同 save_png_auto，扩展名为 ext（例如工程文件 snip）
+pub fn save_file_auto(data: &[u8], name: &str, ext: &str) -> Result<PathBuf> {
+    use std::fs::OpenOptions;
+    use std::io::{ErrorKind, Write};
+    for n in 1u32.. {
+        let path = if n == 1 {
+            PathBuf::from(format!("{name}.{ext}"))
+        } else {
+            PathBuf::from(format!("{name}_{n}.{ext}"))
+        };
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(data)
+                    .map_err(|e| anyhow!("write {ext}: {e}"))?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists && n < 1000 => continue,
+            Err(e) => return Err(anyhow!("write {ext}: {e}")),
+        }
+    }
+    unreachable!()
+}
+
+/// PNG -> `data:image/png;base64,...`（HTML / Markdown / devtools 可直接粘贴）
+pub fn png_data_uri(png: &[u8]) -> String {
+    const PREFIX: &str = "data:image/png;base64,";
+    let mut out = String::with_capacity(PREFIX.len() + png.len().div_ceil(3) * 4);
+    out.push_str(PREFIX);
+    base64_encode_into(png, &mut out);
+    out
+}
+
+/// 标准 base64（RFC 4648，带 = 填充）
+pub fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    base64_encode_into(data, &mut out);
+    out
+}
+
+fn base64_encode_into(data: &[u8], out: &mut String) {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn test_copy_job_completes_and_notifies() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let mut queue = EncodeQueue::new().with_notify(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        let id = queue.submit(EncodePurpose::Copy, 2, 1, vec![255; 8]);
+        let done = queue.rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(done.id, id);
+        assert_eq!((done.width, done.height), (2, 1));
+        let out = done.result.unwrap();
+        assert!(out.path.is_none());
+        assert_eq!(&out.png[..8], b"\x89PNG\r\n\x1a\n");
+        // notify 在结果发送之后调用
+        for _ in 0..100 {
+            if hits.load(Ordering::SeqCst) == 1 {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("notify not called");
+    }
+
+    #[test]
+    fn test_drain_waits_for_pending_jobs() {
+        let mut queue = EncodeQueue::new();
+        queue.submit(EncodePurpose::Copy, 1, 1, vec![0; 4]);
+        queue.submit(EncodePurpose::Copy, 2, 1, vec![0; 8]);
+        let done = queue.drain(Duration::from_secs(5));
+        assert_eq!(done.len(), 2);
+        assert_eq!(queue.pending, 0);
+        assert!(queue.drain(Duration::from_millis(1)).is_empty());
+    }
+
+    #[test]
+    fn test_comment_written_to_png() {
+        let mut queue = EncodeQueue::new().with_comment("from snip".into());
+        queue.submit(EncodePurpose::Copy, 1, 1, vec![0; 4]);
+        let done = queue.rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let png = done.result.unwrap().png;
+        let needle = b"tEXtComment\0from snip";
+        assert!(png.windows(needle.len()).any(|w| w == needle));
+        assert!(EncodeQueue::new()
+            .with_comment(String::new())
+            .comment
+            .is_none());
+    }
+
+    #[test]
+    fn test_optimized_job_reports_progress() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let mut queue = EncodeQueue::new()
+            .with_png_optimize(PngOptimize::Lossless)
+            .with_progress(move |id, f| log.lock().unwrap().push((id, f)));
+        let id = queue.submit(EncodePurpose::Upload, 2, 1, vec![255; 8]);
+        let done = queue.rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let png = done.result.unwrap().png;
+        // 调色板 PNG：IHDR 颜色类型为 3
+        assert_eq!(png[25], 3);
+        {
+            let seen = seen.lock().unwrap();
+            assert!(seen.iter().all(|&(job, _)| job == id));
+            assert_eq!(seen.last().map(|&(_, f)| f), Some(1.0));
+        }
+        // 复制不等待优化：默认 RGBA 编码（颜色类型 6），不报告进度
+        let before = seen.lock().unwrap().len();
+        queue.submit(EncodePurpose::Copy, 2, 1, vec![255; 8]);
+        let done = queue.rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(done.result.unwrap().png[25], 6);
+        assert_eq!(seen.lock().unwrap().len(), before);
+    }
+
+    #[test]
+    fn test_png_data_uri_base64() {
+        let mut s = String::new();
+        for (input, expect) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foobar", "Zm9vYmFy"),
+            (&[0xFB, 0xFF], "+/8="),
+        ] {
+            s.clear();
+            base64_encode_into(input, &mut s);
+            assert_eq!(s, expect);
+        }
+        assert_eq!(png_data_uri(b"foo"), "data:image/png;base64,Zm9v");
+    }
+}
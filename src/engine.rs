@@ -0,0 +1,326 @@
+// engine: 嵌入用的截图流程外观（SnipEngine）
+//
+// 其它 winit 应用不依赖本程序的主循环（app.rs 的托盘 / 热键 / 贴图）也能使用 overlay 选区：
+// 宿主在自己的 ApplicationHandler 中调用 start_capture，把窗口事件转给 handle_window_event、
+// 把 muda 菜单事件转给 handle_menu，结果以 SnipEvent 回调。钉图 / 保存 / 复制等导出动作统一转为
+// SelectionConfirmed + ImageReady（PNG 字节，同步编码），由宿主决定如何处理；overlay 随即关闭。
+// 只需要坐标时用拾取模式（pick_only / pick_region），不裁剪、不编码；命令行一次性截图用 capture_png。
+use anyhow::{anyhow, Result};
+use muda::{MenuEvent, MenuId};
+use std::cell::RefCell;
+use std::rc::Rc;
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop},
+    window::{CursorIcon, WindowId},
+};
+
+use crate::capture::{capture_raw_into_from, encode_png, CaptureBackend, Rect, ScreenBackend};
+use crate::overlay::toolbar::ToolbarSpec;
+use crate::overlay::{OverlayAction, OverlayState};
+
+/// 回调给宿主的事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnipEvent {
+    /// overlay 已显示，等待用户选区
+    CaptureStarted,
+    /// 用户确认导出，矩形为屏幕物理像素坐标
+    SelectionConfirmed(Rect),
+    /// 导出的图像（PNG）；多选区分别导出时每个选区一个；拾取模式下不发送
+    ImageReady(Vec<u8>),
+    /// 用户退出 overlay（Esc / 右键 / 工具栏“退出”），没有导出
+    Canceled,
+}
+
+pub struct SnipEngine {
+    overlay: Option<OverlayState>,
+    toolbar: ToolbarSpec,
+    backend: Box<dyn CaptureBackend>,
+    pick: bool,
+    callback: Box<dyn FnMut(SnipEvent)>,
+}
+
+impl SnipEngine {
+    pub fn new(callback: impl FnMut(SnipEvent) + 'static) -> Self {
+        Self {
+            overlay: None,
+            toolbar: ToolbarSpec::default(),
+            backend: Box::new(ScreenBackend),
+            pick: false,
+            callback: Box::new(callback),
+        }
+    }
+
+    /// 工具栏按钮集合（默认与程序相同）
+    pub fn with_toolbar(mut self, toolbar: ToolbarSpec) -> Self {
+        self.toolbar = toolbar;
+        self
+    }
+
+    /// 截图来源（默认真实屏幕；测试 / 演示可换成 MockBackend 等）
+    pub fn with_backend(mut self, backend: impl CaptureBackend + 'static) -> Self {
+        self.backend = Box::new(backend);
+        self
+    }
+
+    /// 拾取模式：导出按钮 / Enter 只发送 SelectionConfirmed（不裁剪、不编码、不写剪贴板）
+    pub fn pick_only(mut self) -> Self {
+        self.pick = true;
+        self
+    }
+
+    /// overlay 正在显示
+    pub fn is_active(&self) -> bool {
+        self.overlay.as_ref().is_some_and(|ov| ov.visible)
+    }
+
+    /// 从截图后端截取主屏并显示 overlay；已在显示时忽略。overlay 窗口在首次调用时创建
+    pub fn start_capture(&mut self, event_loop: &ActiveEventLoop) -> Result<()> {
+        if self.is_active() {
+            return Ok(());
+        }
+        if self.overlay.is_none() {
+            let mut ov = OverlayState::new(event_loop)?;
+            ov.set_toolbar(self.toolbar.clone());
+            self.overlay = Some(ov);
+        }
+        let Some(ov) = &mut self.overlay else {
+            return Ok(());
+        };
+        let mut buf = ov.take_capture_buffer();
+        let (x, y, w, h) = capture_raw_into_from(self.backend.as_ref(), &mut buf)?;
+        ov.show_with_image(w, h, buf, (x, y))?;
+        ov.set_pick_mode(self.pick);
+        ov.window.set_cursor(CursorIcon::Crosshair);
+        (self.callback)(SnipEvent::CaptureStarted);
+        Ok(())
+    }
+
+    /// 转发窗口事件；返回 false 表示不是 overlay 窗口的事件，宿主自行处理
+    pub fn handle_window_event(&mut self, window_id: WindowId, event: &WindowEvent) -> bool {
+        let Some(ov) = self
+            .overlay
+            .as_mut()
+            .filter(|ov| ov.window.id() == window_id)
+        else {
+            return false;
+        };
+        match event {
+            WindowEvent::RedrawRequested => ov.redraw(),
+            WindowEvent::Resized(_) => ov.window.request_redraw(),
+            _ => {
+                let was_visible = ov.visible;
+                let selection = ov.screen_selection();
+                let action = ov.handle_event(event);
+                let closed = was_visible && !ov.visible;
+                self.finish(action, selection, closed);
+            }
+        }
+        true
+    }
+
+    /// 转发菜单事件（工具栏“更多”菜单）；返回 false 表示不是 overlay 的菜单项
+    pub fn handle_menu(&mut self, id: &MenuId) -> bool {
+        let Some(ov) = &mut self.overlay else {
+            return false;
+        };
+        let Some(button) = ov.more_ids.resolve(id) else {
+            return false;
+        };
+        let was_visible = ov.visible;
+        let selection = ov.screen_selection();
+        let action = ov.execute_tool(button);
+        let closed = was_visible && !ov.visible;
+        self.finish(action, selection, closed);
+        true
+    }
+
+    // 导出动作 -> 事件；没有导出却关闭了 overlay 视为取消
+    fn finish(&mut self, action: OverlayAction, selection: Option<Rect>, closed: bool) {
+        if let OverlayAction::RegionPicked(rect) = action {
+            (self.callback)(SnipEvent::SelectionConfirmed(rect));
+            return;
+        }
+        let mut images = Vec::new();
+        collect_images(action, selection, &mut images);
+        if images.is_empty() {
+            if closed {
+                (self.callback)(SnipEvent::Canceled);
+            }
+            return;
+        }
+        if let Some(ov) = &mut self.overlay {
+            ov.hide();
+        }
+        for (rect, png) in images {
+            (self.callback)(SnipEvent::SelectionConfirmed(rect));
+            (self.callback)(SnipEvent::ImageReady(png));
+        }
+    }
+}
+
+// 展开 Batch，编码每个导出图像；不产生图像的动作（识别二维码 / 敏感信息检测 / 保存工程）在嵌入模式下忽略
+fn collect_images(action: OverlayAction, selection: Option<Rect>, out: &mut Vec<(Rect, Vec<u8>)>) {
+    let (rgba, width, height, pos) = match action {
+        OverlayAction::Batch(actions) => {
+            for a in actions {
+                collect_images(a, selection, out);
+            }
+            return;
+        }
+        OverlayAction::PasteSelection {
+            rgba,
+            width,
+            height,
+            screen_x,
+            screen_y,
+            ..
+        } => (rgba, width, height, Some((screen_x, screen_y))),
+        OverlayAction::SaveSelection {
+            rgba,
+            width,
+            height,
+            ..
+        }
+        | OverlayAction::Copied {
+            rgba,
+            width,
+            height,
+        }
+        | OverlayAction::CopyDataUri {
+            rgba,
+            width,
+            height,
+        }
+        | OverlayAction::OpenWith {
+            rgba,
+            width,
+            height,
+        }
+        | OverlayAction::Share {
+            rgba,
+            width,
+            height,
+        }
+        | OverlayAction::Upload {
+            rgba,
+            width,
+            height,
+            ..
+        } => (rgba, width, height, None),
+        OverlayAction::DecodeQr { .. }
+        | OverlayAction::ImageInfo { .. }
+        | OverlayAction::DetectSensitive { .. }
+        | OverlayAction::RegionPicked(_)
+        | OverlayAction::SaveProject(_)
+        | OverlayAction::Canceled
+        | OverlayAction::None => return,
+    };
+    let (x, y) = pos.or(selection.map(|r| (r.x, r.y))).unwrap_or_default();
+    match encode_png(&rgba, width, height) {
+        Ok(png) => out.push((
+            Rect {
+                x,
+                y,
+                width,
+                height,
+            },
+            png,
+        )),
+        Err(e) => log::warn!("encode selection: {e}"),
+    }
+}
+
+/// 显示 overlay 让用户框选，返回选区的屏幕坐标；Esc / 右键 / “退出”时为 None。
+/// 不保存、不钉图、不写剪贴板。内部新建 winit 事件循环，因此须在主线程调用，
+/// 且进程内不能已经创建过事件循环（winit 限制）；已有事件循环的宿主改用 SnipEngine::pick_only
+pub fn pick_region() -> Result<Option<Rect>> {
+    run_once(|slot| {
+        SnipEngine::new(move |event| match event {
+            SnipEvent::SelectionConfirmed(rect) => finish_once(&slot, Some(rect)),
+            SnipEvent::Canceled => finish_once(&slot, None),
+            SnipEvent::CaptureStarted | SnipEvent::ImageReady(_) => {}
+        })
+        .pick_only()
+    })
+}
+
+/// 显示 overlay 截图，返回第一个导出图像的屏幕矩形与 PNG（钉图 / 保存 / 复制等导出动作都算）；
+/// 取消时为 None。与 pick_region 相同，须在主线程调用且进程内没有别的事件循环
+pub fn capture_png() -> Result<Option<(Rect, Vec<u8>)>> {
+    run_once(|slot| {
+        // SelectionConfirmed 紧接着就是对应的 ImageReady
+        let mut rect = None;
+        SnipEngine::new(move |event| match event {
+            SnipEvent::SelectionConfirmed(r) => rect = Some(r),
+            SnipEvent::ImageReady(png) => {
+                finish_once(&slot, rect.map(|r| (r, png)));
+            }
+            SnipEvent::Canceled => finish_once(&slot, None),
+            SnipEvent::CaptureStarted => {}
+        })
+    })
+}
+
+// 结果槽：外层 None 表示还没有结果
+type Slot<T> = Rc<RefCell<Option<Option<T>>>>;
+
+// 只保留第一个结果（多选区导出时后面的忽略）
+fn finish_once<T>(slot: &Slot<T>, value: Option<T>) {
+    slot.borrow_mut().get_or_insert(value);
+}
+
+// 新建事件循环运行 make 构造的引擎，直到回调写入结果
+fn run_once<T>(make: impl FnOnce(Slot<T>) -> SnipEngine) -> Result<Option<T>> {
+    let result: Slot<T> = Rc::new(RefCell::new(None));
+    let mut picker = Picker {
+        engine: make(result.clone()),
+        result: result.clone(),
+        started: false,
+        error: None,
+    };
+    EventLoop::new()?.run_app(&mut picker)?;
+    if let Some(e) = picker.error {
+        return Err(e);
+    }
+    result
+        .take()
+        .ok_or_else(|| anyhow!("overlay closed without a result"))
+}
+
+struct Picker<T> {
+    engine: SnipEngine,
+    result: Slot<T>,
+    started: bool,
+    error: Option<anyhow::Error>,
+}
+
+impl<T> ApplicationHandler for Picker<T> {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if std::mem::replace(&mut self.started, true) {
+            return;
+        }
+        if let Err(e) = self.engine.start_capture(event_loop) {
+            self.error = Some(e);
+            event_loop.exit();
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, id: WindowId, event: WindowEvent) {
+        self.engine.handle_window_event(id, &event);
+        if self.result.borrow().is_some() {
+            event_loop.exit();
+        }
+    }
+
+    // 工具栏“更多”菜单的选择经 muda 的全局 channel 到达
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        while let Ok(ev) = MenuEvent::receiver().try_recv() {
+            self.engine.handle_menu(&ev.id);
+        }
+        if self.result.borrow().is_some() {
+            event_loop.exit();
+        }
+    }
+}
@@ -0,0 +1,181 @@
+// filters: 导出前对选区 RGBA 做基础调整（灰度 / 反色 / 亮度 / 对比度 / 饱和度）与局部打码
+//
+// 亮度与对比度预先算成 256 项查找表，预览每帧重算也足够快；alpha 保持不变。
+use serde::{Deserialize, Serialize};
+
+/// 调整参数；滑块值范围 -100..=100，0 为不调整
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Adjustments {
+    pub grayscale: bool,
+    pub invert: bool,
+    pub brightness: i32,
+    pub contrast: i32,
+    pub saturation: i32,
+}
+
+pub const SLIDER_MIN: i32 = -100;
+pub const SLIDER_MAX: i32 = 100;
+
+impl Adjustments {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 预计算查找表
+    pub fn prepare(&self) -> Filter {
+        let brightness = self.brightness.clamp(SLIDER_MIN, SLIDER_MAX) as f32 / 100.0;
+        let contrast = self.contrast.clamp(SLIDER_MIN, SLIDER_MAX) as f32 / 100.0;
+        // 对比度 -100 -> 全灰，+100 -> 约 4 倍斜率
+        let slope = if contrast >= 0.0 {
+            1.0 + contrast * 3.0
+        } else {
+            1.0 + contrast
+        };
+        let mut lut = [0u8; 256];
+        for (i, v) in lut.iter_mut().enumerate() {
+            let mut c = i as f32 / 255.0 + brightness;
+            c = (c - 0.5) * slope + 0.5;
+            if self.invert {
+                c = 1.0 - c;
+            }
+            *v = (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+        let saturation = if self.grayscale {
+            0.0
+        } else {
+            1.0 + self.saturation.clamp(SLIDER_MIN, SLIDER_MAX) as f32 / 100.0
+        };
+        Filter { lut, saturation }
+    }
+
+    /// 原地调整 RGBA 缓冲
+    pub fn apply_rgba(&self, rgba: &mut [u8]) {
+        if self.is_identity() {
+            return;
+        }
+        let filter = self.prepare();
+        for px in rgba.chunks_exact_mut(4) {
+            let [r, g, b] = filter.apply([px[0], px[1], px[2]]);
+            px[..3].copy_from_slice(&[r, g, b]);
+        }
+    }
+}
+
+/// 预计算后的调整（饱和度在查找表之前按亮度混合）
+pub struct Filter {
+    lut: [u8; 256],
+    saturation: f32,
+}
+
+impl Filter {
+    pub fn apply(&self, rgb: [u8; 3]) -> [u8; 3] {
+        let [r, g, b] = rgb;
+        let (r, g, b) = if self.saturation == 1.0 {
+            (r, g, b)
+        } else {
+            let l = luma(r, g, b) as f32;
+            let mix = |c: u8| (l + (c as f32 - l) * self.saturation).clamp(0.0, 255.0) as u8;
+            (mix(r), mix(g), mix(b))
+        };
+        [
+            self.lut[r as usize],
+            self.lut[g as usize],
+            self.lut[b as usize],
+        ]
+    }
+}
+
+/// 马赛克：rect 内按 block x block 取平均色（rect 超出图像的部分忽略）
+pub fn pixelate(rgba: &mut [u8], width: u32, height: u32, rect: (u32, u32, u32, u32), block: u32) {
+    let (rx, ry, rw, rh) = rect;
+    let (x1, y1) = ((rx + rw).min(width), (ry + rh).min(height));
+    let block = block.max(1);
+    if rgba.len() < (width * height * 4) as usize {
+        return;
+    }
+    for by in (ry..y1).step_by(block as usize) {
+        for bx in (rx..x1).step_by(block as usize) {
+            let (ex, ey) = ((bx + block).min(x1), (by + block).min(y1));
+            let mut sum = [0u32; 4];
+            for y in by..ey {
+                for x in bx..ex {
+                    let i = ((y * width + x) * 4) as usize;
+                    for c in 0..4 {
+                        sum[c] += rgba[i + c] as u32;
+                    }
+                }
+            }
+            let n = (ex - bx) * (ey - by);
+            let avg = sum.map(|v| (v / n) as u8);
+            for y in by..ey {
+                for x in bx..ex {
+                    let i = ((y * width + x) * 4) as usize;
+                    rgba[i..i + 4].copy_from_slice(&avg);
+                }
+            }
+        }
+    }
+}
+
+/// 亮度（BT.601 整数近似，0..=255）；二维码识别、对比、清晰度、裁边与边框配色共用
+pub fn luma(r: u8, g: u8, b: u8) -> u8 {
+    ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_keeps_pixels() {
+        let mut px = [12u8, 200, 99, 128];
+        Adjustments::default().apply_rgba(&mut px);
+        assert_eq!(px, [12, 200, 99, 128]);
+        let filter = Adjustments::default().prepare();
+        assert_eq!(filter.apply([12, 200, 99]), [12, 200, 99]);
+    }
+
+    #[test]
+    fn test_grayscale_invert_and_sliders() {
+        let gray = Adjustments {
+            grayscale: true,
+            ..Default::default()
+        };
+        let [r, g, b] = gray.prepare().apply([255, 0, 0]);
+        assert!(r == g && g == b);
+        assert_eq!(
+            (luma(255, 255, 255), luma(0, 0, 0), luma(255, 0, 0)),
+            (255, 0, 76)
+        );
+        let mut px = [10u8, 20, 30, 77];
+        Adjustments {
+            invert: true,
+            ..Default::default()
+        }
+        .apply_rgba(&mut px);
+        assert_eq!(px, [245, 235, 225, 77]); // alpha 不变
+        let bright = Adjustments {
+            brightness: 100,
+            ..Default::default()
+        };
+        assert_eq!(bright.prepare().apply([0, 128, 255]), [255, 255, 255]);
+        let flat = Adjustments {
+            contrast: -100,
+            ..Default::default()
+        };
+        assert_eq!(flat.prepare().apply([0, 255, 30]), [128, 128, 128]);
+    }
+
+    #[test]
+    fn test_pixelate_block_average() {
+        // 4x1：前两个像素成一块，第 4 个像素在 rect 外
+        let mut px = [
+            0u8, 0, 0, 255, 100, 100, 100, 255, 50, 50, 50, 255, 9, 9, 9, 9,
+        ];
+        pixelate(&mut px, 4, 1, (0, 0, 3, 1), 2);
+        assert_eq!(&px[..8], &[50, 50, 50, 255, 50, 50, 50, 255]);
+        assert_eq!(&px[8..], &[50, 50, 50, 255, 9, 9, 9, 9]);
+        pixelate(&mut px, 4, 1, (10, 0, 3, 1), 2); // 完全在外：不变
+    }
+}
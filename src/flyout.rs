@@ -0,0 +1,395 @@
+// flyout: 托盘图标左键弹出的快捷面板（截取区域 / 截取窗口 / 钉住剪贴板 / 打开设置）
+//
+// 无边框置顶小窗口，一排图标按钮，配色与 UI 缩放同 overlay 工具栏（跟随高对比度）。位置靠近托盘
+// 图标：任务栏在底部时位于图标正上方，在顶部 / 两侧时贴着相应的工作区边缘，始终限制在离图标
+// 最近的显示器工作区内。单击按钮先隐藏面板再执行；Esc、失去焦点或再次单击托盘图标时隐藏。
+use anyhow::Result;
+use softbuffer::{Context, Surface};
+use std::num::NonZeroU32;
+use std::time::{Duration, Instant};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, KeyEvent, MouseButton, WindowEvent},
+    event_loop::ActiveEventLoop,
+    keyboard::{KeyCode, PhysicalKey},
+    platform::windows::WindowAttributesExtWindows,
+    window::{CursorIcon, WindowAttributes, WindowLevel},
+};
+
+use crate::overlay::drawing::{fill_rect, set_px, stroke_rect};
+use crate::overlay::theme::{Theme, UiScale};
+use crate::window_surface::{SharedWindow, SurfaceWindow};
+
+/// 按钮边长、内边距与间距（1.0 倍 UI 缩放下的像素）
+const BTN: i32 = 40;
+const PAD: i32 = 6;
+const GAP: i32 = 4;
+/// 面板与工作区边缘 / 托盘图标之间的距离
+pub const FLYOUT_MARGIN: i32 = 8;
+/// 失去焦点隐藏后这么短时间内的托盘单击视为“再次单击关闭”，不重新打开
+const REOPEN_GUARD: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlyoutAction {
+    CaptureRegion,
+    CaptureWindow,
+    PinClipboard,
+    OpenSettings,
+}
+
+impl FlyoutAction {
+    pub const ALL: [FlyoutAction; 4] = [
+        FlyoutAction::CaptureRegion,
+        FlyoutAction::CaptureWindow,
+        FlyoutAction::PinClipboard,
+        FlyoutAction::OpenSettings,
+    ];
+
+    /// 悬停时显示在窗口标题中（屏幕阅读器朗读）
+    pub fn label(self) -> &'static str {
+        match self {
+            FlyoutAction::CaptureRegion => "截取区域",
+            FlyoutAction::CaptureWindow => "截取窗口",
+            FlyoutAction::PinClipboard => "钉住剪贴板图片",
+            FlyoutAction::OpenSettings => "打开设置",
+        }
+    }
+}
+
+/// 面板尺寸 (w, h)
+pub fn flyout_size(scale: UiScale) -> (u32, u32) {
+    let n = FlyoutAction::ALL.len() as i32;
+    let (btn, pad, gap) = (scale.px(BTN), scale.px(PAD), scale.px(GAP));
+    (
+        (pad * 2 + n * btn + (n - 1) * gap) as u32,
+        (pad * 2 + btn) as u32,
+    )
+}
+
+// 第 i 个按钮 (x, y, w, h)，窗口内坐标
+fn button_rect(i: usize, scale: UiScale) -> (i32, i32, i32, i32) {
+    let (btn, pad, gap) = (scale.px(BTN), scale.px(PAD), scale.px(GAP));
+    (pad + i as i32 * (btn + gap), pad, btn, btn)
+}
+
+/// 窗口内坐标命中的按钮序号
+pub fn hit_test(x: i32, y: i32, scale: UiScale) -> Option<usize> {
+    (0..FlyoutAction::ALL.len()).find(|&i| {
+        let (bx, by, bw, bh) = button_rect(i, scale);
+        x >= bx && y >= by && x < bx + bw && y < by + bh
+    })
+}
+
+/// 面板左上角的屏幕坐标。icon 为托盘图标矩形，work_areas 为各显示器工作区（均为 (x, y, w, h)）
+pub fn flyout_position(
+    icon: (i32, i32, u32, u32),
+    size: (u32, u32),
+    work_areas: &[(i32, i32, u32, u32)],
+) -> (i32, i32) {
+    let (ix, iy, iw, ih) = icon;
+    let (w, h) = (size.0 as i32, size.1 as i32);
+    let (cx, cy) = (ix + iw as i32 / 2, iy + ih as i32 / 2);
+    // 默认在图标正上方（任务栏在底部 / 图标在溢出区内）
+    let (mut x, mut y) = (cx - w / 2, iy - h - FLYOUT_MARGIN);
+    let distance = |&&(ax, ay, aw, ah): &&(i32, i32, u32, u32)| {
+        let dx = (ax - cx).max(cx - (ax + aw as i32)).max(0) as i64;
+        let dy = (ay - cy).max(cy - (ay + ah as i32)).max(0) as i64;
+        dx * dx + dy * dy
+    };
+    let Some(&(ax, ay, aw, ah)) = work_areas.iter().min_by_key(distance) else {
+        return (x, y);
+    };
+    let (right, bottom) = (ax + aw as i32, ay + ah as i32);
+    if cy < ay {
+        y = ay + FLYOUT_MARGIN; // 任务栏在顶部
+    } else if cx < ax {
+        (x, y) = (ax + FLYOUT_MARGIN, cy - h / 2); // 左侧
+    } else if cx >= right {
+        (x, y) = (right - w - FLYOUT_MARGIN, cy - h / 2); // 右侧
+    } else if cy >= bottom {
+        y = bottom - h - FLYOUT_MARGIN;
+    }
+    let x = x.min(right - w - FLYOUT_MARGIN).max(ax + FLYOUT_MARGIN);
+    let y = y.min(bottom - h - FLYOUT_MARGIN).max(ay + FLYOUT_MARGIN);
+    (x, y)
+}
+
+pub struct Flyout {
+    pub window: SharedWindow,
+    surface: Surface<SharedWindow, SharedWindow>,
+    _context: Context<SharedWindow>,
+    theme: Theme,
+    visible: bool,
+    hover: Option<usize>,
+    cursor: (f64, f64),
+    hidden_at: Option<Instant>, // 因失去焦点隐藏的时间
+}
+
+impl Flyout {
+    /// 创建隐藏的面板窗口（首次单击托盘图标时）
+    pub fn new(active: &ActiveEventLoop) -> Result<Self> {
+        let attrs = WindowAttributes::default()
+            .with_title("Snip Rust 快捷操作")
+            .with_decorations(false)
+            .with_resizable(false)
+            .with_visible(false)
+            .with_window_level(WindowLevel::AlwaysOnTop)
+            .with_inner_size(PhysicalSize::new(1, 1))
+            .with_skip_taskbar(true);
+        let SurfaceWindow {
+            window,
+            surface,
+            context,
+        } = SurfaceWindow::create(active, attrs, "flyout")?;
+        Ok(Self {
+            window,
+            surface,
+            _context: context,
+            theme: Theme::default(),
+            visible: false,
+            hover: None,
+            cursor: (0.0, 0.0),
+            hidden_at: None,
+        })
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// 托盘图标被单击：显示在图标旁；已显示（或刚因单击托盘失去焦点而隐藏）时隐藏
+    pub fn toggle(&mut self, icon: (i32, i32, u32, u32), theme: Theme) {
+        if self.visible {
+            self.hide();
+            return;
+        }
+        if self.hidden_at.is_some_and(|t| t.elapsed() < REOPEN_GUARD) {
+            self.hidden_at = None;
+            return;
+        }
+        let size = flyout_size(theme.scale);
+        let (x, y) = flyout_position(icon, size, &crate::windows_util::monitor_work_areas());
+        self.theme = theme;
+        self.hover = None;
+        let _ = self
+            .window
+            .request_inner_size(PhysicalSize::new(size.0, size.1));
+        self.window.set_outer_position(PhysicalPosition::new(x, y));
+        self.window.set_visible(true);
+        self.window.focus_window();
+        self.visible = true;
+        self.window.request_redraw();
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+        self.window.set_visible(false);
+    }
+
+    /// 处理窗口事件；单击按钮时隐藏面板并返回对应操作
+    pub fn handle_event(&mut self, event: &WindowEvent) -> Option<FlyoutAction> {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = (position.x, position.y);
+                let hover = hit_test(position.x as i32, position.y as i32, self.theme.scale);
+                if hover != self.hover {
+                    self.hover = hover;
+                    let title =
+                        hover.map_or("Snip Rust 快捷操作", |i| FlyoutAction::ALL[i].label());
+                    self.window.set_title(title);
+                    self.window.set_cursor(if hover.is_some() {
+                        CursorIcon::Pointer
+                    } else {
+                        CursorIcon::Default
+                    });
+                    self.window.request_redraw();
+                }
+            }
+            WindowEvent::CursorLeft { .. } if self.hover.is_some() => {
+                self.hover = None;
+                self.window.request_redraw();
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let (x, y) = (self.cursor.0 as i32, self.cursor.1 as i32);
+                if let Some(i) = hit_test(x, y, self.theme.scale) {
+                    self.hide();
+                    return Some(FlyoutAction::ALL[i]);
+                }
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::Escape),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => self.hide(),
+            WindowEvent::Focused(false) if self.visible => {
+                self.hide();
+                self.hidden_at = Some(Instant::now());
+            }
+            _ => {}
+        }
+        None
+    }
+
+    pub fn redraw(&mut self) {
+        if !self.visible {
+            return;
+        }
+        let size = self.window.inner_size();
+        let (Some(w), Some(h)) = (NonZeroU32::new(size.width), NonZeroU32::new(size.height)) else {
+            return;
+        };
+        if self.surface.resize(w, h).is_err() {
+            return;
+        }
+        let Ok(mut frame) = self.surface.buffer_mut() else {
+            return;
+        };
+        let (width, height) = (size.width, size.height);
+        let theme = &self.theme;
+        fill_rect(
+            &mut frame,
+            width,
+            height,
+            0,
+            0,
+            width as i32,
+            height as i32,
+            theme.panel,
+        );
+        theme.outline(
+            &mut frame,
+            width,
+            height,
+            (0, 0, width as i32, height as i32),
+            theme.panel_border,
+        );
+        for (i, action) in FlyoutAction::ALL.into_iter().enumerate() {
+            let rect = button_rect(i, theme.scale);
+            let hovered = self.hover == Some(i);
+            let (bg, border, icon) = if hovered {
+                (theme.hover, theme.hover_border, theme.hover_icon)
+            } else {
+                (theme.button, theme.button_border, theme.icon)
+            };
+            let (bx, by, bw, bh) = rect;
+            fill_rect(&mut frame, width, height, bx, by, bw, bh, bg);
+            theme.outline(&mut frame, width, height, rect, border);
+            let s = theme.scale.px(16);
+            let icon_rect = (bx + (bw - s) / 2, by + (bh - s) / 2, s, s);
+            draw_icon(&mut frame, width, height, action, icon_rect, icon);
+        }
+        let _ = frame.present();
+    }
+}
+
+// 按钮图标（在 s x s 的方框内）
+fn draw_icon(
+    frame: &mut [u32],
+    width: u32,
+    height: u32,
+    action: FlyoutAction,
+    rect: (i32, i32, i32, i32),
+    color: u32,
+) {
+    let (x, y, s, _) = rect;
+    match action {
+        // 区域：虚线框 + 四角
+        FlyoutAction::CaptureRegion => {
+            for i in (0..s).step_by(3) {
+                set_px(frame, width, height, x + i, y, color);
+                set_px(frame, width, height, x + i, y + s - 1, color);
+                set_px(frame, width, height, x, y + i, color);
+                set_px(frame, width, height, x + s - 1, y + i, color);
+            }
+            let c = s / 4;
+            for (cx, cy) in [
+                (x, y),
+                (x + s - c, y),
+                (x, y + s - 2),
+                (x + s - c, y + s - 2),
+            ] {
+                fill_rect(frame, width, height, cx, cy, c, 2, color);
+            }
+        }
+        // 窗口：外框 + 标题栏
+        FlyoutAction::CaptureWindow => {
+            stroke_rect(frame, width, height, x, y + 1, s, s - 2, color);
+            fill_rect(frame, width, height, x, y + 1, s, s / 4, color);
+        }
+        // 剪贴板（夹子在上）+ 图钉竖线
+        FlyoutAction::PinClipboard => {
+            stroke_rect(frame, width, height, x + 1, y + 2, s - 2, s - 2, color);
+            fill_rect(frame, width, height, x + s / 3, y, s / 3, 4, color);
+            fill_rect(frame, width, height, x + s / 4, y + s / 2, s / 2, 2, color);
+            fill_rect(
+                frame,
+                width,
+                height,
+                x + s / 2 - 1,
+                y + s / 2,
+                2,
+                s / 3,
+                color,
+            );
+        }
+        // 设置：齿轮（圆环 + 八个齿）
+        FlyoutAction::OpenSettings => {
+            let c = s as f32 / 2.0;
+            for py in 0..s {
+                for px in 0..s {
+                    let (dx, dy) = (px as f32 + 0.5 - c, py as f32 + 0.5 - c);
+                    let d = dx.hypot(dy);
+                    let tooth = (dy.atan2(dx) * 4.0 / std::f32::consts::PI).round();
+                    let on_tooth = (dy.atan2(dx) * 4.0 / std::f32::consts::PI - tooth).abs() < 0.3;
+                    let ring = d >= c * 0.35 && d <= c * 0.7;
+                    if ring || (on_tooth && d > c * 0.6 && d <= c) {
+                        set_px(frame, width, height, x + px, y + py, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flyout_layout() {
+        let one = UiScale::default();
+        assert_eq!(flyout_size(one), (6 * 2 + 4 * 40 + 3 * 4, 52));
+        assert_eq!(hit_test(7, 7, one), Some(0));
+        assert_eq!(hit_test(48, 20, one), None); // 按钮间隙
+        assert_eq!(hit_test(50, 20, one), Some(1));
+        assert_eq!(hit_test(90, 47, UiScale::new(2.0)), Some(0));
+        // 任务栏在底部：图标正上方，右侧不超出工作区
+        let areas = [(0, 0, 1920, 1040), (1920, 0, 1920, 1040)];
+        let size = (184, 52);
+        let (x, y) = flyout_position((1800, 1048, 24, 24), size, &areas);
+        assert_eq!(y, 1040 - 52 - FLYOUT_MARGIN);
+        assert_eq!(x, (1812 - 92).min(1920 - 184 - FLYOUT_MARGIN));
+        // 副屏的托盘选副屏的工作区
+        let (x, _) = flyout_position((3890, 1048, 24, 24), size, &areas);
+        assert_eq!(x, 3840 - 184 - FLYOUT_MARGIN);
+        // 任务栏在顶部 / 左侧
+        let top = [(0, 40, 1920, 1040)];
+        assert_eq!(
+            flyout_position((1800, 8, 24, 24), size, &top).1,
+            40 + FLYOUT_MARGIN
+        );
+        let left = [(60, 0, 1860, 1080)];
+        let (x, y) = flyout_position((18, 900, 24, 24), size, &left);
+        assert_eq!((x, y), (60 + FLYOUT_MARGIN, 912 - 26));
+        // 没有工作区信息时仍在图标上方
+        assert_eq!(flyout_position((100, 500, 20, 20), size, &[]), (18, 440));
+    }
+}
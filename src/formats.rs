@@ -0,0 +1,167 @@
+// formats: 保存文件的格式（save_format）：png（默认）/ avif（cargo feature "avif"）
+//
+// 只影响写入磁盘的文件；hook、剪贴板、Data URI 与上传缩略图仍使用编码队列同时生成的 PNG。
+// AVIF 由 image crate 的 ravif 编码器生成，画质 / 速度见 [avif]，编码较慢，在编码队列的
+// 工作线程进行。未启用 feature 的构建配置为 avif 时回退为 PNG 并记录警告。
+// AVIF 文件同样按 export_scale 缩放，但不含 ICC 配置文件与 PNG 注释，也不经 png_optimize。
+// 不支持 JPEG XL：没有可用的纯 Rust 编码器（jxl-oxide 只能解码，libjxl 绑定需要 C++ 工具链与
+// CMake，不适合本项目的纯 cargo 构建），save_format = "jxl" 在读取配置时报错，而不是悄悄写成 PNG。
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use crate::capture::encode_png;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum SaveFormat {
+    #[default]
+    Png,
+    Avif,
+}
+
+impl TryFrom<String> for SaveFormat {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, String> {
+        match name.as_str() {
+            "png" => Ok(SaveFormat::Png),
+            "avif" => Ok(SaveFormat::Avif),
+            "jxl" => {
+                Err("save_format \"jxl\" is not supported: no JPEG XL encoder available".into())
+            }
+            other => Err(format!(
+                "unknown save_format {other:?}, expected png or avif"
+            )),
+        }
+    }
+}
+
+impl SaveFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            SaveFormat::Png => "png",
+            SaveFormat::Avif => "avif",
+        }
+    }
+
+    /// 当前构建是否包含该格式的编码器
+    pub fn available(self) -> bool {
+        match self {
+            SaveFormat::Png => true,
+            SaveFormat::Avif => cfg!(feature = "avif"),
+        }
+    }
+
+    /// 实际使用的格式：编码器未编译进来时为 Png
+    pub fn resolve(self) -> SaveFormat {
+        if self.available() {
+            return self;
+        }
+        log::warn!(
+            "save_format {:?} needs the \"{}\" feature; saving png",
+            self,
+            self.extension()
+        );
+        SaveFormat::Png
+    }
+}
+
+/// AVIF 编码参数（[avif]）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct AvifConfig {
+    /// 画质 1..=100（100 最好）
+    pub quality: u8,
+    /// 编码速度 1..=10（1 最慢、文件最小）
+    pub speed: u8,
+}
+
+impl Default for AvifConfig {
+    fn default() -> Self {
+        Self {
+            quality: 80,
+            speed: 6,
+        }
+    }
+}
+
+/// 按格式编码 RGBA
+pub fn encode(
+    format: SaveFormat,
+    rgba: &[u8],
+    w: u32,
+    h: u32,
+    avif: AvifConfig,
+) -> Result<Vec<u8>> {
+    match format {
+        SaveFormat::Png => encode_png(rgba, w, h),
+        SaveFormat::Avif => encode_avif(rgba, w, h, avif),
+    }
+}
+
+#[cfg(feature = "avif")]
+fn encode_avif(rgba: &[u8], w: u32, h: u32, config: AvifConfig) -> Result<Vec<u8>> {
+    use image::codecs::avif::AvifEncoder;
+    use image::{ExtendedColorType, ImageEncoder};
+
+    let mut out = Vec::new();
+    let speed = config.speed.clamp(1, 10);
+    let quality = config.quality.clamp(1, 100);
+    AvifEncoder::new_with_speed_quality(&mut out, speed, quality)
+        .write_image(rgba, w, h, ExtendedColorType::Rgba8)
+        .map_err(|e| anyhow!("encode avif: {e}"))?;
+    Ok(out)
+}
+
+#[cfg(not(feature = "avif"))]
+fn encode_avif(_rgba: &[u8], _w: u32, _h: u32, _config: AvifConfig) -> Result<Vec<u8>> {
+    Err(anyhow!(
+        "avif support not built (enable the \"avif\" feature)"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_format() {
+        #[derive(Deserialize)]
+        struct Wrap {
+            save_format: SaveFormat,
+            #[serde(default)]
+            avif: AvifConfig,
+        }
+        let w: Wrap = toml::from_str("save_format = \"avif\"\n[avif]\nquality = 60").unwrap();
+        assert_eq!(w.save_format, SaveFormat::Avif);
+        assert_eq!(
+            w.avif,
+            AvifConfig {
+                quality: 60,
+                speed: 6
+            }
+        );
+        assert_eq!(SaveFormat::Avif.extension(), "avif");
+        assert_eq!(SaveFormat::Png.resolve(), SaveFormat::Png);
+        let expect = if cfg!(feature = "avif") {
+            SaveFormat::Avif
+        } else {
+            SaveFormat::Png
+        };
+        assert_eq!(SaveFormat::Avif.resolve(), expect);
+        // 没有 JPEG XL 编码器：读取配置时报错，不悄悄改存 PNG
+        let err = toml::from_str::<Wrap>("save_format = \"jxl\"")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("no JPEG XL encoder"));
+        assert!(toml::from_str::<Wrap>("save_format = \"webp\"").is_err());
+        let png = encode(SaveFormat::Png, &[0; 4], 1, 1, AvifConfig::default()).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let avif = encode(SaveFormat::Avif, &[0; 16], 2, 2, AvifConfig::default());
+        assert_eq!(avif.is_ok(), cfg!(feature = "avif"));
+        // ISO-BMFF：第一个 box 为 ftyp，品牌为 avif
+        if let Ok(avif) = avif {
+            assert_eq!(&avif[4..12], b"ftypavif");
+        }
+    }
+}
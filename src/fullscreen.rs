@@ -0,0 +1,127 @@
+// fullscreen: 全屏程序 / 演示（游戏、PowerPoint 放映）期间暂停贴图置顶与截图热键
+//
+// 订阅共用的前台窗口钩子（window_trigger::subscribe），启动时与每次前台切换后，后台线程每隔
+// poll_ms 查询系统的“请勿打扰”状态（SHQueryUserNotificationState：全屏 D3D / 全屏独占 /
+// 演示模式），本程序自己的 overlay 不算，连续 SETTLE 次结果一致后放慢为每 IDLE_POLL 采样一次，
+// 直到下一次切换。慢速采样兜住前台不变时的原地切换（F11、无边框全屏、游戏切换显示模式）；
+// 连续采样避免切换窗口时短暂的全屏状态来回抖动。切换时以 true（进入全屏）/ false（退出）调用 on_change。
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+use crate::window_trigger::{self, ForegroundEvent};
+
+/// 状态需要连续保持的采样次数
+pub const SETTLE: u32 = 2;
+/// 前台窗口不变时的慢速采样间隔（发现同一窗口原地进入 / 退出全屏）
+pub const IDLE_POLL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FullscreenConfig {
+    /// 全屏期间隐藏贴图，退出后恢复
+    pub hide_pins: bool,
+    /// 全屏期间忽略截图热键（托盘与 IPC 仍然可用）
+    pub suppress_hotkey: bool,
+    /// 前台窗口切换后的采样间隔（毫秒）
+    pub poll_ms: u64,
+}
+
+impl Default for FullscreenConfig {
+    fn default() -> Self {
+        Self {
+            hide_pins: true,
+            suppress_hotkey: true,
+            poll_ms: 1000,
+        }
+    }
+}
+
+/// 全屏状态去抖（纯逻辑，便于测试）
+#[derive(Debug, Default)]
+pub struct Watcher {
+    active: bool,
+    streak: u32,
+}
+
+impl Watcher {
+    /// 输入一次采样；状态切换时返回新状态
+    pub fn update(&mut self, busy: bool) -> Option<bool> {
+        if busy == self.active {
+            self.streak = 0;
+            return None;
+        }
+        self.streak += 1;
+        if self.streak < SETTLE {
+            return None;
+        }
+        self.streak = 0;
+        self.active = busy;
+        Some(busy)
+    }
+
+    /// 没有待确认的状态变化（可以停止采样）
+    pub fn settled(&self) -> bool {
+        self.streak == 0
+    }
+}
+
+/// 启动检测线程；全屏状态变化时调用 on_change
+pub fn spawn(config: FullscreenConfig, on_change: impl Fn(bool) + Send + 'static) -> Result<()> {
+    let interval = Duration::from_millis(config.poll_ms.max(100));
+    let (tx, rx) = mpsc::channel();
+    window_trigger::subscribe(false, move |event| {
+        if let ForegroundEvent::Activated(_) = event {
+            let _ = tx.send(());
+        }
+    })?;
+    std::thread::Builder::new()
+        .name("snip-fullscreen".into())
+        .spawn(move || {
+            let mut watcher = Watcher::default();
+            loop {
+                // 至少采样 SETTLE 次，之后一直采到状态稳定；期间的前台切换合并
+                for n in 1.. {
+                    std::thread::sleep(interval);
+                    while rx.try_recv().is_ok() {}
+                    let busy = crate::windows_util::fullscreen_app_active();
+                    if let Some(active) = watcher.update(busy) {
+                        on_change(active);
+                    }
+                    if n >= SETTLE && watcher.settled() {
+                        break;
+                    }
+                }
+                // 等下一次前台切换；超时则照常采样一轮（原地切换全屏不会产生前台事件）
+                if let Err(RecvTimeoutError::Disconnected) = rx.recv_timeout(IDLE_POLL) {
+                    return;
+                }
+            }
+        })
+        .map(|_| ())
+        .map_err(|e| anyhow!("spawn fullscreen watcher: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watcher_settles() {
+        let mut w = Watcher::default();
+        // 单次抖动不切换
+        assert_eq!(w.update(true), None);
+        assert_eq!(w.update(false), None);
+        assert_eq!(w.update(true), None);
+        assert!(!w.settled());
+        assert_eq!(w.update(true), Some(true));
+        assert!(w.settled());
+        assert_eq!(w.update(true), None);
+        assert_eq!(w.update(false), None);
+        assert_eq!(w.update(false), Some(false));
+        let c: FullscreenConfig = toml::from_str("hide_pins = false").unwrap();
+        assert!(!c.hide_pins && c.suppress_hotkey);
+        assert_eq!(c.poll_ms, 1000);
+    }
+}
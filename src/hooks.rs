@@ -0,0 +1,518 @@
+// hooks: 保存 / 复制后执行配置中的外部命令（自定义上传器、PNG 优化器等）
+//
+// 命令模板变量：{path} {width} {height}；stdin = true 时把 PNG 字节写入子进程标准输入。
+// 文件名来自命名模板（可含前台窗口标题），不能拼进命令行：{path} 展开为带引号的环境变量引用
+// （cmd "%SNIP_PATH%" / sh "$SNIP_PATH"），路径经环境变量传给子进程，shell 展开后不再解析其中的
+// & | ; $( ) 等字符。
+// copy_url 设置时读取命令 stdout 中的 URL（上传器返回的链接），按格式写入剪贴板；
+// history = true 时把链接、删除凭据与缩略图写入上传记录（见 uploads）。删除命令模板先按参数
+// 拆开再代入 {url} / {delete}（这些值来自上传器输出，不可信），之后不经 shell 直接运行。
+// 每条命令在独立线程中运行，不阻塞事件循环；运行中的上传（copy_url / history）计入
+// uploads_in_progress，托盘图标据此显示上传动画。
+
+use serde::Deserialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+static UPLOADS: AtomicUsize = AtomicUsize::new(0);
+
+/// 传递 {path} 的环境变量
+pub const PATH_VAR: &str = "SNIP_PATH";
+
+// {path} 在命令中展开成的 shell 引用：引号内展开，值里的特殊字符不再被解析
+// （Windows 文件名不能含 "，cmd 只展开一次 %，值里的 % 不会再被展开）
+#[cfg(target_os = "windows")]
+const PATH_REF: &str = "\"%SNIP_PATH%\"";
+#[cfg(not(target_os = "windows"))]
+const PATH_REF: &str = "\"$SNIP_PATH\"";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HookEvent {
+    Save,
+    Copy,
+}
+
+/// 上传结果写入剪贴板的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlFormat {
+    /// 原始 URL
+    Url,
+    /// `![](url)`
+    Markdown,
+    /// `<img src="url">`
+    Html,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PostCaptureHook {
+    pub on: HookEvent,
+    /// 命令模板，例如 `optipng {path}`（{path} 自带引号）
+    pub command: String,
+    #[serde(default)]
+    pub stdin: bool,
+    /// 命令成功后取 stdout 中最后一个 http(s) URL，按格式复制到剪贴板
+    #[serde(default)]
+    pub copy_url: Option<UrlFormat>,
+    /// 上传目标名称（上传记录中显示），默认为命令本身
+    #[serde(default)]
+    pub name: Option<String>,
+    /// 把 stdout 中的链接写入上传记录
+    #[serde(default)]
+    pub history: bool,
+    /// 删除远程图片的命令模板（{url} / {delete}），上传记录中“删除远程图片”使用；
+    /// 按空白与引号拆分为程序与参数，不经 shell 运行（不支持 &&、| 等）
+    #[serde(default)]
+    pub delete_command: Option<String>,
+}
+
+/// 一次截图输出（保存后的文件路径可选；复制事件没有文件）
+pub struct HookContext<'a> {
+    pub path: Option<&'a Path>,
+    pub width: u32,
+    pub height: u32,
+    pub png: &'a [u8],
+    /// 文件名模板生成的名称（复制事件的临时文件使用）
+    pub name: &'a str,
+}
+
+/// 运行所有匹配 `event` 的 hook。复制事件若模板引用 {path}，先写入临时文件，
+/// 所有 hook 命令结束后删除。
+pub fn run_post_capture(hooks: &[PostCaptureHook], event: HookEvent, ctx: &HookContext) {
+    let matching: Vec<&PostCaptureHook> = hooks.iter().filter(|h| h.on == event).collect();
+    if matching.is_empty() {
+        return;
+    }
+    let mut temp: Option<Arc<TempPng>> = None;
+    if ctx.path.is_none() && matching.iter().any(|h| h.command.contains("{path}")) {
+        match write_temp_png(ctx.png, ctx.name) {
+            Ok(p) => temp = Some(Arc::new(TempPng(p))),
+            Err(e) => log::warn!("hook temp file: {e}"),
+        }
+    }
+    let path = ctx.path.or(temp.as_deref().map(|t| t.0.as_path()));
+    let png: Option<Arc<Vec<u8>>> = matching
+        .iter()
+        .any(|h| h.stdin || h.history)
+        .then(|| Arc::new(ctx.png.to_vec()));
+    for hook in matching {
+        if path.is_none() && hook.command.contains("{path}") {
+            log::warn!("hook `{}` skipped: no file for {{path}}", hook.command);
+            continue;
+        }
+        let cmd = expand_template(&hook.command, ctx.width, ctx.height);
+        let path = path.map(Path::to_path_buf);
+        let stdin = if hook.stdin { png.clone() } else { None };
+        let thumbnail = if hook.history { png.clone() } else { None };
+        let hook = hook.clone();
+        let upload = hook.copy_url.is_some() || hook.history;
+        let guard = upload.then(UploadGuard::begin);
+        let temp = temp.clone();
+        thread::spawn(move || {
+            let _guard = guard;
+            let _temp = temp;
+            let stdin = stdin.as_deref().map(|v| v.as_slice());
+            match run_shell(&cmd, path.as_deref(), stdin, upload) {
+                Ok(Some(out)) => {
+                    if let Some(format) = hook.copy_url {
+                        copy_uploaded_url(&cmd, format, &out);
+                    }
+                    if let Some(png) = thumbnail {
+                        record_upload(&hook, &out, &png);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!("hook `{cmd}` failed: {e}"),
+            }
+        });
+    }
+}
+
+// 复制事件的临时 PNG：每个 hook 线程持有一份，最后一个线程结束时删除
+struct TempPng(PathBuf);
+
+impl Drop for TempPng {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.0) {
+            log::debug!("remove hook temp file {}: {e}", self.0.display());
+        }
+    }
+}
+
+/// 正在运行的上传（hook 与 WebDAV）数
+pub fn uploads_in_progress() -> usize {
+    UPLOADS.load(Ordering::SeqCst)
+}
+
+/// 一次进行中的上传：存在期间计入 uploads_in_progress
+pub struct UploadGuard(());
+
+impl UploadGuard {
+    pub fn begin() -> Self {
+        UPLOADS.fetch_add(1, Ordering::SeqCst);
+        Self(())
+    }
+}
+
+impl Drop for UploadGuard {
+    fn drop(&mut self) {
+        UPLOADS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 是否配置了指定事件的 hook（无 hook 时调用方可跳过 PNG 编码）
+pub fn has_hooks(hooks: &[PostCaptureHook], event: HookEvent) -> bool {
+    hooks.iter().any(|h| h.on == event)
+}
+
+/// 上传器输出中最后一个 http(s) URL（按空白切分，允许前后有其它日志文本）
+pub fn extract_url(stdout: &str) -> Option<&str> {
+    stdout
+        .split_whitespace()
+        .rev()
+        .find(|w| w.starts_with("https://") || w.starts_with("http://"))
+}
+
+/// 上传器输出中的删除凭据：`delete: <值>` / `deletehash: <值>` 行，或 Imgur JSON 的 `"deletehash":"<值>"`
+pub fn extract_delete(stdout: &str) -> Option<&str> {
+    if let Some(start) = stdout.find("\"deletehash\":\"") {
+        let rest = &stdout[start + "\"deletehash\":\"".len()..];
+        return rest.split('"').next().filter(|v| !v.is_empty());
+    }
+    stdout.lines().rev().find_map(|line| {
+        let (key, value) = line.trim().split_once(':')?;
+        let key = key.trim().to_ascii_lowercase();
+        (key == "delete" || key == "deletehash")
+            .then(|| value.split_whitespace().next())
+            .flatten()
+    })
+}
+
+/// 把命令模板拆成参数：按空白分隔，"…" / '…' 内的空白保留（引号本身去掉）
+pub fn split_args(template: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote: Option<char> = None;
+    for c in template.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => args.extend(current.take()),
+            (None, c) => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(current);
+    args
+}
+
+/// 展开删除命令模板：先拆参数再代入 {url} / {delete}，值中的空白、引号与 shell 元字符
+/// 都留在所在参数内
+pub fn expand_delete(template: &str, url: &str, delete: Option<&str>) -> Vec<String> {
+    split_args(template)
+        .into_iter()
+        .map(|arg| {
+            arg.replace("{url}", url)
+                .replace("{delete}", delete.unwrap_or_default())
+        })
+        .collect()
+}
+
+/// 不经 shell 运行程序与参数（上传记录中的删除命令），阻塞到结束
+pub fn run_args(argv: &[String]) -> anyhow::Result<()> {
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("empty command"))?;
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null());
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let status = command.status()?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("exit status {status}"));
+    }
+    log::debug!("command `{program}` finished");
+    Ok(())
+}
+
+fn record_upload(hook: &PostCaptureHook, stdout: &str, png: &[u8]) {
+    let Some(url) = extract_url(stdout) else {
+        return;
+    };
+    let delete = extract_delete(stdout);
+    save_upload_record(
+        hook.name.clone().unwrap_or_else(|| hook.command.clone()),
+        url,
+        delete,
+        hook.delete_command
+            .as_deref()
+            .map(|t| expand_delete(t, url, delete)),
+        png,
+    );
+}
+
+/// 写入一条上传记录与缩略图（上传 hook / WebDAV 共用）；失败只记录日志
+pub fn save_upload_record(
+    destination: String,
+    url: &str,
+    delete: Option<&str>,
+    delete_args: Option<Vec<String>>,
+    png: &[u8],
+) {
+    let Some(history) = crate::uploads::history_path() else {
+        return;
+    };
+    let id = crate::uploads::new_id();
+    let thumbnail = match crate::uploads::write_thumbnail(&history, id, png) {
+        Ok(name) => Some(name),
+        Err(e) => {
+            log::warn!("upload thumbnail: {e}");
+            None
+        }
+    };
+    let record = crate::uploads::UploadRecord {
+        id,
+        destination,
+        url: url.to_string(),
+        delete: delete.map(str::to_string),
+        delete_command: None,
+        delete_args,
+        thumbnail,
+    };
+    if let Err(e) = crate::uploads::append(&history, record) {
+        log::warn!("upload history: {e}");
+    }
+}
+
+pub fn format_url(format: UrlFormat, url: &str) -> String {
+    match format {
+        UrlFormat::Url => url.to_string(),
+        UrlFormat::Markdown => format!("![]({url})"),
+        UrlFormat::Html => format!("<img src=\"{url}\">"),
+    }
+}
+
+fn copy_uploaded_url(cmd: &str, format: UrlFormat, stdout: &str) {
+    let Some(url) = extract_url(stdout) else {
+        log::warn!("hook `{cmd}` printed no url");
+        return;
+    };
+    match crate::clipboard::copy_text(&format_url(format, url)) {
+        Ok(()) => log::debug!("copied {format:?} for {url}"),
+        Err(e) => log::warn!("copy url failed: {e}"),
+    }
+}
+
+/// 代入模板变量；{path} 展开为 PATH_REF（已带引号，旧配置中的 "{path}" / '{path}' 视同 {path}），
+/// 实际路径由 run_shell 经 PATH_VAR 传入
+pub fn expand_template(template: &str, width: u32, height: u32) -> String {
+    template
+        .replace("\"{path}\"", "{path}")
+        .replace("'{path}'", "{path}")
+        .replace("{path}", PATH_REF)
+        .replace("{width}", &width.to_string())
+        .replace("{height}", &height.to_string())
+}
+
+// capture_stdout 时返回子进程标准输出（UTF-8 有损解码）；path 经 PATH_VAR 传给子进程
+fn run_shell(
+    cmd: &str,
+    path: Option<&Path>,
+    stdin: Option<&[u8]>,
+    capture_stdout: bool,
+) -> anyhow::Result<Option<String>> {
+    let mut command = shell_command(cmd);
+    if let Some(path) = path {
+        command.env(PATH_VAR, path);
+    }
+    command.stdin(if stdin.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    });
+    if capture_stdout {
+        command.stdout(Stdio::piped());
+    }
+    let mut child = command.spawn()?;
+    let pipe = child.stdin.take();
+    // stdin 在单独线程写入：子进程边读边输出时不会因 stdout 管道写满而互相等待
+    let output = thread::scope(|s| {
+        if let (Some(data), Some(mut pipe)) = (stdin, pipe) {
+            s.spawn(move || {
+                // 子进程可能不读 stdin 就退出：写入失败只记录，不视为命令失败
+                if let Err(e) = pipe.write_all(data) {
+                    log::debug!("hook stdin write: {e}");
+                }
+            });
+        }
+        child.wait_with_output()
+    })?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("exit status {}", output.status));
+    }
+    log::debug!("hook `{cmd}` finished");
+    Ok(capture_stdout.then(|| String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+#[cfg(target_os = "windows")]
+fn shell_command(cmd: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let mut c = Command::new("cmd");
+    // raw_arg：保持模板中的引号原样交给 cmd 解析
+    c.arg("/C").raw_arg(cmd).creation_flags(CREATE_NO_WINDOW);
+    c
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell_command(cmd: &str) -> Command {
+    let mut c = Command::new("sh");
+    c.arg("-c").arg(cmd);
+    c
+}
+
+// 与保存相同：同名文件已存在（同一秒的两次截图）时顺延为 name_2.png …，不覆盖仍在上传的文件
+fn write_temp_png(png: &[u8], name: &str) -> anyhow::Result<PathBuf> {
+    let base = std::env::temp_dir().join(name);
+    crate::encode::save_png_auto(png, &base.to_string_lossy())
+        .map_err(|e| anyhow::anyhow!("temp png: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_expand_template() {
+        let want = format!("up {PATH_REF} 640x480");
+        assert_eq!(
+            expand_template("up {path} {width}x{height}", 640, 480),
+            want
+        );
+        // 旧配置自带的引号不叠加
+        assert_eq!(
+            expand_template("up \"{path}\" {width}x{height}", 640, 480),
+            want
+        );
+        assert_eq!(
+            expand_template("up '{path}' {width}x{height}", 640, 480),
+            want
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_path_not_parsed_by_shell() {
+        let dir = TempDir::new("hook_path");
+        let marker = dir.join("marker");
+        let evil = format!(
+            "a'b\" $(touch {m}) `touch {m}`; touch {m} & x.png",
+            m = marker.display()
+        );
+        let cmd = expand_template("printf %s {path}", 1, 1);
+        let out = run_shell(&cmd, Some(Path::new(&evil)), None, true).unwrap();
+        assert_eq!(out.as_deref(), Some(evil.as_str()));
+        assert!(!marker.exists());
+    }
+
+    #[test]
+    fn test_extract_and_format_url() {
+        let out = "uploading...\ndone: https://img.example.com/a.png\n";
+        let url = extract_url(out).unwrap();
+        assert_eq!(url, "https://img.example.com/a.png");
+        assert_eq!(extract_url("no link here"), None);
+        assert_eq!(format_url(UrlFormat::Url, url), url);
+        assert_eq!(
+            format_url(UrlFormat::Markdown, url),
+            "![](https://img.example.com/a.png)"
+        );
+        assert_eq!(
+            format_url(UrlFormat::Html, url),
+            "<img src=\"https://img.example.com/a.png\">"
+        );
+    }
+
+    #[test]
+    fn test_extract_delete() {
+        let json = r#"{"data":{"deletehash":"AbC123","link":"https://i.imgur.com/x.png"}}"#;
+        assert_eq!(extract_delete(json), Some("AbC123"));
+        assert_eq!(
+            extract_delete("https://img.example.com/a.png\nDeleteHash: q9z\n"),
+            Some("q9z")
+        );
+        assert_eq!(extract_delete("delete:   \nhttps://a"), None);
+        assert_eq!(extract_delete("https://img.example.com/a.png"), None);
+        assert_eq!(
+            expand_delete("imgur-rm {delete} --url {url}", "https://a", Some("q9z")),
+            ["imgur-rm", "q9z", "--url", "https://a"]
+        );
+    }
+
+    #[test]
+    fn test_delete_args_not_reparsed() {
+        assert_eq!(
+            split_args(r#"curl -X DELETE "https://api.example.com/{delete}" -H 'A: b'"#),
+            [
+                "curl",
+                "-X",
+                "DELETE",
+                "https://api.example.com/{delete}",
+                "-H",
+                "A: b"
+            ]
+        );
+        assert_eq!(split_args(r#"rm "" x"#), ["rm", "", "x"]);
+        // 上传器输出中的 & | " 与空白都留在同一个参数里
+        let url = r#"https://host/x?a=1&b=2|calc "quoted" & del *"#;
+        let args = expand_delete(r#"del-tool "{url}" {delete}"#, url, Some("h&x|y\""));
+        assert_eq!(args, ["del-tool", url, "h&x|y\""]);
+        assert!(run_args(&[]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_args_without_shell() {
+        let dir = TempDir::new("hook_marker");
+        let marker = dir.join("marker");
+        let payload = format!("x; touch {} & touch {}", marker.display(), marker.display());
+        run_args(&["echo".to_string(), payload]).unwrap();
+        assert!(!marker.exists());
+        assert!(run_args(&["false".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_copy_url_config() {
+        let cfg: PostCaptureHook =
+            toml::from_str("on = \"copy\"\ncommand = \"up\"\ncopy_url = \"markdown\"").unwrap();
+        assert_eq!(cfg.copy_url, Some(UrlFormat::Markdown));
+    }
+
+    #[test]
+    fn test_temp_png_not_overwritten() {
+        let name = format!("snip_hook_temp_{}", std::process::id());
+        let a = write_temp_png(b"a", &name).unwrap();
+        let b = write_temp_png(b"b", &name).unwrap();
+        assert_ne!(a, b);
+        assert!(b.to_string_lossy().ends_with("_2.png"));
+        assert_eq!(std::fs::read(&a).unwrap(), b"a");
+        std::fs::remove_file(a).ok();
+        std::fs::remove_file(b).ok();
+    }
+}
@@ -1,25 +1,704 @@
 use anyhow::Result;
 use global_hotkey::hotkey::HotKey;
 use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
-use std::sync::mpsc::{self, Receiver};
-use std::thread;
-
-/// 订阅 F4 按下事件：每次按下发送一个 ()，持续有效。
-pub fn subscribe_f4() -> Result<Receiver<()>> {
-    use global_hotkey::hotkey::{Code, Modifiers};
-    let manager: &'static mut GlobalHotKeyManager =
-        Box::leak(Box::new(GlobalHotKeyManager::new()?));
-    let hotkey = HotKey::new(None, Code::F4);
-    let id = hotkey.id();
-    manager.register(hotkey)?;
-    let (tx, rx) = mpsc::channel();
-    thread::spawn(move || {
-        let rx_events = GlobalHotKeyEvent::receiver();
-        for event in rx_events {
-            if event.id == id && matches!(event.state, HotKeyState::Pressed) {
-                let _ = tx.send(());
-            }
-        }
-    });
-    Ok(rx)
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::CaptureAction;
+
+/// 默认截图热键
+pub const DEFAULT_HOTKEY: &str = "F4";
+/// 首选热键被占用时依次尝试的备用按键（配置 fallback_hotkeys 可覆盖，空数组表示不尝试）
+pub const DEFAULT_FALLBACKS: [&str; 2] = ["Shift+F4", "Ctrl+Shift+F4"];
+
+/// 双击截图热键的默认判定间隔（毫秒）
+pub const DEFAULT_DOUBLE_PRESS_MS: u64 = 300;
+
+/// 双击截图热键（[double_press]）：间隔内按两次截取鼠标所在的整个屏幕并执行 action，
+/// 按一次照常进入选区（需等待间隔结束才能确定，单击会延迟 interval_ms）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct DoublePressConfig {
+    pub interval_ms: u64,
+    /// 整屏截图的处理方式，默认复制到剪贴板
+    pub action: CaptureAction,
+}
+
+impl Default for DoublePressConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: DEFAULT_DOUBLE_PRESS_MS,
+            action: CaptureAction::Copy,
+        }
+    }
+}
+
+/// 截图热键的触发方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyMode {
+    /// 按下即进入选区
+    #[default]
+    Press,
+    /// 按住显示 overlay 并高亮鼠标下的窗口，松开截取高亮的窗口（短按仍为普通选区）
+    Hold,
+}
+
+/// 热键实现方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyBackend {
+    /// RegisterHotKey（global-hotkey）
+    #[default]
+    Global,
+    /// WH_KEYBOARD_LL 低级键盘钩子：全屏游戏 / 提权程序吞掉 RegisterHotKey 时使用
+    LowLevel,
+}
+
+/// 一次绑定的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindOutcome {
+    /// 首选按键注册成功
+    Primary(String),
+    /// 首选按键注册失败，改用备用按键
+    Fallback {
+        wanted: String,
+        used: String,
+        reason: String,
+    },
+    /// 全部失败，当前没有可用热键
+    Failed { wanted: String, reason: String },
+}
+
+impl BindOutcome {
+    /// 需要告知用户时的提示文本（首选成功时为 None）
+    pub fn message(&self) -> Option<String> {
+        match self {
+            Self::Primary(_) => None,
+            Self::Fallback {
+                wanted,
+                used,
+                reason,
+            } => Some(format!(
+                "截图热键 {wanted} 无法注册（{reason}），可能已被其它程序占用。\n已改用 {used}，可在配置 hotkey / fallback_hotkeys 中修改。"
+            )),
+            Self::Failed { wanted, reason } => Some(format!(
+                "截图热键 {wanted} 注册失败（{reason}），备用按键也不可用。\n请修改配置 hotkey 后执行 snip_rust --reload-config，或使用 snip_rust --capture 截图。"
+            )),
+        }
+    }
+}
+
+/// 热键触发的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    /// 进入选区 overlay
+    Capture,
+    /// 进入 overlay 并预选前台窗口（Alt+PrintScreen）
+    CaptureWindow,
+    /// 双击截图热键：截取整个屏幕（见 DoublePressConfig）
+    CaptureFullscreen,
+    /// hold 模式按下截图热键：显示 overlay 并跟随鼠标预选窗口
+    HoldStart,
+    /// hold 模式松开截图热键
+    HoldRelease,
+}
+
+type OnPress = Arc<dyn Fn(HotkeyAction) + Send + Sync>;
+
+// 一次截图热键按下的判定结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TapStep {
+    /// 第一次按下：间隔结束后用 seq 调用 tap_expired；stale 表示上一次单击的计时已过期但尚未触发
+    Wait { seq: u64, stale: bool },
+    /// 间隔内的第二次按下
+    Double,
+}
+
+#[derive(Debug, Default)]
+struct TapState {
+    seq: u64,
+    pending: Option<Instant>, // 等待第二次按下的单击
+}
+
+fn tap_press(state: &mut TapState, now: Instant, window: Duration) -> TapStep {
+    let stale = match state.pending.take() {
+        Some(at) if now.duration_since(at) <= window => {
+            state.seq += 1;
+            return TapStep::Double;
+        }
+        Some(_) => true,
+        None => false,
+    };
+    state.seq += 1;
+    state.pending = Some(now);
+    TapStep::Wait {
+        seq: state.seq,
+        stale,
+    }
+}
+
+// 计时结束：该次单击仍在等待（没有第二次按下）时返回 true
+fn tap_expired(state: &mut TapState, seq: u64) -> bool {
+    if state.seq != seq || state.pending.is_none() {
+        return false;
+    }
+    state.pending = None;
+    true
+}
+
+// 截图热键的单击 / 双击 / 按住分发；间隔为 0 时不等待，每次按下都是单击
+#[derive(Default)]
+struct MultiTap {
+    window_ms: AtomicU64,
+    hold: AtomicBool, // HotkeyMode::Hold：按下 / 松开分别触发，不判定双击
+    state: Mutex<TapState>,
+}
+
+impl MultiTap {
+    fn press(self: &Arc<Self>, on_press: &OnPress) {
+        if self.hold.load(Ordering::SeqCst) {
+            on_press(HotkeyAction::HoldStart);
+            return;
+        }
+        let window = Duration::from_millis(self.window_ms.load(Ordering::SeqCst));
+        if window.is_zero() {
+            on_press(HotkeyAction::Capture);
+            return;
+        }
+        let step = tap_press(&mut self.state.lock().unwrap(), Instant::now(), window);
+        let seq = match step {
+            TapStep::Double => return on_press(HotkeyAction::CaptureFullscreen),
+            TapStep::Wait { seq, stale } => {
+                if stale {
+                    on_press(HotkeyAction::Capture);
+                }
+                seq
+            }
+        };
+        let (taps, on_press) = (self.clone(), on_press.clone());
+        std::thread::spawn(move || {
+            std::thread::sleep(window);
+            if tap_expired(&mut taps.state.lock().unwrap(), seq) {
+                on_press(HotkeyAction::Capture);
+            }
+        });
+    }
+
+    fn release(&self, on_press: &OnPress) {
+        if self.hold.load(Ordering::SeqCst) {
+            on_press(HotkeyAction::HoldRelease);
+        }
+    }
+}
+
+// 注册槽：截图热键 / PrintScreen / Alt+PrintScreen
+const SLOT_CAPTURE: usize = 0;
+const SLOT_PRINT: usize = 1;
+const SLOT_ALT_PRINT: usize = 2;
+const SLOT_ACTIONS: [HotkeyAction; 3] = [
+    HotkeyAction::Capture,
+    HotkeyAction::Capture,
+    HotkeyAction::CaptureWindow,
+];
+
+// 低级钩子暂停时 F4 原样传给前台程序
+static LOW_LEVEL_PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// 截图热键的注册状态：持有 GlobalHotKeyManager，可在运行时重新绑定 / 暂停
+pub struct Hotkeys {
+    backend: HotkeyBackend,
+    manager: Option<GlobalHotKeyManager>, // Global 后端
+    current: [Option<HotKey>; 3],
+    ids: Arc<[AtomicU32; 3]>, // 各槽当前热键 id；0 表示未注册
+    taps: Arc<MultiTap>,
+    paused: bool,
+}
+
+impl Hotkeys {
+    /// 安装事件处理器（尚未注册任何按键，之后调用 `bind`）
+    pub fn new(
+        backend: HotkeyBackend,
+        on_press: impl Fn(HotkeyAction) + Send + Sync + 'static,
+    ) -> Result<Self> {
+        let ids: Arc<[AtomicU32; 3]> = Arc::new(Default::default());
+        let taps = Arc::new(MultiTap::default());
+        let on_press: OnPress = Arc::new(on_press);
+        let manager = match backend {
+            HotkeyBackend::Global => {
+                let manager = GlobalHotKeyManager::new()?;
+                let (slots, taps) = (ids.clone(), taps.clone());
+                GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+                    let slot = slots
+                        .iter()
+                        .position(|id| event.id != 0 && id.load(Ordering::SeqCst) == event.id);
+                    let pressed = matches!(event.state, HotKeyState::Pressed);
+                    match slot {
+                        Some(SLOT_CAPTURE) if pressed => taps.press(&on_press),
+                        Some(SLOT_CAPTURE) => taps.release(&on_press),
+                        Some(slot) if pressed => on_press(SLOT_ACTIONS[slot]),
+                        _ => {}
+                    }
+                }));
+                Some(manager)
+            }
+            HotkeyBackend::LowLevel => {
+                let taps = taps.clone();
+                subscribe_f4_low_level_events(move |down| match down {
+                    true => taps.press(&on_press),
+                    false => taps.release(&on_press),
+                })?;
+                None
+            }
+        };
+        Ok(Self {
+            backend,
+            manager,
+            current: [None; 3],
+            ids,
+            taps,
+            paused: false,
+        })
+    }
+
+    pub fn backend(&self) -> HotkeyBackend {
+        self.backend
+    }
+
+    /// 当前生效的截图热键（`HotKey` 的字符串形式）
+    pub fn current(&self) -> Option<String> {
+        self.current[SLOT_CAPTURE].map(|hk| hk.into_string())
+    }
+
+    /// 截图热键的双击判定间隔；None 时不区分双击（按下立即进入选区）
+    pub fn set_double_press(&self, window: Option<Duration>) {
+        let ms = window.map_or(0, |w| w.as_millis() as u64);
+        self.taps.window_ms.store(ms, Ordering::SeqCst);
+    }
+
+    /// 截图热键按下 / 按住的触发方式；Hold 时双击判定不生效
+    pub fn set_mode(&self, mode: HotkeyMode) {
+        self.taps
+            .hold
+            .store(mode == HotkeyMode::Hold, Ordering::SeqCst);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// 暂停：注销全部按键（记住绑定以便恢复）；低级钩子改为放行 F4
+    pub fn pause(&mut self) {
+        if self.paused {
+            return;
+        }
+        self.paused = true;
+        LOW_LEVEL_PAUSED.store(true, Ordering::SeqCst);
+        for (slot, id) in self.ids.iter().enumerate() {
+            id.store(0, Ordering::SeqCst);
+            if let (Some(manager), Some(hk)) = (&self.manager, self.current[slot]) {
+                if let Err(e) = manager.unregister(hk) {
+                    log::warn!("unregister hotkey {hk}: {e}");
+                }
+            }
+        }
+        log::debug!("hotkeys paused");
+    }
+
+    /// 恢复暂停前的绑定；返回注册失败的按键（可能在暂停期间被其它程序占用）
+    pub fn resume(&mut self) -> Vec<String> {
+        if !self.paused {
+            return Vec::new();
+        }
+        self.paused = false;
+        LOW_LEVEL_PAUSED.store(false, Ordering::SeqCst);
+        let mut failed = Vec::new();
+        for slot in 0..self.current.len() {
+            let Some(hk) = self.current[slot] else {
+                continue;
+            };
+            let result = match &self.manager {
+                Some(manager) => manager.register(hk),
+                None => Ok(()),
+            };
+            match result {
+                Ok(()) => self.ids[slot].store(hk.id(), Ordering::SeqCst),
+                Err(e) => {
+                    log::warn!("re-register hotkey {hk}: {e}");
+                    self.current[slot] = None;
+                    failed.push(hk.into_string());
+                }
+            }
+        }
+        log::debug!("hotkeys resumed");
+        failed
+    }
+
+    /// 退出前注销全部按键（低级钩子改为放行 F4）
+    pub fn unregister_all(&mut self) {
+        for slot in 0..self.current.len() {
+            self.unregister_slot(slot);
+        }
+        LOW_LEVEL_PAUSED.store(true, Ordering::SeqCst);
+    }
+
+    fn unregister_slot(&mut self, slot: usize) {
+        self.ids[slot].store(0, Ordering::SeqCst);
+        let old = self.current[slot].take();
+        if self.paused {
+            return; // 已在 pause 时注销
+        }
+        if let (Some(manager), Some(old)) = (&self.manager, old) {
+            if let Err(e) = manager.unregister(old) {
+                log::warn!("unregister hotkey {old}: {e}");
+            }
+        }
+    }
+
+    fn register_slot(&mut self, slot: usize, hk: HotKey) {
+        self.current[slot] = Some(hk);
+        self.ids[slot].store(hk.id(), Ordering::SeqCst);
+    }
+
+    /// 注销当前按键后依次尝试 wanted 与 fallbacks
+    pub fn bind(&mut self, wanted: &str, fallbacks: &[String]) -> BindOutcome {
+        if self.manager.is_none() {
+            // 低级钩子固定拦截 F4
+            let plain_f4 = wanted
+                .parse::<HotKey>()
+                .is_ok_and(|hk| hk == HotKey::new(None, global_hotkey::hotkey::Code::F4));
+            return if plain_f4 {
+                BindOutcome::Primary(DEFAULT_HOTKEY.to_string())
+            } else {
+                BindOutcome::Failed {
+                    wanted: wanted.to_string(),
+                    reason: "low_level backend only supports F4".into(),
+                }
+            };
+        }
+        self.unregister_slot(SLOT_CAPTURE);
+        if self.paused {
+            // 暂停期间只记录绑定，resume 时注册并报告失败
+            return match wanted.parse::<HotKey>() {
+                Ok(hk) => {
+                    self.current[SLOT_CAPTURE] = Some(hk);
+                    BindOutcome::Primary(wanted.to_string())
+                }
+                Err(e) => BindOutcome::Failed {
+                    wanted: wanted.to_string(),
+                    reason: e.to_string(),
+                },
+            };
+        }
+        let candidates: Vec<&str> = std::iter::once(wanted)
+            .chain(fallbacks.iter().map(String::as_str))
+            .collect();
+        let manager = self.manager.as_ref().expect("global backend");
+        let (chosen, reason) = choose_binding(&candidates, |hk| {
+            manager.register(hk).map_err(anyhow::Error::from)
+        });
+        let reason = reason.unwrap_or_default();
+        match chosen {
+            Some((i, hk)) => {
+                self.register_slot(SLOT_CAPTURE, hk);
+                log::debug!("hotkey registered: {}", candidates[i]);
+                if i == 0 {
+                    BindOutcome::Primary(candidates[0].to_string())
+                } else {
+                    BindOutcome::Fallback {
+                        wanted: wanted.to_string(),
+                        used: candidates[i].to_string(),
+                        reason,
+                    }
+                }
+            }
+            None => BindOutcome::Failed {
+                wanted: wanted.to_string(),
+                reason,
+            },
+        }
+    }
+
+    /// 接管 PrintScreen（截图）与 Alt+PrintScreen（前台窗口）；enabled = false 时释放。
+    /// 两者独立注册，返回第一个失败原因
+    pub fn set_print_screen(&mut self, enabled: bool) -> Result<()> {
+        self.unregister_slot(SLOT_PRINT);
+        self.unregister_slot(SLOT_ALT_PRINT);
+        if !enabled {
+            return Ok(());
+        }
+        let Some(manager) = &self.manager else {
+            return Err(anyhow::anyhow!(
+                "print_screen requires the global hotkey backend"
+            ));
+        };
+        let (mut registered, mut first_error) = (Vec::new(), None);
+        for (slot, text) in [
+            (SLOT_PRINT, "PrintScreen"),
+            (SLOT_ALT_PRINT, "Alt+PrintScreen"),
+        ] {
+            let hk: HotKey = text.parse().expect("valid builtin hotkey");
+            if self.paused {
+                self.current[slot] = Some(hk);
+                continue;
+            }
+            match manager.register(hk) {
+                Ok(()) => registered.push((slot, hk)),
+                Err(e) => {
+                    log::warn!("register hotkey {text}: {e}");
+                    first_error.get_or_insert(anyhow::anyhow!("{text}: {e}"));
+                }
+            }
+        }
+        for (slot, hk) in registered {
+            self.register_slot(slot, hk);
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+}
+
+/// 依次尝试候选按键，返回第一个注册成功的 (下标, HotKey) 与首选按键的失败原因
+fn choose_binding(
+    candidates: &[&str],
+    mut register: impl FnMut(HotKey) -> Result<()>,
+) -> (Option<(usize, HotKey)>, Option<String>) {
+    let mut first_error = None;
+    for (i, text) in candidates.iter().enumerate() {
+        let result = text
+            .parse::<HotKey>()
+            .map_err(|e| anyhow::anyhow!("invalid hotkey {text:?}: {e}"))
+            .and_then(|hk| register(hk).map(|_| hk));
+        match result {
+            Ok(hk) => return (Some((i, hk)), first_error),
+            Err(e) => {
+                log::warn!("register hotkey {text}: {e}");
+                first_error.get_or_insert(e.to_string());
+            }
+        }
+    }
+    (None, first_error)
+}
+
+/// 低级键盘钩子：专用线程安装 WH_KEYBOARD_LL 并运行消息循环（Hotkeys 的 low_level 后端）。
+/// 只响应不带修饰键的 F4（Alt+F4 等照常传给前台程序），按住不放的自动重复只触发一次；
+/// 命中的 F4 被吞掉，与 RegisterHotKey 行为一致。按下时以 true、松开处理过的 F4 时以 false
+/// 调用回调（hold 模式）。进程内只应安装一次。
+#[cfg(target_os = "windows")]
+pub fn subscribe_f4_low_level_events(
+    on_event: impl Fn(bool) + Send + Sync + 'static,
+) -> Result<()> {
+    use anyhow::anyhow;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::OnceLock;
+    use windows::Win32::Foundation::{LPARAM, LRESULT, WPARAM};
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        GetAsyncKeyState, VK_CONTROL, VK_F4, VK_LWIN, VK_MENU, VK_RWIN, VK_SHIFT,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, DispatchMessageW, GetMessageW, SetWindowsHookExW, TranslateMessage,
+        HC_ACTION, KBDLLHOOKSTRUCT, MSG, WH_KEYBOARD_LL, WM_KEYDOWN, WM_KEYUP, WM_SYSKEYDOWN,
+        WM_SYSKEYUP,
+    };
+
+    type Callback = Box<dyn Fn(bool) + Send + Sync>;
+    static CALLBACK: OnceLock<Callback> = OnceLock::new();
+    static HELD: AtomicBool = AtomicBool::new(false);
+
+    unsafe extern "system" fn hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if code == HC_ACTION as i32 && !LOW_LEVEL_PAUSED.load(Ordering::SeqCst) {
+            let info = &*(lparam.0 as *const KBDLLHOOKSTRUCT);
+            let down = matches!(wparam.0 as u32, WM_KEYDOWN | WM_SYSKEYDOWN);
+            let up = matches!(wparam.0 as u32, WM_KEYUP | WM_SYSKEYUP);
+            let modifiers = [VK_MENU, VK_CONTROL, VK_SHIFT, VK_LWIN, VK_RWIN]
+                .iter()
+                .any(|vk| GetAsyncKeyState(vk.0 as i32) as u16 & 0x8000 != 0);
+            let was_held = HELD.load(Ordering::SeqCst);
+            match f4_action(info.vkCode == VK_F4.0 as u32, down, up, modifiers, &HELD) {
+                F4Action::Fire => {
+                    if let Some(cb) = CALLBACK.get() {
+                        cb(true);
+                    }
+                    return LRESULT(1);
+                }
+                F4Action::Swallow => {
+                    // 吞掉的抬起：对应的按下已触发
+                    if up && was_held {
+                        if let Some(cb) = CALLBACK.get() {
+                            cb(false);
+                        }
+                    }
+                    return LRESULT(1);
+                }
+                F4Action::Pass => {}
+            }
+        }
+        CallNextHookEx(None, code, wparam, lparam)
+    }
+
+    CALLBACK
+        .set(Box::new(on_event))
+        .map_err(|_| anyhow!("low-level keyboard hook already installed"))?;
+    let (ready_tx, ready_rx) = mpsc::channel();
+    std::thread::Builder::new()
+        .name("snip-kbd-hook".into())
+        .spawn(move || unsafe {
+            let module = GetModuleHandleW(None).ok();
+            let hook =
+                SetWindowsHookExW(WH_KEYBOARD_LL, Some(hook_proc), module.map(Into::into), 0);
+            let installed = hook.is_ok();
+            let _ = ready_tx.send(
+                hook.map(|_| ())
+                    .map_err(|e| anyhow!("SetWindowsHookExW: {e}")),
+            );
+            if !installed {
+                return;
+            }
+            // 钩子回调在本线程的消息循环中派发，线程随进程存活
+            let mut msg = MSG::default();
+            while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        })?;
+    ready_rx
+        .recv()
+        .map_err(|_| anyhow!("keyboard hook thread exited"))?
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn subscribe_f4_low_level_events(
+    _on_event: impl Fn(bool) + Send + Sync + 'static,
+) -> Result<()> {
+    Err(anyhow::anyhow!(
+        "low-level keyboard hook is only available on Windows"
+    ))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum F4Action {
+    Fire,
+    Swallow,
+    Pass,
+}
+
+// 单个键盘事件的处理：held 记录 F4 是否已按下（过滤自动重复）
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn f4_action(
+    is_f4: bool,
+    down: bool,
+    up: bool,
+    modifiers: bool,
+    held: &std::sync::atomic::AtomicBool,
+) -> F4Action {
+    use std::sync::atomic::Ordering;
+    if !is_f4 {
+        return F4Action::Pass;
+    }
+    if up {
+        // 只吞掉自己处理过的按下对应的抬起
+        return if held.swap(false, Ordering::SeqCst) {
+            F4Action::Swallow
+        } else {
+            F4Action::Pass
+        };
+    }
+    if !down || modifiers {
+        return F4Action::Pass;
+    }
+    if held.swap(true, Ordering::SeqCst) {
+        F4Action::Swallow
+    } else {
+        F4Action::Fire
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+
+    #[test]
+    fn test_choose_binding_falls_back() {
+        let taken: HotKey = "F4".parse().unwrap();
+        let (chosen, reason) = choose_binding(&["F4", "bogus+", "Shift+F4"], |hk| {
+            if hk == taken {
+                Err(anyhow::anyhow!("already registered"))
+            } else {
+                Ok(())
+            }
+        });
+        let (i, hk) = chosen.unwrap();
+        assert_eq!(i, 2);
+        assert_eq!(hk, "shift+F4".parse().unwrap());
+        assert_eq!(reason.as_deref(), Some("already registered"));
+        let (chosen, reason) = choose_binding(&["F4"], |_| Ok(()));
+        assert_eq!(chosen.map(|c| c.0), Some(0));
+        assert!(reason.is_none());
+        let (chosen, _) = choose_binding(&["F4"], |_| Err(anyhow::anyhow!("busy")));
+        assert!(chosen.is_none());
+    }
+
+    #[test]
+    fn test_bind_outcome_message() {
+        assert_eq!(BindOutcome::Primary("F4".into()).message(), None);
+        let msg = BindOutcome::Fallback {
+            wanted: "F4".into(),
+            used: "Shift+F4".into(),
+            reason: "busy".into(),
+        }
+        .message()
+        .unwrap();
+        assert!(msg.contains("已改用 Shift+F4"));
+    }
+
+    #[test]
+    fn test_tap_single_and_double() {
+        let window = Duration::from_millis(300);
+        let t0 = Instant::now();
+        let mut state = TapState::default();
+        // 单击：计时结束时触发
+        let TapStep::Wait { seq, stale: false } = tap_press(&mut state, t0, window) else {
+            panic!("first press should wait");
+        };
+        assert!(tap_expired(&mut state, seq));
+        assert!(!tap_expired(&mut state, seq));
+        // 双击：第二次按下立即触发，第一次的计时作废
+        let t1 = t0 + Duration::from_secs(1);
+        let TapStep::Wait { seq, .. } = tap_press(&mut state, t1, window) else {
+            panic!("first press should wait");
+        };
+        let t2 = t1 + Duration::from_millis(200);
+        assert_eq!(tap_press(&mut state, t2, window), TapStep::Double);
+        assert!(!tap_expired(&mut state, seq));
+        // 超过间隔的第二次按下重新计时，尚未触发的上一次单击标记为过期
+        let t3 = t2 + Duration::from_secs(1);
+        tap_press(&mut state, t3, window);
+        let late = tap_press(&mut state, t3 + Duration::from_millis(400), window);
+        assert!(matches!(late, TapStep::Wait { stale: true, .. }));
+        let cfg: DoublePressConfig = toml::from_str("interval_ms = 250").unwrap();
+        assert_eq!((cfg.interval_ms, cfg.action), (250, CaptureAction::Copy));
+    }
+
+    #[test]
+    fn test_f4_action_repeat_and_modifiers() {
+        let held = AtomicBool::new(false);
+        assert_eq!(f4_action(true, true, false, false, &held), F4Action::Fire);
+        // 自动重复
+        assert_eq!(
+            f4_action(true, true, false, false, &held),
+            F4Action::Swallow
+        );
+        assert_eq!(
+            f4_action(true, false, true, false, &held),
+            F4Action::Swallow
+        );
+        // Alt+F4 原样传递，抬起也不吞
+        assert_eq!(f4_action(true, true, false, true, &held), F4Action::Pass);
+        assert_eq!(f4_action(true, false, true, true, &held), F4Action::Pass);
+        assert_eq!(f4_action(false, true, false, false, &held), F4Action::Pass);
+    }
 }
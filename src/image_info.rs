@@ -0,0 +1,154 @@
+// image_info: 选区图像信息（尺寸 / 百万像素 / 平均色 / 主色 / 不同颜色数 / 估算 PNG 大小）
+//
+// 工具栏“更多”→“图像信息”在后台线程调用 analyze，结果以文本弹窗显示并复制到剪贴板。
+// 主色按每通道高 4 位分桶（4096 个桶）统计，代表色为桶内像素的平均值；忽略 alpha。
+
+use std::collections::HashSet;
+
+/// 最多列出的主色数
+pub const DOMINANT_COLORS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    /// 不同 RGB 颜色数
+    pub unique_colors: usize,
+    pub average: [u8; 3],
+    /// 主色及其像素占比（0..=1），占比高的在前
+    pub dominant: Vec<([u8; 3], f32)>,
+    /// 按当前 PNG 编码估算的文件大小（字节）；编码失败时为 None
+    pub png_bytes: Option<usize>,
+}
+
+/// 统计 RGBA 图像；尺寸与数据不符时按空图像处理
+pub fn analyze(width: u32, height: u32, rgba: &[u8]) -> ImageInfo {
+    let count = width as usize * height as usize;
+    let pixels = if count > 0 && rgba.len() >= count * 4 {
+        &rgba[..count * 4]
+    } else {
+        &[]
+    };
+    let mut unique = HashSet::new();
+    let mut sum = [0u64; 3];
+    // 每个桶：像素数 + 各通道累加
+    let mut bins = vec![(0u32, [0u64; 3]); 4096];
+    for px in pixels.chunks_exact(4) {
+        let (r, g, b) = (px[0], px[1], px[2]);
+        unique.insert(u32::from_le_bytes([r, g, b, 0]));
+        let bin = &mut bins[(r as usize >> 4) << 8 | (g as usize >> 4) << 4 | b as usize >> 4];
+        bin.0 += 1;
+        for (c, v) in [r, g, b].into_iter().enumerate() {
+            sum[c] += v as u64;
+            bin.1[c] += v as u64;
+        }
+    }
+    let n = (pixels.len() / 4) as u64;
+    let mean = |total: u64, n: u64| (total + n / 2).checked_div(n).unwrap_or(0) as u8;
+    let mut used: Vec<_> = bins.into_iter().filter(|b| b.0 > 0).collect();
+    used.sort_by_key(|b| std::cmp::Reverse(b.0));
+    let dominant = used
+        .into_iter()
+        .take(DOMINANT_COLORS)
+        .map(|(k, s)| {
+            let k64 = k as u64;
+            (
+                [mean(s[0], k64), mean(s[1], k64), mean(s[2], k64)],
+                k as f32 / n as f32,
+            )
+        })
+        .collect();
+    let png_bytes = match n {
+        0 => None,
+        _ => crate::capture::encode_png(pixels, width, height)
+            .ok()
+            .map(|png| png.len()),
+    };
+    ImageInfo {
+        width,
+        height,
+        unique_colors: unique.len(),
+        average: [mean(sum[0], n), mean(sum[1], n), mean(sum[2], n)],
+        dominant,
+        png_bytes,
+    }
+}
+
+/// `#RRGGBB`
+pub fn hex(c: [u8; 3]) -> String {
+    format!("#{:02X}{:02X}{:02X}", c[0], c[1], c[2])
+}
+
+impl ImageInfo {
+    pub fn megapixels(&self) -> f64 {
+        self.width as f64 * self.height as f64 / 1_000_000.0
+    }
+
+    /// 弹窗 / 剪贴板文本
+    pub fn summary(&self) -> String {
+        let mut text = format!(
+            "尺寸：{} × {}（{:.2} 百万像素）\n平均色：{}\n不同颜色数：{}\n",
+            self.width,
+            self.height,
+            self.megapixels(),
+            hex(self.average),
+            self.unique_colors
+        );
+        if let Some(bytes) = self.png_bytes {
+            text += &format!("PNG 大小约：{}\n", format_size(bytes));
+        }
+        text += "主色：";
+        for (color, share) in &self.dominant {
+            text += &format!("\n  {}  {:.1}%", hex(*color), share * 100.0);
+        }
+        text
+    }
+}
+
+fn format_size(bytes: usize) -> String {
+    match bytes {
+        0..=1023 => format!("{bytes} B"),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.2} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_two_colors() {
+        // 3 个红色像素 + 1 个接近红色（同一桶）+ 4 个蓝色，4x2
+        let mut rgba = Vec::new();
+        for c in [
+            [255, 0, 0],
+            [255, 0, 0],
+            [255, 0, 0],
+            [251, 3, 0],
+            [0, 0, 200],
+            [0, 0, 200],
+            [0, 0, 200],
+            [0, 0, 200],
+        ] {
+            rgba.extend([c[0], c[1], c[2], 255]);
+        }
+        let info = analyze(4, 2, &rgba);
+        assert_eq!(info.unique_colors, 3);
+        assert_eq!(info.average, [127, 0, 100]);
+        assert_eq!(info.dominant.len(), 2);
+        assert_eq!(info.dominant[0].1, 0.5);
+        // 占比相同时按桶顺序
+        assert_eq!(hex(info.dominant[0].0), "#0000C8");
+        assert_eq!(hex(info.dominant[1].0), "#FE0100");
+        assert!(info.png_bytes.unwrap() > 0);
+        let text = info.summary();
+        assert!(text.contains("4 × 2"));
+        assert!(text.contains("#0000C8  50.0%"));
+        // 数据不足时为空图像
+        let empty = analyze(10, 10, &rgba);
+        assert_eq!((empty.unique_colors, empty.png_bytes), (0, None));
+        assert!(empty.dominant.is_empty());
+        assert_eq!(format_size(2048), "2.0 KB");
+    }
+}
@@ -0,0 +1,380 @@
+// ipc: 外部控制通道（Windows 命名管道 / 其它平台 Unix domain socket）
+//
+// 协议：每行一个 JSON 命令，例如 `{"cmd":"capture_region"}`；服务端对每行回复一行
+// `{"ok":true}` 或 `{"ok":false,"error":"..."}`。解析成功的命令通过 channel 交给主循环。
+// 有结果的命令（pick_region）附带 Responder，连接线程等主循环给出结果后才回复
+// `{"ok":true,"result":...}`。
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum IpcCommand {
+    /// 进入选区 overlay（等同按下 F4）
+    CaptureRegion,
+    /// 把剪贴板图像钉为粘贴窗口
+    PinClipboard,
+    /// 重新读取配置文件并按新配置重新注册热键
+    ReloadConfig,
+    /// 等待用户框选，回复选区屏幕坐标 `{"x","y","width","height"}`，取消时为 null；不保存、不钉图
+    PickRegion,
+    /// 截取全部显示器拼成一张图，执行配置的 capture_action（钉图 / 保存 / 复制）
+    CaptureDesktop,
+    /// 连拍主屏，以最清晰的一帧打开 overlay
+    Burst,
+    /// 载入标注 sidecar（或其标注后的 PNG）到 overlay 继续编辑
+    EditAnnotations {
+        path: PathBuf,
+    },
+    /// 打开 .snip 工程继续编辑
+    OpenProject {
+        path: PathBuf,
+    },
+    /// 把 PNG 文件钉为粘贴窗口
+    PinImage {
+        path: PathBuf,
+    },
+    Quit,
+}
+
+impl IpcCommand {
+    /// 需要等待主循环结果才能回复的命令
+    pub fn wants_result(&self) -> bool {
+        matches!(self, IpcCommand::PickRegion)
+    }
+}
+
+/// 延迟回复：主循环处理完命令后调用 ok / err；未调用就被丢弃时客户端收到错误
+#[derive(Debug)]
+pub struct Responder(Sender<Result<serde_json::Value, String>>);
+
+impl Responder {
+    pub fn ok(self, result: serde_json::Value) {
+        let _ = self.0.send(Ok(result));
+    }
+
+    pub fn err(self, error: impl Into<String>) {
+        let _ = self.0.send(Err(error.into()));
+    }
+}
+
+/// 主循环收到的命令；wants_result 的命令附带 Responder
+pub type IpcRequest = (IpcCommand, Option<Responder>);
+
+pub fn parse_command(line: &str) -> Result<IpcCommand> {
+    serde_json::from_str(line.trim()).map_err(|e| anyhow!("invalid command: {e}"))
+}
+
+/// 启动 IPC 服务线程，返回命令接收端（与 hotkey 的 subscribe_* 模式一致）
+pub fn serve() -> Result<Receiver<IpcRequest>> {
+    let (tx, rx) = mpsc::channel();
+    platform::spawn_server(tx)?;
+    Ok(rx)
+}
+
+/// 客户端：向正在运行的实例发送一条命令并等待回复
+pub fn send(cmd: &IpcCommand) -> Result<()> {
+    request(cmd).map(|_| ())
+}
+
+/// 与 send 相同，返回回复中的 result（没有时为 null）
+pub fn request(cmd: &IpcCommand) -> Result<serde_json::Value> {
+    let mut stream = platform::connect()?;
+    let mut line = serde_json::to_string(cmd)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    stream.flush()?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    let value: serde_json::Value =
+        serde_json::from_str(reply.trim()).map_err(|e| anyhow!("invalid reply: {e}"))?;
+    if value["ok"].as_bool() == Some(true) {
+        Ok(value["result"].clone())
+    } else {
+        Err(anyhow!(
+            "server error: {}",
+            value["error"].as_str().unwrap_or("unknown")
+        ))
+    }
+}
+
+// 处理单个连接：逐行解析，回复结果；连接断开即返回
+fn handle_connection<S: Read + Write>(stream: S, tx: &Sender<IpcRequest>) {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match parse_command(&line) {
+            Ok(cmd) if cmd.wants_result() => {
+                log::debug!("ipc command: {cmd:?}");
+                let (reply_tx, reply_rx) = mpsc::channel();
+                if tx.send((cmd, Some(Responder(reply_tx)))).is_err() {
+                    return; // 主循环已退出
+                }
+                match reply_rx.recv() {
+                    Ok(Ok(result)) => serde_json::json!({ "ok": true, "result": result }),
+                    Ok(Err(e)) => serde_json::json!({ "ok": false, "error": e }),
+                    Err(_) => serde_json::json!({ "ok": false, "error": "no result" }),
+                }
+            }
+            Ok(cmd) => {
+                log::debug!("ipc command: {cmd:?}");
+                if tx.send((cmd, None)).is_err() {
+                    return; // 主循环已退出
+                }
+                serde_json::json!({ "ok": true })
+            }
+            Err(e) => serde_json::json!({ "ok": false, "error": e.to_string() }),
+        };
+        let out = reader.get_mut();
+        if writeln!(out, "{reply}").and_then(|_| out.flush()).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::*;
+    use std::fs::{File, OpenOptions};
+    use std::os::windows::io::{AsRawHandle, FromRawHandle};
+    use std::time::Duration;
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{
+        GetLastError, ERROR_PIPE_CONNECTED, HANDLE, INVALID_HANDLE_VALUE,
+    };
+    use windows::Win32::Storage::FileSystem::PIPE_ACCESS_DUPLEX;
+    use windows::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+        PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+    };
+
+    pub const PIPE_NAME: &str = r"\\.\pipe\SnipRust";
+
+    pub fn spawn_server(tx: Sender<IpcRequest>) -> Result<()> {
+        // 先同步创建第一个实例，确保创建失败（例如名称被占用）能返回给调用方
+        let first = create_instance()?;
+        thread::spawn(move || {
+            let mut next = Some(first);
+            loop {
+                let pipe = match next.take().map(Ok).unwrap_or_else(create_instance) {
+                    Ok(p) => p,
+                    Err(e) => {
+                        log::warn!("ipc pipe: {e}");
+                        return;
+                    }
+                };
+                if let Err(e) = wait_client(&pipe) {
+                    log::warn!("ipc pipe: {e}");
+                    continue;
+                }
+                let tx = tx.clone();
+                thread::spawn(move || handle_connection(pipe, &tx));
+            }
+        });
+        Ok(())
+    }
+
+    // 创建一个管道实例；File 接管句柄（drop 时关闭）
+    fn create_instance() -> Result<File> {
+        let wide: Vec<u16> = PIPE_NAME.encode_utf16().chain(std::iter::once(0)).collect();
+        let handle = unsafe {
+            CreateNamedPipeW(
+                PCWSTR(wide.as_ptr()),
+                PIPE_ACCESS_DUPLEX,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                4096,
+                4096,
+                0,
+                None,
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(anyhow!("create named pipe failed: {:?}", unsafe {
+                GetLastError()
+            }));
+        }
+        Ok(unsafe { File::from_raw_handle(handle.0 as _) })
+    }
+
+    // 阻塞等待客户端连接
+    fn wait_client(pipe: &File) -> Result<()> {
+        let handle = HANDLE(pipe.as_raw_handle() as _);
+        match unsafe { ConnectNamedPipe(handle, None) } {
+            Ok(()) => Ok(()),
+            // 客户端在 Create 与 Connect 之间已连上，视为成功
+            Err(e) if e.code() == ERROR_PIPE_CONNECTED.to_hresult() => Ok(()),
+            Err(e) => Err(anyhow!("connect named pipe: {e}")),
+        }
+    }
+
+    pub fn connect() -> Result<File> {
+        // 服务端在两次连接之间重建实例，短暂 PIPE_BUSY 时重试
+        let mut last_err = None;
+        for _ in 0..5 {
+            match OpenOptions::new().read(true).write(true).open(PIPE_NAME) {
+                Ok(f) => return Ok(f),
+                Err(e) => last_err = Some(e),
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        Err(anyhow!("open pipe {PIPE_NAME}: {}", last_err.unwrap()))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use super::*;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::PathBuf;
+
+    pub fn socket_path() -> PathBuf {
+        dirs::runtime_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("snip_rust.sock")
+    }
+
+    pub fn spawn_server(tx: Sender<IpcRequest>) -> Result<()> {
+        let path = socket_path();
+        if UnixStream::connect(&path).is_ok() {
+            return Err(anyhow!("ipc socket {} already served", path.display()));
+        }
+        // 上次异常退出残留的 socket 文件
+        let _ = std::fs::remove_file(&path);
+        let listener =
+            UnixListener::bind(&path).map_err(|e| anyhow!("bind {}: {e}", path.display()))?;
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let tx = tx.clone();
+                thread::spawn(move || handle_connection(stream, &tx));
+            }
+        });
+        Ok(())
+    }
+
+    pub fn connect() -> Result<UnixStream> {
+        let path = socket_path();
+        UnixStream::connect(&path).map_err(|e| anyhow!("connect {}: {e}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command("{\"cmd\":\"capture_region\"}\n").unwrap(),
+            IpcCommand::CaptureRegion
+        );
+        assert_eq!(
+            parse_command(r#"{"cmd":"quit"}"#).unwrap(),
+            IpcCommand::Quit
+        );
+        assert_eq!(
+            parse_command(r#"{"cmd":"reload_config"}"#).unwrap(),
+            IpcCommand::ReloadConfig
+        );
+        assert_eq!(
+            parse_command(r#"{"cmd":"pick_region"}"#).unwrap(),
+            IpcCommand::PickRegion
+        );
+        assert_eq!(
+            parse_command(r#"{"cmd":"capture_desktop"}"#).unwrap(),
+            IpcCommand::CaptureDesktop
+        );
+        assert_eq!(
+            parse_command(r#"{"cmd":"burst"}"#).unwrap(),
+            IpcCommand::Burst
+        );
+        assert_eq!(
+            parse_command(r#"{"cmd":"edit_annotations","path":"D:/snip_1.png"}"#).unwrap(),
+            IpcCommand::EditAnnotations {
+                path: PathBuf::from("D:/snip_1.png")
+            }
+        );
+        assert_eq!(
+            parse_command(r#"{"cmd":"open_project","path":"D:/a.snip"}"#).unwrap(),
+            IpcCommand::OpenProject {
+                path: PathBuf::from("D:/a.snip")
+            }
+        );
+        assert_eq!(
+            parse_command(r#"{"cmd":"pin_image","path":"D:/shot.png"}"#).unwrap(),
+            IpcCommand::PinImage {
+                path: PathBuf::from("D:/shot.png")
+            }
+        );
+        assert!(IpcCommand::PickRegion.wants_result());
+        assert!(!IpcCommand::CaptureRegion.wants_result());
+        assert!(parse_command(r#"{"cmd":"format_disk"}"#).is_err());
+    }
+
+    // 内存连接：读取给定输入，写出的回复存入共享缓冲
+    struct MemStream {
+        input: std::io::Cursor<Vec<u8>>,
+        output: std::sync::Arc<std::sync::Mutex<Vec<u8>>>,
+    }
+
+    impl Read for MemStream {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for MemStream {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.output.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_pick_region_replies_with_result() {
+        let output = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let stream = MemStream {
+            input: std::io::Cursor::new(
+                b"{\"cmd\":\"pick_region\"}\n{\"cmd\":\"quit\"}\n".to_vec(),
+            ),
+            output: output.clone(),
+        };
+        let (tx, rx) = mpsc::channel();
+        let conn = thread::spawn(move || handle_connection(stream, &tx));
+        let (cmd, reply) = rx.recv().unwrap();
+        assert_eq!(cmd, IpcCommand::PickRegion);
+        // 主循环给出结果之前不回复
+        assert!(output.lock().unwrap().is_empty());
+        reply
+            .unwrap()
+            .ok(serde_json::json!({ "x": 1, "y": 2, "width": 3, "height": 4 }));
+        let (cmd, reply) = rx.recv().unwrap();
+        assert_eq!(cmd, IpcCommand::Quit);
+        assert!(reply.is_none());
+        conn.join().unwrap();
+        let text = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines[0]["result"]["width"], 3);
+        assert_eq!(lines[1], serde_json::json!({ "ok": true }));
+    }
+}
@@ -1,6 +1,50 @@
+pub mod autostart;
+pub mod burst;
 pub mod capture;
+pub mod capture_async;
+pub mod clipboard;
+pub mod config;
+pub mod crash;
+pub mod decode;
+pub mod diff;
+pub mod elevation;
+pub mod encode;
+pub mod engine;
+pub mod filters;
+pub mod flyout;
+pub mod formats;
+pub mod fullscreen;
+pub mod hooks;
 pub mod hotkey;
+pub mod image_info;
+pub mod ipc;
+pub mod logging;
+pub mod naming;
+pub mod ocr;
+pub mod open_with;
+pub mod optimize;
 pub mod overlay;
 pub mod paste_window;
+pub mod paths;
+pub mod postprocess;
+pub mod project;
+pub mod qr;
+pub mod redact;
 pub mod renderer;
+pub mod share;
+pub mod sound;
+pub mod stats;
+#[cfg(test)]
+mod test_util;
+pub mod timelapse;
+pub mod toast;
+pub mod touch;
+pub mod tray_badge;
+#[cfg(feature = "hot_corner")]
+pub mod trigger;
+pub mod uploads;
+pub mod webdav;
+pub mod window_surface;
+pub mod window_trigger;
 pub mod windows_util; // internal platform helpers (non-public API contract)
+pub mod workspace;
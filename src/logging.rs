@@ -0,0 +1,237 @@
+// logging: 滚动文件日志（发布版为 windows 子系统，没有控制台可看）
+//
+// 写入 <数据目录>/logs/snip_rust.log（见 paths）；超过 MAX_BYTES 时滚动为 snip_rust.1.log ..
+// snip_rust.<KEEP>.log（最旧的删除）。级别取配置 log_level，设置了 RUST_LOG 时以环境变量为准；
+// 调试版同时输出到 stderr。每行直接写入文件（不缓冲），崩溃前的日志不会丢失。
+use serde::Deserialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// 单个日志文件上限（字节）
+pub const MAX_BYTES: u64 = 1024 * 1024;
+/// 保留的历史文件数
+pub const KEEP: usize = 3;
+
+const BASE_NAME: &str = "snip_rust";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn filter(self) -> LevelFilter {
+        match self {
+            LogLevel::Off => LevelFilter::Off,
+            LogLevel::Error => LevelFilter::Error,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+// RUST_LOG 生效时忽略配置中的级别
+static ENV_OVERRIDE: AtomicBool = AtomicBool::new(false);
+
+struct Sink {
+    dir: PathBuf,
+    file: Option<File>,
+    size: u64,
+}
+
+struct FileLogger {
+    sink: Mutex<Option<Sink>>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format_line(now_ms(), record);
+        if cfg!(debug_assertions) {
+            eprint!("{line}");
+        }
+        let Ok(mut guard) = self.sink.lock() else {
+            return;
+        };
+        let Some(sink) = guard.as_mut() else {
+            return;
+        };
+        if sink.size + line.len() as u64 > MAX_BYTES {
+            sink.file = None; // 先关闭再改名（Windows 不能重命名已打开的文件）
+            if let Err(e) = rotate(&sink.dir, KEEP) {
+                eprintln!("rotate log: {e}");
+            }
+            sink.size = 0;
+        }
+        if sink.file.is_none() {
+            sink.file = open_log(&sink.dir).ok();
+        }
+        if let Some(file) = &mut sink.file {
+            if file.write_all(line.as_bytes()).is_ok() {
+                sink.size += line.len() as u64;
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// 日志目录
+pub fn log_dir() -> Option<PathBuf> {
+    crate::paths::data_dir().map(|d| d.join("logs"))
+}
+
+/// 当前（最新）日志文件
+pub fn log_path() -> Option<PathBuf> {
+    log_dir().map(|d| d.join(format!("{BASE_NAME}.log")))
+}
+
+/// 安装全局 logger（默认 info 级别）；日志目录不可用时只输出到 stderr（调试版）
+pub fn init() {
+    let sink = log_dir().and_then(|dir| {
+        let file = fs::create_dir_all(&dir)
+            .and_then(|_| open_log(&dir))
+            .map_err(|e| eprintln!("open log file in {}: {e}", dir.display()))
+            .ok()?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Some(Sink {
+            dir,
+            file: Some(file),
+            size,
+        })
+    });
+    static LOGGER: OnceLock<FileLogger> = OnceLock::new();
+    let logger = LOGGER.get_or_init(|| FileLogger {
+        sink: Mutex::new(sink),
+    });
+    if log::set_logger(logger).is_err() {
+        return;
+    }
+    let env = std::env::var("RUST_LOG")
+        .ok()
+        .and_then(|v| parse_env_level(&v));
+    ENV_OVERRIDE.store(env.is_some(), Ordering::Relaxed);
+    log::set_max_level(env.unwrap_or(LogLevel::default().filter()));
+}
+
+/// 应用配置中的级别（启动与重新加载配置时调用）
+pub fn set_level(level: LogLevel) {
+    if !ENV_OVERRIDE.load(Ordering::Relaxed) {
+        log::set_max_level(level.filter());
+    }
+}
+
+/// 解析 RUST_LOG：支持 "debug" 与 "snip_rust=debug,winit=warn" 形式，取其中最详细的级别
+/// （不按模块过滤）
+pub fn parse_env_level(value: &str) -> Option<LevelFilter> {
+    value
+        .split(',')
+        .filter_map(|directive| {
+            let level = directive.rsplit('=').next()?.trim();
+            level.parse::<LevelFilter>().ok()
+        })
+        .max()
+}
+
+fn open_log(dir: &Path) -> std::io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(format!("{BASE_NAME}.log")))
+}
+
+/// snip_rust.log -> snip_rust.1.log -> .. -> snip_rust.<keep>.log（最旧的删除）
+pub fn rotate(dir: &Path, keep: usize) -> std::io::Result<()> {
+    let numbered = |i: usize| dir.join(format!("{BASE_NAME}.{i}.log"));
+    if keep == 0 {
+        return fs::remove_file(dir.join(format!("{BASE_NAME}.log")));
+    }
+    let oldest = numbered(keep);
+    if oldest.exists() {
+        fs::remove_file(&oldest)?;
+    }
+    for i in (1..keep).rev() {
+        let from = numbered(i);
+        if from.exists() {
+            fs::rename(&from, numbered(i + 1))?;
+        }
+    }
+    fs::rename(dir.join(format!("{BASE_NAME}.log")), numbered(1))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+// "2026-01-02 03:04:05.678 INFO  snip_rust::app: message"
+fn format_line(now_ms: u64, record: &Record) -> String {
+    let t = crate::naming::local_now(now_ms / 1000);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:03} {:<5} {}: {}\n",
+        t.year,
+        t.month,
+        t.day,
+        t.hour,
+        t.minute,
+        t.second,
+        now_ms % 1000,
+        record.level(),
+        record.target(),
+        record.args()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_parse_env_level() {
+        assert_eq!(parse_env_level("debug"), Some(LevelFilter::Debug));
+        assert_eq!(
+            parse_env_level("snip_rust=trace,winit=warn"),
+            Some(LevelFilter::Trace)
+        );
+        assert_eq!(parse_env_level("nonsense"), None);
+    }
+
+    #[test]
+    fn test_rotate_keeps_newest_files() {
+        let dir = TempDir::new("log_test");
+        let write = |name: &str, text: &str| fs::write(dir.join(name), text).unwrap();
+        let read = |name: &str| fs::read_to_string(dir.join(name)).ok();
+        write("snip_rust.log", "new");
+        write("snip_rust.1.log", "old1");
+        write("snip_rust.2.log", "old2");
+        rotate(&dir, 2).unwrap();
+        assert_eq!(read("snip_rust.log"), None);
+        assert_eq!(read("snip_rust.1.log").as_deref(), Some("new"));
+        assert_eq!(read("snip_rust.2.log").as_deref(), Some("old1"));
+        assert_eq!(read("snip_rust.3.log"), None);
+    }
+}
@@ -1,213 +1,256 @@
 #![cfg_attr(all(windows, not(debug_assertions)), windows_subsystem = "windows")]
 use anyhow::Result;
-use env_logger;
-use image::ImageReader;
 use log::info;
-use tray_icon::{
-    menu::{Menu, MenuEvent, MenuItem},
-    Icon, TrayIconBuilder,
-};
-use winit::{
-    event::{Event, WindowEvent},
-    event_loop::EventLoop,
-    window::CursorIcon,
-};
+use std::time::Duration;
+use tray_icon::{menu::MenuEvent, MouseButton, MouseButtonState, TrayIconEvent};
+use winit::event_loop::{ControlFlow, EventLoop};
 
-use snip_rust::capture::capture_fullscreen_raw_with_origin;
-use snip_rust::hotkey::subscribe_f4;
-use snip_rust::overlay::{OverlayAction, OverlayState};
-use snip_rust::paste_window::PasteWindow;
+use snip_rust::autostart;
+use snip_rust::config::Config;
+use snip_rust::crash;
+use snip_rust::encode::EncodeQueue;
+use snip_rust::hotkey::{BindOutcome, HotkeyAction, HotkeyBackend, Hotkeys};
+use snip_rust::ipc::{self, IpcCommand};
+use snip_rust::logging;
+use snip_rust::paths;
+use snip_rust::project;
+use snip_rust::windows_util;
+mod app;
+mod cli;
 mod single_instance;
 
-#[allow(deprecated)]
+use app::{App, Tray, UserEvent};
+
+// 以管理员身份重启时等待旧实例退出的最长时间
+const RELAUNCH_WAIT: Duration = Duration::from_secs(10);
+
 fn main() -> Result<()> {
-    // 单实例：若已存在实例则安静退出
-    let _instance_guard = match single_instance::acquire_single_instance() {
+    // verify 子命令：截图与基准图对比后以退出码报告结果，不启动托盘
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|a| a == "verify") {
+        cli::verify(args.into_iter().skip(1));
+    }
+    let json = cli::take_flag(&mut args, "--json");
+    let relaunched = cli::take_flag(&mut args, cli::RELAUNCHED_FLAG);
+    // -o：本进程截图一次，PNG 写到文件或 stdout 后退出，与托盘实例无关
+    if args.iter().any(|a| a == "-o" || a == "--output") {
+        cli::capture_output(&args, json);
+    }
+    let startup_commands = match cli::parse_args(args) {
+        Ok(cmds) => cmds,
+        Err(e) => {
+            eprintln!("snip_rust: {e}\n{}", cli::USAGE);
+            return Ok(());
+        }
+    };
+    // --pick-region：本进程独立显示 overlay，打印选区坐标后退出，与托盘实例无关
+    if startup_commands.contains(&IpcCommand::PickRegion) {
+        return cli::pick_region(&startup_commands, json);
+    }
+    if json {
+        eprintln!(
+            "snip_rust: --json only applies to --pick-region and -o\n{}",
+            cli::USAGE
+        );
+        return Ok(());
+    }
+    // 单实例：若已存在实例，把命令行请求转交给它后退出
+    // 以管理员身份重启：旧实例保存贴图后正在退出，等它释放互斥体
+    let instance = if relaunched {
+        single_instance::wait_single_instance(RELAUNCH_WAIT)
+    } else {
+        single_instance::acquire_single_instance()
+    };
+    let _instance_guard = match instance {
         Some(g) => g,
         None => {
-            println!("snip_rust: 已有实例在运行，退出");
+            if startup_commands.is_empty() {
+                println!("snip_rust: 已有实例在运行，退出");
+            } else if let Err(e) = single_instance::forward_to_running(&startup_commands) {
+                eprintln!("snip_rust: forward to running instance failed: {e}");
+            }
             return Ok(());
         }
     };
-    env_logger::init();
+    logging::init();
+    crash::install_panic_hook();
     info!("starting snip_rust (overlay + paste mode + tray)");
-    let event_loop = EventLoop::new()?;
-
-    // 从嵌入的 PNG 构建托盘图标（assets/app_icon.png）
-    fn build_tray_icon() -> Icon {
-        const BYTES: &[u8] = include_bytes!("../assets/app_icon.png");
-        let reader = ImageReader::new(std::io::Cursor::new(BYTES))
-            .with_guessed_format()
-            .unwrap();
-        let img = reader.decode().expect("decode icon").to_rgba8();
-        let (w, h) = img.dimensions();
-        Icon::from_rgba(img.into_raw(), w, h).expect("icon rgba")
+    if let Some(p) = paths::get() {
+        info!(
+            "{:?} mode: config {}, data {}",
+            p.mode,
+            p.config_file.display(),
+            p.data_dir.display()
+        );
     }
-
-    // 托盘菜单（仅退出）
-    let tray_menu = Menu::new();
-    let quit_item = MenuItem::new("退出(&Q)", true, None);
-    tray_menu.append(&quit_item).ok();
-    let _tray = TrayIconBuilder::new()
-        .with_tooltip("Snip Rust")
-        .with_icon(build_tray_icon())
-        .with_menu(Box::new(tray_menu))
-        .build()
-        .ok();
-    // 仅需一个接收器（tray_icon::menu 与 muda::MenuEvent 实际共用同一全局通道）
-    let menu_event_rx = MenuEvent::receiver();
-    let mut paste_windows: Vec<PasteWindow> = Vec::new(); // 多 PasteWindow
-    let mut hotkey_rx = subscribe_f4().ok();
-    let mut overlay: Option<OverlayState> = None;
-    let _ = event_loop.run(|event, elwt| match event {
-        Event::AboutToWait => {
-            while let Ok(ev) = menu_event_rx.try_recv() {
-                // 1) 托盘退出
-                if ev.id == quit_item.id() {
-                    log::debug!("quit menu selected");
-                    elwt.exit();
-                    return;
-                }
-                // 2) 单次线性扫描：同时识别 copy / destroy（窗口数量一般很少，O(n) 足够）
-                let mut remove_index: Option<usize> = None;
-                for (i, pw) in paste_windows.iter().enumerate() {
-                    if ev.id == pw.ctx_copy_id {
-                        log::debug!("context copy placeholder triggered id={:?}", ev.id);
-                        // TODO: 实现剪贴板复制
-                        break; // 复制不需要继续找
-                    }
-                    if ev.id == pw.ctx_destroy_id {
-                        remove_index = Some(i);
-                        break;
-                    }
-                }
-                if let Some(idx) = remove_index {
-                    log::debug!(
-                        "context destroy triggered id={:?} removing window #{}",
-                        ev.id,
-                        idx
-                    );
-                    let mut pw = paste_windows.remove(idx);
-                    pw.destroy();
-                }
-            }
-            // 轮询热键事件：进入 overlay 选区模式
-            if let Some(rx) = &mut hotkey_rx {
-                while let Ok(()) = rx.try_recv() {
-                    // 若 overlay 已存在且当前可见，则忽略重复 F4，避免多实例 / 叠加创建
-                    let already_visible = overlay.as_ref().map(|o| o.visible).unwrap_or(false);
-                    if already_visible {
-                        continue;
-                    }
-                    if overlay.is_none() {
-                        if let Ok(ov) = OverlayState::new(elwt) {
-                            overlay = Some(ov);
-                        }
-                    }
-                    if let Some(ov) = &mut overlay {
-                        if let Ok((ox, oy, w, h, raw)) = capture_fullscreen_raw_with_origin() {
-                            if ov.show_with_image(w, h, raw, (ox, oy)).is_ok() {
-                                ov.window.set_cursor(CursorIcon::Crosshair);
-                            }
-                        }
-                    }
-                }
-            }
-            if let Some(ov) = &overlay {
-                if ov.visible {
-                    ov.window.request_redraw();
-                }
-            }
-            for pw in paste_windows.iter_mut() {
-                let id = pw.window.id();
-                pw.redraw(id);
-            }
-            // 回收 ESC 标记待销毁窗口（倒序遍历避免索引错位）
-            for i in (0..paste_windows.len()).rev() {
-                if paste_windows[i].is_pending_destroy() {
-                    let mut pw = paste_windows.remove(i);
-                    pw.destroy();
-                }
-            }
+    let config = Config::load();
+    logging::set_level(config.log_level);
+    if config.associate_project_files {
+        if let Err(e) = project::register_file_type() {
+            log::warn!("register .snip association: {e}");
         }
-        Event::WindowEvent {
-            event: WindowEvent::RedrawRequested,
-            window_id,
-        } => {
-            // overlay redraw
-            if let Some(ov) = &mut overlay {
-                if window_id == ov.window.id() {
-                    ov.redraw();
-                }
+    }
+    if let Some(enabled) = config.autostart {
+        if let Err(e) = autostart::set(enabled) {
+            log::warn!("apply autostart config: {e}");
+        }
+    }
+    let event_loop = EventLoop::<UserEvent>::with_user_event().build()?;
+    event_loop.set_control_flow(ControlFlow::Wait);
+
+    let tray = Tray::new();
+    // 菜单 / 热键回调只负责把事件投递到事件循环
+    let proxy = event_loop.create_proxy();
+    MenuEvent::set_event_handler(Some(move |ev: MenuEvent| {
+        let _ = proxy.send_event(UserEvent::Menu(ev.id));
+    }));
+    // 右键菜单在鼠标按下时同步弹出，窗口列表在鼠标移入图标时就要刷新
+    let proxy = event_loop.create_proxy();
+    TrayIconEvent::set_event_handler(Some(move |ev: TrayIconEvent| {
+        match ev {
+            TrayIconEvent::Enter { .. } => {
+                let _ = proxy.send_event(UserEvent::TrayHover);
             }
-            for pw in paste_windows.iter_mut() {
-                pw.redraw(window_id);
+            // 左键单击（右键仍弹出托盘菜单）
+            TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                rect,
+                ..
+            } => {
+                let icon = (
+                    rect.position.x as i32,
+                    rect.position.y as i32,
+                    rect.size.width,
+                    rect.size.height,
+                );
+                let _ = proxy.send_event(UserEvent::TrayClick(icon));
             }
+            _ => {}
         }
-        Event::WindowEvent {
-            event: WindowEvent::CloseRequested,
-            window_id,
-        } => {
-            // 关闭 paste window
-            let before = paste_windows.len();
-            paste_windows.retain(|pw| pw.window.id() != window_id);
-            if before != 0
-                && paste_windows.is_empty()
-                && overlay.as_ref().map(|o| !o.visible).unwrap_or(true)
+    }));
+    let proxy = event_loop.create_proxy();
+    let hotkeys = match Hotkeys::new(config.hotkey_backend, move |action| {
+        let _ = proxy.send_event(match action {
+            HotkeyAction::Capture => UserEvent::Hotkey,
+            HotkeyAction::CaptureWindow => UserEvent::HotkeyWindow,
+            HotkeyAction::CaptureFullscreen => UserEvent::HotkeyFullscreen,
+            HotkeyAction::HoldStart => UserEvent::HotkeyHold(true),
+            HotkeyAction::HoldRelease => UserEvent::HotkeyHold(false),
+        });
+    }) {
+        Ok(mut hotkeys) => {
+            hotkeys.set_double_press(config.double_press_window());
+            hotkeys.set_mode(config.hotkey_mode);
+            let (wanted, fallbacks) = config.hotkey_bindings();
+            let outcome = hotkeys.bind(wanted, &fallbacks);
+            if matches!(outcome, BindOutcome::Failed { .. })
+                && config.hotkey_backend == HotkeyBackend::Global
             {
-                // 若无窗口可见，可考虑退出（暂不自动 exit）
+                log::warn!(
+                    "hotkey may be taken by another program; try hotkey_backend = \"low_level\""
+                );
             }
-            if let Some(ov) = &overlay {
-                if window_id == ov.window.id() {
-                    // 取消选区
-                    // drop overlay keeps screenshot data ephemeral
-                }
+            app::report_hotkey_bind(&outcome);
+            if config.print_screen {
+                app::take_over_print_screen(&mut hotkeys, true);
             }
+            Some(hotkeys)
         }
-        Event::WindowEvent {
-            event: WindowEvent::Resized(_),
-            window_id,
-        } => {
-            if let Some(ov) = &overlay {
-                if window_id == ov.window.id() {
-                    ov.window.request_redraw();
-                }
-            }
-            for pw in &mut paste_windows {
-                if pw.window.id() == window_id {
-                    pw.redraw(window_id);
-                }
-            }
+        Err(e) => {
+            log::warn!("hotkey backend {:?}: {e}", config.hotkey_backend);
+            app::report_hotkey_bind(&BindOutcome::Failed {
+                wanted: config.hotkey_bindings().0.to_string(),
+                reason: e.to_string(),
+            });
+            None
+        }
+    };
+    #[cfg(feature = "hot_corner")]
+    if let Some(trigger) = config.hot_corner.clone() {
+        let proxy = event_loop.create_proxy();
+        if let Err(e) = snip_rust::trigger::spawn(trigger, move || {
+            let _ = proxy.send_event(UserEvent::Hotkey);
+        }) {
+            log::warn!("start hot corner trigger: {e}");
+        }
+    }
+    if let Some(trigger) = config.window_trigger.clone() {
+        let proxy = event_loop.create_proxy();
+        if let Err(e) = snip_rust::window_trigger::spawn(trigger, move |handle| {
+            let _ = proxy.send_event(UserEvent::WindowChanged(handle));
+        }) {
+            log::warn!("start window trigger: {e}");
+        }
+    }
+    if !config.ignore_elevated_windows {
+        let proxy = event_loop.create_proxy();
+        if let Err(e) = snip_rust::elevation::spawn(move || {
+            let _ = proxy.send_event(UserEvent::ElevatedForeground);
+        }) {
+            log::warn!("start elevation watcher: {e}");
         }
-        Event::WindowEvent { event, window_id } => {
-            if let Some(ov) = &mut overlay {
-                if window_id == ov.window.id() {
-                    match ov.handle_event(&event) {
-                        OverlayAction::Canceled => { /* overlay 已隐藏 不做处理 */ }
-                        OverlayAction::PasteSelection {
-                            png,
-                            width: _w,
-                            height: _h,
-                            screen_x,
-                            screen_y,
-                        } => {
-                            if let Ok(pw) =
-                                PasteWindow::new_from_png(elwt, &png, Some((screen_x, screen_y)))
-                            {
-                                paste_windows.push(pw);
-                            }
-                        }
-                        OverlayAction::None => {}
+    }
+    if let Some(fullscreen) = config.fullscreen.clone() {
+        let proxy = event_loop.create_proxy();
+        if let Err(e) = snip_rust::fullscreen::spawn(fullscreen, move |active| {
+            let _ = proxy.send_event(UserEvent::Fullscreen(active));
+        }) {
+            log::warn!("start fullscreen watcher: {e}");
+        }
+    }
+    // 后台 PNG 编码完成时发送 user event，结果在主线程处理
+    let proxy = event_loop.create_proxy();
+    let progress = event_loop.create_proxy();
+    let mut encoder = EncodeQueue::new()
+        .with_notify(move || {
+            let _ = proxy.send_event(UserEvent::Encoded);
+        })
+        .with_png_optimize(config.png_optimize)
+        .with_save_format(config.save_format, config.avif)
+        .with_progress(move |id, f| {
+            let _ = progress.send_event(UserEvent::EncodeProgress(id, f));
+        });
+    // 显示器配置文件启动时读取一次
+    if config.strip_metadata {
+        if config.embed_icc_profile || config.png_comment.is_some() {
+            log::warn!("strip_metadata set: ignoring embed_icc_profile / png_comment");
+        }
+    } else if config.embed_icc_profile {
+        match windows_util::primary_monitor_icc_profile() {
+            Some(icc) => encoder = encoder.with_icc_profile(icc),
+            None => log::warn!("embed_icc_profile set but no monitor profile found"),
+        }
+    }
+    if let Some(template) = &config.filename_template {
+        encoder = encoder.with_name_template(template);
+    }
+    if let Some(comment) = config
+        .png_comment
+        .clone()
+        .filter(|_| !config.strip_metadata)
+    {
+        encoder = encoder.with_comment(comment);
+    }
+    // 外部控制命令：启动参数 + IPC（命名管道 / Unix socket）
+    let proxy = event_loop.create_proxy();
+    for cmd in startup_commands {
+        let _ = proxy.send_event(UserEvent::Ipc(cmd, None));
+    }
+    match ipc::serve() {
+        Ok(rx) => {
+            std::thread::spawn(move || {
+                for (cmd, reply) in rx {
+                    if proxy.send_event(UserEvent::Ipc(cmd, reply)).is_err() {
+                        break; // 事件循环已退出
                     }
                 }
-            }
-            for pw in &mut paste_windows {
-                if pw.window.id() == window_id {
-                    pw.handle_event(&event);
-                }
-            }
+            });
         }
-        _ => {}
-    });
+        Err(e) => log::warn!("ipc server unavailable: {e}"),
+    }
+
+    let mut app = App::new(config, hotkeys, tray, encoder, event_loop.create_proxy());
+    event_loop.run_app(&mut app)?;
     Ok(())
 }
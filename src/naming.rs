@@ -0,0 +1,269 @@
+// naming: 输出文件名模板（自动保存 / 上传 hook 临时文件）
+//
+// 变量：{date}=YYYY-MM-DD {time}=HH-MM-SS {timestamp}=unix 秒 {counter}=持久递增计数
+// {app}=截图时前台程序名 {monitor}=显示器序号（从 1 开始） {w} {h}=输出尺寸。
+// 未知变量原样保留；结果中的非法文件名字符替换为 `_`，不含扩展名。
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 默认模板（与引入模板前的 snip_<unix秒>.png 一致）
+pub const DEFAULT_TEMPLATE: &str = "snip_{timestamp}";
+
+/// 本地时间（非 Windows 平台为 UTC）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalTime {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+/// 一次渲染用到的全部变量
+#[derive(Debug, Clone)]
+pub struct NameVars<'a> {
+    pub time: LocalTime,
+    pub timestamp: u64,
+    pub counter: u64,
+    pub app: &'a str,
+    pub monitor: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 文件名生成器：模板 + 最近一次截图的来源
+#[derive(Debug, Clone)]
+pub struct Namer {
+    template: String,
+    app: String,
+    monitor: u32,
+}
+
+impl Default for Namer {
+    fn default() -> Self {
+        Self::new(DEFAULT_TEMPLATE)
+    }
+}
+
+impl Namer {
+    pub fn new(template: &str) -> Self {
+        Self {
+            template: template.to_string(),
+            app: String::new(),
+            monitor: 1,
+        }
+    }
+
+    /// 每次截图开始时更新来源（前台程序 / 显示器）
+    pub fn set_source(&mut self, app: Option<String>, monitor: u32) {
+        self.app = app.unwrap_or_default();
+        self.monitor = monitor;
+    }
+
+    /// 渲染下一个文件名；模板含 {counter} 时才递增持久计数
+    pub fn next(&self, width: u32, height: u32) -> String {
+        let counter = if self.template.contains("{counter}") {
+            next_counter()
+        } else {
+            0
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        render(
+            &self.template,
+            &NameVars {
+                time: local_now(timestamp),
+                timestamp,
+                counter,
+                app: &self.app,
+                monitor: self.monitor,
+                width,
+                height,
+            },
+        )
+    }
+}
+
+/// 展开模板并清理为合法文件名
+pub fn render(template: &str, vars: &NameVars) -> String {
+    let t = vars.time;
+    let app = if vars.app.is_empty() {
+        "unknown"
+    } else {
+        vars.app
+    };
+    let mut out = String::with_capacity(template.len() + 16);
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            out.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+        let key = &rest[start + 1..start + len];
+        match key {
+            "date" => out.push_str(&format!("{:04}-{:02}-{:02}", t.year, t.month, t.day)),
+            "time" => out.push_str(&format!("{:02}-{:02}-{:02}", t.hour, t.minute, t.second)),
+            "timestamp" => out.push_str(&vars.timestamp.to_string()),
+            "counter" => out.push_str(&vars.counter.to_string()),
+            "app" => out.push_str(app),
+            "monitor" => out.push_str(&vars.monitor.to_string()),
+            "w" => out.push_str(&vars.width.to_string()),
+            "h" => out.push_str(&vars.height.to_string()),
+            _ => out.push_str(&rest[start..=start + len]),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    sanitize(&out)
+}
+
+// Windows 文件名非法字符与控制字符替换为 `_`；去掉结尾的点与空格
+fn sanitize(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let trimmed = cleaned.trim_end_matches(['.', ' ']).trim_start();
+    if trimmed.is_empty() {
+        "snip".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+fn counter_path() -> Option<PathBuf> {
+    crate::paths::data_dir().map(|d| d.join("counter"))
+}
+
+/// 读取并递增持久计数（首次为 1）；读写失败时记录日志，仍返回递增后的值
+pub fn next_counter() -> u64 {
+    let Some(path) = counter_path() else {
+        return 1;
+    };
+    let current = fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+    let next = current + 1;
+    let written = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, next.to_string()));
+    if let Err(e) = written {
+        log::warn!("write counter {}: {e}", path.display());
+    }
+    next
+}
+
+/// unix 秒 -> 本地日期时间（按该时刻所在日期的时区 / 夏令时规则换算）
+#[cfg(target_os = "windows")]
+pub fn local_now(timestamp: u64) -> LocalTime {
+    use windows::Win32::Foundation::SYSTEMTIME;
+    use windows::Win32::System::Time::SystemTimeToTzSpecificLocalTime;
+
+    let utc = utc_from_unix(timestamp);
+    let st = SYSTEMTIME {
+        wYear: utc.year as u16,
+        wMonth: utc.month as u16,
+        wDay: utc.day as u16,
+        wHour: utc.hour as u16,
+        wMinute: utc.minute as u16,
+        wSecond: utc.second as u16,
+        ..Default::default()
+    };
+    let mut local = SYSTEMTIME::default();
+    if unsafe { SystemTimeToTzSpecificLocalTime(None, &st, &mut local) }.is_err() {
+        return utc;
+    }
+    LocalTime {
+        year: local.wYear as u32,
+        month: local.wMonth as u32,
+        day: local.wDay as u32,
+        hour: local.wHour as u32,
+        minute: local.wMinute as u32,
+        second: local.wSecond as u32,
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn local_now(timestamp: u64) -> LocalTime {
+    utc_from_unix(timestamp)
+}
+
+/// unix 秒 -> UTC 日期时间（公历，Howard Hinnant 的 civil_from_days）
+pub fn utc_from_unix(timestamp: u64) -> LocalTime {
+    let days = (timestamp / 86_400) as i64;
+    let secs = (timestamp % 86_400) as u32;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = (yoe + era * 400 + (month <= 2) as i64) as u32;
+    LocalTime {
+        year,
+        month,
+        day,
+        hour: secs / 3600,
+        minute: secs / 60 % 60,
+        second: secs % 60,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(app: &str) -> NameVars<'_> {
+        NameVars {
+            time: utc_from_unix(1_700_000_000),
+            timestamp: 1_700_000_000,
+            counter: 42,
+            app,
+            monitor: 2,
+            width: 640,
+            height: 480,
+        }
+    }
+
+    #[test]
+    fn test_utc_from_unix() {
+        let t = utc_from_unix(1_700_000_000); // 2023-11-14 22:13:20 UTC
+        assert_eq!((t.year, t.month, t.day), (2023, 11, 14));
+        assert_eq!((t.hour, t.minute, t.second), (22, 13, 20));
+        let t = utc_from_unix(951_782_400); // 闰日
+        assert_eq!((t.year, t.month, t.day), (2000, 2, 29));
+    }
+
+    #[test]
+    fn test_render_variables() {
+        let v = vars("chrome");
+        assert_eq!(
+            render("{app}_{date}_{time}_{counter}_m{monitor}_{w}x{h}", &v),
+            "chrome_2023-11-14_22-13-20_42_m2_640x480"
+        );
+        assert_eq!(render(DEFAULT_TEMPLATE, &v), "snip_1700000000");
+        // 未知变量与未闭合的括号原样保留
+        assert_eq!(render("{foo}-{w", &v), "{foo}-{w");
+    }
+
+    #[test]
+    fn test_render_sanitizes() {
+        assert_eq!(render("{app}", &vars("")), "unknown");
+        assert_eq!(render("a/b:c*{app}. ", &vars("x?y")), "a_b_c_x_y");
+        assert_eq!(render("...", &vars("")), "snip");
+    }
+}
@@ -0,0 +1,71 @@
+// ocr: 选区文字识别（Windows.Media.Ocr，使用用户配置的系统语言）
+//
+// 返回按行分组的单词及其在输入图像中的像素矩形，供 redact 做敏感信息定位。
+// 识别为阻塞调用（内部等待 WinRT 异步操作），请在后台线程中调用。
+use anyhow::Result;
+
+/// 识别出的单词（矩形为输入图像内坐标 x, y, w, h）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OcrWord {
+    pub text: String,
+    pub rect: (u32, u32, u32, u32),
+}
+
+/// 一行文字（单词按阅读顺序）
+pub type OcrLine = Vec<OcrWord>;
+
+#[cfg(target_os = "windows")]
+pub fn recognize(width: u32, height: u32, rgba: &[u8]) -> Result<Vec<OcrLine>> {
+    use anyhow::anyhow;
+    use windows::Graphics::Imaging::{BitmapPixelFormat, SoftwareBitmap};
+    use windows::Media::Ocr::OcrEngine;
+    use windows::Storage::Streams::DataWriter;
+
+    if width == 0 || height == 0 || rgba.len() < (width * height * 4) as usize {
+        return Err(anyhow!("invalid image {width}x{height}"));
+    }
+    let max = OcrEngine::MaxImageDimension()?;
+    if width > max || height > max {
+        return Err(anyhow!("selection too large for ocr (max {max}px)"));
+    }
+    // SoftwareBitmap 需要 BGRA
+    let mut bgra = rgba[..(width * height * 4) as usize].to_vec();
+    for px in bgra.chunks_exact_mut(4) {
+        px.swap(0, 2);
+    }
+    let writer = DataWriter::new()?;
+    writer.WriteBytes(&bgra)?;
+    let buffer = writer.DetachBuffer()?;
+    let bitmap = SoftwareBitmap::CreateCopyFromBuffer(
+        &buffer,
+        BitmapPixelFormat::Bgra8,
+        width as i32,
+        height as i32,
+    )?;
+    let engine = OcrEngine::TryCreateFromUserProfileLanguages()
+        .map_err(|e| anyhow!("no ocr language available: {e}"))?;
+    let result = engine.RecognizeAsync(&bitmap)?.join()?;
+    let mut lines = Vec::new();
+    for line in result.Lines()? {
+        let mut words = Vec::new();
+        for word in line.Words()? {
+            let r = word.BoundingRect()?;
+            words.push(OcrWord {
+                text: word.Text()?.to_string_lossy(),
+                rect: (
+                    r.X.max(0.0) as u32,
+                    r.Y.max(0.0) as u32,
+                    r.Width.ceil() as u32,
+                    r.Height.ceil() as u32,
+                ),
+            });
+        }
+        lines.push(words);
+    }
+    Ok(lines)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn recognize(_width: u32, _height: u32, _rgba: &[u8]) -> Result<Vec<OcrLine>> {
+    Err(anyhow::anyhow!("ocr is only available on Windows"))
+}
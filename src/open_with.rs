@@ -0,0 +1,131 @@
+// open_with: “用编辑器打开”——截图写成临时 PNG，交给系统默认图像编辑器或配置的程序
+//
+// 临时文件放在 <temp>/snip_rust_edit/。本次运行写出的文件记录在 TempFiles 中，退出时删除
+// 写出后未被修改的文件（在编辑器里保存过的保留，以免丢失修改）；启动时清理该目录下超过 7 天的旧文件。
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// 启动时清理超过该时长的临时文件
+pub const MAX_AGE: Duration = Duration::from_secs(7 * 24 * 3600);
+
+// 写出时的修改时间与大小；退出时两者都未变视为未编辑
+type Stamp = (SystemTime, u64);
+
+fn stamp(path: &Path) -> Option<Stamp> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// 本次运行写出的临时文件
+pub struct TempFiles {
+    dir: PathBuf,
+    files: Vec<(PathBuf, Stamp)>,
+}
+
+impl Default for TempFiles {
+    fn default() -> Self {
+        Self::in_dir(std::env::temp_dir().join("snip_rust_edit"))
+    }
+}
+
+impl TempFiles {
+    pub fn in_dir(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            files: Vec::new(),
+        }
+    }
+
+    /// 写入 <dir>/<name>.png（同名时追加 _2、_3 …）并记录
+    pub fn write(&mut self, png: &[u8], name: &str) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir).map_err(|e| anyhow!("create {}: {e}", self.dir.display()))?;
+        let base = self.dir.join(name);
+        let path = crate::encode::save_png_auto(png, &base.to_string_lossy())?;
+        if let Some(stamp) = stamp(&path) {
+            self.files.push((path.clone(), stamp));
+        }
+        Ok(path)
+    }
+
+    /// 退出时调用：删除写出后未被修改的文件，返回删除数量
+    pub fn cleanup(&mut self) -> usize {
+        let mut removed = 0;
+        for (path, written) in self.files.drain(..) {
+            if stamp(&path) == Some(written) && fs::remove_file(&path).is_ok() {
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    /// 删除目录中修改时间早于 max_age 的文件（包括以前运行留下的），返回删除数量
+    pub fn sweep(&self, max_age: Duration) -> usize {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return 0;
+        };
+        let now = SystemTime::now();
+        entries
+            .flatten()
+            .filter(|e| {
+                e.metadata()
+                    .and_then(|m| m.modified())
+                    .is_ok_and(|t| now.duration_since(t).is_ok_and(|age| age > max_age))
+            })
+            .filter(|e| fs::remove_file(e.path()).is_ok())
+            .count()
+    }
+}
+
+/// 用配置的程序（editor 为 Some，文件路径作为唯一参数）或系统默认图像编辑器打开
+pub fn launch(path: &Path, editor: Option<&Path>) -> Result<()> {
+    match editor {
+        Some(program) => std::process::Command::new(program)
+            .arg(path)
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| anyhow!("launch {}: {e}", program.display())),
+        None => crate::windows_util::edit_path(path).map_err(|e| anyhow!("open editor: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_cleanup_keeps_edited_files() {
+        let dir = TempDir::new("open_with_cleanup");
+        let mut temp = TempFiles::in_dir(dir.to_path_buf());
+        let untouched = temp.write(b"png", "snip_1").unwrap();
+        let edited = temp.write(b"png", "snip_1").unwrap();
+        assert_eq!(edited, dir.join("snip_1_2.png"));
+        // 编辑器保存了修改
+        fs::write(&edited, b"edited png").unwrap();
+        assert_eq!(temp.cleanup(), 1);
+        assert!(!untouched.exists());
+        assert!(edited.exists());
+        assert_eq!(temp.cleanup(), 0);
+    }
+
+    #[test]
+    fn test_sweep_removes_old_files() {
+        let dir = TempDir::new("open_with_sweep");
+        let mut temp = TempFiles::in_dir(dir.to_path_buf());
+        let old = temp.write(b"png", "old").unwrap();
+        let fresh = temp.write(b"png", "fresh").unwrap();
+        let week_ago = SystemTime::now() - MAX_AGE - Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(week_ago)
+            .unwrap();
+        assert_eq!(temp.sweep(MAX_AGE), 1);
+        assert!(!old.exists());
+        assert!(fresh.exists());
+        assert_eq!(TempFiles::in_dir(dir.join("missing")).sweep(MAX_AGE), 0);
+    }
+}
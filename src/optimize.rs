@@ -0,0 +1,302 @@
+// optimize: 体积优化的 PNG 编码（png_optimize = "lossless" / "quantize"）
+//
+// 界面截图大多是大块纯色：颜色不超过 256 种时改写为调色板 PNG（按颜色数选 1 / 2 / 4 / 8 位深），
+// 像素完全不变；quantize 模式下颜色更多的图用 NeuQuant 量化为 256 色（有损，界面截图几乎看不出，
+// 照片会出现色带）。两种模式都使用最高的 deflate 压缩等级（png crate 的 9 级；没有 zopfli 模式，
+// zopfli 再小几个百分点却慢上百倍，需要时用 post_capture hook 调用 oxipng / zopflipng），比默认
+// 编码慢得多，由编码队列在工作线程执行，并经 progress 回调报告进度（App 显示进度提示窗）。
+// 复制与 Data URI 不走优化（见 EncodeQueue::submit），剪贴板不等待慢速编码。
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::capture::encode_png_with_icc;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PngOptimize {
+    /// 默认编码（RGBA，快速压缩）
+    #[default]
+    Off,
+    /// 无损：颜色不超过 256 种时写调色板 PNG，否则仍为 RGBA；均用最高压缩等级
+    Lossless,
+    /// 同 lossless，颜色更多时量化为 256 色调色板（有损）
+    Quantize,
+}
+
+/// NeuQuant 抽样系数（1 最精细，30 最快）
+const SAMPLE_FACTOR: i32 = 10;
+/// 最高 deflate 压缩等级
+const DEFLATE_LEVEL: u8 = 9;
+/// 每个阶段大约报告这么多次进度
+const PROGRESS_STEPS: usize = 50;
+
+/// 调色板图像：palette 为 RGBA 颜色表，indices 为逐像素的颜色序号
+#[derive(Debug, PartialEq, Eq)]
+pub struct Indexed {
+    pub palette: Vec<[u8; 4]>,
+    pub indices: Vec<u8>,
+}
+
+// 完全透明的像素颜色不可见，统一为一种
+fn color_key(px: &[u8]) -> [u8; 4] {
+    if px[3] == 0 {
+        [0; 4]
+    } else {
+        [px[0], px[1], px[2], px[3]]
+    }
+}
+
+/// 颜色不超过 256 种时精确转为调色板；颜色更多时返回 None
+pub fn exact_palette(rgba: &[u8]) -> Option<Indexed> {
+    let mut lookup: HashMap<[u8; 4], u8> = HashMap::new();
+    let mut palette = Vec::new();
+    let mut indices = Vec::with_capacity(rgba.len() / 4);
+    for px in rgba.chunks_exact(4) {
+        let color = color_key(px);
+        let index = match lookup.get(&color) {
+            Some(&i) => i,
+            None => {
+                if palette.len() == 256 {
+                    return None;
+                }
+                let i = palette.len() as u8;
+                palette.push(color);
+                lookup.insert(color, i);
+                i
+            }
+        };
+        indices.push(index);
+    }
+    Some(Indexed { palette, indices })
+}
+
+/// NeuQuant 量化为 256 色；progress 报告逐像素映射的进度（0..1）
+pub fn quantize(rgba: &[u8], mut progress: impl FnMut(f32)) -> Indexed {
+    let nq = color_quant::NeuQuant::new(SAMPLE_FACTOR, 256, rgba);
+    let palette = nq
+        .color_map_rgba()
+        .chunks_exact(4)
+        .map(|c| [c[0], c[1], c[2], c[3]])
+        .collect();
+    // 重复颜色很多，缓存查找结果
+    let mut cache: HashMap<[u8; 4], u8> = HashMap::new();
+    let n = rgba.len() / 4;
+    let step = (n / PROGRESS_STEPS).max(1);
+    let mut indices = Vec::with_capacity(n);
+    for (i, px) in rgba.chunks_exact(4).enumerate() {
+        let color = color_key(px);
+        let index = *cache
+            .entry(color)
+            .or_insert_with(|| nq.index_of(&color) as u8);
+        indices.push(index);
+        if i % step == 0 {
+            progress(i as f32 / n as f32);
+        }
+    }
+    Indexed { palette, indices }
+}
+
+/// 调色板位深：颜色越少每像素位数越少
+pub fn bit_depth(colors: usize) -> u8 {
+    match colors {
+        0..=2 => 1,
+        3..=4 => 2,
+        5..=16 => 4,
+        _ => 8,
+    }
+}
+
+/// 按位深逐行打包颜色序号（高位在前，每行末尾补 0 到整字节）
+pub fn pack_rows(indices: &[u8], width: u32, bits: u8) -> Vec<u8> {
+    if bits == 8 || width == 0 {
+        return indices.to_vec();
+    }
+    let per_byte = (8 / bits) as usize;
+    let mut out = Vec::with_capacity(indices.len().div_ceil(per_byte));
+    for row in indices.chunks(width as usize) {
+        for group in row.chunks(per_byte) {
+            let mut byte = 0u8;
+            for (i, &v) in group.iter().enumerate() {
+                byte |= v << (8 - bits as usize * (i + 1));
+            }
+            out.push(byte);
+        }
+    }
+    out
+}
+
+fn png_depth(bits: u8) -> png::BitDepth {
+    match bits {
+        1 => png::BitDepth::One,
+        2 => png::BitDepth::Two,
+        4 => png::BitDepth::Four,
+        _ => png::BitDepth::Eight,
+    }
+}
+
+/// 按 mode 编码 RGBA（Off 时为默认编码）；progress 在调用线程报告整体进度（0..=1）
+pub fn encode_png(
+    rgba: &[u8],
+    w: u32,
+    h: u32,
+    mode: PngOptimize,
+    icc: Option<&[u8]>,
+    mut progress: impl FnMut(f32),
+) -> Result<Vec<u8>> {
+    if mode == PngOptimize::Off {
+        return encode_png_with_icc(rgba, w, h, icc);
+    }
+    if w == 0 || h == 0 || rgba.len() != w as usize * h as usize * 4 {
+        return Err(anyhow!("rgba buffer does not match {w}x{h}"));
+    }
+    progress(0.0);
+    // 量化与压缩大约各占一半耗时
+    let (indexed, base) = match exact_palette(rgba) {
+        Some(indexed) => (Some(indexed), 0.0),
+        None if mode == PngOptimize::Quantize => (Some(quantize(rgba, |f| progress(f * 0.5))), 0.5),
+        None => (None, 0.0),
+    };
+    let mut info = png::Info::with_size(w, h);
+    info.icc_profile = icc.map(Cow::Borrowed);
+    let (data, filter) = match &indexed {
+        Some(Indexed { palette, indices }) => {
+            let bits = bit_depth(palette.len());
+            info.color_type = png::ColorType::Indexed;
+            info.bit_depth = png_depth(bits);
+            let rgb: Vec<u8> = palette.iter().flat_map(|c| [c[0], c[1], c[2]]).collect();
+            info.palette = Some(rgb.into());
+            // tRNS 只需写到最后一个不是完全不透明的颜色
+            let alpha_len = palette
+                .iter()
+                .rposition(|c| c[3] < 255)
+                .map_or(0, |i| i + 1);
+            if alpha_len > 0 {
+                let alpha: Vec<u8> = palette[..alpha_len].iter().map(|c| c[3]).collect();
+                info.trns = Some(alpha.into());
+            }
+            // 调色板图像做行滤波通常反而更大
+            (
+                Cow::Owned(pack_rows(indices, w, bits)),
+                png::Filter::NoFilter,
+            )
+        }
+        None => {
+            info.color_type = png::ColorType::Rgba;
+            info.bit_depth = png::BitDepth::Eight;
+            (Cow::Borrowed(rgba), png::Filter::Adaptive)
+        }
+    };
+    let mut out = Vec::new();
+    let mut encoder =
+        png::Encoder::with_info(&mut out, info).map_err(|e| anyhow!("png header: {e}"))?;
+    encoder.set_deflate_compression(png::DeflateCompression::Level(DEFLATE_LEVEL));
+    encoder.set_filter(filter);
+    let mut writer = encoder.write_header()?;
+    let mut stream = writer.stream_writer()?;
+    let row_len = data.len() / h as usize;
+    let rows_per_step = (h as usize).div_ceil(PROGRESS_STEPS);
+    for (i, chunk) in data.chunks(row_len * rows_per_step).enumerate() {
+        stream.write_all(chunk)?;
+        let rows = ((i + 1) * rows_per_step).min(h as usize);
+        progress(base + (1.0 - base) * rows as f32 / h as f32);
+    }
+    stream.finish()?;
+    writer.finish()?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(png: &[u8]) -> (png::ColorType, png::BitDepth, Vec<u8>) {
+        let img = image::load_from_memory(png).unwrap().to_rgba8();
+        let reader = png::Decoder::new(std::io::Cursor::new(png))
+            .read_info()
+            .unwrap();
+        let info = reader.info();
+        (info.color_type, info.bit_depth, img.into_raw())
+    }
+
+    #[test]
+    fn test_pack_and_palette() {
+        assert_eq!(bit_depth(2), 1);
+        assert_eq!(bit_depth(16), 4);
+        assert_eq!(bit_depth(17), 8);
+        // 每行单独补齐：宽 3 的 1 位图每行 1 字节
+        assert_eq!(
+            pack_rows(&[1, 0, 1, 0, 1, 1], 3, 1),
+            [0b1010_0000, 0b0110_0000]
+        );
+        assert_eq!(
+            pack_rows(&[3, 2, 1, 0, 1], 5, 2),
+            [0b1110_0100, 0b0100_0000]
+        );
+        assert_eq!(pack_rows(&[0xA, 0x5], 2, 4), [0xA5]);
+        // 完全透明的像素合并为一种颜色
+        let rgba = [1, 2, 3, 255, 9, 9, 9, 0, 1, 2, 3, 255, 5, 5, 5, 0];
+        let indexed = exact_palette(&rgba).unwrap();
+        assert_eq!(indexed.palette, [[1, 2, 3, 255], [0; 4]]);
+        assert_eq!(indexed.indices, [0, 1, 0, 1]);
+        let many: Vec<u8> = (0..257u32)
+            .flat_map(|i| [i as u8, (i >> 8) as u8, 0, 255])
+            .collect();
+        assert!(exact_palette(&many).is_none());
+    }
+
+    #[test]
+    fn test_optimized_png_round_trip() {
+        // 纯色块 + 半透明：无损写为 1 位调色板，像素不变
+        let (w, h) = (37u32, 5u32);
+        let mut rgba = Vec::new();
+        for y in 0..h {
+            for x in 0..w {
+                let px = if (x + y) % 3 == 0 {
+                    [200, 10, 10, 128]
+                } else {
+                    [255; 4]
+                };
+                rgba.extend_from_slice(&px);
+            }
+        }
+        let mut steps = Vec::new();
+        let png = encode_png(&rgba, w, h, PngOptimize::Lossless, None, |f| steps.push(f)).unwrap();
+        let (color, depth, pixels) = decode(&png);
+        assert_eq!(
+            (color, depth),
+            (png::ColorType::Indexed, png::BitDepth::One)
+        );
+        assert_eq!(pixels, rgba);
+        assert_eq!(steps.first(), Some(&0.0));
+        assert_eq!(steps.last(), Some(&1.0));
+        assert!(steps.windows(2).all(|s| s[0] <= s[1]));
+        assert!(png.len() < encode_png_with_icc(&rgba, w, h, None).unwrap().len());
+
+        // 渐变超过 256 色：lossless 保持 RGBA，quantize 量化为 8 位调色板
+        let (w, h) = (64u32, 64u32);
+        let grad: Vec<u8> = (0..w * h)
+            .flat_map(|i| [(i % w * 4) as u8, (i / w * 4) as u8, 128, 255])
+            .collect();
+        let lossless = encode_png(&grad, w, h, PngOptimize::Lossless, None, |_| {}).unwrap();
+        let (color, _, pixels) = decode(&lossless);
+        assert_eq!(color, png::ColorType::Rgba);
+        assert_eq!(pixels, grad);
+        let quantized = encode_png(&grad, w, h, PngOptimize::Quantize, None, |_| {}).unwrap();
+        let (color, depth, pixels) = decode(&quantized);
+        assert_eq!(
+            (color, depth),
+            (png::ColorType::Indexed, png::BitDepth::Eight)
+        );
+        let err: u32 = pixels
+            .iter()
+            .zip(&grad)
+            .map(|(a, b)| a.abs_diff(*b) as u32)
+            .sum();
+        let mean = err as f32 / grad.len() as f32;
+        assert!(mean < 8.0, "quantization error {mean}");
+        assert!(encode_png(&grad, w, h + 1, PngOptimize::Quantize, None, |_| {}).is_err());
+    }
+}
@@ -1,7 +1,17 @@
+pub mod adjust;
+pub mod annotate;
+pub mod dirty;
 pub mod drawing;
+#[cfg(feature = "gpu")]
+pub mod gpu;
 pub mod handles;
+pub mod regions;
+pub mod snap;
 pub mod state;
+pub mod strip;
+pub mod theme;
 pub mod toolbar;
+pub mod trim;
 
 pub use handles::{hit_test_handle, ResizeHandle};
-pub use state::{OverlayAction, OverlayMode, OverlayState};
+pub use state::{OverlayAction, OverlayMenuIds, OverlayMode, OverlayState};
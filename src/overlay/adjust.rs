@@ -0,0 +1,215 @@
+// adjust: 工具栏“标注”按钮展开的图像调整面板（灰度 / 反色 开关 + 亮度 / 对比度 / 饱和度 滑块）
+//
+// 无文字渲染，每行左侧用小图标区分：灰度=黑白渐变，反色=黑白对半，
+// 亮度=暖色块，对比度=对角黑白，饱和度=RGB 三色条。
+use crate::filters::{Adjustments, SLIDER_MAX, SLIDER_MIN};
+use crate::overlay::drawing::{fill_rect, set_px, stroke_rect};
+use crate::overlay::theme::Theme;
+use crate::overlay::toolbar::TB_MARGIN;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdjustRow {
+    Grayscale,
+    Invert,
+    Brightness,
+    Contrast,
+    Saturation,
+}
+
+pub const ADJUST_ROWS: [AdjustRow; 5] = [
+    AdjustRow::Grayscale,
+    AdjustRow::Invert,
+    AdjustRow::Brightness,
+    AdjustRow::Contrast,
+    AdjustRow::Saturation,
+];
+
+const PANEL_W: i32 = 200;
+const ROW_H: i32 = 22;
+const PAD: i32 = 6;
+const ICON: i32 = 12;
+const TRACK_X: i32 = PAD + ICON + 10; // 滑块 / 开关起点（相对面板）
+const SWITCH_W: i32 = 26;
+const KNOB_W: i32 = 6;
+
+impl AdjustRow {
+    pub fn is_toggle(self) -> bool {
+        matches!(self, Self::Grayscale | Self::Invert)
+    }
+
+    pub fn toggle(self, adj: &mut Adjustments) {
+        match self {
+            Self::Grayscale => adj.grayscale = !adj.grayscale,
+            Self::Invert => adj.invert = !adj.invert,
+            _ => {}
+        }
+    }
+
+    pub fn value(self, adj: &Adjustments) -> i32 {
+        match self {
+            Self::Grayscale => adj.grayscale as i32,
+            Self::Invert => adj.invert as i32,
+            Self::Brightness => adj.brightness,
+            Self::Contrast => adj.contrast,
+            Self::Saturation => adj.saturation,
+        }
+    }
+
+    pub fn set_value(self, adj: &mut Adjustments, v: i32) {
+        let v = v.clamp(SLIDER_MIN, SLIDER_MAX);
+        match self {
+            Self::Brightness => adj.brightness = v,
+            Self::Contrast => adj.contrast = v,
+            Self::Saturation => adj.saturation = v,
+            _ => {}
+        }
+    }
+}
+
+/// 面板矩形：优先放在工具栏下方，空间不足时放在上方；水平与工具栏左对齐并限制在屏幕内
+pub fn adjust_panel_rect(
+    toolbar: (i32, i32, i32, i32),
+    screen_w: u32,
+    screen_h: u32,
+) -> (i32, i32, i32, i32) {
+    let (bx, by, _, bh) = toolbar;
+    let h = PAD * 2 + ROW_H * ADJUST_ROWS.len() as i32;
+    let below = by + bh + TB_MARGIN;
+    let y = if below + h <= screen_h as i32 {
+        below
+    } else {
+        (by - TB_MARGIN - h).max(0)
+    };
+    let x = bx.min(screen_w as i32 - PANEL_W).max(0);
+    (x, y, PANEL_W, h)
+}
+
+/// 命中的行
+pub fn hit_test_adjust(panel: (i32, i32, i32, i32), px: i32, py: i32) -> Option<AdjustRow> {
+    let (x, y, w, h) = panel;
+    if px < x || px >= x + w || py < y + PAD || py >= y + h - PAD {
+        return None;
+    }
+    ADJUST_ROWS.get(((py - y - PAD) / ROW_H) as usize).copied()
+}
+
+/// 光标 x -> 滑块值（超出轨道两端时取端点）
+pub fn slider_value(panel: (i32, i32, i32, i32), px: i32) -> i32 {
+    let (x0, x1) = track_span(panel);
+    let t = (px - x0).clamp(0, x1 - x0) as f32 / (x1 - x0) as f32;
+    SLIDER_MIN + (t * (SLIDER_MAX - SLIDER_MIN) as f32).round() as i32
+}
+
+fn track_span(panel: (i32, i32, i32, i32)) -> (i32, i32) {
+    let (x, _, w, _) = panel;
+    (x + TRACK_X, x + w - PAD - KNOB_W)
+}
+
+pub fn draw_adjust_panel(
+    frame: &mut [u32],
+    width: u32,
+    height: u32,
+    panel: (i32, i32, i32, i32),
+    adj: &Adjustments,
+    theme: &Theme,
+) {
+    let (px, py, pw, ph) = panel;
+    fill_rect(frame, width, height, px, py, pw, ph, theme.panel);
+    theme.outline(frame, width, height, panel, theme.panel_border);
+    let (x0, x1) = track_span(panel);
+    for (i, row) in ADJUST_ROWS.into_iter().enumerate() {
+        let cy = py + PAD + ROW_H * i as i32 + ROW_H / 2;
+        draw_icon(frame, width, height, px + PAD, cy - ICON / 2, row);
+        let value = row.value(adj);
+        if row.is_toggle() {
+            // 开关：开启为高亮底色，滑钮在右
+            let on = value != 0;
+            let (bg, knob_x) = if on {
+                (0xFF3DA5F4, x0 + SWITCH_W - KNOB_W - 2)
+            } else {
+                (0xFF555555, x0 + 2)
+            };
+            fill_rect(frame, width, height, x0, cy - 6, SWITCH_W, 12, bg);
+            fill_rect(frame, width, height, knob_x, cy - 4, KNOB_W, 8, 0xFFFFFFFF);
+        } else {
+            // 滑块：灰色轨道 + 中点刻度 + 从中点到当前值的高亮段
+            fill_rect(frame, width, height, x0, cy - 1, x1 - x0, 3, 0xFF555555);
+            let mid = (x0 + x1) / 2;
+            let t = (value - SLIDER_MIN) as f32 / (SLIDER_MAX - SLIDER_MIN) as f32;
+            let kx = x0 + ((x1 - x0) as f32 * t).round() as i32;
+            let (a, b) = (mid.min(kx), mid.max(kx));
+            fill_rect(frame, width, height, a, cy - 1, b - a, 3, 0xFF3DA5F4);
+            fill_rect(frame, width, height, mid, cy - 4, 1, 9, 0xFF888888);
+            fill_rect(
+                frame,
+                width,
+                height,
+                kx - KNOB_W / 2,
+                cy - 6,
+                KNOB_W,
+                12,
+                0xFFFFFFFF,
+            );
+        }
+    }
+}
+
+fn draw_icon(frame: &mut [u32], width: u32, height: u32, x: i32, y: i32, row: AdjustRow) {
+    for dy in 0..ICON {
+        for dx in 0..ICON {
+            let color = match row {
+                AdjustRow::Grayscale => {
+                    let v = (dx * 255 / (ICON - 1)) as u32;
+                    0xFF000000 | v << 16 | v << 8 | v
+                }
+                AdjustRow::Invert if dx < ICON / 2 => 0xFFFFFFFF,
+                AdjustRow::Invert => 0xFF000000,
+                AdjustRow::Brightness => 0xFFFFD24D,
+                AdjustRow::Contrast if dx > dy => 0xFFFFFFFF,
+                AdjustRow::Contrast => 0xFF000000,
+                AdjustRow::Saturation => {
+                    [0xFFFF3030, 0xFF30C030, 0xFF3060FF][(dx * 3 / ICON) as usize]
+                }
+            };
+            set_px(frame, width, height, x + dx, y + dy, color);
+        }
+    }
+    stroke_rect(frame, width, height, x, y, ICON, ICON, 0xFFCCCCCC);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panel_placement_and_hit() {
+        // 工具栏下方空间足够
+        let p = adjust_panel_rect((100, 100, 300, 28), 1920, 1080);
+        assert_eq!((p.0, p.1), (100, 134));
+        // 靠近屏幕底部时放到工具栏上方，靠右时左移
+        let p = adjust_panel_rect((1800, 1050, 300, 28), 1920, 1080);
+        assert_eq!(p.0, 1920 - PANEL_W);
+        assert!(p.1 + p.3 <= 1050);
+        let p = (0, 0, PANEL_W, PAD * 2 + ROW_H * 5);
+        assert_eq!(hit_test_adjust(p, 10, PAD + 1), Some(AdjustRow::Grayscale));
+        assert_eq!(
+            hit_test_adjust(p, 10, PAD + ROW_H * 4 + 1),
+            Some(AdjustRow::Saturation)
+        );
+        assert_eq!(hit_test_adjust(p, PANEL_W, PAD + 1), None);
+    }
+
+    #[test]
+    fn test_slider_value_range() {
+        let p = (0, 0, PANEL_W, 100);
+        let (x0, x1) = track_span(p);
+        assert_eq!(slider_value(p, x0 - 50), SLIDER_MIN);
+        assert_eq!(slider_value(p, x1 + 50), SLIDER_MAX);
+        assert_eq!(slider_value(p, (x0 + x1) / 2), 0);
+        let mut adj = Adjustments::default();
+        AdjustRow::Contrast.set_value(&mut adj, 500);
+        assert_eq!(adj.contrast, SLIDER_MAX);
+        AdjustRow::Invert.toggle(&mut adj);
+        assert!(adj.invert);
+    }
+}
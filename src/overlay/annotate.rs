@@ -0,0 +1,536 @@
+// annotate: 画笔标注（工具栏“标注”）与其第二行样式栏（颜色色块 + 粗细预设）
+//
+// 笔画以 overlay 坐标记录：预览时画进帧（BGRA），导出时画进裁剪结果（RGBA），只在选区内可见。
+// 最近使用的颜色与粗细保存在 <数据目录>/annotation.json（见 paths），下次截图沿用。
+// 开启 annotation_sidecar 时，保存的 PNG 旁另写未标注的底图与笔画 JSON（AnnotationLayers），
+// 之后可重新载入 overlay 继续编辑。
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::capture::encode_png;
+use crate::overlay::drawing::{fill_rect, stroke_rect};
+use crate::overlay::regions::Region;
+use crate::overlay::theme::Theme;
+use crate::overlay::toolbar::TB_MARGIN;
+
+/// 色块（0xRRGGBB）：红 橙 黄 绿 蓝 紫 白 黑
+pub const COLORS: [u32; 8] = [
+    0xFF3B30, 0xFF9500, 0xFFCC00, 0x34C759, 0x007AFF, 0xAF52DE, 0xFFFFFF, 0x000000,
+];
+/// 粗细预设（像素）
+pub const WIDTHS: [u32; 3] = [2, 4, 8];
+
+const MAX_WIDTH: u32 = 32;
+const CELL: i32 = 20;
+const GAP: i32 = 4;
+const PAD: i32 = 4;
+const SEPARATOR: i32 = 8; // 色块与粗细之间的额外间距
+const SELECTED_RING: u32 = 0xFFFFD24D;
+
+/// 画笔样式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnnotationStyle {
+    pub color: u32,
+    pub width: u32,
+}
+
+impl Default for AnnotationStyle {
+    fn default() -> Self {
+        Self {
+            color: COLORS[0],
+            width: WIDTHS[1],
+        }
+    }
+}
+
+impl AnnotationStyle {
+    // 文件被手改时限制到合理范围
+    fn normalized(self) -> Self {
+        Self {
+            color: self.color & 0xFFFFFF,
+            width: self.width.clamp(1, MAX_WIDTH),
+        }
+    }
+}
+
+/// 一笔：按下到松开之间的光标轨迹
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stroke {
+    pub style: AnnotationStyle,
+    pub points: Vec<(i32, i32)>,
+    /// 触控笔压力（与 points 一一对应，0..=255）；为空时整笔等宽
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pressure: Vec<u8>,
+}
+
+impl Stroke {
+    /// 第 i 个点处的笔宽：style.width 按压力在 0.5x（最轻）..1.5x（最重）之间缩放
+    pub fn width_at(&self, i: usize) -> u32 {
+        match self.pressure.get(i) {
+            Some(&p) => ((self.style.width as f64 * (0.5 + p as f64 / 255.0)).round() as u32)
+                .clamp(1, MAX_WIDTH),
+            None => self.style.width,
+        }
+    }
+}
+
+/// 压力（0..=1）-> Stroke::pressure 中的取值
+pub fn pressure_level(pressure: f64) -> u8 {
+    (pressure.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// 样式栏上的一个格子
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StylePick {
+    Color(u32),
+    Width(u32),
+}
+
+impl StylePick {
+    pub fn apply(self, style: &mut AnnotationStyle) {
+        match self {
+            StylePick::Color(c) => style.color = c,
+            StylePick::Width(w) => style.width = w,
+        }
+    }
+
+    fn is_selected(self, style: &AnnotationStyle) -> bool {
+        match self {
+            StylePick::Color(c) => style.color == c,
+            StylePick::Width(w) => style.width == w,
+        }
+    }
+}
+
+// 格子左上角（相对样式栏）与对应选项
+fn cells() -> impl Iterator<Item = (i32, StylePick)> {
+    let colors = COLORS.into_iter().map(StylePick::Color);
+    let widths = WIDTHS.into_iter().map(StylePick::Width);
+    colors.chain(widths).enumerate().map(|(i, pick)| {
+        let sep = if i >= COLORS.len() { SEPARATOR } else { 0 };
+        (PAD + i as i32 * (CELL + GAP) + sep, pick)
+    })
+}
+
+fn bar_size() -> (i32, i32) {
+    let n = (COLORS.len() + WIDTHS.len()) as i32;
+    (
+        PAD * 2 + n * CELL + (n - 1) * GAP + SEPARATOR,
+        CELL + PAD * 2,
+    )
+}
+
+/// 样式栏矩形：作为工具栏的第二行，优先放在下方，空间不足时放在上方；水平与工具栏左对齐
+pub fn style_bar_rect(
+    toolbar: (i32, i32, i32, i32),
+    screen_w: u32,
+    screen_h: u32,
+) -> (i32, i32, i32, i32) {
+    let (bx, by, _, bh) = toolbar;
+    let (w, h) = bar_size();
+    let below = by + bh + TB_MARGIN / 2;
+    let y = if below + h <= screen_h as i32 {
+        below
+    } else {
+        (by - TB_MARGIN / 2 - h).max(0)
+    };
+    let x = bx.min(screen_w as i32 - w).max(0);
+    (x, y, w, h)
+}
+
+/// 命中的格子
+pub fn hit_test_style_bar(bar: (i32, i32, i32, i32), px: i32, py: i32) -> Option<StylePick> {
+    let (x, y, _, _) = bar;
+    if py < y + PAD || py >= y + PAD + CELL {
+        return None;
+    }
+    cells()
+        .find(|&(cx, _)| px >= x + cx && px < x + cx + CELL)
+        .map(|(_, pick)| pick)
+}
+
+pub fn draw_style_bar(
+    frame: &mut [u32],
+    width: u32,
+    height: u32,
+    bar: (i32, i32, i32, i32),
+    style: &AnnotationStyle,
+    theme: &Theme,
+) {
+    let (bx, by, bw, bh) = bar;
+    fill_rect(frame, width, height, bx, by, bw, bh, theme.panel);
+    theme.outline(frame, width, height, bar, theme.panel_border);
+    let y = by + PAD;
+    for (cx, pick) in cells() {
+        let x = bx + cx;
+        match pick {
+            StylePick::Color(c) => {
+                fill_rect(frame, width, height, x, y, CELL, CELL, 0xFF000000 | c);
+                stroke_rect(frame, width, height, x, y, CELL, CELL, 0xFF888888);
+            }
+            StylePick::Width(w) => {
+                // 粗细：当前颜色的横线
+                fill_rect(frame, width, height, x, y, CELL, CELL, 0xFF333333);
+                let t = (w as i32).min(CELL - 4);
+                let color = 0xFF000000 | style.color;
+                fill_rect(
+                    frame,
+                    width,
+                    height,
+                    x + 3,
+                    y + (CELL - t) / 2,
+                    CELL - 6,
+                    t,
+                    color,
+                );
+            }
+        }
+        if pick.is_selected(style) {
+            stroke_rect(
+                frame,
+                width,
+                height,
+                x - 2,
+                y - 2,
+                CELL + 4,
+                CELL + 4,
+                SELECTED_RING,
+            );
+            stroke_rect(
+                frame,
+                width,
+                height,
+                x - 1,
+                y - 1,
+                CELL + 2,
+                CELL + 2,
+                SELECTED_RING,
+            );
+        }
+    }
+}
+
+// 笔画覆盖的像素（圆头，逐段按 1 像素步长盖章）：坐标减去 offset 后裁到 clip (x0, y0, x1, y1)
+fn for_each_px(
+    stroke: &Stroke,
+    offset: (i32, i32),
+    clip: (i32, i32, i32, i32),
+    mut f: impl FnMut(i32, i32),
+) {
+    let mut stamp = |px: i32, py: i32, r: i32| {
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let (x, y) = (px + dx, py + dy);
+                if dx * dx + dy * dy <= r * r + r
+                    && x >= clip.0
+                    && y >= clip.1
+                    && x < clip.2
+                    && y < clip.3
+                {
+                    f(x, y);
+                }
+            }
+        }
+    };
+    let points: Vec<(i32, i32)> = stroke
+        .points
+        .iter()
+        .map(|&(x, y)| (x - offset.0, y - offset.1))
+        .collect();
+    let radius = |i: usize| (stroke.width_at(i) / 2) as i32;
+    if let [(x, y)] = points[..] {
+        stamp(x, y, radius(0));
+    }
+    for (n, seg) in points.windows(2).enumerate() {
+        let ((x0, y0), (x1, y1)) = (seg[0], seg[1]);
+        let (r0, r1) = (radius(n), radius(n + 1));
+        let steps = (x1 - x0).abs().max((y1 - y0).abs()).max(1);
+        for i in 0..=steps {
+            // 有压力时半径沿线段线性过渡
+            stamp(
+                x0 + (x1 - x0) * i / steps,
+                y0 + (y1 - y0) * i / steps,
+                r0 + (r1 - r0) * i / steps,
+            );
+        }
+    }
+}
+
+/// 预览：把笔画画进帧（BGRA），只画 clip 区域（选区）内的部分
+pub fn draw_strokes(frame: &mut [u32], width: u32, height: u32, clip: Region, strokes: &[Stroke]) {
+    let (x, y, w, h) = clip;
+    let clip = (
+        x as i32,
+        y as i32,
+        (x + w).min(width) as i32,
+        (y + h).min(height) as i32,
+    );
+    for stroke in strokes {
+        let color = 0xFF000000 | stroke.style.color;
+        for_each_px(stroke, (0, 0), clip, |x, y| {
+            frame[(y as u32 * width + x as u32) as usize] = color;
+        });
+    }
+}
+
+/// 导出：把笔画画进裁剪结果（RGBA），origin 为裁剪区域在 overlay 内的左上角
+pub fn apply_strokes_rgba(
+    rgba: &mut [u8],
+    width: u32,
+    height: u32,
+    origin: (u32, u32),
+    strokes: &[Stroke],
+) {
+    let clip = (0, 0, width as i32, height as i32);
+    let offset = (origin.0 as i32, origin.1 as i32);
+    for stroke in strokes {
+        let [b, g, r, _] = stroke.style.color.to_le_bytes();
+        for_each_px(stroke, offset, clip, |x, y| {
+            let i = (y as u32 * width + x as u32) as usize * 4;
+            rgba[i..i + 4].copy_from_slice(&[r, g, b, 255]);
+        });
+    }
+}
+
+/// 平移笔画（overlay 坐标 <-> 图像坐标）
+pub fn translate_strokes(strokes: &[Stroke], dx: i32, dy: i32) -> Vec<Stroke> {
+    strokes
+        .iter()
+        .map(|s| Stroke {
+            style: s.style,
+            points: s.points.iter().map(|&(x, y)| (x + dx, y + dy)).collect(),
+            pressure: s.pressure.clone(),
+        })
+        .collect()
+}
+
+const SIDECAR_VERSION: u32 = 1;
+
+/// 标注图层：未画笔画的底图（RGBA，已打码 / 调整）与图像坐标的笔画
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotationLayers {
+    pub width: u32,
+    pub height: u32,
+    pub base: Vec<u8>,
+    pub strokes: Vec<Stroke>,
+}
+
+// <name>.annotations.json 内容；底图以同目录下的文件名引用
+#[derive(Debug, Serialize, Deserialize)]
+struct SidecarFile {
+    version: u32,
+    base: String,
+    width: u32,
+    height: u32,
+    strokes: Vec<Stroke>,
+}
+
+/// foo.png -> foo.annotations.json
+pub fn sidecar_path(png: &Path) -> PathBuf {
+    png.with_extension("annotations.json")
+}
+
+// foo.png -> foo.base.png
+fn base_path(png: &Path) -> PathBuf {
+    png.with_extension("base.png")
+}
+
+impl AnnotationLayers {
+    /// 在已保存的标注后 PNG 旁写出底图与 sidecar，返回 sidecar 路径
+    pub fn write(&self, png: &Path) -> Result<PathBuf> {
+        let base = base_path(png);
+        let file = SidecarFile {
+            version: SIDECAR_VERSION,
+            base: base
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .ok_or_else(|| anyhow!("invalid path: {}", png.display()))?,
+            width: self.width,
+            height: self.height,
+            strokes: self.strokes.clone(),
+        };
+        let json = serde_json::to_string(&file)?;
+        fs::write(&base, encode_png(&self.base, self.width, self.height)?)
+            .map_err(|e| anyhow!("write {}: {e}", base.display()))?;
+        let path = sidecar_path(png);
+        fs::write(&path, json).map_err(|e| anyhow!("write {}: {e}", path.display()))?;
+        Ok(path)
+    }
+
+    /// 读取 sidecar（也可传入标注后的 PNG，按 sidecar_path 查找）与其底图
+    pub fn load(path: &Path) -> Result<Self> {
+        let path = if path.to_string_lossy().ends_with(".annotations.json") {
+            path.to_path_buf()
+        } else {
+            sidecar_path(path)
+        };
+        let text =
+            fs::read_to_string(&path).map_err(|e| anyhow!("read {}: {e}", path.display()))?;
+        let file: SidecarFile =
+            serde_json::from_str(&text).map_err(|e| anyhow!("invalid sidecar: {e}"))?;
+        if file.version > SIDECAR_VERSION {
+            return Err(anyhow!("unsupported sidecar version {}", file.version));
+        }
+        let base = path.with_file_name(&file.base);
+        let image = image::open(&base)
+            .map_err(|e| anyhow!("open {}: {e}", base.display()))?
+            .to_rgba8();
+        if image.dimensions() != (file.width, file.height) {
+            return Err(anyhow!("base image size does not match sidecar"));
+        }
+        let strokes = file
+            .strokes
+            .into_iter()
+            .map(|s| Stroke {
+                style: s.style.normalized(),
+                points: s.points,
+                pressure: s.pressure,
+            })
+            .collect();
+        Ok(Self {
+            width: file.width,
+            height: file.height,
+            base: image.into_raw(),
+            strokes,
+        })
+    }
+
+    /// 合成标注后的图像（与保存的 PNG 一致）
+    pub fn flatten(&self) -> Vec<u8> {
+        let mut out = self.base.clone();
+        apply_strokes_rgba(&mut out, self.width, self.height, (0, 0), &self.strokes);
+        out
+    }
+}
+
+fn style_path() -> Option<PathBuf> {
+    crate::paths::data_dir().map(|d| d.join("annotation.json"))
+}
+
+/// 上次使用的样式（文件不存在或无效时为默认）
+pub fn load_style() -> AnnotationStyle {
+    style_path()
+        .and_then(|p| fs::read_to_string(p).ok())
+        .and_then(|text| serde_json::from_str::<AnnotationStyle>(&text).ok())
+        .unwrap_or_default()
+        .normalized()
+}
+
+/// 记住样式（overlay 隐藏时样式有变化才调用）
+pub fn save_style(style: AnnotationStyle) {
+    let Some(path) = style_path() else {
+        return;
+    };
+    let written = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, serde_json::to_string(&style).unwrap_or_default()));
+    if let Err(e) = written {
+        log::warn!("write {}: {e}", path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_style_bar_hit() {
+        let bar = style_bar_rect((100, 100, 300, 28), 1920, 1080);
+        assert_eq!(bar.1, 100 + 28 + TB_MARGIN / 2);
+        let y = bar.1 + PAD + 1;
+        for (cx, pick) in cells() {
+            assert_eq!(hit_test_style_bar(bar, bar.0 + cx + 1, y), Some(pick));
+        }
+        // 色块与粗细之间的间隔、边距不命中
+        let (last_color, _) = cells().nth(COLORS.len() - 1).unwrap();
+        assert_eq!(
+            hit_test_style_bar(bar, bar.0 + last_color + CELL + 1, y),
+            None
+        );
+        assert_eq!(hit_test_style_bar(bar, bar.0 + PAD + 1, bar.1), None);
+        // 屏幕底部放不下时放到工具栏上方
+        let bar = style_bar_rect((100, 1050, 300, 28), 1920, 1080);
+        assert!(bar.1 + bar.3 <= 1050);
+    }
+
+    #[test]
+    fn test_strokes_rasterize_and_clip() {
+        let stroke = Stroke {
+            style: AnnotationStyle {
+                color: 0x112233,
+                width: 1,
+            },
+            points: vec![(8, 11), (20, 11)],
+            pressure: Vec::new(),
+        };
+        // 裁剪区域从 (10, 10) 开始、4x3：第 1 行整行被画上，其余不变
+        let (w, h) = (4, 3);
+        let mut rgba = vec![0u8; (w * h * 4) as usize];
+        apply_strokes_rgba(&mut rgba, w, h, (10, 10), std::slice::from_ref(&stroke));
+        let px = |x: u32, y: u32| &rgba[((y * w + x) * 4) as usize..][..4];
+        for x in 0..w {
+            assert_eq!(px(x, 1), [0x11, 0x22, 0x33, 255]);
+            assert_eq!(px(x, 0), [0, 0, 0, 0]);
+            assert_eq!(px(x, 2), [0, 0, 0, 0]);
+        }
+        // 预览只画选区内
+        let mut frame = vec![0u32; 30 * 20];
+        draw_strokes(&mut frame, 30, 20, (10, 10, 4, 3), &[stroke]);
+        assert_eq!(frame.iter().filter(|&&p| p == 0xFF112233).count(), 4);
+        // 触控笔压力：0.5x..1.5x，没有压力的点用原宽度
+        let pen = Stroke {
+            style: AnnotationStyle { color: 0, width: 4 },
+            points: vec![(0, 0), (10, 0)],
+            pressure: vec![0, pressure_level(1.0)],
+        };
+        assert_eq!(
+            (pen.width_at(0), pen.width_at(1), pen.width_at(2)),
+            (2, 6, 4)
+        );
+        assert_eq!(pressure_level(0.5), 128);
+    }
+
+    #[test]
+    fn test_sidecar_round_trip() {
+        let dir = TempDir::new("sidecar_test");
+        let png = dir.join("snip_1.png");
+        assert_eq!(sidecar_path(&png), dir.join("snip_1.annotations.json"));
+        let strokes = vec![Stroke {
+            style: AnnotationStyle::default(),
+            points: vec![(110, 52), (104, 50)],
+            pressure: vec![64, 255],
+        }];
+        let layers = AnnotationLayers {
+            width: 8,
+            height: 4,
+            base: (0..8 * 4 * 4).map(|i| i as u8).collect(),
+            // overlay 中选区左上角为 (100, 50)
+            strokes: translate_strokes(&strokes, -100, -50),
+        };
+        assert_eq!(layers.strokes[0].points, [(10, 2), (4, 0)]);
+        let sidecar = layers.write(&png).unwrap();
+        assert!(dir.join("snip_1.base.png").exists());
+        // 从 sidecar 或标注后的 PNG 都能载入
+        assert_eq!(AnnotationLayers::load(&sidecar).unwrap(), layers);
+        assert_eq!(AnnotationLayers::load(&png).unwrap(), layers);
+        assert_ne!(layers.flatten(), layers.base);
+        assert!(AnnotationLayers::load(&dir.join("missing.png")).is_err());
+    }
+
+    #[test]
+    fn test_style_file_fallbacks() {
+        let style: AnnotationStyle = serde_json::from_str(r#"{"color": 255}"#).unwrap();
+        assert_eq!(style.width, AnnotationStyle::default().width);
+        let style = AnnotationStyle {
+            color: 0xFF00FF00,
+            width: 500,
+        }
+        .normalized();
+        assert_eq!((style.color, style.width), (0x00FF00, MAX_WIDTH));
+    }
+}
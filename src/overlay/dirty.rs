@@ -0,0 +1,135 @@
+// 脏矩形：拖动时只重写 旧选区 ∪ 新选区 ∪ 工具栏 覆盖的区域，而不是整屏重合成
+use std::num::NonZeroU32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirtyRect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+impl DirtyRect {
+    /// 有符号矩形裁剪到 [0,width)x[0,height)，完全在外返回 None
+    pub fn clamped(x: i32, y: i32, w: i32, h: i32, width: u32, height: u32) -> Option<Self> {
+        let x0 = x.max(0) as i64;
+        let y0 = y.max(0) as i64;
+        let x1 = (x as i64 + w as i64).min(width as i64);
+        let y1 = (y as i64 + h as i64).min(height as i64);
+        if x1 <= x0 || y1 <= y0 {
+            return None;
+        }
+        Some(Self {
+            x: x0 as u32,
+            y: y0 as u32,
+            w: (x1 - x0) as u32,
+            h: (y1 - y0) as u32,
+        })
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        let x0 = self.x.min(other.x);
+        let y0 = self.y.min(other.y);
+        let x1 = (self.x + self.w).max(other.x + other.w);
+        let y1 = (self.y + self.h).max(other.y + other.h);
+        Self {
+            x: x0,
+            y: y0,
+            w: x1 - x0,
+            h: y1 - y0,
+        }
+    }
+
+    pub fn to_damage(self) -> Option<softbuffer::Rect> {
+        Some(softbuffer::Rect {
+            x: self.x,
+            y: self.y,
+            width: NonZeroU32::new(self.w)?,
+            height: NonZeroU32::new(self.h)?,
+        })
+    }
+}
+
+fn union_opt(a: Option<DirtyRect>, b: Option<DirtyRect>) -> Option<DirtyRect> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.union(b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// 当前帧在暗背景之上绘制内容的包围盒：选区（含边框手柄外扩 handle_half）+ 工具栏
+pub fn overlay_bounds(
+    selection: Option<(u32, u32, u32, u32)>,
+    toolbar: Option<(i32, i32, i32, i32)>,
+    handle_half: i32,
+    width: u32,
+    height: u32,
+) -> Option<DirtyRect> {
+    let sel = selection
+        .filter(|&(_, _, w, h)| w > 0 && h > 0)
+        .and_then(|(x, y, w, h)| {
+            let pad = handle_half + 1;
+            DirtyRect::clamped(
+                x as i32 - pad,
+                y as i32 - pad,
+                w as i32 + pad * 2,
+                h as i32 + pad * 2,
+                width,
+                height,
+            )
+        });
+    let bar = toolbar.and_then(|(x, y, w, h)| DirtyRect::clamped(x, y, w, h, width, height));
+    union_opt(sel, bar)
+}
+
+/// 本帧需要重写的区域；None 表示整帧重绘。
+/// 包围盒未变化（系统重绘 / 悬停高亮等）时也整帧重绘，保证被遮挡区域得到恢复。
+pub fn damage(
+    previous: Option<DirtyRect>,
+    current: Option<DirtyRect>,
+    force_full: bool,
+) -> Option<DirtyRect> {
+    if force_full || previous == current {
+        return None;
+    }
+    union_opt(previous, current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_damage_union_of_old_and_new() {
+        let old = overlay_bounds(Some((10, 10, 20, 20)), None, 3, 100, 100);
+        let new = overlay_bounds(Some((40, 10, 20, 20)), None, 3, 100, 100);
+        let d = damage(old, new, false).unwrap();
+        assert_eq!(
+            d,
+            DirtyRect {
+                x: 6,
+                y: 6,
+                w: 58,
+                h: 28
+            }
+        );
+        assert_eq!(damage(old, old, false), None);
+        assert_eq!(damage(old, new, true), None);
+    }
+
+    #[test]
+    fn test_bounds_clamped_to_screen() {
+        let b = overlay_bounds(Some((0, 0, 5, 5)), Some((90, 95, 20, 20)), 3, 100, 100).unwrap();
+        assert_eq!(
+            b,
+            DirtyRect {
+                x: 0,
+                y: 0,
+                w: 100,
+                h: 100
+            }
+        );
+        assert_eq!(DirtyRect::clamped(-10, -10, 5, 5, 100, 100), None);
+    }
+}
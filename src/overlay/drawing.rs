@@ -1,3 +1,5 @@
+use serde::Deserialize;
+
 pub fn set_px(frame: &mut [u32], width: u32, height: u32, x: i32, y: i32, color: u32) {
     if x < 0 || y < 0 {
         return;
@@ -62,7 +64,29 @@ pub fn stroke_rect(
     }
 }
 
-pub fn draw_handle(frame: &mut [u32], width: u32, height: u32, cx: i32, cy: i32, half: i32) {
+/// 棋盘格（表示透明，挖空区域用）
+pub fn fill_checker(frame: &mut [u32], width: u32, height: u32, rect: (i32, i32, i32, i32)) {
+    const CELL: i32 = 8;
+    let (x, y, w, h) = rect;
+    let (sw, sh) = (width as i32, height as i32);
+    for yy in y.max(0)..(y + h).min(sh) {
+        let row = yy as u32 * width;
+        for xx in x.max(0)..(x + w).min(sw) {
+            let light = ((xx - x) / CELL + (yy - y) / CELL) % 2 == 0;
+            frame[(row + xx as u32) as usize] = if light { 0xFFE0E0E0 } else { 0xFFA0A0A0 };
+        }
+    }
+}
+
+pub fn draw_handle(
+    frame: &mut [u32],
+    width: u32,
+    height: u32,
+    cx: i32,
+    cy: i32,
+    half: i32,
+    color: u32,
+) {
     let (sw, sh) = (width as i32, height as i32);
     for yy in (cy - half)..=(cy + half) {
         if yy < 0 || yy >= sh {
@@ -73,7 +97,197 @@ pub fn draw_handle(frame: &mut [u32], width: u32, height: u32, cx: i32, cy: i32,
                 continue;
             }
             let idx = (yy as u32 * width + xx as u32) as usize;
-            frame[idx] = 0xFFFFFFFF;
+            frame[idx] = color;
+        }
+    }
+}
+
+/// 边框虚线每段长度（像素）
+const BORDER_DASH: u32 = 6;
+/// 亮度不低于此值的像素算“亮”
+const BRIGHT_LUMA: u8 = 160;
+
+/// 选区边框：默认白色；边框下的像素大多很亮时改为黑色，明暗混杂时为黑白相间的虚线
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorderStyle {
+    Solid(u32),
+    Dashed(u32, u32),
+}
+
+impl BorderStyle {
+    /// 按边框下像素的亮度选择：亮像素不到 20% 为白色，超过 80% 为黑色，其余为虚线
+    pub fn contrast(lumas: impl IntoIterator<Item = u8>) -> Self {
+        let (mut bright, mut total) = (0usize, 0usize);
+        for l in lumas {
+            bright += (l >= BRIGHT_LUMA) as usize;
+            total += 1;
+        }
+        match bright * 5 {
+            b if b <= total => Self::Solid(0xFFFFFFFF),
+            b if b >= total * 4 => Self::Solid(0xFF000000),
+            _ => Self::Dashed(0xFFFFFFFF, 0xFF000000),
+        }
+    }
+
+    /// 沿边框第 i 个像素的颜色
+    pub fn color_at(self, i: u32) -> u32 {
+        match self {
+            Self::Solid(c) => c,
+            Self::Dashed(a, b) => [a, b][(i / BORDER_DASH % 2) as usize],
+        }
+    }
+
+    pub fn primary(self) -> u32 {
+        self.color_at(0)
+    }
+
+    /// 虚线的另一种颜色（手柄用作描边）
+    pub fn secondary(self) -> Option<u32> {
+        match self {
+            Self::Solid(_) => None,
+            Self::Dashed(_, b) => Some(b),
         }
     }
 }
+
+/// BGRA 像素的亮度（0..=255，见 filters::luma）
+pub fn luma(px: u32) -> u8 {
+    let [b, g, r, _] = px.to_le_bytes();
+    crate::filters::luma(r, g, b)
+}
+
+/// 选区内的构图辅助线（配置 `selection_guides`，overlay 中按 G 切换）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionGuides {
+    #[default]
+    Off,
+    /// 三分法 3×3 网格
+    Thirds,
+    /// 中心十字
+    Center,
+    /// 网格 + 中心十字
+    All,
+}
+
+/// 中心十字每侧臂长（像素）
+const CROSS_ARM: i32 = 10;
+/// 网格虚线的实线 / 间隔长度（像素）
+const GUIDE_DASH: i32 = 4;
+const GUIDE_COLOR: u32 = 0xFFD0D0D0;
+
+impl SelectionGuides {
+    /// G 键循环：off → thirds → center → all → off
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Thirds,
+            Self::Thirds => Self::Center,
+            Self::Center => Self::All,
+            Self::All => Self::Off,
+        }
+    }
+
+    /// 辅助线线段 (x, y, w, h, 虚线)，宽或高为 1；选区太小（任一边 < 9）时为空
+    pub fn segments(self, region: (u32, u32, u32, u32)) -> Vec<(i32, i32, i32, i32, bool)> {
+        let (x, y, w, h) = (
+            region.0 as i32,
+            region.1 as i32,
+            region.2 as i32,
+            region.3 as i32,
+        );
+        let mut out = Vec::new();
+        if self == Self::Off || w < 9 || h < 9 {
+            return out;
+        }
+        if matches!(self, Self::Thirds | Self::All) {
+            for i in 1..3 {
+                out.push((x + w * i / 3, y, 1, h, true));
+                out.push((x, y + h * i / 3, w, 1, true));
+            }
+        }
+        if matches!(self, Self::Center | Self::All) {
+            let (cx, cy) = (x + w / 2, y + h / 2);
+            let arm = CROSS_ARM.min(w / 2 - 1).min(h / 2 - 1);
+            out.push((cx - arm, cy, arm * 2 + 1, 1, false));
+            out.push((cx, cy - arm, 1, arm * 2 + 1, false));
+        }
+        out
+    }
+}
+
+/// 在选区内画辅助线（网格为虚线，十字为实线）
+pub fn draw_guides(
+    frame: &mut [u32],
+    width: u32,
+    height: u32,
+    region: (u32, u32, u32, u32),
+    guides: SelectionGuides,
+) {
+    for (x, y, w, h, dashed) in guides.segments(region) {
+        let len = w.max(h);
+        for i in (0..len).filter(|i| !dashed || (i / GUIDE_DASH) % 2 == 0) {
+            let (px, py) = if w > h { (x + i, y) } else { (x, y + i) };
+            set_px(frame, width, height, px, py, GUIDE_COLOR);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guide_segments() {
+        assert!(SelectionGuides::Off.segments((0, 0, 300, 300)).is_empty());
+        assert!(SelectionGuides::All.segments((0, 0, 8, 300)).is_empty());
+        let thirds = SelectionGuides::Thirds.segments((10, 20, 300, 150));
+        assert_eq!(
+            thirds,
+            [
+                (110, 20, 1, 150, true),
+                (10, 70, 300, 1, true),
+                (210, 20, 1, 150, true),
+                (10, 120, 300, 1, true),
+            ]
+        );
+        // 小选区的十字臂长不超出选区
+        let cross = SelectionGuides::Center.segments((0, 0, 12, 40));
+        assert_eq!(cross, [(1, 20, 11, 1, false), (6, 15, 1, 11, false)]);
+        assert_eq!(SelectionGuides::All.segments((0, 0, 90, 90)).len(), 6);
+        assert_eq!(SelectionGuides::All.next(), SelectionGuides::Off);
+
+        let mut frame = vec![0u32; 100 * 100];
+        draw_guides(
+            &mut frame,
+            100,
+            100,
+            (0, 0, 90, 90),
+            SelectionGuides::Thirds,
+        );
+        assert_eq!(frame[30], GUIDE_COLOR);
+        // 虚线：第 4~7 个像素为间隔
+        assert_eq!(frame[5 * 100 + 30], 0);
+    }
+
+    #[test]
+    fn test_border_contrast() {
+        let white = luma(0xFFFFFFFF);
+        let black = luma(0xFF000000);
+        assert_eq!((white, black), (255, 0));
+        assert_eq!(
+            BorderStyle::contrast([black; 10]),
+            BorderStyle::Solid(0xFFFFFFFF)
+        );
+        // 白色背景上改为黑色边框
+        let mostly_white = [[white; 9].as_slice(), &[black]].concat();
+        assert_eq!(
+            BorderStyle::contrast(mostly_white),
+            BorderStyle::Solid(0xFF000000)
+        );
+        let mixed = BorderStyle::contrast([white, black, white, black]);
+        assert_eq!(mixed, BorderStyle::Dashed(0xFFFFFFFF, 0xFF000000));
+        assert_eq!(mixed.color_at(BORDER_DASH), 0xFF000000);
+        assert_eq!(mixed.secondary(), Some(0xFF000000));
+        assert_eq!(BorderStyle::contrast([]), BorderStyle::Solid(0xFFFFFFFF));
+    }
+}
@@ -0,0 +1,543 @@
+// gpu: wgpu overlay 渲染后端（feature = "gpu"）
+//
+// 截图作为纹理上传一次；变暗、选区高亮、边框、手柄在片元着色器中完成，
+// 工具栏仍由 CPU 绘制（toolbar::draw_toolbar）到一张小纹理，仅在悬停 / 键盘焦点变化时重传。
+// 拖动时每帧只更新一个 uniform，4K / HDR 屏上也能保持流畅。
+// 着色器画不了的状态（多选区、标注、调整面板等）由 CPU 合成整帧，present_frame 按脏区上传为纹理
+// 再经同一个 wgpu surface 呈现：启用 GPU 后窗口上不再有 softbuffer 呈现者。
+use anyhow::{anyhow, Result};
+
+use crate::overlay::drawing::BorderStyle;
+use crate::overlay::theme::{Theme, UiScale};
+use crate::overlay::toolbar::{draw_toolbar, toolbar_size, Slot, MAX_SLOTS};
+use crate::window_surface::SharedWindow;
+
+// 两个管线共用：单个覆盖全屏的三角形
+const VERTEX: &str = r#"
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((i << 1u) & 2u), f32(i & 2u));
+    return vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+}
+"#;
+
+const SHADER: &str = r#"
+struct Params {
+    sel: vec4<f32>,    // x, y, w, h（w = 0 表示无选区）
+    bar: vec4<f32>,    // 工具栏 x, y, w, h（w = 0 表示不显示）
+    style: vec4<f32>,  // dim 系数, 手柄半径, 选区内是否高亮, 未用
+};
+
+@group(0) @binding(0) var screenshot: texture_2d<f32>;
+@group(0) @binding(1) var toolbar: texture_2d<f32>;
+@group(0) @binding(2) var<uniform> params: Params;
+
+// 边框颜色：style.w = 0 白 / 1 黑 / 2 黑白虚线（每段 6 像素，同 drawing::BORDER_DASH），t 为沿边位置
+fn border_color(t: f32) -> vec4<f32> {
+    let mode = params.style.w;
+    if (mode < 0.5 || (mode > 1.5 && floor(t / 6.0) % 2.0 < 0.5)) {
+        return vec4<f32>(1.0);
+    }
+    return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+}
+
+fn inside(p: vec2<f32>, r: vec4<f32>) -> bool {
+    return p.x >= r.x && p.y >= r.y && p.x < r.x + r.z && p.y < r.y + r.w;
+}
+
+@fragment
+fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    let p = floor(pos.xy);
+    let dims = vec2<f32>(textureDimensions(screenshot));
+    if (p.x >= dims.x || p.y >= dims.y) {
+        return vec4<f32>(0.0, 0.0, 0.0, 0.53);
+    }
+    let bar = params.bar;
+    if (bar.z > 0.0 && inside(p, bar)) {
+        return textureLoad(toolbar, vec2<i32>(p - bar.xy), 0);
+    }
+    let src = textureLoad(screenshot, vec2<i32>(p), 0);
+    let sel = params.sel;
+    if (sel.z > 0.0 && sel.w > 0.0) {
+        let x2 = sel.x + sel.z - 1.0;
+        let y2 = sel.y + sel.w - 1.0;
+        let half = params.style.y;
+        // 8 个控制手柄（四角 + 四边中点）
+        let cx = array<f32, 3>(sel.x, sel.x + floor(sel.z / 2.0), x2);
+        let cy = array<f32, 3>(sel.y, sel.y + floor(sel.w / 2.0), y2);
+        for (var ix = 0; ix < 3; ix++) {
+            for (var iy = 0; iy < 3; iy++) {
+                if (ix == 1 && iy == 1) {
+                    continue;
+                }
+                let d = max(abs(p.x - cx[ix]), abs(p.y - cy[iy]));
+                if (d <= half) {
+                    return border_color(0.0);
+                }
+                // 虚线边框时手柄外加一圈黑色
+                if (params.style.w > 1.5 && d <= half + 1.0) {
+                    return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+                }
+            }
+        }
+        let in_sel = p.x >= sel.x && p.y >= sel.y && p.x <= x2 && p.y <= y2;
+        if (in_sel && (p.y == sel.y || p.y == y2)) {
+            return border_color(p.x - sel.x);
+        }
+        if (in_sel && (p.x == sel.x || p.x == x2)) {
+            return border_color(p.y - sel.y);
+        }
+        if (in_sel && params.style.z > 0.5) {
+            return src;
+        }
+    }
+    return vec4<f32>(src.rgb * params.style.x, src.a);
+}
+"#;
+
+// CPU 合成的帧（BGRA u32，与 softbuffer 缓冲相同）原样输出，alpha 同 softbuffer 忽略
+const BLIT_SHADER: &str = r#"
+@group(0) @binding(0) var frame: texture_2d<f32>;
+
+@fragment
+fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    return vec4<f32>(textureLoad(frame, vec2<i32>(floor(pos.xy)), 0).rgb, 1.0);
+}
+"#;
+
+// 着色器 style.w：0 白 / 1 黑 / 2 黑白虚线（GPU 路径只用于默认主题，颜色固定为黑白）
+fn border_mode(border: BorderStyle) -> f32 {
+    match border {
+        BorderStyle::Solid(0xFF000000) => 1.0,
+        BorderStyle::Solid(_) => 0.0,
+        BorderStyle::Dashed(..) => 2.0,
+    }
+}
+
+// 与着色器 Params 布局一致（3 x vec4<f32>）
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct Params {
+    sel: [f32; 4],
+    bar: [f32; 4],
+    style: [f32; 4],
+}
+
+impl Params {
+    fn as_bytes(&self) -> Vec<u8> {
+        self.sel
+            .iter()
+            .chain(&self.bar)
+            .chain(&self.style)
+            .flat_map(|v| v.to_le_bytes())
+            .collect()
+    }
+}
+
+/// 每帧绘制所需的 overlay 状态
+pub struct GpuFrame {
+    pub selection: Option<(u32, u32, u32, u32)>,
+    pub highlight: bool, // 选区内显示原图亮度
+    pub toolbar: Option<(i32, i32, i32, i32)>,
+    pub toolbar_slots: Vec<Slot>,
+    pub toolbar_hover: Option<usize>,
+    pub toolbar_focus: Option<usize>,
+    pub border: BorderStyle, // 选区边框（按背景明暗选择，见 drawing::BorderStyle）
+}
+
+pub struct GpuRenderer {
+    // surface 通过裸窗口句柄创建，持有 Rc 保证窗口比 surface 活得久
+    surface: wgpu::Surface<'static>,
+    _window: SharedWindow,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    config: wgpu::SurfaceConfiguration,
+    pipeline: wgpu::RenderPipeline,
+    layout: wgpu::BindGroupLayout,
+    params: wgpu::Buffer,
+    toolbar_tex: wgpu::Texture,
+    toolbar_drawn: Option<(Vec<Slot>, Option<usize>, Option<usize>)>, // 已上传的（位置, 悬停, 焦点）
+    bind_group: Option<wgpu::BindGroup>,                              // 随截图纹理重建
+    blit_pipeline: wgpu::RenderPipeline,
+    blit_layout: wgpu::BindGroupLayout,
+    frame: Option<(wgpu::Texture, wgpu::BindGroup)>, // CPU 合成帧，随窗口尺寸重建
+}
+
+impl GpuRenderer {
+    /// 初始化失败（无可用适配器等）时返回错误，调用方回退 softbuffer
+    pub fn new(window: SharedWindow) -> Result<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+        // SAFETY: self._window 持有同一窗口的 Rc，且字段顺序保证 surface 先于窗口释放
+        let surface = unsafe {
+            let target = wgpu::SurfaceTargetUnsafe::from_window(&*window)
+                .map_err(|e| anyhow!("gpu window handle: {e}"))?;
+            instance
+                .create_surface_unsafe(target)
+                .map_err(|e| anyhow!("gpu surface: {e}"))?
+        };
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: Some(&surface),
+            ..Default::default()
+        }))
+        .map_err(|e| anyhow!("gpu adapter: {e}"))?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&Default::default()))
+            .map_err(|e| anyhow!("gpu device: {e}"))?;
+
+        let size = window.inner_size();
+        let mut config = surface
+            .get_default_config(&adapter, size.width.max(1), size.height.max(1))
+            .ok_or_else(|| anyhow!("gpu surface unsupported"))?;
+        // 优先非 sRGB 格式：像素值与 softbuffer 路径一致，不做伽马转换
+        let caps = surface.get_capabilities(&adapter);
+        if let Some(fmt) = caps.formats.iter().find(|f| !f.is_srgb()) {
+            config.format = *fmt;
+        }
+        surface.configure(&device, &config);
+
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("overlay"),
+            entries: &[
+                texture_entry(0),
+                texture_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline = create_pipeline(&device, SHADER, &layout, config.format);
+        let blit_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("overlay frame"),
+            entries: &[texture_entry(0)],
+        });
+        let blit_pipeline = create_pipeline(&device, BLIT_SHADER, &blit_layout, config.format);
+        let params = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("overlay params"),
+            size: std::mem::size_of::<Params>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let (tb_w, tb_h) = toolbar_size(MAX_SLOTS, UiScale::default());
+        let toolbar_tex = create_texture(
+            &device,
+            tb_w as u32,
+            tb_h as u32,
+            wgpu::TextureFormat::Bgra8Unorm,
+        );
+        log::debug!("gpu overlay renderer: {:?}", adapter.get_info().name);
+        Ok(Self {
+            surface,
+            _window: window,
+            device,
+            queue,
+            config,
+            pipeline,
+            layout,
+            params,
+            toolbar_tex,
+            toolbar_drawn: None,
+            bind_group: None,
+            blit_pipeline,
+            blit_layout,
+            frame: None,
+        })
+    }
+
+    /// 上传新截图（每次显示 overlay 一次）
+    pub fn upload(&mut self, width: u32, height: u32, rgba: &[u8]) {
+        let tex = create_texture(&self.device, width, height, wgpu::TextureFormat::Rgba8Unorm);
+        write_texture(&self.queue, &tex, width, height, rgba);
+        let screenshot = tex.create_view(&Default::default());
+        let toolbar = self.toolbar_tex.create_view(&Default::default());
+        self.bind_group = Some(self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("overlay"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&screenshot),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&toolbar),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.params.as_entire_binding(),
+                },
+            ],
+        }));
+    }
+
+    /// 释放截图与合成帧纹理（overlay 隐藏时）
+    pub fn clear(&mut self) {
+        self.bind_group = None;
+        self.frame = None;
+    }
+
+    // 窗口尺寸变化时重新配置 surface
+    fn fit_surface(&mut self) {
+        let size = self._window.inner_size();
+        let (w, h) = (size.width.max(1), size.height.max(1));
+        if (w, h) != (self.config.width, self.config.height) {
+            self.config.width = w;
+            self.config.height = h;
+            self.surface.configure(&self.device, &self.config);
+        }
+    }
+
+    pub fn render(&mut self, frame: &GpuFrame, handle_half: i32) -> Result<()> {
+        if self.bind_group.is_none() {
+            return Ok(());
+        }
+        self.fit_surface();
+        let toolbar_state = (
+            frame.toolbar_slots.clone(),
+            frame.toolbar_hover,
+            frame.toolbar_focus,
+        );
+        if frame.toolbar.is_some() && self.toolbar_drawn.as_ref() != Some(&toolbar_state) {
+            // 纹理按最多位置分配，当前工具栏画在左上角（着色器按像素偏移采样）
+            let (tb_w, tb_h) = toolbar_size(MAX_SLOTS, UiScale::default());
+            let (bar_w, _) = toolbar_size(frame.toolbar_slots.len(), UiScale::default());
+            let mut pixels = vec![0u32; (tb_w * tb_h) as usize];
+            draw_toolbar(
+                &mut pixels,
+                tb_w as u32,
+                tb_h as u32,
+                0,
+                0,
+                bar_w,
+                tb_h,
+                &frame.toolbar_slots,
+                frame.toolbar_hover,
+                frame.toolbar_focus,
+                // 高对比度主题不走 GPU（见 OverlayState::redraw），这里总是默认配色
+                &Theme::default(),
+            );
+            let bytes: Vec<u8> = pixels.iter().flat_map(|p| p.to_le_bytes()).collect();
+            write_texture(
+                &self.queue,
+                &self.toolbar_tex,
+                tb_w as u32,
+                tb_h as u32,
+                &bytes,
+            );
+            self.toolbar_drawn = Some(toolbar_state);
+        }
+        let params = Params {
+            sel: frame
+                .selection
+                .map(|(x, y, w, h)| [x as f32, y as f32, w as f32, h as f32])
+                .unwrap_or_default(),
+            bar: frame
+                .toolbar
+                .map(|(x, y, w, h)| [x as f32, y as f32, w as f32, h as f32])
+                .unwrap_or_default(),
+            style: [
+                0.6,
+                handle_half as f32,
+                frame.highlight as u8 as f32,
+                border_mode(frame.border),
+            ],
+        };
+        self.queue.write_buffer(&self.params, 0, &params.as_bytes());
+        match &self.bind_group {
+            Some(bind_group) => self.draw(&self.pipeline, bind_group),
+            None => Ok(()),
+        }
+    }
+
+    /// 呈现 CPU 合成的帧（width x height 的 BGRA u32）。dirty 为上一次上传以来变化的区域，
+    /// None 或帧尺寸变化时整帧上传
+    pub fn present_frame(
+        &mut self,
+        pixels: &[u32],
+        width: u32,
+        height: u32,
+        dirty: Option<(u32, u32, u32, u32)>,
+    ) -> Result<()> {
+        self.fit_surface();
+        let mut dirty = dirty;
+        let stale = self
+            .frame
+            .as_ref()
+            .is_none_or(|(tex, _)| (tex.width(), tex.height()) != (width.max(1), height.max(1)));
+        if stale {
+            let tex = create_texture(&self.device, width, height, wgpu::TextureFormat::Bgra8Unorm);
+            let view = tex.create_view(&Default::default());
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("overlay frame"),
+                layout: &self.blit_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                }],
+            });
+            self.frame = Some((tex, bind_group));
+            dirty = None;
+        }
+        let Some((tex, bind_group)) = &self.frame else {
+            return Ok(());
+        };
+        let region = dirty.unwrap_or((0, 0, width, height));
+        write_texture_region(
+            &self.queue,
+            tex,
+            region,
+            width,
+            bytemuck::cast_slice(pixels),
+        );
+        self.draw(&self.blit_pipeline, bind_group)
+    }
+
+    // 用指定管线画满 surface 并呈现
+    fn draw(&self, pipeline: &wgpu::RenderPipeline, bind_group: &wgpu::BindGroup) -> Result<()> {
+        let output = match self.surface.get_current_texture() {
+            wgpu::CurrentSurfaceTexture::Success(t)
+            | wgpu::CurrentSurfaceTexture::Suboptimal(t) => t,
+            wgpu::CurrentSurfaceTexture::Outdated | wgpu::CurrentSurfaceTexture::Lost => {
+                self.surface.configure(&self.device, &self.config);
+                return Ok(());
+            }
+            other => return Err(anyhow!("gpu frame unavailable: {other:?}")),
+        };
+        let view = output.texture.create_view(&Default::default());
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("overlay"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                ..Default::default()
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+        self.queue.submit([encoder.finish()]);
+        self.queue.present(output);
+        Ok(())
+    }
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    fragment: &str,
+    layout: &wgpu::BindGroupLayout,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("overlay"),
+        source: wgpu::ShaderSource::Wgsl(format!("{VERTEX}{fragment}").into()),
+    });
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("overlay"),
+        bind_group_layouts: &[Some(layout)],
+        immediate_size: 0,
+    });
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("overlay"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &module,
+            entry_point: Some("vs_main"),
+            compilation_options: Default::default(),
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &module,
+            entry_point: Some("fs_main"),
+            compilation_options: Default::default(),
+            targets: &[Some(format.into())],
+        }),
+        primitive: Default::default(),
+        depth_stencil: None,
+        multisample: Default::default(),
+        multiview_mask: None,
+        cache: None,
+    })
+}
+
+fn create_texture(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("overlay"),
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    })
+}
+
+// 4 字节像素，按行紧密排列
+fn write_texture(queue: &wgpu::Queue, tex: &wgpu::Texture, width: u32, height: u32, data: &[u8]) {
+    write_texture_region(queue, tex, (0, 0, width, height), width, data);
+}
+
+// 只上传 data（每行 stride 像素的整幅图）中的 (x, y, w, h) 区域
+fn write_texture_region(
+    queue: &wgpu::Queue,
+    tex: &wgpu::Texture,
+    (x, y, w, h): (u32, u32, u32, u32),
+    stride: u32,
+    data: &[u8],
+) {
+    if w == 0 || h == 0 {
+        return;
+    }
+    queue.write_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture: tex,
+            mip_level: 0,
+            origin: wgpu::Origin3d { x, y, z: 0 },
+            aspect: wgpu::TextureAspect::All,
+        },
+        data,
+        wgpu::TexelCopyBufferLayout {
+            offset: (y as u64 * stride as u64 + x as u64) * 4,
+            bytes_per_row: Some(stride * 4),
+            rows_per_image: Some(h),
+        },
+        wgpu::Extent3d {
+            width: w,
+            height: h,
+            depth_or_array_layers: 1,
+        },
+    );
+}
@@ -1,3 +1,11 @@
+use crate::overlay::regions::Region;
+
+/// 调整大小时选区的默认最小宽高（配置 min_selection_size；屏幕更小时取屏幕尺寸）
+pub const MIN_RESIZE: u32 = 4;
+
+/// 1.0 倍 UI 缩放下手柄的命中半径（像素）
+pub const HANDLE_HIT: i32 = 5;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ResizeHandle {
     TopLeft,
@@ -10,7 +18,84 @@ pub enum ResizeHandle {
     Left,
 }
 
-pub fn hit_test_handle(cx: i32, cy: i32, x: u32, y: u32, w: u32, h: u32) -> Option<ResizeHandle> {
+impl ResizeHandle {
+    pub const ALL: [ResizeHandle; 8] = [
+        ResizeHandle::TopLeft,
+        ResizeHandle::Top,
+        ResizeHandle::TopRight,
+        ResizeHandle::Right,
+        ResizeHandle::BottomRight,
+        ResizeHandle::Bottom,
+        ResizeHandle::BottomLeft,
+        ResizeHandle::Left,
+    ];
+
+    /// 手柄拖动的边 (左, 上, 右, 下)
+    pub fn edges(self) -> (bool, bool, bool, bool) {
+        use ResizeHandle::*;
+        (
+            matches!(self, TopLeft | BottomLeft | Left),
+            matches!(self, TopLeft | Top | TopRight),
+            matches!(self, TopRight | Right | BottomRight),
+            matches!(self, BottomRight | Bottom | BottomLeft),
+        )
+    }
+}
+
+/// 拖动手柄调整选区：手柄控制的边跟随光标（光标所在像素包含在选区内），其余边不动。
+/// 结果总在 bounds 内，宽高至少 min_size（至少 1）；cursor 可以是窗口外任意坐标
+pub fn apply_resize(
+    handle: ResizeHandle,
+    cursor: (i32, i32),
+    sel: Region,
+    bounds: (u32, u32),
+    min_size: u32,
+) -> Region {
+    if bounds.0 == 0 || bounds.1 == 0 {
+        return (0, 0, 0, 0);
+    }
+    let (bw, bh) = (bounds.0 as i64, bounds.1 as i64);
+    let min_size = min_size.max(1) as i64;
+    let min_w = min_size.min(bw);
+    let min_h = min_size.min(bh);
+    // 先把原选区规整到 bounds 内，保证下面各个 clamp 的区间非空
+    let w = (sel.2 as i64).clamp(min_w, bw);
+    let h = (sel.3 as i64).clamp(min_h, bh);
+    let mut left = (sel.0 as i64).min(bw - w);
+    let mut top = (sel.1 as i64).min(bh - h);
+    let (mut right, mut bottom) = (left + w, top + h);
+    let (cx, cy) = (cursor.0 as i64, cursor.1 as i64);
+    let (moves_left, moves_top, moves_right, moves_bottom) = handle.edges();
+    if moves_left {
+        left = cx.clamp(0, right - min_w);
+    }
+    if moves_right {
+        right = (cx + 1).clamp(left + min_w, bw);
+    }
+    if moves_top {
+        top = cy.clamp(0, bottom - min_h);
+    }
+    if moves_bottom {
+        bottom = (cy + 1).clamp(top + min_h, bh);
+    }
+    (
+        left as u32,
+        top as u32,
+        (right - left) as u32,
+        (bottom - top) as u32,
+    )
+}
+
+/// radius 为命中半径（HANDLE_HIT 按 UI 缩放放大）
+pub fn hit_test_handle(
+    cx: i32,
+    cy: i32,
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+    radius: i32,
+) -> Option<ResizeHandle> {
     if w == 0 || h == 0 {
         return None;
     }
@@ -28,11 +113,112 @@ pub fn hit_test_handle(cx: i32, cy: i32, x: u32, y: u32, w: u32, h: u32) -> Opti
         (x, y + h - 1, ResizeHandle::BottomLeft),
         (x, y + h / 2, ResizeHandle::Left),
     ];
-    const R: i32 = 5;
     for (px, py, id) in points {
-        if (cx - px).abs() <= R && (cy - py).abs() <= R {
+        if (cx - px).abs() <= radius && (cy - py).abs() <= radius {
             return Some(id);
         }
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_apply_resize_follows_cursor() {
+        let sel = (10, 10, 20, 20);
+        let b = (100, 100);
+        assert_eq!(
+            apply_resize(ResizeHandle::BottomRight, (49, 39), sel, b, MIN_RESIZE),
+            (10, 10, 40, 30)
+        );
+        assert_eq!(
+            apply_resize(ResizeHandle::TopLeft, (-50, 5), sel, b, MIN_RESIZE),
+            (0, 5, 30, 25)
+        );
+        // 越过对边：停在最小尺寸，不翻转
+        assert_eq!(
+            apply_resize(ResizeHandle::Left, (90, 0), sel, b, MIN_RESIZE),
+            (26, 10, 4, 20)
+        );
+        // 贴右下边
+        assert_eq!(
+            apply_resize(
+                ResizeHandle::BottomRight,
+                (i32::MAX, i32::MAX),
+                sel,
+                b,
+                MIN_RESIZE
+            ),
+            (10, 10, 90, 90)
+        );
+        // 比最小尺寸还小的屏幕
+        assert_eq!(
+            apply_resize(
+                ResizeHandle::Right,
+                (0, 0),
+                (0, 0, 1, 1),
+                (2, 2),
+                MIN_RESIZE
+            ),
+            (0, 0, 2, 2)
+        );
+        // 配置的最小尺寸；0 按 1 处理
+        assert_eq!(
+            apply_resize(ResizeHandle::Left, (90, 0), sel, b, 16),
+            (14, 10, 16, 20)
+        );
+        assert_eq!(
+            apply_resize(ResizeHandle::Left, (90, 0), sel, b, 0),
+            (29, 10, 1, 20)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_apply_resize_stays_inside(
+            handle in prop::sample::select(ResizeHandle::ALL.to_vec()),
+            cx in any::<i32>(),
+            cy in any::<i32>(),
+            x in 0u32..5000,
+            y in 0u32..5000,
+            w in 0u32..5000,
+            h in 0u32..5000,
+            bw in 1u32..4000,
+            bh in 1u32..4000,
+        ) {
+            let (rx, ry, rw, rh) = apply_resize(handle, (cx, cy), (x, y, w, h), (bw, bh), MIN_RESIZE);
+            prop_assert!(rx + rw <= bw && ry + rh <= bh);
+            prop_assert!(rw >= MIN_RESIZE.min(bw) && rh >= MIN_RESIZE.min(bh));
+        }
+
+        #[test]
+        fn prop_apply_resize_keeps_other_edges(
+            handle in prop::sample::select(ResizeHandle::ALL.to_vec()),
+            cx in -100i32..200,
+            cy in -100i32..200,
+            x in 0u32..60,
+            y in 0u32..60,
+            w in MIN_RESIZE..40,
+            h in MIN_RESIZE..40,
+            bw in 100u32..120,
+            bh in 100u32..120,
+        ) {
+            let r = apply_resize(handle, (cx, cy), (x, y, w, h), (bw, bh), MIN_RESIZE);
+            let (l, t, rt, b) = handle.edges();
+            if !l { prop_assert_eq!(r.0, x); }
+            if !t { prop_assert_eq!(r.1, y); }
+            if !rt { prop_assert_eq!(r.0 + r.2, x + w); }
+            if !b { prop_assert_eq!(r.1 + r.3, y + h); }
+            // 光标在可行范围内时，拖动的边正好跟随光标
+            if rt && cx >= (r.0 + MIN_RESIZE) as i32 && cx < bw as i32 {
+                prop_assert_eq!(r.0 + r.2, cx as u32 + 1);
+            }
+            if l && cx >= 0 && cx <= (x + w - MIN_RESIZE) as i32 {
+                prop_assert_eq!(r.0, cx as u32);
+            }
+        }
+    }
+}
@@ -0,0 +1,339 @@
+// regions: 多选区（Ctrl + 拖拽）导出时的排版与合成，纯像素运算便于单元测试
+use serde::Deserialize;
+
+use crate::capture::Rect;
+use crate::renderer::{stitch, Renderer, StitchLayout};
+
+pub type Region = (u32, u32, u32, u32); // x, y, w, h（overlay 内坐标）
+
+/// 多选区导出方式（配置 `multi_selection_layout`）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RegionLayout {
+    /// 每个选区单独保存 / 钉图；复制等单图操作只取当前选区
+    #[default]
+    Separate,
+    /// 保持屏幕上的相对位置，合成到包围盒内（空隙透明）
+    Original,
+    /// 按 x 排序横向拼接（顶端对齐）
+    Horizontal,
+    /// 按 y 排序纵向拼接（左端对齐）
+    Vertical,
+}
+
+/// 一个已裁剪的选区（RGBA）
+pub struct Part<'a> {
+    pub region: Region,
+    pub rgba: &'a [u8],
+}
+
+/// 点是否落在选区内
+pub fn region_contains(r: Region, px: i32, py: i32) -> bool {
+    let (x, y, w, h) = r;
+    px >= x as i32 && py >= y as i32 && px < (x + w) as i32 && py < (y + h) as i32
+}
+
+/// 移动选区：左上角放到 pos，整体限制在 bounds 内（超出 bounds 的宽高先缩到 bounds）
+pub fn clamp_selection(pos: (i32, i32), size: (u32, u32), bounds: (u32, u32)) -> Region {
+    let w = size.0.min(bounds.0);
+    let h = size.1.min(bounds.1);
+    let x = (pos.0 as i64).clamp(0, (bounds.0 - w) as i64);
+    let y = (pos.1 as i64).clamp(0, (bounds.1 - h) as i64);
+    (x as u32, y as u32, w, h)
+}
+
+/// 拖出新选区：起点与光标为对角，两个角都先限制在 bounds 内（光标可能在窗口外）
+pub fn drag_region(start: (f64, f64), cursor: (f64, f64), bounds: (u32, u32)) -> Region {
+    // NaN 转换为 0
+    let clip = |v: f64, max: u32| v.clamp(0.0, max as f64) as u32;
+    let (x0, x1) = (clip(start.0, bounds.0), clip(cursor.0, bounds.0));
+    let (y0, y1) = (clip(start.1, bounds.1), clip(cursor.1, bounds.1));
+    (x0.min(x1), y0.min(y1), x0.abs_diff(x1), y0.abs_diff(y1))
+}
+
+/// 键盘微调：移动选区，或（resize）移动右 / 下边；结果限制在 bounds 内，宽高至少 1
+pub fn nudge_region(r: Region, dx: i32, dy: i32, resize: bool, bounds: (u32, u32)) -> Region {
+    let (x, y, w, h) = (r.0 as i64, r.1 as i64, r.2 as i64, r.3 as i64);
+    let (bw, bh) = (bounds.0 as i64, bounds.1 as i64);
+    let (dx, dy) = (dx as i64, dy as i64);
+    if resize {
+        let w = (w + dx).clamp(1, (bw - x).max(1));
+        let h = (h + dy).clamp(1, (bh - y).max(1));
+        (x as u32, y as u32, w as u32, h as u32)
+    } else {
+        let x = (x + dx).clamp(0, (bw - w).max(0));
+        let y = (y + dy).clamp(0, (bh - h).max(0));
+        (x as u32, y as u32, w as u32, h as u32)
+    }
+}
+
+/// 选区扩展方向（W / H / M 键）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expand {
+    /// 横向铺满，保持纵向位置与高度
+    Width,
+    /// 纵向铺满，保持横向位置与宽度
+    Height,
+    /// 整个屏幕
+    Both,
+}
+
+/// 把选区扩展到 bounds 的整宽 / 整高 / 全部
+pub fn expand_region(r: Region, expand: Expand, bounds: (u32, u32)) -> Region {
+    let (x, y, w, h) = r;
+    match expand {
+        Expand::Width => (0, y, bounds.0, h),
+        Expand::Height => (x, 0, w, bounds.1),
+        Expand::Both => (0, 0, bounds.0, bounds.1),
+    }
+}
+
+/// 从 sw x sh 的 RGBA 截图裁出选区，超出截图的部分截掉；返回 (width, height, RGBA)，
+/// 空选区或起点在截图外时为 None
+pub fn crop_region(rgba: &[u8], sw: u32, sh: u32, region: Region) -> Option<(u32, u32, Vec<u8>)> {
+    let (x, y, w, h) = region;
+    if w == 0 || h == 0 {
+        return None;
+    }
+    if x >= sw || y >= sh {
+        return None;
+    }
+    let rw = w.min(sw - x);
+    let rh = h.min(sh - y);
+    let mut out: Vec<u8> = Vec::with_capacity((rw * rh * 4) as usize);
+    for row in 0..rh {
+        let start = (((y + row) * sw) + x) * 4;
+        let end = start + rw * 4;
+        out.extend_from_slice(&rgba[start as usize..end as usize]);
+    }
+    Some((rw, rh, out))
+}
+
+/// 两个选区的交集；不相交时为 None
+pub fn intersect_region(a: Region, b: Region) -> Option<Region> {
+    let (x0, y0) = (a.0.max(b.0), a.1.max(b.1));
+    let (x1, y1) = ((a.0 + a.2).min(b.0 + b.2), (a.1 + a.3).min(b.1 + b.3));
+    (x0 < x1 && y0 < y1).then(|| (x0, y0, x1 - x0, y1 - y0))
+}
+
+/// 挖空：裁剪结果（w x h，左上角在 overlay 坐标 origin）与各 rect 的交集清为全透明
+pub fn clear_rects(rgba: &mut [u8], w: u32, h: u32, origin: (u32, u32), rects: &[Region]) {
+    for &r in rects {
+        let Some((x, y, rw, rh)) = intersect_region(r, (origin.0, origin.1, w, h)) else {
+            continue;
+        };
+        for row in (y - origin.1)..(y - origin.1 + rh) {
+            let start = ((row * w + x - origin.0) * 4) as usize;
+            rgba[start..start + (rw * 4) as usize].fill(0);
+        }
+    }
+}
+
+/// overlay 内选区 -> 屏幕坐标（origin 为截图所在显示器左上角）
+pub fn region_to_screen(origin: (i32, i32), r: Region) -> Rect {
+    Rect {
+        x: origin.0 + r.0 as i32,
+        y: origin.1 + r.1 as i32,
+        width: r.2,
+        height: r.3,
+    }
+}
+
+/// 屏幕坐标矩形 -> overlay 内选区，裁到 bounds 内；无交集时为 None
+pub fn screen_to_region(origin: (i32, i32), rect: Rect, bounds: (u32, u32)) -> Option<Region> {
+    let (x0, y0) = (rect.x - origin.0, rect.y - origin.1);
+    let (x1, y1) = (x0 + rect.width as i32, y0 + rect.height as i32);
+    let (x0, y0) = (x0.max(0), y0.max(0));
+    let (x1, y1) = (x1.min(bounds.0 as i32), y1.min(bounds.1 as i32));
+    if x1 <= x0 || y1 <= y0 {
+        return None;
+    }
+    Some((x0 as u32, y0 as u32, (x1 - x0) as u32, (y1 - y0) as u32))
+}
+
+/// 按布局合成为一张 RGBA 图像，返回 (width, height, RGBA)；
+/// Separate 不合成，返回 None。横向 / 纵向按屏幕位置排序后交给 renderer::stitch
+pub fn compose_regions(layout: RegionLayout, parts: &[Part]) -> Option<(u32, u32, Vec<u8>)> {
+    if parts.is_empty() {
+        return None;
+    }
+    let mut order: Vec<&Part> = parts.iter().collect();
+    let stitch_layout = match layout {
+        RegionLayout::Separate => return None,
+        RegionLayout::Original => return compose_original(&order),
+        RegionLayout::Horizontal => {
+            order.sort_by_key(|p| (p.region.0, p.region.1));
+            StitchLayout::Horizontal
+        }
+        RegionLayout::Vertical => {
+            order.sort_by_key(|p| (p.region.1, p.region.0));
+            StitchLayout::Vertical
+        }
+    };
+    let images: Vec<(u32, u32, &[u8])> = order
+        .iter()
+        .map(|p| (p.region.2, p.region.3, p.rgba))
+        .collect();
+    stitch(stitch_layout, &images).ok()
+}
+
+// 保持屏幕上的相对位置，合成到包围盒内（空隙透明）
+fn compose_original(parts: &[&Part]) -> Option<(u32, u32, Vec<u8>)> {
+    let x0 = parts.iter().map(|p| p.region.0).min()?;
+    let y0 = parts.iter().map(|p| p.region.1).min()?;
+    let x1 = parts.iter().map(|p| p.region.0 + p.region.2).max()?;
+    let y1 = parts.iter().map(|p| p.region.1 + p.region.3).max()?;
+    let mut canvas = Renderer::new(x1 - x0, y1 - y0).ok()?;
+    for p in parts {
+        let (x, y, w, h) = p.region;
+        canvas.blit_rgba(x - x0, y - y0, w, h, p.rgba);
+    }
+    Some((x1 - x0, y1 - y0, canvas.into_rgba()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn solid(w: u32, h: u32, v: u8) -> Vec<u8> {
+        vec![v; (w * h * 4) as usize]
+    }
+
+    #[test]
+    fn test_clear_rects_masks_intersection() {
+        assert_eq!(
+            intersect_region((0, 0, 4, 4), (2, 2, 4, 4)),
+            Some((2, 2, 2, 2))
+        );
+        assert_eq!(intersect_region((0, 0, 2, 2), (2, 0, 2, 2)), None);
+        // 裁剪区域 (10, 10, 3, 2)；挖空矩形一部分超出裁剪区域，另一个完全在外
+        let mut rgba = solid(3, 2, 255);
+        clear_rects(&mut rgba, 3, 2, (10, 10), &[(11, 9, 5, 2), (0, 0, 5, 5)]);
+        let alpha: Vec<u8> = rgba.chunks(4).map(|p| p[3]).collect();
+        assert_eq!(alpha, [255, 0, 0, 255, 255, 255]);
+        assert_eq!(&rgba[4..8], &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_compose_horizontal_sorted_and_padded() {
+        let a = solid(2, 1, 1);
+        let b = solid(1, 2, 2);
+        // b 在屏幕左侧：横向拼接时排在前面
+        let parts = [
+            Part {
+                region: (10, 0, 2, 1),
+                rgba: &a,
+            },
+            Part {
+                region: (0, 5, 1, 2),
+                rgba: &b,
+            },
+        ];
+        let (w, h, out) = compose_regions(RegionLayout::Horizontal, &parts).unwrap();
+        assert_eq!((w, h), (3, 2));
+        let px = |x: usize, y: usize| out[(y * 3 + x) * 4];
+        assert_eq!((px(0, 0), px(1, 0), px(2, 0)), (2, 1, 1));
+        assert_eq!((px(0, 1), px(1, 1)), (2, 0)); // a 较矮，下方透明
+        assert!(compose_regions(RegionLayout::Separate, &parts).is_none());
+    }
+
+    #[test]
+    fn test_compose_original_keeps_gaps() {
+        let a = solid(1, 1, 7);
+        let b = solid(1, 1, 9);
+        let parts = [
+            Part {
+                region: (4, 4, 1, 1),
+                rgba: &a,
+            },
+            Part {
+                region: (6, 5, 1, 1),
+                rgba: &b,
+            },
+        ];
+        let (w, h, out) = compose_regions(RegionLayout::Original, &parts).unwrap();
+        assert_eq!((w, h), (3, 2));
+        assert_eq!(out[0], 7);
+        assert_eq!(out[(3 + 2) * 4], 9);
+        assert_eq!(out[4], 0);
+        let (w, h, _) = compose_regions(RegionLayout::Vertical, &parts).unwrap();
+        assert_eq!((w, h), (1, 2));
+    }
+
+    #[test]
+    fn test_clamp_and_drag_at_edges() {
+        assert_eq!(
+            clamp_selection((-5, 95), (20, 20), (100, 100)),
+            (0, 80, 20, 20)
+        );
+        assert_eq!(
+            clamp_selection((3, 3), (200, 50), (100, 100)),
+            (0, 3, 100, 50)
+        );
+        assert_eq!(
+            drag_region((90.0, 10.0), (150.0, -20.0), (100, 100)),
+            (90, 0, 10, 10)
+        );
+        assert_eq!(
+            drag_region((5.0, 5.0), (f64::NAN, 5.0), (100, 100)),
+            (0, 5, 5, 0)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_clamp_selection_stays_inside(
+            x in any::<i32>(),
+            y in any::<i32>(),
+            w in 0u32..5000,
+            h in 0u32..5000,
+            bw in 1u32..4000,
+            bh in 1u32..4000,
+        ) {
+            let r = clamp_selection((x, y), (w, h), (bw, bh));
+            prop_assert!(r.0 + r.2 <= bw && r.1 + r.3 <= bh);
+            prop_assert_eq!((r.2, r.3), (w.min(bw), h.min(bh)));
+            // 已在范围内的选区不再移动
+            prop_assert_eq!(clamp_selection((r.0 as i32, r.1 as i32), (r.2, r.3), (bw, bh)), r);
+        }
+
+        #[test]
+        fn prop_drag_region_stays_inside(
+            sx in -10_000.0f64..10_000.0,
+            sy in -10_000.0f64..10_000.0,
+            cx in -10_000.0f64..10_000.0,
+            cy in -10_000.0f64..10_000.0,
+            bw in 1u32..64,
+            bh in 1u32..64,
+        ) {
+            let r = drag_region((sx, sy), (cx, cy), (bw, bh));
+            prop_assert!(r.0 + r.2 <= bw && r.1 + r.3 <= bh);
+            // 交换起点与光标结果相同
+            prop_assert_eq!(drag_region((cx, cy), (sx, sy), (bw, bh)), r);
+        }
+    }
+
+    #[test]
+    fn test_nudge_region_clamps_to_bounds() {
+        let r = (10, 10, 20, 20);
+        assert_eq!(nudge_region(r, 1, -1, false, (100, 100)), (11, 9, 20, 20));
+        assert_eq!(
+            nudge_region(r, -50, 500, false, (100, 100)),
+            (0, 80, 20, 20)
+        );
+        assert_eq!(nudge_region(r, 10, -10, true, (100, 100)), (10, 10, 30, 10));
+        assert_eq!(
+            nudge_region(r, 500, -500, true, (100, 100)),
+            (10, 10, 90, 1)
+        );
+    }
+
+    #[test]
+    fn test_expand_region() {
+        let r = (10, 20, 30, 40);
+        assert_eq!(expand_region(r, Expand::Width, (100, 80)), (0, 20, 100, 40));
+        assert_eq!(expand_region(r, Expand::Height, (100, 80)), (10, 0, 30, 80));
+        assert_eq!(expand_region(r, Expand::Both, (100, 80)), (0, 0, 100, 80));
+    }
+}
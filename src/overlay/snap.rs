@@ -0,0 +1,234 @@
+// snap: 选区边缘吸附到检测到的矩形（顶层窗口外框）的边线
+//
+// 拖出 / 调整 / 移动选区时，距离某条边线不超过 SNAP_DISTANCE 像素的选区边对齐到该线，
+// 便于精确截取窗口或面板；按住 Alt 时不吸附。边线为 overlay 坐标，右 / 下边线是矩形外侧
+// （x + w / y + h），与 Region 的右 / 下边一致。
+//
+// 配置 selection_grid 时改为吸附到固定网格（grid_edges / grid_move），便于文档截图保持统一尺寸；
+// 按住 Ctrl 时暂时不吸附网格。
+use crate::overlay::regions::Region;
+
+/// 吸附阈值（像素）
+pub const SNAP_DISTANCE: u32 = 8;
+
+/// 候选的竖线 (xs) 与横线 (ys)，已排序去重
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapLines {
+    xs: Vec<u32>,
+    ys: Vec<u32>,
+}
+
+impl SnapLines {
+    /// rects 为桌面坐标 (x, y, w, h)，按 origin（overlay 左上角的桌面坐标）换算；
+    /// 落在 bounds 之外的边线丢弃
+    pub fn from_rects(
+        rects: &[(i32, i32, u32, u32)],
+        origin: (i32, i32),
+        bounds: (u32, u32),
+    ) -> Self {
+        let mut xs = Vec::new();
+        let mut ys = Vec::new();
+        let keep = |v: i64, max: u32| (0..=max as i64).contains(&v).then_some(v as u32);
+        for &(x, y, w, h) in rects {
+            let left = x as i64 - origin.0 as i64;
+            let top = y as i64 - origin.1 as i64;
+            xs.extend(keep(left, bounds.0));
+            xs.extend(keep(left + w as i64, bounds.0));
+            ys.extend(keep(top, bounds.1));
+            ys.extend(keep(top + h as i64, bounds.1));
+        }
+        for lines in [&mut xs, &mut ys] {
+            lines.sort_unstable();
+            lines.dedup();
+        }
+        Self { xs, ys }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.xs.is_empty() && self.ys.is_empty()
+    }
+
+    /// 吸附 edges (左, 上, 右, 下) 指定的边，其余边不动；宽高至少 1
+    pub fn snap_edges(&self, r: Region, edges: (bool, bool, bool, bool)) -> Region {
+        let (mut left, mut top) = (r.0, r.1);
+        let (mut right, mut bottom) = (r.0 + r.2, r.1 + r.3);
+        let (snap_left, snap_top, snap_right, snap_bottom) = edges;
+        if snap_left {
+            left = nearest(&self.xs, left)
+                .filter(|&x| x < right)
+                .unwrap_or(left);
+        }
+        if snap_right {
+            right = nearest(&self.xs, right)
+                .filter(|&x| x > left)
+                .unwrap_or(right);
+        }
+        if snap_top {
+            top = nearest(&self.ys, top)
+                .filter(|&y| y < bottom)
+                .unwrap_or(top);
+        }
+        if snap_bottom {
+            bottom = nearest(&self.ys, bottom)
+                .filter(|&y| y > top)
+                .unwrap_or(bottom);
+        }
+        (left, top, right - left, bottom - top)
+    }
+
+    /// 移动选区：每个方向上两条边中离边线更近的一条吸附，宽高不变，结果限制在 bounds 内
+    pub fn snap_move(&self, r: Region, bounds: (u32, u32)) -> Region {
+        let (x, y, w, h) = r;
+        let x = shift(&self.xs, x, w).clamp(0, bounds.0.saturating_sub(w) as i64);
+        let y = shift(&self.ys, y, h).clamp(0, bounds.1.saturating_sub(h) as i64);
+        (x as u32, y as u32, w, h)
+    }
+}
+
+// 最近的网格线
+fn round_to(v: u32, grid: u32) -> u32 {
+    (v + grid / 2) / grid * grid
+}
+
+/// 网格吸附：edges (左, 上, 右, 下) 指定的边移到最近的网格线（不超出 bounds），其余边不动；
+/// 某个方向吸附后为空时该方向保持原样。grid < 2 时原样返回
+pub fn grid_edges(
+    r: Region,
+    edges: (bool, bool, bool, bool),
+    grid: u32,
+    bounds: (u32, u32),
+) -> Region {
+    if grid < 2 {
+        return r;
+    }
+    let (snap_left, snap_top, snap_right, snap_bottom) = edges;
+    let axis = |start: u32, len: u32, lo: bool, hi: bool, max: u32| {
+        let end = start + len;
+        let start2 = if lo {
+            round_to(start, grid).min(max)
+        } else {
+            start
+        };
+        let end2 = if hi {
+            round_to(end, grid).min(max)
+        } else {
+            end
+        };
+        if end2 > start2 {
+            (start2, end2 - start2)
+        } else {
+            (start, len)
+        }
+    };
+    let (x, w) = axis(r.0, r.2, snap_left, snap_right, bounds.0);
+    let (y, h) = axis(r.1, r.3, snap_top, snap_bottom, bounds.1);
+    (x, y, w, h)
+}
+
+/// 移动选区时左上角吸附到网格，宽高不变，结果限制在 bounds 内
+pub fn grid_move(r: Region, grid: u32, bounds: (u32, u32)) -> Region {
+    if grid < 2 {
+        return r;
+    }
+    let (x, y, w, h) = r;
+    let x = round_to(x, grid).min(bounds.0.saturating_sub(w));
+    let y = round_to(y, grid).min(bounds.1.saturating_sub(h));
+    (x, y, w, h)
+}
+
+// 阈值内最近的边线
+fn nearest(lines: &[u32], v: u32) -> Option<u32> {
+    lines
+        .iter()
+        .copied()
+        .filter(|&line| line.abs_diff(v) <= SNAP_DISTANCE)
+        .min_by_key(|&line| line.abs_diff(v))
+}
+
+// 区间 [start, start + len) 吸附后的新起点（可能为负，由调用方限制）
+fn shift(lines: &[u32], start: u32, len: u32) -> i64 {
+    let end = start + len;
+    let by_start = nearest(lines, start).map(|l| l as i64 - start as i64);
+    let by_end = nearest(lines, end).map(|l| l as i64 - end as i64);
+    let delta = match (by_start, by_end) {
+        (Some(a), Some(b)) => {
+            if a.abs() <= b.abs() {
+                a
+            } else {
+                b
+            }
+        }
+        (a, b) => a.or(b).unwrap_or(0),
+    };
+    start as i64 + delta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snap_edges_within_threshold() {
+        // 窗口 (100, 50) 400x300，overlay 原点在桌面 (0, 0)
+        let lines = SnapLines::from_rects(&[(100, 50, 400, 300)], (0, 0), (1920, 1080));
+        assert!(!lines.is_empty());
+        let snapped = lines.snap_edges((95, 55, 410, 200), (true, true, true, true));
+        assert_eq!(snapped, (100, 50, 400, 205));
+        // 只吸附指定的边；超出阈值的不动
+        assert_eq!(
+            lines.snap_edges((95, 55, 410, 200), (false, false, true, false)),
+            (95, 55, 405, 200)
+        );
+        assert_eq!(
+            lines.snap_edges((80, 20, 100, 100), (true, true, true, true)),
+            (80, 20, 100, 100)
+        );
+        // 吸附后不会出现空选区
+        assert_eq!(
+            lines.snap_edges((98, 60, 3, 10), (true, false, true, false)),
+            (100, 60, 1, 10)
+        );
+    }
+
+    #[test]
+    fn test_snap_move_keeps_size() {
+        // 副屏原点 (1920, 0)：桌面坐标换算到 overlay 坐标，屏外的边线丢弃
+        let lines = SnapLines::from_rects(&[(1900, 100, 500, 300)], (1920, 0), (1920, 1080));
+        assert_eq!(
+            lines
+                .snap_edges((3, 0, 50, 50), (true, false, false, false))
+                .0,
+            3
+        );
+        assert_eq!(
+            lines.snap_move((472, 96, 10, 10), (1920, 1080)),
+            (470, 100, 10, 10)
+        );
+        // 右边更近时按右边对齐
+        assert_eq!(lines.snap_move((100, 200, 378, 50), (1920, 1080)).0, 102);
+        assert_eq!(
+            SnapLines::default().snap_move((5, 5, 10, 10), (100, 100)),
+            (5, 5, 10, 10)
+        );
+    }
+
+    #[test]
+    fn test_grid_snapping() {
+        let all = (true, true, true, true);
+        assert_eq!(
+            grid_edges((3, 13, 30, 20), all, 8, (100, 100)),
+            (0, 16, 32, 16)
+        );
+        // 只吸附拖动的边；吸附后为空的方向保持原样
+        assert_eq!(
+            grid_edges((3, 13, 30, 20), (false, false, true, false), 8, (100, 100)),
+            (3, 13, 29, 20)
+        );
+        assert_eq!(grid_edges((9, 9, 2, 2), all, 8, (100, 100)), (9, 9, 2, 2));
+        // 网格线超出屏幕时停在屏幕边缘
+        assert_eq!(grid_edges((90, 0, 4, 8), all, 8, (94, 100)), (88, 0, 6, 8));
+        assert_eq!(grid_edges((3, 3, 5, 5), all, 1, (100, 100)), (3, 3, 5, 5));
+        assert_eq!(grid_move((13, 4, 10, 10), 8, (100, 100)), (16, 8, 10, 10));
+        assert_eq!(grid_move((93, 0, 10, 10), 8, (100, 100)), (90, 0, 10, 10));
+    }
+}
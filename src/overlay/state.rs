@@ -1,8 +1,10 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
+use muda::{Menu, MenuId, MenuItem};
 use softbuffer::{Context, Surface};
 use std::num::NonZeroU32;
+use std::rc::Weak;
 use winit::{
-    event::{ElementState, KeyEvent, MouseButton, WindowEvent},
+    event::{ElementState, KeyEvent, MouseButton, Touch, TouchPhase, WindowEvent},
     event_loop::ActiveEventLoop,
     keyboard::{KeyCode, PhysicalKey},
     platform::windows::WindowAttributesExtWindows,
@@ -12,21 +14,156 @@ use winit::{
     },
 };
 
-use crate::overlay::drawing::draw_handle;
-use crate::overlay::handles::{hit_test_handle, ResizeHandle};
-use crate::overlay::toolbar::{compute_toolbar_rect, draw_toolbar, hit_test_toolbar_button};
+use crate::burst::BurstFrame;
+use crate::capture::{encode_png, Rect};
+use crate::filters::{pixelate, Adjustments, Filter};
+use crate::overlay::adjust::{
+    adjust_panel_rect, draw_adjust_panel, hit_test_adjust, slider_value, AdjustRow,
+};
+use crate::overlay::annotate::{
+    apply_strokes_rgba, draw_strokes, draw_style_bar, hit_test_style_bar, pressure_level,
+    style_bar_rect, translate_strokes, AnnotationLayers, AnnotationStyle, Stroke,
+};
+use crate::overlay::dirty::{damage, overlay_bounds, DirtyRect};
+use crate::overlay::drawing::{
+    draw_guides, draw_handle, fill_checker, luma, stroke_rect, BorderStyle, SelectionGuides,
+};
+#[cfg(feature = "gpu")]
+use crate::overlay::gpu::{GpuFrame, GpuRenderer};
+use crate::overlay::handles::{
+    apply_resize, hit_test_handle, ResizeHandle, HANDLE_HIT, MIN_RESIZE,
+};
+use crate::overlay::regions::{
+    clamp_selection, clear_rects, compose_regions, crop_region, drag_region, expand_region,
+    intersect_region, nudge_region, region_contains, region_to_screen, screen_to_region, Expand,
+    Part, Region, RegionLayout,
+};
+use crate::overlay::snap::{grid_edges, grid_move, SnapLines};
+use crate::overlay::strip::{
+    draw_strip, hit_test_strip, strip_rects, thumb_width, thumbnail, Thumb,
+};
+use crate::overlay::theme::Theme;
+use crate::overlay::toolbar::{
+    compute_toolbar_rect_in, draw_toolbar, hit_test_toolbar_button, max_slots_for_width,
+    next_focus, toolbar_bounds, Slot, ToolButton, ToolbarLayout, ToolbarSpec,
+};
+use crate::overlay::trim::trim_region;
+use crate::postprocess::ExportScale;
+use crate::project::{Manifest, Project};
+use crate::touch;
+use crate::window_surface::{SharedWindow, SurfaceWindow};
 
-// OverlayAction: 外部事件结果（当前仍只返回 None；按钮交互未来扩展）
+// OverlayAction: 外部事件结果；选区像素以 RGBA 交给主循环，PNG 编码由后台队列完成（见 encode.rs）
 pub enum OverlayAction {
     None,
     Canceled,
+    /// refreshable：像素与屏幕原样一致（无编辑、非拼合），贴图记录来源区域以便“刷新”
     PasteSelection {
-        png: Vec<u8>,
+        rgba: Vec<u8>,
         width: u32,
         height: u32,
         screen_x: i32,
         screen_y: i32,
+        refreshable: bool,
+    },
+    /// 保存：主循环提交后台编码并写文件；layers 为 Some 时另写标注 sidecar
+    SaveSelection {
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+        layers: Option<AnnotationLayers>,
+    },
+    /// 已写入剪贴板（overlay 已关闭）；主循环按需编码并触发 copy hook
+    Copied {
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+    },
+    /// 复制为 data URI：主循环后台编码 PNG 后以文本写入剪贴板（overlay 已关闭）
+    CopyDataUri {
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+    },
+    /// 用编辑器打开：主循环后台编码后写入临时文件并启动编辑器（overlay 已关闭）
+    OpenWith {
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+    },
+    /// 分享：主循环后台编码后写入临时文件，交给分享面板或邮件（overlay 已关闭）
+    Share {
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+    },
+    /// 上传：主循环后台编码后上传到第 destination 个 WebDAV 目标（overlay 已关闭）
+    Upload {
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+        destination: usize,
+    },
+    /// 识别选区二维码（overlay 已关闭）
+    DecodeQr {
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+    },
+    /// 统计选区图像信息（overlay 已关闭）
+    ImageInfo {
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+    },
+    /// OCR 检测选区内的敏感信息（overlay 保持打开，结果经 set_redactions 回填）
+    DetectSensitive {
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+        origin: (u32, u32), // 选区在 overlay 内的左上角
+        session: u64,       // 发起检测时的截图，回填时据此丢弃过期结果
     },
+    /// 多选区分别导出（RegionLayout::Separate）：依次处理每个动作
+    Batch(Vec<OverlayAction>),
+    /// 拾取模式下确认的选区（屏幕坐标，overlay 已关闭；不产生图像）
+    RegionPicked(Rect),
+    /// 保存为 .snip 工程（overlay 保持打开）；主循环补上截图来源后在后台写文件
+    SaveProject(Box<Project>),
+}
+
+/// 工具栏“更多”溢出菜单条目 id（由 App 统一解析）；每个按钮一个条目，
+/// 另有上传目标选择菜单的条目（按目标顺序）
+pub struct OverlayMenuIds {
+    items: Vec<(MenuId, ToolButton)>,
+    uploads: Vec<MenuId>,
+}
+
+impl OverlayMenuIds {
+    pub fn new(items: Vec<(MenuId, ToolButton)>) -> Self {
+        Self {
+            items,
+            uploads: Vec::new(),
+        }
+    }
+
+    pub fn resolve(&self, id: &MenuId) -> Option<ToolButton> {
+        self.items.iter().find(|(i, _)| i == id).map(|&(_, b)| b)
+    }
+
+    /// 上传目标选择菜单中被选中的目标序号
+    pub fn resolve_upload(&self, id: &MenuId) -> Option<usize> {
+        self.uploads.iter().position(|i| i == id)
+    }
+}
+
+const REDACT_BLOCK: u32 = 8; // 敏感信息马赛克块大小
+
+// 跨次截图复用的缓冲：只保留容量，不保留内容语义
+#[derive(Default)]
+struct BufferPool {
+    rgba: Vec<u8>,
+    dim: Vec<u32>,
 }
 
 // OverlayMode: 内部状态机
@@ -40,23 +177,108 @@ pub enum OverlayMode {
     Annotating,
 }
 
+// 稳定模式（Idle / IdleWithSelection / Annotating）的栈，Esc 逐级返回；
+// 拖动 / 移动 / 缩放是临时模式，不入栈，结束后 settle 回稳定模式
+#[derive(Debug)]
+struct ModeStack(Vec<OverlayMode>);
+
+impl ModeStack {
+    fn new() -> Self {
+        Self(vec![OverlayMode::Idle])
+    }
+
+    // 已在栈中时回退到该层（丢弃其上的模式），否则压栈
+    fn settle(&mut self, mode: OverlayMode) {
+        match self.0.iter().position(|&m| m == mode) {
+            Some(i) => self.0.truncate(i + 1),
+            None => self.0.push(mode),
+        }
+    }
+
+    // 弹出当前模式，返回上一层；栈空（已在 Idle）时返回 None，表示关闭 overlay
+    fn back(&mut self) -> Option<OverlayMode> {
+        self.0.pop();
+        self.0.last().copied()
+    }
+}
+
 // OverlayState: 全屏覆盖层，基于预先截取的原始 RGBA 图像进行交互式选区
 pub struct OverlayState {
-    pub window: &'static Window,
-    context: Context<&'static Window>,
-    surface: Surface<&'static Window, &'static Window>,
+    pub window: SharedWindow,
+    // 启用 GPU 渲染器时为 None：每个窗口只有一个呈现者
+    surface: Option<Surface<SharedWindow, SharedWindow>>,
+    #[cfg(feature = "gpu")]
+    context: Context<SharedWindow>, // GPU 渲染器出错回退时据此重建 surface
+    #[cfg(not(feature = "gpu"))]
+    _context: Context<SharedWindow>,
     pub visible: bool,
+    // 每次 show_with_image 递增：丢弃上一次截图的后台结果
+    session: u64,
     pub screenshot: Option<(u32, u32, Vec<u8>)>, // 原始 RGBA
     origin: (i32, i32),                          // 截图对应显示器原点
+    work_areas: Vec<(i32, i32, u32, u32)>,       // 各显示器工作区（桌面坐标），工具栏避开任务栏
     dim_cache: Option<Vec<u32>>,                 // 变暗 BGRA 缓存
+    pool: BufferPool,                            // 隐藏后保留的缓冲（复用容量）
+    low_memory: bool,                            // true: hide 时释放全部缓冲
+    last_bounds: Option<DirtyRect>,              // 上一帧选区 + 工具栏包围盒
+    full_redraw: bool,                           // 下一帧强制整帧重绘（新截图）
+    #[cfg(feature = "gpu")]
+    gpu: Option<GpuRenderer>, // 可用时替代 softbuffer 呈现
+    #[cfg(feature = "gpu")]
+    gpu_back: Vec<u32>, // GPU 呈现时 CPU 合成的后备缓冲
     drag_start: Option<(f64, f64)>,
     last_cursor: (f64, f64),
-    pub selection: Option<(u32, u32, u32, u32)>, // x,y,w,h
+    pub selection: Option<(u32, u32, u32, u32)>, // x,y,w,h（当前活动选区）
+    others: Vec<Region>,                         // Ctrl + 拖拽追加的其它选区
+    ctrl_down: bool,
+    shift_down: bool,
+    alt_down: bool,               // 按住 Alt 时不吸附
+    grid: Option<u32>,            // 网格吸附（像素；按住 Ctrl 时暂停）
+    min_size: u32,                // 调整大小时的最小宽高
+    snap_lines: SnapLines,        // 顶层窗口外框的边线（overlay 坐标），选区边缘吸附
+    guides: SelectionGuides,      // 选区内构图辅助线（G 键切换）
+    live: bool,                   // 实时选区：半透明层盖在实时桌面上，确认时才截图
+    live_pins: Vec<Weak<Window>>, // 实时选区确认截图时需临时隐藏的贴图
+    layout: RegionLayout,         // 多选区导出方式
+    auto_trim: bool,              // 导出前自动裁掉纯色边
+    corner_radius: u32,           // 导出圆角半径（像素，0 为直角）
+    export_scale: ExportScale,    // 保存 / 复制 / 分享前的缩放（钉图不缩放）
+    annotation_sidecar: bool,     // 保存有标注的选区时另写底图 + 笔画 JSON
+    pick_only: bool,              // 拾取模式：导出按钮 / Enter 只确认选区坐标
+    // 按住热键预览：顶层窗口矩形（桌面坐标，Z 序），Some 时选区跟随鼠标下的窗口
+    detect_windows: Option<Vec<(i32, i32, u32, u32)>>,
     move_offset: Option<(i32, i32)>,
     mode: OverlayMode,
+    mode_stack: ModeStack,
     resize_handle: Option<ResizeHandle>,
     toolbar_rect: Option<(i32, i32, i32, i32)>, // 缓存当前工具栏矩形（屏幕内坐标）
     toolbar_hover: Option<usize>,               // 当前悬停按钮
+    toolbar_focus: Option<usize>,               // 键盘焦点按钮（Tab / 方向键）
+    adjust: Adjustments,                        // 导出前应用的图像调整
+    adjust_open: bool,                          // “调整”按钮展开调整面板
+    adjust_rect: Option<(i32, i32, i32, i32)>,  // 调整面板矩形（随工具栏计算）
+    adjust_drag: Option<AdjustRow>,             // 正在拖动的滑块
+    redactions: Vec<(Region, bool)>,            // 检测到的敏感区域（overlay 坐标）与是否已打码
+    excluded: Vec<Region>,                      // Shift+Alt+拖出的挖空区域（导出为透明）
+    exclude_start: Option<(f64, f64)>,          // 拖出挖空区域的起点（拖出的是最后一项）
+    strokes: Vec<Stroke>,                       // 标注笔画（overlay 坐标），导出时画进选区
+    stroking: bool,                             // 左键按住中，光标轨迹追加到最后一笔
+    pen_pressure: Option<f64>,                  // 触摸 / 触控笔按下期间最近一次的压力
+    style: AnnotationStyle,                     // 当前画笔样式（跨截图保留，隐藏时持久化）
+    saved_style: AnnotationStyle,               // 已写入 annotation.json 的样式（未变化时不重写）
+    theme: Theme,                               // 配色与线宽（默认 / 高对比度）
+    style_rect: Option<(i32, i32, i32, i32)>,   // 标注时工具栏第二行（样式栏）矩形
+    toolbar_spec: ToolbarSpec,                  // 配置的按钮集合
+    toolbar_layout: ToolbarLayout,              // 本帧工具栏位置与溢出按钮（随选区宽度变化）
+    more_menu: Menu,                            // 工具栏“更多”溢出菜单（弹出前按溢出按钮重排）
+    more_items: Vec<(ToolButton, MenuItem)>,
+    pub more_ids: OverlayMenuIds,
+    upload_menu: Menu, // 多个 WebDAV 目标时“上传”弹出的目标选择菜单
+    upload_items: Vec<MenuItem>,
+    burst: Vec<Vec<u8>>, // 连拍的各帧 RGBA；当前帧在 screenshot 中，此处对应位置为空
+    burst_index: usize,
+    burst_thumbs: Vec<Thumb>,               // 连拍各帧缩略图（顶部缩略图条）
+    burst_strip: Vec<(i32, i32, i32, i32)>, // 各缩略图在 overlay 内的矩形
 }
 
 impl OverlayState {
@@ -75,31 +297,112 @@ impl OverlayState {
             .with_title("Snip Overlay")
             .with_inner_size(size) // 物理像素避免 DPI 放大二次缩放
             .with_skip_taskbar(true);
-        let window = active.create_window(attrs)?;
-        let window: &'static Window = Box::leak(Box::new(window));
-
-        // 禁用窗口淡入淡出动画，提升显隐响应（Windows）
-        crate::windows_util::disable_window_transitions(window);
-
-        let context = Context::new(window).map_err(|e| anyhow!("overlay ctx: {e}"))?;
-        let surface =
-            Surface::new(&context, window).map_err(|e| anyhow!("overlay surface: {e}"))?;
-        Ok(Self {
+        let SurfaceWindow {
             window,
+            surface,
             context,
+        } = SurfaceWindow::create(active, attrs, "overlay")?;
+        // 溢出菜单：选中项经 MenuEvent 回到主循环（见 App::handle_menu）
+        let more_menu = Menu::new();
+        let more_items: Vec<(ToolButton, MenuItem)> = ToolButton::ALL
+            .into_iter()
+            .map(|b| (b, MenuItem::new(b.label(), true, None)))
+            .collect();
+        let more_ids = OverlayMenuIds::new(
+            more_items
+                .iter()
+                .map(|(b, item)| (item.id().clone(), *b))
+                .collect(),
+        );
+        let toolbar_spec = ToolbarSpec::default();
+        let toolbar_layout = toolbar_spec.layout(usize::MAX);
+        let style = crate::overlay::annotate::load_style();
+        #[cfg(feature = "gpu")]
+        let gpu = match GpuRenderer::new(window.clone()) {
+            Ok(r) => Some(r),
+            Err(e) => {
+                log::warn!("gpu overlay unavailable, using softbuffer: {e}");
+                None
+            }
+        };
+        // GPU 渲染器接管呈现时不保留 softbuffer surface
+        #[cfg(feature = "gpu")]
+        let surface = gpu.is_none().then_some(surface);
+        #[cfg(not(feature = "gpu"))]
+        let surface = Some(surface);
+        Ok(Self {
+            window,
             surface,
+            #[cfg(feature = "gpu")]
+            context,
+            #[cfg(not(feature = "gpu"))]
+            _context: context,
             visible: false,
+            session: 0,
             screenshot: None,
             origin: (0, 0),
+            work_areas: Vec::new(),
             dim_cache: None,
+            pool: BufferPool::default(),
+            low_memory: false,
+            last_bounds: None,
+            full_redraw: true,
+            #[cfg(feature = "gpu")]
+            gpu,
+            #[cfg(feature = "gpu")]
+            gpu_back: Vec::new(),
             drag_start: None,
             last_cursor: (0.0, 0.0),
             selection: None,
+            others: Vec::new(),
+            ctrl_down: false,
+            shift_down: false,
+            alt_down: false,
+            grid: None,
+            min_size: MIN_RESIZE,
+            snap_lines: SnapLines::default(),
+            guides: SelectionGuides::Off,
+            live: false,
+            live_pins: Vec::new(),
+            layout: RegionLayout::default(),
+            auto_trim: false,
+            corner_radius: 0,
+            export_scale: ExportScale::default(),
+            annotation_sidecar: false,
+            pick_only: false,
+            detect_windows: None,
             move_offset: None,
             mode: OverlayMode::Idle,
+            mode_stack: ModeStack::new(),
             resize_handle: None,
             toolbar_rect: None,
             toolbar_hover: None,
+            toolbar_focus: None,
+            adjust: Adjustments::default(),
+            adjust_open: false,
+            adjust_rect: None,
+            adjust_drag: None,
+            redactions: Vec::new(),
+            excluded: Vec::new(),
+            exclude_start: None,
+            strokes: Vec::new(),
+            stroking: false,
+            pen_pressure: None,
+            style,
+            saved_style: style,
+            theme: Theme::default(),
+            style_rect: None,
+            toolbar_spec,
+            toolbar_layout,
+            more_menu,
+            more_items,
+            more_ids,
+            upload_menu: Menu::new(),
+            upload_items: Vec::new(),
+            burst: Vec::new(),
+            burst_index: 0,
+            burst_thumbs: Vec::new(),
+            burst_strip: Vec::new(),
         })
     }
 
@@ -111,15 +414,29 @@ impl OverlayState {
         origin: (i32, i32),
     ) -> Result<()> {
         self.screenshot = Some((w, h, pixels));
+        self.session += 1;
         self.origin = origin;
+        self.work_areas = crate::windows_util::monitor_work_areas();
+        self.snap_lines =
+            SnapLines::from_rects(&crate::windows_util::top_window_rects(), origin, (w, h));
         self.selection = None;
+        self.others.clear();
         self.drag_start = None;
+        self.reset_edits();
         self.visible = true;
+        self.mode_stack = ModeStack::new();
         self.mode = OverlayMode::Idle;
         self.window.set_visible(true);
         self.window
             .set_outer_position(winit::dpi::PhysicalPosition::new(origin.0, origin.1));
+        #[cfg(feature = "gpu")]
+        if let Some(gpu) = &mut self.gpu {
+            if let Some((w, h, buf)) = &self.screenshot {
+                gpu.upload(*w, *h, buf);
+            }
+        }
         self.build_caches();
+        self.full_redraw = true;
         self.window.request_redraw();
         self.window.focus_window();
         Ok(())
@@ -127,21 +444,477 @@ impl OverlayState {
 
     pub fn hide(&mut self) {
         self.visible = false;
+        if self.live {
+            self.live = false;
+            crate::windows_util::set_live_overlay(&self.window, false);
+        }
+        self.live_pins.clear();
+        self.pick_only = false;
+        self.detect_windows = None;
         self.window.set_visible(false);
-        // 释放截图与缓存，避免在取消后仍长期占用显著内存（全屏 RGBA + 缓存数十 MB）
-        // 重新显示时会通过 show_with_image 重新构建
-        self.screenshot = None;
+        if self.style != self.saved_style {
+            crate::overlay::annotate::save_style(self.style);
+            self.saved_style = self.style;
+        }
         self.selection = None;
+        self.others.clear();
         self.drag_start = None;
-        self.dim_cache = None;
-        // 主动收缩可能的临时 Vec 容量（注意 allocator 可能仍保留，但可提示归还）
-        // 由于我们把 Option<Vec<_>> 设为 None，这里暂无直接 shrink；若后续改为复用缓冲则可调用 shrink_to_fit。
+        self.reset_edits();
+        self.set_toolbar_focus(None);
+        self.burst = Vec::new();
+        self.burst_thumbs = Vec::new();
+        self.burst_strip.clear();
+        let screenshot = self.screenshot.take();
+        let dim = self.dim_cache.take();
+        #[cfg(feature = "gpu")]
+        if let Some(gpu) = &mut self.gpu {
+            gpu.clear();
+        }
+        if self.low_memory {
+            // 低内存模式：释放截图与缓存（全屏 RGBA + 缓存数十 MB），下次显示重新分配
+            self.pool = BufferPool::default();
+            return;
+        }
+        // 默认：保留分配，下次截图只刷新内容（4K 下避免每次 F4 重新分配上百 MB）
+        if let Some((_, _, mut buf)) = screenshot {
+            buf.clear();
+            self.pool.rgba = buf;
+        }
+        if let Some(mut dim) = dim {
+            dim.clear();
+            self.pool.dim = dim;
+        }
+    }
+
+    /// 连拍：以第 pick 帧打开，顶部缩略图条单击或 PageUp / PageDown 切换到其它帧
+    /// （与该帧尺寸不同的帧丢弃）
+    pub fn show_burst(&mut self, frames: Vec<BurstFrame>, pick: usize) -> Result<()> {
+        let Some(first) = frames.get(pick) else {
+            return Err(anyhow::anyhow!("burst frame {pick} out of range"));
+        };
+        let (w, h, origin) = (first.width, first.height, first.origin);
+        let keep = |f: &BurstFrame| (f.width, f.height, f.origin) == (w, h, origin);
+        // 过滤后 pick 之前的帧数即其新位置
+        let index = frames[..pick].iter().filter(|f| keep(f)).count();
+        let mut burst: Vec<Vec<u8>> = frames.into_iter().filter(keep).map(|f| f.rgba).collect();
+        let thumb_w = thumb_width(burst.len(), w, self.theme.scale);
+        let thumbs: Vec<Thumb> = burst.iter().map(|f| thumbnail(w, h, f, thumb_w)).collect();
+        let pixels = std::mem::take(&mut burst[index]);
+        self.show_with_image(w, h, pixels, origin)?;
+        if burst.len() > 1 {
+            let size = (thumbs[0].width, thumbs[0].height);
+            self.burst_strip = strip_rects(thumbs.len(), size, w, self.theme.scale);
+            self.burst_thumbs = thumbs;
+            self.burst = burst;
+            self.burst_index = index;
+            self.set_burst_title();
+        }
+        Ok(())
+    }
+
+    // 切换到相邻的连拍帧（循环）
+    fn step_burst(&mut self, forward: bool) {
+        let n = self.burst.len();
+        if n < 2 {
+            return;
+        }
+        let next = if forward {
+            (self.burst_index + 1) % n
+        } else {
+            (self.burst_index + n - 1) % n
+        };
+        self.select_burst(next);
+    }
+
+    // 切换到第 next 帧；选区与编辑保留
+    fn select_burst(&mut self, next: usize) {
+        if next == self.burst_index || next >= self.burst.len() {
+            return;
+        }
+        let Some((_, _, pixels)) = self.screenshot.as_mut() else {
+            return;
+        };
+        self.burst[self.burst_index] = std::mem::take(pixels);
+        *pixels = std::mem::take(&mut self.burst[next]);
+        self.burst_index = next;
+        #[cfg(feature = "gpu")]
+        if let (Some(gpu), Some((w, h, buf))) = (&mut self.gpu, &self.screenshot) {
+            gpu.upload(*w, *h, buf);
+        }
+        self.build_caches();
+        self.set_burst_title();
+        self.full_redraw = true;
+        self.window.request_redraw();
+    }
+
+    fn set_burst_title(&self) {
+        let title = format!(
+            "Snip Overlay - 连拍 {}/{}",
+            self.burst_index + 1,
+            self.burst.len()
+        );
+        self.window.set_title(&title);
+    }
+
+    /// 拾取模式（本次显示有效，hide 时恢复）：钉住 / 保存 / 复制等导出按钮与 Enter
+    /// 只返回 OverlayAction::RegionPicked，不裁剪、不写剪贴板
+    pub fn set_pick_mode(&mut self, pick: bool) {
+        self.pick_only = pick;
+    }
+
+    /// 低内存模式：hide 时释放缓冲，而不是保留复用
+    pub fn set_low_memory(&mut self, low_memory: bool) {
+        self.low_memory = low_memory;
+        if low_memory {
+            self.pool = BufferPool::default();
+        }
+    }
+
+    // 选区边缘吸附：配置了网格时吸附到网格（按住 Ctrl 时不吸附），否则吸附窗口边线（按住 Alt 时原样返回）
+    fn snap_edges(&self, r: Region, edges: (bool, bool, bool, bool)) -> Region {
+        if let Some(grid) = self.active_grid() {
+            let bounds = self.screenshot.as_ref().map_or((0, 0), |s| (s.0, s.1));
+            grid_edges(r, edges, grid, bounds)
+        } else if self.alt_down {
+            r
+        } else {
+            self.snap_lines.snap_edges(r, edges)
+        }
+    }
+
+    fn active_grid(&self) -> Option<u32> {
+        self.grid.filter(|_| !self.ctrl_down)
+    }
+
+    /// 调整选区大小时的最小宽高（默认 MIN_RESIZE）
+    pub fn set_min_selection_size(&mut self, size: u32) {
+        self.min_size = size.max(1);
+    }
+
+    /// 拖出 / 调整 / 移动选区时吸附到 grid 像素的网格（None 或小于 2 时关闭）
+    pub fn set_selection_grid(&mut self, grid: Option<u32>) {
+        self.grid = grid.filter(|&g| g >= 2);
+    }
+
+    // 每次截图从未调整 / 未打码 / 无标注状态开始（画笔样式保留）
+    fn reset_edits(&mut self) {
+        self.redactions.clear();
+        self.excluded.clear();
+        self.exclude_start = None;
+        self.strokes.clear();
+        self.stroking = false;
+        self.style_rect = None;
+        self.adjust = Adjustments::default();
+        self.adjust_open = false;
+        self.adjust_rect = None;
+        self.adjust_drag = None;
+    }
+
+    /// 多选区导出方式（保存 / 钉图 / 复制）
+    pub fn set_region_layout(&mut self, layout: RegionLayout) {
+        self.layout = layout;
+    }
+
+    /// 导出（保存 / 钉图 / 复制）前自动裁掉选区四周的纯色边
+    pub fn set_auto_trim(&mut self, auto_trim: bool) {
+        self.auto_trim = auto_trim;
+    }
+
+    /// 导出（保存 / 钉图 / 复制）的四角裁成半径 radius 像素的透明圆角；None 为直角
+    pub fn set_corner_radius(&mut self, radius: Option<u32>) {
+        self.corner_radius = radius.unwrap_or(0);
+    }
+
+    /// 保存 / 复制 / 复制为 Data URI / 分享 / 上传前的缩放（[export_scale]）
+    pub fn set_export_scale(&mut self, scale: ExportScale) {
+        self.export_scale = scale;
+    }
+
+    /// “上传”可选的 WebDAV 目标名称（配置顺序）；重建目标选择菜单
+    pub fn set_upload_destinations<'a>(&mut self, names: impl IntoIterator<Item = &'a str>) {
+        for item in self.upload_items.drain(..) {
+            self.upload_menu.remove(&item).ok();
+        }
+        for name in names {
+            let item = MenuItem::new(name, true, None);
+            self.upload_menu.append(&item).ok();
+            self.upload_items.push(item);
+        }
+        self.more_ids.uploads = self.upload_items.iter().map(|i| i.id().clone()).collect();
+    }
+
+    /// 选区内的构图辅助线（三分网格 / 中心十字）；overlay 中按 G 可临时切换
+    pub fn set_selection_guides(&mut self, guides: SelectionGuides) {
+        self.guides = guides;
+    }
+
+    /// 配色（高对比度时使用系统颜色、加粗边框）；在下一次重绘时生效
+    pub fn set_theme(&mut self, theme: Theme) {
+        if theme != self.theme {
+            self.theme = theme;
+            self.full_redraw = true;
+        }
+    }
+
+    /// 保存有标注的选区时，在 PNG 旁另写未标注的底图与笔画 sidecar（见 annotate::AnnotationLayers）
+    pub fn set_annotation_sidecar(&mut self, enabled: bool) {
+        self.annotation_sidecar = enabled;
+    }
+
+    /// 载入标注图层继续编辑（overlay 已显示）：底图居中贴进截图并选中，恢复笔画后进入标注模式。
+    /// 底图超出屏幕的部分被裁掉
+    pub fn load_layers(&mut self, layers: AnnotationLayers) {
+        let Some(offset) = self.paste_centered(layers.width, layers.height, &layers.base) else {
+            return;
+        };
+        self.selection = self.shift_region((0, 0, layers.width, layers.height), offset);
+        self.strokes = translate_strokes(&layers.strokes, offset.0, offset.1);
+        self.resume_editing();
+    }
+
+    /// 打开 .snip 工程（overlay 已显示）：原始截图替换当前画面（尺寸不同时居中），
+    /// 恢复选区、打码、调整与笔画；有笔画时进入标注模式
+    pub fn load_project(&mut self, project: Project) {
+        let Project { manifest, capture } = project;
+        let Some(offset) = self.paste_centered(manifest.width, manifest.height, &capture) else {
+            return;
+        };
+        self.selection = manifest
+            .selection
+            .and_then(|r| self.shift_region(r, offset));
+        self.others = manifest
+            .others
+            .iter()
+            .filter_map(|&r| self.shift_region(r, offset))
+            .collect();
+        self.redactions = manifest
+            .redactions
+            .iter()
+            .filter_map(|&r| self.shift_region(r, offset))
+            .map(|r| (r, true))
+            .collect();
+        self.excluded = manifest
+            .excluded
+            .iter()
+            .filter_map(|&r| self.shift_region(r, offset))
+            .collect();
+        self.adjust = manifest.adjust;
+        self.strokes = translate_strokes(&manifest.strokes, offset.0, offset.1);
+        self.resume_editing();
+    }
+
+    /// 当前画面与编辑打包为工程（截图来源由调用方填写）
+    pub fn project(&self) -> Option<Project> {
+        let (sw, sh, buf) = self.screenshot.as_ref().filter(|_| self.visible)?;
+        let mut manifest = Manifest::new(*sw, *sh, self.origin);
+        manifest.selection = self.selection;
+        manifest.others = self.others.clone();
+        manifest.strokes = self.strokes.clone();
+        manifest.redactions = self
+            .redactions
+            .iter()
+            .filter(|(_, applied)| *applied)
+            .map(|&(r, _)| r)
+            .collect();
+        manifest.excluded = self.excluded.clone();
+        manifest.adjust = self.adjust;
+        Some(Project {
+            manifest,
+            capture: buf.clone(),
+        })
+    }
+
+    // 把 w x h 的 RGBA 居中贴进截图（超出部分裁掉），清空编辑；返回图像坐标到 overlay 坐标的偏移
+    fn paste_centered(&mut self, w: u32, h: u32, rgba: &[u8]) -> Option<(i32, i32)> {
+        if !self.visible || w == 0 || h == 0 {
+            return None;
+        }
+        let (sw, sh, buf) = self.screenshot.as_mut()?;
+        let dx = (*sw as i32 - w as i32) / 2;
+        let dy = (*sh as i32 - h as i32) / 2;
+        let (x0, x1) = (dx.max(0), (dx + w as i32).min(*sw as i32));
+        let row_len = (x1 - x0) as usize * 4;
+        for y in dy.max(0)..(dy + h as i32).min(*sh as i32) {
+            let src = (((y - dy) as u32 * w + (x0 - dx) as u32) * 4) as usize;
+            let dst = ((y as u32 * *sw + x0 as u32) * 4) as usize;
+            buf[dst..dst + row_len].copy_from_slice(&rgba[src..src + row_len]);
+        }
+        #[cfg(feature = "gpu")]
+        if let Some(gpu) = &mut self.gpu {
+            if let Some((w, h, buf)) = &self.screenshot {
+                gpu.upload(*w, *h, buf);
+            }
+        }
+        self.build_caches();
+        self.others.clear();
+        self.reset_edits();
+        Some((dx, dy))
+    }
+
+    // 图像坐标的区域平移到 overlay 坐标并裁到截图内
+    fn shift_region(&self, r: Region, offset: (i32, i32)) -> Option<Region> {
+        let (sw, sh, _) = self.screenshot.as_ref()?;
+        let rect = Rect {
+            x: r.0 as i32 + offset.0,
+            y: r.1 as i32 + offset.1,
+            width: r.2,
+            height: r.3,
+        };
+        screen_to_region((0, 0), rect, (*sw, *sh))
+    }
+
+    // 载入后：有选区时回到选区模式，有笔画时进入标注模式
+    fn resume_editing(&mut self) {
+        if self.selection.is_some() {
+            self.settle(OverlayMode::IdleWithSelection);
+            if !self.strokes.is_empty() {
+                self.settle(OverlayMode::Annotating);
+                self.window.set_cursor(CursorIcon::Crosshair);
+            }
+        }
+        self.full_redraw = true;
+        self.window.request_redraw();
+    }
+
+    /// 工具栏按钮集合（配置 `toolbar`）
+    pub fn set_toolbar(&mut self, spec: ToolbarSpec) {
+        self.toolbar_layout = spec.layout(usize::MAX);
+        self.toolbar_spec = spec;
+        self.set_toolbar_focus(None);
+    }
+
+    /// 实时选区：overlay 作为半透明层盖在实时桌面上，选区内镂空（色键透明，点击直达下方程序），
+    /// 确认（保存 / 钉图 / 复制等）时才截图。系统不支持分层窗口或启用了 GPU 渲染器时返回 false，
+    /// 调用方改用冻结截图。pins 为确认截图时需临时隐藏的贴图（无法排除出截图的）
+    pub fn show_live(
+        &mut self,
+        w: u32,
+        h: u32,
+        origin: (i32, i32),
+        pins: Vec<Weak<Window>>,
+    ) -> Result<bool> {
+        // 色键镂空依赖 GDI 呈现，wgpu 交换链不支持分层窗口：GPU 渲染器在时用冻结截图
+        #[cfg(feature = "gpu")]
+        if self.gpu.is_some() {
+            return Ok(false);
+        }
+        if !crate::windows_util::set_live_overlay(&self.window, true) {
+            return Ok(false);
+        }
+        // 占位截图填满色键色：选区“亮图”即为镂空
+        let key = crate::windows_util::LIVE_COLOR_KEY.to_be_bytes();
+        let mut buf = self.take_capture_buffer();
+        buf.clear();
+        buf.resize(w as usize * h as usize * 4, 0);
+        for px in buf.chunks_exact_mut(4) {
+            px.copy_from_slice(&[key[1], key[2], key[3], 0xFF]);
+        }
+        self.live = true;
+        self.live_pins = pins;
+        if let Err(e) = self.show_with_image(w, h, buf, origin) {
+            self.live = false;
+            self.live_pins.clear();
+            crate::windows_util::set_live_overlay(&self.window, false);
+            return Err(e);
+        }
+        Ok(true)
+    }
+
+    // 实时选区确认：隐藏 overlay 截取当前画面替换占位截图，之后与冻结截图相同；截图失败返回 false
+    // 与冻结截图一样先隐藏贴图，截完恢复
+    fn freeze_live(&mut self) -> bool {
+        self.live = false;
+        self.window.set_visible(false);
+        crate::windows_util::set_live_overlay(&self.window, false);
+        let pins: Vec<SharedWindow> = std::mem::take(&mut self.live_pins)
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter(|w| w.is_visible() != Some(false))
+            .collect();
+        for w in &pins {
+            w.set_visible(false);
+        }
+        crate::windows_util::flush_compositor();
+        let mut buf = self
+            .screenshot
+            .take()
+            .map(|(_, _, buf)| buf)
+            .unwrap_or_default();
+        let captured = crate::capture::capture_fullscreen_raw_into(&mut buf);
+        for w in &pins {
+            w.set_visible(true);
+        }
+        match captured {
+            Ok((x, y, w, h)) => {
+                self.screenshot = Some((w, h, buf));
+                self.origin = (x, y);
+                #[cfg(feature = "gpu")]
+                if let (Some(gpu), Some((w, h, buf))) = (&mut self.gpu, &self.screenshot) {
+                    gpu.upload(*w, *h, buf);
+                }
+                self.build_caches();
+                self.full_redraw = true;
+                true
+            }
+            Err(e) => {
+                log::warn!("live selection capture failed: {e}");
+                false
+            }
+        }
+    }
+
+    /// 取出复用的截图缓冲（容量保留，内容为空），截图写入后交回 show_with_image
+    pub fn take_capture_buffer(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.pool.rgba)
+    }
+
+    // 单指 / 触控笔由系统据触摸生成的鼠标消息处理（拖出选区、移动、标注），这里只记下压力，
+    // 供随后的鼠标事件画笔画；overlay 内不支持捏合
+    fn handle_touch(&mut self, t: &Touch) {
+        self.pen_pressure = match t.phase {
+            TouchPhase::Started | TouchPhase::Moved => touch::pressure(t.force),
+            TouchPhase::Ended | TouchPhase::Cancelled => None,
+        };
     }
 
     pub fn handle_event(&mut self, event: &WindowEvent) -> OverlayAction {
+        if let WindowEvent::ModifiersChanged(m) = event {
+            self.ctrl_down = m.state().control_key();
+            self.shift_down = m.state().shift_key();
+            self.alt_down = m.state().alt_key();
+        }
         if !self.visible {
             return OverlayAction::None;
         }
+        if let WindowEvent::Touch(t) = event {
+            self.handle_touch(t);
+            return OverlayAction::None;
+        }
+        if let WindowEvent::MouseInput {
+            state: ElementState::Pressed,
+            ..
+        } = event
+        {
+            // 按住热键时点了鼠标：转为普通选区
+            self.detect_windows = None;
+        }
+        if let WindowEvent::MouseInput {
+            state: ElementState::Pressed,
+            button: MouseButton::Left,
+            ..
+        } = event
+        {
+            // 连拍缩略图条：单击切换帧，不开始选区
+            let idle = matches!(
+                self.mode,
+                OverlayMode::Idle | OverlayMode::IdleWithSelection
+            );
+            let (cx, cy) = (self.last_cursor.0 as i32, self.last_cursor.1 as i32);
+            if let Some(i) = hit_test_strip(&self.burst_strip, cx, cy).filter(|_| idle) {
+                self.select_burst(i);
+                return OverlayAction::None;
+            }
+        }
         let mut immediate_action = OverlayAction::None;
         match event {
             WindowEvent::MouseInput {
@@ -157,33 +930,132 @@ impl OverlayState {
                         self.window.request_redraw();
                     }
                     OverlayMode::IdleWithSelection => {
-                        if let Some((x, y, w, h)) = self.selection {
-                            let (cx, cy) = (self.last_cursor.0 as i32, self.last_cursor.1 as i32);
-                            if let Some(handle) = hit_test_handle(cx, cy, x, y, w, h) {
+                        let (cx, cy) = (self.last_cursor.0 as i32, self.last_cursor.1 as i32);
+                        // 调整面板优先：开关立即切换，滑块进入拖动
+                        if let Some(panel) = self.adjust_rect {
+                            if let Some(row) = hit_test_adjust(panel, cx, cy) {
+                                if row.is_toggle() {
+                                    row.toggle(&mut self.adjust);
+                                } else {
+                                    row.set_value(&mut self.adjust, slider_value(panel, cx));
+                                    self.adjust_drag = Some(row);
+                                }
+                                self.window.request_redraw();
+                                return OverlayAction::None;
+                            }
+                        }
+                        let on_toolbar = self
+                            .toolbar_rect
+                            .map(|(bx, by, bw, bh)| {
+                                region_contains(
+                                    (bx as u32, by as u32, bw as u32, bh as u32),
+                                    cx,
+                                    cy,
+                                )
+                            })
+                            .unwrap_or(false);
+                        if self.ctrl_down && !on_toolbar {
+                            // Ctrl：保留当前选区，再拖一个新选区
+                            if let Some(sel) = self.selection.take() {
+                                self.others.push(sel);
+                            }
+                            self.drag_start = Some(self.last_cursor);
+                            self.mode = OverlayMode::Dragging;
+                            self.full_redraw = true;
+                            self.window.request_redraw();
+                        } else if let Some((x, y, w, h)) = self.selection {
+                            if let Some(handle) =
+                                hit_test_handle(cx, cy, x, y, w, h, self.theme.scale.px(HANDLE_HIT))
+                            {
                                 self.resize_handle = Some(handle);
                                 self.mode = OverlayMode::Resizing;
-                            } else if cx >= x as i32
-                                && cy >= y as i32
-                                && cx < (x + w) as i32
-                                && cy < (y + h) as i32
+                            } else if self.alt_down
+                                && self.shift_down
+                                && region_contains((x, y, w, h), cx, cy)
+                            {
+                                // Shift+Alt：在选区内拖出挖空区域（单独的 Alt 只关闭吸附）
+                                self.exclude_start = Some(self.last_cursor);
+                                self.excluded.push((cx as u32, cy as u32, 0, 0));
+                            } else if let Some((_, applied)) = self
+                                .redactions
+                                .iter_mut()
+                                .find(|(r, _)| region_contains(*r, cx, cy))
                             {
+                                // 单击检测到的敏感区域：切换马赛克
+                                *applied = !*applied;
+                                self.full_redraw = true;
+                                self.window.request_redraw();
+                            } else if region_contains((x, y, w, h), cx, cy) {
                                 self.move_offset = Some((cx - x as i32, cy - y as i32));
                                 self.mode = OverlayMode::MovingSelection;
+                            } else if let Some(i) =
+                                self.others.iter().position(|r| region_contains(*r, cx, cy))
+                            {
+                                // 点中其它选区：切换为活动选区（工具栏 / 手柄随之移动）
+                                let prev = std::mem::replace(&mut self.others[i], (x, y, w, h));
+                                self.selection = Some(prev);
+                                self.full_redraw = true;
+                                self.window.request_redraw();
                             }
                         }
                     }
+                    OverlayMode::Annotating => {
+                        let (cx, cy) = (self.last_cursor.0 as i32, self.last_cursor.1 as i32);
+                        if let Some(pick) = self
+                            .style_rect
+                            .and_then(|bar| hit_test_style_bar(bar, cx, cy))
+                        {
+                            pick.apply(&mut self.style);
+                            self.window.request_redraw();
+                        } else if self.selection.is_some_and(|r| region_contains(r, cx, cy)) {
+                            // 工具栏按钮在松开时处理；选区内按下开始新的一笔
+                            self.strokes.push(Stroke {
+                                style: self.style,
+                                points: vec![(cx, cy)],
+                                pressure: self
+                                    .pen_pressure
+                                    .map(pressure_level)
+                                    .into_iter()
+                                    .collect(),
+                            });
+                            self.stroking = true;
+                            self.window.request_redraw();
+                        }
+                    }
                     OverlayMode::Dragging
                     | OverlayMode::MovingSelection
-                    | OverlayMode::Resizing
-                    | OverlayMode::Annotating => {}
+                    | OverlayMode::Resizing => {}
                 },
                 ElementState::Released => {
+                    if self.adjust_drag.take().is_some() {
+                        return OverlayAction::None;
+                    }
+                    if std::mem::take(&mut self.stroking) {
+                        return OverlayAction::None;
+                    }
+                    if self.exclude_start.take().is_some() {
+                        // 没拖出面积的 Alt 单击不留下挖空
+                        self.excluded.retain(|&(_, _, w, h)| w > 0 && h > 0);
+                        self.full_redraw = true;
+                        self.window.request_redraw();
+                        return OverlayAction::None;
+                    }
                     // 工具栏点击优先
-                    if matches!(self.mode, OverlayMode::IdleWithSelection) {
+                    if matches!(
+                        self.mode,
+                        OverlayMode::IdleWithSelection | OverlayMode::Annotating
+                    ) {
                         if let Some((bx, by, bw, bh)) = self.toolbar_rect {
                             let cx = self.last_cursor.0 as i32;
                             let cy = self.last_cursor.1 as i32;
-                            if let Some(btn) = hit_test_toolbar_button(cx, cy, bx, by, bw, bh) {
+                            let slots = self.toolbar_layout.slots.len();
+                            if let Some(btn) = hit_test_toolbar_button(
+                                cx,
+                                cy,
+                                (bx, by, bw, bh),
+                                slots,
+                                self.theme.scale,
+                            ) {
                                 immediate_action = self.execute_toolbar_button(btn);
                             }
                         }
@@ -191,22 +1063,32 @@ impl OverlayState {
                     match self.mode {
                         OverlayMode::Dragging => {
                             self.drag_start = None;
+                            let empty = self.selection.is_none_or(|(_, _, w, h)| w == 0 || h == 0);
+                            if empty && !self.others.is_empty() {
+                                // Ctrl 单击但没拖出区域：恢复上一个选区
+                                self.selection = self.others.pop();
+                                self.full_redraw = true;
+                            }
                             if self.selection.is_some() {
-                                self.mode = OverlayMode::IdleWithSelection;
+                                self.settle(OverlayMode::IdleWithSelection);
                             } else {
-                                self.mode = OverlayMode::Idle;
+                                self.settle(OverlayMode::Idle);
                             }
                         }
                         OverlayMode::MovingSelection => {
                             self.move_offset = None;
-                            self.mode = OverlayMode::IdleWithSelection;
+                            self.settle(OverlayMode::IdleWithSelection);
                         }
                         OverlayMode::Resizing => {
                             self.resize_handle = None;
-                            self.mode = OverlayMode::IdleWithSelection;
+                            self.settle(OverlayMode::IdleWithSelection);
                         }
                         _ => {}
                     }
+                    // 模式切换后工具栏显隐可能变化
+                    if self.visible {
+                        self.window.request_redraw();
+                    }
                 }
             },
             WindowEvent::MouseInput {
@@ -219,9 +1101,25 @@ impl OverlayState {
                         self.hide();
                     }
                     OverlayMode::IdleWithSelection => {
-                        self.selection = None;
-                        self.mode = OverlayMode::Idle;
-                        self.window.set_cursor(CursorIcon::Crosshair);
+                        let (cx, cy) = (self.last_cursor.0 as i32, self.last_cursor.1 as i32);
+                        if let Some(i) = self
+                            .excluded
+                            .iter()
+                            .rposition(|&r| region_contains(r, cx, cy))
+                        {
+                            // 右键挖空区域：恢复该区域
+                            self.excluded.remove(i);
+                            self.full_redraw = true;
+                            self.window.request_redraw();
+                            return OverlayAction::None;
+                        }
+                        // 多选区时逐个撤销，最后一个撤销后回到空闲
+                        self.selection = self.others.pop();
+                        self.full_redraw = true;
+                        if self.selection.is_none() {
+                            self.settle(OverlayMode::Idle);
+                            self.window.set_cursor(CursorIcon::Crosshair);
+                        }
                         self.window.request_redraw();
                     }
                     OverlayMode::Dragging
@@ -233,125 +1131,75 @@ impl OverlayState {
             },
             WindowEvent::CursorMoved { position, .. } => {
                 self.last_cursor = (position.x, position.y);
+                if self.detect_windows.is_some() {
+                    let (ox, oy) = self.origin;
+                    self.detect_at((ox + position.x as i32, oy + position.y as i32));
+                    return OverlayAction::None;
+                }
                 match self.mode {
                     OverlayMode::Dragging => {
-                        if let Some((sx, sy)) = self.drag_start {
-                            let x0 = sx.min(position.x);
-                            let y0 = sy.min(position.y);
-                            let w = (sx - position.x).abs();
-                            let h = (sy - position.y).abs();
-                            self.selection = Some((x0 as u32, y0 as u32, w as u32, h as u32));
+                        if let (Some(start), Some((sw, sh, _))) =
+                            (self.drag_start, self.screenshot.as_ref())
+                        {
+                            let cursor = (position.x, position.y);
+                            let r = drag_region(start, cursor, (*sw, *sh));
+                            self.selection = Some(self.snap_edges(r, (true, true, true, true)));
                             self.window.request_redraw();
                         }
                     }
                     OverlayMode::MovingSelection => {
-                        if let (Some((sw, sh, _)), Some((_x, _y, w, h)), Some((ox, oy))) =
+                        if let (Some((sw, sh, _)), Some((_, _, w, h)), Some((ox, oy))) =
                             (self.screenshot.as_ref(), self.selection, self.move_offset)
                         {
-                            let cx = position.x as i32;
-                            let cy = position.y as i32;
-                            let mut new_x = cx - ox;
-                            let mut new_y = cy - oy;
-                            if new_x < 0 {
-                                new_x = 0;
-                            }
-                            if new_y < 0 {
-                                new_y = 0;
-                            }
-                            let max_x = (*sw as i32 - w as i32).max(0);
-                            let max_y = (*sh as i32 - h as i32).max(0);
-                            if new_x > max_x {
-                                new_x = max_x;
-                            }
-                            if new_y > max_y {
-                                new_y = max_y;
-                            }
-                            self.selection = Some((new_x as u32, new_y as u32, w, h));
+                            let pos = (position.x as i32 - ox, position.y as i32 - oy);
+                            let r = clamp_selection(pos, (w, h), (*sw, *sh));
+                            self.selection = Some(match self.active_grid() {
+                                Some(grid) => grid_move(r, grid, (*sw, *sh)),
+                                None if self.alt_down => r,
+                                None => self.snap_lines.snap_move(r, (*sw, *sh)),
+                            });
                             self.window.request_redraw();
                         }
                     }
                     OverlayMode::Resizing => {
-                        if let (Some((sw, sh, _)), Some((sx, sy, w, h)), Some(handle)) =
+                        if let (Some((sw, sh, _)), Some(sel), Some(handle)) =
                             (self.screenshot.as_ref(), self.selection, self.resize_handle)
                         {
-                            let cx = position.x as i32;
-                            let cy = position.y as i32;
-                            let mut x = sx as i32;
-                            let mut y = sy as i32;
-                            let mut rw = w as i32;
-                            let mut rh = h as i32;
-                            const MIN: i32 = 4;
-                            match handle {
-                                ResizeHandle::TopLeft => {
-                                    let nx = cx.clamp(0, (sx + w) as i32 - MIN);
-                                    let ny = cy.clamp(0, (sy + h) as i32 - MIN);
-                                    rw = (x + rw - nx).max(MIN);
-                                    rh = (y + rh - ny).max(MIN);
-                                    x = nx;
-                                    y = ny;
-                                }
-                                ResizeHandle::Top => {
-                                    let ny = cy.clamp(0, (sy + h) as i32 - MIN);
-                                    rh = (y + rh - ny).max(MIN);
-                                    y = ny;
-                                }
-                                ResizeHandle::TopRight => {
-                                    let ny = cy.clamp(0, (sy + h) as i32 - MIN);
-                                    let nx2 = cx.clamp(sx as i32 + MIN, *sw as i32 - 1);
-                                    rw = (nx2 - x + 1).max(MIN);
-                                    rh = (y + rh - ny).max(MIN);
-                                    y = ny;
-                                }
-                                ResizeHandle::Right => {
-                                    let nx2 = cx.clamp(sx as i32 + MIN, *sw as i32 - 1);
-                                    rw = (nx2 - x + 1).max(MIN);
-                                }
-                                ResizeHandle::BottomRight => {
-                                    let nx2 = cx.clamp(sx as i32 + MIN, *sw as i32 - 1);
-                                    let ny2 = cy.clamp(sy as i32 + MIN, *sh as i32 - 1);
-                                    rw = (nx2 - x + 1).max(MIN);
-                                    rh = (ny2 - y + 1).max(MIN);
-                                }
-                                ResizeHandle::Bottom => {
-                                    let ny2 = cy.clamp(sy as i32 + MIN, *sh as i32 - 1);
-                                    rh = (ny2 - y + 1).max(MIN);
-                                }
-                                ResizeHandle::BottomLeft => {
-                                    let nx = cx.clamp(0, (sx + w) as i32 - MIN);
-                                    let ny2 = cy.clamp(sy as i32 + MIN, *sh as i32 - 1);
-                                    rw = (x + rw - nx).max(MIN);
-                                    rh = (ny2 - y + 1).max(MIN);
-                                    x = nx;
-                                }
-                                ResizeHandle::Left => {
-                                    let nx = cx.clamp(0, (sx + w) as i32 - MIN);
-                                    rw = (x + rw - nx).max(MIN);
-                                    x = nx;
-                                }
-                            }
-                            if x < 0 {
-                                x = 0;
-                            }
-                            if y < 0 {
-                                y = 0;
-                            }
-                            if x + rw > *sw as i32 {
-                                rw = *sw as i32 - x;
-                            }
-                            if y + rh > *sh as i32 {
-                                rh = *sh as i32 - y;
-                            }
-                            self.selection = Some((x as u32, y as u32, rw as u32, rh as u32));
+                            let cursor = (position.x as i32, position.y as i32);
+                            let r = apply_resize(handle, cursor, sel, (*sw, *sh), self.min_size);
+                            self.selection = Some(self.snap_edges(r, handle.edges()));
                             self.window.request_redraw();
                         }
                     }
                     OverlayMode::IdleWithSelection => {
-                        if let Some((x, y, w, h)) = self.selection {
+                        if let (Some(start), Some(sel), Some((sw, sh, _))) =
+                            (self.exclude_start, self.selection, self.screenshot.as_ref())
+                        {
+                            let cursor = (position.x, position.y);
+                            let r = drag_region(start, cursor, (*sw, *sh));
+                            if let Some(last) = self.excluded.last_mut() {
+                                *last = intersect_region(r, sel).unwrap_or((r.0, r.1, 0, 0));
+                            }
+                            self.window.request_redraw();
+                        } else if let (Some(row), Some(panel)) =
+                            (self.adjust_drag, self.adjust_rect)
+                        {
+                            row.set_value(&mut self.adjust, slider_value(panel, position.x as i32));
+                            self.window.request_redraw();
+                        } else if let Some((x, y, w, h)) = self.selection {
                             let (cx, cy) = (position.x as i32, position.y as i32);
                             // 1. 工具栏 hover 检测（若命中则直接使用 Pointer，不再继续后续手柄/区域判定）
+                            let prev_hover = self.toolbar_hover;
                             let mut over_toolbar = false;
                             if let Some((bx, by, bw, bh)) = self.toolbar_rect {
-                                if let Some(btn) = hit_test_toolbar_button(cx, cy, bx, by, bw, bh) {
+                                let slots = self.toolbar_layout.slots.len();
+                                if let Some(btn) = hit_test_toolbar_button(
+                                    cx,
+                                    cy,
+                                    (bx, by, bw, bh),
+                                    slots,
+                                    self.theme.scale,
+                                ) {
                                     self.toolbar_hover = Some(btn);
                                     self.window.set_cursor(CursorIcon::Pointer);
                                     over_toolbar = true;
@@ -361,10 +1209,22 @@ impl OverlayState {
                             } else {
                                 self.toolbar_hover = None;
                             }
+                            // 高亮变化才重绘（事件循环不再每轮刷新 overlay）
+                            if self.toolbar_hover != prev_hover {
+                                self.window.request_redraw();
+                            }
 
                             if !over_toolbar {
                                 // 2. 手柄与选区区域判定
-                                if let Some(handle) = hit_test_handle(cx, cy, x, y, w, h) {
+                                if let Some(handle) = hit_test_handle(
+                                    cx,
+                                    cy,
+                                    x,
+                                    y,
+                                    w,
+                                    h,
+                                    self.theme.scale.px(HANDLE_HIT),
+                                ) {
                                     let icon = match handle {
                                         ResizeHandle::Top | ResizeHandle::Bottom => NsResize,
                                         ResizeHandle::Left | ResizeHandle::Right => EwResize,
@@ -391,19 +1251,59 @@ impl OverlayState {
                             }
                         }
                     }
+                    OverlayMode::Annotating => {
+                        let (cx, cy) = (position.x as i32, position.y as i32);
+                        if self.stroking {
+                            if let Some(stroke) = self.strokes.last_mut() {
+                                if stroke.points.last() != Some(&(cx, cy)) {
+                                    stroke.points.push((cx, cy));
+                                    // 带压力的笔画：没有压力的事件沿用上一个点的压力
+                                    if let Some(&last) = stroke.pressure.last() {
+                                        let level = self.pen_pressure.map_or(last, pressure_level);
+                                        stroke.pressure.push(level);
+                                    }
+                                    self.window.request_redraw();
+                                }
+                            }
+                            return OverlayAction::None;
+                        }
+                        let prev_hover = self.toolbar_hover;
+                        let slots = self.toolbar_layout.slots.len();
+                        self.toolbar_hover = self.toolbar_rect.and_then(|(bx, by, bw, bh)| {
+                            hit_test_toolbar_button(
+                                cx,
+                                cy,
+                                (bx, by, bw, bh),
+                                slots,
+                                self.theme.scale,
+                            )
+                        });
+                        if self.toolbar_hover != prev_hover {
+                            self.window.request_redraw();
+                        }
+                        let on_style = self
+                            .style_rect
+                            .is_some_and(|bar| hit_test_style_bar(bar, cx, cy).is_some());
+                        let icon = if self.toolbar_hover.is_some() || on_style {
+                            CursorIcon::Pointer
+                        } else {
+                            CursorIcon::Crosshair
+                        };
+                        self.window.set_cursor(icon);
+                    }
                     _ => {}
                 }
             }
             WindowEvent::KeyboardInput {
                 event:
                     KeyEvent {
-                        physical_key: PhysicalKey::Code(KeyCode::Escape),
+                        physical_key: PhysicalKey::Code(code),
                         state: ElementState::Pressed,
                         ..
                     },
                 ..
             } => {
-                self.hide();
+                immediate_action = self.handle_key(*code);
             }
             _ => {}
         }
@@ -416,153 +1316,696 @@ impl OverlayState {
             return;
         }
         if let Some((sw, sh, _)) = self.screenshot {
+            let handle_size = self.theme.scale.px(6);
+            let hs2 = handle_size / 2;
+            // 工具栏位置先算出，参与本帧包围盒；工具栏与面板限制在选区所在显示器的工作区内
+            let bounds = self.selection.map_or((0, 0, sw as i32, sh as i32), |sel| {
+                toolbar_bounds(sel, self.origin, &self.work_areas, sw, sh)
+            });
+            self.toolbar_rect = match self.selection {
+                Some((x, y, w, h))
+                    if matches!(
+                        self.mode,
+                        OverlayMode::IdleWithSelection | OverlayMode::Annotating
+                    ) =>
+                {
+                    let scale = self.theme.scale;
+                    self.toolbar_layout = self.toolbar_spec.layout(max_slots_for_width(w, scale));
+                    let slots = self.toolbar_layout.slots.len();
+                    compute_toolbar_rect_in(x, y, w, h, bounds, slots, scale)
+                }
+                _ => None,
+            };
+            // 调整面板 / 样式栏按工作区的右、下边界限制
+            let (limit_w, limit_h) = ((bounds.0 + bounds.2) as u32, (bounds.1 + bounds.3) as u32);
+            let slots = self.toolbar_layout.slots.len();
+            if self.toolbar_focus.is_some_and(|i| i >= slots) {
+                self.set_toolbar_focus(None);
+            }
+            if self.toolbar_hover.is_some_and(|i| i >= slots) {
+                self.toolbar_hover = None;
+            }
+            self.adjust_rect = self
+                .toolbar_rect
+                .filter(|_| self.adjust_open)
+                .map(|tb| adjust_panel_rect(tb, limit_w, limit_h));
+            self.style_rect = self
+                .toolbar_rect
+                .filter(|_| self.mode == OverlayMode::Annotating)
+                .map(|tb| style_bar_rect(tb, limit_w, limit_h));
+            // GPU 着色器只画默认配色下单个未调整、无标注的选区；多选区 / 调整面板 / 标注 / 打码 /
+            // 辅助线 / 连拍缩略图条 / 高对比度 / UI 缩放时走 softbuffer
+            #[cfg(feature = "gpu")]
+            if self.others.is_empty()
+                && !self.theme.high_contrast
+                && self.theme.scale.get() == 1.0
+                && self.adjust_rect.is_none()
+                && self.adjust.is_identity()
+                && self.style_rect.is_none()
+                && self.strokes.is_empty()
+                && self.excluded.is_empty()
+                && self.redactions.is_empty()
+                && self.guides == SelectionGuides::Off
+                && self.burst_thumbs.is_empty()
+                && !self.live
+                && self.redraw_gpu(hs2)
+            {
+                return;
+            }
             let size = self.window.inner_size();
             let width = size.width.max(1);
             let height = size.height.max(1);
-            let _ = self.surface.resize(
+            // 工具栏、调整面板与样式栏合并为一个包围盒参与脏区计算（连拍缩略图条不动，
+            // 只在切换帧的整帧重绘时更新，不计入）
+            let chrome = [self.adjust_rect, self.style_rect]
+                .into_iter()
+                .flatten()
+                .fold(self.toolbar_rect, |acc, (px, py, pw, ph)| {
+                    let (tx, ty, tw, th) = acc?;
+                    let (x0, y0) = (tx.min(px), ty.min(py));
+                    let (x1, y1) = ((tx + tw).max(px + pw), (ty + th).max(py + ph));
+                    Some((x0, y0, x1 - x0, y1 - y0))
+                });
+            let bounds = overlay_bounds(self.selection, chrome, hs2, width, height);
+            #[cfg(feature = "gpu")]
+            if self.gpu.is_some() {
+                self.present_gpu(width, height, bounds, hs2);
+                return;
+            }
+            // 取出 surface，合成期间只借用 self
+            let Some(mut surface) = self.surface.take() else {
+                return;
+            };
+            let _ = surface.resize(
                 NonZeroU32::new(width).unwrap(),
                 NonZeroU32::new(height).unwrap(),
             );
-            if let Ok(mut frame) = self.surface.buffer_mut() {
-                if let Some(cache) = &self.dim_cache {
-                    let copy_w = sw.min(width);
-                    let copy_h = sh.min(height);
-                    for y in 0..copy_h {
-                        let dst_row = (y * width) as usize;
-                        let src_row = (y * sw) as usize;
-                        frame[dst_row..dst_row + copy_w as usize]
-                            .copy_from_slice(&cache[src_row..src_row + copy_w as usize]);
-                    }
-                } else {
-                    frame.fill(0x88000000);
-                }
-                if let Some((x, y, w, h)) = self.selection {
-                    let x2 = (x + w).saturating_sub(1);
-                    let y2 = (y + h).saturating_sub(1);
-                    if w > 0 && h > 0 {
-                        if matches!(
-                            self.mode,
-                            OverlayMode::Dragging
-                                | OverlayMode::IdleWithSelection
-                                | OverlayMode::MovingSelection
-                                | OverlayMode::Resizing
-                        ) {
-                            if let Some((sw, sh, buf)) = &self.screenshot {
-                                let copy_w = w.min(*sw - x).min(width - x);
-                                let copy_h = h.min(*sh - y).min(height - y);
-                                // 按需转换选区 RGBA -> BGRA，避免存整幅亮度缓存占用额外内存
-                                for row in 0..copy_h {
-                                    let src_row_start = (((y + row) * *sw) + x) as usize * 4;
-                                    let dst_row_start = ((y + row) * width + x) as usize;
-                                    for col in 0..copy_w {
-                                        let si = src_row_start + col as usize * 4;
-                                        let r = buf[si];
-                                        let g = buf[si + 1];
-                                        let b = buf[si + 2];
-                                        let a = buf[si + 3];
-                                        frame[dst_row_start + col as usize] =
-                                            u32::from_le_bytes([b, g, r, a]);
-                                    }
-                                }
-                            }
-                        }
-                        for i in x..=x2.min(width - 1) {
-                            let top = (y.min(height - 1) * width + i) as usize;
-                            frame[top] = 0xFFFFFFFF;
-                            let bottom_y = y2.min(height - 1);
-                            let bottom = (bottom_y * width + i) as usize;
-                            frame[bottom] = 0xFFFFFFFF;
-                        }
-                        for j in y..=y2.min(height - 1) {
-                            let left = (j * width + x.min(width - 1)) as usize;
-                            frame[left] = 0xFFFFFFFF;
-                            let right_x = x2.min(width - 1);
-                            let right = (j * width + right_x) as usize;
-                            frame[right] = 0xFFFFFFFF;
-                        }
-                        let handle_size: i32 = 6;
-                        let hs2 = handle_size / 2;
-                        let centers = [
-                            (x as i32, y as i32),
-                            ((x + w / 2) as i32, y as i32),
-                            ((x + w) as i32 - 1, y as i32),
-                            ((x + w) as i32 - 1, (y + h / 2) as i32),
-                            ((x + w) as i32 - 1, (y + h) as i32 - 1),
-                            ((x + w / 2) as i32, (y + h) as i32 - 1),
-                            (x as i32, (y + h) as i32 - 1),
-                            (x as i32, (y + h / 2) as i32),
-                        ];
-                        for (cx, cy) in centers {
-                            draw_handle(&mut frame, width, height, cx, cy, hs2);
-                        }
-                        if matches!(self.mode, OverlayMode::IdleWithSelection) {
-                            self.toolbar_rect = compute_toolbar_rect(x, y, w, h, sw, sh);
-                            if let Some((bar_x, bar_y, bar_w, bar_h)) = self.toolbar_rect {
-                                draw_toolbar(
-                                    &mut frame,
-                                    width,
-                                    height,
-                                    bar_x,
-                                    bar_y,
-                                    bar_w,
-                                    bar_h,
-                                    self.toolbar_hover,
-                                );
-                            }
-                        } else {
-                            self.toolbar_rect = None;
-                        }
+            if let Ok(mut frame) = surface.buffer_mut() {
+                // 脏区只相对上一帧：缓冲内容未知（首帧 / 尺寸变化，age 0）或是更早的帧
+                // （多缓冲交换链，age >= 2）时必须整帧重绘
+                let force_full = self.full_redraw || frame.age() != 1;
+                let dirty = damage(self.last_bounds, bounds, force_full);
+                self.full_redraw = false;
+                self.last_bounds = bounds;
+                self.compose(&mut frame, width, height, dirty, hs2);
+                let _ = match dirty.and_then(|r| r.to_damage()) {
+                    Some(rect) => frame.present_with_damage(&[rect]),
+                    None => frame.present(),
+                };
+            }
+            self.surface = Some(surface);
+        }
+    }
+
+    // CPU 合成一帧：dirty 为 None 时整帧，否则只恢复脏区背景（其余像素保持上一帧）
+    fn compose(
+        &self,
+        frame: &mut [u32],
+        width: u32,
+        height: u32,
+        dirty: Option<DirtyRect>,
+        hs2: i32,
+    ) {
+        let Some((sw, sh, _)) = self.screenshot else {
+            return;
+        };
+        let copy_w = sw.min(width);
+        let copy_h = sh.min(height);
+        // 背景：整帧或仅脏区恢复为变暗缓存
+        let (rx, ry, rw, rh) = match dirty {
+            Some(r) => (r.x, r.y, r.w, r.h),
+            None => (0, 0, width, height),
+        };
+        if let Some(cache) = &self.dim_cache {
+            let col_end = (rx + rw).min(copy_w);
+            if rx < col_end {
+                for y in ry..(ry + rh).min(copy_h) {
+                    let dst_row = (y * width) as usize;
+                    let src_row = (y * sw) as usize;
+                    frame[dst_row + rx as usize..dst_row + col_end as usize]
+                        .copy_from_slice(&cache[src_row + rx as usize..src_row + col_end as usize]);
+                }
+            }
+        } else {
+            for y in ry..ry + rh {
+                let row = (y * width) as usize;
+                frame[row + rx as usize..row + (rx + rw) as usize].fill(0x88000000);
+            }
+        }
+        // 调整预览：只作用于已画出的选区像素
+        let filter = (!self.adjust.is_identity()).then(|| self.adjust.prepare());
+        // 其它选区：原图亮度 + 边框，无手柄
+        if let Some((sw, sh, buf)) = &self.screenshot {
+            for &r in &self.others {
+                let shot = (*sw, *sh, buf.as_slice());
+                draw_region(frame, width, height, shot, r, true, &self.theme);
+                if let Some(f) = &filter {
+                    preview_filter(frame, width, height, r, f);
+                }
+                draw_strokes(frame, width, height, r, &self.strokes);
+            }
+        }
+        if let Some((x, y, w, h)) = self.selection {
+            if w > 0 && h > 0 {
+                let bright = matches!(
+                    self.mode,
+                    OverlayMode::Dragging
+                        | OverlayMode::IdleWithSelection
+                        | OverlayMode::MovingSelection
+                        | OverlayMode::Resizing
+                        | OverlayMode::Annotating
+                );
+                let mut border = BorderStyle::Solid(self.theme.selection);
+                if let Some((sw, sh, buf)) = &self.screenshot {
+                    border = draw_region(
+                        frame,
+                        width,
+                        height,
+                        (*sw, *sh, buf),
+                        (x, y, w, h),
+                        bright,
+                        &self.theme,
+                    );
+                }
+                // 已确认的敏感区域：帧内直接打码（通道顺序不影响取平均），与导出一致先于调整
+                for &(r, _) in self.redactions.iter().filter(|(_, applied)| *applied) {
+                    pixelate(
+                        bytemuck::cast_slice_mut(frame),
+                        width,
+                        height,
+                        r,
+                        REDACT_BLOCK,
+                    );
+                }
+                if let (true, Some(f)) = (bright, &filter) {
+                    preview_filter(frame, width, height, (x, y, w, h), f);
+                }
+                draw_strokes(frame, width, height, (x, y, w, h), &self.strokes);
+                // 挖空区域：棋盘格表示导出后透明
+                for &(ex, ey, ew, eh) in &self.excluded {
+                    let rect = (ex as i32, ey as i32, ew as i32, eh as i32);
+                    fill_checker(frame, width, height, rect);
+                    self.theme
+                        .outline(frame, width, height, rect, self.theme.selection);
+                }
+                if bright {
+                    draw_guides(frame, width, height, (x, y, w, h), self.guides);
+                }
+                let centers = [
+                    (x as i32, y as i32),
+                    ((x + w / 2) as i32, y as i32),
+                    ((x + w) as i32 - 1, y as i32),
+                    ((x + w) as i32 - 1, (y + h / 2) as i32),
+                    ((x + w) as i32 - 1, (y + h) as i32 - 1),
+                    ((x + w / 2) as i32, (y + h) as i32 - 1),
+                    (x as i32, (y + h) as i32 - 1),
+                    (x as i32, (y + h / 2) as i32),
+                ];
+                for (cx, cy) in centers {
+                    // 虚线边框时手柄加一圈另一种颜色，任何背景上都看得见
+                    if let Some(outer) = border.secondary() {
+                        draw_handle(frame, width, height, cx, cy, hs2 + 1, outer);
                     }
+                    let color = border.primary();
+                    draw_handle(frame, width, height, cx, cy, hs2, color);
+                }
+                if let Some((bar_x, bar_y, bar_w, bar_h)) = self.toolbar_rect {
+                    draw_toolbar(
+                        frame,
+                        width,
+                        height,
+                        bar_x,
+                        bar_y,
+                        bar_w,
+                        bar_h,
+                        &self.toolbar_layout.slots,
+                        self.toolbar_hover,
+                        self.toolbar_focus,
+                        &self.theme,
+                    );
+                }
+                for &(r, applied) in &self.redactions {
+                    let (rx, ry, rw, rh) = r;
+                    let color = if applied { 0xFF888888 } else { 0xFFFF4040 };
+                    stroke_rect(
+                        frame,
+                        width,
+                        height,
+                        rx as i32 - 1,
+                        ry as i32 - 1,
+                        rw as i32 + 2,
+                        rh as i32 + 2,
+                        color,
+                    );
+                }
+                if let Some(panel) = self.adjust_rect {
+                    let adjust = &self.adjust;
+                    draw_adjust_panel(frame, width, height, panel, adjust, &self.theme);
+                }
+                if let Some(bar) = self.style_rect {
+                    let style = &self.style;
+                    draw_style_bar(frame, width, height, bar, style, &self.theme);
                 }
-                let _ = frame.present();
             }
         }
+        // 缩略图条最后画，盖在选区 / 工具栏之上；脏区内背景恢复后随之重画
+        let (thumbs, rects) = (&self.burst_thumbs, &self.burst_strip);
+        draw_strip(
+            frame,
+            width,
+            height,
+            thumbs,
+            rects,
+            self.burst_index,
+            &self.theme,
+        );
     }
 
-    pub fn take_selection_png(&self) -> Option<Vec<u8>> {
-        use image::{ImageBuffer, Rgba};
-        let (sw, sh, ref buf) = self.screenshot.as_ref()?;
-        let (x, y, w, h) = self.selection?;
-        if w == 0 || h == 0 {
+    // GPU 渲染器在时 CPU 帧也经 wgpu 呈现（合成到常驻后备缓冲，按脏区上传）
+    #[cfg(feature = "gpu")]
+    fn present_gpu(&mut self, width: u32, height: u32, bounds: Option<DirtyRect>, hs2: i32) {
+        let mut back = std::mem::take(&mut self.gpu_back);
+        let len = (width * height) as usize;
+        let resized = back.len() != len;
+        if resized {
+            back.clear();
+            back.resize(len, 0);
+        }
+        let dirty = damage(self.last_bounds, bounds, self.full_redraw || resized);
+        self.full_redraw = false;
+        self.last_bounds = bounds;
+        self.compose(&mut back, width, height, dirty, hs2);
+        let result = match &mut self.gpu {
+            Some(gpu) => {
+                gpu.present_frame(&back, width, height, dirty.map(|r| (r.x, r.y, r.w, r.h)))
+            }
+            None => Ok(()),
+        };
+        self.gpu_back = back;
+        if let Err(e) = result {
+            self.drop_gpu(e);
+            self.window.request_redraw();
+        }
+    }
+
+    // 丢弃 GPU 渲染器：先释放 wgpu surface，再在同一窗口上建 softbuffer surface，下一帧整帧重绘
+    #[cfg(feature = "gpu")]
+    fn drop_gpu(&mut self, e: anyhow::Error) {
+        log::warn!("gpu overlay failed, falling back to softbuffer: {e}");
+        self.gpu = None;
+        self.gpu_back = Vec::new();
+        self.surface = match Surface::new(&self.context, self.window.clone()) {
+            Ok(surface) => Some(surface),
+            Err(e) => {
+                log::warn!("overlay softbuffer surface: {e}");
+                None
+            }
+        };
+        self.full_redraw = true;
+    }
+
+    // GPU 着色器路径；失败时丢弃 GPU 渲染器并回退 softbuffer（返回 false）
+    #[cfg(feature = "gpu")]
+    fn redraw_gpu(&mut self, handle_half: i32) -> bool {
+        let Some(gpu) = &mut self.gpu else {
+            return false;
+        };
+        let mut frame = GpuFrame {
+            selection: self.selection,
+            highlight: matches!(
+                self.mode,
+                OverlayMode::Dragging
+                    | OverlayMode::IdleWithSelection
+                    | OverlayMode::MovingSelection
+                    | OverlayMode::Resizing
+            ),
+            toolbar: self.toolbar_rect,
+            toolbar_slots: self.toolbar_layout.slots.clone(),
+            toolbar_hover: self.toolbar_hover,
+            toolbar_focus: self.toolbar_focus,
+            border: BorderStyle::Solid(0xFFFFFFFF),
+        };
+        if let (Some((sw, sh, buf)), Some(sel)) = (&self.screenshot, self.selection) {
+            frame.border = border_style((*sw, *sh, buf), sel, frame.highlight, &self.theme);
+        }
+        match gpu.render(&frame, handle_half) {
+            Ok(()) => true,
+            Err(e) => {
+                self.drop_gpu(e);
+                false
+            }
+        }
+    }
+
+    /// 裁剪当前选区，返回 (width, height, RGBA)；
+    /// 多选区且布局不是 Separate 时返回按布局合成的整图
+    pub fn take_selection_rgba(&self) -> Option<(u32, u32, Vec<u8>)> {
+        if self.others.is_empty() || self.layout == RegionLayout::Separate {
+            return self.crop(self.export_region(self.selection?));
+        }
+        let crops = self.crop_all();
+        let parts: Vec<Part> = crops
+            .iter()
+            .map(|(region, rgba)| Part {
+                region: *region,
+                rgba,
+            })
+            .collect();
+        compose_regions(self.layout, &parts)
+    }
+
+    // 其它选区在前，活动选区在最后（已按 auto_trim 收缩）
+    fn all_regions(&self) -> Vec<Region> {
+        let mut all = self.others.clone();
+        all.extend(self.selection);
+        all.into_iter().map(|r| self.export_region(r)).collect()
+    }
+
+    fn export_region(&self, region: Region) -> Region {
+        match &self.screenshot {
+            Some((sw, sh, buf)) if self.auto_trim => trim_region(buf, *sw, *sh, region),
+            _ => region,
+        }
+    }
+
+    /// 当前活动选区的屏幕坐标（嵌入 API 用，见 engine）
+    pub fn screen_selection(&self) -> Option<Rect> {
+        let sel = self.selection.filter(|_| self.visible)?;
+        Some(region_to_screen(self.origin, sel))
+    }
+
+    // 拾取模式确认：交出活动选区的屏幕坐标（已按 auto_trim 收缩）并关闭 overlay
+    fn pick_selection(&mut self) -> OverlayAction {
+        let Some(sel) = self.selection.filter(|_| self.visible) else {
+            return OverlayAction::None;
+        };
+        let rect = region_to_screen(self.origin, self.export_region(sel));
+        self.hide();
+        OverlayAction::RegionPicked(rect)
+    }
+
+    /// 溢出菜单“检测敏感信息”：交出当前选区原始像素做 OCR，overlay 保持打开
+    pub fn detect_sensitive(&self) -> OverlayAction {
+        let Some(sel) = self.selection.filter(|_| self.visible) else {
+            return OverlayAction::None;
+        };
+        match self.crop_raw(sel) {
+            Some((width, height, rgba)) => OverlayAction::DetectSensitive {
+                rgba,
+                width,
+                height,
+                origin: (sel.0, sel.1),
+                session: self.session,
+            },
+            None => OverlayAction::None,
+        }
+    }
+
+    /// 当前显示的截图编号；overlay 已关闭时为 None
+    pub fn session(&self) -> Option<u64> {
+        self.visible.then_some(self.session)
+    }
+
+    /// 回填检测结果（overlay 坐标），全部未打码，等待用户逐个单击确认；
+    /// 结果属于已关闭或已被替换的截图时忽略
+    pub fn set_redactions(&mut self, session: u64, regions: Vec<Region>) {
+        if self.session() != Some(session) {
+            return;
+        }
+        self.redactions = regions.into_iter().map(|r| (r, false)).collect();
+        self.full_redraw = true;
+        self.window.request_redraw();
+    }
+
+    /// 预选一个屏幕坐标矩形（Alt+PrintScreen 前台窗口），裁到截图范围内；与截图无交集时忽略
+    pub fn preselect(&mut self, screen_rect: (i32, i32, u32, u32)) {
+        let Some((sw, sh, _)) = &self.screenshot else {
+            return;
+        };
+        let (x, y, width, height) = screen_rect;
+        let rect = Rect {
+            x,
+            y,
+            width,
+            height,
+        };
+        let Some(region) = screen_to_region(self.origin, rect, (*sw, *sh)) else {
+            return;
+        };
+        self.selection = Some(region);
+        self.settle(OverlayMode::IdleWithSelection);
+        self.full_redraw = true;
+        self.window.request_redraw();
+    }
+
+    /// 按住热键预览：选区跟随鼠标下的顶层窗口，直到 stop_window_detect 或按下鼠标
+    pub fn start_window_detect(&mut self) {
+        self.detect_windows = Some(crate::windows_util::top_window_rects());
+        if let Some(point) = crate::windows_util::cursor_position() {
+            self.detect_at(point);
+        }
+    }
+
+    /// 结束窗口预览；返回此前是否处于预览中（鼠标已接管时为 false）
+    pub fn stop_window_detect(&mut self) -> bool {
+        self.detect_windows.take().is_some()
+    }
+
+    // 预选桌面坐标 point 下的窗口；不在任何窗口上时清除选区
+    fn detect_at(&mut self, point: (i32, i32)) {
+        let Some(rects) = &self.detect_windows else {
+            return;
+        };
+        let hit = window_at(rects, point);
+        let region = match (hit, &self.screenshot) {
+            (Some(&(x, y, width, height)), Some((sw, sh, _))) => {
+                let rect = Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                };
+                screen_to_region(self.origin, rect, (*sw, *sh))
+            }
+            _ => None,
+        };
+        if region == self.selection {
+            return;
+        }
+        self.selection = region;
+        self.settle(if region.is_some() {
+            OverlayMode::IdleWithSelection
+        } else {
+            OverlayMode::Idle
+        });
+        self.full_redraw = true;
+        self.window.request_redraw();
+    }
+
+    /// 溢出菜单“自动裁边”：就地收缩当前选区，overlay 保持打开
+    pub fn auto_trim_selection(&mut self) {
+        let (Some(sel), Some((sw, sh, buf))) = (self.selection, &self.screenshot) else {
+            return;
+        };
+        self.selection = Some(trim_region(buf, *sw, *sh, sel));
+        self.full_redraw = true;
+        self.window.request_redraw();
+    }
+
+    // 导出位置：全部选区包围盒左上角（单选区即选区本身）
+    fn export_origin(&self) -> Option<(i32, i32)> {
+        let all = self.all_regions();
+        let x = all.iter().map(|r| r.0).min()?;
+        let y = all.iter().map(|r| r.1).min()?;
+        Some((self.origin.0 + x as i32, self.origin.1 + y as i32))
+    }
+
+    // 逐个裁剪全部选区；返回的宽高为实际裁剪尺寸（超出截图的部分已截掉）
+    fn crop_all(&self) -> Vec<(Region, Vec<u8>)> {
+        self.all_regions()
+            .into_iter()
+            .filter_map(|r| self.crop(r).map(|(w, h, px)| ((r.0, r.1, w, h), px)))
+            .collect()
+    }
+
+    // Separate 布局下多选区逐个导出；其它情况返回 None，由调用方按单图处理
+    fn separate_parts(&self) -> Option<Vec<(Region, Vec<u8>)>> {
+        if self.others.is_empty() || self.layout != RegionLayout::Separate {
             return None;
         }
-        if x >= *sw || y >= *sh {
+        Some(self.crop_all())
+    }
+
+    // 导出结果是否就是屏幕原样（重新截取来源区域不会丢掉打码等编辑）
+    fn unedited(&self) -> bool {
+        unedited(
+            &self.redactions,
+            &self.adjust,
+            &self.strokes,
+            &self.excluded,
+            self.corner_radius,
+        )
+    }
+
+    // 裁剪 + 已确认的打码 + 图像调整 + 标注（标注不受调整影响）
+    fn crop(&self, region: Region) -> Option<(u32, u32, Vec<u8>)> {
+        let (rw, rh, mut out) = self.crop_base(region)?;
+        apply_strokes_rgba(&mut out, rw, rh, (region.0, region.1), &self.strokes);
+        // 笔画也不画进挖空区域
+        clear_rects(&mut out, rw, rh, (region.0, region.1), &self.excluded);
+        crate::renderer::round_corners(&mut out, rw, rh, self.corner_radius);
+        Some((rw, rh, out))
+    }
+
+    // 开启 annotation_sidecar 且有标注时：未画笔画的裁剪结果 + 裁剪区域内坐标的笔画
+    fn layers(&self, region: Region) -> Option<AnnotationLayers> {
+        if !self.annotation_sidecar || self.strokes.is_empty() {
             return None;
         }
-        let rw = w.min(sw - x);
-        let rh = h.min(sh - y);
-        let mut out: Vec<u8> = Vec::with_capacity((rw * rh * 4) as usize);
-        for row in 0..rh {
-            let start = (((y + row) * sw) + x) * 4;
-            let end = start + rw * 4;
-            out.extend_from_slice(&buf[start as usize..end as usize]);
+        let (width, height, base) = self.crop_base(region)?;
+        let strokes = translate_strokes(&self.strokes, -(region.0 as i32), -(region.1 as i32));
+        Some(AnnotationLayers {
+            width,
+            height,
+            base,
+            strokes,
+        })
+    }
+
+    // 笔画以外的编辑：已确认的打码 + 图像调整
+    fn crop_base(&self, region: Region) -> Option<(u32, u32, Vec<u8>)> {
+        let (rw, rh, mut out) = self.crop_raw(region)?;
+        for &(r, _) in self.redactions.iter().filter(|(_, applied)| *applied) {
+            // 转为裁剪结果内坐标，与选区不相交时跳过
+            let (x0, y0) = (r.0.max(region.0), r.1.max(region.1));
+            let (x1, y1) = (
+                (r.0 + r.2).min(region.0 + rw),
+                (r.1 + r.3).min(region.1 + rh),
+            );
+            if x0 < x1 && y0 < y1 {
+                let local = (x0 - region.0, y0 - region.1, x1 - x0, y1 - y0);
+                pixelate(&mut out, rw, rh, local, REDACT_BLOCK);
+            }
         }
-        let img: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::from_raw(rw, rh, out)?;
-        let mut png_data = Vec::new();
-        {
-            use image::{codecs::png::PngEncoder, ExtendedColorType, ImageEncoder};
-            let encoder = PngEncoder::new(&mut png_data);
-            if encoder
-                .write_image(img.as_raw(), rw, rh, ExtendedColorType::Rgba8)
-                .is_err()
-            {
-                return None;
+        self.adjust.apply_rgba(&mut out);
+        clear_rects(&mut out, rw, rh, (region.0, region.1), &self.excluded);
+        Some((rw, rh, out))
+    }
+
+    fn crop_raw(&self, region: Region) -> Option<(u32, u32, Vec<u8>)> {
+        let (sw, sh, ref buf) = self.screenshot.as_ref()?;
+        crop_region(buf, *sw, *sh, region)
+    }
+
+    /// 溢出菜单“复制为 Data URI”：交出选区并关闭 overlay
+    pub fn copy_selection_data_uri(&mut self) -> OverlayAction {
+        let scale = self.export_scale;
+        match self.take_selection_and_hide() {
+            Some((width, height, rgba)) => {
+                let (width, height, rgba) = scale.apply(width, height, rgba);
+                OverlayAction::CopyDataUri {
+                    rgba,
+                    width,
+                    height,
+                }
+            }
+            None => OverlayAction::None,
+        }
+    }
+
+    /// 溢出菜单“用编辑器打开”：交出选区并关闭 overlay
+    pub fn open_selection_with(&mut self) -> OverlayAction {
+        match self.take_selection_and_hide() {
+            Some((width, height, rgba)) => OverlayAction::OpenWith {
+                rgba,
+                width,
+                height,
+            },
+            None => OverlayAction::None,
+        }
+    }
+
+    /// 溢出菜单“分享”：交出选区并关闭 overlay
+    pub fn share_selection(&mut self) -> OverlayAction {
+        let scale = self.export_scale;
+        match self.take_selection_and_hide() {
+            Some((width, height, rgba)) => {
+                let (width, height, rgba) = scale.apply(width, height, rgba);
+                OverlayAction::Share {
+                    rgba,
+                    width,
+                    height,
+                }
             }
+            None => OverlayAction::None,
         }
-        Some(png_data)
+    }
+
+    /// 上传到第 destination 个 WebDAV 目标（目标选择菜单 / 只有一个目标时的“上传”）：
+    /// 交出选区并关闭 overlay
+    pub fn upload_selection(&mut self, destination: usize) -> OverlayAction {
+        let scale = self.export_scale;
+        match self.take_selection_and_hide() {
+            Some((width, height, rgba)) => {
+                let (width, height, rgba) = scale.apply(width, height, rgba);
+                OverlayAction::Upload {
+                    rgba,
+                    width,
+                    height,
+                    destination,
+                }
+            }
+            None => OverlayAction::None,
+        }
+    }
+
+    /// 溢出菜单“识别二维码”：交出选区并关闭 overlay（识别在后台进行）
+    pub fn decode_selection_qr(&mut self) -> OverlayAction {
+        match self.take_selection_and_hide() {
+            Some((width, height, rgba)) => OverlayAction::DecodeQr {
+                rgba,
+                width,
+                height,
+            },
+            None => OverlayAction::None,
+        }
+    }
+
+    /// 溢出菜单“图像信息”：交出选区并关闭 overlay（统计在后台进行）
+    pub fn selection_image_info(&mut self) -> OverlayAction {
+        match self.take_selection_and_hide() {
+            Some((width, height, rgba)) => OverlayAction::ImageInfo {
+                rgba,
+                width,
+                height,
+            },
+            None => OverlayAction::None,
+        }
+    }
+
+    fn take_selection_and_hide(&mut self) -> Option<(u32, u32, Vec<u8>)> {
+        if !self.visible {
+            return None;
+        }
+        let sel = self.take_selection_rgba()?;
+        self.hide();
+        Some(sel)
+    }
+
+    pub fn take_selection_png(&self) -> Option<Vec<u8>> {
+        let (rw, rh, out) = self.take_selection_rgba()?;
+        encode_png(&out, rw, rh).ok()
     }
 
     fn build_caches(&mut self) {
-        if let Some((w, h, ref buf)) = self.screenshot {
-            let total = (w * h) as usize;
-            let mut dim: Vec<u32> = Vec::with_capacity(total);
-            for px in buf.chunks_exact(4) {
-                let r = px[0];
-                let g = px[1];
-                let b = px[2];
-                let a = px[3];
-                let packed = u32::from_le_bytes([b, g, r, a]);
-                dim.push(mix_dim(packed));
+        if let Some((_, _, ref buf)) = self.screenshot {
+            let mut dim = std::mem::take(&mut self.pool.dim);
+            if self.live {
+                // 实时选区：选区外为纯黑，经窗口透明度呈现为变暗的实时桌面
+                dim.clear();
+                dim.resize(buf.len() / 4, 0xFF000000);
+            } else {
+                build_dim_cache(buf, &mut dim);
             }
             self.dim_cache = Some(dim);
         } else {
@@ -572,65 +2015,505 @@ impl OverlayState {
 }
 
 impl OverlayState {
+    // 纯键盘流程：Enter / 空格在无选区时选中整屏；Tab / Shift+Tab（或工具栏有焦点时的 ← →）
+    // 在工具栏按钮间移动焦点，Enter / 空格执行；方向键移动选区，Shift 调整右下边，Ctrl 每次 10 像素。
+    // Esc 逐级返回（见 step_back），Ctrl+Z 撤销最后一笔标注
+    fn handle_key(&mut self, code: KeyCode) -> OverlayAction {
+        match code {
+            KeyCode::Escape => return self.step_back(),
+            // 连拍：PageUp / PageDown 切换帧
+            KeyCode::PageUp | KeyCode::PageDown if !self.burst.is_empty() => {
+                self.step_burst(code == KeyCode::PageDown);
+                return OverlayAction::None;
+            }
+            KeyCode::KeyZ if self.ctrl_down => {
+                self.undo_stroke();
+                return OverlayAction::None;
+            }
+            // M：未选区时同 Enter，选中整个屏幕
+            KeyCode::KeyM if self.mode == OverlayMode::Idle => {
+                return self.handle_key(KeyCode::Enter);
+            }
+            KeyCode::Enter | KeyCode::NumpadEnter | KeyCode::Space => match self.mode {
+                OverlayMode::Idle => {
+                    if let Some((sw, sh, _)) = &self.screenshot {
+                        self.selection = Some((0, 0, *sw, *sh));
+                        self.settle(OverlayMode::IdleWithSelection);
+                        self.window.request_redraw();
+                    }
+                }
+                OverlayMode::IdleWithSelection | OverlayMode::Annotating => {
+                    if let Some(btn) = self.toolbar_focus {
+                        return self.execute_toolbar_button(btn);
+                    }
+                    if self.pick_only {
+                        return self.pick_selection();
+                    }
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        if !matches!(
+            self.mode,
+            OverlayMode::IdleWithSelection | OverlayMode::Annotating
+        ) {
+            return OverlayAction::None;
+        }
+        if code == KeyCode::KeyG {
+            self.guides = self.guides.next();
+            self.full_redraw = true;
+            self.window.request_redraw();
+            return OverlayAction::None;
+        }
+        let forward = match code {
+            KeyCode::Tab => Some(!self.shift_down),
+            KeyCode::ArrowLeft if self.toolbar_focus.is_some() => Some(false),
+            KeyCode::ArrowRight if self.toolbar_focus.is_some() => Some(true),
+            _ => None,
+        };
+        if let Some(forward) = forward {
+            let slots = self.toolbar_layout.slots.len();
+            self.set_toolbar_focus(Some(next_focus(self.toolbar_focus, forward, slots)));
+            self.window.request_redraw();
+            return OverlayAction::None;
+        }
+        if self.mode == OverlayMode::Annotating {
+            return OverlayAction::None; // 标注时不移动选区（笔画不随选区移动）
+        }
+        // W / H：扩展到整宽 / 整高，M：整个屏幕
+        let expand = match code {
+            KeyCode::KeyW => Some(Expand::Width),
+            KeyCode::KeyH => Some(Expand::Height),
+            KeyCode::KeyM => Some(Expand::Both),
+            _ => None,
+        };
+        if let Some(expand) = expand {
+            if let (Some(sel), Some((sw, sh, _))) = (self.selection, &self.screenshot) {
+                self.selection = Some(expand_region(sel, expand, (*sw, *sh)));
+                self.window.request_redraw();
+            }
+            return OverlayAction::None;
+        }
+        let step = if self.ctrl_down { 10 } else { 1 };
+        let (dx, dy) = match code {
+            KeyCode::ArrowLeft => (-step, 0),
+            KeyCode::ArrowRight => (step, 0),
+            KeyCode::ArrowUp => (0, -step),
+            KeyCode::ArrowDown => (0, step),
+            _ => return OverlayAction::None,
+        };
+        if let (Some(sel), Some((sw, sh, _))) = (self.selection, &self.screenshot) {
+            self.selection = Some(nudge_region(sel, dx, dy, self.shift_down, (*sw, *sh)));
+            self.window.request_redraw();
+        }
+        OverlayAction::None
+    }
+
+    fn settle(&mut self, mode: OverlayMode) {
+        self.mode_stack.settle(mode);
+        self.mode = mode;
+    }
+
+    /// Esc：退出当前模式回到上一层（标注 → 选区 → 空闲 → 关闭）。
+    /// 拖动中先取消本次拖动；退出标注保留笔画（撤销用 Ctrl+Z），退出选区丢弃全部选区与编辑
+    fn step_back(&mut self) -> OverlayAction {
+        match self.mode {
+            OverlayMode::Dragging => {
+                self.drag_start = None;
+                self.selection = self.others.pop();
+                let mode = if self.selection.is_some() {
+                    OverlayMode::IdleWithSelection
+                } else {
+                    OverlayMode::Idle
+                };
+                self.settle(mode);
+            }
+            OverlayMode::MovingSelection | OverlayMode::Resizing => {
+                self.move_offset = None;
+                self.resize_handle = None;
+                self.settle(OverlayMode::IdleWithSelection);
+            }
+            OverlayMode::Annotating | OverlayMode::IdleWithSelection | OverlayMode::Idle => {
+                if self.mode == OverlayMode::IdleWithSelection {
+                    self.selection = None;
+                    self.others.clear();
+                    self.reset_edits();
+                    self.set_toolbar_focus(None);
+                }
+                self.stroking = false;
+                match self.mode_stack.back() {
+                    Some(mode) => self.mode = mode,
+                    None => {
+                        self.hide();
+                        return OverlayAction::Canceled;
+                    }
+                }
+            }
+        }
+        let icon = match self.mode {
+            OverlayMode::Idle => CursorIcon::Crosshair,
+            _ => CursorIcon::Default,
+        };
+        self.window.set_cursor(icon);
+        self.full_redraw = true;
+        self.window.request_redraw();
+        OverlayAction::None
+    }
+
+    // Ctrl+Z：撤销最后一笔标注（标注模式与选区模式下均可）
+    fn undo_stroke(&mut self) {
+        self.stroking = false;
+        if self.strokes.pop().is_some() {
+            self.full_redraw = true;
+            self.window.request_redraw();
+        }
+    }
+
+    // 焦点按钮名称写入窗口标题：自绘工具栏没有 UIA 元素，读屏软件可朗读标题变化
+    fn set_toolbar_focus(&mut self, focus: Option<usize>) {
+        if self.toolbar_focus == focus {
+            return;
+        }
+        self.toolbar_focus = focus;
+        let label = focus.and_then(|i| self.toolbar_layout.slots.get(i));
+        let title = match label {
+            Some(slot) => format!("Snip Overlay - {}", slot.label()),
+            None => "Snip Overlay".to_string(),
+        };
+        self.window.set_title(&title);
+    }
+
     fn execute_toolbar_button(&mut self, index: usize) -> OverlayAction {
-        match index {
-            0 => {
+        match self.toolbar_layout.slots.get(index) {
+            Some(&Slot::Button(button)) => self.execute_tool(button),
+            Some(Slot::More) => {
+                // More -> 按当前溢出按钮重排菜单后弹出（阻塞到菜单关闭，选中项异步到达）
+                for (_, item) in &self.more_items {
+                    self.more_menu.remove(item).ok();
+                }
+                for b in &self.toolbar_layout.overflow {
+                    if let Some((_, item)) = self.more_items.iter().find(|(i, _)| i == b) {
+                        self.more_menu.append(item).ok();
+                    }
+                }
+                crate::windows_util::show_context_menu(
+                    &self.window,
+                    &self.more_menu,
+                    self.last_cursor,
+                );
+                OverlayAction::None
+            }
+            None => OverlayAction::None,
+        }
+    }
+
+    /// 执行工具栏按钮（工具栏点击 / 键盘 / “更多”菜单项）
+    pub fn execute_tool(&mut self, button: ToolButton) -> OverlayAction {
+        // 实时选区：除退出外的按钮都要用到像素，先截取此刻的画面（标注需要冻结的底图，
+        // 否则笔画画在色键镂空上，导出时对应的是确认那一刻的画面）
+        let froze = self.live && button != ToolButton::Exit;
+        if froze && !self.freeze_live() {
+            self.hide();
+            return OverlayAction::Canceled;
+        }
+        let action = self.run_tool(button);
+        if froze && self.visible {
+            self.window.set_visible(true);
+            self.window.request_redraw();
+        }
+        action
+    }
+
+    fn run_tool(&mut self, button: ToolButton) -> OverlayAction {
+        let exports = matches!(
+            button,
+            ToolButton::Pin
+                | ToolButton::Save
+                | ToolButton::Copy
+                | ToolButton::DataUri
+                | ToolButton::DecodeQr
+        );
+        if self.pick_only && exports {
+            return self.pick_selection();
+        }
+        match button {
+            ToolButton::Exit => {
                 // Exit
                 self.hide();
                 OverlayAction::Canceled
             }
-            1 => {
+            ToolButton::Pin => {
                 // Pin -> 生成贴图窗口，携带屏幕绝对坐标
-                if let Some(png) = self.take_selection_png() {
-                    if let Some((sx, sy, w, h)) = self.selection {
-                        let screen_x = self.origin.0 + sx as i32;
-                        let screen_y = self.origin.1 + sy as i32;
+                let refreshable = self.unedited();
+                if let Some(parts) = self.separate_parts() {
+                    let origin = self.origin;
+                    self.hide();
+                    return OverlayAction::Batch(
+                        parts
+                            .into_iter()
+                            .map(
+                                |((x, y, width, height), rgba)| OverlayAction::PasteSelection {
+                                    rgba,
+                                    width,
+                                    height,
+                                    screen_x: origin.0 + x as i32,
+                                    screen_y: origin.1 + y as i32,
+                                    refreshable,
+                                },
+                            )
+                            .collect(),
+                    );
+                }
+                if let Some((width, height, rgba)) = self.take_selection_rgba() {
+                    if let Some((screen_x, screen_y)) = self.export_origin() {
                         self.hide();
                         return OverlayAction::PasteSelection {
-                            png,
-                            width: w,
-                            height: h,
+                            rgba,
+                            width,
+                            height,
                             screen_x,
                             screen_y,
+                            // 多选区拼合的结果与屏幕上的任何区域都不对应
+                            refreshable: refreshable && self.others.is_empty(),
                         };
                     }
                     self.hide();
                 }
                 OverlayAction::None
             }
-            2 => {
-                // Save to file (简单写入当前工作目录 snip_YYYYMMDD_HHMMSS.png)
-                if let Some(png) = self.take_selection_png() {
-                    if let Err(e) = save_png_auto(&png) {
-                        eprintln!("save failed: {e}");
+            ToolButton::Save => {
+                // Save -> 编码 + 写文件在后台完成，避免大选区卡住事件处理
+                if let Some(parts) = self.separate_parts() {
+                    return OverlayAction::Batch(
+                        parts
+                            .into_iter()
+                            .map(|(region, rgba)| {
+                                let (width, height, rgba) =
+                                    self.export_scale.apply(region.2, region.3, rgba);
+                                OverlayAction::SaveSelection {
+                                    rgba,
+                                    width,
+                                    height,
+                                    layers: self.layers(region),
+                                }
+                            })
+                            .collect(),
+                    );
+                }
+                // 按布局合成的多选区整图不写 sidecar
+                let layers = self
+                    .selection
+                    .filter(|_| self.others.is_empty())
+                    .and_then(|sel| self.layers(self.export_region(sel)));
+                match self.take_selection_rgba() {
+                    Some((width, height, rgba)) => {
+                        let (width, height, rgba) = self.export_scale.apply(width, height, rgba);
+                        OverlayAction::SaveSelection {
+                            rgba,
+                            width,
+                            height,
+                            layers,
+                        }
+                    }
+                    None => OverlayAction::None,
+                }
+            }
+            ToolButton::Copy => {
+                // Copy -> 剪贴板（RGBA），成功后关闭 overlay
+                if let Some((width, height, rgba)) = self.take_selection_rgba() {
+                    let (width, height, rgba) = self.export_scale.apply(width, height, rgba);
+                    match crate::clipboard::copy_rgba(width, height, &rgba) {
+                        Ok(()) => {
+                            self.hide();
+                            return OverlayAction::Copied {
+                                rgba,
+                                width,
+                                height,
+                            };
+                        }
+                        Err(e) => log::warn!("copy failed: {e}"),
                     }
                 }
                 OverlayAction::None
             }
-            3 => {
-                // Copy (占位：暂未实现剪贴板集成)
-                // TODO: 后续可引入 arboard / copypasta 以支持 RGBA + PNG
+            ToolButton::Annotate => {
+                // Annotate：进入 / 退出画笔标注，工具栏下方显示颜色与粗细
+                if self.mode == OverlayMode::Annotating {
+                    self.settle(OverlayMode::IdleWithSelection);
+                    self.window.set_cursor(CursorIcon::Default);
+                } else if self.selection.is_some() {
+                    self.settle(OverlayMode::Annotating);
+                    self.adjust_open = false;
+                    self.window.set_cursor(CursorIcon::Crosshair);
+                }
+                self.stroking = false;
+                self.full_redraw = true;
+                self.window.request_redraw();
+                OverlayAction::None
+            }
+            ToolButton::Adjust => {
+                // Adjust：展开 / 收起调整面板（灰度 / 反色 / 亮度 / 对比度 / 饱和度），与标注互斥
+                if self.mode == OverlayMode::Annotating {
+                    self.settle(OverlayMode::IdleWithSelection);
+                }
+                self.adjust_open = !self.adjust_open;
+                self.adjust_drag = None;
+                self.full_redraw = true;
+                self.window.request_redraw();
                 OverlayAction::None
             }
-            4 => {
-                // Annotate 模式切换
-                self.mode = OverlayMode::Annotating; // 目前仅状态标记
+            ToolButton::DataUri => self.copy_selection_data_uri(),
+            ToolButton::DecodeQr => self.decode_selection_qr(),
+            ToolButton::AutoTrim => {
+                self.auto_trim_selection();
                 OverlayAction::None
             }
-            _ => OverlayAction::None,
+            ToolButton::Redact => self.detect_sensitive(),
+            ToolButton::SaveProject => match self.project() {
+                Some(project) => OverlayAction::SaveProject(Box::new(project)),
+                None => OverlayAction::None,
+            },
+            ToolButton::OpenWith => self.open_selection_with(),
+            ToolButton::Share => self.share_selection(),
+            ToolButton::ImageInfo => self.selection_image_info(),
+            // 多个目标时弹出选择菜单（阻塞到菜单关闭，选中项异步到达，见 upload_selection）
+            ToolButton::Upload => match self.upload_items.len() {
+                0 => {
+                    log::warn!("upload: no [[webdav]] destination configured");
+                    OverlayAction::None
+                }
+                1 => self.upload_selection(0),
+                _ => {
+                    crate::windows_util::show_context_menu(
+                        &self.window,
+                        &self.upload_menu,
+                        self.last_cursor,
+                    );
+                    OverlayAction::None
+                }
+            },
         }
     }
 }
 
-fn save_png_auto(data: &[u8]) -> Result<()> {
-    use std::fs;
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let ts = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-    let path = format!("snip_{ts}.png");
-    fs::write(&path, data).map_err(|e| anyhow!("write png: {e}"))
+// 选区边框样式：按边框下方像素（截图；未高亮时按变暗后的亮度）的明暗选择白色 / 黑色 / 黑白虚线，
+// 沿边框每 3 个像素采样一次；高对比度主题总是系统高亮色
+fn border_style(
+    shot: (u32, u32, &[u8]),
+    region: Region,
+    bright: bool,
+    theme: &Theme,
+) -> BorderStyle {
+    let (sw, sh, buf) = shot;
+    let (x, y, w, h) = region;
+    if theme.high_contrast || sw == 0 || sh == 0 || w == 0 || h == 0 {
+        return BorderStyle::Solid(theme.selection);
+    }
+    let (x0, y0) = (x.min(sw - 1), y.min(sh - 1));
+    let x1 = (x + w - 1).min(sw - 1);
+    let y1 = (y + h - 1).min(sh - 1);
+    let rows = (x0..=x1).step_by(3).flat_map(|i| [(i, y0), (i, y1)]);
+    let cols = (y0..=y1).step_by(3).flat_map(|j| [(x0, j), (x1, j)]);
+    let lumas = rows.chain(cols).filter_map(|(px, py)| {
+        let i = (py * sw + px) as usize * 4;
+        let &[r, g, b, a] = buf.get(i..i + 4)? else {
+            return None;
+        };
+        let l = luma(u32::from_le_bytes([b, g, r, a]));
+        // 与 mix_dim 的变暗系数一致
+        Some(if bright { l } else { (l as f32 * 0.6) as u8 })
+    });
+    BorderStyle::contrast(lumas)
+}
+
+// 选区：可选按原图亮度重绘（RGBA -> BGRA 按需转换，避免存整幅亮度缓存）+ 边框（样式见 border_style），
+// 返回所用边框样式供手柄配色
+fn draw_region(
+    frame: &mut [u32],
+    width: u32,
+    height: u32,
+    shot: (u32, u32, &[u8]),
+    region: Region,
+    bright: bool,
+    theme: &Theme,
+) -> BorderStyle {
+    let (sw, sh, buf) = shot;
+    let (x, y, w, h) = region;
+    if w == 0 || h == 0 || x >= width || y >= height {
+        return BorderStyle::Solid(theme.selection);
+    }
+    let x2 = (x + w).saturating_sub(1);
+    let y2 = (y + h).saturating_sub(1);
+    if bright && x < sw && y < sh {
+        let copy_w = w.min(sw - x).min(width - x);
+        let copy_h = h.min(sh - y).min(height - y);
+        for row in 0..copy_h {
+            let src_row_start = (((y + row) * sw) + x) as usize * 4;
+            let dst_row_start = ((y + row) * width + x) as usize;
+            for col in 0..copy_w {
+                let si = src_row_start + col as usize * 4;
+                let r = buf[si];
+                let g = buf[si + 1];
+                let b = buf[si + 2];
+                let a = buf[si + 3];
+                frame[dst_row_start + col as usize] = u32::from_le_bytes([b, g, r, a]);
+            }
+        }
+    }
+    let style = border_style(shot, region, bright, theme);
+    // 颜色按沿边的位置取（虚线），横边从 x、竖边从 y 起算
+    for i in x..=x2.min(width - 1) {
+        let color = style.color_at(i - x);
+        let top = (y.min(height - 1) * width + i) as usize;
+        frame[top] = color;
+        let bottom_y = y2.min(height - 1);
+        let bottom = (bottom_y * width + i) as usize;
+        frame[bottom] = color;
+    }
+    for j in y..=y2.min(height - 1) {
+        let color = style.color_at(j - y);
+        let left = (j * width + x.min(width - 1)) as usize;
+        frame[left] = color;
+        let right_x = x2.min(width - 1);
+        let right = (j * width + right_x) as usize;
+        frame[right] = color;
+    }
+    // 高对比度：向内加粗
+    let inner = (x as i32 + 1, y as i32 + 1, w as i32 - 2, h as i32 - 2);
+    let rest = Theme {
+        line: theme.line - 1,
+        ..*theme
+    };
+    rest.outline(frame, width, height, inner, style.primary());
+    style
+}
+
+// 对帧内选区像素（BGRA）应用调整，用于实时预览
+fn preview_filter(frame: &mut [u32], width: u32, height: u32, region: Region, filter: &Filter) {
+    let (x, y, w, h) = region;
+    // 跳过 1px 边框
+    let (x0, y0) = (x + 1, y + 1);
+    let x1 = (x + w).saturating_sub(1).min(width);
+    let y1 = (y + h).saturating_sub(1).min(height);
+    for row in y0..y1 {
+        let base = (row * width) as usize;
+        for px in &mut frame[base + x0 as usize..base + x1.max(x0) as usize] {
+            let [b, g, r, a] = px.to_le_bytes();
+            let [r, g, b] = filter.apply([r, g, b]);
+            *px = u32::from_le_bytes([b, g, r, a]);
+        }
+    }
+}
+
+/// 由 RGBA 截图生成变暗的 0RGB 帧缓冲（overlay 选区外的遮罩层）；out 先清空，复用已有容量
+pub fn build_dim_cache(rgba: &[u8], out: &mut Vec<u32>) {
+    out.clear();
+    out.reserve(rgba.len() / 4);
+    for px in rgba.chunks_exact(4) {
+        let packed = u32::from_le_bytes([px[2], px[1], px[0], px[3]]);
+        out.push(mix_dim(packed));
+    }
 }
 
 fn mix_dim(src: u32) -> u32 {
@@ -643,3 +2526,80 @@ fn mix_dim(src: u32) -> u32 {
     let db = ((b as f32) * 0.6) as u8;
     u32::from_le_bytes([db, dg, dr, a])
 }
+
+// 没有已确认的打码、图像调整、标注、挖空与圆角
+fn unedited(
+    redactions: &[(Region, bool)],
+    adjust: &Adjustments,
+    strokes: &[Stroke],
+    excluded: &[Region],
+    corner_radius: u32,
+) -> bool {
+    !redactions.iter().any(|&(_, applied)| applied)
+        && adjust.is_identity()
+        && strokes.is_empty()
+        && excluded.is_empty()
+        && corner_radius == 0
+}
+
+// 包含 point 的最上层窗口矩形（rects 按 Z 序，最上层在前）
+fn window_at(rects: &[(i32, i32, u32, u32)], point: (i32, i32)) -> Option<&(i32, i32, u32, u32)> {
+    let (px, py) = point;
+    rects
+        .iter()
+        .find(|&&(x, y, w, h)| px >= x && py >= y && (px - x) < w as i32 && (py - y) < h as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_at_topmost() {
+        let rects = [
+            (100, 100, 200, 100),
+            (0, 0, 800, 600),
+            (-1920, 0, 1920, 1080),
+        ];
+        assert_eq!(window_at(&rects, (150, 150)), Some(&rects[0]));
+        assert_eq!(window_at(&rects, (300, 150)), Some(&rects[1]));
+        assert_eq!(window_at(&rects, (-5, 10)), Some(&rects[2]));
+        assert_eq!(window_at(&rects, (900, 10)), None);
+    }
+
+    #[test]
+    fn test_redacted_pin_not_refreshable() {
+        let none = Adjustments::default();
+        assert!(unedited(&[], &none, &[], &[], 0));
+        // 检测到但未确认的打码不进导出结果
+        assert!(unedited(&[((0, 0, 10, 10), false)], &none, &[], &[], 0));
+        // 已打码：刷新会重新露出被打码的内容
+        assert!(!unedited(&[((0, 0, 10, 10), true)], &none, &[], &[], 0));
+        let gray = Adjustments {
+            grayscale: true,
+            ..none
+        };
+        assert!(!unedited(&[], &gray, &[], &[], 0));
+        assert!(!unedited(&[], &none, &[], &[(0, 0, 4, 4)], 0));
+        assert!(!unedited(&[], &none, &[], &[], 8));
+    }
+
+    #[test]
+    fn test_mode_stack_steps_back() {
+        use OverlayMode::*;
+        let mut stack = ModeStack::new();
+        stack.settle(IdleWithSelection);
+        stack.settle(Annotating);
+        // 再次进入已有的层：丢弃其上的模式
+        stack.settle(IdleWithSelection);
+        stack.settle(Annotating);
+        assert_eq!(stack.back(), Some(IdleWithSelection));
+        assert_eq!(stack.back(), Some(Idle));
+        assert_eq!(stack.back(), None);
+        // 右键撤销最后一个选区后直接回到空闲
+        let mut stack = ModeStack::new();
+        stack.settle(IdleWithSelection);
+        stack.settle(Idle);
+        assert_eq!(stack.back(), None);
+    }
+}
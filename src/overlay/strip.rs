@@ -0,0 +1,196 @@
+// strip: 连拍缩略图条——overlay 顶部居中排成一行，单击切换到该帧（PageUp / PageDown 同样可用）
+//
+// 缩略图在 show_burst 时各帧缩小一次（最近邻采样）并转成帧缓冲的 0xAARRGGBB，之后重绘只做拷贝；
+// 当前帧加选区色外框。帧数多、屏幕窄时缩小缩略图让整行放得下。
+use crate::overlay::drawing::fill_rect;
+use crate::overlay::theme::{Theme, UiScale};
+
+/// 缩略图宽度（1.0 倍 UI 缩放下的像素），高度按截图宽高比
+pub const THUMB_WIDTH: i32 = 160;
+const THUMB_MIN: i32 = 24; // 放不下时缩小到的最小宽度
+const GAP: i32 = 8; // 缩略图间距，也是整行距屏幕顶部的距离
+const PAD: i32 = 4; // 底板比缩略图外扩的边距
+
+/// 一帧的缩略图（帧缓冲像素格式）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thumb {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u32>,
+}
+
+/// count 张缩略图排一行时每张的宽度：默认 THUMB_WIDTH，超出屏幕时缩小
+pub fn thumb_width(count: usize, screen_w: u32, scale: UiScale) -> u32 {
+    let n = count.max(1) as i32;
+    let gap = scale.px(GAP);
+    let fit = (screen_w as i32 - gap * (n + 1)) / n;
+    scale.px(THUMB_WIDTH).min(fit).max(THUMB_MIN) as u32
+}
+
+/// 把 width x height 的 RGBA 缩小到 thumb_w 宽（不放大）；缓冲长度不足时为黑图
+pub fn thumbnail(width: u32, height: u32, rgba: &[u8], thumb_w: u32) -> Thumb {
+    let tw = thumb_w.min(width).max(1);
+    let th = (height as u64 * tw as u64 / width.max(1) as u64).max(1) as u32;
+    let len = (tw * th) as usize;
+    if rgba.len() < width as usize * height as usize * 4 {
+        return Thumb {
+            width: tw,
+            height: th,
+            pixels: vec![0xFF000000; len],
+        };
+    }
+    let mut pixels = Vec::with_capacity(len);
+    for y in 0..th {
+        let sy = (y as u64 * height as u64 / th as u64) as usize;
+        for x in 0..tw {
+            let sx = (x as u64 * width as u64 / tw as u64) as usize;
+            let i = (sy * width as usize + sx) * 4;
+            pixels.push(u32::from_le_bytes([
+                rgba[i + 2],
+                rgba[i + 1],
+                rgba[i],
+                0xFF,
+            ]));
+        }
+    }
+    Thumb {
+        width: tw,
+        height: th,
+        pixels,
+    }
+}
+
+/// 各缩略图在 overlay 内的矩形 (x, y, w, h)：顶部居中一行
+pub fn strip_rects(
+    count: usize,
+    thumb: (u32, u32),
+    screen_w: u32,
+    scale: UiScale,
+) -> Vec<(i32, i32, i32, i32)> {
+    let (tw, th) = (thumb.0 as i32, thumb.1 as i32);
+    let gap = scale.px(GAP);
+    let n = count as i32;
+    let total = n * tw + (n - 1).max(0) * gap;
+    let x0 = ((screen_w as i32 - total) / 2).max(0);
+    (0..n).map(|i| (x0 + i * (tw + gap), gap, tw, th)).collect()
+}
+
+/// 命中的缩略图下标
+pub fn hit_test_strip(rects: &[(i32, i32, i32, i32)], x: i32, y: i32) -> Option<usize> {
+    rects
+        .iter()
+        .position(|&(rx, ry, rw, rh)| x >= rx && x < rx + rw && y >= ry && y < ry + rh)
+}
+
+/// 整行（含底板）的包围盒
+pub fn strip_bounds(
+    rects: &[(i32, i32, i32, i32)],
+    scale: UiScale,
+) -> Option<(i32, i32, i32, i32)> {
+    let (first, last) = (rects.first()?, rects.last()?);
+    let pad = scale.px(PAD);
+    let (x0, y0) = (first.0 - pad, first.1 - pad);
+    let (x1, y1) = (last.0 + last.2 + pad, last.1 + last.3 + pad);
+    Some((x0, y0, x1 - x0, y1 - y0))
+}
+
+/// 画缩略图条：面板底色底板 + 各帧缩略图，current 帧加选区色外框
+pub fn draw_strip(
+    frame: &mut [u32],
+    width: u32,
+    height: u32,
+    thumbs: &[Thumb],
+    rects: &[(i32, i32, i32, i32)],
+    current: usize,
+    theme: &Theme,
+) {
+    let Some((bx, by, bw, bh)) = strip_bounds(rects, theme.scale) else {
+        return;
+    };
+    fill_rect(frame, width, height, bx, by, bw, bh, theme.panel);
+    theme.outline(frame, width, height, (bx, by, bw, bh), theme.panel_border);
+    for (i, (thumb, &(x, y, _, _))) in thumbs.iter().zip(rects).enumerate() {
+        blit(frame, width, height, thumb, x, y);
+        if i == current {
+            let (w, h) = (thumb.width as i32, thumb.height as i32);
+            let line = theme.line;
+            let rect = (x - line, y - line, w + 2 * line, h + 2 * line);
+            theme.outline(frame, width, height, rect, theme.selection);
+        }
+    }
+}
+
+// 拷贝缩略图到帧缓冲 (x, y)，裁到帧内
+fn blit(frame: &mut [u32], width: u32, height: u32, thumb: &Thumb, x: i32, y: i32) {
+    let (fw, fh) = (width as i32, height as i32);
+    let (tw, th) = (thumb.width as i32, thumb.height as i32);
+    let (c0, c1) = ((-x).max(0), tw.min(fw - x));
+    if c0 >= c1 {
+        return;
+    }
+    let n = (c1 - c0) as usize;
+    for row in (-y).max(0)..th.min(fh - y) {
+        let src = (row * tw + c0) as usize;
+        let dst = ((y + row) * fw + x + c0) as usize;
+        frame[dst..dst + n].copy_from_slice(&thumb.pixels[src..src + n]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thumbnail_samples_and_packs() {
+        // 4x2：左半红、右半蓝
+        let rgba: Vec<u8> = (0..8)
+            .flat_map(|i| {
+                if i % 4 < 2 {
+                    [255, 0, 0, 255]
+                } else {
+                    [0, 0, 255, 255]
+                }
+            })
+            .collect();
+        let t = thumbnail(4, 2, &rgba, 2);
+        assert_eq!((t.width, t.height), (2, 1));
+        assert_eq!(t.pixels, vec![0xFFFF0000, 0xFF0000FF]);
+        // 不放大；缓冲不足时为黑图
+        assert_eq!(thumbnail(4, 2, &rgba, 100).width, 4);
+        assert_eq!(thumbnail(4, 2, &[], 2).pixels, vec![0xFF000000; 2]);
+    }
+
+    #[test]
+    fn test_strip_layout_and_hit() {
+        let scale = UiScale::default();
+        assert_eq!(thumb_width(5, 1920, scale), THUMB_WIDTH as u32);
+        // 20 帧在 1280 宽的屏幕上缩小到放得下
+        let w = thumb_width(20, 1280, scale);
+        assert!(w < THUMB_WIDTH as u32 && 20 * (w + GAP as u32) <= 1280);
+        // 3 x 100 + 2 x 8 = 316，居中于 1000
+        let rects = strip_rects(3, (100, 50), 1000, scale);
+        assert_eq!(rects[0], (342, GAP, 100, 50));
+        assert_eq!(rects[2].0 + rects[2].2, 1000 - 342);
+        assert_eq!(hit_test_strip(&rects, 455, GAP + 10), Some(1));
+        assert_eq!(hit_test_strip(&rects, 445, GAP + 10), None); // 间隙
+        assert_eq!(hit_test_strip(&rects, 348, GAP + 50), None);
+        assert_eq!(
+            strip_bounds(&rects, scale),
+            Some((342 - PAD, GAP - PAD, 316 + 2 * PAD, 50 + 2 * PAD))
+        );
+        assert_eq!(strip_bounds(&[], scale), None);
+    }
+
+    #[test]
+    fn test_blit_clips() {
+        let thumb = Thumb {
+            width: 2,
+            height: 2,
+            pixels: vec![1, 2, 3, 4],
+        };
+        // 上移一行、右侧超出一列：只剩左下角的 3
+        let mut frame = vec![0u32; 9];
+        blit(&mut frame, 3, 3, &thumb, 2, -1);
+        assert_eq!(frame, vec![0, 0, 3, 0, 0, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,246 @@
+// theme: overlay 与工具栏 / 样式栏 / 调整面板的配色与线宽
+//
+// 默认为深色工具栏 + 1 像素白色选区边框。Windows 开启高对比度（或配置 overlay_theme =
+// "high_contrast"）时改用系统颜色（窗口底色 / 文字 / 高亮 / 按钮）并把边框加粗到 HC_LINE 像素；
+// 此时 overlay 走 softbuffer（GPU 着色器只有默认配色）。每次打开 overlay 前按当前系统设置重新解析。
+//
+// UiScale（配置 ui_scale，1.0–2.0）与系统 DPI 无关，在其之上再放大工具栏按钮、手柄与边框线宽，
+// 方便低视力用户或高密度屏幕；不为 1.0 时同样走 softbuffer。
+use serde::Deserialize;
+
+use crate::overlay::drawing::stroke_rect;
+use crate::windows_util::SystemColors;
+
+/// 高对比度下的边框线宽（像素）
+pub const HC_LINE: i32 = 2;
+
+/// UI 缩放的取值范围
+pub const UI_SCALE_MIN: f32 = 1.0;
+pub const UI_SCALE_MAX: f32 = 2.0;
+
+/// UI 缩放倍数，按百分比保存（便于比较与整数换算）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UiScale(u32);
+
+impl Default for UiScale {
+    fn default() -> Self {
+        Self(100)
+    }
+}
+
+impl UiScale {
+    /// 限制在 UI_SCALE_MIN..=UI_SCALE_MAX；非法值（NaN）视为 1.0
+    pub fn new(scale: f32) -> Self {
+        if scale.is_nan() {
+            return Self::default();
+        }
+        Self((scale.clamp(UI_SCALE_MIN, UI_SCALE_MAX) * 100.0).round() as u32)
+    }
+
+    pub fn get(self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+
+    /// 把 1.0 倍下的像素尺寸换算到当前倍数（四舍五入）
+    pub fn px(self, v: i32) -> i32 {
+        (v * self.0 as i32 + 50).div_euclid(100)
+    }
+}
+
+/// 配置 overlay_theme
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeMode {
+    /// 跟随 Windows 高对比度设置
+    #[default]
+    Auto,
+    /// 总是使用默认深色主题
+    Default,
+    /// 总是使用高对比度（系统未开启高对比度时使用黑底白字、黄色高亮）
+    HighContrast,
+}
+
+/// 系统未开启高对比度时强制 high_contrast 使用的配色（同 Windows“高对比度黑色”）
+const FALLBACK_COLORS: SystemColors = SystemColors {
+    window: 0xFF000000,
+    text: 0xFFFFFFFF,
+    highlight: 0xFFFFFF00,
+    highlight_text: 0xFF000000,
+    button: 0xFF000000,
+    button_text: 0xFFFFFFFF,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub panel: u32,        // 工具栏 / 样式栏 / 调整面板底色
+    pub panel_border: u32, // 其外框
+    pub button: u32,
+    pub button_border: u32,
+    pub icon: u32,
+    pub hover: u32, // 悬停 / 焦点按钮底色
+    pub hover_border: u32,
+    pub hover_icon: u32,
+    pub focus_ring: u32, // 键盘焦点框
+    pub selection: u32,  // 选区边框与手柄
+    pub line: i32,       // 边框线宽（已按 scale 放大）
+    pub scale: UiScale,  // 工具栏按钮 / 手柄尺寸倍数
+    pub high_contrast: bool,
+    /// 减少动效（配置 reduced_motion 或系统关闭“显示动画”）：不做任何过渡动画。
+    /// 目前 overlay 与工具栏的状态切换都是即时的，之后加入淡入淡出等动效时需检查此项
+    pub reduced_motion: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            panel: 0xFF202020,
+            panel_border: 0xFFFFFFFF,
+            button: 0xFF333333,
+            button_border: 0xFFCCCCCC,
+            icon: 0xFFFFFFFF,
+            hover: 0xFF4A4A4A,
+            hover_border: 0xFFFFFFFF,
+            hover_icon: 0xFFFFD24D,
+            focus_ring: 0xFF3DA5FF,
+            selection: 0xFFFFFFFF,
+            line: 1,
+            scale: UiScale::default(),
+            high_contrast: false,
+            reduced_motion: false,
+        }
+    }
+}
+
+impl Theme {
+    /// 高对比度主题：按钮为系统按钮色，悬停 / 焦点为系统高亮色，焦点框用文字色以与悬停区分
+    pub fn high_contrast(c: SystemColors) -> Self {
+        Self {
+            panel: c.window,
+            panel_border: c.text,
+            button: c.button,
+            button_border: c.button_text,
+            icon: c.button_text,
+            hover: c.highlight,
+            hover_border: c.highlight_text,
+            hover_icon: c.highlight_text,
+            focus_ring: c.text,
+            selection: c.highlight,
+            line: HC_LINE,
+            scale: UiScale::default(),
+            high_contrast: true,
+            reduced_motion: false,
+        }
+    }
+
+    /// 按配置与系统颜色解析主题；system 为 None 表示系统未开启高对比度
+    pub fn resolve(mode: ThemeMode, system: Option<SystemColors>, reduced_motion: bool) -> Self {
+        let theme = match (mode, system) {
+            (ThemeMode::Default, _) | (ThemeMode::Auto, None) => Self::default(),
+            (_, Some(colors)) => Self::high_contrast(colors),
+            (ThemeMode::HighContrast, None) => Self::high_contrast(FALLBACK_COLORS),
+        };
+        Self {
+            reduced_motion,
+            ..theme
+        }
+    }
+
+    /// 按当前系统设置解析（reduced_motion 为 None 时跟随系统“显示动画”）
+    pub fn detect(mode: ThemeMode, reduced_motion: Option<bool>) -> Self {
+        let system = match mode {
+            ThemeMode::Default => None,
+            _ => crate::windows_util::high_contrast_colors(),
+        };
+        let reduced = reduced_motion.unwrap_or_else(crate::windows_util::reduced_motion);
+        Self::resolve(mode, system, reduced)
+    }
+
+    /// 按 UI 缩放放大线宽（至少 1 像素）
+    pub fn scaled(self, scale: UiScale) -> Self {
+        Self {
+            line: scale.px(self.line).max(1),
+            scale,
+            ..self
+        }
+    }
+
+    /// 画 line 像素宽的矩形框（向内加粗，外沿与 1 像素时一致）
+    pub fn outline(
+        &self,
+        frame: &mut [u32],
+        width: u32,
+        height: u32,
+        rect: (i32, i32, i32, i32),
+        color: u32,
+    ) {
+        let (x, y, w, h) = rect;
+        for i in 0..self.line {
+            stroke_rect(
+                frame,
+                width,
+                height,
+                x + i,
+                y + i,
+                w - i * 2,
+                h - i * 2,
+                color,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYSTEM: SystemColors = SystemColors {
+        window: 0xFF000000,
+        text: 0xFFFFFFFF,
+        highlight: 0xFF1AEBFF,
+        highlight_text: 0xFF000000,
+        button: 0xFF101010,
+        button_text: 0xFFFFFFFF,
+    };
+
+    #[test]
+    fn test_resolve_follows_system() {
+        assert_eq!(
+            Theme::resolve(ThemeMode::Auto, None, false),
+            Theme::default()
+        );
+        let hc = Theme::resolve(ThemeMode::Auto, Some(SYSTEM), true);
+        assert!(hc.high_contrast && hc.reduced_motion);
+        assert_eq!(
+            (hc.selection, hc.hover, hc.line),
+            (0xFF1AEBFF, 0xFF1AEBFF, HC_LINE)
+        );
+        // default 忽略系统设置；high_contrast 在系统未开启时用备用配色
+        assert!(!Theme::resolve(ThemeMode::Default, Some(SYSTEM), false).high_contrast);
+        let forced = Theme::resolve(ThemeMode::HighContrast, None, false);
+        assert_eq!(forced.selection, FALLBACK_COLORS.highlight);
+        let mode: ThemeMode = serde_json::from_str("\"high_contrast\"").unwrap();
+        assert_eq!(mode, ThemeMode::HighContrast);
+    }
+
+    #[test]
+    fn test_outline_width() {
+        let (w, h) = (8, 8);
+        let mut frame = vec![0u32; 64];
+        Theme::high_contrast(SYSTEM).outline(&mut frame, w, h, (0, 0, 8, 8), 1);
+        // 外两圈被画上，中间 4x4 不变
+        assert_eq!(frame.iter().filter(|&&p| p == 1).count(), 64 - 16);
+        assert_eq!(frame[(3 * w + 3) as usize], 0);
+    }
+
+    #[test]
+    fn test_ui_scale() {
+        assert_eq!(UiScale::new(0.5), UiScale::default());
+        assert_eq!(UiScale::new(f32::NAN), UiScale::default());
+        assert_eq!(UiScale::new(3.0).get(), 2.0);
+        let s = UiScale::new(1.5);
+        assert_eq!((s.px(1), s.px(6), s.px(48)), (2, 9, 72));
+        let t = Theme::high_contrast(SYSTEM).scaled(UiScale::new(2.0));
+        assert_eq!((t.line, t.scale.px(26)), (HC_LINE * 2, 52));
+        assert_eq!(Theme::default().scaled(UiScale::new(1.2)).line, 1);
+    }
+}
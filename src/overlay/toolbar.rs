@@ -1,7 +1,155 @@
 use crate::overlay::drawing::{fill_rect, set_px, stroke_rect};
 use crate::overlay::handles::ResizeHandle; // may be used later for hover states
+use crate::overlay::theme::{Theme, UiScale};
+use serde::Deserialize;
 
-pub const TB_BUTTONS: usize = 5; // Exit / Pin / Save / Copy / Annotate
+/// 工具栏按钮；配置 `toolbar` 使用 snake_case 名称
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolButton {
+    Exit,
+    Pin,
+    Save,
+    Copy,
+    Annotate,
+    Adjust,
+    DataUri,
+    DecodeQr,
+    AutoTrim,
+    Redact,
+    SaveProject,
+    OpenWith,
+    Share,
+    ImageInfo,
+    Upload,
+}
+
+impl ToolButton {
+    pub const ALL: [ToolButton; 15] = [
+        ToolButton::Exit,
+        ToolButton::Pin,
+        ToolButton::Save,
+        ToolButton::Copy,
+        ToolButton::Annotate,
+        ToolButton::Adjust,
+        ToolButton::DataUri,
+        ToolButton::DecodeQr,
+        ToolButton::AutoTrim,
+        ToolButton::Redact,
+        ToolButton::SaveProject,
+        ToolButton::OpenWith,
+        ToolButton::Share,
+        ToolButton::ImageInfo,
+        ToolButton::Upload,
+    ];
+
+    /// 显示名称（“更多”菜单项 / 键盘焦点时的窗口标题）
+    pub fn label(self) -> &'static str {
+        match self {
+            ToolButton::Exit => "退出",
+            ToolButton::Pin => "钉图",
+            ToolButton::Save => "保存",
+            ToolButton::Copy => "复制",
+            ToolButton::Annotate => "标注",
+            ToolButton::Adjust => "调整",
+            ToolButton::DataUri => "复制为 Data URI",
+            ToolButton::DecodeQr => "识别二维码",
+            ToolButton::AutoTrim => "自动裁边",
+            ToolButton::Redact => "检测敏感信息",
+            ToolButton::SaveProject => "保存为工程",
+            ToolButton::OpenWith => "用编辑器打开",
+            ToolButton::Share => "分享",
+            ToolButton::ImageInfo => "图像信息",
+            ToolButton::Upload => "上传",
+        }
+    }
+}
+
+/// 工具栏上的一个位置：按钮或“更多”(⋯) 溢出菜单
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    Button(ToolButton),
+    More,
+}
+
+impl Slot {
+    pub fn label(self) -> &'static str {
+        match self {
+            Slot::Button(b) => b.label(),
+            Slot::More => "更多",
+        }
+    }
+}
+
+/// 工具栏按钮集合（配置顺序，去重）；未列出的按钮只出现在“更多”菜单中
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolbarSpec {
+    buttons: Vec<ToolButton>,
+}
+
+impl Default for ToolbarSpec {
+    fn default() -> Self {
+        Self::new(&[
+            ToolButton::Exit,
+            ToolButton::Pin,
+            ToolButton::Save,
+            ToolButton::Copy,
+            ToolButton::Annotate,
+        ])
+    }
+}
+
+/// 一次布局结果：工具栏位置 + “更多”菜单中的按钮
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolbarLayout {
+    pub slots: Vec<Slot>,
+    pub overflow: Vec<ToolButton>,
+}
+
+impl ToolbarSpec {
+    pub fn new(buttons: &[ToolButton]) -> Self {
+        let mut unique = Vec::with_capacity(buttons.len());
+        for &b in buttons {
+            if !unique.contains(&b) {
+                unique.push(b);
+            }
+        }
+        Self { buttons: unique }
+    }
+
+    /// 最多放 max_slots 个位置；放不下的按钮与未列出的按钮进入“更多”（占一个位置）
+    pub fn layout(&self, max_slots: usize) -> ToolbarLayout {
+        let max_slots = max_slots.max(MIN_SLOTS);
+        let extra = ToolButton::ALL
+            .into_iter()
+            .filter(|b| !self.buttons.contains(b));
+        if extra.clone().next().is_none() && self.buttons.len() <= max_slots {
+            return ToolbarLayout {
+                slots: self.buttons.iter().map(|&b| Slot::Button(b)).collect(),
+                overflow: Vec::new(),
+            };
+        }
+        let shown = self.buttons.len().min(max_slots - 1);
+        let mut slots: Vec<Slot> = self.buttons[..shown]
+            .iter()
+            .map(|&b| Slot::Button(b))
+            .collect();
+        slots.push(Slot::More);
+        let overflow = self.buttons[shown..].iter().copied().chain(extra).collect();
+        ToolbarLayout { slots, overflow }
+    }
+}
+
+/// 选区宽度能容纳的位置数（不少于 MIN_SLOTS，极窄选区时工具栏仍比选区宽）
+pub fn max_slots_for_width(width: u32, scale: UiScale) -> usize {
+    let (btn_w, gap) = (scale.px(TB_BTN_W), scale.px(TB_BTN_GAP));
+    let inner = width as i32 - scale.px(TB_BTN_PAD_X) * 2 + gap;
+    ((inner / (btn_w + gap)).max(0) as usize).max(MIN_SLOTS)
+}
+
+/// 最多位置数（全部按钮 + 更多），GPU 工具栏纹理按此分配
+pub const MAX_SLOTS: usize = ToolButton::ALL.len() + 1;
+const MIN_SLOTS: usize = 4;
 const TB_BTN_W: i32 = 48;
 const TB_BTN_H: i32 = 26;
 const TB_BTN_PAD_X: i32 = 6;
@@ -9,6 +157,26 @@ const TB_BTN_GAP: i32 = 4;
 pub const TB_MARGIN: i32 = 6;
 const INSET_PAD: i32 = 4;
 
+/// Tab / 方向键在 count 个位置间循环移动焦点；尚无焦点时从首个（forward）或末个开始
+pub fn next_focus(current: Option<usize>, forward: bool, count: usize) -> usize {
+    let count = count.max(1);
+    match (current, forward) {
+        (None, true) => 0,
+        (None, false) => count - 1,
+        (Some(i), true) => (i + 1) % count,
+        (Some(i), false) => (i + count - 1) % count,
+    }
+}
+
+/// slots 个位置的工具栏尺寸 (w, h)，按钮与间距按 UI 缩放放大
+pub fn toolbar_size(slots: usize, scale: UiScale) -> (i32, i32) {
+    let n = slots.max(1) as i32;
+    let total_w =
+        scale.px(TB_BTN_PAD_X) * 2 + n * scale.px(TB_BTN_W) + (n - 1) * scale.px(TB_BTN_GAP);
+    (total_w, scale.px(TB_BTN_H) + 2)
+}
+
+/// 整张截图内、默认 UI 缩放下的工具栏位置
 pub fn compute_toolbar_rect(
     sel_x: u32,
     sel_y: u32,
@@ -16,70 +184,85 @@ pub fn compute_toolbar_rect(
     sel_h: u32,
     screen_w: u32,
     screen_h: u32,
+    slots: usize,
+) -> Option<(i32, i32, i32, i32)> {
+    compute_toolbar_rect_in(
+        sel_x,
+        sel_y,
+        sel_w,
+        sel_h,
+        (0, 0, screen_w as i32, screen_h as i32),
+        slots,
+        UiScale::default(),
+    )
+}
+
+/// 在 bounds (x, y, w, h；overlay 坐标，通常为显示器工作区) 内放置工具栏：
+/// 优先选区下方，下方空间不足时翻到上方，都不够时嵌入选区右下角；结果始终限制在 bounds 内
+pub fn compute_toolbar_rect_in(
+    sel_x: u32,
+    sel_y: u32,
+    sel_w: u32,
+    sel_h: u32,
+    bounds: (i32, i32, i32, i32),
+    slots: usize,
+    scale: UiScale,
 ) -> Option<(i32, i32, i32, i32)> {
     if sel_w == 0 || sel_h == 0 {
         return None;
     }
-    let total_w =
-        TB_BTN_PAD_X * 2 + (TB_BUTTONS as i32) * TB_BTN_W + (TB_BUTTONS as i32 - 1) * TB_BTN_GAP;
-    let total_h = TB_BTN_H + 2;
-    let (sw, sh) = (screen_w as i32, screen_h as i32);
-    if sw <= 0 || sh <= 0 {
+    let (total_w, total_h) = toolbar_size(slots, scale);
+    let (left, top, bw, bh) = bounds;
+    if bw <= 0 || bh <= 0 {
         return None;
     }
+    let (right, bottom) = (left + bw, top + bh);
+    let clamp_x = |x: i32| x.min(right - total_w).max(left);
+    let clamp_y = |y: i32| y.min(bottom - total_h).max(top);
+    let center_x = sel_x as i32 + (sel_w as i32 / 2) - total_w / 2;
     let sel_bottom = sel_y as i32 + sel_h as i32;
-    let space_below = (sh - sel_bottom).max(0);
-    let space_above = sel_y as i32;
-    if space_below >= total_h + TB_MARGIN {
-        let bar_y = sel_bottom + TB_MARGIN;
-        let mut bar_x = sel_x as i32 + (sel_w as i32 / 2) - total_w / 2;
-        if bar_x < 0 {
-            bar_x = 0;
-        }
-        let max_x = sw - total_w;
-        if max_x < 0 {
-            bar_x = 0;
-        } else if bar_x > max_x {
-            bar_x = max_x;
-        }
-        return Some((bar_x, bar_y, total_w, total_h));
-    }
-    if space_above >= total_h + TB_MARGIN {
-        let bar_y = sel_y as i32 - TB_MARGIN - total_h;
-        if bar_y >= 0 {
-            let mut bar_x = sel_x as i32 + (sel_w as i32 / 2) - total_w / 2;
-            if bar_x < 0 {
-                bar_x = 0;
-            }
-            let max_x = sw - total_w;
-            if max_x < 0 {
-                bar_x = 0;
-            } else if bar_x > max_x {
-                bar_x = max_x;
-            }
-            return Some((bar_x, bar_y, total_w, total_h));
-        }
+    if bottom - sel_bottom >= total_h + TB_MARGIN {
+        let bar_y = (sel_bottom + TB_MARGIN).max(top);
+        return Some((clamp_x(center_x), bar_y, total_w, total_h));
     }
-    // embed bottom-right (clamped to screen)
-    let sel_w_i = sel_w as i32;
-    let sel_h_i = sel_h as i32;
-    let mut bar_x = sel_x as i32 + sel_w_i - total_w - INSET_PAD;
-    let mut bar_y = sel_y as i32 + sel_h_i - total_h - INSET_PAD;
-    if bar_x < 0 {
-        bar_x = 0;
+    if sel_y as i32 - top >= total_h + TB_MARGIN {
+        let bar_y = (sel_y as i32 - TB_MARGIN - total_h).min(bottom - total_h);
+        return Some((clamp_x(center_x), bar_y, total_w, total_h));
     }
-    if bar_y < 0 {
-        bar_y = 0;
-    }
-    let max_x = sw - total_w;
-    let max_y = sh - total_h;
-    if bar_x > max_x {
-        bar_x = max_x.max(0);
-    }
-    if bar_y > max_y {
-        bar_y = max_y.max(0);
+    // embed bottom-right (clamped to bounds)
+    let bar_x = sel_x as i32 + sel_w as i32 - total_w - INSET_PAD;
+    let bar_y = sel_bottom - total_h - INSET_PAD;
+    Some((clamp_x(bar_x), clamp_y(bar_y), total_w, total_h))
+}
+
+/// 工具栏可用区域：选区中心所在显示器的工作区（不含任务栏），换算为 overlay 坐标并与截图求交；
+/// work_areas 为虚拟桌面坐标 (x, y, w, h)，origin 为 overlay 左上角的桌面坐标。
+/// 没有匹配的工作区时为整张截图
+pub fn toolbar_bounds(
+    selection: (u32, u32, u32, u32),
+    origin: (i32, i32),
+    work_areas: &[(i32, i32, u32, u32)],
+    screen_w: u32,
+    screen_h: u32,
+) -> (i32, i32, i32, i32) {
+    let full = (0, 0, screen_w as i32, screen_h as i32);
+    let (sx, sy, sw, sh) = selection;
+    let cx = origin.0 + (sx + sw / 2) as i32;
+    let cy = origin.1 + (sy + sh / 2) as i32;
+    let contains = |&&(x, y, w, h): &&(i32, i32, u32, u32)| {
+        cx >= x && cx < x + w as i32 && cy >= y && cy < y + h as i32
+    };
+    let Some(&(x, y, w, h)) = work_areas.iter().find(contains) else {
+        return full;
+    };
+    let left = (x - origin.0).max(0);
+    let top = (y - origin.1).max(0);
+    let right = (x - origin.0 + w as i32).min(full.2);
+    let bottom = (y - origin.1 + h as i32).min(full.3);
+    if right <= left || bottom <= top {
+        return full;
     }
-    Some((bar_x, bar_y, total_w, total_h))
+    (left, top, right - left, bottom - top)
 }
 
 pub fn draw_toolbar(
@@ -90,52 +273,58 @@ pub fn draw_toolbar(
     y: i32,
     w: i32,
     h: i32,
+    slots: &[Slot],
     hovered: Option<usize>,
+    focused: Option<usize>,
+    theme: &Theme,
 ) {
     // 改为完全不透明背景，避免看到后方变暗像素导致“透视”感
-    fill_rect(frame, width, height, x, y, w, h, 0xFF202020);
-    stroke_rect(frame, width, height, x, y, w, h, 0xFFFFFFFF);
-    let mut cursor_x = x + TB_BTN_PAD_X;
+    fill_rect(frame, width, height, x, y, w, h, theme.panel);
+    theme.outline(frame, width, height, (x, y, w, h), theme.panel_border);
+    let scale = theme.scale;
+    let (btn_w, btn_h) = (scale.px(TB_BTN_W), scale.px(TB_BTN_H));
+    let mut cursor_x = x + scale.px(TB_BTN_PAD_X);
     let center_y = y + h / 2;
-    let icon_color = 0xFFFFFFFF;
-    for idx in 0..TB_BUTTONS {
+    for (idx, &slot) in slots.iter().enumerate() {
         let bx = cursor_x;
-        let by = center_y - TB_BTN_H / 2;
+        let by = center_y - btn_h / 2;
         draw_button(
             frame,
             width,
             height,
             bx,
             by,
-            TB_BTN_W,
-            TB_BTN_H,
-            idx,
-            icon_color,
+            btn_w,
+            btn_h,
+            slot,
+            theme,
             hovered == Some(idx),
+            focused == Some(idx),
         );
-        cursor_x += TB_BTN_W + TB_BTN_GAP;
+        cursor_x += btn_w + scale.px(TB_BTN_GAP);
     }
 }
 
-// 根据屏幕坐标命中哪个按钮（0..TB_BUTTONS-1）
+// 根据屏幕坐标命中第几个位置（0..slots）；bar 为工具栏 (x, y, w, h)
 pub fn hit_test_toolbar_button(
     px: i32,
     py: i32,
-    bar_x: i32,
-    bar_y: i32,
-    bar_w: i32,
-    bar_h: i32,
+    bar: (i32, i32, i32, i32),
+    slots: usize,
+    scale: UiScale,
 ) -> Option<usize> {
+    let (bar_x, bar_y, bar_w, bar_h) = bar;
     if px < bar_x || py < bar_y || px >= bar_x + bar_w || py >= bar_y + bar_h {
         return None;
     }
     // 按钮水平排布：从 bar_x + TB_BTN_PAD_X 开始
-    let mut cursor = bar_x + TB_BTN_PAD_X;
-    for idx in 0..TB_BUTTONS {
-        if px >= cursor && px < cursor + TB_BTN_W && py >= bar_y && py < bar_y + bar_h {
+    let btn_w = scale.px(TB_BTN_W);
+    let mut cursor = bar_x + scale.px(TB_BTN_PAD_X);
+    for idx in 0..slots {
+        if px >= cursor && px < cursor + btn_w {
             return Some(idx);
         }
-        cursor += TB_BTN_W + TB_BTN_GAP;
+        cursor += btn_w + scale.px(TB_BTN_GAP);
     }
     None
 }
@@ -148,56 +337,60 @@ fn draw_button(
     y: i32,
     w: i32,
     h: i32,
-    index: usize,
-    base_icon_color: u32,
+    slot: Slot,
+    theme: &Theme,
     hovered: bool,
+    focused: bool,
 ) {
-    let (bg, border, icon_color) = if hovered {
-        (0xFF4A4A4A, 0xFFFFFFFF, 0xFFFFD24D)
+    let (bg, border, icon_color) = if hovered || focused {
+        (theme.hover, theme.hover_border, theme.hover_icon)
     } else {
-        (0xFF333333, 0xFFCCCCCC, base_icon_color)
+        (theme.button, theme.button_border, theme.icon)
     };
     fill_rect(frame, width, height, x, y, w, h, bg);
-    stroke_rect(frame, width, height, x, y, w, h, border);
-    let icon_w = 12;
-    let icon_h = 12;
+    theme.outline(frame, width, height, (x, y, w, h), border);
+    if focused {
+        // 键盘焦点：比边框宽 1 像素的焦点框（默认蓝色，与鼠标悬停区分）
+        let ring = Theme {
+            line: theme.line + 1,
+            ..*theme
+        };
+        ring.outline(frame, width, height, (x, y, w, h), theme.focus_ring);
+    }
+    let icon_w = theme.scale.px(12);
+    let icon_h = icon_w;
     let ix = x + (w - icon_w) / 2;
     let iy = y + (h - icon_h) / 2;
-    match index {
-        0 => icon_exit(frame, width, height, ix, iy, icon_w, icon_h, icon_color),
-        1 => icon_pin(frame, width, height, ix, iy, icon_w, icon_h, icon_color),
-        2 => icon_save(frame, width, height, ix, iy, icon_w, icon_h, icon_color),
-        3 => icon_copy(frame, width, height, ix, iy, icon_w, icon_h, icon_color),
-        4 => icon_annotate(frame, width, height, ix, iy, icon_w, icon_h, icon_color),
-        _ => {}
-    }
+    let icon = match slot {
+        Slot::Button(ToolButton::Exit) => icon_exit,
+        Slot::Button(ToolButton::Pin) => icon_pin,
+        Slot::Button(ToolButton::Save) => icon_save,
+        Slot::Button(ToolButton::Copy) => icon_copy,
+        Slot::Button(ToolButton::Annotate) => icon_annotate,
+        Slot::Button(ToolButton::Adjust) => icon_adjust,
+        Slot::Button(ToolButton::DataUri) => icon_data_uri,
+        Slot::Button(ToolButton::DecodeQr) => icon_qr,
+        Slot::Button(ToolButton::AutoTrim) => icon_trim,
+        Slot::Button(ToolButton::Redact) => icon_redact,
+        Slot::Button(ToolButton::SaveProject) => icon_project,
+        Slot::Button(ToolButton::OpenWith) => icon_open_with,
+        Slot::Button(ToolButton::Share) => icon_share,
+        Slot::Button(ToolButton::ImageInfo) => icon_info,
+        Slot::Button(ToolButton::Upload) => icon_upload,
+        Slot::More => icon_more,
+    };
+    icon(frame, width, height, (ix, iy, icon_w, icon_h), icon_color);
 }
 
-fn icon_exit(
-    frame: &mut [u32],
-    width: u32,
-    height: u32,
-    x: i32,
-    y: i32,
-    w: i32,
-    h: i32,
-    color: u32,
-) {
+fn icon_exit(frame: &mut [u32], width: u32, height: u32, rect: (i32, i32, i32, i32), color: u32) {
+    let (x, y, w, _) = rect;
     for i in 0..w {
         set_px(frame, width, height, x + i, y + i, color);
         set_px(frame, width, height, x + (w - 1 - i), y + i, color);
     }
 }
-fn icon_pin(
-    frame: &mut [u32],
-    width: u32,
-    height: u32,
-    x: i32,
-    y: i32,
-    w: i32,
-    h: i32,
-    color: u32,
-) {
+fn icon_pin(frame: &mut [u32], width: u32, height: u32, rect: (i32, i32, i32, i32), color: u32) {
+    let (x, y, w, h) = rect;
     for xx in x..x + w {
         set_px(frame, width, height, xx, y, color);
     }
@@ -209,16 +402,8 @@ fn icon_pin(
         set_px(frame, width, height, x + w / 2 + i, y + h - 1 - i, color);
     }
 }
-fn icon_save(
-    frame: &mut [u32],
-    width: u32,
-    height: u32,
-    x: i32,
-    y: i32,
-    w: i32,
-    h: i32,
-    color: u32,
-) {
+fn icon_save(frame: &mut [u32], width: u32, height: u32, rect: (i32, i32, i32, i32), color: u32) {
+    let (x, y, w, h) = rect;
     for xx in x..x + w {
         set_px(frame, width, height, xx, y, color);
         set_px(frame, width, height, xx, y + h - 1, color);
@@ -235,16 +420,8 @@ fn icon_save(
         set_px(frame, width, height, x + w - 3, yy, color);
     }
 }
-fn icon_copy(
-    frame: &mut [u32],
-    width: u32,
-    height: u32,
-    x: i32,
-    y: i32,
-    w: i32,
-    h: i32,
-    color: u32,
-) {
+fn icon_copy(frame: &mut [u32], width: u32, height: u32, rect: (i32, i32, i32, i32), color: u32) {
+    let (x, y, w, h) = rect;
     for xx in x + 2..x + w {
         set_px(frame, width, height, xx, y + 2, color);
         set_px(frame, width, height, xx, y + h - 1, color);
@@ -266,12 +443,10 @@ fn icon_annotate(
     frame: &mut [u32],
     width: u32,
     height: u32,
-    x: i32,
-    y: i32,
-    w: i32,
-    h: i32,
+    rect: (i32, i32, i32, i32),
     color: u32,
 ) {
+    let (x, y, w, h) = rect;
     let len = w.min(h);
     for i in 0..len {
         set_px(frame, width, height, x + i, y + h - 1 - i, color);
@@ -282,3 +457,287 @@ fn icon_annotate(
         }
     }
 }
+// 调整：两条滑杆 + 滑钮
+fn icon_adjust(frame: &mut [u32], width: u32, height: u32, rect: (i32, i32, i32, i32), color: u32) {
+    let (x, y, w, h) = rect;
+    for (row, knob) in [(h / 3, w / 3), (h * 2 / 3, w * 2 / 3)] {
+        fill_rect(frame, width, height, x, y + row, w, 1, color);
+        fill_rect(frame, width, height, x + knob - 1, y + row - 2, 3, 5, color);
+    }
+}
+// Data URI：尖括号 <>
+fn icon_data_uri(
+    frame: &mut [u32],
+    width: u32,
+    height: u32,
+    rect: (i32, i32, i32, i32),
+    color: u32,
+) {
+    let (x, y, w, h) = rect;
+    let mid = h / 2;
+    for i in 0..=mid {
+        let dx = mid - i;
+        set_px(frame, width, height, x + dx / 2, y + i, color);
+        set_px(frame, width, height, x + dx / 2, y + h - 1 - i, color);
+        set_px(frame, width, height, x + w - 1 - dx / 2, y + i, color);
+        set_px(
+            frame,
+            width,
+            height,
+            x + w - 1 - dx / 2,
+            y + h - 1 - i,
+            color,
+        );
+    }
+}
+// 二维码：三个定位框 + 中心点
+fn icon_qr(frame: &mut [u32], width: u32, height: u32, rect: (i32, i32, i32, i32), color: u32) {
+    let (x, y, w, h) = rect;
+    let s = w.min(h) / 3 + 1;
+    for (fx, fy) in [(x, y), (x + w - s, y), (x, y + h - s)] {
+        stroke_rect(frame, width, height, fx, fy, s, s, color);
+    }
+    fill_rect(
+        frame,
+        width,
+        height,
+        x + w - s + 1,
+        y + h - s + 1,
+        2,
+        2,
+        color,
+    );
+}
+// 自动裁边：左上与右下两个直角
+fn icon_trim(frame: &mut [u32], width: u32, height: u32, rect: (i32, i32, i32, i32), color: u32) {
+    let (x, y, w, h) = rect;
+    for i in 0..w - 2 {
+        set_px(frame, width, height, x + 2 + i, y + 2, color);
+        set_px(frame, width, height, x + i, y + h - 3, color);
+    }
+    for i in 0..h - 2 {
+        set_px(frame, width, height, x + 2, y + i, color);
+        set_px(frame, width, height, x + w - 3, y + 2 + i, color);
+    }
+}
+// 敏感信息打码：棋盘格马赛克
+fn icon_redact(frame: &mut [u32], width: u32, height: u32, rect: (i32, i32, i32, i32), color: u32) {
+    let (x, y, w, h) = rect;
+    let cell = w.min(h) / 4;
+    for cy in 0..4 {
+        for cx in 0..4 {
+            if (cx + cy) % 2 == 0 {
+                fill_rect(
+                    frame,
+                    width,
+                    height,
+                    x + cx * cell,
+                    y + cy * cell,
+                    cell,
+                    cell,
+                    color,
+                );
+            }
+        }
+    }
+}
+// 工程文件：右上角折起的文档
+fn icon_project(
+    frame: &mut [u32],
+    width: u32,
+    height: u32,
+    rect: (i32, i32, i32, i32),
+    color: u32,
+) {
+    let (x, y, w, h) = rect;
+    let fold = w / 3;
+    for xx in x..x + w - fold {
+        set_px(frame, width, height, xx, y, color);
+    }
+    for i in 0..=fold {
+        set_px(frame, width, height, x + w - fold - 1 + i, y + i, color);
+    }
+    for xx in x..x + w {
+        set_px(frame, width, height, xx, y + h - 1, color);
+    }
+    for yy in y..y + h {
+        set_px(frame, width, height, x, yy, color);
+    }
+    for yy in y + fold..y + h {
+        set_px(frame, width, height, x + w - 1, yy, color);
+    }
+    for xx in x + 3..x + w - 3 {
+        set_px(frame, width, height, xx, y + h / 2, color);
+        set_px(frame, width, height, xx, y + h / 2 + 3, color);
+    }
+}
+// 用编辑器打开：方框 + 指向右上角外的箭头
+fn icon_open_with(
+    frame: &mut [u32],
+    width: u32,
+    height: u32,
+    rect: (i32, i32, i32, i32),
+    color: u32,
+) {
+    let (x, y, w, h) = rect;
+    let half = w / 2;
+    for i in 0..w - 2 {
+        set_px(frame, width, height, x + i, y + h - 1, color);
+        set_px(frame, width, height, x, y + 2 + i, color);
+    }
+    for i in 0..half {
+        set_px(frame, width, height, x + i, y + 2, color);
+        set_px(frame, width, height, x + w - 3, y + h - 1 - i, color);
+    }
+    for i in 0..half + 1 {
+        set_px(frame, width, height, x + w - 1 - i, y, color);
+        set_px(frame, width, height, x + w - 1, y + i, color);
+        set_px(frame, width, height, x + w - 1 - i, y + i, color);
+    }
+}
+// 分享：三个节点（右上 / 左中 / 右下）由两条线相连
+fn icon_share(frame: &mut [u32], width: u32, height: u32, rect: (i32, i32, i32, i32), color: u32) {
+    let (x, y, w, h) = rect;
+    let (left, mid) = (x + 1, y + h / 2);
+    let right = x + w - 3;
+    for i in 0..=right - left {
+        let dy = i * (mid - y - 1) / (right - left);
+        set_px(frame, width, height, left + i, mid - dy, color);
+        set_px(frame, width, height, left + i, mid + dy, color);
+    }
+    for (nx, ny) in [(right, y), (left - 1, mid - 1), (right, y + h - 3)] {
+        fill_rect(frame, width, height, nx, ny, 3, 3, color);
+    }
+}
+// 图像信息：圆角框内的 “i”
+fn icon_info(frame: &mut [u32], width: u32, height: u32, rect: (i32, i32, i32, i32), color: u32) {
+    let (x, y, w, h) = rect;
+    stroke_rect(frame, width, height, x, y, w, h, color);
+    let cx = x + w / 2 - 1;
+    fill_rect(frame, width, height, cx, y + 2, 2, 2, color);
+    fill_rect(frame, width, height, cx, y + 5, 2, h - 7, color);
+}
+// 上传：托盘底线上方的向上箭头
+fn icon_upload(frame: &mut [u32], width: u32, height: u32, rect: (i32, i32, i32, i32), color: u32) {
+    let (x, y, w, h) = rect;
+    let cx = x + w / 2 - 1;
+    fill_rect(frame, width, height, x, y + h - 2, w, 2, color);
+    fill_rect(frame, width, height, cx, y + 1, 2, h - 5, color);
+    for i in 0..w / 3 {
+        set_px(frame, width, height, cx - 1 - i, y + 2 + i, color);
+        set_px(frame, width, height, cx + 2 + i, y + 2 + i, color);
+    }
+}
+// 更多（溢出菜单）：水平三个 2x2 圆点
+fn icon_more(frame: &mut [u32], width: u32, height: u32, rect: (i32, i32, i32, i32), color: u32) {
+    let (x, y, w, h) = rect;
+    let cy = y + h / 2 - 1;
+    let step = (w - 4) / 2;
+    for i in 0..3 {
+        fill_rect(frame, width, height, x + 1 + i * step, cy, 2, 2, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_focus_wraps() {
+        assert_eq!(next_focus(None, true, 6), 0);
+        assert_eq!(next_focus(None, false, 6), 5);
+        assert_eq!(next_focus(Some(5), true, 6), 0);
+        assert_eq!(next_focus(Some(0), false, 6), 5);
+        assert_eq!(next_focus(Some(2), true, 6), 3);
+    }
+
+    #[test]
+    fn test_layout_overflows_into_more() {
+        use ToolButton::*;
+        let spec = ToolbarSpec::default();
+        let wide = spec.layout(MAX_SLOTS);
+        assert_eq!(wide.slots.len(), 6);
+        assert_eq!(wide.slots[5], Slot::More);
+        assert_eq!(
+            wide.overflow,
+            [
+                Adjust,
+                DataUri,
+                DecodeQr,
+                AutoTrim,
+                Redact,
+                SaveProject,
+                OpenWith,
+                Share,
+                ImageInfo,
+                Upload
+            ]
+        );
+        // 选区太窄：后面的按钮移入“更多”，顺序不变
+        let narrow = spec.layout(1);
+        assert_eq!(
+            narrow.slots,
+            [
+                Slot::Button(Exit),
+                Slot::Button(Pin),
+                Slot::Button(Save),
+                Slot::More
+            ]
+        );
+        assert_eq!(narrow.overflow[..2], [Copy, Annotate]);
+        // 列出全部按钮且放得下时不需要“更多”；重复项只保留第一个
+        let all = ToolbarSpec::new(&[ToolButton::ALL.as_slice(), &[Exit]].concat());
+        assert_eq!(all.layout(MAX_SLOTS).slots.len(), ToolButton::ALL.len());
+        assert!(all.layout(MAX_SLOTS).overflow.is_empty());
+    }
+
+    #[test]
+    fn test_slots_for_width() {
+        let one = UiScale::default();
+        let (w, _) = toolbar_size(6, one);
+        assert_eq!(max_slots_for_width(w as u32, one), 6);
+        assert_eq!(max_slots_for_width(w as u32 - 1, one), 5);
+        assert_eq!(max_slots_for_width(10, one), MIN_SLOTS);
+        // 2 倍：同样宽度只放得下一半按钮，命中区域随之放大
+        let two = UiScale::new(2.0);
+        let (w2, h2) = toolbar_size(6, two);
+        assert_eq!((w2, h2), (w * 2, TB_BTN_H * 2 + 2));
+        assert_eq!(max_slots_for_width(w as u32, two), MIN_SLOTS);
+        let bar = (0, 0, w2, h2);
+        assert_eq!(
+            hit_test_toolbar_button(TB_BTN_PAD_X * 2 + 60, 40, bar, 6, two),
+            Some(0)
+        );
+        assert_eq!(
+            hit_test_toolbar_button(TB_BTN_PAD_X + 60, 20, bar, 6, one),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_toolbar_stays_in_work_area() {
+        // 右侧显示器 (1920, 0) 1920x1080，底部 40px 任务栏
+        let areas = [(0, 0, 1920, 1040), (1920, 0, 1920, 1040)];
+        let bounds = toolbar_bounds((100, 900, 300, 120), (1920, 0), &areas, 1920, 1080);
+        assert_eq!(bounds, (0, 0, 1920, 1040));
+        let (tw, th) = toolbar_size(6, UiScale::default());
+        // 选区下方只剩 20px 工作区：翻到上方
+        let (x, y, w, h) =
+            compute_toolbar_rect_in(100, 900, 300, 120, bounds, 6, UiScale::default()).unwrap();
+        assert_eq!((w, h), (tw, th));
+        assert_eq!(y, 900 - TB_MARGIN - th);
+        assert!(x >= 0 && x + w <= 1920);
+        // 选区贴满工作区：嵌入右下角，不落在任务栏上
+        let (x, y, _, _) =
+            compute_toolbar_rect_in(1500, 0, 420, 1080, bounds, 6, UiScale::default()).unwrap();
+        assert!(y + th <= 1040);
+        assert!(x + tw <= 1920);
+        // 工作区越过截图边缘时求交；找不到工作区时用整张截图
+        let bounds = toolbar_bounds((10, 10, 20, 20), (0, 0), &[(-100, 0, 300, 200)], 800, 600);
+        assert_eq!(bounds, (0, 0, 200, 200));
+        assert_eq!(
+            toolbar_bounds((10, 10, 20, 20), (0, 0), &[], 800, 600),
+            (0, 0, 800, 600)
+        );
+    }
+}
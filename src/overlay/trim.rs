@@ -0,0 +1,87 @@
+// trim: 自动裁掉选区四周的纯色边（拖拽略超出窗口时去掉桌面 / 背景色边）
+//
+// 逐行 / 逐列计算亮度方差，从四边向内收缩，直到遇到方差超过阈值的行或列。
+use crate::filters::luma;
+use crate::overlay::regions::Region;
+
+/// 行 / 列亮度方差不超过此值视为纯色（容忍轻微噪声 / 抖动）
+const UNIFORM_VARIANCE: f64 = 4.0;
+
+/// 在整幅截图 (screen_w x screen_h RGBA) 上收缩 region；全部为纯色时原样返回
+pub fn trim_region(rgba: &[u8], screen_w: u32, screen_h: u32, region: Region) -> Region {
+    let (x, y, w, h) = region;
+    if w == 0 || h == 0 || x >= screen_w || y >= screen_h {
+        return region;
+    }
+    let (x1, y1) = ((x + w).min(screen_w), (y + h).min(screen_h));
+    if rgba.len() < (screen_w * screen_h * 4) as usize {
+        return region;
+    }
+    let luma_at = |px, py| {
+        let i = ((py * screen_w + px) * 4) as usize;
+        luma(rgba[i], rgba[i + 1], rgba[i + 2]) as f64
+    };
+    let row_uniform = |py: u32, xa: u32, xb: u32| uniform((xa..xb).map(|px| luma_at(px, py)));
+    let col_uniform = |px: u32, ya: u32, yb: u32| uniform((ya..yb).map(|py| luma_at(px, py)));
+    let (mut top, mut bottom) = (y, y1);
+    while top < bottom && row_uniform(top, x, x1) {
+        top += 1;
+    }
+    if top == bottom {
+        return region;
+    }
+    while bottom > top && row_uniform(bottom - 1, x, x1) {
+        bottom -= 1;
+    }
+    let (mut left, mut right) = (x, x1);
+    while left < right && col_uniform(left, top, bottom) {
+        left += 1;
+    }
+    while right > left && col_uniform(right - 1, top, bottom) {
+        right -= 1;
+    }
+    (left, top, right - left, bottom - top)
+}
+
+fn uniform(values: impl Iterator<Item = f64>) -> bool {
+    let (mut n, mut sum, mut sq) = (0.0, 0.0, 0.0);
+    for v in values {
+        n += 1.0;
+        sum += v;
+        sq += v * v;
+    }
+    if n == 0.0 {
+        return true;
+    }
+    let mean = sum / n;
+    sq / n - mean * mean <= UNIFORM_VARIANCE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 8x6 白底，(3,2)-(4,3) 黑白相间的内容
+    fn screen() -> Vec<u8> {
+        let mut px = vec![255u8; 8 * 6 * 4];
+        for (x, y) in [(3, 2), (4, 3)] {
+            let i = (y * 8 + x) * 4;
+            px[i..i + 3].fill(0);
+        }
+        px
+    }
+
+    #[test]
+    fn test_trim_uniform_border() {
+        let px = screen();
+        assert_eq!(trim_region(&px, 8, 6, (0, 0, 8, 6)), (3, 2, 2, 2));
+        assert_eq!(trim_region(&px, 8, 6, (1, 1, 5, 4)), (3, 2, 2, 2));
+    }
+
+    #[test]
+    fn test_trim_all_uniform_keeps_region() {
+        let px = vec![40u8; 8 * 6 * 4];
+        assert_eq!(trim_region(&px, 8, 6, (1, 1, 4, 4)), (1, 1, 4, 4));
+        assert_eq!(trim_region(&px, 8, 6, (9, 0, 4, 4)), (9, 0, 4, 4));
+    }
+}
@@ -1,17 +1,94 @@
 use anyhow::{anyhow, Result};
 use image::GenericImageView;
 use softbuffer::{Context, Surface};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::window_surface::{SharedWindow, SurfaceWindow};
 use winit::{
     dpi::PhysicalSize,
-    event::{ElementState, KeyEvent, MouseButton, WindowEvent},
+    event::{ElementState, KeyEvent, MouseButton, Touch, WindowEvent},
     event_loop::ActiveEventLoop,
     keyboard::{KeyCode, PhysicalKey},
     platform::windows::WindowAttributesExtWindows,
-    window::{Window, WindowAttributes, WindowLevel},
+    window::{WindowAttributes, WindowLevel},
 };
 
-// muda 右键上下文菜单（复制图像 / 销毁）
-use muda::{ContextMenu, Menu, MenuId, MenuItem as CtxMenuItem, PredefinedMenuItem};
+// muda 右键上下文菜单（复制图像 / 复制为 Data URI / 选中 / 对比 / 合并 / 销毁）
+use muda::{CheckMenuItem, Menu, MenuId, MenuItem as CtxMenuItem, PredefinedMenuItem, Submenu};
+use serde::{Deserialize, Serialize};
+
+use crate::capture::Rect;
+use crate::config::PinEscape;
+use crate::overlay::theme::UiScale;
+use crate::renderer::StitchLayout;
+use crate::touch::{TouchInput, TouchTracker};
+
+/// 缩略图模式（双击切换）的图像宽度；原图更窄时不缩放
+pub const THUMBNAIL_WIDTH: u32 = 120;
+/// 双指捏合缩放的范围（相对原图）
+pub const MIN_SCALE: f64 = 0.1;
+pub const MAX_SCALE: f64 = 4.0;
+/// 阴影样式（set_shadow）的边距：阴影在图像四周的扩散范围
+pub const SHADOW_MARGIN: u32 = 12;
+/// 阴影样式的图像圆角半径
+pub const CORNER_RADIUS: f32 = 6.0;
+
+const BORDER_MARGIN: u32 = 2; // 外 1 像素暗线 + 内 1 像素彩色/灰线（内线随 UI 缩放加粗）
+
+/// 贴图颜色标记（右键“颜色标记”）：外圈边框与非聚焦的内圈 / 阴影改为标记色，便于给参考图分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PinTag {
+    Red,
+    Yellow,
+    Green,
+    Blue,
+}
+
+impl PinTag {
+    pub const ALL: [PinTag; 4] = [PinTag::Red, PinTag::Yellow, PinTag::Green, PinTag::Blue];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PinTag::Red => "红色",
+            PinTag::Yellow => "黄色",
+            PinTag::Green => "绿色",
+            PinTag::Blue => "蓝色",
+        }
+    }
+
+    /// 边框颜色（0xAARRGGBB，与帧像素相同）
+    pub fn color(self) -> u32 {
+        match self {
+            PinTag::Red => 0xFFE5484D,
+            PinTag::Yellow => 0xFFF5C518,
+            PinTag::Green => 0xFF30A46C,
+            PinTag::Blue => 0xFF0090FF,
+        }
+    }
+}
+
+/// 贴图右键菜单条目 id（由 App 统一解析）
+pub struct PinMenuIds {
+    pub copy: MenuId,
+    pub data_uri: MenuId,
+    pub copy_rect: MenuId,
+    pub paste_rect: MenuId,
+    pub open_with: MenuId,
+    pub share: MenuId,
+    pub refresh: MenuId,
+    // “分组”子菜单：[0] 为未分组，[i + 1] 为第 i 个分组（见 set_groups）
+    pub groups: Vec<MenuId>,
+    // “颜色标记”子菜单：[0] 为无，[i + 1] 为 PinTag::ALL[i]
+    pub tags: [MenuId; 5],
+    pub select: MenuId,
+    pub compare: MenuId,
+    // 合并选中贴图：按 StitchLayout::ALL 顺序，分别钉为新贴图 / 直接保存
+    pub stitch_pin: [MenuId; 3],
+    pub stitch_save: [MenuId; 3],
+    pub destroy: MenuId,
+}
 
 // PasteWindow: 钉住的图片窗口（无边框 / 可拖动 / 置顶 / 预渲染边框提升性能）
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -23,25 +100,37 @@ enum ClickGuard {
 }
 
 pub struct PasteWindow {
-    // 原始 Window Box 指针；通过 into_raw 获取，用于最终回收
-    raw_window: *mut Window,
-    pub window: &'static Window,
-    surface: Option<Surface<&'static Window, &'static Window>>,
-    _context: Option<Context<&'static Window>>,
+    // 窗口随 PasteWindow drop 释放（从列表移除即回收，无需手动销毁）
+    pub window: SharedWindow,
+    surface: Surface<SharedWindow, SharedWindow>,
+    _context: Context<SharedWindow>,
     pub width: u32,  // 原始图像宽
     pub height: u32, // 原始图像高
-    #[allow(dead_code)]
-    margin: u32, // 边框/阴影 margin（左右上下各 margin 像素）
-    total_w: u32,    // 含 margin 的窗口像素宽
-    total_h: u32,    // 含 margin 的窗口像素高
+    margin: u32,     // 边框/阴影 margin（左右上下各 margin 像素）
+    // 阴影样式：分层窗口逐像素透明，柔和阴影 + 圆角代替 2 像素边框
+    shadow: bool,
+    // 阴影样式下已提交给分层窗口的帧（是否为聚焦帧）；None 表示帧已重建，需要重新提交
+    presented: Option<bool>,
+    total_w: u32, // 含 margin 的窗口像素宽（缩略图模式下按缩略图尺寸）
+    total_h: u32, // 含 margin 的窗口像素高
+    // 缩略图模式：窗口缩小为 THUMBNAIL_WIDTH 宽，左上角位置不变；导出仍用原图
+    thumbnail: bool,
+    // 双指捏合的显示缩放（1.0 为原尺寸）；左上角位置不变，导出仍用原图
+    scale: f64,
+    touch: TouchTracker,
+    // UI 缩放（配置 ui_scale）：加粗边框、放宽双击判定
+    ui_scale: UiScale,
+    // 上一次左键按下的时间与窗口内位置（双击检测）
+    last_press: Option<(Instant, (f64, f64))>,
     // 拖动状态
     dragging: bool,
     drag_offset: (i32, i32),
     // 焦点状态
     focused: bool,
-    // 原始图像像素（BGRA u32）
-    #[allow(dead_code)]
-    pixels: Vec<u32>,
+    // 原始图像像素（BGRA u32；与 crash 登记表共享）
+    pixels: Arc<[u32]>,
+    // crash 登记 id（崩溃时抢救贴图）
+    crash_id: u64,
     // 预渲染帧（含边框+图像）
     frame_focus: Vec<u32>,
     frame_unfocus: Vec<u32>,
@@ -51,10 +140,29 @@ pub struct PasteWindow {
     ctx_menu: Menu,
     // 菜单关闭后的单次左键防拖动守卫
     click_guard: ClickGuard,
-    pub ctx_copy_id: MenuId,
-    pub ctx_destroy_id: MenuId,
-    // 标记：等待销毁（在主循环统一回收，避免当帧内继续使用引用）
+    pub ctx_ids: PinMenuIds,
+    // 多选（对比 / 合并）：勾选状态即选中状态
+    select_item: CheckMenuItem,
+    // 标记：等待销毁（主循环从列表移除即 drop，避免在事件处理中途释放）
     pub pending_destroy: bool,
+    // Esc 的行为（配置 pin_escape）；hide 隐藏的贴图在“全部显示”前保持隐藏
+    escape: PinEscape,
+    user_hidden: bool,
+    // Ctrl+Z：请求恢复最近关闭的贴图（主循环处理）
+    restore_requested: bool,
+    ctrl_down: bool,
+    // 已通过显示亲和性排除在屏幕截图之外
+    capture_excluded: bool,
+    // 截图来源的屏幕区域（虚拟桌面坐标）；有来源时“刷新”可用
+    source: Option<Rect>,
+    refresh_item: CtxMenuItem,
+    // 所属分组（托盘“贴图分组”按组切换显示）及“分组”子菜单条目（与 ctx_ids.groups 一一对应）
+    group: Option<String>,
+    group_menu: Submenu,
+    group_items: Vec<(CheckMenuItem, Option<String>)>,
+    // 颜色标记及“颜色标记”子菜单条目（与 ctx_ids.tags 一一对应）
+    tag: Option<PinTag>,
+    tag_items: [CheckMenuItem; 5],
 }
 
 impl PasteWindow {
@@ -65,19 +173,25 @@ impl PasteWindow {
     ) -> Result<Self> {
         let img = image::load_from_memory(png_bytes)?;
         let (w, h) = img.dimensions();
-        let margin: u32 = 2; // 外 1 像素暗线 + 内 1 像素彩色/灰线
-        let total_w = w + margin * 2;
-        let total_h = h + margin * 2;
-        let mut pixels: Vec<u32> = Vec::with_capacity((w * h) as usize);
         let rgba = img.to_rgba8();
-        for px in rgba.as_raw().chunks_exact(4) {
-            // RGBA -> BGRA
-            let b = px[2];
-            let g = px[1];
-            let r = px[0];
-            let a = px[3];
-            pixels.push(u32::from_le_bytes([b, g, r, a]));
+        Self::new_from_rgba(active, w, h, rgba.as_raw(), desired_pos)
+    }
+
+    /// 直接从 RGBA 像素创建（剪贴板等非 PNG 来源）
+    pub fn new_from_rgba(
+        active: &ActiveEventLoop,
+        w: u32,
+        h: u32,
+        rgba: &[u8],
+        desired_pos: Option<(i32, i32)>,
+    ) -> Result<Self> {
+        if w == 0 || h == 0 || rgba.len() < (w * h * 4) as usize {
+            return Err(anyhow!("invalid image {w}x{h}"));
         }
+        let margin = BORDER_MARGIN;
+        let total_w = w + margin * 2;
+        let total_h = h + margin * 2;
+        let pixels = to_pixels(w, h, rgba);
         // 使用物理像素尺寸（含 margin）
         let attrs = WindowAttributes::default()
             .with_title("Snip Paste")
@@ -87,22 +201,17 @@ impl PasteWindow {
             .with_window_level(WindowLevel::AlwaysOnTop)
             .with_inner_size(PhysicalSize::new(total_w, total_h))
             .with_skip_taskbar(true);
-        let win = active.create_window(attrs)?;
+        let SurfaceWindow {
+            window: win,
+            mut surface,
+            context,
+        } = SurfaceWindow::create(active, attrs, "paste")?;
         if let Some((x, y)) = desired_pos {
             // 目标位置应与选区左上对齐，窗口包含 margin 需向左上偏移 margin
             let px = x - margin as i32;
             let py = y - margin as i32;
             win.set_outer_position(winit::dpi::PhysicalPosition::new(px, py));
         }
-        let boxed = Box::new(win);
-        let raw_window = Box::into_raw(boxed);
-        let win: &'static Window = unsafe { &*raw_window };
-
-        // 禁用淡入淡出动画确保显示/隐藏即时反馈（Windows 平台）
-        crate::windows_util::disable_window_transitions(win);
-
-        let context = Context::new(win).map_err(|e| anyhow!("paste ctx: {e}"))?;
-        let mut surface = Surface::new(&context, win).map_err(|e| anyhow!("paste surface: {e}"))?;
         use std::num::NonZeroU32;
         surface
             .resize(
@@ -110,47 +219,132 @@ impl PasteWindow {
                 NonZeroU32::new(total_h.max(1)).unwrap(),
             )
             .map_err(|e| anyhow!("paste resize: {e}"))?;
-        let (frame_focus, frame_unfocus) = build_frames(&pixels, w, h, margin);
+        let (frame_focus, frame_unfocus) = build_frames(&pixels, w, h, margin, None);
+        let pixels: Arc<[u32]> = pixels.into();
 
-        // 构建右键菜单（两组：复制图像 | 分隔 | 销毁）
-        // 使用 Menu 构建，再通过 ContextMenu trait 提供 show_context_menu_for_hwnd 能力
+        // 构建右键菜单（三组：复制图像 / 复制为 Data URI / 复制位置 / 粘贴位置 / 用编辑器打开 / 分享 / 刷新 | 分组 / 颜色标记 / 选中 / 对比 / 合并 | 销毁）
+        // 使用 Menu 构建，弹出由 windows_util::show_context_menu 完成
         let ctx_menu = Menu::new();
         let copy_item = CtxMenuItem::new("复制图像", true, None);
+        let data_uri_item = CtxMenuItem::new("复制为 Data URI", true, None);
+        let copy_rect_item = CtxMenuItem::new("复制位置", true, None);
+        let paste_rect_item = CtxMenuItem::new("粘贴位置", true, None);
+        let open_with_item = CtxMenuItem::new("用编辑器打开", true, None);
+        let share_item = CtxMenuItem::new("分享", true, None);
+        // 只有记录了屏幕区域（set_source）的贴图可以刷新
+        let refresh_item = CtxMenuItem::new("刷新", false, None);
+        // 分组条目由 set_groups 按配置填充
+        let group_menu = Submenu::new("分组", true);
+        let tag_menu = Submenu::new("颜色标记", true);
+        let tag_items: [CheckMenuItem; 5] = std::array::from_fn(|i| {
+            let label = i.checked_sub(1).map_or("无", |t| PinTag::ALL[t].label());
+            let item = CheckMenuItem::new(label, true, i == 0, None);
+            tag_menu.append(&item).ok();
+            item
+        });
+        let select_item = CheckMenuItem::new("选中", true, false, None);
+        let compare_item = CtxMenuItem::new("对比选中贴图", true, None);
+        let stitch_pin_menu = Submenu::new("合并选中贴图", true);
+        let stitch_save_menu = Submenu::new("合并选中贴图并保存", true);
+        let stitch_items = |menu: &Submenu| {
+            StitchLayout::ALL.map(|layout| {
+                let item = CtxMenuItem::new(layout.label(), true, None);
+                menu.append(&item).ok();
+                item.id().clone()
+            })
+        };
+        let stitch_pin = stitch_items(&stitch_pin_menu);
+        let stitch_save = stitch_items(&stitch_save_menu);
         let destroy_item = CtxMenuItem::new("销毁", true, None);
-        let copy_id = copy_item.id().clone();
-        let destroy_id = destroy_item.id().clone();
+        let ctx_ids = PinMenuIds {
+            copy: copy_item.id().clone(),
+            data_uri: data_uri_item.id().clone(),
+            copy_rect: copy_rect_item.id().clone(),
+            paste_rect: paste_rect_item.id().clone(),
+            open_with: open_with_item.id().clone(),
+            share: share_item.id().clone(),
+            refresh: refresh_item.id().clone(),
+            groups: Vec::new(),
+            tags: std::array::from_fn(|i| tag_items[i].id().clone()),
+            select: select_item.id().clone(),
+            compare: compare_item.id().clone(),
+            stitch_pin,
+            stitch_save,
+            destroy: destroy_item.id().clone(),
+        };
         ctx_menu.append(&copy_item).ok();
+        ctx_menu.append(&data_uri_item).ok();
+        ctx_menu.append(&copy_rect_item).ok();
+        ctx_menu.append(&paste_rect_item).ok();
+        ctx_menu.append(&open_with_item).ok();
+        ctx_menu.append(&share_item).ok();
+        ctx_menu.append(&refresh_item).ok();
+        ctx_menu.append(&PredefinedMenuItem::separator()).ok();
+        ctx_menu.append(&group_menu).ok();
+        ctx_menu.append(&tag_menu).ok();
+        ctx_menu.append(&select_item).ok();
+        ctx_menu.append(&compare_item).ok();
+        ctx_menu.append(&stitch_pin_menu).ok();
+        ctx_menu.append(&stitch_save_menu).ok();
         ctx_menu.append(&PredefinedMenuItem::separator()).ok();
         ctx_menu.append(&destroy_item).ok();
 
         win.set_visible(true);
+        let pos = match desired_pos {
+            Some(pos) => pos,
+            None => win
+                .outer_position()
+                .map(|p| (p.x + margin as i32, p.y + margin as i32))
+                .unwrap_or_default(),
+        };
+        let crash_id = crate::crash::register_pin(pos, w, h, pixels.clone());
         Ok(Self {
-            raw_window,
             window: win,
-            surface: Some(surface),
-            _context: Some(context),
+            surface,
+            _context: context,
             width: w,
             height: h,
             margin,
+            shadow: false,
+            presented: None,
             total_w,
             total_h,
+            thumbnail: false,
+            scale: 1.0,
+            touch: TouchTracker::default(),
+            ui_scale: UiScale::default(),
+            last_press: None,
             dragging: false,
             drag_offset: (0, 0),
             focused: true,
             pixels,
+            crash_id,
             frame_focus,
             frame_unfocus,
             last_local_cursor: (0.0, 0.0),
             ctx_menu,
             click_guard: ClickGuard::Idle,
-            ctx_copy_id: copy_id,
-            ctx_destroy_id: destroy_id,
+            ctx_ids,
+            select_item,
             pending_destroy: false,
+            escape: PinEscape::default(),
+            user_hidden: false,
+            restore_requested: false,
+            ctrl_down: false,
+            capture_excluded: false,
+            source: None,
+            refresh_item,
+            group: None,
+            group_menu,
+            group_items: Vec::new(),
+            tag: None,
+            tag_items,
         })
     }
 
     pub fn handle_event(&mut self, event: &WindowEvent) {
         match event {
+            WindowEvent::Touch(t) => self.handle_touch(t),
             WindowEvent::CursorMoved { position, .. } => {
                 // 记录窗口内局部坐标（逻辑像素）
                 self.last_local_cursor = (position.x, position.y);
@@ -171,9 +365,15 @@ impl PasteWindow {
                             self.click_guard = ClickGuard::Idle;
                             return; // 不进入拖动
                         }
+                        if self.is_double_click() {
+                            self.last_press = None;
+                            self.toggle_thumbnail();
+                            return;
+                        }
+                        self.last_press = Some((Instant::now(), self.last_local_cursor));
 
                         self.dragging = true;
-                        self.focused = true;
+                        self.set_focused(true);
                         self.drag_offset = (
                             self.last_local_cursor.0 as i32,
                             self.last_local_cursor.1 as i32,
@@ -183,6 +383,12 @@ impl PasteWindow {
                 },
                 ElementState::Released => match button {
                     MouseButton::Left => {
+                        if self.dragging {
+                            if let Ok(p) = self.window.outer_position() {
+                                let m = self.margin as i32;
+                                crate::crash::update_pin(self.crash_id, (p.x + m, p.y + m));
+                            }
+                        }
                         self.dragging = false;
                     }
                     MouseButton::Right => {
@@ -191,25 +397,43 @@ impl PasteWindow {
                     _ => {}
                 },
             },
+            WindowEvent::ModifiersChanged(m) => {
+                self.ctrl_down = m.state().control_key();
+            }
             WindowEvent::KeyboardInput {
                 event:
                     KeyEvent {
-                        physical_key: PhysicalKey::Code(KeyCode::Escape),
+                        physical_key: PhysicalKey::Code(code),
                         state: ElementState::Pressed,
                         ..
                     },
                 ..
-            } => {
-                // 标记销毁，交由主循环统一回收释放资源
-                self.pending_destroy = true;
-            }
-            WindowEvent::Focused(f) => {
-                self.focused = *f;
-            }
+            } => match code {
+                KeyCode::Escape => match self.escape {
+                    // 标记销毁，交由主循环统一回收释放资源
+                    PinEscape::Close => self.pending_destroy = true,
+                    PinEscape::Hide => {
+                        self.user_hidden = true;
+                        self.window.set_visible(false);
+                    }
+                    PinEscape::Ignore => {}
+                },
+                KeyCode::KeyZ if self.ctrl_down => self.restore_requested = true,
+                _ => {}
+            },
+            WindowEvent::Focused(f) => self.set_focused(*f),
             _ => {}
         }
     }
 
+    // 焦点变化切换高亮边框：只在变化时请求重绘
+    fn set_focused(&mut self, focused: bool) {
+        if self.focused != focused {
+            self.focused = focused;
+            self.window.request_redraw();
+        }
+    }
+
     pub fn redraw(&mut self, window_id: winit::window::WindowId) {
         if window_id != self.window.id() {
             return;
@@ -223,83 +447,447 @@ impl PasteWindow {
                 .request_inner_size(PhysicalSize::new(self.total_w, self.total_h));
         }
 
-        if let Some(surf) = &mut self.surface {
-            if let Ok(mut buf) = surf.buffer_mut() {
-                let src = if self.focused {
-                    &self.frame_focus
-                } else {
-                    &self.frame_unfocus
-                };
-                let need = (self.total_w * self.total_h) as usize;
-                if buf.len() >= need && src.len() == need {
-                    buf[..need].copy_from_slice(src);
-                }
-                let _ = buf.present();
+        // 选中的贴图保持高亮边框，便于看出参与对比 / 合并的窗口
+        let highlight = self.focused || self.select_item.is_checked();
+        let src = if highlight {
+            &self.frame_focus
+        } else {
+            &self.frame_unfocus
+        };
+        if self.shadow {
+            // 分层窗口保留提交的内容：帧不变时不重复提交（系统重绘请求也会走到这里）
+            if self.presented != Some(highlight)
+                && crate::windows_util::update_layered_window(
+                    &self.window,
+                    self.total_w,
+                    self.total_h,
+                    src,
+                )
+            {
+                self.presented = Some(highlight);
             }
+            return;
+        }
+        if let Ok(mut buf) = self.surface.buffer_mut() {
+            let need = (self.total_w * self.total_h) as usize;
+            if buf.len() >= need && src.len() == need {
+                buf[..need].copy_from_slice(src);
+            }
+            let _ = buf.present();
         }
     }
 }
 
 impl PasteWindow {
     fn show_context_menu(&mut self) {
-        #[cfg(target_os = "windows")]
-        unsafe {
-            use windows::Win32::Foundation::HWND;
-            use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
-            let hwnd = if let Ok(h) = self.window.window_handle() {
-                if let RawWindowHandle::Win32(w) = h.as_raw() {
-                    HWND(w.hwnd.get() as *mut _)
-                } else {
-                    return;
-                }
-            } else {
-                return;
-            };
-
-            let (x, y) = self.last_local_cursor;
-            let pos = muda::dpi::PhysicalPosition { x, y };
-            self.ctx_menu
-                .show_context_menu_for_hwnd(hwnd.0 as isize, Some(pos.into()));
+        if crate::windows_util::show_context_menu(
+            &self.window,
+            &self.ctx_menu,
+            self.last_local_cursor,
+        ) {
             // 标记：下一次左键按下（用于关闭菜单）不启动拖动
             self.click_guard = ClickGuard::SkipNext;
         }
-        #[cfg(target_os = "linux")]
-        {
-            // TODO: gtk handle; 暂不实现
+    }
+
+    /// 是否在右键菜单中勾选了“选中”
+    pub fn is_selected(&self) -> bool {
+        self.select_item.is_checked()
+    }
+
+    pub fn is_pending_destroy(&self) -> bool {
+        self.pending_destroy
+    }
+
+    pub fn set_escape(&mut self, escape: PinEscape) {
+        self.escape = escape;
+    }
+
+    /// 是否被 Esc（pin_escape = "hide"）隐藏
+    pub fn is_user_hidden(&self) -> bool {
+        self.user_hidden
+    }
+
+    /// 清除 Esc 隐藏标记（可见性由调用方按分组重新设置）
+    pub fn clear_user_hidden(&mut self) {
+        self.user_hidden = false;
+    }
+
+    /// 取出 Ctrl+Z 恢复请求
+    pub fn take_restore_request(&mut self) -> bool {
+        std::mem::take(&mut self.restore_requested)
+    }
+
+    /// 关闭前的快照（原图、图像左上角位置、分组、颜色标记），供“恢复关闭的贴图”重建
+    pub fn snapshot(&self) -> crate::crash::RecoveredPin {
+        let m = self.margin as i32;
+        crate::crash::RecoveredPin {
+            pos: self
+                .window
+                .outer_position()
+                .map(|p| (p.x + m, p.y + m))
+                .unwrap_or_default(),
+            width: self.width,
+            height: self.height,
+            rgba: self.image_rgba(),
+            group: self.group.clone(),
+            tag: self.tag,
         }
-        #[cfg(target_os = "macos")]
-        unsafe {
-            // TODO: nsview handle; 暂不实现
+    }
+
+    /// 让截图不包含本窗口；系统不支持时返回 false（调用方改为截图期间临时隐藏）
+    pub fn exclude_from_capture(&mut self) -> bool {
+        self.capture_excluded = crate::windows_util::set_exclude_from_capture(&self.window, true);
+        self.capture_excluded
+    }
+
+    pub fn is_capture_excluded(&self) -> bool {
+        self.capture_excluded
+    }
+
+    /// 图像（不含边框 / 阴影）在屏幕上的矩形，物理像素；缩略图模式下为缩略图尺寸
+    pub fn screen_rect(&self) -> Option<Rect> {
+        let p = self.window.outer_position().ok()?;
+        let m = self.margin as i32;
+        Some(Rect {
+            x: p.x + m,
+            y: p.y + m,
+            width: self.total_w - self.margin * 2,
+            height: self.total_h - self.margin * 2,
+        })
+    }
+
+    /// 把图像左上角移动到屏幕坐标 (x, y)（“粘贴位置”）
+    pub fn move_to(&mut self, x: i32, y: i32) {
+        let m = self.margin as i32;
+        self.window
+            .set_outer_position(winit::dpi::PhysicalPosition::new(x - m, y - m));
+        crate::crash::update_pin(self.crash_id, (x, y));
+    }
+
+    /// 记录截图来源的屏幕区域，启用右键“刷新”
+    pub fn set_source(&mut self, rect: Rect) {
+        self.source = Some(rect);
+        self.refresh_item.set_enabled(true);
+    }
+
+    pub fn source(&self) -> Option<Rect> {
+        self.source
+    }
+
+    /// 按分组名称重建“分组”子菜单（首项为“未分组”），勾选当前分组
+    pub fn set_groups(&mut self, names: &[String]) {
+        while self.group_menu.remove_at(0).is_some() {}
+        let entries = std::iter::once(("未分组", None))
+            .chain(names.iter().map(|n| (n.as_str(), Some(n.clone()))));
+        self.group_items = entries
+            .map(|(label, group)| {
+                let item = CheckMenuItem::new(label, true, group == self.group, None);
+                self.group_menu.append(&item).ok();
+                (item, group)
+            })
+            .collect();
+        self.ctx_ids.groups = self
+            .group_items
+            .iter()
+            .map(|(item, _)| item.id().clone())
+            .collect();
+    }
+
+    /// 设置所属分组（None 为未分组），同步菜单勾选与崩溃恢复登记
+    pub fn set_group(&mut self, group: Option<String>) {
+        for (item, g) in &self.group_items {
+            item.set_checked(*g == group);
         }
+        crate::crash::update_pin_group(self.crash_id, group.clone());
+        self.group = group;
     }
 
-    // 真正资源销毁：释放 surface/context 并回收 Window Box
-    pub fn destroy(&mut self) {
-        if self.raw_window.is_null() {
+    pub fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    /// 设置颜色标记（None 为无），重建边框帧并同步菜单勾选与崩溃恢复登记
+    pub fn set_tag(&mut self, tag: Option<PinTag>) {
+        let tags = std::iter::once(None).chain(PinTag::ALL.map(Some));
+        for (item, t) in self.tag_items.iter().zip(tags) {
+            item.set_checked(t == tag);
+        }
+        crate::crash::update_pin_tag(self.crash_id, tag);
+        if tag != self.tag {
+            self.tag = tag;
+            if let Err(e) = self.rebuild_frames() {
+                log::warn!("pin tag: {e}");
+            }
+        }
+    }
+
+    pub fn tag(&self) -> Option<PinTag> {
+        self.tag
+    }
+
+    /// 切换阴影样式（柔和阴影 + 圆角，需要逐像素透明的分层窗口）；系统不支持时返回 false。
+    /// 边距随样式变化，窗口相应移动，图像在屏幕上的位置不变
+    pub fn set_shadow(&mut self, shadow: bool) -> bool {
+        if shadow == self.shadow {
+            return true;
+        }
+        if !crate::windows_util::set_per_pixel_alpha(&self.window, shadow) {
+            return false;
+        }
+        self.shadow = shadow;
+        if let Err(e) = self.update_margin() {
+            log::warn!("pin shadow: {e}");
+        }
+        true
+    }
+
+    /// 设置 UI 缩放：无阴影时边框内线按倍数加粗，双击允许的位移同样放大
+    pub fn set_ui_scale(&mut self, scale: UiScale) {
+        if scale == self.ui_scale {
             return;
         }
-        self.window.set_visible(false);
-        if let Some(s) = self.surface.take() {
-            drop(s);
+        self.ui_scale = scale;
+        if let Err(e) = self.update_margin() {
+            log::warn!("pin ui scale: {e}");
         }
-        if let Some(c) = self._context.take() {
-            drop(c);
+    }
+
+    // 按阴影样式 / UI 缩放重新计算边距并重建帧；窗口相应移动，图像在屏幕上的位置不变
+    fn update_margin(&mut self) -> Result<()> {
+        let old_margin = self.margin as i32;
+        self.margin = if self.shadow {
+            SHADOW_MARGIN
+        } else {
+            border_margin(self.ui_scale)
+        };
+        if let Ok(p) = self.window.outer_position() {
+            let shift = self.margin as i32 - old_margin;
+            self.window
+                .set_outer_position(winit::dpi::PhysicalPosition::new(p.x - shift, p.y - shift));
         }
-        let raw = self.raw_window;
-        self.raw_window = std::ptr::null_mut();
-        unsafe {
-            drop(Box::from_raw(raw));
+        self.rebuild_frames()
+    }
+
+    pub fn is_thumbnail(&self) -> bool {
+        self.thumbnail
+    }
+
+    /// 切换缩略图模式（双击贴图）：按缩略图 / 原图尺寸重建边框帧并调整窗口
+    pub fn toggle_thumbnail(&mut self) {
+        self.thumbnail = !self.thumbnail;
+        if let Err(e) = self.rebuild_frames() {
+            log::warn!("toggle pin thumbnail: {e}");
+            self.thumbnail = !self.thumbnail;
         }
-        log::debug!("paste window resources destroyed");
     }
 
-    pub fn is_pending_destroy(&self) -> bool {
-        self.pending_destroy
+    // 双指捏合缩放；单指的拖动与双击由系统据触摸生成的鼠标消息处理
+    fn handle_touch(&mut self, t: &Touch) {
+        match self
+            .touch
+            .update(t.id, t.phase, (t.location.x, t.location.y))
+        {
+            // 第二根手指落下：结束第一根手指开始的拖动
+            TouchInput::PinchStarted => self.dragging = false,
+            TouchInput::Pinch(ratio) => self.zoom_by(ratio),
+            TouchInput::None => {}
+        }
     }
+
+    /// 按比例缩放显示尺寸（限制在 MIN_SCALE..=MAX_SCALE）；缩略图模式下不缩放
+    pub fn zoom_by(&mut self, ratio: f64) {
+        let scale = (self.scale * ratio).clamp(MIN_SCALE, MAX_SCALE);
+        if self.thumbnail || !scale.is_finite() {
+            return;
+        }
+        let unchanged = scaled_size(self.width, self.height, scale)
+            == scaled_size(self.width, self.height, self.scale);
+        self.scale = scale;
+        if unchanged {
+            return;
+        }
+        if let Err(e) = self.rebuild_frames() {
+            log::warn!("zoom pin: {e}");
+        }
+    }
+
+    // 与上一次左键按下的间隔与位移都在系统双击阈值内
+    fn is_double_click(&self) -> bool {
+        let Some((at, (x, y))) = self.last_press else {
+            return false;
+        };
+        let (cx, cy) = self.last_local_cursor;
+        let slop = DOUBLE_CLICK_SLOP * self.ui_scale.get();
+        at.elapsed() <= crate::windows_util::double_click_time()
+            && (cx - x).abs() <= slop
+            && (cy - y).abs() <= slop
+    }
+
+    // 按当前模式重建显示帧（缩略图模式 / 捏合缩放先缩放原图），尺寸变化时调整 surface 与窗口
+    fn rebuild_frames(&mut self) -> Result<()> {
+        let (w, h) = if self.thumbnail {
+            thumbnail_size(self.width, self.height)
+        } else {
+            scaled_size(self.width, self.height, self.scale)
+        };
+        let total_w = w + self.margin * 2;
+        let total_h = h + self.margin * 2;
+        if (total_w, total_h) != (self.total_w, self.total_h) {
+            use std::num::NonZeroU32;
+            self.surface
+                .resize(
+                    NonZeroU32::new(total_w).unwrap(),
+                    NonZeroU32::new(total_h).unwrap(),
+                )
+                .map_err(|e| anyhow!("paste resize: {e}"))?;
+            (self.total_w, self.total_h) = (total_w, total_h);
+            let _ = self
+                .window
+                .request_inner_size(PhysicalSize::new(total_w, total_h));
+        }
+        let build = if self.shadow {
+            build_shadow_frames
+        } else {
+            build_frames
+        };
+        (self.frame_focus, self.frame_unfocus) = if (w, h) == (self.width, self.height) {
+            build(&self.pixels, w, h, self.margin, self.tag)
+        } else {
+            let scaled = downscale(&self.pixels, self.width, self.height, w, h);
+            build(&scaled, w, h, self.margin, self.tag)
+        };
+        self.presented = None;
+        self.window.request_redraw();
+        Ok(())
+    }
+
+    /// 原地替换贴图图像（“刷新”重新截取来源区域后调用）；窗口位置不变，尺寸随图像调整，
+    /// 缩略图模式保持不变
+    pub fn replace_image(&mut self, w: u32, h: u32, rgba: &[u8]) -> Result<()> {
+        if w == 0 || h == 0 || rgba.len() < (w * h * 4) as usize {
+            return Err(anyhow!("invalid image {w}x{h}"));
+        }
+        let old = (self.width, self.height, std::mem::take(&mut self.pixels));
+        (self.width, self.height) = (w, h);
+        self.pixels = to_pixels(w, h, rgba).into();
+        if let Err(e) = self.rebuild_frames() {
+            (self.width, self.height, self.pixels) = old;
+            return Err(e);
+        }
+        // crash 登记表持有旧像素：按当前位置重新登记
+        crate::crash::unregister_pin(self.crash_id);
+        let m = self.margin as i32;
+        let pos = self
+            .window
+            .outer_position()
+            .map(|p| (p.x + m, p.y + m))
+            .unwrap_or_default();
+        self.crash_id = crate::crash::register_pin(pos, w, h, self.pixels.clone());
+        crate::crash::update_pin_group(self.crash_id, self.group.clone());
+        crate::crash::update_pin_tag(self.crash_id, self.tag);
+        self.window.request_redraw();
+        Ok(())
+    }
+
+    /// 原始图像转回 RGBA（剪贴板 / 导出用）
+    pub fn image_rgba(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.pixels.len() * 4);
+        for px in self.pixels.iter() {
+            let [b, g, r, a] = px.to_le_bytes();
+            out.extend_from_slice(&[r, g, b, a]);
+        }
+        out
+    }
+}
+
+impl Drop for PasteWindow {
+    fn drop(&mut self) {
+        crate::crash::unregister_pin(self.crash_id);
+    }
+}
+
+/// “复制位置”的文本：`x y width height`（与 --pick-region 的输出相同）
+pub fn rect_text(rect: Rect) -> String {
+    format!("{} {} {} {}", rect.x, rect.y, rect.width, rect.height)
+}
+
+/// “粘贴位置”：从文本中取前两个整数作为图像左上角 (x, y)；
+/// 分隔符不限（空格 / 逗号 / JSON 等），其后的宽高忽略（贴图尺寸由图像决定）
+pub fn parse_position(text: &str) -> Option<(i32, i32)> {
+    let mut numbers = text
+        .split(|c: char| !(c.is_ascii_digit() || c == '-'))
+        .filter(|t| !t.is_empty())
+        .map(|t| t.parse::<i32>());
+    let x = numbers.next()?.ok()?;
+    let y = numbers.next()?.ok()?;
+    Some((x, y))
 }
 
-// 预构建含边框帧：外 1px 暗色 + 内 1px (聚焦高亮 / 非聚焦灰) + 原图像
-fn build_frames(image: &[u32], w: u32, h: u32, margin: u32) -> (Vec<u32>, Vec<u32>) {
+const DOUBLE_CLICK_SLOP: f64 = 4.0; // 双击两次按下允许的位移（像素，按 UI 缩放放大）
+
+// 无阴影时的边距：外 1 像素暗线 + 按 UI 缩放加粗的内线
+fn border_margin(scale: UiScale) -> u32 {
+    1 + scale.px(BORDER_MARGIN as i32 - 1) as u32
+}
+
+// 缩略图尺寸：宽 THUMBNAIL_WIDTH（原图更窄时不变），高按比例，至少 1
+fn thumbnail_size(w: u32, h: u32) -> (u32, u32) {
+    if w <= THUMBNAIL_WIDTH {
+        return (w, h);
+    }
+    let th = (h as u64 * THUMBNAIL_WIDTH as u64 + w as u64 / 2) / w as u64;
+    (THUMBNAIL_WIDTH, (th as u32).max(1))
+}
+
+// 捏合缩放后的尺寸：按比例取整，至少 1
+fn scaled_size(w: u32, h: u32, scale: f64) -> (u32, u32) {
+    let side = |v: u32| ((v as f64 * scale).round() as u32).max(1);
+    (side(w), side(h))
+}
+
+// 区域平均缩放 BGRA 图像（缩小时每个目标像素取其覆盖的源像素均值，文字比最近邻清晰；
+// 放大时每个目标像素只覆盖一个源像素，即最近邻）
+fn downscale(image: &[u32], w: u32, h: u32, tw: u32, th: u32) -> Vec<u32> {
+    let mut out = Vec::with_capacity((tw * th) as usize);
+    for ty in 0..th {
+        let y0 = (ty as u64 * h as u64 / th as u64) as u32;
+        let y1 = (((ty + 1) as u64 * h as u64 / th as u64) as u32).max(y0 + 1);
+        for tx in 0..tw {
+            let x0 = (tx as u64 * w as u64 / tw as u64) as u32;
+            let x1 = (((tx + 1) as u64 * w as u64 / tw as u64) as u32).max(x0 + 1);
+            let mut sum = [0u32; 4];
+            for y in y0..y1 {
+                for &px in &image[(y * w + x0) as usize..(y * w + x1) as usize] {
+                    for (s, c) in sum.iter_mut().zip(px.to_le_bytes()) {
+                        *s += c as u32;
+                    }
+                }
+            }
+            let n = (x1 - x0) * (y1 - y0);
+            out.push(u32::from_le_bytes(sum.map(|s| ((s + n / 2) / n) as u8)));
+        }
+    }
+    out
+}
+
+// RGBA -> BGRA u32
+fn to_pixels(w: u32, h: u32, rgba: &[u8]) -> Vec<u32> {
+    rgba[..(w * h * 4) as usize]
+        .chunks_exact(4)
+        .map(|px| u32::from_le_bytes([px[2], px[1], px[0], px[3]]))
+        .collect()
+}
+
+// 预构建含边框帧：外 1px 暗色 + 内 1px (聚焦高亮 / 非聚焦灰) + 原图像。有颜色标记时外圈始终为标记色，
+// 非聚焦内圈也为标记色，聚焦时内圈仍是高亮色（标记不遮住焦点）
+fn build_frames(
+    image: &[u32],
+    w: u32,
+    h: u32,
+    margin: u32,
+    tag: Option<PinTag>,
+) -> (Vec<u32>, Vec<u32>) {
     let total_w = w + margin * 2;
     let total_h = h + margin * 2;
     let len = (total_w * total_h) as usize;
@@ -314,9 +902,9 @@ fn build_frames(image: &[u32], w: u32, h: u32, margin: u32) -> (Vec<u32>, Vec<u3
         unfocus[dst_base..dst_base + w as usize]
             .copy_from_slice(&image[src_start..src_start + w as usize]);
     }
-    let outer = 0xFF202020u32;
+    let outer = tag.map_or(0xFF202020u32, PinTag::color);
     let inner_focus = 0xFF3DA5F4u32;
-    let inner_unfocus = 0xFF888888u32;
+    let inner_unfocus = tag.map_or(0xFF888888u32, PinTag::color);
     let tw = total_w as usize;
     let th = total_h as usize;
     // 外圈
@@ -333,21 +921,87 @@ fn build_frames(image: &[u32], w: u32, h: u32, margin: u32) -> (Vec<u32>, Vec<u3
         focus[row + (tw - 1)] = outer;
         unfocus[row + (tw - 1)] = outer;
     }
-    if margin >= 2 {
-        let top = tw;
-        let bottom = (th - 2) * tw;
-        for x in 1..tw - 1 {
+    // 内线：外圈以内的 margin - 1 圈
+    for i in 1..margin as usize {
+        let top = i * tw;
+        let bottom = (th - 1 - i) * tw;
+        for x in i..tw - i {
             focus[top + x] = inner_focus;
             focus[bottom + x] = inner_focus;
             unfocus[top + x] = inner_unfocus;
             unfocus[bottom + x] = inner_unfocus;
         }
-        for y in 1..th - 1 {
+        for y in i..th - i {
             let row = y * tw;
-            focus[row + 1] = inner_focus;
-            focus[row + tw - 2] = inner_focus;
-            unfocus[row + 1] = inner_unfocus;
-            unfocus[row + tw - 2] = inner_unfocus;
+            focus[row + i] = inner_focus;
+            focus[row + tw - 1 - i] = inner_focus;
+            unfocus[row + i] = inner_unfocus;
+            unfocus[row + tw - 1 - i] = inner_unfocus;
+        }
+    }
+    (focus, unfocus)
+}
+
+const SHADOW_OFFSET: f32 = 2.0; // 阴影相对图像向下偏移（像素）
+const SHADOW_FOCUS: (u32, f32) = (0x3DA5F4, 0.55); // 聚焦 / 选中：强调色光晕（颜色, 最大不透明度）
+const SHADOW_UNFOCUS: (u32, f32) = (0x000000, 0.35); // 非聚焦：黑色投影（有颜色标记时为标记色）
+
+// 点 (px, py) 到圆角矩形 [0, w] x [0, h]（半径 r）边缘的有符号距离，内部为负
+fn rounded_rect_distance(px: f32, py: f32, w: f32, h: f32, r: f32) -> f32 {
+    let r = r.min(w / 2.0).min(h / 2.0);
+    let qx = (px - w / 2.0).abs() - (w / 2.0 - r);
+    let qy = (py - h / 2.0).abs() - (h / 2.0 - r);
+    let outside = (qx.max(0.0).powi(2) + qy.max(0.0).powi(2)).sqrt();
+    outside + qx.max(qy).min(0.0) - r
+}
+
+// 阴影样式的两帧（聚焦 / 非聚焦），预乘 alpha：圆角图像（边缘抗锯齿）叠在向外渐隐的阴影上，
+// 阴影之外完全透明（分层窗口在透明处不接收点击）
+fn build_shadow_frames(
+    image: &[u32],
+    w: u32,
+    h: u32,
+    margin: u32,
+    tag: Option<PinTag>,
+) -> (Vec<u32>, Vec<u32>) {
+    let total_w = w + margin * 2;
+    let total_h = h + margin * 2;
+    let len = (total_w * total_h) as usize;
+    let mut focus = Vec::with_capacity(len);
+    let mut unfocus = Vec::with_capacity(len);
+    let (fw, fh) = (w as f32, h as f32);
+    let spread = margin as f32 - SHADOW_OFFSET;
+    // 聚焦光晕保持强调色，颜色标记只染非聚焦阴影
+    let (rgb, max) = SHADOW_UNFOCUS;
+    let shadow_unfocus = (tag.map_or(rgb, |t| t.color() & 0xFFFFFF), max);
+    let shadow_focus = SHADOW_FOCUS;
+    // 预乘图像叠在阴影之上：over((颜色, 最大不透明度), 衰减系数, 图像 [b, g, r, a])
+    let over = |(rgb, max): (u32, f32), falloff: f32, src: [f32; 4]| -> u32 {
+        let a = max * falloff * (1.0 - src[3]);
+        let [b, g, r, _] = rgb.to_le_bytes().map(|c| c as f32 / 255.0);
+        let out = [src[0] + b * a, src[1] + g * a, src[2] + r * a, src[3] + a];
+        u32::from_le_bytes(out.map(|c| (c * 255.0).round().clamp(0.0, 255.0) as u8))
+    };
+    for y in 0..total_h {
+        for x in 0..total_w {
+            let px = x as f32 + 0.5 - margin as f32;
+            let py = y as f32 + 0.5 - margin as f32;
+            // 图像：圆角外透明，边缘按距离抗锯齿；预乘后的 [b, g, r, a]
+            let mut src = [0.0; 4];
+            let (ix, iy) = (x.wrapping_sub(margin), y.wrapping_sub(margin));
+            if ix < w && iy < h {
+                let d = rounded_rect_distance(px, py, fw, fh, CORNER_RADIUS);
+                let cover = (0.5 - d).clamp(0.0, 1.0);
+                let [b, g, r, a] = image[(iy * w + ix) as usize].to_le_bytes();
+                let alpha = a as f32 / 255.0 * cover;
+                let [b, g, r] = [b, g, r].map(|c| c as f32 / 255.0 * alpha);
+                src = [b, g, r, alpha];
+            }
+            let d = rounded_rect_distance(px, py - SHADOW_OFFSET, fw, fh, CORNER_RADIUS);
+            let t = (1.0 - d.max(0.0) / spread).clamp(0.0, 1.0);
+            let falloff = t * t;
+            focus.push(over(shadow_focus, falloff, src));
+            unfocus.push(over(shadow_unfocus, falloff, src));
         }
     }
     (focus, unfocus)
@@ -381,3 +1035,119 @@ fn global_cursor_position() -> Option<(i32, i32)> {
 fn global_cursor_position() -> Option<(i32, i32)> {
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thumbnail_size_and_downscale() {
+        assert_eq!(thumbnail_size(1200, 300), (THUMBNAIL_WIDTH, 30));
+        assert_eq!(thumbnail_size(80, 40), (80, 40));
+        assert_eq!(thumbnail_size(10_000, 10), (THUMBNAIL_WIDTH, 1));
+        // 4x2 -> 2x1：每个目标像素是 2x2 块的均值
+        let image = [
+            0xFF000000, 0xFF0000FF, 0xFFFFFFFF, 0xFFFFFFFF, //
+            0xFF000000, 0xFF0000FF, 0xFF000000, 0xFF000000,
+        ];
+        assert_eq!(downscale(&image, 4, 2, 2, 1), [0xFF000080, 0xFF808080]);
+        // 放大为最近邻
+        assert_eq!(downscale(&[1, 2], 2, 1, 4, 1), [1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn test_scaled_size() {
+        assert_eq!(scaled_size(200, 100, 1.0), (200, 100));
+        assert_eq!(scaled_size(200, 100, 1.5), (300, 150));
+        assert_eq!(scaled_size(200, 3, MIN_SCALE), (20, 1));
+    }
+
+    #[test]
+    fn test_shadow_frames() {
+        let (w, h) = (40, 20);
+        let image = vec![0xFFFFFFFF; (w * h) as usize];
+        let (focus, unfocus) = build_shadow_frames(&image, w, h, SHADOW_MARGIN, None);
+        let total_w = w + SHADOW_MARGIN * 2;
+        assert_eq!(focus.len(), (total_w * (h + SHADOW_MARGIN * 2)) as usize);
+        let at = |frame: &[u32], x: u32, y: u32| frame[(y * total_w + x) as usize].to_le_bytes();
+        // 图像中心不透明；窗口角落完全透明
+        assert_eq!(at(&focus, total_w / 2, SHADOW_MARGIN + h / 2), [255; 4]);
+        assert_eq!(at(&unfocus, 0, 0)[3], 0);
+        // 圆角：图像左上角像素透出阴影（部分透明）
+        let corner = at(&unfocus, SHADOW_MARGIN, SHADOW_MARGIN);
+        assert!(corner[3] < 255);
+        // 图像正下方是阴影：非聚焦为黑色，聚焦带强调色；预乘后颜色不超过 alpha
+        let below = SHADOW_MARGIN + h + 1;
+        let [b, g, r, a] = at(&unfocus, total_w / 2, below);
+        assert!(a > 0 && [b, g, r] == [0, 0, 0]);
+        let [b, _, r, a] = at(&focus, total_w / 2, below);
+        assert!(b > r && b <= a);
+        assert_eq!(rounded_rect_distance(20.0, 10.0, 40.0, 20.0, 6.0), -10.0);
+        // 颜色标记：非聚焦阴影带标记色，聚焦光晕不变
+        let (focused, green) =
+            build_shadow_frames(&image, w, h, SHADOW_MARGIN, Some(PinTag::Green));
+        let [b, g, r, _] = at(&green, total_w / 2, below);
+        assert!(g > r && g > b);
+        assert_eq!(focused, focus);
+    }
+
+    #[test]
+    fn test_rect_text_round_trip() {
+        let rect = Rect {
+            x: -1920,
+            y: 40,
+            width: 640,
+            height: 480,
+        };
+        assert_eq!(rect_text(rect), "-1920 40 640 480");
+        assert_eq!(parse_position(&rect_text(rect)), Some((-1920, 40)));
+        assert_eq!(parse_position("  100, 80\n"), Some((100, 80)));
+        assert_eq!(
+            parse_position(r#"{"x":12,"y":-3,"width":1,"height":1}"#),
+            Some((12, -3))
+        );
+        assert_eq!(parse_position("x=5"), None);
+        assert_eq!(parse_position("1-2 3"), None);
+        assert_eq!(parse_position(""), None);
+    }
+
+    #[test]
+    fn test_tag_border_color() {
+        let image = [0xFF000000; 4];
+        // (聚焦外圈, 聚焦内圈, 非聚焦外圈, 非聚焦内圈)：内圈为第 2 行第 2 列
+        let rings = |tag| {
+            let (focus, unfocus) = build_frames(&image, 2, 2, BORDER_MARGIN, tag);
+            (focus[0], focus[6 + 1], unfocus[0], unfocus[6 + 1])
+        };
+        assert_eq!(
+            rings(None),
+            (0xFF202020, 0xFF3DA5F4, 0xFF202020, 0xFF888888)
+        );
+        // 标记色在外圈始终可见，聚焦内圈仍为高亮色
+        let red = PinTag::Red.color();
+        assert_eq!(rings(Some(PinTag::Red)), (red, 0xFF3DA5F4, red, red));
+        #[derive(Deserialize)]
+        struct Wrap {
+            tag: PinTag,
+        }
+        let w: Wrap = serde_json::from_str(r#"{"tag":"yellow"}"#).unwrap();
+        assert_eq!(w.tag, PinTag::Yellow);
+    }
+
+    #[test]
+    fn test_scaled_border() {
+        assert_eq!(border_margin(UiScale::default()), BORDER_MARGIN);
+        let margin = border_margin(UiScale::new(2.0));
+        assert_eq!(margin, 3);
+        let image = [0xFF000000; 4];
+        let (focus, _) = build_frames(&image, 2, 2, margin, None);
+        // 8x8：外圈暗线，其内两圈为内线，中间 2x2 为图像
+        let row = |y: usize| &focus[y * 8..y * 8 + 8];
+        assert_eq!(row(0)[3], 0xFF202020);
+        assert_eq!(
+            (row(1)[1], row(2)[2], row(2)[5]),
+            (0xFF3DA5F4, 0xFF3DA5F4, 0xFF3DA5F4)
+        );
+        assert_eq!(row(3)[3..5], [0xFF000000; 2]);
+    }
+}
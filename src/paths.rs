@@ -0,0 +1,104 @@
+// paths: 配置文件与数据目录的解析（便携模式 / 安装模式）
+//
+// 可执行文件旁存在 snip_rust.toml 时为便携模式：配置即该文件，数据（日志、计数、崩溃恢复）
+// 放在可执行文件旁的 data/ 下；否则为安装模式，使用 <config_dir>/snip_rust/snip_rust.toml 与
+// <data_dir>/snip_rust（Windows 均为 %APPDATA%\snip_rust）。进程内只解析一次。
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub const CONFIG_FILE: &str = "snip_rust.toml";
+const APP_DIR: &str = "snip_rust";
+const PORTABLE_DATA_DIR: &str = "data";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Portable,
+    Installed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppPaths {
+    pub mode: Mode,
+    pub config_file: PathBuf,
+    pub data_dir: PathBuf,
+}
+
+/// 按优先级解析：exe_dir 下有配置文件则便携，否则使用系统目录（缺失时返回 None）
+pub fn resolve(
+    exe_dir: Option<&Path>,
+    config_dir: Option<PathBuf>,
+    data_dir: Option<PathBuf>,
+) -> Option<AppPaths> {
+    if let Some(dir) = exe_dir {
+        let config_file = dir.join(CONFIG_FILE);
+        if config_file.is_file() {
+            return Some(AppPaths {
+                mode: Mode::Portable,
+                config_file,
+                data_dir: dir.join(PORTABLE_DATA_DIR),
+            });
+        }
+    }
+    let config_file = config_dir?.join(APP_DIR).join(CONFIG_FILE);
+    let data_dir = data_dir?.join(APP_DIR);
+    Some(AppPaths {
+        mode: Mode::Installed,
+        config_file,
+        data_dir,
+    })
+}
+
+/// 当前进程生效的路径
+pub fn get() -> Option<&'static AppPaths> {
+    static PATHS: OnceLock<Option<AppPaths>> = OnceLock::new();
+    PATHS
+        .get_or_init(|| {
+            let exe = std::env::current_exe().ok();
+            resolve(
+                exe.as_deref().and_then(Path::parent),
+                dirs::config_dir(),
+                dirs::data_dir(),
+            )
+        })
+        .as_ref()
+}
+
+/// 配置文件路径
+pub fn config_file() -> Option<PathBuf> {
+    get().map(|p| p.config_file.clone())
+}
+
+/// 数据目录（日志、文件名计数、崩溃日志与待恢复贴图）
+pub fn data_dir() -> Option<PathBuf> {
+    get().map(|p| p.data_dir.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_resolve_portable_and_installed() {
+        let exe_dir = TempDir::new("paths_test");
+        let system = || (Some(PathBuf::from("/cfg")), Some(PathBuf::from("/data")));
+
+        let (cfg, data) = system();
+        let installed = resolve(Some(&exe_dir), cfg, data).unwrap();
+        assert_eq!(installed.mode, Mode::Installed);
+        assert_eq!(
+            installed.config_file,
+            Path::new("/cfg/snip_rust/snip_rust.toml")
+        );
+        assert_eq!(installed.data_dir, Path::new("/data/snip_rust"));
+
+        std::fs::write(exe_dir.join(CONFIG_FILE), "").unwrap();
+        let (cfg, data) = system();
+        let portable = resolve(Some(&exe_dir), cfg, data).unwrap();
+        assert_eq!(portable.mode, Mode::Portable);
+        assert_eq!(portable.config_file, exe_dir.join(CONFIG_FILE));
+        assert_eq!(portable.data_dir, exe_dir.join("data"));
+
+        assert_eq!(resolve(None, None, Some(PathBuf::from("/data"))), None);
+    }
+}
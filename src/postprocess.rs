@@ -0,0 +1,99 @@
+// postprocess: 导出前的图像变换（[export_scale] 缩放）
+//
+// 作用于保存 / 复制 / 复制为 Data URI / 分享的选区图像与不经选区的截图（之后的上传 hook 拿到的也是
+// 缩放后的文件），钉图保持原尺寸。先按 percent 缩放，再等比缩小到 max_width / max_height 以内；
+// 使用 Lanczos3 重采样，目标尺寸与原图相同时原样返回。
+use image::imageops::{self, FilterType};
+use image::RgbaImage;
+use serde::Deserialize;
+
+/// percent 的取值范围
+pub const PERCENT_MIN: u32 = 1;
+pub const PERCENT_MAX: u32 = 400;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ExportScale {
+    /// 按百分比缩放（100 为原尺寸）
+    pub percent: Option<u32>,
+    /// 宽 / 高上限（像素）：超出时等比缩小，不会放大
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+}
+
+impl ExportScale {
+    /// 缩放后的尺寸，宽高至少 1
+    pub fn target_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let (mut w, mut h) = (width as f64, height as f64);
+        if let Some(p) = self.percent {
+            let f = p.clamp(PERCENT_MIN, PERCENT_MAX) as f64 / 100.0;
+            (w, h) = (w * f, h * f);
+        }
+        let fit_w = self
+            .max_width
+            .filter(|&m| m > 0)
+            .map_or(1.0, |m| m as f64 / w);
+        let fit_h = self
+            .max_height
+            .filter(|&m| m > 0)
+            .map_or(1.0, |m| m as f64 / h);
+        let fit = fit_w.min(fit_h).min(1.0);
+        let size = |v: f64| ((v * fit).round() as u32).max(1);
+        (size(w), size(h))
+    }
+
+    /// 缩放 RGBA；返回 (width, height, RGBA)。缓冲长度与尺寸不符时不缩放，原样返回
+    pub fn apply(&self, width: u32, height: u32, rgba: Vec<u8>) -> (u32, u32, Vec<u8>) {
+        let (tw, th) = self.target_size(width, height);
+        if (tw, th) == (width, height) {
+            return (width, height, rgba);
+        }
+        // from_raw 失败时会消耗缓冲：先检查长度
+        if rgba.len() != width as usize * height as usize * 4 {
+            log::warn!("export scale: buffer does not match {width}x{height}");
+            return (width, height, rgba);
+        }
+        let Some(img) = RgbaImage::from_raw(width, height, rgba) else {
+            return (width, height, Vec::new()); // 长度已检查，不会走到这里
+        };
+        let out = imageops::resize(&img, tw, th, FilterType::Lanczos3);
+        (tw, th, out.into_raw())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_scale() {
+        let none = ExportScale::default();
+        assert_eq!(none.target_size(1920, 1080), (1920, 1080));
+        let half: ExportScale = toml::from_str("percent = 50").unwrap();
+        assert_eq!(half.target_size(1920, 1080), (960, 540));
+        // 先缩放百分比，再限制最大宽度（等比）
+        let both: ExportScale = toml::from_str("percent = 200\nmax_width = 1000").unwrap();
+        assert_eq!(both.target_size(800, 400), (1000, 500));
+        // 上限不放大；极小的图至少 1 像素
+        let cap = ExportScale {
+            max_height: Some(600),
+            ..Default::default()
+        };
+        assert_eq!(cap.target_size(300, 200), (300, 200));
+        assert_eq!(cap.target_size(100, 6000), (10, 600));
+        let tiny = ExportScale {
+            percent: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(tiny.target_size(50, 20), (1, 1));
+        // 纯色图缩放后颜色不变
+        let (w, h, out) = half.apply(4, 2, [10, 20, 30, 255].repeat(8));
+        assert_eq!((w, h), (2, 1));
+        assert_eq!(out, [10, 20, 30, 255].repeat(2));
+        let (w, h, out) = none.apply(1, 1, vec![1, 2, 3, 4]);
+        assert_eq!((w, h, out), (1, 1, vec![1, 2, 3, 4]));
+        // 缓冲与尺寸不符：原样返回而不是丢掉图像
+        let (w, h, out) = half.apply(4, 2, vec![7; 12]);
+        assert_eq!((w, h, out), (4, 2, vec![7; 12]));
+    }
+}
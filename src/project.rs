@@ -0,0 +1,410 @@
+// project: .snip 工程文件，保存原始截图与全部编辑，之后在 overlay 中重新打开继续编辑
+//
+// 容器为 zip：manifest.json（版本、截图来源、选区、笔画、打码、调整）+ capture.png（未编辑的整屏截图）。
+// 只写 stored（不压缩）条目：PNG 本身已压缩；读取时也接受 deflate 条目（其它 zip 工具重新打包后）。
+// Windows 上可在 HKCU 注册 .snip 文件关联，双击即以 --open 打开（已运行时转发给该实例）。
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::capture::encode_png;
+use crate::encode::save_file_auto;
+use crate::filters::Adjustments;
+use crate::overlay::annotate::Stroke;
+use crate::overlay::regions::Region;
+
+pub const EXTENSION: &str = "snip";
+const MANIFEST: &str = "manifest.json";
+const CAPTURE: &str = "capture.png";
+const VERSION: u32 = 1;
+
+/// 截图来源（开始截图时记录）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureSource {
+    /// 截图时间（Unix 秒）
+    pub created: u64,
+    /// 前台程序名（不含 .exe）
+    pub app: Option<String>,
+    /// 显示器序号（从 1 开始）
+    pub monitor: u32,
+}
+
+impl CaptureSource {
+    /// 以当前时间记录
+    pub fn now(app: Option<String>, monitor: u32) -> Self {
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            created,
+            app,
+            monitor,
+        }
+    }
+}
+
+/// manifest.json；坐标均为截图内像素
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    #[serde(flatten)]
+    pub source: CaptureSource,
+    /// 截图左上角的屏幕坐标
+    pub origin: (i32, i32),
+    pub width: u32,
+    pub height: u32,
+    /// 活动选区
+    pub selection: Option<Region>,
+    /// Ctrl 多选的其它选区
+    #[serde(default)]
+    pub others: Vec<Region>,
+    #[serde(default)]
+    pub strokes: Vec<Stroke>,
+    /// 已打码的区域
+    #[serde(default)]
+    pub redactions: Vec<Region>,
+    /// 挖空（导出为透明）的区域
+    #[serde(default)]
+    pub excluded: Vec<Region>,
+    #[serde(default)]
+    pub adjust: Adjustments,
+}
+
+impl Manifest {
+    pub fn new(width: u32, height: u32, origin: (i32, i32)) -> Self {
+        Self {
+            version: VERSION,
+            source: CaptureSource::default(),
+            origin,
+            width,
+            height,
+            selection: None,
+            others: Vec::new(),
+            strokes: Vec::new(),
+            redactions: Vec::new(),
+            excluded: Vec::new(),
+            adjust: Adjustments::default(),
+        }
+    }
+}
+
+/// 工程：清单 + 原始截图（RGBA）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    pub manifest: Manifest,
+    pub capture: Vec<u8>,
+}
+
+impl Project {
+    /// 打包为 .snip（zip）字节
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let manifest = serde_json::to_vec_pretty(&self.manifest)?;
+        let png = encode_png(&self.capture, self.manifest.width, self.manifest.height)?;
+        Ok(write_zip(&[(MANIFEST, &manifest), (CAPTURE, &png)]))
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let manifest: Manifest = serde_json::from_slice(&zip_entry(data, MANIFEST)?)
+            .map_err(|e| anyhow!("invalid manifest: {e}"))?;
+        if manifest.version > VERSION {
+            return Err(anyhow!("unsupported project version {}", manifest.version));
+        }
+        let image = image::load_from_memory(&zip_entry(data, CAPTURE)?)
+            .map_err(|e| anyhow!("decode capture: {e}"))?
+            .to_rgba8();
+        if image.dimensions() != (manifest.width, manifest.height) {
+            return Err(anyhow!("capture size does not match manifest"));
+        }
+        Ok(Self {
+            manifest,
+            capture: image.into_raw(),
+        })
+    }
+
+    /// 写入 <name>.snip（同名时追加 _2、_3 …）
+    pub fn save(&self, name: &str) -> Result<PathBuf> {
+        save_file_auto(&self.to_bytes()?, name, EXTENSION)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path).map_err(|e| anyhow!("read {}: {e}", path.display()))?;
+        Self::from_bytes(&data)
+    }
+}
+
+const LOCAL_SIG: u32 = 0x0403_4b50;
+const CENTRAL_SIG: u32 = 0x0201_4b50;
+const END_SIG: u32 = 0x0605_4b50;
+const DOS_DATE: u16 = 0x21; // 1980-01-01：条目时间无意义，创建时间见 manifest
+const UTF8_NAMES: u16 = 0x0800;
+
+// stored 条目的 zip（无 zip64，单个条目小于 4 GB）
+fn write_zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let u16le = |out: &mut Vec<u8>, v: u16| out.extend_from_slice(&v.to_le_bytes());
+    let u32le = |out: &mut Vec<u8>, v: u32| out.extend_from_slice(&v.to_le_bytes());
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+    for &(name, data) in entries {
+        let offset = out.len() as u32;
+        let (crc, size) = (crc32fast::hash(data), data.len() as u32);
+        // 本地头与中央目录共有的字段：版本、标志、方法、时间、日期、CRC、大小、名称长度
+        let common = |out: &mut Vec<u8>| {
+            u16le(out, 20);
+            u16le(out, UTF8_NAMES);
+            u16le(out, 0);
+            u16le(out, 0);
+            u16le(out, DOS_DATE);
+            u32le(out, crc);
+            u32le(out, size);
+            u32le(out, size);
+            u16le(out, name.len() as u16);
+        };
+        u32le(&mut out, LOCAL_SIG);
+        common(&mut out);
+        u16le(&mut out, 0);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(data);
+
+        u32le(&mut central, CENTRAL_SIG);
+        u16le(&mut central, 20);
+        common(&mut central);
+        for _ in 0..4 {
+            u16le(&mut central, 0); // extra / comment / disk / internal attr
+        }
+        u32le(&mut central, 0);
+        u32le(&mut central, offset);
+        central.extend_from_slice(name.as_bytes());
+    }
+    let central_offset = out.len() as u32;
+    out.extend_from_slice(&central);
+    u32le(&mut out, END_SIG);
+    u16le(&mut out, 0);
+    u16le(&mut out, 0);
+    u16le(&mut out, entries.len() as u16);
+    u16le(&mut out, entries.len() as u16);
+    u32le(&mut out, central.len() as u32);
+    u32le(&mut out, central_offset);
+    u16le(&mut out, 0);
+    out
+}
+
+// 按中央目录查找条目内容（stored 原样返回，deflate 解压）并校验 CRC
+fn zip_entry<'a>(data: &'a [u8], name: &str) -> Result<Cow<'a, [u8]>> {
+    let u16_at = |i: usize| -> Result<usize> {
+        data.get(i..i + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+            .ok_or_else(|| anyhow!("truncated project file"))
+    };
+    let u32_at = |i: usize| -> Result<u32> {
+        data.get(i..i + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| anyhow!("truncated project file"))
+    };
+    // 目录结尾记录可能带注释：从末尾向前找签名
+    let end = (0..data.len().saturating_sub(21))
+        .rev()
+        .find(|&i| u32_at(i).ok() == Some(END_SIG))
+        .ok_or_else(|| anyhow!("not a project file"))?;
+    let count = u16_at(end + 10)?;
+    let mut at = u32_at(end + 16)? as usize;
+    for _ in 0..count {
+        if u32_at(at)? != CENTRAL_SIG {
+            return Err(anyhow!("corrupt project file"));
+        }
+        let (method, crc) = (u16_at(at + 10)?, u32_at(at + 16)?);
+        let (size, unpacked) = (u32_at(at + 20)?, u32_at(at + 24)?);
+        let name_len = u16_at(at + 28)?;
+        let skip = name_len + u16_at(at + 30)? + u16_at(at + 32)?;
+        let local = u32_at(at + 42)? as usize;
+        let entry_name = data.get(at + 46..at + 46 + name_len);
+        at += 46 + skip;
+        if entry_name != Some(name.as_bytes()) {
+            continue;
+        }
+        let start = local + 30 + u16_at(local + 26)? + u16_at(local + 28)?;
+        let raw = data
+            .get(start..start + size as usize)
+            .ok_or_else(|| anyhow!("truncated project file"))?;
+        let content = match method {
+            0 => Cow::Borrowed(raw),
+            8 => {
+                // 解压结果不超过目录记录的大小（多读 1 字节用于发现不符）
+                let mut out = Vec::with_capacity(unpacked as usize);
+                flate2::read::DeflateDecoder::new(raw)
+                    .take(unpacked as u64 + 1)
+                    .read_to_end(&mut out)
+                    .map_err(|e| anyhow!("inflate {name}: {e}"))?;
+                if out.len() != unpacked as usize {
+                    return Err(anyhow!("size mismatch in {name}"));
+                }
+                Cow::Owned(out)
+            }
+            _ => return Err(anyhow!("unsupported compression {method} in {name}")),
+        };
+        if crc32fast::hash(&content) != crc {
+            return Err(anyhow!("checksum mismatch in {name}"));
+        }
+        return Ok(content);
+    }
+    Err(anyhow!("{name} missing from project file"))
+}
+
+/// 在 HKCU\Software\Classes 注册 .snip 文件关联（双击以 --open 打开）；已指向当前程序时不改动
+#[cfg(target_os = "windows")]
+pub fn register_file_type() -> Result<()> {
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::Foundation::ERROR_SUCCESS;
+    use windows::Win32::System::Registry::{
+        RegGetValueW, RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ, RRF_RT_REG_SZ,
+    };
+    use windows::Win32::UI::Shell::{SHChangeNotify, SHCNE_ASSOCCHANGED, SHCNF_IDLIST};
+
+    const PROG_ID: &str = "SnipRust.Project";
+    let exe = std::env::current_exe().map_err(|e| anyhow!("locate executable: {e}"))?;
+    let command = format!("\"{}\" --open \"%1\"", exe.display());
+    let command_key = format!("Software\\Classes\\{PROG_ID}\\shell\\open\\command");
+
+    // 默认值（PCWSTR::null）
+    let read = |key: &str| -> Option<String> {
+        let mut buf = [0u16; 1024];
+        let mut len = (buf.len() * 2) as u32;
+        let err = unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                &HSTRING::from(key),
+                PCWSTR::null(),
+                RRF_RT_REG_SZ,
+                None,
+                Some(buf.as_mut_ptr() as *mut _),
+                Some(&mut len),
+            )
+        };
+        (err == ERROR_SUCCESS).then(|| {
+            let n = (len as usize / 2).saturating_sub(1);
+            String::from_utf16_lossy(&buf[..n])
+        })
+    };
+    let write = |key: &str, value: &str| -> Result<()> {
+        let wide: Vec<u16> = value.encode_utf16().chain(std::iter::once(0)).collect();
+        let err = unsafe {
+            RegSetKeyValueW(
+                HKEY_CURRENT_USER,
+                &HSTRING::from(key),
+                PCWSTR::null(),
+                REG_SZ.0,
+                Some(wide.as_ptr() as *const _),
+                (wide.len() * 2) as u32,
+            )
+        };
+        if err != ERROR_SUCCESS {
+            return Err(anyhow!("set {key} failed: {err:?}"));
+        }
+        Ok(())
+    };
+
+    let ext_key = format!("Software\\Classes\\.{EXTENSION}");
+    if read(&command_key).as_deref() == Some(command.as_str())
+        && read(&ext_key).as_deref() == Some(PROG_ID)
+    {
+        return Ok(());
+    }
+    write(&ext_key, PROG_ID)?;
+    write(&format!("Software\\Classes\\{PROG_ID}"), "Snip Rust 工程")?;
+    write(
+        &format!("Software\\Classes\\{PROG_ID}\\DefaultIcon"),
+        &format!("\"{}\",0", exe.display()),
+    )?;
+    write(&command_key, &command)?;
+    unsafe { SHChangeNotify(SHCNE_ASSOCCHANGED, SHCNF_IDLIST, None, None) };
+    log::info!("registered .{EXTENSION} file association");
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn register_file_type() -> Result<()> {
+    Err(anyhow!("file association is only available on Windows"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::overlay::annotate::AnnotationStyle;
+
+    fn sample() -> Project {
+        let mut manifest = Manifest::new(6, 4, (-1920, 0));
+        manifest.source = CaptureSource {
+            created: 1_700_000_000,
+            app: Some("notepad".into()),
+            monitor: 2,
+        };
+        manifest.selection = Some((1, 1, 4, 2));
+        manifest.others = vec![(0, 0, 2, 2)];
+        manifest.strokes = vec![Stroke {
+            style: AnnotationStyle::default(),
+            points: vec![(1, 1), (3, 2)],
+            pressure: Vec::new(),
+        }];
+        manifest.redactions = vec![(2, 1, 2, 1)];
+        manifest.adjust.grayscale = true;
+        Project {
+            manifest,
+            capture: (0..6 * 4 * 4).map(|i| i as u8).collect(),
+        }
+    }
+
+    #[test]
+    fn test_project_round_trip() {
+        let project = sample();
+        let bytes = project.to_bytes().unwrap();
+        assert_eq!(&bytes[..4], b"PK\x03\x04");
+        assert_eq!(Project::from_bytes(&bytes).unwrap(), project);
+        // 清单扁平存放来源字段，便于其它工具读取
+        let entry = zip_entry(&bytes, MANIFEST).unwrap();
+        let manifest = std::str::from_utf8(&entry).unwrap();
+        assert!(manifest.contains("\"app\": \"notepad\""));
+        assert!(manifest.contains("\"monitor\": 2"));
+    }
+
+    #[test]
+    fn test_zip_rejects_damaged_files() {
+        assert_eq!(crc32fast::hash(b"123456789"), 0xCBF4_3926);
+        let mut bytes = write_zip(&[("a.txt", b"hello"), ("b.txt", b"world")]);
+        assert_eq!(&*zip_entry(&bytes, "b.txt").unwrap(), b"world");
+        assert!(zip_entry(&bytes, "c.txt").is_err());
+        assert!(Project::from_bytes(&bytes).is_err());
+        // 改动内容：CRC 不匹配
+        let i = bytes.windows(5).position(|w| w == b"hello").unwrap();
+        bytes[i] = b'j';
+        assert!(zip_entry(&bytes, "a.txt").is_err());
+        assert!(zip_entry(&bytes[..bytes.len() - 30], "a.txt").is_err());
+        assert!(zip_entry(b"not a zip", "a.txt").is_err());
+    }
+
+    #[test]
+    fn test_zip_reads_deflated_entries() {
+        use flate2::{write::DeflateEncoder, Compression};
+        use std::io::Write;
+        let text = b"hello hello hello hello";
+        let mut enc = DeflateEncoder::new(Vec::new(), Compression::default());
+        enc.write_all(text).unwrap();
+        let packed = enc.finish().unwrap();
+        // 先按 stored 写出压缩后的字节，再把方法 / CRC / 原始大小改成 deflate 条目
+        let mut bytes = write_zip(&[("a.txt", &packed)]);
+        let central = bytes.len() - 22 - 46 - 5;
+        let crc = crc32fast::hash(text).to_le_bytes();
+        for (at, crc_at, size_at) in [(8, 14, 22), (central + 10, central + 16, central + 24)] {
+            bytes[at..at + 2].copy_from_slice(&8u16.to_le_bytes());
+            bytes[crc_at..crc_at + 4].copy_from_slice(&crc);
+            bytes[size_at..size_at + 4].copy_from_slice(&(text.len() as u32).to_le_bytes());
+        }
+        assert_eq!(&*zip_entry(&bytes, "a.txt").unwrap(), text);
+        // 目录记录的原始大小与解压结果不符
+        bytes[central + 24] -= 1;
+        assert!(zip_entry(&bytes, "a.txt").is_err());
+    }
+}
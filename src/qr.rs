@@ -0,0 +1,61 @@
+// qr: 文本 -> 二维码图像（qrcode 生成模块矩阵，tiny-skia 绘制），托盘“文本转二维码”钉图使用
+
+use anyhow::{anyhow, Result};
+use qrcode::{Color as QrColor, QrCode};
+use tiny_skia::{Color, Paint, Pixmap, Rect, Transform};
+
+const QUIET_ZONE: u32 = 4; // 规范要求四个模块宽的静区，手机扫码更稳定
+
+/// 渲染二维码，每个模块 `module_px` 像素，返回 (width, height, RGBA)
+pub fn render_qr(text: &str, module_px: u32) -> Result<(u32, u32, Vec<u8>)> {
+    if text.trim().is_empty() {
+        return Err(anyhow!("qr text is empty"));
+    }
+    let code = QrCode::new(text.as_bytes()).map_err(|e| anyhow!("encode qr: {e}"))?;
+    let modules = code.width() as u32;
+    let module_px = module_px.max(1);
+    let size = (modules + QUIET_ZONE * 2) * module_px;
+    let mut pixmap = Pixmap::new(size, size).ok_or_else(|| anyhow!("create pixmap failed"))?;
+    pixmap.fill(Color::WHITE);
+    let mut paint = Paint::default();
+    paint.set_color(Color::BLACK);
+    paint.anti_alias = false;
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color != QrColor::Dark {
+            continue;
+        }
+        let mx = i as u32 % modules + QUIET_ZONE;
+        let my = i as u32 / modules + QUIET_ZONE;
+        if let Some(rect) = Rect::from_xywh(
+            (mx * module_px) as f32,
+            (my * module_px) as f32,
+            module_px as f32,
+            module_px as f32,
+        ) {
+            pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+        }
+    }
+    // 全部像素不透明：预乘 RGBA 与直通 RGBA 相同
+    Ok((size, size, pixmap.take()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::decode_qr;
+
+    #[test]
+    fn test_render_qr_roundtrip() {
+        let text = "https://example.com/snip?x=1";
+        let (w, h, rgba) = render_qr(text, 4).unwrap();
+        assert_eq!(w, h);
+        assert_eq!(rgba.len(), (w * h * 4) as usize);
+        assert_eq!(&rgba[..4], &[255, 255, 255, 255]); // 静区为白色
+        assert_eq!(decode_qr(w, h, &rgba), vec![text.to_string()]);
+    }
+
+    #[test]
+    fn test_render_qr_rejects_empty() {
+        assert!(render_qr("  \n", 4).is_err());
+    }
+}
@@ -1,9 +1,30 @@
 // renderer: 使用 tiny-skia 在 CPU 上绘制图像并输出到 softbuffer
-// 提供加载 PNG/JPEG 等（通过 image crate）并拷贝到 tiny-skia Pixmap，然后附加简单形状绘制
+// 提供加载 PNG/JPEG 等（通过 image crate）并拷贝到 tiny-skia Pixmap，然后附加简单形状绘制；
+// 以及把多张图像拼接到同一画布（贴图“合并”）、导出时把四角裁成圆角
 
 use anyhow::{anyhow, Result};
 use image::GenericImageView;
-use tiny_skia::{Color, Paint, PathBuilder, Pixmap, Stroke, Transform};
+use tiny_skia::{Color, FillRule, Mask, Paint, PathBuilder, Pixmap, Stroke, Transform};
+
+/// 多图拼接排版（贴图“合并”）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StitchLayout {
+    Horizontal, // 横向，顶端对齐
+    Vertical,   // 纵向，左端对齐
+    Grid,       // 近似正方形网格，单元格取最大宽高
+}
+
+impl StitchLayout {
+    pub const ALL: [StitchLayout; 3] = [Self::Horizontal, Self::Vertical, Self::Grid];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Horizontal => "横向",
+            Self::Vertical => "纵向",
+            Self::Grid => "网格",
+        }
+    }
+}
 
 pub struct Renderer {
     pub pixmap: Pixmap,
@@ -66,11 +87,7 @@ impl Renderer {
     }
 
     pub fn as_bgra_u32(&self) -> Vec<u32> {
-        // 如果平台需要 BGRA 顺序（Windows 常见），转换一次
-        // 数据为预乘 RGBA, 这里只调换 R 与 B 通道
-        if std::env::var("SNIP_ASSUME_BGRA").is_ok() {
-            return bytemuck::cast_slice(self.pixmap.data()).to_vec();
-        }
+        // tiny-skia pixmap 固定为预乘 RGBA，softbuffer 需要 BGRA，这里只调换 R 与 B 通道
         let data = self.pixmap.data();
         let mut out: Vec<u32> =
             Vec::with_capacity(self.pixmap.width() as usize * self.pixmap.height() as usize);
@@ -85,6 +102,25 @@ impl Renderer {
         out
     }
 
+    /// 把 RGBA 图像原样拷贝到画布 (x, y)，超出画布部分裁掉（不做混合）
+    pub fn blit_rgba(&mut self, x: u32, y: u32, w: u32, h: u32, rgba: &[u8]) {
+        let (cw, ch) = (self.pixmap.width(), self.pixmap.height());
+        if x >= cw || y >= ch || rgba.len() < (w * h * 4) as usize {
+            return;
+        }
+        let copy_w = w.min(cw - x) as usize * 4;
+        for row in 0..h.min(ch - y) {
+            let src = (row * w * 4) as usize;
+            let dst = (((y + row) * cw + x) * 4) as usize;
+            self.pixmap.data_mut()[dst..dst + copy_w].copy_from_slice(&rgba[src..src + copy_w]);
+        }
+    }
+
+    /// 画布像素（未经绘制操作时即原样 RGBA）
+    pub fn into_rgba(self) -> Vec<u8> {
+        self.pixmap.take()
+    }
+
     pub fn load_png_bytes(&mut self, bytes: &[u8]) -> Result<()> {
         let img = image::load_from_memory(bytes)?; // DynamicImage
         let (w, h) = img.dimensions();
@@ -104,3 +140,125 @@ impl Renderer {
         Ok(())
     }
 }
+
+/// 圆角导出：按抗锯齿的圆角矩形遮罩缩放 alpha（RGBA 为非预乘，颜色不变），四角变为透明。
+/// 半径超过短边一半时按一半处理；radius 为 0 或图像为空时不处理
+pub fn round_corners(rgba: &mut [u8], width: u32, height: u32, radius: u32) {
+    let r = radius.min(width / 2).min(height / 2) as f32;
+    if r <= 0.0 || rgba.len() < (width * height * 4) as usize {
+        return;
+    }
+    let Some(mut mask) = Mask::new(width, height) else {
+        return;
+    };
+    let (w, h) = (width as f32, height as f32);
+    // 四分之一圆的三次贝塞尔近似
+    let k = r * 0.552_284_8;
+    let mut pb = PathBuilder::new();
+    pb.move_to(r, 0.0);
+    pb.line_to(w - r, 0.0);
+    pb.cubic_to(w - r + k, 0.0, w, r - k, w, r);
+    pb.line_to(w, h - r);
+    pb.cubic_to(w, h - r + k, w - r + k, h, w - r, h);
+    pb.line_to(r, h);
+    pb.cubic_to(r - k, h, 0.0, h - r + k, 0.0, h - r);
+    pb.line_to(0.0, r);
+    pb.cubic_to(0.0, r - k, r - k, 0.0, r, 0.0);
+    pb.close();
+    let Some(path) = pb.finish() else {
+        return;
+    };
+    mask.fill_path(&path, FillRule::Winding, true, Transform::identity());
+    for (px, &m) in rgba.chunks_exact_mut(4).zip(mask.data()) {
+        if m != 255 {
+            px[3] = ((px[3] as u32 * m as u32 + 127) / 255) as u8;
+        }
+    }
+}
+
+/// 按布局拼接多张 (width, height, RGBA) 图像，空白处透明；返回 (width, height, RGBA)
+pub fn stitch(layout: StitchLayout, images: &[(u32, u32, &[u8])]) -> Result<(u32, u32, Vec<u8>)> {
+    if images.is_empty() {
+        return Err(anyhow!("nothing to stitch"));
+    }
+    let max_w = images.iter().map(|i| i.0).max().unwrap_or(0);
+    let max_h = images.iter().map(|i| i.1).max().unwrap_or(0);
+    let cols = match layout {
+        StitchLayout::Horizontal => images.len(),
+        StitchLayout::Vertical => 1,
+        StitchLayout::Grid => (images.len() as f64).sqrt().ceil() as usize,
+    };
+    let rows = images.len().div_ceil(cols);
+    // 每张图的左上角
+    let mut offsets = Vec::with_capacity(images.len());
+    let (width, height) = match layout {
+        StitchLayout::Horizontal => {
+            let mut x = 0;
+            for img in images {
+                offsets.push((x, 0));
+                x += img.0;
+            }
+            (x, max_h)
+        }
+        StitchLayout::Vertical => {
+            let mut y = 0;
+            for img in images {
+                offsets.push((0, y));
+                y += img.1;
+            }
+            (max_w, y)
+        }
+        StitchLayout::Grid => {
+            for i in 0..images.len() {
+                offsets.push(((i % cols) as u32 * max_w, (i / cols) as u32 * max_h));
+            }
+            (cols as u32 * max_w, rows as u32 * max_h)
+        }
+    };
+    let mut canvas = Renderer::new(width, height)?;
+    for (&(w, h, rgba), (x, y)) in images.iter().zip(offsets) {
+        canvas.blit_rgba(x, y, w, h, rgba);
+    }
+    Ok((width, height, canvas.into_rgba()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_corners() {
+        let (w, h) = (40, 20);
+        let mut rgba = vec![255u8; (w * h * 4) as usize];
+        round_corners(&mut rgba, w, h, 8);
+        let alpha = |x: u32, y: u32| rgba[((y * w + x) * 4 + 3) as usize];
+        // 四角透明，边缘中点与中心不透明，圆弧上为半透明过渡
+        for (x, y) in [(0, 0), (w - 1, 0), (0, h - 1), (w - 1, h - 1)] {
+            assert_eq!(alpha(x, y), 0);
+        }
+        assert_eq!((alpha(20, 0), alpha(0, 10), alpha(20, 10)), (255, 255, 255));
+        assert!((1..8).any(|i| (1..255).contains(&alpha(i, i))));
+        // 颜色通道不变；半径 0 不处理
+        assert_eq!(&rgba[..3], &[255, 255, 255]);
+        let mut plain = vec![255u8; 16];
+        round_corners(&mut plain, 2, 2, 0);
+        assert!(plain.iter().all(|&v| v == 255));
+    }
+
+    #[test]
+    fn test_stitch_layouts() {
+        let a = [1u8; 2 * 2 * 4];
+        let b = [2u8; 4];
+        let images = [(2, 2, &a[..]), (1, 1, &b[..]), (1, 1, &b[..])];
+        let (w, h, out) = stitch(StitchLayout::Horizontal, &images).unwrap();
+        assert_eq!((w, h), (4, 2));
+        assert_eq!((out[0], out[2 * 4], out[(4 + 2) * 4]), (1, 2, 0));
+        let (w, h, _) = stitch(StitchLayout::Vertical, &images).unwrap();
+        assert_eq!((w, h), (2, 4));
+        // 3 张 -> 2 列 2 行，单元格 2x2
+        let (w, h, out) = stitch(StitchLayout::Grid, &images).unwrap();
+        assert_eq!((w, h), (4, 4));
+        assert_eq!(out[(2 * 4) * 4], 2); // 第三张在第二行第一格
+        assert!(stitch(StitchLayout::Grid, &[]).is_err());
+    }
+}
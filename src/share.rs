@@ -0,0 +1,173 @@
+// share: “分享”——把截图临时文件交给 Windows 分享面板（DataTransferManager）或邮件客户端（Simple MAPI）
+//
+// 分享面板以我们自己的窗口（贴图 / overlay）为锚点，必须在该窗口的线程（事件循环）中调用；
+// DataRequested 回调在面板打开时触发（每次分享注册一次，触发后即注销），取得 deferral 后
+// 异步打开文件，完成后以只读文件 + 位图两种格式提供截图，Teams / Outlook / 邮件等目标各取所需。MAPISendMailW 弹出带附件的新邮件窗口，
+// 会阻塞到窗口关闭，请在后台线程调用。
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+/// 分享方式（配置 `share`）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareMethod {
+    /// 系统分享面板，失败时回退为邮件
+    #[default]
+    Sheet,
+    /// 默认邮件客户端新建邮件并附上截图
+    Mail,
+}
+
+/// 分享面板标题 / 邮件主题
+pub fn subject(path: &Path) -> String {
+    match path.file_name() {
+        Some(name) => format!("截图 {}", name.to_string_lossy()),
+        None => "截图".to_string(),
+    }
+}
+
+/// 以窗口 handle 为锚点打开系统分享面板，分享 path 指向的 PNG
+#[cfg(target_os = "windows")]
+pub fn share_sheet(handle: isize, path: &Path) -> Result<()> {
+    use std::sync::atomic::{AtomicI64, Ordering};
+    use std::sync::Arc;
+    use windows::core::{factory, Interface, HSTRING};
+    use windows::ApplicationModel::DataTransfer::{DataRequestedEventArgs, DataTransferManager};
+    use windows::Foundation::TypedEventHandler;
+    use windows::Storage::Streams::RandomAccessStreamReference;
+    use windows::Storage::{IStorageItem, StorageFile};
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::Shell::IDataTransferManagerInterop;
+    use windows_collections::IIterable;
+    use windows_future::AsyncOperationCompletedHandler;
+
+    let hwnd = HWND(handle as *mut _);
+    let interop = factory::<DataTransferManager, IDataTransferManagerInterop>()?;
+    let manager: DataTransferManager = unsafe { interop.GetForWindow(hwnd)? };
+    let file = HSTRING::from(path.as_os_str());
+    let title = HSTRING::from(subject(path));
+    // 注册后才知道 token；回调在 ShowShareUIForWindow 之后触发，届时已写入
+    let token = Arc::new(AtomicI64::new(0));
+    let registered = token.clone();
+    let handler = TypedEventHandler::<DataTransferManager, DataRequestedEventArgs>::new(
+        move |manager, args| {
+            // 只服务这一次分享：下次分享同一窗口时注册新的回调
+            if let Some(manager) = manager.as_ref() {
+                let _ = manager.RemoveDataRequested(registered.load(Ordering::Acquire));
+            }
+            let request = args.ok()?.Request()?;
+            let data = request.Data()?;
+            data.Properties()?.SetTitle(&title)?;
+            // 不在 UI 回调里等待文件打开：deferral 让面板等到异步操作完成
+            let deferral = request.GetDeferral()?;
+            let pending = deferral.clone();
+            let completed = AsyncOperationCompletedHandler::<StorageFile>::new(move |op, _| {
+                let provide = || -> windows::core::Result<()> {
+                    let file = op.ok()?.GetResults()?;
+                    data.SetBitmap(&RandomAccessStreamReference::CreateFromFile(&file)?)?;
+                    let item: IStorageItem = file.cast()?;
+                    data.SetStorageItemsReadOnly(&IIterable::<IStorageItem>::from(vec![Some(item)]))
+                };
+                let result = provide();
+                if let Err(e) = &result {
+                    log::warn!("share: {e}");
+                }
+                pending.Complete()?;
+                result
+            });
+            let started =
+                StorageFile::GetFileFromPathAsync(&file).and_then(|op| op.SetCompleted(&completed));
+            if started.is_err() {
+                let _ = deferral.Complete();
+            }
+            started
+        },
+    );
+    token.store(manager.DataRequested(&handler)?, Ordering::Release);
+    unsafe { interop.ShowShareUIForWindow(hwnd) }.map_err(|e| {
+        let _ = manager.RemoveDataRequested(token.load(Ordering::Acquire));
+        anyhow!("show share ui: {e}")
+    })
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn share_sheet(_handle: isize, _path: &Path) -> Result<()> {
+    Err(anyhow!("share sheet is only available on Windows"))
+}
+
+/// 用默认邮件客户端新建邮件并附上 path（阻塞到邮件窗口关闭或发送）
+#[cfg(target_os = "windows")]
+pub fn send_mail(path: &Path) -> Result<()> {
+    use windows::core::{s, w, PWSTR};
+    use windows::Win32::System::LibraryLoader::{GetProcAddress, LoadLibraryW};
+    use windows::Win32::System::Mapi::{
+        MapiFileDescW, MapiMessageW, MAPI_DIALOG, MAPI_E_USER_ABORT, MAPI_LOGON_UI,
+    };
+
+    type SendMailW = unsafe extern "system" fn(usize, usize, *const MapiMessageW, u32, u32) -> u32;
+
+    let wide = |s: &str| -> Vec<u16> { s.encode_utf16().chain(std::iter::once(0)).collect() };
+    let mut file_path = wide(&path.to_string_lossy());
+    let mut file_name = wide(&path.file_name().unwrap_or_default().to_string_lossy());
+    let mut title = wide(&subject(path));
+    let mut attachment = MapiFileDescW {
+        nPosition: u32::MAX, // 不在正文中占位
+        lpszPathName: PWSTR(file_path.as_mut_ptr()),
+        lpszFileName: PWSTR(file_name.as_mut_ptr()),
+        ..Default::default()
+    };
+    let message = MapiMessageW {
+        lpszSubject: PWSTR(title.as_mut_ptr()),
+        nFileCount: 1,
+        lpFiles: &mut attachment,
+        ..Default::default()
+    };
+    // MAPI32.dll 是邮件客户端注册的 Simple MAPI 转发层
+    let send: SendMailW = unsafe {
+        let module = LoadLibraryW(w!("MAPI32.dll")).map_err(|e| anyhow!("load mapi: {e}"))?;
+        let proc = GetProcAddress(module, s!("MAPISendMailW"))
+            .ok_or_else(|| anyhow!("no mail client supports MAPISendMailW"))?;
+        std::mem::transmute::<unsafe extern "system" fn() -> isize, SendMailW>(proc)
+    };
+    match unsafe { send(0, 0, &message, MAPI_DIALOG | MAPI_LOGON_UI, 0) } {
+        0 => Ok(()),
+        code if code == MAPI_E_USER_ABORT => Ok(()),
+        code => Err(anyhow!("MAPISendMailW failed: {code}")),
+    }
+}
+
+/// 用默认邮件客户端新建邮件并附上 path（xdg-email）
+#[cfg(not(target_os = "windows"))]
+pub fn send_mail(path: &Path) -> Result<()> {
+    let status = std::process::Command::new("xdg-email")
+        .arg("--subject")
+        .arg(subject(path))
+        .arg("--attach")
+        .arg(path)
+        .status()
+        .map_err(|e| anyhow!("launch xdg-email: {e}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!("xdg-email failed: {status}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subject_and_method_parse() {
+        assert_eq!(subject(Path::new("/tmp/snip_1.png")), "截图 snip_1.png");
+        assert_eq!(subject(Path::new("/")), "截图");
+        #[derive(Deserialize)]
+        struct Wrap {
+            share: ShareMethod,
+        }
+        let w: Wrap = toml::from_str("share = \"mail\"").unwrap();
+        assert_eq!(w.share, ShareMethod::Mail);
+        assert_eq!(ShareMethod::default(), ShareMethod::Sheet);
+    }
+}
@@ -1,6 +1,9 @@
 //! Single instance helper.
 //! Windows: use a named mutex (Global scope) to prevent multiple instances.
 //! Other platforms: currently no restriction (always succeeds).
+//! A second instance forwards its CLI commands to the running one over IPC.
+
+use snip_rust::ipc::{self, IpcCommand};
 
 #[cfg(target_os = "windows")]
 use windows::core::PCWSTR;
@@ -37,6 +40,8 @@ pub fn acquire_single_instance() -> Option<InstanceGuard> {
     };
     unsafe {
         if GetLastError() == ERROR_ALREADY_EXISTS {
+            // 不持有多余的句柄，否则运行中的实例退出后互斥体仍然存在
+            let _ = CloseHandle(handle);
             None
         } else {
             Some(InstanceGuard(handle))
@@ -50,3 +55,26 @@ pub struct InstanceGuard;
 pub fn acquire_single_instance() -> Option<InstanceGuard> {
     Some(InstanceGuard)
 }
+
+/// Like `acquire_single_instance`, but keeps retrying until `timeout` while the previous
+/// instance shuts down (elevated relaunch).
+pub fn wait_single_instance(timeout: std::time::Duration) -> Option<InstanceGuard> {
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        if let Some(guard) = acquire_single_instance() {
+            return Some(guard);
+        }
+        if std::time::Instant::now() >= deadline {
+            return None;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+}
+
+/// Forward commands from a second invocation to the running instance.
+pub fn forward_to_running(cmds: &[IpcCommand]) -> anyhow::Result<()> {
+    for cmd in cmds {
+        ipc::send(cmd)?;
+    }
+    Ok(())
+}
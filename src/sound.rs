@@ -0,0 +1,176 @@
+// sound: 确认保存 / 复制时的快门声与复制 / 保存完成的提示音（[sounds] 表；未设置时静音）
+//
+// 不附带音频文件：首次播放时把每种提示音合成为内存中的 16 位单声道 WAV 并缓存，由后台线程经
+// winmm PlaySound(SND_MEMORY) 依次播放，不阻塞事件循环；紧接着的提示音（快门后的复制音）排队
+// 而不打断上一个。
+use serde::Deserialize;
+use std::sync::mpsc;
+use std::sync::OnceLock;
+
+/// 采样率（Hz）
+pub const SAMPLE_RATE: u32 = 22050;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    /// 确认保存 / 复制截图：短促的快门声（衰减噪声）
+    Capture,
+    /// 复制到剪贴板：一声短高音
+    Copy,
+    /// 保存文件完成：上行两音
+    Save,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SoundConfig {
+    pub capture: bool,
+    pub copy: bool,
+    pub save: bool,
+}
+
+impl Default for SoundConfig {
+    fn default() -> Self {
+        Self {
+            capture: true,
+            copy: true,
+            save: true,
+        }
+    }
+}
+
+impl SoundConfig {
+    pub fn enabled(&self, sound: Sound) -> bool {
+        match sound {
+            Sound::Capture => self.capture,
+            Sound::Copy => self.copy,
+            Sound::Save => self.save,
+        }
+    }
+}
+
+/// 交给提示音线程排队播放；系统不支持时静默忽略
+pub fn play(sound: Sound) {
+    static QUEUE: OnceLock<Option<mpsc::Sender<Sound>>> = OnceLock::new();
+    let queue = QUEUE.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<Sound>();
+        std::thread::Builder::new()
+            .name("snip-sound".into())
+            .spawn(move || {
+                for sound in rx {
+                    if !crate::windows_util::play_wav(cached(sound)) {
+                        log::debug!("play {sound:?} sound failed");
+                    }
+                }
+            })
+            .map_err(|e| log::warn!("spawn sound thread: {e}"))
+            .ok()
+            .map(|_| tx)
+    });
+    if let Some(tx) = queue {
+        let _ = tx.send(sound);
+    }
+}
+
+// 每种提示音只合成一次
+fn cached(sound: Sound) -> &'static [u8] {
+    static CACHE: [OnceLock<Vec<u8>>; 3] = [OnceLock::new(), OnceLock::new(), OnceLock::new()];
+    let slot = match sound {
+        Sound::Capture => &CACHE[0],
+        Sound::Copy => &CACHE[1],
+        Sound::Save => &CACHE[2],
+    };
+    slot.get_or_init(|| wav(sound))
+}
+
+/// 合成提示音的 WAV 字节
+pub fn wav(sound: Sound) -> Vec<u8> {
+    let samples = match sound {
+        Sound::Capture => shutter(),
+        Sound::Copy => tone(&[(1320.0, 60)]),
+        Sound::Save => tone(&[(880.0, 70), (1320.0, 90)]),
+    };
+    encode_wav(&samples)
+}
+
+fn samples_for(ms: u32) -> usize {
+    (SAMPLE_RATE * ms / 1000) as usize
+}
+
+// 快门：两段指数衰减的白噪声（开 / 合），固定种子，每次合成结果一致
+fn shutter() -> Vec<i16> {
+    let mut seed: u32 = 0x2545_F491;
+    let mut noise = move || {
+        seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (seed >> 16) as i16 as f32 / 32768.0
+    };
+    let mut out = Vec::new();
+    for (ms, gap) in [(35, 25), (45, 0)] {
+        let n = samples_for(ms);
+        for i in 0..n {
+            let env = (-6.0 * i as f32 / n as f32).exp();
+            out.push((noise() * env * 0.6 * i16::MAX as f32) as i16);
+        }
+        out.resize(out.len() + samples_for(gap), 0);
+    }
+    out
+}
+
+// 依次播放的正弦音（频率 Hz, 时长 ms），首尾各 5 毫秒淡入淡出避免爆音
+fn tone(notes: &[(f32, u32)]) -> Vec<i16> {
+    let fade = samples_for(5) as f32;
+    let mut out = Vec::new();
+    for &(freq, ms) in notes {
+        let n = samples_for(ms);
+        for i in 0..n {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let env = (i as f32 / fade).min((n - i) as f32 / fade).min(1.0);
+            let v = (t * freq * std::f32::consts::TAU).sin() * env * 0.4;
+            out.push((v * i16::MAX as f32) as i16);
+        }
+    }
+    out
+}
+
+// 16 位单声道 PCM WAV
+fn encode_wav(samples: &[i16]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&1u16.to_le_bytes()); // 单声道
+    out.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    out.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&16u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for s in samples {
+        out.extend_from_slice(&s.to_le_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wav_header_and_length() {
+        let save = wav(Sound::Save);
+        assert_eq!(&save[..4], b"RIFF");
+        assert_eq!(&save[8..16], b"WAVEfmt ");
+        let data_len = u32::from_le_bytes(save[40..44].try_into().unwrap()) as usize;
+        assert_eq!(data_len, save.len() - 44);
+        // 70 + 90 毫秒
+        assert_eq!(data_len, (samples_for(70) + samples_for(90)) * 2);
+        // 快门声确定且不为静音
+        let shutter = wav(Sound::Capture);
+        assert_eq!(shutter, wav(Sound::Capture));
+        assert!(shutter[44..].iter().any(|&b| b != 0));
+        let c: SoundConfig = toml::from_str("copy = false").unwrap();
+        assert!(c.enabled(Sound::Capture) && !c.enabled(Sound::Copy));
+    }
+}
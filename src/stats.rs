@@ -0,0 +1,143 @@
+// stats: 本地使用统计（配置 stats = true 开启，默认关闭）
+//
+// 只在本机计数：截图、保存、复制、钉图次数与平均选区尺寸，写入 <数据目录>/stats.json（见 paths），
+// 不联网、不上传。托盘“统计”弹窗显示汇总。每次计数后立即写盘（文件很小），崩溃也不丢失。
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 计数的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatEvent {
+    Capture,
+    Save,
+    Copy,
+    Pin,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    /// 开始统计的时间（unix 秒）
+    pub since: u64,
+    pub captures: u64,
+    pub saves: u64,
+    pub copies: u64,
+    pub pins: u64,
+    /// 导出（保存 / 复制 / 钉图）的选区数与宽高总和，用于平均尺寸
+    pub selections: u64,
+    pub selection_width_sum: u64,
+    pub selection_height_sum: u64,
+}
+
+impl Stats {
+    /// 从现在开始计数
+    pub fn new() -> Self {
+        Self {
+            since: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            ..Self::default()
+        }
+    }
+
+    pub fn record(&mut self, event: StatEvent) {
+        let counter = match event {
+            StatEvent::Capture => &mut self.captures,
+            StatEvent::Save => &mut self.saves,
+            StatEvent::Copy => &mut self.copies,
+            StatEvent::Pin => &mut self.pins,
+        };
+        *counter += 1;
+    }
+
+    /// 导出动作附带的选区尺寸
+    pub fn record_selection(&mut self, width: u32, height: u32) {
+        self.selections += 1;
+        self.selection_width_sum += width as u64;
+        self.selection_height_sum += height as u64;
+    }
+
+    /// 平均选区宽高（四舍五入；尚无选区时为 None）
+    pub fn average_selection(&self) -> Option<(u64, u64)> {
+        let n = self.selections;
+        (n > 0).then(|| {
+            (
+                (self.selection_width_sum + n / 2) / n,
+                (self.selection_height_sum + n / 2) / n,
+            )
+        })
+    }
+
+    /// “统计”弹窗文本
+    pub fn summary(&self) -> String {
+        let t = crate::naming::local_now(self.since);
+        let average = match self.average_selection() {
+            Some((w, h)) => format!("{w} × {h}"),
+            None => "-".to_string(),
+        };
+        format!(
+            "自 {:04}-{:02}-{:02} 起\n截图：{}\n保存：{}\n复制：{}\n钉图：{}\n平均选区：{average}",
+            t.year, t.month, t.day, self.captures, self.saves, self.copies, self.pins
+        )
+    }
+}
+
+/// 统计文件路径
+pub fn stats_path() -> Option<PathBuf> {
+    crate::paths::data_dir().map(|d| d.join("stats.json"))
+}
+
+/// 读取统计；文件不存在时从现在开始计数
+pub fn load(path: &Path) -> Result<Stats> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(serde_json::from_str(&text)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Stats::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn save(path: &Path, stats: &Stats) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(stats)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_record_and_average() {
+        let mut stats = Stats::default();
+        assert_eq!(stats.average_selection(), None);
+        stats.record(StatEvent::Capture);
+        stats.record(StatEvent::Capture);
+        stats.record(StatEvent::Save);
+        stats.record_selection(100, 50);
+        stats.record_selection(201, 50);
+        assert_eq!((stats.captures, stats.saves, stats.copies), (2, 1, 0));
+        assert_eq!(stats.average_selection(), Some((151, 50)));
+        assert!(stats.summary().contains("平均选区：151 × 50"));
+    }
+
+    #[test]
+    fn test_load_and_save_roundtrip() {
+        let dir = TempDir::new("stats_test");
+        let path = dir.join("stats.json");
+        let fresh = load(&path).unwrap();
+        assert_eq!(fresh.captures, 0);
+        assert!(fresh.since > 0);
+        let mut stats = fresh.clone();
+        stats.record(StatEvent::Pin);
+        save(&path, &stats).unwrap();
+        let loaded = load(&path).unwrap();
+        assert_eq!(loaded, stats);
+    }
+}
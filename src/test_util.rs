@@ -0,0 +1,64 @@
+// test_util: 单元测试共用的临时目录（只在 cfg(test) 下编译）
+//
+// 目录名带进程 id 与进程内递增序号，同一进程中并行运行的测试互不冲突；
+// TempDir 离开作用域时（包括断言失败 panic 时）删除整个目录。
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// 新建的空临时目录，drop 时递归删除
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// tag 标明用途，测试中途被杀掉时便于辨认残留目录
+    pub fn new(tag: &str) -> Self {
+        let n = NEXT.fetch_add(1, Ordering::SeqCst);
+        let dir = std::env::temp_dir().join(format!("snip_{tag}_{}_{n}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("create test temp dir");
+        Self(dir)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unique_and_removed_on_drop() {
+        let a = TempDir::new("util");
+        let b = TempDir::new("util");
+        assert_ne!(a.path(), b.path());
+        assert!(a.is_dir() && b.is_dir());
+        std::fs::write(a.join("x"), b"x").unwrap();
+        let kept = a.to_path_buf();
+        drop(a);
+        assert!(!kept.exists());
+    }
+}
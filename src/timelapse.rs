@@ -0,0 +1,603 @@
+// timelapse: 定时截图（每 N 秒截取全屏或固定区域，写入目录，可限制张数 / 总大小）
+//
+// 托盘“定时截图”启动 / 停止后台线程；线程以 recv_timeout 等待停止信号，不占用事件循环。
+// 按固定节拍（start + n × interval）截图，截图耗时不会累积成漂移；错过的节拍直接跳过，不补拍。
+// 节拍只是 Instant 计时，不是录制所需的 DXGI 帧就绪 / 垂直同步节奏（没有视频录制器）。
+// 达到 max_count / max_mb / max_minutes 时自动停止并回调 on_stop（托盘据此取消勾选）。
+// 托盘“暂停定时截图”经 Control 暂停 / 继续：暂停期间不截图，也不计入运行时长。
+// （录制“暂停 / 时长上限”的替代：状态只经托盘显示，没有屏幕上的录制指示器）
+// cursor = true 时把截图瞬间的鼠标指针画进图像（屏幕截图本身不含指针），clicks = true 时
+// 自上一张以来有过点击则在指针处加两圈“点击波纹”，便于做教程图。没有视频录制器：
+// 这是录制“指针 / 点击可视化”在定时截图上的替代，点击靠截图时轮询 GetAsyncKeyState，
+// 波纹为静态图形。
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::capture::{self, Rect};
+use crate::encode::save_png_auto;
+use crate::naming::{self, LocalTime};
+use crate::windows_util;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct TimelapseConfig {
+    /// 截图间隔（秒），最小 1
+    pub interval_secs: u64,
+    /// 输出目录；未设置时为数据目录下的 timelapse/
+    pub folder: Option<PathBuf>,
+    /// 固定区域（屏幕物理像素，`{ x, y, width, height }`）；未设置时截取主屏全屏
+    pub region: Option<Rect>,
+    /// 最多保存张数
+    pub max_count: Option<u32>,
+    /// 本次运行写入的总大小上限（MB）
+    pub max_mb: Option<u64>,
+    /// 本次运行时长上限（分钟，不含暂停）
+    pub max_minutes: Option<u64>,
+    /// 在截图中绘制鼠标指针
+    pub cursor: bool,
+    /// 绘制指针时，自上一张以来按过鼠标左 / 右键则加点击波纹
+    pub clicks: bool,
+}
+
+impl Default for TimelapseConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 60,
+            folder: None,
+            region: None,
+            max_count: None,
+            max_mb: None,
+            max_minutes: None,
+            cursor: false,
+            clicks: true,
+        }
+    }
+}
+
+/// 后台线程结束的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// 托盘取消勾选 / 程序退出
+    Requested,
+    MaxCount,
+    Quota,
+    Duration,
+}
+
+/// 本次运行已写入的张数与字节数，以及运行时长（不含暂停）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    pub count: u32,
+    pub bytes: u64,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// 托盘菜单 / 提示中的状态：`12 张 · 05:32`，暂停时带“已暂停”前缀
+    pub fn status_text(&self, paused: bool) -> String {
+        let secs = self.elapsed.as_secs();
+        let time = match secs / 3600 {
+            0 => format!("{:02}:{:02}", secs / 60, secs % 60),
+            h => format!("{h}:{:02}:{:02}", secs / 60 % 60, secs % 60),
+        };
+        let text = format!("{} 张 · {time}", self.count);
+        if paused {
+            format!("已暂停 · {text}")
+        } else {
+            text
+        }
+    }
+}
+
+/// 后台线程与托盘共享的状态：暂停开关 + 进度（运行时长按暂停 / 继续的时刻精确累计）
+#[derive(Debug)]
+pub struct Control {
+    state: Mutex<ControlState>,
+}
+
+#[derive(Debug)]
+struct ControlState {
+    /// elapsed 为截至上次暂停的累计时长
+    progress: Progress,
+    /// 本段运行的开始时刻；暂停时为 None
+    running_since: Option<Instant>,
+}
+
+impl Default for Control {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(ControlState {
+                progress: Progress::default(),
+                running_since: Some(Instant::now()),
+            }),
+        }
+    }
+}
+
+impl Control {
+    fn state(&self) -> std::sync::MutexGuard<'_, ControlState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state().running_since.is_none()
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        let mut state = self.state();
+        match (paused, state.running_since) {
+            (true, Some(since)) => {
+                state.progress.elapsed += since.elapsed();
+                state.running_since = None;
+            }
+            (false, None) => state.running_since = Some(Instant::now()),
+            _ => {}
+        }
+    }
+
+    /// 当前进度（含本段运行的时长）
+    pub fn progress(&self) -> Progress {
+        let state = self.state();
+        let mut progress = state.progress;
+        if let Some(since) = state.running_since {
+            progress.elapsed += since.elapsed();
+        }
+        progress
+    }
+
+    fn add_frame(&self, bytes: u64) {
+        let mut state = self.state();
+        state.progress.count += 1;
+        state.progress.bytes += bytes;
+    }
+}
+
+impl TimelapseConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.max(1))
+    }
+
+    /// 输出目录（平台无数据目录且未配置 folder 时为 None）
+    pub fn folder(&self) -> Option<PathBuf> {
+        self.folder
+            .clone()
+            .or_else(|| crate::paths::data_dir().map(|d| d.join("timelapse")))
+    }
+
+    /// 再写入一张 next_bytes 字节的截图是否超出限制；None 表示可以写入
+    pub fn check(&self, done: Progress, next_bytes: u64) -> Option<StopReason> {
+        if self.max_count.is_some_and(|max| done.count >= max) {
+            return Some(StopReason::MaxCount);
+        }
+        let limit = self
+            .max_minutes
+            .map(|m| Duration::from_secs(m.saturating_mul(60)));
+        if limit.is_some_and(|limit| done.elapsed >= limit) {
+            return Some(StopReason::Duration);
+        }
+        let quota = self.max_mb.map(|mb| mb.saturating_mul(1024 * 1024));
+        if quota.is_some_and(|quota| done.bytes + next_bytes > quota) {
+            return Some(StopReason::Quota);
+        }
+        None
+    }
+}
+
+/// 截图文件名（不含扩展名）：timelapse_YYYY-MM-DD_HH-MM-SS
+pub fn frame_name(t: LocalTime) -> String {
+    format!(
+        "timelapse_{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        t.year, t.month, t.day, t.hour, t.minute, t.second
+    )
+}
+
+/// 运行中的定时截图；stop 或 drop 时结束后台线程
+pub struct Timelapse {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+    control: Arc<Control>,
+}
+
+impl Timelapse {
+    /// 创建输出目录并启动后台线程（立即截取第一张）；线程结束时调用 on_stop
+    pub fn start(
+        config: TimelapseConfig,
+        on_stop: impl FnOnce(StopReason, Progress) + Send + 'static,
+    ) -> Result<Self> {
+        let folder = config
+            .folder()
+            .ok_or_else(|| anyhow!("no timelapse folder configured"))?;
+        std::fs::create_dir_all(&folder)
+            .map_err(|e| anyhow!("create {}: {e}", folder.display()))?;
+        let (tx, rx) = mpsc::channel();
+        let interval = config.interval();
+        let control = Arc::new(Control::default());
+        let shared = Arc::clone(&control);
+        let thread = std::thread::Builder::new()
+            .name("snip-timelapse".into())
+            .spawn(move || {
+                let grab = {
+                    let config = config.clone();
+                    move || grab_frame(&config)
+                };
+                let (reason, progress) = run(&config, &folder, interval, &rx, &shared, grab);
+                log::info!(
+                    "timelapse stopped ({reason:?}): {} captures, {} bytes",
+                    progress.count,
+                    progress.bytes
+                );
+                on_stop(reason, progress);
+            })?;
+        Ok(Self {
+            stop: Some(tx),
+            thread: Some(thread),
+            control,
+        })
+    }
+
+    /// 暂停 / 继续与当前进度
+    pub fn control(&self) -> &Control {
+        &self.control
+    }
+
+    /// 通知后台线程停止并等待其结束（最多等待一次截图的时间）
+    pub fn stop(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Timelapse {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// 截取一张 PNG；需要绘制指针时先取 RGBA（全屏时带原点，用于换算指针坐标）
+fn grab_frame(config: &TimelapseConfig) -> Result<Vec<u8>> {
+    if !config.cursor {
+        return match config.region {
+            Some(rect) => capture::capture_area(rect),
+            None => capture::capture_fullscreen(),
+        };
+    }
+    let (rect, mut rgba) = match config.region {
+        Some(rect) => (rect, capture::capture_area_rgba(rect)?),
+        None => {
+            let (x, y, width, height, rgba) = capture::capture_fullscreen_raw_with_origin()?;
+            let rect = Rect {
+                x,
+                y,
+                width,
+                height,
+            };
+            (rect, rgba)
+        }
+    };
+    if let Some((cx, cy)) = windows_util::cursor_position() {
+        let clicked = config.clicks && windows_util::mouse_clicked();
+        let at = (cx - rect.x, cy - rect.y);
+        draw_cursor(&mut rgba, rect.width, rect.height, at, clicked);
+    }
+    capture::encode_png(&rgba, rect.width, rect.height)
+}
+
+// 箭头指针（热点在左上角）：'#' 黑色描边，'.' 白色填充
+const ARROW: [&str; 17] = [
+    "#",
+    "##",
+    "#.#",
+    "#..#",
+    "#...#",
+    "#....#",
+    "#.....#",
+    "#......#",
+    "#.......#",
+    "#........#",
+    "#.........#",
+    "#......####",
+    "#..#..#",
+    "#.# #..#",
+    "##  #..#",
+    "     #..#",
+    "      ##",
+];
+const RIPPLE_RADII: [f32; 2] = [10.0, 18.0];
+const RIPPLE_COLOR: [u8; 3] = [255, 200, 0];
+
+/// 在 RGBA 图像的 at（图像坐标，可在图像外）处画鼠标指针；clicked 时先画点击波纹
+pub fn draw_cursor(rgba: &mut [u8], width: u32, height: u32, at: (i32, i32), clicked: bool) {
+    let (w, h) = (width as i32, height as i32);
+    let mut blend = |x: i32, y: i32, color: [u8; 3], alpha: f32| {
+        if x < 0 || y < 0 || x >= w || y >= h {
+            return;
+        }
+        let i = (y * w + x) as usize * 4;
+        let Some(px) = rgba.get_mut(i..i + 4) else {
+            return;
+        };
+        for (dst, src) in px.iter_mut().zip(color) {
+            *dst = (src as f32 * alpha + *dst as f32 * (1.0 - alpha)).round() as u8;
+        }
+        px[3] = px[3].max((alpha * 255.0) as u8);
+    };
+    if clicked {
+        // 2 像素宽的圆环，外圈更淡
+        let reach = RIPPLE_RADII[1] as i32 + 2;
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                let d = ((dx * dx + dy * dy) as f32).sqrt();
+                for (k, r) in RIPPLE_RADII.into_iter().enumerate() {
+                    let cover = (1.0 - ((d - r).abs() - 0.5).max(0.0)).clamp(0.0, 1.0);
+                    if cover > 0.0 {
+                        let alpha = cover * if k == 0 { 0.9 } else { 0.5 };
+                        blend(at.0 + dx, at.1 + dy, RIPPLE_COLOR, alpha);
+                    }
+                }
+            }
+        }
+    }
+    for (row, line) in ARROW.iter().enumerate() {
+        for (col, c) in line.bytes().enumerate() {
+            let color = match c {
+                b'#' => [0, 0, 0],
+                b'.' => [255, 255, 255],
+                _ => continue,
+            };
+            blend(at.0 + col as i32, at.1 + row as i32, color, 1.0);
+        }
+    }
+}
+
+/// 固定节拍：返回 elapsed（距开始）之后的下一个节拍序号，至少为 slot + 1；
+/// 截图 / 写文件超过一个周期时跳过已错过的节拍
+pub fn next_slot(slot: u64, elapsed: Duration, interval: Duration) -> u64 {
+    let passed = elapsed.as_nanos() / interval.as_nanos().max(1);
+    (passed as u64).max(slot) + 1
+}
+
+// 第 n 个节拍距开始的时间
+fn slot_time(n: u64, interval: Duration) -> Duration {
+    interval
+        .checked_mul(u32::try_from(n).unwrap_or(u32::MAX))
+        .unwrap_or(Duration::MAX)
+}
+
+// 调度循环：检查限制 -> 截图 -> 写文件 -> 等到下一个节拍或停止信号。
+// 单次截图失败（锁屏 / 安全桌面）只记录日志，下个周期重试；暂停时只等待
+fn run(
+    config: &TimelapseConfig,
+    folder: &Path,
+    interval: Duration,
+    stop: &Receiver<()>,
+    control: &Control,
+    mut grab: impl FnMut() -> Result<Vec<u8>>,
+) -> (StopReason, Progress) {
+    let start = Instant::now();
+    let mut slot = 0;
+    loop {
+        let progress = control.progress();
+        if let Some(reason) = config.check(progress, 0) {
+            return (reason, progress);
+        }
+        if !control.is_paused() {
+            match grab() {
+                Ok(png) => {
+                    if let Some(reason) = config.check(progress, png.len() as u64) {
+                        return (reason, progress);
+                    }
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map(|d| d.as_secs())
+                        .unwrap_or(0);
+                    let name = folder.join(frame_name(naming::local_now(now)));
+                    match save_png_auto(&png, &name.to_string_lossy()) {
+                        Ok(_) => control.add_frame(png.len() as u64),
+                        Err(e) => log::warn!("timelapse save: {e}"),
+                    }
+                }
+                Err(e) => log::warn!("timelapse capture: {e}"),
+            }
+        }
+        let next = next_slot(slot, start.elapsed(), interval);
+        if next > slot + 1 {
+            log::debug!("timelapse skipped {} slots", next - slot - 1);
+        }
+        slot = next;
+        let wait = slot_time(slot, interval).saturating_sub(start.elapsed());
+        match stop.recv_timeout(wait) {
+            Err(RecvTimeoutError::Timeout) => continue,
+            // 收到信号或 Timelapse 已释放
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => {
+                return (StopReason::Requested, control.progress());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_check_limits() {
+        let config = TimelapseConfig {
+            max_count: Some(3),
+            max_mb: Some(1),
+            max_minutes: Some(2),
+            ..Default::default()
+        };
+        let done = |count, bytes| Progress {
+            count,
+            bytes,
+            elapsed: Duration::ZERO,
+        };
+        assert_eq!(config.check(done(2, 0), 1000), None);
+        assert_eq!(config.check(done(3, 0), 0), Some(StopReason::MaxCount));
+        assert_eq!(config.check(done(1, 1024 * 1024 - 10), 10), None);
+        assert_eq!(
+            config.check(done(1, 1024 * 1024 - 10), 11),
+            Some(StopReason::Quota)
+        );
+        let mut late = done(0, 0);
+        late.elapsed = Duration::from_secs(119);
+        assert_eq!(config.check(late, 0), None);
+        late.elapsed = Duration::from_secs(120);
+        assert_eq!(config.check(late, 0), Some(StopReason::Duration));
+        assert_eq!(TimelapseConfig::default().check(done(u32::MAX, 0), 1), None);
+    }
+
+    #[test]
+    fn test_pause_and_status_text() {
+        let control = Control::default();
+        control.set_paused(true);
+        let paused = control.progress().elapsed;
+        std::thread::sleep(Duration::from_millis(20));
+        // 暂停期间时长不增加
+        assert_eq!(control.progress().elapsed, paused);
+        assert!(control.is_paused());
+        control.set_paused(false);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(control.progress().elapsed > paused);
+        let progress = Progress {
+            count: 12,
+            bytes: 0,
+            elapsed: Duration::from_secs(332),
+        };
+        assert_eq!(progress.status_text(false), "12 张 · 05:32");
+        assert_eq!(progress.status_text(true), "已暂停 · 12 张 · 05:32");
+        let long = Progress {
+            elapsed: Duration::from_secs(3600 + 61),
+            ..progress
+        };
+        assert_eq!(long.status_text(false), "12 张 · 1:01:01");
+    }
+
+    #[test]
+    fn test_run_skips_grabs_while_paused() {
+        let dir = TempDir::new("timelapse_pause");
+        let (tx, rx) = mpsc::channel();
+        drop(tx);
+        let control = Control::default();
+        control.set_paused(true);
+        let (reason, progress) = run(
+            &TimelapseConfig::default(),
+            &dir,
+            Duration::from_secs(60),
+            &rx,
+            &control,
+            || panic!("grab while paused"),
+        );
+        assert_eq!(reason, StopReason::Requested);
+        assert_eq!(progress.count, 0);
+    }
+
+    #[test]
+    fn test_config_parse() {
+        let c: TimelapseConfig = toml::from_str(
+            "interval_secs = 0\nmax_count = 100\nregion = { x = -1920, y = 0, width = 800, height = 600 }",
+        )
+        .unwrap();
+        assert_eq!(c.interval(), Duration::from_secs(1));
+        assert_eq!(c.max_count, Some(100));
+        assert!(!c.cursor && c.clicks);
+        assert_eq!(c.region.map(|r| (r.x, r.width)), Some((-1920, 800)));
+        assert_eq!(
+            TimelapseConfig::default().interval(),
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn test_run_stops_at_max_count() {
+        let dir = TempDir::new("timelapse_test");
+        let config = TimelapseConfig {
+            max_count: Some(3),
+            ..Default::default()
+        };
+        let (_tx, rx) = mpsc::channel();
+        let mut grabs = 0;
+        let control = Control::default();
+        let (reason, progress) = run(
+            &config,
+            &dir,
+            Duration::from_millis(1),
+            &rx,
+            &control,
+            || {
+                grabs += 1;
+                Ok(vec![0u8; 10])
+            },
+        );
+        assert_eq!(reason, StopReason::MaxCount);
+        assert_eq!((progress.count, progress.bytes), (3, 30));
+        assert_eq!(grabs, 3);
+        // 同一秒内的文件名追加 _2、_3，不互相覆盖
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn test_run_stops_when_requested() {
+        let dir = TempDir::new("timelapse_stop");
+        let (tx, rx) = mpsc::channel();
+        drop(tx);
+        // 截图失败不计数，仍等待下个周期（此处停止信号已到）
+        let (reason, progress) = run(
+            &TimelapseConfig::default(),
+            &dir,
+            Duration::from_secs(60),
+            &rx,
+            &Control::default(),
+            || Err(anyhow!("locked")),
+        );
+        assert_eq!(reason, StopReason::Requested);
+        assert_eq!((progress.count, progress.bytes), (0, 0));
+    }
+
+    #[test]
+    fn test_draw_cursor() {
+        let (w, h) = (40, 40);
+        let mut rgba = vec![128u8; w * h * 4];
+        let px = |rgba: &[u8], x: usize, y: usize| rgba[(y * w + x) * 4..][..3].to_vec();
+        draw_cursor(&mut rgba, w as u32, h as u32, (20, 20), false);
+        assert_eq!(px(&rgba, 20, 20), [0, 0, 0]);
+        assert_eq!(px(&rgba, 21, 23), [255, 255, 255]);
+        // 未点击时不画波纹
+        assert_eq!(px(&rgba, 10, 20), [128, 128, 128]);
+        draw_cursor(&mut rgba, w as u32, h as u32, (20, 20), true);
+        assert_eq!(px(&rgba, 10, 20)[0], 242);
+        assert_eq!(px(&rgba, 2, 20)[0], 192);
+        // 指针在图像外（部分可见）不越界
+        draw_cursor(&mut rgba, w as u32, h as u32, (-5, 38), true);
+        draw_cursor(&mut rgba, w as u32, h as u32, (1000, -1000), true);
+    }
+
+    #[test]
+    fn test_next_slot_skips_missed_ticks() {
+        let second = Duration::from_secs(1);
+        let ms = Duration::from_millis;
+        // 截图耗时不足一个周期：下一个节拍
+        assert_eq!(next_slot(0, ms(300), second), 1);
+        assert_eq!(next_slot(4, ms(4_900), second), 5);
+        // 截图耗时 2.5 个周期：跳过节拍 1、2
+        assert_eq!(next_slot(0, ms(2_500), second), 3);
+        // 恰好落在节拍上时不重复该节拍
+        assert_eq!(next_slot(2, ms(3_000), second), 4);
+        assert_eq!(slot_time(3, second), ms(3_000));
+        assert_eq!(slot_time(u64::MAX, second), second * u32::MAX);
+    }
+
+    #[test]
+    fn test_frame_name() {
+        let t = naming::utc_from_unix(1_700_000_000);
+        assert_eq!(frame_name(t), "timelapse_2023-11-14_22-13-20");
+    }
+}
@@ -0,0 +1,201 @@
+// toast: 耗时后台任务的进度提示（PNG 体积优化）
+//
+// 无边框置顶小窗口，位于主显示器工作区右下角，只画一条进度条；不抢焦点、鼠标穿透，并排除在
+// 截图之外（不会出现在下一张截图里）。任务名与百分比写入窗口标题（屏幕阅读器朗读）。
+// 配色与 UI 缩放同 overlay 工具栏，任务全部结束后由 App 隐藏。
+use anyhow::Result;
+use softbuffer::{Context, Surface};
+use std::num::NonZeroU32;
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event_loop::ActiveEventLoop,
+    platform::windows::WindowAttributesExtWindows,
+    window::{WindowAttributes, WindowLevel},
+};
+
+use crate::overlay::drawing::fill_rect;
+use crate::overlay::theme::{Theme, UiScale};
+use crate::window_surface::{SharedWindow, SurfaceWindow};
+
+/// 提示窗尺寸与进度条内边距（1.0 倍 UI 缩放下的像素）
+const WIDTH: i32 = 220;
+const HEIGHT: i32 = 28;
+const PAD: i32 = 9;
+/// 提示窗与工作区边缘的距离
+pub const TOAST_MARGIN: i32 = 12;
+
+/// 提示窗尺寸 (w, h)
+pub fn toast_size(scale: UiScale) -> (u32, u32) {
+    (scale.px(WIDTH) as u32, scale.px(HEIGHT) as u32)
+}
+
+/// 提示窗左上角的屏幕坐标：主显示器（包含原点的）工作区右下角，找不到时用第一个工作区
+pub fn toast_position(size: (u32, u32), work_areas: &[(i32, i32, u32, u32)]) -> (i32, i32) {
+    let contains_origin = |&&(x, y, w, h): &&(i32, i32, u32, u32)| {
+        x <= 0 && y <= 0 && x + w as i32 > 0 && y + h as i32 > 0
+    };
+    let Some(&(x, y, w, h)) = work_areas
+        .iter()
+        .find(contains_origin)
+        .or(work_areas.first())
+    else {
+        return (TOAST_MARGIN, TOAST_MARGIN);
+    };
+    (
+        x + w as i32 - size.0 as i32 - TOAST_MARGIN,
+        y + h as i32 - size.1 as i32 - TOAST_MARGIN,
+    )
+}
+
+/// 进度条已完成部分的宽度（progress 限制在 0..=1）
+pub fn bar_fill(width: i32, progress: f32) -> i32 {
+    (width as f32 * progress.clamp(0.0, 1.0)).round() as i32
+}
+
+pub struct ProgressToast {
+    pub window: SharedWindow,
+    surface: Surface<SharedWindow, SharedWindow>,
+    _context: Context<SharedWindow>,
+    theme: Theme,
+    visible: bool,
+    progress: f32,
+    percent: Option<u32>, // 标题中显示的百分比，变化时才更新标题
+}
+
+impl ProgressToast {
+    /// 创建隐藏的提示窗（首次需要显示进度时）
+    pub fn new(active: &ActiveEventLoop) -> Result<Self> {
+        let attrs = WindowAttributes::default()
+            .with_title("Snip Rust")
+            .with_decorations(false)
+            .with_resizable(false)
+            .with_visible(false)
+            .with_active(false)
+            .with_window_level(WindowLevel::AlwaysOnTop)
+            .with_inner_size(PhysicalSize::new(1, 1))
+            .with_skip_taskbar(true);
+        let SurfaceWindow {
+            window,
+            surface,
+            context,
+        } = SurfaceWindow::create(active, attrs, "toast")?;
+        let _ = window.set_cursor_hittest(false);
+        crate::windows_util::set_exclude_from_capture(&window, true);
+        Ok(Self {
+            window,
+            surface,
+            _context: context,
+            theme: Theme::default(),
+            visible: false,
+            progress: 0.0,
+            percent: None,
+        })
+    }
+
+    /// 显示 / 更新进度（0..=1）；首次显示时按 theme 摆放
+    pub fn show(&mut self, label: &str, progress: f32, theme: Theme) {
+        if !self.visible {
+            let size = toast_size(theme.scale);
+            let (x, y) = toast_position(size, &crate::windows_util::monitor_work_areas());
+            self.theme = theme;
+            let _ = self
+                .window
+                .request_inner_size(PhysicalSize::new(size.0, size.1));
+            self.window.set_outer_position(PhysicalPosition::new(x, y));
+            self.window.set_visible(true);
+            self.visible = true;
+        }
+        self.progress = progress;
+        let percent = (progress.clamp(0.0, 1.0) * 100.0) as u32;
+        if self.percent != Some(percent) {
+            self.percent = Some(percent);
+            self.window.set_title(&format!("{label} {percent}%"));
+        }
+        self.window.request_redraw();
+    }
+
+    pub fn hide(&mut self) {
+        if self.visible {
+            self.visible = false;
+            self.percent = None;
+            self.window.set_visible(false);
+        }
+    }
+
+    pub fn redraw(&mut self) {
+        if !self.visible {
+            return;
+        }
+        let size = self.window.inner_size();
+        let (Some(w), Some(h)) = (NonZeroU32::new(size.width), NonZeroU32::new(size.height)) else {
+            return;
+        };
+        if self.surface.resize(w, h).is_err() {
+            return;
+        }
+        let Ok(mut frame) = self.surface.buffer_mut() else {
+            return;
+        };
+        let (width, height) = (size.width, size.height);
+        let theme = &self.theme;
+        fill_rect(
+            &mut frame,
+            width,
+            height,
+            0,
+            0,
+            width as i32,
+            height as i32,
+            theme.panel,
+        );
+        theme.outline(
+            &mut frame,
+            width,
+            height,
+            (0, 0, width as i32, height as i32),
+            theme.panel_border,
+        );
+        let pad = theme.scale.px(PAD);
+        let track = (pad, pad, width as i32 - pad * 2, height as i32 - pad * 2);
+        let (tx, ty, tw, th) = track;
+        fill_rect(&mut frame, width, height, tx, ty, tw, th, theme.button);
+        let done = bar_fill(tw, self.progress);
+        fill_rect(
+            &mut frame,
+            width,
+            height,
+            tx,
+            ty,
+            done,
+            th,
+            theme.focus_ring,
+        );
+        theme.outline(&mut frame, width, height, track, theme.button_border);
+        let _ = frame.present();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toast_layout() {
+        assert_eq!(toast_size(UiScale::new(1.0)), (220, 28));
+        assert_eq!(toast_size(UiScale::new(2.0)), (440, 56));
+        // 主显示器在右，副显示器在左：选包含原点的工作区，贴右下角
+        let areas = [(-1920, 0, 1920, 1040), (0, 0, 2560, 1400)];
+        assert_eq!(
+            toast_position((220, 28), &areas),
+            (2560 - 220 - TOAST_MARGIN, 1400 - 28 - TOAST_MARGIN)
+        );
+        assert_eq!(
+            toast_position((220, 28), &[(100, 50, 800, 600)]),
+            (900 - 220 - TOAST_MARGIN, 650 - 28 - TOAST_MARGIN)
+        );
+        assert_eq!(toast_position((220, 28), &[]), (TOAST_MARGIN, TOAST_MARGIN));
+        assert_eq!(bar_fill(200, 0.25), 50);
+        assert_eq!(bar_fill(200, 1.5), 200);
+        assert_eq!(bar_fill(200, -1.0), 0);
+    }
+}
@@ -0,0 +1,131 @@
+// touch: 触摸屏 / 触控笔输入 -> 双指捏合与压力（overlay 与贴图共用）
+//
+// winit 把手指与触控笔都作为 WindowEvent::Touch 发送（Windows 下带压力），同时照常送出系统据同一次
+// 触摸生成的鼠标消息。单指 / 笔的拖动与点击只走鼠标路径（再按 Touch 转发一次会重复按下 / 抬起），
+// 这里只跟踪手指：第二根手指按下时报告 PinchStarted（调用方结束单指操作），之后两指距离的变化以
+// Pinch(本次距离 / 上次距离) 报告，直到手指全部抬起。
+use winit::event::{Force, TouchPhase};
+
+/// 一次触摸事件的处理结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TouchInput {
+    PinchStarted,
+    Pinch(f64),
+    None,
+}
+
+#[derive(Debug, Default)]
+pub struct TouchTracker {
+    fingers: Vec<(u64, (f64, f64))>,
+    pinch: Option<f64>, // 捏合中：上一次的两指距离
+}
+
+impl TouchTracker {
+    pub fn update(&mut self, id: u64, phase: TouchPhase, pos: (f64, f64)) -> TouchInput {
+        match phase {
+            TouchPhase::Started => {
+                self.fingers.retain(|f| f.0 != id);
+                self.fingers.push((id, pos));
+                if self.fingers.len() == 2 {
+                    self.pinch = Some(self.spread());
+                    return TouchInput::PinchStarted;
+                }
+                TouchInput::None
+            }
+            TouchPhase::Moved => {
+                if let Some(f) = self.fingers.iter_mut().find(|f| f.0 == id) {
+                    f.1 = pos;
+                }
+                let Some(last) = self.pinch.filter(|_| self.fingers.len() >= 2) else {
+                    return TouchInput::None;
+                };
+                let spread = self.spread();
+                self.pinch = Some(spread);
+                if last > 0.0 && spread > 0.0 {
+                    TouchInput::Pinch(spread / last)
+                } else {
+                    TouchInput::None
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.fingers.retain(|f| f.0 != id);
+                if self.fingers.is_empty() {
+                    self.pinch = None;
+                }
+                TouchInput::None
+            }
+        }
+    }
+
+    /// 捏合中（两指按下后，直到手指全部抬起）
+    pub fn pinching(&self) -> bool {
+        self.pinch.is_some()
+    }
+
+    // 前两根手指的距离
+    fn spread(&self) -> f64 {
+        match self.fingers[..] {
+            [(_, a), (_, b), ..] => (a.0 - b.0).hypot(a.1 - b.1),
+            _ => 0.0,
+        }
+    }
+}
+
+/// 压力（0..=1）；设备不支持压力时为 None
+pub fn pressure(force: Option<Force>) -> Option<f64> {
+    force.map(|f| f.normalized().clamp(0.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_finger_then_pinch() {
+        let mut t = TouchTracker::default();
+        // 单指由系统生成的鼠标消息处理，这里不产生输入
+        assert_eq!(
+            t.update(1, TouchPhase::Started, (10.0, 10.0)),
+            TouchInput::None
+        );
+        assert_eq!(
+            t.update(1, TouchPhase::Moved, (20.0, 10.0)),
+            TouchInput::None
+        );
+        assert!(!t.pinching());
+        // 第二根手指：进入捏合
+        assert_eq!(
+            t.update(2, TouchPhase::Started, (120.0, 10.0)),
+            TouchInput::PinchStarted
+        );
+        assert_eq!(
+            t.update(2, TouchPhase::Moved, (220.0, 10.0)),
+            TouchInput::Pinch(2.0)
+        );
+        assert_eq!(
+            t.update(1, TouchPhase::Ended, (20.0, 10.0)),
+            TouchInput::None
+        );
+        // 捏合持续到手指全部抬起，剩下的手指不再缩放
+        assert!(t.pinching());
+        assert_eq!(
+            t.update(2, TouchPhase::Moved, (230.0, 10.0)),
+            TouchInput::None
+        );
+        assert_eq!(
+            t.update(2, TouchPhase::Ended, (230.0, 10.0)),
+            TouchInput::None
+        );
+        assert!(!t.pinching());
+        assert_eq!(
+            t.update(3, TouchPhase::Started, (5.0, 5.0)),
+            TouchInput::None
+        );
+        assert_eq!(
+            t.update(3, TouchPhase::Cancelled, (5.0, 5.0)),
+            TouchInput::None
+        );
+        assert_eq!(pressure(Some(Force::Normalized(1.5))), Some(1.0));
+        assert_eq!(pressure(None), None);
+    }
+}
@@ -0,0 +1,189 @@
+// tray_badge: 托盘图标状态角标（定时截图中的红点、热键暂停的暂停标记、上传中的旋转指示）
+//
+// 基础图标先缩小为 ICON_SIZE 见方（通知区域 200% 缩放时也只显示 32 像素），角标按比例画在其上：
+// 右下红点 = 定时截图运行中，左下暂停标记 = 热键已暂停，右上一圈圆点 = 上传 hook 运行中。
+// 上传指示按帧转动，事件循环每 UPLOAD_FRAME 收到一次 UploadTick 时换帧重建图标。
+use std::time::Duration;
+
+/// 托盘图标边长（像素）
+pub const ICON_SIZE: u32 = 64;
+/// 上传动画的帧间隔
+pub const UPLOAD_FRAME: Duration = Duration::from_millis(150);
+/// 上传指示的圆点数（一圈的帧数）
+pub const SPINNER_DOTS: u32 = 8;
+
+const RED: [u8; 4] = [0xE5, 0x39, 0x35, 0xFF];
+const WHITE: [u8; 4] = [0xFF, 0xFF, 0xFF, 0xFF];
+const DARK: [u8; 4] = [0x30, 0x30, 0x30, 0xFF];
+const BLUE: [u8; 3] = [0x3D, 0xA5, 0xF4];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Badges {
+    /// 定时截图运行中（未暂停）
+    pub recording: bool,
+    /// 热键已暂停
+    pub paused: bool,
+    /// 上传中：动画帧序号
+    pub uploading: Option<u32>,
+}
+
+/// 按面积平均把 RGBA 图像缩小为 size 见方（非正方形时拉伸）
+pub fn downscale(rgba: &[u8], w: u32, h: u32, size: u32) -> Vec<u8> {
+    let mut out = vec![0u8; (size * size * 4) as usize];
+    if w == 0 || h == 0 || rgba.len() < (w * h * 4) as usize {
+        return out;
+    }
+    for ty in 0..size {
+        let (y0, y1) = (ty * h / size, ((ty + 1) * h / size).max(ty * h / size + 1));
+        for tx in 0..size {
+            let (x0, x1) = (tx * w / size, ((tx + 1) * w / size).max(tx * w / size + 1));
+            let mut sum = [0u32; 4];
+            for y in y0..y1.min(h) {
+                for x in x0..x1.min(w) {
+                    let i = ((y * w + x) * 4) as usize;
+                    for (c, s) in sum.iter_mut().enumerate() {
+                        *s += rgba[i + c] as u32;
+                    }
+                }
+            }
+            let n = (y1.min(h) - y0) * (x1.min(w) - x0);
+            let o = ((ty * size + tx) * 4) as usize;
+            for c in 0..4 {
+                out[o + c] = (sum[c] / n.max(1)) as u8;
+            }
+        }
+    }
+    out
+}
+
+/// 在 size 见方的基础图标上画角标；没有角标时原样返回
+pub fn compose(base: &[u8], size: u32, badges: Badges) -> Vec<u8> {
+    let mut img = base.to_vec();
+    let s = size as f32 / ICON_SIZE as f32;
+    if badges.recording {
+        disc(&mut img, size, (48.0 * s, 48.0 * s), 14.0 * s, WHITE);
+        disc(&mut img, size, (48.0 * s, 48.0 * s), 11.0 * s, RED);
+    }
+    if badges.paused {
+        disc(&mut img, size, (16.0 * s, 48.0 * s), 14.0 * s, WHITE);
+        disc(&mut img, size, (16.0 * s, 48.0 * s), 12.0 * s, DARK);
+        for bar_x in [11.0, 18.0] {
+            rect(
+                &mut img,
+                size,
+                (bar_x * s, 41.0 * s, 3.5 * s, 14.0 * s),
+                WHITE,
+            );
+        }
+    }
+    if let Some(frame) = badges.uploading {
+        disc(&mut img, size, (48.0 * s, 16.0 * s), 15.0 * s, DARK);
+        let head = frame % SPINNER_DOTS;
+        for i in 0..SPINNER_DOTS {
+            let angle = i as f32 / SPINNER_DOTS as f32 * std::f32::consts::TAU;
+            let center = (48.0 + 10.0 * angle.sin(), 16.0 - 10.0 * angle.cos());
+            // 当前帧的圆点最亮，其后依次变暗形成拖尾
+            let age = (head + SPINNER_DOTS - i) % SPINNER_DOTS;
+            let alpha = 255 - (age * 200 / SPINNER_DOTS) as u8;
+            let [r, g, b] = BLUE;
+            disc(
+                &mut img,
+                size,
+                (center.0 * s, center.1 * s),
+                2.6 * s,
+                [r, g, b, alpha],
+            );
+        }
+    }
+    img
+}
+
+// 按 alpha 叠加一个像素
+fn blend(img: &mut [u8], size: u32, x: u32, y: u32, color: [u8; 4], coverage: f32) {
+    let a = color[3] as f32 / 255.0 * coverage.clamp(0.0, 1.0);
+    if a <= 0.0 {
+        return;
+    }
+    let i = ((y * size + x) * 4) as usize;
+    for c in 0..3 {
+        img[i + c] = (color[c] as f32 * a + img[i + c] as f32 * (1.0 - a)).round() as u8;
+    }
+    img[i + 3] = (255.0 * a + img[i + 3] as f32 * (1.0 - a)).round() as u8;
+}
+
+// 实心圆，边缘 1 像素抗锯齿
+fn disc(img: &mut [u8], size: u32, center: (f32, f32), r: f32, color: [u8; 4]) {
+    let (cx, cy) = center;
+    let y0 = (cy - r - 1.0).max(0.0) as u32;
+    let x0 = (cx - r - 1.0).max(0.0) as u32;
+    for y in y0..((cy + r + 1.0) as u32).min(size) {
+        for x in x0..((cx + r + 1.0) as u32).min(size) {
+            let d = (x as f32 + 0.5 - cx).hypot(y as f32 + 0.5 - cy);
+            blend(img, size, x, y, color, r + 0.5 - d);
+        }
+    }
+}
+
+fn rect(img: &mut [u8], size: u32, r: (f32, f32, f32, f32), color: [u8; 4]) {
+    let (x, y, w, h) = r;
+    for py in (y.round() as u32)..((y + h).round() as u32).min(size) {
+        for px in (x.round() as u32)..((x + w).round() as u32).min(size) {
+            blend(img, size, px, py, color, 1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn px(img: &[u8], x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * ICON_SIZE + x) * 4) as usize;
+        img[i..i + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_compose_badges() {
+        // 2x2 棋盘缩小为 1 像素取平均
+        let src = [
+            0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 0, 0, 0, 255,
+        ];
+        assert_eq!(downscale(&src, 2, 2, 1), [127, 127, 127, 255]);
+        let base = vec![0u8; (ICON_SIZE * ICON_SIZE * 4) as usize];
+        assert_eq!(compose(&base, ICON_SIZE, Badges::default()), base);
+        let rec = compose(
+            &base,
+            ICON_SIZE,
+            Badges {
+                recording: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(px(&rec, 48, 48), RED);
+        assert_eq!(px(&rec, 16, 48), [0; 4]);
+        let paused = compose(
+            &base,
+            ICON_SIZE,
+            Badges {
+                paused: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(px(&paused, 12, 48), WHITE);
+        assert_eq!(px(&paused, 16, 48), DARK);
+        // 上传动画：不同帧的图标不同，转一圈后相同
+        let frame = |f| {
+            compose(
+                &base,
+                ICON_SIZE,
+                Badges {
+                    uploading: Some(f),
+                    ..Default::default()
+                },
+            )
+        };
+        assert_ne!(frame(0), frame(1));
+        assert_eq!(frame(1), frame(1 + SPINNER_DOTS));
+        assert_eq!(px(&frame(0), 48, 16), DARK);
+    }
+}
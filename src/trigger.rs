@@ -0,0 +1,228 @@
+// trigger: 热区 / 中键手势触发截图（feature = "hot_corner"）
+//
+// 后台线程以低频（POLL_MS）轮询光标位置与中键状态，不安装全局鼠标钩子：
+// - 光标在配置的主屏角落停留 dwell_ms 后触发一次，离开角落后才重新生效；
+// - middle_drag 开启时，按住中键拖动超过 drag_distance 像素触发一次，松开后重置。
+use serde::Deserialize;
+
+/// 轮询间隔（毫秒）
+pub const POLL_MS: u64 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TriggerConfig {
+    /// 触发角落；未设置时只使用中键手势
+    pub corner: Option<Corner>,
+    /// 角落热区边长（像素）
+    pub size: u32,
+    /// 停留多久触发（毫秒），避免光标划过角落时误触
+    pub dwell_ms: u64,
+    /// 按住中键拖动触发
+    pub middle_drag: bool,
+    /// 中键拖动触发距离（像素）
+    pub drag_distance: u32,
+}
+
+impl Default for TriggerConfig {
+    fn default() -> Self {
+        Self {
+            corner: None,
+            size: 2,
+            dwell_ms: 250,
+            middle_drag: false,
+            drag_distance: 120,
+        }
+    }
+}
+
+/// 一次轮询采样（坐标为主屏像素坐标）
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub cursor: (i32, i32),
+    pub screen: (u32, u32),
+    pub middle_down: bool,
+    pub now_ms: u64,
+}
+
+/// 触发判定状态机（纯逻辑，便于测试）
+#[derive(Debug, Default)]
+pub struct Detector {
+    entered_at: Option<u64>,
+    corner_fired: bool,
+    drag_start: Option<(i32, i32)>,
+    drag_fired: bool,
+}
+
+impl Detector {
+    /// 输入一次采样；返回 true 表示应开始截图
+    pub fn update(&mut self, config: &TriggerConfig, s: Sample) -> bool {
+        let mut fire = false;
+        match config.corner {
+            Some(corner) if in_corner(corner, config.size, s.cursor, s.screen) => {
+                let since = *self.entered_at.get_or_insert(s.now_ms);
+                if !self.corner_fired && s.now_ms - since >= config.dwell_ms {
+                    self.corner_fired = true;
+                    fire = true;
+                }
+            }
+            _ => {
+                self.entered_at = None;
+                self.corner_fired = false;
+            }
+        }
+        if config.middle_drag && s.middle_down {
+            let (sx, sy) = *self.drag_start.get_or_insert(s.cursor);
+            let (dx, dy) = ((s.cursor.0 - sx) as i64, (s.cursor.1 - sy) as i64);
+            let limit = config.drag_distance as i64;
+            if !self.drag_fired && dx * dx + dy * dy >= limit * limit {
+                self.drag_fired = true;
+                fire = true;
+            }
+        } else {
+            self.drag_start = None;
+            self.drag_fired = false;
+        }
+        fire
+    }
+}
+
+fn in_corner(corner: Corner, size: u32, (x, y): (i32, i32), (w, h): (u32, u32)) -> bool {
+    let size = size.max(1) as i32;
+    let (w, h) = (w as i32, h as i32);
+    let left = x < size;
+    let right = x >= w - size;
+    let top = y < size;
+    let bottom = y >= h - size;
+    match corner {
+        Corner::TopLeft => left && top,
+        Corner::TopRight => right && top,
+        Corner::BottomLeft => left && bottom,
+        Corner::BottomRight => right && bottom,
+    }
+}
+
+/// 启动轮询线程；每次触发调用 on_trigger（例如 EventLoopProxy::send_event）
+#[cfg(target_os = "windows")]
+pub fn spawn(config: TriggerConfig, on_trigger: impl Fn() + Send + 'static) -> anyhow::Result<()> {
+    use std::time::{Duration, Instant};
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetAsyncKeyState, VK_MBUTTON};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetCursorPos, GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN,
+    };
+
+    std::thread::Builder::new()
+        .name("snip-trigger".into())
+        .spawn(move || {
+            let start = Instant::now();
+            let mut detector = Detector::default();
+            loop {
+                std::thread::sleep(Duration::from_millis(POLL_MS));
+                let mut pt = POINT::default();
+                if unsafe { GetCursorPos(&mut pt) }.is_err() {
+                    continue; // 安全桌面 / 锁屏时失败
+                }
+                let (w, h) =
+                    unsafe { (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)) };
+                let middle_down =
+                    unsafe { GetAsyncKeyState(VK_MBUTTON.0 as i32) } as u16 & 0x8000 != 0;
+                let sample = Sample {
+                    cursor: (pt.x, pt.y),
+                    screen: (w.max(0) as u32, h.max(0) as u32),
+                    middle_down,
+                    now_ms: start.elapsed().as_millis() as u64,
+                };
+                if detector.update(&config, sample) {
+                    log::debug!("capture triggered by hot corner / gesture");
+                    on_trigger();
+                }
+            }
+        })?;
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn spawn(
+    _config: TriggerConfig,
+    _on_trigger: impl Fn() + Send + 'static,
+) -> anyhow::Result<()> {
+    Err(anyhow::anyhow!(
+        "hot corner trigger is only available on Windows"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(cursor: (i32, i32), middle_down: bool, now_ms: u64) -> Sample {
+        Sample {
+            cursor,
+            screen: (1920, 1080),
+            middle_down,
+            now_ms,
+        }
+    }
+
+    #[test]
+    fn test_corner_dwell_fires_once() {
+        let config = TriggerConfig {
+            corner: Some(Corner::BottomRight),
+            ..Default::default()
+        };
+        let mut d = Detector::default();
+        assert!(!d.update(&config, sample((1919, 1079), false, 0)));
+        assert!(!d.update(&config, sample((1919, 1079), false, 200)));
+        assert!(d.update(&config, sample((1918, 1078), false, 250)));
+        // 停留期间不重复触发；离开后重新计时
+        assert!(!d.update(&config, sample((1919, 1079), false, 900)));
+        assert!(!d.update(&config, sample((900, 500), false, 950)));
+        assert!(!d.update(&config, sample((1919, 1079), false, 1000)));
+        assert!(d.update(&config, sample((1919, 1079), false, 1300)));
+        // 其它角落不触发
+        let mut d = Detector::default();
+        assert!(!d.update(&config, sample((0, 0), false, 0)));
+        assert!(!d.update(&config, sample((0, 0), false, 1000)));
+    }
+
+    #[test]
+    fn test_middle_drag_gesture() {
+        let config = TriggerConfig {
+            corner: None,
+            middle_drag: true,
+            ..Default::default()
+        };
+        let mut d = Detector::default();
+        assert!(!d.update(&config, sample((500, 500), true, 0)));
+        assert!(!d.update(&config, sample((560, 560), true, 50))); // ~85px
+        assert!(d.update(&config, sample((600, 600), true, 100)));
+        assert!(!d.update(&config, sample((800, 800), true, 150)));
+        assert!(!d.update(&config, sample((800, 800), false, 200)));
+        // 手势关闭时中键拖动无效
+        let config = TriggerConfig {
+            middle_drag: false,
+            ..config
+        };
+        let mut d = Detector::default();
+        assert!(!d.update(&config, sample((0, 500), true, 0)));
+        assert!(!d.update(&config, sample((900, 500), true, 50)));
+    }
+
+    #[test]
+    fn test_config_parse() {
+        let c: TriggerConfig =
+            toml::from_str("corner = \"top-right\"\nmiddle_drag = true").unwrap();
+        assert_eq!(c.corner, Some(Corner::TopRight));
+        assert!(c.middle_drag);
+        assert_eq!(c.dwell_ms, 250);
+    }
+}
@@ -0,0 +1,229 @@
+// uploads: 上传记录（hook 配置 history = true 时记录上传器返回的链接）
+//
+// 写入 <数据目录>/uploads.json（最新在前，最多 MAX_RECORDS 条）与 <数据目录>/uploads/<id>.png 缩略图。
+// hook 线程追加、托盘菜单读取与删除，读改写由 LOCK 串行化。托盘“上传记录”列出最近的记录，
+// 可重新复制链接，或运行记录中的删除命令（不经 shell）删除远程图片；每次追加 / 删除递增
+// GENERATION，托盘只在它变化后重新读取记录与缩略图。
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 保留的记录数（更早的连同缩略图一起删除）
+pub const MAX_RECORDS: usize = 200;
+/// 缩略图最长边（像素）
+pub const THUMBNAIL_SIZE: u32 = 64;
+
+static LOCK: Mutex<()> = Mutex::new(());
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadRecord {
+    /// 上传时间（unix 毫秒），兼作记录 id
+    pub id: u64,
+    /// 上传目标（hook 的 name，未设置时为命令）
+    pub destination: String,
+    pub url: String,
+    /// 上传器输出的删除凭据（例如 Imgur 的 deletehash）
+    #[serde(default)]
+    pub delete: Option<String>,
+    /// 旧版记录的删除命令字符串（不再写入；运行时按参数拆开，同样不经 shell）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delete_command: Option<String>,
+    /// 删除远程图片的程序与参数（{url} / {delete} 已逐个参数代入）
+    #[serde(default)]
+    pub delete_args: Option<Vec<String>>,
+    /// 缩略图文件名（<数据目录>/uploads/ 下）
+    #[serde(default)]
+    pub thumbnail: Option<String>,
+}
+
+impl UploadRecord {
+    /// 删除命令的程序与参数；没有配置删除命令时为 None
+    pub fn delete_argv(&self) -> Option<Vec<String>> {
+        self.delete_args
+            .clone()
+            .or_else(|| self.delete_command.as_deref().map(crate::hooks::split_args))
+    }
+}
+
+/// 当前时间作为新记录的 id
+pub fn new_id() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// 本进程内记录变化（追加 / 删除）的次数
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::Acquire)
+}
+
+/// 上传记录文件路径
+pub fn history_path() -> Option<PathBuf> {
+    crate::paths::data_dir().map(|d| d.join("uploads.json"))
+}
+
+fn thumbnail_dir(history: &Path) -> PathBuf {
+    history.with_file_name("uploads")
+}
+
+/// 读取记录（最新在前）；文件不存在时为空
+pub fn load(history: &Path) -> Result<Vec<UploadRecord>> {
+    match fs::read_to_string(history) {
+        Ok(text) => Ok(serde_json::from_str(&text)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn save(history: &Path, records: &[UploadRecord]) -> Result<()> {
+    if let Some(dir) = history.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(history, serde_json::to_string_pretty(records)?)?;
+    Ok(())
+}
+
+fn remove_thumbnail(history: &Path, record: &UploadRecord) {
+    if let Some(name) = &record.thumbnail {
+        let _ = fs::remove_file(thumbnail_dir(history).join(name));
+    }
+}
+
+/// 追加一条记录（id 与已有记录重复时顺延），超出 MAX_RECORDS 时删除最旧的；返回实际 id
+pub fn append(history: &Path, mut record: UploadRecord) -> Result<u64> {
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut records = load(history)?;
+    while records.iter().any(|r| r.id == record.id) {
+        record.id += 1;
+    }
+    let id = record.id;
+    records.insert(0, record);
+    for old in records.drain(MAX_RECORDS.min(records.len())..) {
+        remove_thumbnail(history, &old);
+    }
+    save(history, &records)?;
+    GENERATION.fetch_add(1, Ordering::AcqRel);
+    Ok(id)
+}
+
+/// 删除一条记录及其缩略图，返回被删除的记录
+pub fn remove(history: &Path, id: u64) -> Result<Option<UploadRecord>> {
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut records = load(history)?;
+    let Some(i) = records.iter().position(|r| r.id == id) else {
+        return Ok(None);
+    };
+    let record = records.remove(i);
+    save(history, &records)?;
+    GENERATION.fetch_add(1, Ordering::AcqRel);
+    remove_thumbnail(history, &record);
+    Ok(Some(record))
+}
+
+/// 按 id 查找记录
+pub fn find(history: &Path, id: u64) -> Option<UploadRecord> {
+    load(history).ok()?.into_iter().find(|r| r.id == id)
+}
+
+/// 把 PNG 缩小为缩略图写入 uploads/<id>.png，返回文件名
+pub fn write_thumbnail(history: &Path, id: u64, png: &[u8]) -> Result<String> {
+    let image = image::load_from_memory(png).map_err(|e| anyhow!("decode png: {e}"))?;
+    let thumb = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgba8();
+    let dir = thumbnail_dir(history);
+    fs::create_dir_all(&dir)?;
+    let name = format!("{id}.png");
+    let png = crate::capture::encode_png(thumb.as_raw(), thumb.width(), thumb.height())?;
+    fs::write(dir.join(&name), png)?;
+    Ok(name)
+}
+
+/// 缩略图居中缩放到 size×size（菜单图标），返回 RGBA；没有缩略图或读取失败时为 None
+pub fn thumbnail_icon(history: &Path, record: &UploadRecord, size: u32) -> Option<Vec<u8>> {
+    let path = thumbnail_dir(history).join(record.thumbnail.as_ref()?);
+    let thumb = image::open(path).ok()?.thumbnail(size, size).to_rgba8();
+    let mut icon = image::RgbaImage::new(size, size);
+    let x = (size - thumb.width()) / 2;
+    let y = (size - thumb.height()) / 2;
+    image::imageops::overlay(&mut icon, &thumb, x as i64, y as i64);
+    Some(icon.into_raw())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn record(id: u64, url: &str) -> UploadRecord {
+        UploadRecord {
+            id,
+            destination: "imgur".into(),
+            url: url.into(),
+            delete: None,
+            delete_command: None,
+            delete_args: None,
+            thumbnail: None,
+        }
+    }
+
+    #[test]
+    fn test_legacy_delete_command() {
+        let json =
+            r#"[{"id":1,"destination":"x","url":"https://a","delete_command":"rm \"a b\""}]"#;
+        let records: Vec<UploadRecord> = serde_json::from_str(json).unwrap();
+        assert_eq!(records[0].delete_argv().unwrap(), ["rm", "a b"]);
+        let mut r = record(2, "https://b");
+        assert_eq!(r.delete_argv(), None);
+        r.delete_args = Some(vec!["rm".into(), "https://b?x=1&y=2".into()]);
+        let text = serde_json::to_string(&r).unwrap();
+        assert!(!text.contains("delete_command"));
+        assert_eq!(serde_json::from_str::<UploadRecord>(&text).unwrap(), r);
+    }
+
+    #[test]
+    fn test_append_remove_and_trim() {
+        let dir = TempDir::new("uploads_test");
+        let history = dir.join("uploads.json");
+        assert!(load(&history).unwrap().is_empty());
+        let before = generation();
+        assert_eq!(append(&history, record(5, "https://a")).unwrap(), 5);
+        assert!(generation() > before);
+        // 同一毫秒内的第二次上传
+        assert_eq!(append(&history, record(5, "https://b")).unwrap(), 6);
+        let loaded = load(&history).unwrap();
+        assert_eq!(loaded[0].url, "https://b");
+        assert_eq!(find(&history, 5).unwrap().url, "https://a");
+        let before = generation();
+        assert_eq!(remove(&history, 5).unwrap().unwrap().url, "https://a");
+        assert!(generation() > before);
+        assert_eq!(remove(&history, 5).unwrap(), None);
+        for i in 0..MAX_RECORDS as u64 + 3 {
+            append(&history, record(100 + i, "https://c")).unwrap();
+        }
+        let loaded = load(&history).unwrap();
+        assert_eq!(loaded.len(), MAX_RECORDS);
+        assert_eq!(loaded[0].id, 100 + MAX_RECORDS as u64 + 2);
+    }
+
+    #[test]
+    fn test_thumbnail_round_trip() {
+        let dir = TempDir::new("uploads_thumb");
+        let history = dir.join("uploads.json");
+        let png = crate::capture::encode_png(&[200; 256 * 128 * 4], 256, 128).unwrap();
+        let mut r = record(1, "https://a");
+        r.thumbnail = Some(write_thumbnail(&history, 1, &png).unwrap());
+        let icon = thumbnail_icon(&history, &r, 16).unwrap();
+        assert_eq!(icon.len(), 16 * 16 * 4);
+        // 宽图上下留透明边
+        assert_eq!(icon[3], 0);
+        assert_eq!(icon[(8 * 16 + 8) * 4 + 3], 200);
+        append(&history, r).unwrap();
+        remove(&history, 1).unwrap();
+        assert!(!dir.join("uploads").join("1.png").exists());
+    }
+}
@@ -0,0 +1,561 @@
+// webdav: 内置 WebDAV 上传目标（[[webdav]]）——选区 PNG 以 HTTP PUT 写入配置的目录
+//
+// 工具栏 / “更多”菜单中的“上传”把选区编码后 PUT 到 <url>/<文件名>.png（WinHTTP，使用系统代理）；
+// 配置了多个目标时先弹出目标选择菜单。用户名与密码作为通用凭据 snip_rust:webdav:<name> 保存在
+// Windows 凭据管理器中：没有保存的凭据时先匿名上传，服务器返回 401 时弹出系统凭据对话框，
+// 上传成功后才写入凭据管理器。Basic 认证的账号是明文，只随 https 发送；http 目标需要显式
+// allow_insecure = true。服务器以 409 回应（上级目录不存在）时逐级 MKCOL 创建目录后重试一次。
+// 成功后按 copy_url 复制链接，history = true 时写入上传记录（与上传 hook 相同）。上传在独立线程
+// 进行并计入 hooks::uploads_in_progress（托盘动画）。
+// FTP / SFTP 没有内置目标，仍通过 [[post_capture]] hook 调用 curl / scp。
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::thread;
+
+use crate::hooks::{UploadGuard, UrlFormat};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct WebDavDestination {
+    /// 目标名称（选择菜单 / 上传记录 / 凭据名）
+    pub name: String,
+    /// 上传目录，例如 `https://dav.example.com/shots/`
+    pub url: String,
+    /// 凭据对话框预填的用户名；密码只保存在凭据管理器中
+    #[serde(default)]
+    pub username: Option<String>,
+    /// 复制 / 记录的链接前缀（例如对外的静态站点），默认与 url 相同
+    #[serde(default)]
+    pub public_url: Option<String>,
+    /// 上传成功后按格式复制链接
+    #[serde(default)]
+    pub copy_url: Option<UrlFormat>,
+    /// 把链接写入上传记录
+    #[serde(default)]
+    pub history: bool,
+    /// 允许通过 http:// 明文发送账号（默认只随 https 发送）
+    #[serde(default)]
+    pub allow_insecure: bool,
+}
+
+/// 凭据管理器中的用户名与密码
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credential {
+    pub username: String,
+    pub password: String,
+}
+
+/// 解析后的 http(s) 地址
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpUrl {
+    pub secure: bool,
+    pub host: String,
+    pub port: u16,
+    /// 以 / 开头的路径（含查询串）
+    pub path: String,
+}
+
+/// 拆分 http(s) URL；不接受 URL 中的用户名 / 密码（应保存在凭据管理器中）
+pub fn parse_url(url: &str) -> Result<HttpUrl> {
+    let (secure, rest) = if let Some(rest) = url.strip_prefix("https://") {
+        (true, rest)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        (false, rest)
+    } else {
+        return Err(anyhow!(
+            "webdav url must start with http:// or https://: {url}"
+        ));
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    if authority.contains('@') {
+        return Err(anyhow!(
+            "webdav url must not contain credentials (they are kept in the credential manager)"
+        ));
+    }
+    let default_port = if secure { 443 } else { 80 };
+    let (host, port) = match authority.rsplit_once(':') {
+        // IPv6 字面量 [::1] 本身含冒号，只有 ] 之后的冒号才是端口
+        Some((host, port)) if !port.contains(']') => (
+            host,
+            port.parse()
+                .map_err(|_| anyhow!("invalid port in webdav url: {url}"))?,
+        ),
+        _ => (authority, default_port),
+    };
+    if host.is_empty() {
+        return Err(anyhow!("webdav url has no host: {url}"));
+    }
+    Ok(HttpUrl {
+        secure,
+        host: host.to_string(),
+        port,
+        path: path.to_string(),
+    })
+}
+
+/// 百分号编码路径中的一段（只保留 RFC 3986 非保留字符）
+pub fn encode_segment(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for b in segment.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+/// 目录地址 + 文件名（编码后）
+pub fn file_url(base: &str, file_name: &str) -> String {
+    format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        encode_segment(file_name)
+    )
+}
+
+/// 凭据管理器中的通用凭据名
+pub fn credential_target(name: &str) -> String {
+    format!("snip_rust:webdav:{name}")
+}
+
+/// 账号能否发往该地址：https，或目标显式允许明文
+pub fn credentials_allowed(dest: &WebDavDestination, url: &HttpUrl) -> bool {
+    url.secure || dest.allow_insecure
+}
+
+/// 文件路径的各级上级目录（不含根），供 MKCOL 从外到内逐级创建
+pub fn collection_paths(path: &str) -> Vec<String> {
+    let path = path.split('?').next().unwrap_or_default();
+    let Some((dir, _file)) = path.rsplit_once('/') else {
+        return Vec::new();
+    };
+    let mut out = Vec::new();
+    let mut prefix = String::new();
+    for segment in dir.split('/').filter(|s| !s.is_empty()) {
+        prefix.push('/');
+        prefix.push_str(segment);
+        out.push(format!("{prefix}/"));
+    }
+    out
+}
+
+/// HTTP Basic 认证头的值
+pub fn basic_auth(credential: &Credential) -> String {
+    let pair = format!("{}:{}", credential.username, credential.password);
+    format!("Basic {}", crate::encode::base64_encode(pair.as_bytes()))
+}
+
+/// 上传 png 为 <name>.png，返回对外链接；阻塞到完成（可能弹出凭据对话框）
+pub fn upload(dest: &WebDavDestination, name: &str, png: &[u8]) -> Result<String> {
+    let file = format!("{name}.png");
+    let target = file_url(&dest.url, &file);
+    let url = parse_url(&target)?;
+    let cred_name = credential_target(&dest.name);
+    let secure = credentials_allowed(dest, &url);
+    let mut credential = read_credential(&cred_name).filter(|_| secure);
+    let mut prompted = false;
+    let mut created = false;
+    loop {
+        let status = send(&url, "PUT", credential.as_ref(), png)?;
+        match status {
+            200..=299 => break,
+            409 if !created => {
+                make_collections(&url, credential.as_ref())?;
+                created = true;
+            }
+            401 | 403 if !secure => {
+                return Err(anyhow!(
+                    "webdav {target} needs a login; refusing to send it over http \
+                     (use https or set allow_insecure = true)"
+                ))
+            }
+            401 | 403 if !prompted => {
+                let default_user = credential
+                    .as_ref()
+                    .map(|c| c.username.clone())
+                    .or_else(|| dest.username.clone())
+                    .unwrap_or_default();
+                let failed = credential.is_some();
+                credential = Some(
+                    prompt_credential(&cred_name, &dest.name, &default_user, failed)?
+                        .ok_or_else(|| anyhow!("webdav login canceled"))?,
+                );
+                prompted = true;
+            }
+            status => return Err(anyhow!("webdav put {target}: http {status}")),
+        }
+    }
+    if let (true, Some(credential)) = (prompted, &credential) {
+        if let Err(e) = write_credential(&cred_name, credential) {
+            log::warn!("save webdav credential: {e}");
+        }
+    }
+    Ok(file_url(
+        dest.public_url.as_deref().unwrap_or(&dest.url),
+        &file,
+    ))
+}
+
+// 逐级 MKCOL 创建上级目录；已存在（405）的跳过
+fn make_collections(url: &HttpUrl, credential: Option<&Credential>) -> Result<()> {
+    for path in collection_paths(&url.path) {
+        let dir = HttpUrl {
+            path,
+            ..url.clone()
+        };
+        match send(&dir, "MKCOL", credential, &[])? {
+            200..=299 | 405 => {}
+            status => return Err(anyhow!("webdav mkcol {}: http {status}", dir.path)),
+        }
+    }
+    Ok(())
+}
+
+/// 在独立线程上传，成功后按目标配置复制链接 / 写入上传记录
+pub fn spawn_upload(dest: WebDavDestination, name: String, png: Vec<u8>) {
+    let guard = UploadGuard::begin();
+    thread::spawn(move || {
+        let _guard = guard;
+        let link = match upload(&dest, &name, &png) {
+            Ok(link) => link,
+            Err(e) => {
+                log::warn!("webdav upload to {}: {e}", dest.name);
+                return;
+            }
+        };
+        log::info!("uploaded {name}.png to {}", dest.name);
+        if let Some(format) = dest.copy_url {
+            if let Err(e) = crate::clipboard::copy_text(&crate::hooks::format_url(format, &link)) {
+                log::warn!("copy url failed: {e}");
+            }
+        }
+        if dest.history {
+            crate::hooks::save_upload_record(dest.name.clone(), &link, None, None, &png);
+        }
+    });
+}
+
+#[cfg(target_os = "windows")]
+fn wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+// 发送请求（PUT 带图片 / MKCOL 无正文），返回 HTTP 状态码
+#[cfg(target_os = "windows")]
+fn send(url: &HttpUrl, method: &str, credential: Option<&Credential>, body: &[u8]) -> Result<u32> {
+    use std::ffi::c_void;
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Networking::WinHttp::{
+        WinHttpCloseHandle, WinHttpConnect, WinHttpOpen, WinHttpOpenRequest, WinHttpQueryHeaders,
+        WinHttpReceiveResponse, WinHttpSendRequest, WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY,
+        WINHTTP_FLAG_SECURE, WINHTTP_OPEN_REQUEST_FLAGS, WINHTTP_QUERY_FLAG_NUMBER,
+        WINHTTP_QUERY_STATUS_CODE,
+    };
+
+    // 会话 / 连接 / 请求句柄，逆序关闭
+    struct Handle(*mut c_void);
+    impl Drop for Handle {
+        fn drop(&mut self) {
+            unsafe {
+                let _ = WinHttpCloseHandle(self.0);
+            }
+        }
+    }
+    let check = |h: *mut c_void, what: &str| {
+        if h.is_null() {
+            Err(anyhow!("{what}: {}", windows::core::Error::from_thread()))
+        } else {
+            Ok(Handle(h))
+        }
+    };
+
+    let host = wide(&url.host);
+    let path = wide(&url.path);
+    let verb = wide(method);
+    let mut headers = String::new();
+    if !body.is_empty() {
+        headers.push_str("Content-Type: image/png\r\n");
+    }
+    if let Some(credential) = credential {
+        headers.push_str(&format!("Authorization: {}\r\n", basic_auth(credential)));
+    }
+    let headers: Vec<u16> = headers.encode_utf16().collect();
+    let flags = if url.secure {
+        WINHTTP_FLAG_SECURE
+    } else {
+        WINHTTP_OPEN_REQUEST_FLAGS(0)
+    };
+    let len = u32::try_from(body.len()).map_err(|_| anyhow!("image too large to upload"))?;
+    unsafe {
+        let session = check(
+            WinHttpOpen(
+                w!("snip_rust"),
+                WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY,
+                PCWSTR::null(),
+                PCWSTR::null(),
+                0,
+            ),
+            "winhttp open",
+        )?;
+        let connect = check(
+            WinHttpConnect(session.0, PCWSTR(host.as_ptr()), url.port, 0),
+            "winhttp connect",
+        )?;
+        let request = check(
+            WinHttpOpenRequest(
+                connect.0,
+                PCWSTR(verb.as_ptr()),
+                PCWSTR(path.as_ptr()),
+                PCWSTR::null(),
+                PCWSTR::null(),
+                std::ptr::null(),
+                flags,
+            ),
+            "winhttp request",
+        )?;
+        WinHttpSendRequest(
+            request.0,
+            Some(&headers),
+            Some(body.as_ptr() as *const c_void),
+            len,
+            len,
+            0,
+        )
+        .map_err(|e| anyhow!("send to {}: {e}", url.host))?;
+        WinHttpReceiveResponse(request.0, std::ptr::null_mut())
+            .map_err(|e| anyhow!("response from {}: {e}", url.host))?;
+        let mut status = 0u32;
+        let mut size = std::mem::size_of::<u32>() as u32;
+        WinHttpQueryHeaders(
+            request.0,
+            WINHTTP_QUERY_STATUS_CODE | WINHTTP_QUERY_FLAG_NUMBER,
+            PCWSTR::null(),
+            Some(&mut status as *mut u32 as *mut c_void),
+            &mut size,
+            std::ptr::null_mut(),
+        )
+        .map_err(|e| anyhow!("read http status: {e}"))?;
+        drop((request, connect, session));
+        Ok(status)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn send(
+    _url: &HttpUrl,
+    _method: &str,
+    _credential: Option<&Credential>,
+    _body: &[u8],
+) -> Result<u32> {
+    Err(anyhow!("webdav upload is only available on Windows"))
+}
+
+/// 读取凭据管理器中的通用凭据；不存在时为 None
+#[cfg(target_os = "windows")]
+pub fn read_credential(target: &str) -> Option<Credential> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Security::Credentials::{
+        CredFree, CredReadW, CREDENTIALW, CRED_TYPE_GENERIC,
+    };
+
+    let target = wide(target);
+    let mut cred: *mut CREDENTIALW = std::ptr::null_mut();
+    unsafe {
+        CredReadW(PCWSTR(target.as_ptr()), CRED_TYPE_GENERIC, None, &mut cred).ok()?;
+        let c = &*cred;
+        let username = c.UserName.to_string().unwrap_or_default();
+        // 密码按 UTF-16 保存（与 CredWriteW 写入的一致）
+        let blob = std::slice::from_raw_parts(
+            c.CredentialBlob as *const u16,
+            c.CredentialBlobSize as usize / 2,
+        );
+        let password = String::from_utf16_lossy(blob);
+        CredFree(cred as *const _);
+        Some(Credential { username, password })
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn read_credential(_target: &str) -> Option<Credential> {
+    None
+}
+
+/// 写入（覆盖）凭据管理器中的通用凭据，保存在本机
+#[cfg(target_os = "windows")]
+pub fn write_credential(target: &str, credential: &Credential) -> Result<()> {
+    use windows::core::PWSTR;
+    use windows::Win32::Security::Credentials::{
+        CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE, CRED_TYPE_GENERIC,
+    };
+
+    let mut target = wide(target);
+    let mut username = wide(&credential.username);
+    let mut blob: Vec<u16> = credential.password.encode_utf16().collect();
+    let cred = CREDENTIALW {
+        Type: CRED_TYPE_GENERIC,
+        TargetName: PWSTR(target.as_mut_ptr()),
+        CredentialBlobSize: (blob.len() * 2) as u32,
+        CredentialBlob: blob.as_mut_ptr() as *mut u8,
+        Persist: CRED_PERSIST_LOCAL_MACHINE,
+        UserName: PWSTR(username.as_mut_ptr()),
+        ..Default::default()
+    };
+    unsafe { CredWriteW(&cred, 0) }.map_err(|e| anyhow!("CredWriteW: {e}"))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn write_credential(_target: &str, _credential: &Credential) -> Result<()> {
+    Err(anyhow!("credential manager is only available on Windows"))
+}
+
+// 系统凭据对话框（不由对话框自行保存，上传成功后由 write_credential 写入）；取消时为 None
+#[cfg(target_os = "windows")]
+fn prompt_credential(
+    target: &str,
+    name: &str,
+    username: &str,
+    failed: bool,
+) -> Result<Option<Credential>> {
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::{ERROR_CANCELLED, ERROR_LOGON_FAILURE, NO_ERROR};
+    use windows::Win32::Security::Credentials::{
+        CredUIPromptForCredentialsW, CREDUI_FLAGS_ALWAYS_SHOW_UI, CREDUI_FLAGS_DO_NOT_PERSIST,
+        CREDUI_FLAGS_GENERIC_CREDENTIALS, CREDUI_INFOW, CREDUI_MAX_USERNAME_LENGTH,
+        CRED_MAX_CREDENTIAL_BLOB_SIZE,
+    };
+
+    let target = wide(target);
+    let message = wide(&format!("登录 WebDAV 上传目标“{name}”"));
+    let info = CREDUI_INFOW {
+        cbSize: std::mem::size_of::<CREDUI_INFOW>() as u32,
+        pszMessageText: PCWSTR(message.as_ptr()),
+        pszCaptionText: w!("Snip Rust"),
+        ..Default::default()
+    };
+    let mut user = vec![0u16; CREDUI_MAX_USERNAME_LENGTH as usize + 1];
+    for (dst, src) in user.iter_mut().zip(username.encode_utf16()) {
+        *dst = src;
+    }
+    let mut password = vec![0u16; CRED_MAX_CREDENTIAL_BLOB_SIZE as usize / 2 + 1];
+    let error = if failed { ERROR_LOGON_FAILURE.0 } else { 0 };
+    let flags = CREDUI_FLAGS_GENERIC_CREDENTIALS
+        | CREDUI_FLAGS_ALWAYS_SHOW_UI
+        | CREDUI_FLAGS_DO_NOT_PERSIST;
+    let result = unsafe {
+        CredUIPromptForCredentialsW(
+            Some(&info),
+            PCWSTR(target.as_ptr()),
+            None,
+            error,
+            &mut user,
+            &mut password,
+            None,
+            flags,
+        )
+    };
+    let text = |buf: &[u16]| {
+        let end = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+        String::from_utf16_lossy(&buf[..end])
+    };
+    let credential = Credential {
+        username: text(&user),
+        password: text(&password),
+    };
+    password.fill(0);
+    match result {
+        NO_ERROR => Ok(Some(credential)),
+        ERROR_CANCELLED => Ok(None),
+        e => Err(anyhow!("credential prompt failed: {}", e.0)),
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn prompt_credential(
+    _target: &str,
+    _name: &str,
+    _username: &str,
+    _failed: bool,
+) -> Result<Option<Credential>> {
+    Err(anyhow!("credential prompt is only available on Windows"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url() {
+        assert_eq!(
+            parse_url("https://dav.example.com/shots/a%20b.png").unwrap(),
+            HttpUrl {
+                secure: true,
+                host: "dav.example.com".into(),
+                port: 443,
+                path: "/shots/a%20b.png".into(),
+            }
+        );
+        let local = parse_url("http://[::1]:8080").unwrap();
+        assert_eq!((local.host.as_str(), local.port), ("[::1]", 8080));
+        assert_eq!(local.path, "/");
+        assert_eq!(parse_url("http://[::1]/x").unwrap().port, 80);
+        assert!(parse_url("ftp://example.com/").is_err());
+        assert!(parse_url("https://me:pw@example.com/").is_err());
+        assert!(parse_url("https://example.com:abc/").is_err());
+        assert!(parse_url("https:///x").is_err());
+    }
+
+    #[test]
+    fn test_file_url_and_auth() {
+        assert_eq!(
+            file_url("https://dav.example.com/shots/", "截图 1.png"),
+            "https://dav.example.com/shots/%E6%88%AA%E5%9B%BE%201.png"
+        );
+        assert_eq!(
+            file_url("https://dav.example.com/shots", "a&b#c.png"),
+            "https://dav.example.com/shots/a%26b%23c.png"
+        );
+        assert_eq!(credential_target("nas"), "snip_rust:webdav:nas");
+        let credential = Credential {
+            username: "Aladdin".into(),
+            password: "open sesame".into(),
+        };
+        assert_eq!(
+            basic_auth(&credential),
+            "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+        );
+    }
+
+    #[test]
+    fn test_destination_config() {
+        let dest: WebDavDestination = toml::from_str(
+            "name = \"nas\"\nurl = \"https://dav.example.com/shots/\"\ncopy_url = \"markdown\"",
+        )
+        .unwrap();
+        assert_eq!(dest.copy_url, Some(UrlFormat::Markdown));
+        assert_eq!(dest.username, None);
+        assert!(!dest.history);
+        assert!(credentials_allowed(&dest, &parse_url(&dest.url).unwrap()));
+        // http 目标默认不发送账号
+        let plain = parse_url("http://nas.local/shots/").unwrap();
+        assert!(!credentials_allowed(&dest, &plain));
+        let dest = WebDavDestination {
+            allow_insecure: true,
+            ..dest
+        };
+        assert!(credentials_allowed(&dest, &plain));
+    }
+
+    #[test]
+    fn test_collection_paths() {
+        assert_eq!(
+            collection_paths("/dav/shots/2024/a.png"),
+            ["/dav/", "/dav/shots/", "/dav/shots/2024/"]
+        );
+        assert_eq!(collection_paths("/a.png?x=/y"), Vec::<String>::new());
+        assert!(collection_paths("/").is_empty());
+    }
+}
@@ -0,0 +1,39 @@
+// window_surface: 窗口 + softbuffer surface 的所有权（替代 Box::leak / 裸指针）
+//
+// softbuffer 的 Context / Surface 需要持有窗口句柄；用 Rc<Window> 共享所有权，
+// 持有者（OverlayState / PasteWindow）被 drop 时窗口随最后一个 Rc 一起释放。
+
+use anyhow::{anyhow, Result};
+use softbuffer::{Context, Surface};
+use std::rc::Rc;
+use winit::{
+    event_loop::ActiveEventLoop,
+    window::{Window, WindowAttributes},
+};
+
+pub type SharedWindow = Rc<Window>;
+
+pub struct SurfaceWindow {
+    pub window: SharedWindow,
+    pub surface: Surface<SharedWindow, SharedWindow>,
+    pub context: Context<SharedWindow>,
+}
+
+impl SurfaceWindow {
+    /// 创建窗口并绑定 surface；label 用于错误信息（如 "overlay" / "paste"）
+    pub fn create(active: &ActiveEventLoop, attrs: WindowAttributes, label: &str) -> Result<Self> {
+        let window = Rc::new(active.create_window(attrs)?);
+
+        // 禁用窗口淡入淡出动画，提升显隐响应（Windows）
+        crate::windows_util::disable_window_transitions(&window);
+
+        let context = Context::new(window.clone()).map_err(|e| anyhow!("{label} ctx: {e}"))?;
+        let surface =
+            Surface::new(&context, window.clone()).map_err(|e| anyhow!("{label} surface: {e}"))?;
+        Ok(Self {
+            window,
+            surface,
+            context,
+        })
+    }
+}
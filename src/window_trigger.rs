@@ -0,0 +1,298 @@
+// window_trigger: 前台窗口变化时自动截图（记录安装向导等多步操作）
+//
+// WinEvent 钩子（EVENT_SYSTEM_FOREGROUND，可选前台窗口的 EVENT_OBJECT_NAMECHANGE）在独立线程的
+// 消息循环中派发，经 subscribe 分发给各订阅者（本模块、fullscreen、elevation 共用同一个钩子）；
+// 回调只把 HWND 交给 worker 线程。worker 等待 delay_ms 让窗口完成绘制并合并
+// 期间的后续事件，再按标题规则过滤、去重（同一窗口同一标题只截一次），最后调用 on_change。
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::config::CaptureAction;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WindowTriggerConfig {
+    /// 只截取标题匹配该正则的窗口；未设置时任何前台窗口都截取
+    pub title_pattern: Option<String>,
+    /// 前台窗口标题变化（同一窗口切换向导页）时也截图
+    pub title_changes: bool,
+    /// 事件后等待多久再截图（毫秒），期间的后续变化合并为一次
+    pub delay_ms: u64,
+    /// 截图后的动作：save（默认）/ pin / copy
+    pub action: CaptureAction,
+}
+
+impl Default for WindowTriggerConfig {
+    fn default() -> Self {
+        Self {
+            title_pattern: None,
+            title_changes: true,
+            delay_ms: 400,
+            action: CaptureAction::Save,
+        }
+    }
+}
+
+/// 标题过滤 + 去重（纯逻辑，便于测试）
+#[derive(Debug)]
+pub struct Filter {
+    pattern: Option<Regex>,
+    title_changes: bool,
+    last: Option<(isize, String)>,
+}
+
+impl Filter {
+    /// title_pattern 不是合法正则时返回错误
+    pub fn new(config: &WindowTriggerConfig) -> Result<Self> {
+        let pattern = match &config.title_pattern {
+            Some(p) => Some(Regex::new(p).map_err(|e| anyhow!("invalid title_pattern: {e}"))?),
+            None => None,
+        };
+        Ok(Self {
+            pattern,
+            title_changes: config.title_changes,
+            last: None,
+        })
+    }
+
+    /// 输入一次合并后的前台窗口；返回 true 表示应截图
+    pub fn accept(&mut self, handle: isize, title: &str) -> bool {
+        if title.is_empty() || self.pattern.as_ref().is_some_and(|p| !p.is_match(title)) {
+            return false;
+        }
+        // 不关心标题变化时只按窗口去重
+        let key = (
+            handle,
+            if self.title_changes { title } else { "" }.to_string(),
+        );
+        if self.last.as_ref() == Some(&key) {
+            return false;
+        }
+        self.last = Some(key);
+        true
+    }
+}
+
+/// 共用 WinEvent 钩子派发的前台窗口事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForegroundEvent {
+    /// 前台窗口切换（EVENT_SYSTEM_FOREGROUND）
+    Activated(isize),
+    /// 前台窗口自身的标题变化；只有订阅时要求 title_changes 后才安装这个钩子
+    TitleChanged(isize),
+}
+
+/// 订阅前台窗口事件。进程内只有一个 snip-winevent-hook 线程：首次订阅时安装 EVENT_SYSTEM_FOREGROUND
+/// 钩子，之后的订阅（窗口触发器、全屏检测、管理员窗口检测）共用；第一个 title_changes = true 的订阅
+/// 再让该线程装上 EVENT_OBJECT_NAMECHANGE。listener 在钩子线程上调用，只应把事件转交给自己的线程，
+/// 不能在其中再次订阅。
+#[cfg(target_os = "windows")]
+pub fn subscribe(
+    title_changes: bool,
+    listener: impl Fn(ForegroundEvent) + Send + 'static,
+) -> Result<()> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Mutex};
+    use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+    use windows::Win32::System::Threading::GetCurrentThreadId;
+    use windows::Win32::UI::Accessibility::{SetWinEventHook, HWINEVENTHOOK};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        DispatchMessageW, GetForegroundWindow, GetMessageW, PeekMessageW, PostThreadMessageW,
+        TranslateMessage, CHILDID_SELF, EVENT_OBJECT_NAMECHANGE, EVENT_SYSTEM_FOREGROUND, MSG,
+        OBJID_WINDOW, PM_NOREMOVE, WINEVENT_OUTOFCONTEXT, WINEVENT_SKIPOWNPROCESS, WM_APP,
+    };
+
+    type Listener = Box<dyn Fn(ForegroundEvent) + Send>;
+    static LISTENERS: Mutex<Vec<Listener>> = Mutex::new(Vec::new());
+    // 钩子线程 id（已启动时）
+    static HOOK_THREAD: Mutex<Option<u32>> = Mutex::new(None);
+    static TITLE_HOOK: AtomicBool = AtomicBool::new(false);
+    // 发给钩子线程：安装标题变化钩子
+    const WM_WATCH_TITLES: u32 = WM_APP + 1;
+
+    unsafe extern "system" fn on_event(
+        _hook: HWINEVENTHOOK,
+        event: u32,
+        hwnd: HWND,
+        id_object: i32,
+        id_child: i32,
+        _thread: u32,
+        _time: u32,
+    ) {
+        let event = if event == EVENT_OBJECT_NAMECHANGE {
+            // 标题变化事件来自所有控件，只保留前台顶层窗口自身的
+            if id_object != OBJID_WINDOW.0
+                || id_child != CHILDID_SELF as i32
+                || hwnd != GetForegroundWindow()
+            {
+                return;
+            }
+            ForegroundEvent::TitleChanged(hwnd.0 as isize)
+        } else {
+            ForegroundEvent::Activated(hwnd.0 as isize)
+        };
+        let listeners = LISTENERS.lock().unwrap_or_else(|e| e.into_inner());
+        for listener in listeners.iter() {
+            listener(event);
+        }
+    }
+
+    LISTENERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Box::new(listener));
+    let mut thread = HOOK_THREAD.lock().unwrap_or_else(|e| e.into_inner());
+    let watch_titles = title_changes && !TITLE_HOOK.swap(true, Ordering::SeqCst);
+    if let Some(id) = *thread {
+        if watch_titles {
+            unsafe { PostThreadMessageW(id, WM_WATCH_TITLES, WPARAM(0), LPARAM(0)) }
+                .map_err(|e| anyhow!("watch title changes: {e}"))?;
+        }
+        return Ok(());
+    }
+    let (ready_tx, ready_rx) = mpsc::channel();
+    std::thread::Builder::new()
+        .name("snip-winevent-hook".into())
+        .spawn(move || unsafe {
+            let flags = WINEVENT_OUTOFCONTEXT | WINEVENT_SKIPOWNPROCESS;
+            let hook = |event| SetWinEventHook(event, event, None, Some(on_event), 0, 0, flags);
+            let hook_titles = || {
+                if hook(EVENT_OBJECT_NAMECHANGE).is_invalid() {
+                    log::warn!("title change hook unavailable; only foreground changes trigger");
+                }
+            };
+            // 先建立消息队列，之后发来的 WM_WATCH_TITLES 不会丢
+            let mut msg = MSG::default();
+            let _ = PeekMessageW(&mut msg, None, 0, 0, PM_NOREMOVE);
+            let installed = !hook(EVENT_SYSTEM_FOREGROUND).is_invalid();
+            if installed && watch_titles {
+                hook_titles();
+            }
+            let _ = ready_tx.send(installed.then(|| GetCurrentThreadId()));
+            if !installed {
+                return;
+            }
+            // 钩子回调在本线程的消息循环中派发，线程随进程存活
+            while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+                if msg.message == WM_WATCH_TITLES {
+                    hook_titles();
+                    continue;
+                }
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        })?;
+    match ready_rx.recv() {
+        Ok(Some(id)) => {
+            *thread = Some(id);
+            Ok(())
+        }
+        _ => {
+            // 下一次订阅重试安装
+            TITLE_HOOK.store(false, Ordering::SeqCst);
+            LISTENERS.lock().unwrap_or_else(|e| e.into_inner()).pop();
+            Err(anyhow!("SetWinEventHook failed"))
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn subscribe(
+    _title_changes: bool,
+    _listener: impl Fn(ForegroundEvent) + Send + 'static,
+) -> Result<()> {
+    Err(anyhow!(
+        "foreground window events are only available on Windows"
+    ))
+}
+
+/// 订阅前台窗口事件并启动 worker 线程；每次需要截图时以窗口句柄调用 on_change。进程内只能启动一次
+pub fn spawn(
+    config: WindowTriggerConfig,
+    on_change: impl Fn(isize) + Send + 'static,
+) -> Result<()> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    static STARTED: AtomicBool = AtomicBool::new(false);
+
+    let mut filter = Filter::new(&config)?;
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return Err(anyhow!("window trigger already installed"));
+    }
+    let (tx, rx) = mpsc::channel();
+    subscribe(config.title_changes, move |event| {
+        let (ForegroundEvent::Activated(handle) | ForegroundEvent::TitleChanged(handle)) = event;
+        let _ = tx.send(handle);
+    })?;
+    let delay = Duration::from_millis(config.delay_ms);
+    std::thread::Builder::new()
+        .name("snip-window-trigger".into())
+        .spawn(move || {
+            while let Ok(mut handle) = rx.recv() {
+                std::thread::sleep(delay);
+                while let Ok(next) = rx.try_recv() {
+                    handle = next;
+                }
+                let title = crate::windows_util::window_title(handle).unwrap_or_default();
+                if filter.accept(handle, &title) {
+                    log::debug!("foreground window changed: {title}");
+                    on_change(handle);
+                }
+            }
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_dedupes_and_matches_title() {
+        let config = WindowTriggerConfig {
+            title_pattern: Some("^Setup".into()),
+            ..Default::default()
+        };
+        let mut f = Filter::new(&config).unwrap();
+        assert!(f.accept(1, "Setup - Welcome"));
+        assert!(!f.accept(1, "Setup - Welcome"));
+        // 同一窗口切换到下一页
+        assert!(f.accept(1, "Setup - License"));
+        assert!(!f.accept(2, "Notepad"));
+        assert!(!f.accept(2, ""));
+        // 切走再切回：上一次接受的仍是同一页，不重复截图
+        assert!(!f.accept(1, "Setup - License"));
+        assert!(f.accept(3, "Setup - License"));
+    }
+
+    #[test]
+    fn test_filter_ignores_title_changes_when_disabled() {
+        let config = WindowTriggerConfig {
+            title_changes: false,
+            ..Default::default()
+        };
+        let mut f = Filter::new(&config).unwrap();
+        assert!(f.accept(1, "a"));
+        assert!(!f.accept(1, "b"));
+        assert!(f.accept(2, "b"));
+        assert!(Filter::new(&WindowTriggerConfig {
+            title_pattern: Some("(".into()),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_config_parse() {
+        let c: WindowTriggerConfig =
+            toml::from_str("title_pattern = \"安装\"\naction = \"copy\"").unwrap();
+        assert_eq!(c.title_pattern.as_deref(), Some("安装"));
+        assert_eq!(c.action, CaptureAction::Copy);
+        assert!(c.title_changes);
+        assert_eq!(c.delay_ms, 400);
+        assert_eq!(WindowTriggerConfig::default().action, CaptureAction::Save);
+    }
+}
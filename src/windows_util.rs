@@ -1,37 +1,1229 @@
-// Windows-specific utility helpers. Currently only provides a helper to disable
-// DWM transition animations (fade) for instant show/hide UX. This is internal
+// Windows-specific utility helpers: DWM transition disabling (instant show/hide UX),
+// capture exclusion for pinned windows, and compositor flush. This is internal
 // and not part of the public API surface.
 
 #[cfg(target_os = "windows")]
-pub fn disable_window_transitions(window: &winit::window::Window) {
+fn hwnd_of(window: &winit::window::Window) -> Option<windows::Win32::Foundation::HWND> {
     use windows::Win32::Foundation::HWND;
-    use windows::Win32::Graphics::Dwm::{DwmSetWindowAttribute, DWMWA_TRANSITIONS_FORCEDISABLED};
     use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
 
-    if let Ok(h) = window.window_handle() {
-        let raw = h.as_raw();
-        if let RawWindowHandle::Win32(win) = raw {
-            unsafe {
-                let hwnd = HWND(win.hwnd.get() as *mut _);
-                let value: i32 = 1;
-                let hr = DwmSetWindowAttribute(
-                    hwnd,
-                    DWMWA_TRANSITIONS_FORCEDISABLED,
-                    &value as *const _ as *const _,
-                    std::mem::size_of_val(&value) as u32,
-                );
-                #[allow(unused)]
-                {
-                    if hr.is_ok() {
-                        log::debug!("disabled DWM transitions for window: {:?}", hwnd);
-                    } else {
-                        log::debug!(
-                            "failed to disable DWM transitions (hr={hr:?}) for window: {:?}",
-                            hwnd
-                        );
-                    }
+    match window.window_handle().ok()?.as_raw() {
+        RawWindowHandle::Win32(win) => Some(HWND(win.hwnd.get() as *mut _)),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn disable_window_transitions(window: &winit::window::Window) {
+    use windows::Win32::Graphics::Dwm::{DwmSetWindowAttribute, DWMWA_TRANSITIONS_FORCEDISABLED};
+
+    if let Some(hwnd) = hwnd_of(window) {
+        unsafe {
+            let value: i32 = 1;
+            let hr = DwmSetWindowAttribute(
+                hwnd,
+                DWMWA_TRANSITIONS_FORCEDISABLED,
+                &value as *const _ as *const _,
+                std::mem::size_of_val(&value) as u32,
+            );
+            #[allow(unused)]
+            {
+                if hr.is_ok() {
+                    log::debug!("disabled DWM transitions for window: {:?}", hwnd);
+                } else {
+                    log::debug!(
+                        "failed to disable DWM transitions (hr={hr:?}) for window: {:?}",
+                        hwnd
+                    );
                 }
             }
         }
     }
 }
+
+/// Colour (0x00RRGGBB, as written to the softbuffer frame) that a live overlay keys out:
+/// such pixels are fully transparent and clicks on them reach the window below.
+pub const LIVE_COLOR_KEY: u32 = 0x00FF00FF;
+/// Opacity of the remaining pixels of a live overlay.
+pub const LIVE_ALPHA: u8 = 160;
+
+/// Turn a window into a live overlay (WS_EX_LAYERED with `LIVE_COLOR_KEY` + `LIVE_ALPHA`),
+/// or back into a normal opaque window. Returns false when unsupported.
+#[cfg(target_os = "windows")]
+pub fn set_live_overlay(window: &winit::window::Window, live: bool) -> bool {
+    use windows::Win32::Foundation::COLORREF;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetWindowLongPtrW, SetLayeredWindowAttributes, SetWindowLongPtrW, GWL_EXSTYLE, LWA_ALPHA,
+        LWA_COLORKEY, WS_EX_LAYERED,
+    };
+
+    let Some(hwnd) = hwnd_of(window) else {
+        return false;
+    };
+    unsafe {
+        let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+        let layered = WS_EX_LAYERED.0 as isize;
+        if !live {
+            SetWindowLongPtrW(hwnd, GWL_EXSTYLE, ex_style & !layered);
+            return true;
+        }
+        SetWindowLongPtrW(hwnd, GWL_EXSTYLE, ex_style | layered);
+        // COLORREF is 0x00BBGGRR; the key is symmetric in R and B
+        let key = COLORREF(LIVE_COLOR_KEY);
+        match SetLayeredWindowAttributes(hwnd, key, LIVE_ALPHA, LWA_COLORKEY | LWA_ALPHA) {
+            Ok(()) => true,
+            Err(e) => {
+                log::debug!("SetLayeredWindowAttributes failed: {e}");
+                SetWindowLongPtrW(hwnd, GWL_EXSTYLE, ex_style);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn set_live_overlay(_window: &winit::window::Window, _live: bool) -> bool {
+    false
+}
+
+/// Switch a window to per-pixel alpha (WS_EX_LAYERED without layered attributes), or back.
+/// While enabled its content comes only from `update_layered_window`; returns false when unsupported.
+#[cfg(target_os = "windows")]
+pub fn set_per_pixel_alpha(window: &winit::window::Window, enabled: bool) -> bool {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetWindowLongPtrW, SetWindowLongPtrW, GWL_EXSTYLE, WS_EX_LAYERED,
+    };
+
+    let Some(hwnd) = hwnd_of(window) else {
+        return false;
+    };
+    unsafe {
+        let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+        let layered = WS_EX_LAYERED.0 as isize;
+        let style = if enabled {
+            ex_style | layered
+        } else {
+            ex_style & !layered
+        };
+        SetWindowLongPtrW(hwnd, GWL_EXSTYLE, style);
+    }
+    if !enabled {
+        // 恢复为普通窗口后需要重新绘制
+        window.request_redraw();
+    }
+    true
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn set_per_pixel_alpha(_window: &winit::window::Window, _enabled: bool) -> bool {
+    false
+}
+
+/// Present a premultiplied-alpha frame (`0xAARRGGBB` per pixel, width × height, as for
+/// softbuffer) on a `set_per_pixel_alpha` window, keeping its position. Returns false on failure.
+#[cfg(target_os = "windows")]
+pub fn update_layered_window(
+    window: &winit::window::Window,
+    width: u32,
+    height: u32,
+    pixels: &[u32],
+) -> bool {
+    use windows::Win32::Foundation::{COLORREF, POINT, SIZE};
+    use windows::Win32::Graphics::Gdi::{
+        CreateCompatibleDC, CreateDIBSection, DeleteDC, DeleteObject, SelectObject, AC_SRC_ALPHA,
+        AC_SRC_OVER, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, BLENDFUNCTION, DIB_RGB_COLORS,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{UpdateLayeredWindow, ULW_ALPHA};
+
+    let len = (width * height) as usize;
+    let Some(hwnd) = hwnd_of(window).filter(|_| pixels.len() >= len && len > 0) else {
+        return false;
+    };
+    unsafe {
+        let info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width as i32,
+                biHeight: -(height as i32), // 自上而下
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let dc = CreateCompatibleDC(None);
+        if dc.is_invalid() {
+            return false;
+        }
+        let mut bits = std::ptr::null_mut();
+        let Some(bitmap) = CreateDIBSection(Some(dc), &info, DIB_RGB_COLORS, &mut bits, None, 0)
+            .ok()
+            .filter(|_| !bits.is_null())
+        else {
+            let _ = DeleteDC(dc);
+            return false;
+        };
+        std::ptr::copy_nonoverlapping(pixels.as_ptr(), bits as *mut u32, len);
+        let old = SelectObject(dc, bitmap.into());
+        let size = SIZE {
+            cx: width as i32,
+            cy: height as i32,
+        };
+        let origin = POINT { x: 0, y: 0 };
+        let blend = BLENDFUNCTION {
+            BlendOp: AC_SRC_OVER as u8,
+            BlendFlags: 0,
+            SourceConstantAlpha: 255,
+            AlphaFormat: AC_SRC_ALPHA as u8,
+        };
+        let result = UpdateLayeredWindow(
+            hwnd,
+            None,
+            None,
+            Some(&size),
+            Some(dc),
+            Some(&origin),
+            COLORREF(0),
+            Some(&blend),
+            ULW_ALPHA,
+        );
+        SelectObject(dc, old);
+        let _ = DeleteObject(bitmap.into());
+        let _ = DeleteDC(dc);
+        if let Err(e) = &result {
+            log::debug!("UpdateLayeredWindow failed: {e}");
+        }
+        result.is_ok()
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn update_layered_window(
+    _window: &winit::window::Window,
+    _width: u32,
+    _height: u32,
+    _pixels: &[u32],
+) -> bool {
+    false
+}
+
+/// Exclude a window from screen capture (WDA_EXCLUDEFROMCAPTURE, Windows 10 2004+).
+/// Returns false when unsupported so callers can fall back to hiding the window.
+#[cfg(target_os = "windows")]
+pub fn set_exclude_from_capture(window: &winit::window::Window, exclude: bool) -> bool {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SetWindowDisplayAffinity, WDA_EXCLUDEFROMCAPTURE, WDA_NONE,
+    };
+
+    let Some(hwnd) = hwnd_of(window) else {
+        return false;
+    };
+    let affinity = if exclude {
+        WDA_EXCLUDEFROMCAPTURE
+    } else {
+        WDA_NONE
+    };
+    match unsafe { SetWindowDisplayAffinity(hwnd, affinity) } {
+        Ok(()) => true,
+        Err(e) => {
+            log::debug!("SetWindowDisplayAffinity failed: {e}");
+            false
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn set_exclude_from_capture(_window: &winit::window::Window, _exclude: bool) -> bool {
+    false
+}
+
+/// Pop up a muda context menu at a window-local position (physical pixels).
+/// Blocks until the menu closes; the chosen item arrives as a `MenuEvent`.
+/// Returns false when no menu could be shown.
+#[cfg(target_os = "windows")]
+pub fn show_context_menu(
+    window: &winit::window::Window,
+    menu: &muda::Menu,
+    pos: (f64, f64),
+) -> bool {
+    use muda::ContextMenu;
+
+    let Some(hwnd) = hwnd_of(window) else {
+        return false;
+    };
+    let pos = muda::dpi::PhysicalPosition { x: pos.0, y: pos.1 };
+    unsafe {
+        menu.show_context_menu_for_hwnd(hwnd.0 as isize, Some(pos.into()));
+    }
+    true
+}
+
+// TODO: gtk / nsview handle; 暂不实现
+#[cfg(not(target_os = "windows"))]
+pub fn show_context_menu(
+    _window: &winit::window::Window,
+    _menu: &muda::Menu,
+    _pos: (f64, f64),
+) -> bool {
+    false
+}
+
+/// Block until DWM has composed pending changes (e.g. windows just hidden).
+#[cfg(target_os = "windows")]
+pub fn flush_compositor() {
+    let _ = unsafe { windows::Win32::Graphics::Dwm::DwmFlush() };
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn flush_compositor() {}
+
+/// ICC profile bytes of the primary monitor (the screen `capture_fullscreen*` grabs).
+/// Returns None when no profile is associated or the file cannot be read.
+#[cfg(target_os = "windows")]
+pub fn primary_monitor_icc_profile() -> Option<Vec<u8>> {
+    use windows::core::PWSTR;
+    use windows::Win32::Graphics::Gdi::{GetDC, ReleaseDC};
+    use windows::Win32::UI::ColorSystem::GetICMProfileW;
+
+    unsafe {
+        let hdc = GetDC(None);
+        if hdc.is_invalid() {
+            return None;
+        }
+        let mut len: u32 = 0;
+        // 第一次调用只取所需长度（含结尾 0）
+        let _ = GetICMProfileW(hdc, &mut len, None);
+        let mut buf = vec![0u16; len.max(1) as usize];
+        let ok = len > 0 && GetICMProfileW(hdc, &mut len, Some(PWSTR(buf.as_mut_ptr()))).as_bool();
+        ReleaseDC(None, hdc);
+        if !ok {
+            log::debug!("no icc profile for primary monitor");
+            return None;
+        }
+        let end = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+        let path = String::from_utf16_lossy(&buf[..end]);
+        match std::fs::read(&path) {
+            Ok(bytes) => Some(bytes),
+            Err(e) => {
+                log::warn!("read icc profile {path}: {e}");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn primary_monitor_icc_profile() -> Option<Vec<u8>> {
+    None
+}
+
+/// Show a top-most message box. Blocks the calling thread; use from a worker thread.
+#[cfg(target_os = "windows")]
+pub fn show_message(title: &str, text: &str) {
+    use windows::core::HSTRING;
+    use windows::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_ICONINFORMATION, MB_TOPMOST};
+
+    unsafe {
+        MessageBoxW(
+            None,
+            &HSTRING::from(text),
+            &HSTRING::from(title),
+            MB_ICONINFORMATION | MB_TOPMOST,
+        );
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn show_message(title: &str, text: &str) {
+    log::info!("{title}: {text}");
+}
+
+/// Executable name (without extension) of the process owning the foreground window.
+#[cfg(target_os = "windows")]
+pub fn foreground_app_name() -> Option<String> {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_invalid() {
+            return None;
+        }
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        if pid == 0 {
+            return None;
+        }
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut buf = [0u16; 260];
+        let mut len = buf.len() as u32;
+        let ok = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(buf.as_mut_ptr()),
+            &mut len,
+        );
+        let _ = CloseHandle(process);
+        ok.ok()?;
+        let path = String::from_utf16_lossy(&buf[..len as usize]);
+        std::path::Path::new(&path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn foreground_app_name() -> Option<String> {
+    None
+}
+
+/// Screen rectangle (x, y, w, h) of the foreground window, without the invisible resize borders.
+#[cfg(target_os = "windows")]
+pub fn foreground_window_rect() -> Option<(i32, i32, u32, u32)> {
+    use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.is_invalid() {
+        return None;
+    }
+    hwnd_rect(hwnd, false)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn foreground_window_rect() -> Option<(i32, i32, u32, u32)> {
+    None
+}
+
+/// Work areas (x, y, w, h in virtual-desktop pixels) of all monitors, i.e. the monitor
+/// rectangles minus the taskbar and docked app bars.
+#[cfg(target_os = "windows")]
+pub fn monitor_work_areas() -> Vec<(i32, i32, u32, u32)> {
+    use windows::core::BOOL;
+    use windows::Win32::Foundation::{LPARAM, RECT};
+    use windows::Win32::Graphics::Gdi::{
+        EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITORINFO,
+    };
+
+    unsafe extern "system" fn collect(
+        monitor: HMONITOR,
+        _hdc: HDC,
+        _rect: *mut RECT,
+        lparam: LPARAM,
+    ) -> BOOL {
+        let out = &mut *(lparam.0 as *mut Vec<(i32, i32, u32, u32)>);
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if GetMonitorInfoW(monitor, &mut info).as_bool() {
+            let r = info.rcWork;
+            out.push((
+                r.left,
+                r.top,
+                (r.right - r.left) as u32,
+                (r.bottom - r.top) as u32,
+            ));
+        }
+        true.into()
+    }
+
+    let mut areas = Vec::new();
+    let ok = unsafe {
+        EnumDisplayMonitors(
+            None,
+            None,
+            Some(collect),
+            LPARAM(&mut areas as *mut _ as isize),
+        )
+    };
+    if !ok.as_bool() {
+        log::debug!("EnumDisplayMonitors failed");
+    }
+    areas
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn monitor_work_areas() -> Vec<(i32, i32, u32, u32)> {
+    Vec::new()
+}
+
+// DWM extended frame bounds (what the user sees); `with_shadow` uses GetWindowRect,
+// which also covers the drop shadow / invisible resize borders
+#[cfg(target_os = "windows")]
+fn hwnd_rect(
+    hwnd: windows::Win32::Foundation::HWND,
+    with_shadow: bool,
+) -> Option<(i32, i32, u32, u32)> {
+    use windows::Win32::Foundation::RECT;
+    use windows::Win32::Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_EXTENDED_FRAME_BOUNDS};
+    use windows::Win32::UI::WindowsAndMessaging::GetWindowRect;
+
+    unsafe {
+        let mut rect = RECT::default();
+        let extended = !with_shadow
+            && DwmGetWindowAttribute(
+                hwnd,
+                DWMWA_EXTENDED_FRAME_BOUNDS,
+                &mut rect as *mut _ as *mut _,
+                std::mem::size_of::<RECT>() as u32,
+            )
+            .is_ok();
+        if !extended {
+            GetWindowRect(hwnd, &mut rect).ok()?;
+        }
+        let (w, h) = (rect.right - rect.left, rect.bottom - rect.top);
+        (w > 0 && h > 0).then_some((rect.left, rect.top, w as u32, h as u32))
+    }
+}
+
+/// Edge length of `TopWindow::icon` in pixels.
+pub const WINDOW_ICON_SIZE: u32 = 16;
+
+/// A visible top-level window of another process, as offered by the tray window list.
+#[derive(Debug, Clone)]
+pub struct TopWindow {
+    /// HWND value; may become stale once the window closes
+    pub handle: isize,
+    pub title: String,
+    /// WINDOW_ICON_SIZE² RGBA pixels, None when neither the window nor its class has an icon
+    pub icon: Option<Vec<u8>>,
+}
+
+/// Visible, non-minimized top-level windows with a title, in Z order (topmost first).
+/// Skips cloaked windows (other virtual desktops, suspended UWP), tool windows,
+/// the desktop and this process's own windows.
+#[cfg(target_os = "windows")]
+pub fn list_top_windows() -> Vec<TopWindow> {
+    top_window_handles()
+        .into_iter()
+        .filter_map(|hwnd| {
+            let title = window_title(hwnd.0 as isize)?;
+            hwnd_rect(hwnd, false)?;
+            Some(TopWindow {
+                handle: hwnd.0 as isize,
+                title,
+                icon: window_icon(hwnd),
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn list_top_windows() -> Vec<TopWindow> {
+    Vec::new()
+}
+
+/// Screen rectangles (x, y, w, h, without the invisible resize borders) of the windows
+/// `list_top_windows` would offer, in the same Z order; skips the title and icon lookups.
+#[cfg(target_os = "windows")]
+pub fn top_window_rects() -> Vec<(i32, i32, u32, u32)> {
+    top_window_handles()
+        .into_iter()
+        .filter(|&hwnd| window_title(hwnd.0 as isize).is_some())
+        .filter_map(|hwnd| hwnd_rect(hwnd, false))
+        .collect()
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn top_window_rects() -> Vec<(i32, i32, u32, u32)> {
+    Vec::new()
+}
+
+// Candidate windows for `list_top_windows` / `top_window_rects`, before the title check
+#[cfg(target_os = "windows")]
+fn top_window_handles() -> Vec<windows::Win32::Foundation::HWND> {
+    use windows::core::BOOL;
+    use windows::Win32::Foundation::{HWND, LPARAM};
+    use windows::Win32::Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED};
+    use windows::Win32::System::Threading::GetCurrentProcessId;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetShellWindow, GetWindowLongPtrW, GetWindowThreadProcessId, IsIconic,
+        IsWindowVisible, GWL_EXSTYLE, WS_EX_TOOLWINDOW,
+    };
+
+    unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let out = &mut *(lparam.0 as *mut Vec<HWND>);
+        out.push(hwnd);
+        true.into()
+    }
+
+    let mut handles: Vec<HWND> = Vec::new();
+    unsafe {
+        if let Err(e) = EnumWindows(Some(collect), LPARAM(&mut handles as *mut _ as isize)) {
+            log::debug!("EnumWindows failed: {e}");
+        }
+        let own_pid = GetCurrentProcessId();
+        let shell = GetShellWindow();
+        handles.retain(|&hwnd| {
+            if hwnd == shell || !IsWindowVisible(hwnd).as_bool() || IsIconic(hwnd).as_bool() {
+                return false;
+            }
+            let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE) as u32;
+            if ex_style & WS_EX_TOOLWINDOW.0 != 0 {
+                return false;
+            }
+            let mut pid = 0u32;
+            GetWindowThreadProcessId(hwnd, Some(&mut pid));
+            if pid == own_pid {
+                return false;
+            }
+            let mut cloaked = 0u32;
+            let cloak = DwmGetWindowAttribute(
+                hwnd,
+                DWMWA_CLOAKED,
+                &mut cloaked as *mut _ as *mut _,
+                std::mem::size_of::<u32>() as u32,
+            );
+            cloak.is_err() || cloaked == 0
+        });
+    }
+    handles
+}
+
+/// Trimmed title of a window; None when it has none (or the handle is stale).
+#[cfg(target_os = "windows")]
+pub fn window_title(handle: isize) -> Option<String> {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::{GetWindowTextLengthW, GetWindowTextW};
+
+    let hwnd = HWND(handle as *mut _);
+    unsafe {
+        let len = GetWindowTextLengthW(hwnd);
+        if len <= 0 {
+            return None;
+        }
+        let mut buf = vec![0u16; len as usize + 1];
+        let n = GetWindowTextW(hwnd, &mut buf).max(0) as usize;
+        let title = String::from_utf16_lossy(&buf[..n]).trim().to_string();
+        (!title.is_empty()).then_some(title)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn window_title(_handle: isize) -> Option<String> {
+    None
+}
+
+// Small icon of a window (WM_GETICON, then the class icon) rendered to RGBA.
+// Icons without an alpha channel take transparency from their AND mask.
+#[cfg(target_os = "windows")]
+fn window_icon(hwnd: windows::Win32::Foundation::HWND) -> Option<Vec<u8>> {
+    use windows::Win32::Foundation::{LPARAM, WPARAM};
+    use windows::Win32::Graphics::Gdi::{
+        CreateCompatibleDC, CreateDIBSection, DeleteDC, DeleteObject, SelectObject, BITMAPINFO,
+        BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        DrawIconEx, GetClassLongPtrW, SendMessageTimeoutW, DI_MASK, DI_NORMAL, GCLP_HICON,
+        GCLP_HICONSM, HICON, ICON_BIG, ICON_SMALL2, SMTO_ABORTIFHUNG, WM_GETICON,
+    };
+
+    const SIZE: i32 = WINDOW_ICON_SIZE as i32;
+    unsafe {
+        // 无响应的窗口不能阻塞托盘：超时后退回类图标
+        let mut icon = 0usize;
+        for kind in [ICON_SMALL2, ICON_BIG] {
+            let mut result = 0usize;
+            let sent = SendMessageTimeoutW(
+                hwnd,
+                WM_GETICON,
+                WPARAM(kind as usize),
+                LPARAM(0),
+                SMTO_ABORTIFHUNG,
+                50,
+                Some(&mut result),
+            );
+            if sent.0 != 0 && result != 0 {
+                icon = result;
+                break;
+            }
+        }
+        if icon == 0 {
+            icon = GetClassLongPtrW(hwnd, GCLP_HICONSM);
+        }
+        if icon == 0 {
+            icon = GetClassLongPtrW(hwnd, GCLP_HICON);
+        }
+        if icon == 0 {
+            return None;
+        }
+        let icon = HICON(icon as *mut _);
+
+        let info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: SIZE,
+                biHeight: -SIZE, // 自上而下
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let dc = CreateCompatibleDC(None);
+        if dc.is_invalid() {
+            return None;
+        }
+        let render = |flags| -> Option<Vec<u8>> {
+            let mut bits = std::ptr::null_mut();
+            let bitmap = CreateDIBSection(Some(dc), &info, DIB_RGB_COLORS, &mut bits, None, 0)
+                .ok()
+                .filter(|_| !bits.is_null())?;
+            let old = SelectObject(dc, bitmap.into());
+            let drawn = DrawIconEx(dc, 0, 0, icon, SIZE, SIZE, 0, None, flags);
+            let pixels = drawn.is_ok().then(|| {
+                std::slice::from_raw_parts(bits as *const u8, (SIZE * SIZE * 4) as usize).to_vec()
+            });
+            SelectObject(dc, old);
+            let _ = DeleteObject(bitmap.into());
+            pixels
+        };
+        let color = render(DI_NORMAL);
+        let has_alpha = color
+            .as_ref()
+            .is_some_and(|px| px.chunks_exact(4).any(|p| p[3] != 0));
+        let mask = if has_alpha { None } else { render(DI_MASK) };
+        let _ = DeleteDC(dc);
+        let mut bgra = color?;
+        if let Some(mask) = mask {
+            // AND 掩码：黑色为不透明
+            for (p, m) in bgra.chunks_exact_mut(4).zip(mask.chunks_exact(4)) {
+                p[3] = if m[0] == 0 { 255 } else { 0 };
+            }
+        } else if !has_alpha {
+            return None;
+        }
+        for p in bgra.chunks_exact_mut(4) {
+            p.swap(0, 2);
+        }
+        Some(bgra)
+    }
+}
+
+/// Current rectangle of a listed window (see `hwnd_rect`); None once it is closed or minimized.
+#[cfg(target_os = "windows")]
+pub fn window_rect(handle: isize, with_shadow: bool) -> Option<(i32, i32, u32, u32)> {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::{IsIconic, IsWindow};
+
+    let hwnd = HWND(handle as *mut _);
+    unsafe {
+        if !IsWindow(Some(hwnd)).as_bool() || IsIconic(hwnd).as_bool() {
+            return None;
+        }
+    }
+    hwnd_rect(hwnd, with_shadow)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn window_rect(_handle: isize, _with_shadow: bool) -> Option<(i32, i32, u32, u32)> {
+    None
+}
+
+/// The user's double-click interval (Control Panel → Mouse).
+#[cfg(target_os = "windows")]
+pub fn double_click_time() -> std::time::Duration {
+    let ms = unsafe { windows::Win32::UI::Input::KeyboardAndMouse::GetDoubleClickTime() };
+    std::time::Duration::from_millis(ms as u64)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn double_click_time() -> std::time::Duration {
+    std::time::Duration::from_millis(500)
+}
+
+/// Mouse pointer position in physical screen coordinates; None when it cannot be read
+/// (secure desktop / lock screen).
+#[cfg(target_os = "windows")]
+pub fn cursor_position() -> Option<(i32, i32)> {
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
+
+    let mut pt = POINT::default();
+    unsafe { GetCursorPos(&mut pt) }.ok()?;
+    Some((pt.x, pt.y))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn cursor_position() -> Option<(i32, i32)> {
+    None
+}
+
+/// Whether the left or right mouse button is down, or was pressed since the previous call.
+/// Best effort: the "pressed since" bit is shared with other `GetAsyncKeyState` callers.
+#[cfg(target_os = "windows")]
+pub fn mouse_clicked() -> bool {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetAsyncKeyState, VK_LBUTTON, VK_RBUTTON};
+
+    [VK_LBUTTON, VK_RBUTTON]
+        .into_iter()
+        .map(|vk| unsafe { GetAsyncKeyState(vk.0 as i32) } as u16 & 0x8001 != 0)
+        .fold(false, |any, clicked| any | clicked)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn mouse_clicked() -> bool {
+    false
+}
+
+/// Whether a fullscreen game (D3D exclusive), fullscreen app or presentation is in the
+/// foreground, per the shell's notification state. Our own fullscreen overlay does not count.
+#[cfg(target_os = "windows")]
+pub fn fullscreen_app_active() -> bool {
+    use windows::Win32::UI::Shell::{
+        SHQueryUserNotificationState, QUNS_BUSY, QUNS_PRESENTATION_MODE,
+        QUNS_RUNNING_D3D_FULL_SCREEN,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    let mut pid = 0;
+    unsafe { GetWindowThreadProcessId(GetForegroundWindow(), Some(&mut pid)) };
+    if pid == std::process::id() {
+        return false;
+    }
+    unsafe { SHQueryUserNotificationState() }.is_ok_and(|state| {
+        [
+            QUNS_BUSY,
+            QUNS_RUNNING_D3D_FULL_SCREEN,
+            QUNS_PRESENTATION_MODE,
+        ]
+        .contains(&state)
+    })
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn fullscreen_app_active() -> bool {
+    false
+}
+
+// Mandatory integrity level RID (SECURITY_MANDATORY_*_RID) of a process; None when the process
+// or its token cannot be opened / queried (level unknown).
+#[cfg(target_os = "windows")]
+fn process_integrity(pid: Option<u32>) -> Option<u32> {
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::Security::{
+        GetSidSubAuthority, GetSidSubAuthorityCount, GetTokenInformation, TokenIntegrityLevel,
+        TOKEN_MANDATORY_LABEL, TOKEN_QUERY,
+    };
+    use windows::Win32::System::Threading::{
+        GetCurrentProcess, OpenProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    unsafe {
+        let process = match pid {
+            Some(pid) => OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?,
+            None => GetCurrentProcess(),
+        };
+        let mut token = HANDLE::default();
+        let opened = OpenProcessToken(process, TOKEN_QUERY, &mut token);
+        if pid.is_some() {
+            let _ = CloseHandle(process);
+        }
+        opened.ok()?;
+        // TOKEN_MANDATORY_LABEL followed by the SID; u64 keeps the pointer field aligned
+        let mut buf = [0u64; 16];
+        let mut len = 0;
+        let queried = GetTokenInformation(
+            token,
+            TokenIntegrityLevel,
+            Some(buf.as_mut_ptr().cast()),
+            std::mem::size_of_val(&buf) as u32,
+            &mut len,
+        );
+        let _ = CloseHandle(token);
+        queried.ok()?;
+        let sid = (*buf.as_ptr().cast::<TOKEN_MANDATORY_LABEL>()).Label.Sid;
+        let count = *GetSidSubAuthorityCount(sid);
+        Some(*GetSidSubAuthority(sid, count.checked_sub(1)? as u32))
+    }
+}
+
+/// Whether this process runs elevated (high integrity or above).
+#[cfg(target_os = "windows")]
+pub fn is_elevated() -> bool {
+    // SECURITY_MANDATORY_HIGH_RID
+    process_integrity(None).is_some_and(|rid| rid >= 0x3000)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn is_elevated() -> bool {
+    false
+}
+
+/// Whether the foreground window belongs to a process with a higher integrity level than ours
+/// (an elevated app or a system window). Windows (UIPI) then blocks our low-level hooks,
+/// so hotkeys and the overlay stop reacting while it has focus. False when either level
+/// cannot be read: an unknown level is not reported as elevated.
+#[cfg(target_os = "windows")]
+pub fn foreground_elevated() -> bool {
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    let mut pid = 0;
+    unsafe { GetWindowThreadProcessId(GetForegroundWindow(), Some(&mut pid)) };
+    if pid == 0 || pid == std::process::id() {
+        return false;
+    }
+    match (process_integrity(Some(pid)), process_integrity(None)) {
+        (Some(theirs), Some(ours)) => theirs > ours,
+        _ => false,
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn foreground_elevated() -> bool {
+    false
+}
+
+/// Whether the secure desktop (UAC prompt, lock screen, Ctrl+Alt+Del) is showing; it cannot be
+/// captured and receives no input from us.
+#[cfg(target_os = "windows")]
+pub fn secure_desktop_active() -> bool {
+    use windows::Win32::System::StationsAndDesktops::{
+        CloseDesktop, OpenInputDesktop, DESKTOP_CONTROL_FLAGS, DESKTOP_READOBJECTS,
+    };
+
+    match unsafe { OpenInputDesktop(DESKTOP_CONTROL_FLAGS(0), false, DESKTOP_READOBJECTS) } {
+        Ok(desktop) => {
+            let _ = unsafe { CloseDesktop(desktop) };
+            false
+        }
+        Err(_) => true,
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn secure_desktop_active() -> bool {
+    false
+}
+
+/// Start this executable again elevated (UAC prompt) with the given command line.
+/// Fails when the user declines the prompt.
+#[cfg(target_os = "windows")]
+pub fn relaunch_elevated(args: &str) -> std::io::Result<()> {
+    use windows::core::{w, HSTRING, PCWSTR};
+    use windows::Win32::UI::Shell::ShellExecuteW;
+    use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+    let exe = HSTRING::from(std::env::current_exe()?.as_os_str());
+    let params = HSTRING::from(args);
+    // ShellExecuteW reports success with a value greater than 32
+    let result = unsafe {
+        ShellExecuteW(
+            None,
+            w!("runas"),
+            &exe,
+            &params,
+            PCWSTR::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+    if result.0 as usize > 32 {
+        Ok(())
+    } else {
+        Err(std::io::Error::other("elevation was declined or failed"))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn relaunch_elevated(_args: &str) -> std::io::Result<()> {
+    Err(std::io::Error::other(
+        "elevation is only supported on Windows",
+    ))
+}
+
+/// Integer handle (HWND) of one of our winit windows, as used by `window_title` / `bring_to_front`.
+#[cfg(target_os = "windows")]
+pub fn window_handle(window: &winit::window::Window) -> Option<isize> {
+    hwnd_of(window).map(|hwnd| hwnd.0 as isize)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn window_handle(_window: &winit::window::Window) -> Option<isize> {
+    None
+}
+
+/// Raise a window above the others so it is not covered when captured.
+/// Returns false when Windows refuses the foreground change.
+#[cfg(target_os = "windows")]
+pub fn bring_to_front(handle: isize) -> bool {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::SetForegroundWindow;
+
+    unsafe { SetForegroundWindow(HWND(handle as *mut _)).as_bool() }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn bring_to_front(_handle: isize) -> bool {
+    false
+}
+
+#[cfg(target_os = "windows")]
+const SNIPPING_KEY: windows::core::PCWSTR = windows::core::w!("Control Panel\\Keyboard");
+#[cfg(target_os = "windows")]
+const SNIPPING_VALUE: windows::core::PCWSTR = windows::core::w!("PrintScreenKeyForSnippingEnabled");
+
+/// Whether Windows routes PrintScreen to the Snipping Tool
+/// (Settings → Accessibility → Keyboard; HKCU\Control Panel\Keyboard).
+#[cfg(target_os = "windows")]
+pub fn print_screen_opens_snipping() -> bool {
+    use windows::Win32::Foundation::ERROR_SUCCESS;
+    use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+
+    let mut value: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let err = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            SNIPPING_KEY,
+            SNIPPING_VALUE,
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut value as *mut u32 as *mut _),
+            Some(&mut size),
+        )
+    };
+    err == ERROR_SUCCESS && value != 0
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn print_screen_opens_snipping() -> bool {
+    false
+}
+
+/// Turn the Snipping Tool PrintScreen setting on / off for the current user.
+#[cfg(target_os = "windows")]
+pub fn set_print_screen_opens_snipping(enabled: bool) -> std::io::Result<()> {
+    use windows::Win32::Foundation::ERROR_SUCCESS;
+    use windows::Win32::System::Registry::{RegSetKeyValueW, HKEY_CURRENT_USER, REG_DWORD};
+
+    let value = enabled as u32;
+    let err = unsafe {
+        RegSetKeyValueW(
+            HKEY_CURRENT_USER,
+            SNIPPING_KEY,
+            SNIPPING_VALUE,
+            REG_DWORD.0,
+            Some(&value as *const u32 as *const _),
+            std::mem::size_of::<u32>() as u32,
+        )
+    };
+    if err != ERROR_SUCCESS {
+        return Err(std::io::Error::from_raw_os_error(err.0 as i32));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn set_print_screen_opens_snipping(_enabled: bool) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Yes/No question box; true when the user picked Yes. Blocks like `show_message`.
+#[cfg(target_os = "windows")]
+pub fn confirm(title: &str, text: &str) -> bool {
+    use windows::core::HSTRING;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        MessageBoxW, IDYES, MB_ICONQUESTION, MB_TOPMOST, MB_YESNO,
+    };
+
+    let answer = unsafe {
+        MessageBoxW(
+            None,
+            &HSTRING::from(text),
+            &HSTRING::from(title),
+            MB_YESNO | MB_ICONQUESTION | MB_TOPMOST,
+        )
+    };
+    answer == IDYES
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn confirm(_title: &str, _text: &str) -> bool {
+    false
+}
+
+/// Open a file or folder with its associated program.
+pub fn open_path(path: &std::path::Path) -> std::io::Result<()> {
+    use std::process::Command;
+
+    #[cfg(target_os = "windows")]
+    let program = "explorer";
+    #[cfg(target_os = "macos")]
+    let program = "open";
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let program = "xdg-open";
+    Command::new(program).arg(path).spawn().map(|_| ())
+}
+
+/// Open a file with the default editor for its type (the shell "edit" verb),
+/// falling back to the default "open" handler when no editor is registered.
+#[cfg(target_os = "windows")]
+pub fn edit_path(path: &std::path::Path) -> std::io::Result<()> {
+    use windows::core::{w, HSTRING, PCWSTR};
+    use windows::Win32::UI::Shell::ShellExecuteW;
+    use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+    let file = HSTRING::from(path.as_os_str());
+    for verb in [w!("edit"), w!("open")] {
+        // ShellExecuteW reports success with a value greater than 32
+        let result = unsafe {
+            ShellExecuteW(
+                None,
+                verb,
+                &file,
+                PCWSTR::null(),
+                PCWSTR::null(),
+                SW_SHOWNORMAL,
+            )
+        };
+        if result.0 as usize > 32 {
+            return Ok(());
+        }
+    }
+    Err(std::io::Error::other(
+        "no application registered for this file",
+    ))
+}
+
+/// Open a file with the default editor for its type (the platform open handler).
+#[cfg(not(target_os = "windows"))]
+pub fn edit_path(path: &std::path::Path) -> std::io::Result<()> {
+    open_path(path)
+}
+
+/// Open a URL with the default browser (no shell parsing of the URL).
+pub fn open_url(url: &str) -> std::io::Result<()> {
+    use std::process::Command;
+
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        let mut c = Command::new("rundll32");
+        c.arg("url.dll,FileProtocolHandler").arg(url);
+        c
+    };
+    #[cfg(target_os = "macos")]
+    let mut cmd = {
+        let mut c = Command::new("open");
+        c.arg(url);
+        c
+    };
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut cmd = {
+        let mut c = Command::new("xdg-open");
+        c.arg(url);
+        c
+    };
+    cmd.spawn().map(|_| ())
+}
+
+/// System colours of the active high-contrast scheme (0xFFRRGGBB, as drawn into the
+/// softbuffer frame).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemColors {
+    pub window: u32,
+    pub text: u32,
+    pub highlight: u32,
+    pub highlight_text: u32,
+    pub button: u32,
+    pub button_text: u32,
+}
+
+/// The high-contrast palette, or None when high contrast is off.
+#[cfg(target_os = "windows")]
+pub fn high_contrast_colors() -> Option<SystemColors> {
+    use windows::Win32::Graphics::Gdi::{
+        GetSysColor, COLOR_BTNFACE, COLOR_BTNTEXT, COLOR_HIGHLIGHT, COLOR_HIGHLIGHTTEXT,
+        COLOR_WINDOW, COLOR_WINDOWTEXT, SYS_COLOR_INDEX,
+    };
+    use windows::Win32::UI::Accessibility::{HCF_HIGHCONTRASTON, HIGHCONTRASTW};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SystemParametersInfoW, SPI_GETHIGHCONTRAST, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+    };
+
+    let mut hc = HIGHCONTRASTW {
+        cbSize: std::mem::size_of::<HIGHCONTRASTW>() as u32,
+        ..Default::default()
+    };
+    unsafe {
+        SystemParametersInfoW(
+            SPI_GETHIGHCONTRAST,
+            hc.cbSize,
+            Some(&mut hc as *mut _ as *mut _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    }
+    .ok()?;
+    if !hc.dwFlags.contains(HCF_HIGHCONTRASTON) {
+        return None;
+    }
+    // COLORREF is 0x00BBGGRR
+    let color = |index: SYS_COLOR_INDEX| {
+        let [r, g, b, _] = unsafe { GetSysColor(index) }.to_le_bytes();
+        u32::from_le_bytes([b, g, r, 0xFF])
+    };
+    Some(SystemColors {
+        window: color(COLOR_WINDOW),
+        text: color(COLOR_WINDOWTEXT),
+        highlight: color(COLOR_HIGHLIGHT),
+        highlight_text: color(COLOR_HIGHLIGHTTEXT),
+        button: color(COLOR_BTNFACE),
+        button_text: color(COLOR_BTNTEXT),
+    })
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn high_contrast_colors() -> Option<SystemColors> {
+    None
+}
+
+/// Whether the user turned off "Show animations in Windows" (reduced motion).
+#[cfg(target_os = "windows")]
+pub fn reduced_motion() -> bool {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SystemParametersInfoW, SPI_GETCLIENTAREAANIMATION, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+    };
+
+    let mut enabled = windows::core::BOOL(1);
+    let queried = unsafe {
+        SystemParametersInfoW(
+            SPI_GETCLIENTAREAANIMATION,
+            0,
+            Some(&mut enabled as *mut _ as *mut _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    };
+    queried.is_ok() && !enabled.as_bool()
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn reduced_motion() -> bool {
+    false
+}
+
+/// Play an in-memory WAV, blocking until it finishes (call it off the event loop thread).
+#[cfg(target_os = "windows")]
+pub fn play_wav(data: &[u8]) -> bool {
+    use windows::core::PCWSTR;
+    use windows::Win32::Media::Audio::{PlaySoundW, SND_MEMORY, SND_NODEFAULT};
+
+    unsafe {
+        PlaySoundW(
+            PCWSTR(data.as_ptr() as *const u16),
+            None,
+            SND_MEMORY | SND_NODEFAULT,
+        )
+    }
+    .as_bool()
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn play_wav(_data: &[u8]) -> bool {
+    false
+}
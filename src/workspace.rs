@@ -0,0 +1,114 @@
+// workspace: 贴图工作区——把当前全部贴图（图像 / 位置 / 分组）保存到 <数据目录>/workspaces/<名称>/，
+// 之后从托盘整体重新打开
+//
+// 目录格式与崩溃恢复相同（pin_<序号>.png + pins.json，见 crash::save_pins），名称为保存时的本地时间。
+use anyhow::{anyhow, bail, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::crash::{self, PinSnapshot, RecoveredPin};
+use crate::naming::LocalTime;
+
+/// 工作区根目录
+pub fn root() -> Option<PathBuf> {
+    crate::paths::data_dir().map(|d| d.join("workspaces"))
+}
+
+/// 以本地时间命名（YYYY-MM-DD HH-MM-SS），同名已存在时追加 (2)、(3) …
+pub fn new_name(root: &Path, time: &LocalTime) -> String {
+    let base = format!(
+        "{:04}-{:02}-{:02} {:02}-{:02}-{:02}",
+        time.year, time.month, time.day, time.hour, time.minute, time.second
+    );
+    let mut name = base.clone();
+    let mut n = 2;
+    while root.join(&name).exists() {
+        name = format!("{base} ({n})");
+        n += 1;
+    }
+    name
+}
+
+/// 已保存的工作区名称，最新在前
+pub fn list(root: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().to_str().map(str::to_string))
+        .collect();
+    names.sort_unstable_by(|a, b| b.cmp(a));
+    names
+}
+
+/// 保存贴图为工作区，返回目录
+pub fn save(root: &Path, name: &str, pins: &[PinSnapshot]) -> Result<PathBuf> {
+    let dir = root.join(name);
+    crash::save_pins(&dir, pins)?;
+    Ok(dir)
+}
+
+/// 以当前时间命名，保存当前全部贴图（崩溃恢复登记中的）为工作区，返回目录
+pub fn save_current() -> Result<PathBuf> {
+    let root = root().ok_or_else(|| anyhow!("no data directory"))?;
+    let pins = crash::registered_pins();
+    if pins.is_empty() {
+        bail!("no pins to save");
+    }
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let name = new_name(&root, &crate::naming::local_now(secs));
+    save(&root, &name, &pins)
+}
+
+/// 读取工作区中的贴图（单个文件损坏时跳过）
+pub fn load(root: &Path, name: &str) -> Result<Vec<RecoveredPin>> {
+    crash::load_pins(&root.join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_save_list_and_load() {
+        let root = TempDir::new("workspace_test");
+        assert!(list(&root).is_empty());
+        let time = LocalTime {
+            year: 2026,
+            month: 3,
+            day: 5,
+            hour: 9,
+            minute: 7,
+            second: 1,
+        };
+        let pin = PinSnapshot {
+            id: 1,
+            pos: (10, 20),
+            width: 1,
+            height: 1,
+            pixels: Arc::from(vec![u32::from_le_bytes([0, 0, 255, 255])]),
+            group: Some("任务 A".into()),
+            tag: None,
+        };
+        let first = new_name(&root, &time);
+        assert_eq!(first, "2026-03-05 09-07-01");
+        save(&root, &first, std::slice::from_ref(&pin)).unwrap();
+        let second = new_name(&root, &time);
+        assert_eq!(second, "2026-03-05 09-07-01 (2)");
+        save(&root, &second, &[pin.clone(), pin]).unwrap();
+        assert_eq!(list(&root), [second.clone(), first.clone()]);
+        let loaded = load(&root, &second).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].pos, (10, 20));
+        assert_eq!(loaded[0].group.as_deref(), Some("任务 A"));
+        fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,175 @@
+// 无显示器集成测试：MockBackend 提供确定性画面，覆盖选区换算 / 裁剪、工具栏命中与 PNG 往返
+use snip_rust::capture::{
+    capture_raw_into_from, encode_png, CaptureBackend, MockBackend, PixelFormat, Rect,
+};
+use snip_rust::overlay::regions::{
+    compose_regions, crop_region, region_contains, region_to_screen, screen_to_region, Part,
+    RegionLayout,
+};
+use snip_rust::overlay::theme::UiScale;
+use snip_rust::overlay::toolbar::{compute_toolbar_rect, hit_test_toolbar_button, TB_MARGIN};
+
+fn mock_rgba(mock: &MockBackend) -> Vec<u8> {
+    let mut buf = Vec::new();
+    let (x, y, w, h) = capture_raw_into_from(mock, &mut buf).unwrap();
+    assert_eq!(
+        (x, y, w, h),
+        (mock.origin.0, mock.origin.1, mock.width, mock.height)
+    );
+    buf
+}
+
+#[test]
+fn test_mock_backend_is_deterministic() {
+    let mock = MockBackend::new(64, 32);
+    let a = mock.capture().unwrap();
+    let b = mock.capture().unwrap();
+    assert_eq!(a.data, b.data);
+    assert_eq!(a.timestamp, b.timestamp);
+    let rgba = mock_rgba(&mock);
+    assert_eq!(&rgba[..4], &[0, 0, 0, 255]);
+    let last = rgba.len() - 4;
+    assert_eq!(&rgba[last..], &mock.pixel(63, 31));
+    assert_eq!(mock.pixel(63, 31)[..2], [255, 255]);
+}
+
+#[test]
+fn test_bgra_backend_normalizes_to_rgba() {
+    let rgba = MockBackend::new(17, 9);
+    let bgra = MockBackend {
+        format: PixelFormat::Bgra8,
+        ..rgba
+    };
+    assert_ne!(bgra.capture().unwrap().data, rgba.capture().unwrap().data);
+    assert_eq!(mock_rgba(&bgra), mock_rgba(&rgba));
+    assert_eq!(bgra.capture().unwrap().into_rgba(), mock_rgba(&rgba));
+}
+
+#[test]
+fn test_crop_matches_source_and_clips_at_edges() {
+    let mock = MockBackend::new(40, 30);
+    let rgba = mock_rgba(&mock);
+    let (w, h, crop) = crop_region(&rgba, 40, 30, (5, 7, 10, 4)).unwrap();
+    assert_eq!((w, h), (10, 4));
+    for y in 0..h {
+        for x in 0..w {
+            let i = ((y * w + x) * 4) as usize;
+            assert_eq!(&crop[i..i + 4], &mock.pixel(5 + x, 7 + y));
+        }
+    }
+    // 超出右下边：只保留截图内的部分
+    let (w, h, crop) = crop_region(&rgba, 40, 30, (35, 28, 20, 20)).unwrap();
+    assert_eq!((w, h), (5, 2));
+    assert_eq!(&crop[crop.len() - 4..], &mock.pixel(39, 29));
+    assert!(crop_region(&rgba, 40, 30, (40, 0, 5, 5)).is_none());
+    assert!(crop_region(&rgba, 40, 30, (0, 0, 0, 5)).is_none());
+}
+
+#[test]
+fn test_selection_screen_coordinates() {
+    // 副屏在主屏左侧：原点为负
+    let origin = (-1920, 0);
+    let rect = region_to_screen(origin, (100, 50, 300, 200));
+    assert_eq!(
+        rect,
+        Rect {
+            x: -1820,
+            y: 50,
+            width: 300,
+            height: 200
+        }
+    );
+    assert_eq!(
+        screen_to_region(origin, rect, (1920, 1080)),
+        Some((100, 50, 300, 200))
+    );
+    // 部分在屏幕外：裁到截图范围
+    let partial = Rect {
+        x: -2000,
+        y: 1000,
+        width: 200,
+        height: 200,
+    };
+    assert_eq!(
+        screen_to_region(origin, partial, (1920, 1080)),
+        Some((0, 1000, 120, 80))
+    );
+    let outside = Rect {
+        x: 0,
+        y: 0,
+        width: 10,
+        height: 10,
+    };
+    assert_eq!(screen_to_region(origin, outside, (1920, 1080)), None);
+    assert!(region_contains((100, 50, 300, 200), 100, 50));
+    assert!(!region_contains((100, 50, 300, 200), 400, 50));
+}
+
+#[test]
+fn test_toolbar_hit_test_covers_every_slot() {
+    let (sw, sh) = (1280, 720);
+    let slots = 6;
+    let (bx, by, bw, bh) = compute_toolbar_rect(400, 200, 300, 150, sw, sh, slots).unwrap();
+    // 下方空间足够：紧贴选区下方，水平居中于选区
+    assert_eq!(by, 200 + 150 + TB_MARGIN);
+    assert!((bx + bw / 2 - 550).abs() <= 1);
+    let cy = by + bh / 2;
+    let mut hits = Vec::new();
+    for px in bx - 2..bx + bw + 2 {
+        if let Some(i) =
+            hit_test_toolbar_button(px, cy, (bx, by, bw, bh), slots, UiScale::default())
+        {
+            if hits.last() != Some(&i) {
+                hits.push(i);
+            }
+        }
+    }
+    assert_eq!(hits, (0..slots).collect::<Vec<_>>());
+    assert_eq!(
+        hit_test_toolbar_button(bx, cy, (bx, by, bw, bh), slots, UiScale::default()),
+        None
+    );
+    assert_eq!(
+        hit_test_toolbar_button(bx + 10, by - 1, (bx, by, bw, bh), slots, UiScale::default()),
+        None
+    );
+    // 选区贴近屏幕底部：工具栏移到上方，且不出屏
+    let (bx, by, bw, bh) = compute_toolbar_rect(1200, 600, 80, 120, sw, sh, slots).unwrap();
+    assert!(by + bh <= 600 - TB_MARGIN);
+    assert!(bx >= 0 && bx + bw <= sw as i32);
+}
+
+#[test]
+fn test_png_round_trip() {
+    let mock = MockBackend::new(48, 36);
+    let rgba = mock_rgba(&mock);
+    let (w, h, crop) = crop_region(&rgba, 48, 36, (8, 4, 24, 20)).unwrap();
+    let png = encode_png(&crop, w, h).unwrap();
+    let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+    assert_eq!(decoded.dimensions(), (w, h));
+    assert_eq!(decoded.into_raw(), crop);
+}
+
+#[test]
+fn test_compose_mock_selections() {
+    let mock = MockBackend::new(32, 32);
+    let rgba = mock_rgba(&mock);
+    let a = crop_region(&rgba, 32, 32, (0, 0, 4, 4)).unwrap();
+    let b = crop_region(&rgba, 32, 32, (20, 10, 6, 2)).unwrap();
+    let parts = [
+        Part {
+            region: (20, 10, b.0, b.1),
+            rgba: &b.2,
+        },
+        Part {
+            region: (0, 0, a.0, a.1),
+            rgba: &a.2,
+        },
+    ];
+    let (w, h, out) = compose_regions(RegionLayout::Horizontal, &parts).unwrap();
+    assert_eq!((w, h), (10, 4));
+    // 按 x 排序：左边是 (0,0) 选区，右边紧接 (20,10) 选区
+    assert_eq!(&out[..4], &mock.pixel(0, 0));
+    let right = (4 * 4) as usize;
+    assert_eq!(&out[right..right + 4], &mock.pixel(20, 10));
+}